@@ -376,8 +376,6 @@ settings_extra_paths_title = Extra Paths
 settings_paths_mymod = MyMod's Folder
 settings_paths_mymod_ph = This is the folder where you want to store all "MyMod" related files.
 
-settings_paths_zip = 7Zip Exe's Path
-settings_paths_zip_ph = This is the full path to 7Zip's executable.
 
 settings_game_label = TW: {"{"}{"}"} Folder
 settings_game_line_ph = This is the folder where you have {"{"}{"}"} installed, where the .exe is.
@@ -515,6 +513,7 @@ settings_font_title = Font Settings
 
 title_success = Success!
 title_error = Error!
+table_migrations_need_review = Some tables were imported from older versions, and their data was renamed, removed or converted. Check them before saving:
 
 rename_instructions = It's easy, but you'll not understand it without an example, so here it's one:
      - Your files/folders says 'you' and 'I'.
@@ -557,6 +556,7 @@ context_menu_copy_as_lua_table = &Copy as &LUA Table
 context_menu_paste = &Paste
 context_menu_search = &Search
 context_menu_sidebar = Si&debar
+context_menu_find_usages = &Find Usages
 context_menu_import_tsv = &Import TSV
 context_menu_export_tsv = &Export TSV
 context_menu_invert_selection = Inver&t Selection
@@ -565,6 +565,10 @@ context_menu_resize_columns = Resize Columns
 context_menu_undo = &Undo
 context_menu_redo = &Redo
 
+find_usages_title = <p>Usages of the key <b>{"{"}{"}"}</b> of the table <i>{"{"}{"}"}</i>:</p>
+find_usages_none = <p>No usages found.</p>
+find_usages_dependency = {" "}(dependency)
+
 header_column = <b><i>Column Name</i></b>
 header_hidden = <b><i>Hidden</i></b>
 header_frozen = <b><i>Frozen</i></b>
@@ -633,6 +637,11 @@ label_no_reference_table_nor_column_found_pak = No reference Table/Column found:
 label_no_reference_table_nor_column_found_no_pak = No reference Table/Column/PAK found:
 label_invalid_escape = Invalid escape:
 label_duplicated_row = Duplicated row:
+label_field_max_length_exceeded = Max length exceeded:
+label_invalid_enum_value = Invalid enum value:
+label_file_not_found = File not found:
+label_invalid_bitwise_value = Invalid bitwise value:
+label_lua = Lua script issues:
 label_invalid_dependency_packfile = Invalid dependency PackFile:
 
 diagnostics_button_show_more_filters = Show more filters
@@ -646,3 +655,5 @@ dependency_packfile_list_label = <p><b style="color:red;">WARNING: Adding a Pack
 context_menu_open_packfile_settings = Open PackFile Settings
 pfs_diagnostics_files_to_ignore_label = <p><b><i>PackedFiles to Ignore on Diagnostics Check</i></b></p>
 pfs_diagnostics_files_to_ignore_description_label = <p>------------</p><p>The PackedFiles on this list will be ignored when doing a diagnostics check. They'll still be used as source data for other checks (like reference checks) but they will not be analyzed.</p><p><b>One path per line.</b></p>
+pfs_diagnostics_check_undefined_lua_functions_label = <p><b><i>Report Unknown Functions on Lua Scripts</i></b></p>
+pfs_diagnostics_check_undefined_lua_functions_description_label = <p>If enabled, calls to functions not found in a known class of the game's Lua types file will be reported by the diagnostics check. These files are incomplete, so expect false positives.</p>
@@ -134,6 +134,90 @@ pub fn initialize_app<'a, 'b>() -> App<'a, 'b> {
                 .long("list")
                 .help("Lists the contents of the PackFile."))
 
+            // `Diff` option. Requires you to provide the path of the PackFile to compare against.
+            .arg(Arg::with_name("diff")
+                .long("diff")
+                .value_name("OTHER PACKFILE PATH")
+                .help("Compares the PackFile with another one, listing the PackedFiles, table rows and lines added, removed or modified in the other one.")
+                .takes_value(true))
+
+            // `JSON` flag. Changes the output of `Diff` to JSON.
+            .arg(Arg::with_name("json")
+                .long("json")
+                .requires("diff")
+                .help("Prints the output of the diff as JSON."))
+
+            // `Merge` option. Requires you to provide the path of the PackFile to merge into this one.
+            .arg(Arg::with_name("merge")
+                .long("merge")
+                .value_name("OTHER PACKFILE PATH")
+                .help("Merges the DB and Loc Tables of another PackFile into the ones with the same path in the PackFile, using the vanilla tables as base, then saves the PackFile. Tables only in the other PackFile are added. Conflicts are reported, and the values of the PackFile are kept for them.")
+                .takes_value(true))
+
+            // `Update Tables` option. Migrates all the outdated DB Tables of the PackFile to the versions used by the game.
+            .arg(Arg::with_name("update-tables")
+                .long("update-tables")
+                .help("Updates all the outdated DB Tables of the PackFile to the versions used by the game, then saves it. Renamed, added, removed and converted fields are reported."))
+
+            // `Port Tables` option. Ports all the DB and Loc Tables of the PackFile to the definitions of another game.
+            .arg(Arg::with_name("port-tables")
+                .long("port-tables")
+                .value_names(&["TARGET GAME", "DESTINATION PACKFILE"])
+                .help("Ports all the DB and Loc Tables of the PackFile from the game selected to the provided game, matching their fields by name, then saves the PackFile for that game in the destination path. Added and removed fields, and references not found in the files of the provided game, are reported. If any table cannot be ported, the PackFile is not saved.")
+                .takes_value(true)
+                .number_of_values(2))
+
+            // `Remove Failed` option. To save the ported PackFile without the tables that cannot be ported.
+            .arg(Arg::with_name("remove-failed")
+                .long("remove-failed")
+                .help("When porting tables, removes the tables that cannot be ported from the destination PackFile instead of not saving it.")
+                .requires("port-tables")
+                .takes_value(false))
+
+            // `Add Loc Stubs` option. Adds placeholder Loc entries for the localised fields of DB rows without them.
+            .arg(Arg::with_name("add-loc-stubs")
+                .long("add-loc-stubs")
+                .value_name("LOC PATH IN PACKFILE")
+                .help("Adds placeholder Loc entries for the localised fields of the DB rows without one in the PackFile or the game files to the provided Loc PackedFile, creating it if needed, then saves the PackFile.")
+                .takes_value(true))
+
+            // `Dry Run` option. To only report the Loc entries that would be added.
+            .arg(Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("When adding Loc stubs, only prints the missing Loc entries, without adding them.")
+                .requires("add-loc-stubs")
+                .takes_value(false))
+
+            // `Export JSON` option. To export a decoded PackedFile to JSON/RON.
+            .arg(Arg::with_name("export-json")
+                .long("export-json")
+                .value_names(&["PATH IN PACKFILE", "DESTINATION FILE"])
+                .help("Exports the PackedFile in the provided path of the PackFile to a JSON file, or a RON file if the destination ends in .ron, keeping the type of all its values. Supports DB, Loc, AnimTable, AnimFragment, MatchedCombat, AnimPack and CA_VP8 PackedFiles.")
+                .takes_value(true)
+                .number_of_values(2))
+
+            // `Import JSON` option. To import a decoded PackedFile from JSON/RON.
+            .arg(Arg::with_name("import-json")
+                .long("import-json")
+                .value_names(&["SOURCE FILE", "PATH IN PACKFILE"])
+                .help("Imports a JSON/RON file generated by --export-json into the provided path of the PackFile, overwriting the PackedFile there, then saves the PackFile.")
+                .takes_value(true)
+                .number_of_values(2))
+
+            // `Unpack` option. To unpack the PackFile into a project folder.
+            .arg(Arg::with_name("unpack")
+                .long("unpack")
+                .value_name("PROJECT FOLDER")
+                .help("Unpacks the PackFile into the provided folder, with its tables as TSV/JSON files and its header, notes, settings and dependencies in a manifest.")
+                .takes_value(true))
+
+            // `Pack` option. To rebuild the PackFile from a project folder.
+            .arg(Arg::with_name("pack")
+                .long("pack")
+                .value_name("PROJECT FOLDER")
+                .help("Rebuilds the PackFile from the provided project folder, and saves it in the path of the PackFile.")
+                .takes_value(true))
+
             // `New Packfile` option. The destination is the path of the PackFile you provided before.
             .arg(Arg::with_name("new-packfile")
                 .short("n")
@@ -149,7 +233,7 @@ pub fn initialize_app<'a, 'b>() -> App<'a, 'b> {
                 .short("i")
                 .long("import")
                 .value_name("TSV FILE - DESTINATION FILE")
-                .help("Import a compatible TSV file as a DB/LOC table.")
+                .help("Import a compatible TSV file as a DB/LOC table. TSV files from old versions of the table are updated to the latest one, reporting the changes.")
                 .takes_value(true)
                 .min_values(1)
                 .max_values(2))
@@ -164,12 +248,271 @@ pub fn initialize_app<'a, 'b>() -> App<'a, 'b> {
                 .min_values(1)
                 .max_values(2)))
 
+        // `Image` Subcommand. Every command that allows you to check or convert Images goes here.
+        .subcommand(SubCommand::with_name("image")
+            .about("Allows you to check and convert the Images of a PackFile.")
+
+            // `Info` option. To list the images with their format and size, checking if they can be decoded.
+            .arg(Arg::with_name("info")
+                .short("i")
+                .long("info")
+                .value_name("FILE/FOLDER PATHS")
+                .help("Lists the format, size and mipmaps of the Images in the provided paths, checking they can be decoded. If no path is provided, all the Images of the PackFile are checked.")
+                .takes_value(true)
+                .min_values(0))
+
+            // `Convert` option. To convert images to PNG.
+            .arg(Arg::with_name("convert")
+                .short("c")
+                .long("convert")
+                .value_name("DESTINATION FOLDER - FILE/FOLDER PATHS")
+                .help("Converts the Images in the provided paths to PNG. If no path is provided, all the Images of the PackFile are converted.")
+                .takes_value(true)
+                .min_values(1))
+
+            // `Thumbnail` option. To generate PNG thumbnails of images.
+            .arg(Arg::with_name("thumbnail")
+                .short("t")
+                .long("thumbnail")
+                .value_name("SIZE - DESTINATION FOLDER - FILE/FOLDER PATHS")
+                .help("Generates PNG thumbnails of the provided size of the Images in the provided paths. If no path is provided, thumbnails for all the Images of the PackFile are generated.")
+                .takes_value(true)
+                .min_values(2)))
+
+        // `ESF` Subcommand. Every command that allows you to export or import ESF files goes here.
+        .subcommand(SubCommand::with_name("esf")
+            .about("Allows you to export the ESF files of a PackFile to JSON/XML, and import them back.")
+
+            // `Export` option. To export ESF files to JSON/XML.
+            .arg(Arg::with_name("export")
+                .short("e")
+                .long("export")
+                .value_name("DESTINATION FOLDER - FILE/FOLDER PATHS")
+                .help("Exports the ESF files in the provided paths to the destination folder. If no path is provided, all the ESF files of the PackFile are exported.")
+                .takes_value(true)
+                .min_values(1))
+
+            // `Format` option. To choose the format of the exported files.
+            .arg(Arg::with_name("format")
+                .short("f")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Format of the exported files. It can be \"json\" (default) or \"xml\".")
+                .possible_values(&["json", "xml"])
+                .takes_value(true))
+
+            // `Import` option. To import JSON/XML files as ESF files.
+            .arg(Arg::with_name("import")
+                .short("i")
+                .long("import")
+                .value_name("JSON/XML FILE - DESTINATION PATH")
+                .help("Imports a JSON/XML file exported with --export as an ESF file in the provided path of the PackFile, overwriting it if it already exists.")
+                .takes_value(true)
+                .number_of_values(2)))
+
+        // `Diagnostics` Subcommand. Checks a PackFile for errors, like the Diagnostics panel of the UI does.
+        .subcommand(SubCommand::with_name("diagnostics")
+            .about("Checks the PackFile for errors, and fails if any of them is over the provided level.")
+
+            // `Format` option. To choose how to output the results.
+            .arg(Arg::with_name("format")
+                .short("f")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Format of the report. Defaults to text.")
+                .possible_values(&["text", "json", "sarif"])
+                .takes_value(true))
+
+            // `Fail Level` option. The minimum level of a result for the check to fail.
+            .arg(Arg::with_name("fail-level")
+                .short("l")
+                .long("fail-level")
+                .value_name("LEVEL")
+                .help("Minimum level of a result for the check to fail. Defaults to error.")
+                .possible_values(&["info", "warning", "error", "none"])
+                .takes_value(true))
+
+            // `Fail On` option. To only fail on specific types of results.
+            .arg(Arg::with_name("fail-on")
+                .long("fail-on")
+                .value_name("RESULT TYPES")
+                .help("Only fail on results of the provided types, like InvalidReference or DuplicatedCombinedKeys. If not provided, all types count.")
+                .takes_value(true)
+                .min_values(1)))
+
+        // `Conflicts` Subcommand. Analyses the load order of all the PackFiles of the game selected, reporting what overwrites what.
+        .subcommand(SubCommand::with_name("conflicts")
+            .about("Reports the PackedFiles and DB rows overwritten by other PackFiles in the load order of the game selected.")
+
+            // `Mods` option. To add PackFiles outside the game's folders to the analysis.
+            .arg(Arg::with_name("mods")
+                .short("m")
+                .long("mods")
+                .value_name("PACKFILE PATHS")
+                .help("Adds the provided PackFiles to the ones found in the data and content folders of the game.")
+                .takes_value(true)
+                .min_values(1))
+
+            // `Filter` option. To only show conflicts about a specific file, table or key.
+            .arg(Arg::with_name("filter")
+                .short("f")
+                .long("filter")
+                .value_name("TEXT")
+                .help("Only reports conflicts whose path, table or key contains the provided text.")
+                .takes_value(true))
+
+            // `No Rows` flag. Skips the DB rows analysis, which is the slow part.
+            .arg(Arg::with_name("no-rows")
+                .long("no-rows")
+                .help("Skips checking DB rows for conflicts, only reporting overwritten PackedFiles."))
+
+            // `JSON` flag. Changes the output to JSON.
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Prints the output as JSON.")))
+
         // `Schema` Subcommand. Basically, here goes commands destined to keep schemas up-to-date.
         .subcommand(SubCommand::with_name("schema")
-            .about("Allows you to keep your schemas up-to-date.")
+            .about("Allows you to keep your schemas up-to-date, and to guess the definitions of new versions of DB Tables.")
             .arg(Arg::with_name("update")
                 .short("u")
                 .long("update")
+                .takes_value(false))
+
+            // `Guess` option. Tries to guess the definition of a DB Table we cannot decode. If a PackFile is provided, the path is a path inside it.
+            .arg(Arg::with_name("guess")
+                .long("guess")
+                .value_name("TABLE PATH")
+                .help("Tries to guess the definition of a DB Table that cannot be decoded, using the previous versions of it in the schema as a base.")
+                .takes_value(true))
+
+            // Amount of candidates to show when guessing definitions.
+            .arg(Arg::with_name("candidates")
+                .long("candidates")
+                .value_name("AMOUNT")
+                .help("Amount of candidates to show when guessing definitions. Defaults to 5.")
+                .requires("guess")
+                .takes_value(true)))
+
+        // `References` Subcommand. Finds where a key is used, across the PackFile and its dependencies.
+        .subcommand(SubCommand::with_name("references")
+            .about("Finds all the usages of a key of a DB Table in the PackFile and its dependencies, including its Loc entries and scripts.")
+
+            // `Table` option. The table the key belongs to.
+            .arg(Arg::with_name("table")
+                .short("t")
+                .long("table")
+                .value_name("TABLE NAME")
+                .help("Name of the table the key belongs to, like main_units or main_units_tables.")
+                .required(true)
+                .takes_value(true))
+
+            // `Key` option. The key to find.
+            .arg(Arg::with_name("key")
+                .short("k")
+                .long("key")
+                .value_name("KEY")
+                .help("Key to find the usages of.")
+                .required(true)
+                .takes_value(true))
+
+            // `Rename` option. To rename the key, along with everything referencing it in the PackFile.
+            .arg(Arg::with_name("rename")
+                .short("r")
+                .long("rename")
+                .value_name("NEW KEY")
+                .help("Renames the key in the PackFile, along with the rows referencing it and its Loc entries, and saves the PackFile.")
+                .takes_value(true))
+
+            // `Scripts` option. To also rename the key in the Lua scripts of the PackFile.
+            .arg(Arg::with_name("scripts")
+                .short("s")
+                .long("scripts")
+                .help("When renaming, also renames the Lua strings equal to the key.")
+                .requires("rename")
+                .takes_value(false))
+
+            // `Dry Run` option. To only report the changes a rename would do.
+            .arg(Arg::with_name("dry-run")
+                .short("d")
+                .long("dry-run")
+                .help("When renaming, only prints the changes, without applying them.")
+                .requires("rename")
+                .takes_value(false))
+
+            // `JSON` option. To output the usages as JSON.
+            .arg(Arg::with_name("json")
+                .short("j")
+                .long("json")
+                .help("Outputs the usages or the changes of a rename as JSON.")
                 .takes_value(false)))
 
+        // `Translation` command. Everything related to translating the Loc entries of a PackFile goes here.
+        .subcommand(SubCommand::with_name("translation")
+            .about("Allows you to export the Loc entries of a PackFile to PO or XLIFF files, import them back and check which ones are missing or outdated.")
+
+            // `Export` option. To export the Loc entries to a translation file.
+            .arg(Arg::with_name("export")
+                .short("e")
+                .long("export")
+                .value_name("TRANSLATION FILE PATH")
+                .help("Exports the Loc entries of the PackFile to the provided file. The format (.po or .xlf/.xliff) is deduced from its extension.")
+                .requires("language")
+                .takes_value(true))
+
+            // `Language` option. The language of the exported file.
+            .arg(Arg::with_name("language")
+                .short("l")
+                .long("language")
+                .value_name("LANGUAGE")
+                .help("Language the exported file is meant to be translated to, like es or zh.")
+                .takes_value(true))
+
+            // `Existing` option. To keep the translated entries of a previous translation file when exporting.
+            .arg(Arg::with_name("existing")
+                .short("x")
+                .long("existing")
+                .value_name("TRANSLATION FILE PATH")
+                .help("Existing translation file for the same language. Its translated entries are kept in the exported file. Without it, nothing is exported as translated.")
+                .requires("export")
+                .takes_value(true))
+
+            // `Import` option. To import a translation file into the Loc entries.
+            .arg(Arg::with_name("import")
+                .short("i")
+                .long("import")
+                .value_name("TRANSLATION FILE PATH")
+                .help("Imports the translated entries of the provided file into the Loc entries of the PackFile, matching them by key, and saves the PackFile.")
+                .takes_value(true))
+
+            // `Report` option. To check which entries are missing or outdated in translation files.
+            .arg(Arg::with_name("report")
+                .short("r")
+                .long("report")
+                .value_name("TRANSLATION FILE PATHS")
+                .help("Prints the Loc entries of the PackFile missing or outdated in each of the provided files.")
+                .takes_value(true)
+                .min_values(1)))
+
+        // `Watch` command. To keep a PackFile in sync with a source folder.
+        .subcommand(SubCommand::with_name("watch")
+            .about("Watches a source folder, updating the PackFile with the files that change in it and saving it to the game's data folder.")
+
+            // `Source` option. The folder to watch.
+            .arg(Arg::with_name("source")
+                .short("s")
+                .long("source")
+                .value_name("SOURCE FOLDER")
+                .help("Folder to watch. Its contents are mapped to the root of the PackFile.")
+                .required(true)
+                .takes_value(true))
+
+            // `Destination` option. The folder where the PackFile is saved.
+            .arg(Arg::with_name("destination")
+                .short("d")
+                .long("destination")
+                .value_name("DESTINATION FOLDER")
+                .help("Folder where the PackFile is saved after every change. If not provided, the data folder of the game selected is used.")
+                .takes_value(true)))
+
 }
@@ -0,0 +1,54 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+//! This module contains the `Conflicts` command's functions.
+
+use log::info;
+
+use std::path::PathBuf;
+
+use rpfm_error::{ErrorKind, Result};
+use rpfm_lib::load_order::LoadOrder;
+use rpfm_lib::schema::Schema;
+use rpfm_lib::{GAME_SELECTED, SUPPORTED_GAMES};
+
+use crate::config::Config;
+
+//---------------------------------------------------------------------------//
+// 							Conflicts Command Variants
+//---------------------------------------------------------------------------//
+
+/// This function analyses the load order of all the PackFiles of the game selected, plus the provided mods, printing the conflicts found.
+pub fn analyse(config: &Config, mod_paths: &[&str], filter: Option<&str>, check_rows: bool, json: bool) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Analysing the load order of {}.", game_selected);
+            }
+
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+            let schema = if check_rows { Some(Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?) } else { None };
+            let mod_paths = mod_paths.iter().map(PathBuf::from).collect::<Vec<PathBuf>>();
+            let mut load_order = LoadOrder::new_from_game_selected(&mod_paths, schema.as_ref())?;
+            if let Some(filter) = filter {
+                load_order.filter(filter);
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&load_order)?);
+            } else {
+                print!("{}", load_order);
+            }
+
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
@@ -0,0 +1,81 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+//! This module contains the `Diagnostics` command's functions.
+
+use log::info;
+use rayon::prelude::*;
+
+use std::path::PathBuf;
+
+use rpfm_error::{ErrorKind, Result};
+use rpfm_lib::context::GameContext;
+use rpfm_lib::diagnostics::{Diagnostics, DiagnosticLevel};
+use rpfm_lib::packedfile::PackedFileType;
+use rpfm_lib::packfile::PackFile;
+use rpfm_lib::SETTINGS;
+
+use crate::config::Config;
+
+//---------------------------------------------------------------------------//
+// 							Diagnostics Command Variants
+//---------------------------------------------------------------------------//
+
+/// This function runs all the diagnostics checks over a PackFile, printing the results in the provided format.
+///
+/// If any result is of the provided level or higher (and of one of the provided types, if any), this returns an error.
+pub fn check(config: &Config, packfile: &str, format: &str, fail_level: &str, fail_on: &[&str]) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Checking the PackFile: {}", packfile);
+            }
+
+            let mut context = GameContext::new(game_selected, &SETTINGS.read().unwrap())?;
+            context.load_schema()?;
+
+            let packfile_path = PathBuf::from(packfile);
+            let mut pack_file = PackFile::open_packfiles_with_context(&[packfile_path], true, false, false, &context)?;
+            pack_file.get_ref_mut_packed_files_by_types(&[PackedFileType::DB, PackedFileType::Loc], false).par_iter_mut().for_each(|x| {
+                let _ = x.decode_with_context(&context);
+            });
+            context.rebuild_dependencies(pack_file.get_packfiles_list());
+
+            let mut diagnostics = Diagnostics::default();
+            diagnostics.check_with_context(&pack_file, &context);
+
+            match format {
+                "json" => println!("{}", serde_json::to_string_pretty(&diagnostics.get_entries())?),
+                "sarif" => println!("{}", diagnostics.to_sarif(&pack_file.get_file_name())?),
+                _ => print!("{}", diagnostics),
+            }
+
+            let fail_level = match fail_level {
+                "info" => Some(DiagnosticLevel::Info),
+                "warning" => Some(DiagnosticLevel::Warning),
+                "error" => Some(DiagnosticLevel::Error),
+                _ => None,
+            };
+
+            if let Some(fail_level) = fail_level {
+                let failures = diagnostics.get_entries().iter()
+                    .filter(|x| x.level >= fail_level && (fail_on.is_empty() || fail_on.contains(&&*x.report_type)))
+                    .count();
+
+                if failures > 0 {
+                    return Err(ErrorKind::NoHTMLError(format!("Diagnostics check failed: {} results of level {} or higher found.", failures, fail_level)).into());
+                }
+            }
+
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
@@ -0,0 +1,108 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+//! This module contains the `ESF` command's functions.
+
+use log::info;
+
+use std::fs::{create_dir_all, read_to_string, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use rpfm_error::{ErrorKind, Result};
+use rpfm_lib::packedfile::{DecodedPackedFile, PackedFileType};
+use rpfm_lib::packedfile::esf::ESF;
+use rpfm_lib::packfile::PackFile;
+use rpfm_lib::packfile::packedfile::PackedFile;
+
+use crate::config::Config;
+
+//---------------------------------------------------------------------------//
+// 							ESF Command Variants
+//---------------------------------------------------------------------------//
+
+/// This function exports the ESF files of the PackFile to JSON or XML, saving them in the provided folder with their paths.
+///
+/// If no paths are provided, all the ESF files of the PackFile are exported.
+pub fn export(config: &Config, packfile: &str, paths: &[&str], destination_path: &str, format: &str) -> Result<()> {
+    if config.verbosity_level > 0 {
+        info!("Exporting ESF files from the PackFile: {}", packfile);
+    }
+
+    let packfile_path = PathBuf::from(packfile);
+    let packfile = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+    let paths = paths.iter().map(|x| x.split('/').map(|x| x.to_owned()).collect::<Vec<String>>()).collect::<Vec<Vec<String>>>();
+    let destination_path = PathBuf::from(destination_path);
+
+    for packed_file in packfile.get_ref_packed_files_by_type(PackedFileType::StarPos, false) {
+        let path = packed_file.get_path();
+        if paths.is_empty() || paths.iter().any(|x| path.starts_with(x)) {
+            let esf = ESF::read(&packed_file.get_raw_data()?)?;
+            let data = match format {
+                "json" => esf.to_json()?,
+                "xml" => esf.to_xml()?,
+                _ => return Err(ErrorKind::NoHTMLError(format!("Invalid format: {}.", format)).into()),
+            };
+
+            save_file(&destination_path, &path.join("/"), format, &data)?;
+        }
+    }
+
+    if config.verbosity_level > 0 {
+        info!("ESF files successfully exported.");
+    }
+
+    Ok(())
+}
+
+/// This function imports a JSON or XML file exported with `export` as an ESF file in the provided path of the PackFile, then saves it.
+///
+/// The format is guessed from the extension of the source file. If there is already a file in the path, it's overwritten.
+pub fn import(config: &Config, packfile: &str, source_path: &str, destination_path: &str) -> Result<()> {
+    if config.verbosity_level > 0 {
+        info!("Importing {} as an ESF file in the PackFile: {}", source_path, packfile);
+    }
+
+    let source = read_to_string(source_path)?;
+    let esf = match Path::new(source_path).extension().and_then(|x| x.to_str()) {
+        Some("json") => ESF::from_json(&source)?,
+        Some("xml") => ESF::from_xml(&source)?,
+        _ => return Err(ErrorKind::NoHTMLError(format!("{} is not a JSON or XML file.", source_path)).into()),
+    };
+
+    let packfile_path = PathBuf::from(packfile);
+    let mut packfile = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+    let destination_path = destination_path.split('/').map(|x| x.to_owned()).collect::<Vec<String>>();
+    packfile.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::StarPos(esf), &destination_path), true)?;
+    let result = packfile.save(None);
+
+    if config.verbosity_level > 0 {
+        info!("ESF file successfully imported.");
+    }
+
+    result
+}
+
+//---------------------------------------------------------------------------//
+// 							    Helpers
+//---------------------------------------------------------------------------//
+
+/// This function saves an exported ESF file in the destination folder, under the path of the ESF file it came from.
+fn save_file(destination_path: &Path, path: &str, extension: &str, data: &str) -> Result<()> {
+    let mut file_path = destination_path.join(path);
+    file_path.set_extension(extension);
+    if let Some(parent) = file_path.parent() {
+        create_dir_all(parent)?;
+    }
+
+    let mut file = File::create(&file_path)?;
+    file.write_all(data.as_bytes()).map_err(|_| ErrorKind::IOGenericWrite(vec![file_path.to_string_lossy().to_string()]))?;
+    Ok(())
+}
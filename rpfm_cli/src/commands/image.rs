@@ -0,0 +1,142 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+//! This module contains the `Image` command's functions.
+
+use log::{info, warn};
+use prettytable::{Table, row, cell};
+
+use std::fs::{create_dir_all, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use rpfm_error::{ErrorKind, Result};
+use rpfm_lib::packedfile::PackedFileType;
+use rpfm_lib::packedfile::image::Image;
+use rpfm_lib::packfile::PackFile;
+
+use crate::config::Config;
+
+//---------------------------------------------------------------------------//
+// 							Image Command Variants
+//---------------------------------------------------------------------------//
+
+/// This function lists the format, size and mipmaps of the images in the PackFile, checking they can be decoded.
+///
+/// If no paths are provided, all the images of the PackFile are checked. If any of them fails to decode, this returns an error.
+pub fn info(config: &Config, packfile: &str, paths: &[&str]) -> Result<()> {
+    if config.verbosity_level > 0 {
+        info!("Checking Images from the PackFile: {}", packfile);
+    }
+
+    let images = get_images(packfile, paths)?;
+    let mut broken = vec![];
+    let mut table = Table::new();
+    table.add_row(row!["PackedFile Path", "Format", "Width", "Height", "Mipmaps", "Status"]);
+    for (path, image) in &images {
+        let status = match image.decode_rgba8(0) {
+            Ok(_) => "Ok".to_owned(),
+            Err(error) => {
+                broken.push(path.to_owned());
+                error.to_terminal()
+            }
+        };
+        table.add_row(row![path, image.get_format(), image.get_width(), image.get_height(), image.get_mipmap_count(), status]);
+    }
+
+    table.printstd();
+    if broken.is_empty() { Ok(()) }
+    else { Err(ErrorKind::NoHTMLError(format!("{} of {} Images cannot be decoded.", broken.len(), images.len())).into()) }
+}
+
+/// This function converts the images of the PackFile to PNG, saving them in the provided folder with their paths.
+///
+/// If no paths are provided, all the images of the PackFile are converted.
+pub fn convert(config: &Config, packfile: &str, paths: &[&str], destination_path: &str) -> Result<()> {
+    if config.verbosity_level > 0 {
+        info!("Converting Images from the PackFile: {}", packfile);
+    }
+
+    let destination_path = PathBuf::from(destination_path);
+    for (path, image) in get_images(packfile, paths)? {
+        match image.to_png(0) {
+            Ok(data) => save_png(&destination_path, &path, &data)?,
+            Err(error) => warn!("{} could not be converted: {}", path, error.to_terminal()),
+        }
+    }
+
+    if config.verbosity_level > 0 {
+        info!("Images successfully converted.");
+    }
+
+    Ok(())
+}
+
+/// This function generates PNG thumbnails of the images of the PackFile, saving them in the provided folder with their paths.
+///
+/// If no paths are provided, thumbnails of all the images of the PackFile are generated.
+pub fn thumbnail(config: &Config, packfile: &str, paths: &[&str], destination_path: &str, size: &str) -> Result<()> {
+    if config.verbosity_level > 0 {
+        info!("Generating thumbnails of the Images from the PackFile: {}", packfile);
+    }
+
+    let size = size.parse::<u32>().map_err(|_| ErrorKind::NoHTMLError(format!("Invalid thumbnail size: {}.", size)))?;
+    let destination_path = PathBuf::from(destination_path);
+    for (path, image) in get_images(packfile, paths)? {
+        match image.to_png_thumbnail(size) {
+            Ok(data) => save_png(&destination_path, &path, &data)?,
+            Err(error) => warn!("{} could not be converted: {}", path, error.to_terminal()),
+        }
+    }
+
+    if config.verbosity_level > 0 {
+        info!("Thumbnails successfully generated.");
+    }
+
+    Ok(())
+}
+
+//---------------------------------------------------------------------------//
+// 							    Helpers
+//---------------------------------------------------------------------------//
+
+/// This function returns the images of the PackFile that are in any of the provided paths (files or folders), with their paths.
+///
+/// If no paths are provided, all the images are returned.
+fn get_images(packfile: &str, paths: &[&str]) -> Result<Vec<(String, Image)>> {
+    let packfile_path = PathBuf::from(packfile);
+    let packfile = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+    let paths = paths.iter().map(|x| x.split('/').map(|x| x.to_owned()).collect::<Vec<String>>()).collect::<Vec<Vec<String>>>();
+
+    let mut images = vec![];
+    for packed_file in packfile.get_ref_packed_files_by_type(PackedFileType::Image, false) {
+        let path = packed_file.get_path();
+        if paths.is_empty() || paths.iter().any(|x| path.starts_with(x)) {
+            let data = packed_file.get_raw_data()?;
+            images.push((path.join("/"), Image::read(&data)?));
+        }
+    }
+
+    images.sort_by(|(x, _), (y, _)| x.cmp(y));
+    Ok(images)
+}
+
+/// This function saves a PNG in the destination folder, under the path of the image it came from.
+fn save_png(destination_path: &Path, path: &str, data: &[u8]) -> Result<()> {
+    let mut file_path = destination_path.join(path);
+    file_path.set_extension("png");
+    if let Some(parent) = file_path.parent() {
+        create_dir_all(parent)?;
+    }
+
+    let mut file = File::create(&file_path)?;
+    file.write_all(data).map_err(|_| ErrorKind::IOGenericWrite(vec![file_path.to_string_lossy().to_string()]))?;
+    Ok(())
+}
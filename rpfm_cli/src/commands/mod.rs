@@ -17,9 +17,16 @@ use rpfm_error::{ErrorKind, Result};
 
 use crate::config::Config;
 
+mod conflicts;
+mod diagnostics;
+mod esf;
+mod image;
 mod table;
 mod packfile;
+mod references;
 mod schema;
+mod translation;
+mod watch;
 
 //---------------------------------------------------------------------------//
 // 								Command Variants
@@ -96,6 +103,24 @@ pub fn command_packfile(config: &Config, matches: &ArgMatches, packfile: Option<
             }
 
 			else if matches.is_present("list") { packfile::list_packfile_contents(&config, packfile_path) }
+            else if let Some(other_packfile_path) = matches.value_of("diff") { packfile::diff(config, packfile_path, other_packfile_path, matches.is_present("json")) }
+            else if let Some(other_packfile_path) = matches.value_of("merge") { packfile::merge(config, packfile_path, other_packfile_path) }
+            else if matches.is_present("update-tables") { packfile::update_tables(config, packfile_path) }
+            else if let Some(values) = matches.values_of("port-tables") {
+                let values = values.collect::<Vec<&str>>();
+                packfile::port_tables(config, packfile_path, values[0], values[1], matches.is_present("remove-failed"))
+            }
+            else if let Some(loc_path) = matches.value_of("add-loc-stubs") { packfile::add_loc_stubs(config, packfile_path, loc_path, matches.is_present("dry-run")) }
+            else if let Some(paths) = matches.values_of("export-json") {
+                let paths = paths.collect::<Vec<&str>>();
+                packfile::export_json(config, packfile_path, paths[0], paths[1])
+            }
+            else if let Some(paths) = matches.values_of("import-json") {
+                let paths = paths.collect::<Vec<&str>>();
+                packfile::import_json(config, packfile_path, paths[0], paths[1])
+            }
+            else if let Some(folder) = matches.value_of("unpack") { packfile::unpack(config, packfile_path, folder) }
+            else if let Some(folder) = matches.value_of("pack") { packfile::pack(config, packfile_path, folder) }
             else if matches.is_present("new-packfile") { packfile::new_packfile(&config, packfile_path)}
 
 			else { Err(ErrorKind::NoHTMLError("No valid argument provided.".to_owned()).into()) }
@@ -129,11 +154,166 @@ pub fn command_table(config: &Config, matches: &ArgMatches, _packfile: Option<&s
 	else { Err(ErrorKind::NoHTMLError("No valid argument provided.".to_owned()).into()) }
 }
 
+/// This function triggers functions that require the `Image` command.
+pub fn command_image(config: &Config, matches: &ArgMatches, packfile: Option<&str>) -> Result<()> {
+    match packfile {
+        Some(packfile_path) => {
+            if matches.is_present("info") {
+                let paths = matches.values_of("info").map(|values| values.collect::<Vec<&str>>()).unwrap_or_default();
+                image::info(config, packfile_path, &paths)
+            }
+
+            else if matches.is_present("convert") {
+                match matches.values_of("convert") {
+                    Some(mut values) => {
+                        let destination_path = values.next().unwrap();
+                        let paths = values.collect::<Vec<&str>>();
+                        image::convert(config, packfile_path, &paths, destination_path)
+                    },
+                    None => Err(ErrorKind::NoHTMLError("No valid argument provided.".to_owned()).into())
+                }
+            }
+
+            else if matches.is_present("thumbnail") {
+                match matches.values_of("thumbnail") {
+                    Some(mut values) => {
+                        let size = values.next().unwrap();
+                        let destination_path = values.next().unwrap();
+                        let paths = values.collect::<Vec<&str>>();
+                        image::thumbnail(config, packfile_path, &paths, destination_path, size)
+                    },
+                    None => Err(ErrorKind::NoHTMLError("No valid argument provided.".to_owned()).into())
+                }
+            }
+
+            else { Err(ErrorKind::NoHTMLError("No valid argument provided.".to_owned()).into()) }
+        },
+        None => Err(ErrorKind::NoHTMLError("No PackFile provided.".to_owned()).into()),
+    }
+}
+
+/// This function triggers functions that require the `ESF` command.
+pub fn command_esf(config: &Config, matches: &ArgMatches, packfile: Option<&str>) -> Result<()> {
+    match packfile {
+        Some(packfile_path) => {
+            if matches.is_present("export") {
+                match matches.values_of("export") {
+                    Some(mut values) => {
+                        let destination_path = values.next().unwrap();
+                        let paths = values.collect::<Vec<&str>>();
+                        let format = matches.value_of("format").unwrap_or("json");
+                        esf::export(config, packfile_path, &paths, destination_path, format)
+                    },
+                    None => Err(ErrorKind::NoHTMLError("No valid argument provided.".to_owned()).into())
+                }
+            }
+
+            else if matches.is_present("import") {
+                match matches.values_of("import") {
+                    Some(mut values) => {
+                        let source_path = values.next().unwrap();
+                        let destination_path = values.next().unwrap();
+                        esf::import(config, packfile_path, source_path, destination_path)
+                    },
+                    None => Err(ErrorKind::NoHTMLError("No valid argument provided.".to_owned()).into())
+                }
+            }
+
+            else { Err(ErrorKind::NoHTMLError("No valid argument provided.".to_owned()).into()) }
+        },
+        None => Err(ErrorKind::NoHTMLError("No PackFile provided.".to_owned()).into()),
+    }
+}
+
+/// This function triggers functions that require the `Diagnostics` command.
+pub fn command_diagnostics(config: &Config, matches: &ArgMatches, packfile: Option<&str>) -> Result<()> {
+    match packfile {
+        Some(packfile_path) => {
+            let format = matches.value_of("format").unwrap_or("text");
+            let fail_level = matches.value_of("fail-level").unwrap_or("error");
+            let fail_on = matches.values_of("fail-on").map(|values| values.collect::<Vec<&str>>()).unwrap_or_default();
+            diagnostics::check(config, packfile_path, format, fail_level, &fail_on)
+        },
+        None => Err(ErrorKind::NoHTMLError("No PackFile provided.".to_owned()).into()),
+    }
+}
+
+/// This function triggers functions that require the `Conflicts` command.
+///
+/// If a PackFile is provided, it's analysed along with the mods.
+pub fn command_conflicts(config: &Config, matches: &ArgMatches, packfile: Option<&str>) -> Result<()> {
+    let mut mod_paths = matches.values_of("mods").map(|values| values.collect::<Vec<&str>>()).unwrap_or_default();
+    if let Some(packfile_path) = packfile {
+        mod_paths.push(packfile_path);
+    }
+
+    conflicts::analyse(config, &mod_paths, matches.value_of("filter"), !matches.is_present("no-rows"), matches.is_present("json"))
+}
+
 /// This function triggers functions that require the `Schema` command.
-pub fn command_schema(config: &Config, matches: &ArgMatches) -> Result<()> {
+pub fn command_schema(config: &Config, matches: &ArgMatches, packfile: Option<&str>) -> Result<()> {
     if matches.is_present("update") {
 		schema::update(config)
     }
 
+    else if let Some(table_path) = matches.value_of("guess") {
+        match matches.value_of("candidates").unwrap_or("5").parse::<usize>() {
+            Ok(max_candidates) => schema::guess(config, packfile, table_path, max_candidates),
+            Err(_) => Err(ErrorKind::NoHTMLError("Invalid amount of candidates provided.".to_owned()).into()),
+        }
+    }
+
 	else { Err(ErrorKind::NoHTMLError("No valid argument provided.".to_owned()).into()) }
 }
+
+/// This function triggers functions that require the `References` command.
+pub fn command_references(config: &Config, matches: &ArgMatches, packfile: Option<&str>) -> Result<()> {
+    match packfile {
+        Some(packfile_path) => {
+            match (matches.value_of("table"), matches.value_of("key")) {
+                (Some(table_name), Some(key)) => match matches.value_of("rename") {
+                    Some(new_key) => references::rename_key(config, packfile_path, table_name, key, new_key, matches.is_present("scripts"), matches.is_present("dry-run"), matches.is_present("json")),
+                    None => references::find_usages(config, packfile_path, table_name, key, matches.is_present("json")),
+                },
+                _ => Err(ErrorKind::NoHTMLError("No table or key provided.".to_owned()).into()),
+            }
+        },
+        None => Err(ErrorKind::NoHTMLError("No PackFile provided.".to_owned()).into()),
+    }
+}
+
+/// This function triggers functions that require the `Translation` command.
+pub fn command_translation(config: &Config, matches: &ArgMatches, packfile: Option<&str>) -> Result<()> {
+    match packfile {
+        Some(packfile_path) => {
+            if let Some(path) = matches.value_of("export") {
+                match matches.value_of("language") {
+                    Some(language) => translation::export(config, packfile_path, path, language, matches.value_of("existing")),
+                    None => Err(ErrorKind::NoHTMLError("No language provided.".to_owned()).into()),
+                }
+            }
+
+            else if let Some(path) = matches.value_of("import") {
+                translation::import(config, packfile_path, path)
+            }
+
+            else if let Some(paths) = matches.values_of("report") {
+                translation::report(config, packfile_path, &paths.collect::<Vec<&str>>())
+            }
+
+            else { Err(ErrorKind::NoHTMLError("No valid argument provided.".to_owned()).into()) }
+        },
+        None => Err(ErrorKind::NoHTMLError("No PackFile provided.".to_owned()).into()),
+    }
+}
+
+/// This function triggers functions that require the `Watch` command.
+pub fn command_watch(config: &Config, matches: &ArgMatches, packfile: Option<&str>) -> Result<()> {
+    match packfile {
+        Some(packfile_path) => match matches.value_of("source") {
+            Some(source) => watch::watch(config, packfile_path, source, matches.value_of("destination")),
+            None => Err(ErrorKind::NoHTMLError("No source folder provided.".to_owned()).into()),
+        },
+        None => Err(ErrorKind::NoHTMLError("No PackFile provided.".to_owned()).into()),
+    }
+}
@@ -11,15 +11,22 @@
 //! This module contains the `PackFile` command's functions.
 
 use bytesize::ByteSize;
-use log::info;
+use log::{info, warn};
 use prettytable::{Table, row, cell};
 
-use std::path::PathBuf;
+use std::fs::{read_to_string, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use rpfm_error::{ErrorKind, Result};
-use rpfm_lib::packedfile::PackedFileType;
+use rpfm_lib::context::GameContext;
+use rpfm_lib::dependencies::Dependencies;
+use rpfm_lib::diff::PackFileDiff;
+use rpfm_lib::packedfile::{DecodedPackedFile, PackedFileType};
 use rpfm_lib::packfile::{PackFile, PathType};
-use rpfm_lib::SUPPORTED_GAMES;
+use rpfm_lib::packfile::packedfile::PackedFile;
+use rpfm_lib::schema::Schema;
+use rpfm_lib::{GAME_SELECTED, SCHEMA, SETTINGS, SUPPORTED_GAMES};
 
 use crate::config::Config;
 
@@ -223,6 +230,328 @@ pub fn list_packfile_contents(config: &Config, packfile: &str) -> Result<()> {
 	Ok(())
 }
 
+/// This function compares the provided PackFile with another one, printing the changes needed to go from the first to the second.
+///
+/// Tables are only compared row by row if a game is selected, as we need its schema to decode them.
+pub fn diff(config: &Config, packfile: &str, other_packfile: &str, json: bool) -> Result<()> {
+    if config.verbosity_level > 0 {
+        info!("Comparing PackFile {} with {}.", packfile, other_packfile);
+    }
+
+    if let Some(game_selected) = &config.game_selected {
+        *SCHEMA.write().unwrap() = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema).ok();
+    }
+
+    let old_packfile = PackFile::open_packfiles(&[PathBuf::from(packfile)], true, false, false)?;
+    let new_packfile = PackFile::open_packfiles(&[PathBuf::from(other_packfile)], true, false, false)?;
+    let diff = PackFileDiff::new(&old_packfile, &new_packfile)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        print!("{}", diff);
+    }
+
+    Ok(())
+}
+
+/// This function merges the DB and Loc Tables of another PackFile into the ones with the same path in the provided PackFile, then saves it.
+///
+/// The vanilla tables of the game selected are used as base for the merge.
+pub fn merge(config: &Config, packfile: &str, other_packfile: &str) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Merging the tables of the PackFile {} into {}.", other_packfile, packfile);
+            }
+
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+            let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
+
+            let mut packfile = PackFile::open_packfiles(&[PathBuf::from(packfile)], true, false, false)?;
+            let other_packfile = PackFile::open_packfiles(&[PathBuf::from(other_packfile)], true, false, false)?;
+
+            let mut dependencies = Dependencies::default();
+            let mut real_dep_db = PackFile::load_all_dependency_packfiles(packfile.get_packfiles_list());
+            real_dep_db.iter_mut().for_each(|x| {
+                let _ = x.decode_no_locks(&schema);
+            });
+            *dependencies.get_ref_mut_dependency_database() = real_dep_db;
+            *SCHEMA.write().unwrap() = Some(schema);
+
+            let merges = packfile.merge_tables_from_packfile(&other_packfile, &dependencies);
+            let mut conflicts_count = 0;
+            for (path, merge) in &merges {
+                match merge {
+                    Ok(conflicts) => {
+                        conflicts_count += conflicts.len();
+                        for conflict in conflicts {
+                            println!("{}: {}", path.join("/"), conflict);
+                        }
+                    }
+                    Err(error) => warn!("{}: {}", path.join("/"), error),
+                }
+            }
+
+            packfile.save(None)?;
+
+            if config.verbosity_level > 0 {
+                info!("{} tables merged, with {} conflicts.", merges.iter().filter(|(_, x)| x.is_ok()).count(), conflicts_count);
+            }
+
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
+
+/// This function updates all the outdated DB Tables of a PackFile to the versions used by the game, then saves it.
+pub fn update_tables(config: &Config, packfile: &str) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Updating the tables of the PackFile: {}", packfile);
+            }
+
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+            let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
+
+            let packfile_path = PathBuf::from(packfile);
+            let mut packfile = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+
+            let mut dependencies = Dependencies::default();
+            let mut real_dep_db = PackFile::load_all_dependency_packfiles(packfile.get_packfiles_list());
+            real_dep_db.iter_mut().for_each(|x| {
+                let _ = x.decode_no_locks(&schema);
+            });
+            *dependencies.get_ref_mut_dependency_database() = real_dep_db;
+            *SCHEMA.write().unwrap() = Some(schema);
+
+            let migrations = packfile.update_tables(&dependencies);
+            for (path, migration) in &migrations {
+                print!("{}: {}", path.join("/"), migration);
+            }
+
+            if !migrations.is_empty() {
+                packfile.save(None)?;
+            }
+
+            if config.verbosity_level > 0 {
+                info!("{} tables updated.", migrations.len());
+            }
+
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
+
+/// This function ports all the DB and Loc Tables of a PackFile from the game selected to the provided game, then saves it for that game in the destination path.
+///
+/// If any table cannot be ported, the PackFile is only saved if `remove_failed` is true, removing those tables from it.
+pub fn port_tables(config: &Config, packfile: &str, target_game: &str, destination: &str, remove_failed: bool) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Porting the tables of the PackFile {} to {}.", packfile, target_game);
+            }
+
+            let mut source = GameContext::new(game_selected, &SETTINGS.read().unwrap())?;
+            source.load_schema()?;
+
+            let mut target = GameContext::new(target_game, &SETTINGS.read().unwrap())?;
+            target.load_schema()?;
+
+            let packfile_path = PathBuf::from(packfile);
+            let mut packfile = PackFile::open_packfiles_with_context(&[packfile_path], true, false, false, &source)?;
+            target.rebuild_dependencies(packfile.get_packfiles_list());
+
+            let ports = packfile.port_tables(&source, &target, remove_failed);
+            let mut ported = 0;
+            for (path, port) in &ports {
+                match port {
+                    Ok(port) => {
+                        ported += 1;
+                        print!("{}: {}", path.join("/"), port);
+                    }
+                    Err(error) => warn!("{}: {}", path.join("/"), error),
+                }
+            }
+
+            let failed = ports.len() - ported;
+            if failed > 0 && !remove_failed {
+                return Err(ErrorKind::NoHTMLError(format!("{} tables cannot be ported, so the PackFile has not been saved. Use --remove-failed to save it without them.", failed)).into());
+            }
+
+            packfile.save_with_context(Some(PathBuf::from(destination)), &target)?;
+
+            if config.verbosity_level > 0 {
+                info!("{} of {} tables ported, {} removed.", ported, ports.len(), failed);
+            }
+
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
+
+/// This function adds placeholder Loc entries for the localised fields of the DB rows of the PackFile without one, and saves the PackFile.
+///
+/// The entries are always printed. If `dry_run` is true, they are not added and the PackFile is not saved.
+pub fn add_loc_stubs(config: &Config, packfile: &str, loc_path: &str, dry_run: bool) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Adding the missing Loc entries of the PackFile {} to: {}", packfile, loc_path);
+            }
+
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+            let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
+
+            let packfile_path = PathBuf::from(packfile);
+            let mut packfile = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+
+            let mut dependencies = Dependencies::default();
+            let mut real_dep_db = PackFile::load_all_dependency_packfiles(packfile.get_packfiles_list());
+            real_dep_db.iter_mut().for_each(|x| {
+                let _ = x.decode_no_locks(&schema);
+            });
+            *dependencies.get_ref_mut_dependency_database() = real_dep_db;
+
+            let loc_path = loc_path.split('/').map(|x| x.to_owned()).collect::<Vec<String>>();
+            let entries = packfile.add_loc_stubs(&schema, &dependencies, &loc_path, dry_run)?;
+            for entry in &entries {
+                println!("{}\t{}\t{}", entry.path, entry.row, entry.key);
+            }
+
+            if !dry_run && !entries.is_empty() {
+                packfile.save(None)?;
+            }
+
+            if config.verbosity_level > 0 {
+                info!("{} missing Loc entries found.", entries.len());
+            }
+
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
+
+/// This function exports a decoded PackedFile of the PackFile to a JSON file, or to a RON file if the destination ends in `.ron`.
+pub fn export_json(config: &Config, packfile: &str, packed_file_path: &str, destination: &str) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Exporting the PackedFile {} of the PackFile {} to: {}", packed_file_path, packfile, destination);
+            }
+
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+            let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
+
+            let packfile_path = PathBuf::from(packfile);
+            let mut packfile = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+            let packed_file_path = packed_file_path.split('/').map(|x| x.to_owned()).collect::<Vec<String>>();
+            let decoded = packfile.get_ref_mut_packed_file_by_path(&packed_file_path)
+                .ok_or(ErrorKind::PackedFileNotFound)?
+                .decode_return_ref_no_locks(&schema)?;
+
+            let data = if is_ron(destination) { decoded.to_ron()? } else { decoded.to_json()? };
+            File::create(destination)?.write_all(data.as_bytes())?;
+
+            if config.verbosity_level > 0 {
+                info!("PackedFile exported.");
+            }
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
+
+/// This function imports a JSON/RON file generated by `export_json` into the provided path of the PackFile, then saves it.
+pub fn import_json(config: &Config, packfile: &str, source: &str, packed_file_path: &str) -> Result<()> {
+    if config.verbosity_level > 0 {
+        info!("Importing the file {} into the PackedFile {} of the PackFile: {}", source, packed_file_path, packfile);
+    }
+
+    let data = read_to_string(source)?;
+    let decoded = if is_ron(source) { DecodedPackedFile::from_ron(&data)? } else { DecodedPackedFile::from_json(&data)? };
+
+    // Make sure we don't end up with a PackedFile the game will not be able to read.
+    let packed_file_path = packed_file_path.split('/').map(|x| x.to_owned()).collect::<Vec<String>>();
+    if PackedFileType::get_packed_file_type(&packed_file_path) != PackedFileType::from(&decoded) {
+        return Err(ErrorKind::NoHTMLError(format!("The type of the file ({}) doesn't match the type of the path ({}).", PackedFileType::from(&decoded), PackedFileType::get_packed_file_type(&packed_file_path))).into());
+    }
+
+    let packfile_path = PathBuf::from(packfile);
+    let mut packfile = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+    packfile.add_packed_file(&PackedFile::new_from_decoded(&decoded, &packed_file_path), true)?;
+    packfile.save(None)?;
+
+    if config.verbosity_level > 0 {
+        info!("PackedFile imported.");
+    }
+    Ok(())
+}
+
+/// This function returns if the provided path is for a RON file.
+fn is_ron(path: &str) -> bool {
+    match Path::new(path).extension() {
+        Some(extension) => extension.to_string_lossy().to_lowercase() == "ron",
+        None => false,
+    }
+}
+
+/// This function unpacks the PackFile into a project folder.
+pub fn unpack(config: &Config, packfile: &str, folder: &str) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Unpacking the PackFile {} into: {}", packfile, folder);
+            }
+
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+            let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
+
+            let packfile_path = PathBuf::from(packfile);
+            let mut packfile = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+            let raw_tables = packfile.unpack_to_folder(&schema, Path::new(folder))?;
+            for path in &raw_tables {
+                warn!("Table stored as raw data, as it cannot be rebuilt from a TSV/JSON file: {}", path.join("/"));
+            }
+
+            if config.verbosity_level > 0 {
+                info!("PackFile unpacked.");
+            }
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
+
+/// This function rebuilds the PackFile from a project folder, and saves it in the provided path.
+pub fn pack(config: &Config, packfile: &str, folder: &str) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Packing the folder {} into: {}", folder, packfile);
+            }
+
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+            let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
+
+            let mut packfile_new = PackFile::pack_from_folder(&schema, Path::new(folder))?;
+            packfile_new.save_keeping_timestamp(Some(PathBuf::from(packfile)))?;
+
+            if config.verbosity_level > 0 {
+                info!("PackFile packed.");
+            }
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
+
 /// This function creates a new packfile with the provided path.
 pub fn new_packfile(config: &Config, packfile: &str) -> Result<()> {
     if config.verbosity_level > 0 {
@@ -0,0 +1,110 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+//! This module contains the `References` command's functions.
+
+use log::info;
+use rayon::prelude::*;
+
+use std::path::PathBuf;
+
+use rpfm_error::{ErrorKind, Result};
+use rpfm_lib::dependencies::Dependencies;
+use rpfm_lib::packedfile::PackedFileType;
+use rpfm_lib::packedfile::table::db::DB;
+use rpfm_lib::packfile::PackFile;
+use rpfm_lib::references::ReferenceIndex;
+use rpfm_lib::schema::Schema;
+use rpfm_lib::{GAME_SELECTED, SUPPORTED_GAMES};
+
+use crate::config::Config;
+
+//---------------------------------------------------------------------------//
+// 							References Command Variants
+//---------------------------------------------------------------------------//
+
+/// This function prints all the usages of the provided key of the provided table, in the PackFile and its dependencies.
+pub fn find_usages(config: &Config, packfile: &str, table_name: &str, key: &str, json: bool) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Finding the usages of the key \"{}\" of the table \"{}\" in the PackFile: {}", key, table_name, packfile);
+            }
+
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+            let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
+
+            let packfile_path = PathBuf::from(packfile);
+            let mut pack_file = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+            pack_file.get_ref_mut_packed_files_by_types(&[PackedFileType::DB, PackedFileType::Loc], false).par_iter_mut().for_each(|x| {
+                let _ = x.decode_no_locks(&schema);
+            });
+
+            let mut dependencies = Dependencies::default();
+            let mut real_dep_db = PackFile::load_all_dependency_packfiles(pack_file.get_packfiles_list());
+            real_dep_db.par_iter_mut().for_each(|x| {
+                let _ = x.decode_no_locks(&schema);
+            });
+            *dependencies.get_ref_mut_dependency_database() = real_dep_db;
+            *dependencies.get_ref_mut_fake_dependency_database() = DB::read_pak_file();
+
+            let usages = ReferenceIndex::new(&schema).find_usages(&pack_file, &dependencies, table_name, key);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&usages)?);
+            } else {
+                for usage in &usages {
+                    let source = if usage.is_dependency { " (dependency)" } else { "" };
+                    println!("{}\t{}{}\t{}:{}\t{}", usage.usage_type, usage.path, source, usage.row, usage.column, usage.context);
+                }
+            }
+
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
+
+/// This function renames the provided key of the provided table in the PackFile, along with everything referencing it, and saves the PackFile.
+///
+/// The changes are always printed. If `dry_run` is true, they are not applied and the PackFile is not saved.
+#[allow(clippy::too_many_arguments)]
+pub fn rename_key(config: &Config, packfile: &str, table_name: &str, old_key: &str, new_key: &str, rename_in_scripts: bool, dry_run: bool, json: bool) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Renaming the key \"{}\" of the table \"{}\" to \"{}\" in the PackFile: {}", old_key, table_name, new_key, packfile);
+            }
+
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+            let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
+
+            let packfile_path = PathBuf::from(packfile);
+            let mut pack_file = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+            let renames = pack_file.rename_key(&schema, table_name, old_key, new_key, rename_in_scripts, dry_run)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&renames)?);
+            } else {
+                for rename in &renames {
+                    println!("{}\t{}\t{}:{}\t{} -> {}", rename.usage_type, rename.path, rename.row, rename.column, rename.old_value, rename.new_value);
+                }
+            }
+
+            if !dry_run {
+                pack_file.save(None)?;
+                if config.verbosity_level > 0 {
+                    info!("Key successfully renamed in {} places.", renames.len());
+                }
+            }
+
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
@@ -10,8 +10,15 @@
 
 use log::info;
 
-use rpfm_error::Result;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::PathBuf;
+
+use rpfm_error::{ErrorKind, Result};
+use rpfm_lib::packedfile::table::db::DB;
+use rpfm_lib::packfile::PackFile;
 use rpfm_lib::schema::Schema;
+use rpfm_lib::SUPPORTED_GAMES;
 
 use crate::config::Config;
 
@@ -30,3 +37,50 @@ pub fn update(config: &Config) -> Result<()> {
     }
     result
 }
+
+/// This function tries to guess the definition of a DB Table, printing the best candidates found.
+///
+/// If a PackFile is provided, the table path is a path inside it. Otherwise, it's a path on disk.
+pub fn guess(config: &Config, packfile: Option<&str>, table_path: &str, max_candidates: usize) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Guessing the definition of the table: {}", table_path);
+            }
+
+            let (table_name, data) = match packfile {
+                Some(packfile) => {
+                    let packfile = PackFile::open_packfiles(&[PathBuf::from(packfile)], true, false, false)?;
+                    let path = table_path.split('/').map(|x| x.to_owned()).collect::<Vec<String>>();
+                    let packed_file = packfile.get_ref_packed_file_by_path(&path).ok_or(ErrorKind::PackedFileNotFound)?;
+                    (path.get(1).cloned().unwrap_or_default(), packed_file.get_raw_data()?)
+                }
+                None => {
+                    let path = PathBuf::from(table_path);
+                    let mut data = vec![];
+                    BufReader::new(File::open(&path)?).read_to_end(&mut data)?;
+                    let table_name = path.parent().and_then(|x| x.file_name()).map(|x| x.to_string_lossy().to_string()).unwrap_or_default();
+                    (table_name, data)
+                }
+            };
+
+            let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
+            let previous_definitions = schema.get_ref_versioned_file_db(&table_name).map(|x| x.get_version_list().to_vec()).unwrap_or_default();
+            let candidates = DB::guess_definitions(&data, &previous_definitions)?;
+            if candidates.is_empty() {
+                return Err(ErrorKind::NoHTMLError("No valid definitions found.".to_owned()).into());
+            }
+
+            for (index, candidate) in candidates.iter().take(max_candidates).enumerate() {
+                let definition = candidate.get_ref_definition();
+                println!("Candidate {} (version {}, penalty {}):", index + 1, definition.get_version(), candidate.get_penalty());
+                for field in definition.get_ref_fields() {
+                    println!("    {}: {}", field.get_name(), field.get_ref_field_type());
+                }
+            }
+
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
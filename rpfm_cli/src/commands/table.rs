@@ -25,6 +25,7 @@ use crate::config::Config;
 /// This function imports a TSV file into a binary DB/Loc file.
 ///
 /// If no destination path was provided, it leaves the DB/Loc File in the same place as the tsv file, with the same name.
+/// TSV files from old versions of their table are migrated to the latest one, and the changes are printed.
 pub fn import_tsv(
     config: &Config,
     source_paths: &[&str],
@@ -38,9 +39,13 @@ pub fn import_tsv(
         Some(game_selected) => {
             let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
         	let source_paths = source_paths.iter().map(PathBuf::from).collect::<Vec<PathBuf>>();
-        	let result = DB::import_tsv_to_binary_file(&schema, &source_paths);
+        	let migrations = DB::import_tsv_to_binary_file(&schema, &source_paths)?;
+            for (path, migration) in &migrations {
+                print!("{}: {}", path.display(), migration);
+            }
+
             info!("All TSV files imported to binary.");
-            result
+            Ok(())
         },
         None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
     }
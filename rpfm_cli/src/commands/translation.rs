@@ -0,0 +1,143 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+//! This module contains the `Translation` command's functions.
+
+use log::{info, warn};
+use rayon::prelude::*;
+
+use std::fs::{read_to_string, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use rpfm_error::{ErrorKind, Result};
+use rpfm_lib::packedfile::PackedFileType;
+use rpfm_lib::packfile::PackFile;
+use rpfm_lib::schema::Schema;
+use rpfm_lib::translation::{Translation, TranslationFormat};
+use rpfm_lib::{GAME_SELECTED, SUPPORTED_GAMES};
+
+use crate::config::Config;
+
+//---------------------------------------------------------------------------//
+// 							Translation Command Variants
+//---------------------------------------------------------------------------//
+
+/// This function exports the Loc entries of the PackFile to a translation file, in the format of its extension.
+///
+/// If an existing translation file is provided, its translated entries are kept in the exported file.
+pub fn export(config: &Config, packfile: &str, path: &str, language: &str, existing_path: Option<&str>) -> Result<()> {
+    if config.verbosity_level > 0 {
+        info!("Exporting the Loc entries of the PackFile {} to: {}", packfile, path);
+    }
+
+    let format = TranslationFormat::from_path(Path::new(path))?;
+    let existing = match existing_path {
+        Some(existing_path) => {
+            let existing = Translation::read(&read_to_string(existing_path)?, TranslationFormat::from_path(Path::new(existing_path))?)?;
+            if !existing.get_language().is_empty() && existing.get_language() != language {
+                return Err(ErrorKind::NoHTMLError(format!("The existing translation file is for the language \"{}\", not \"{}\".", existing.get_language(), language)).into());
+            }
+            Some(existing)
+        }
+        None => None,
+    };
+
+    let translation = load_current_translation(config, packfile, language, existing.as_ref())?;
+    File::create(path)?.write_all(translation.save(format)?.as_bytes())?;
+
+    if config.verbosity_level > 0 {
+        info!("{} Loc entries exported.", translation.get_ref_entries().len());
+    }
+    Ok(())
+}
+
+/// This function imports the translated entries of a translation file into the Loc entries of the PackFile, and saves the PackFile.
+pub fn import(config: &Config, packfile: &str, path: &str) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            if config.verbosity_level > 0 {
+                info!("Importing the translation file {} into the PackFile: {}", path, packfile);
+            }
+
+            let translation = Translation::read(&read_to_string(path)?, TranslationFormat::from_path(Path::new(path))?)?;
+
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+            let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
+
+            let packfile_path = PathBuf::from(packfile);
+            let mut pack_file = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+            let keys_not_found = translation.import(&mut pack_file, &schema)?;
+            for key in &keys_not_found {
+                warn!("Key not found in the PackFile: {}", key);
+            }
+
+            pack_file.save(None)?;
+            if config.verbosity_level > 0 {
+                info!("Translation imported.");
+            }
+            Ok(())
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
+
+/// This function prints the Loc entries of the PackFile missing or outdated in each of the provided translation files.
+pub fn report(config: &Config, packfile: &str, paths: &[&str]) -> Result<()> {
+    if config.verbosity_level > 0 {
+        info!("Checking the translations of the PackFile: {}", packfile);
+    }
+
+    let current = load_current_translation(config, packfile, "", None)?;
+    for path in paths {
+        let translation = Translation::read(&read_to_string(path)?, TranslationFormat::from_path(Path::new(path))?)?;
+        let report = translation.get_report(&current);
+
+        println!("{} ({}): {} missing, {} outdated.", path, report.language, report.missing.len(), report.outdated.len());
+        for key in &report.missing {
+            println!("\tMissing\t{}", key);
+        }
+        for key in &report.outdated {
+            println!("\tOutdated\t{}", key);
+        }
+    }
+
+    Ok(())
+}
+
+/// This function returns a `Translation` with the current Loc entries of the PackFile.
+///
+/// If the vanilla english Loc entries cannot be loaded, the texts of the PackFile are used as source.
+/// Targets are only taken from the existing translation, if any.
+fn load_current_translation(config: &Config, packfile: &str, language: &str, existing: Option<&Translation>) -> Result<Translation> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+            let schema = Schema::load(&SUPPORTED_GAMES[&**game_selected].schema)?;
+
+            let packfile_path = PathBuf::from(packfile);
+            let mut pack_file = PackFile::open_packfiles(&[packfile_path], true, false, false)?;
+            pack_file.get_ref_mut_packed_files_by_type(PackedFileType::Loc, false).par_iter_mut().for_each(|x| {
+                let _ = x.decode_no_locks(&schema);
+            });
+
+            let source_texts = match Translation::get_source_texts(&schema) {
+                Ok(source_texts) => source_texts,
+                Err(error) => {
+                    warn!("Vanilla Loc entries could not be loaded, so the ones in the PackFile will be used as source: {}", error.to_terminal());
+                    Default::default()
+                }
+            };
+
+            Ok(Translation::new(&pack_file, &source_texts, language, existing))
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
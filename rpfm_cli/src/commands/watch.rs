@@ -0,0 +1,131 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+//! This module contains the `Watch` command's functions.
+
+use log::{info, warn};
+use notify::{DebouncedEvent, RecursiveMode, Watcher, watcher};
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+use rpfm_error::{ErrorKind, Result};
+use rpfm_lib::common::get_game_selected_data_path;
+use rpfm_lib::packfile::PackFile;
+use rpfm_lib::{GAME_SELECTED, SUPPORTED_GAMES};
+
+use crate::config::Config;
+
+/// Time we wait for a file to stop changing before updating the PackFile with it.
+const DEBOUNCE_TIME: Duration = Duration::from_millis(500);
+
+//---------------------------------------------------------------------------//
+// 							Watch Command Variants
+//---------------------------------------------------------------------------//
+
+/// This function watches the source folder, updating the PackFile with its changes and saving it to the destination folder.
+///
+/// If the PackFile doesn't exist, a new one is created. This function only returns on error.
+pub fn watch(config: &Config, packfile: &str, source: &str, destination: Option<&str>) -> Result<()> {
+    match &config.game_selected {
+        Some(game_selected) => {
+            *GAME_SELECTED.write().unwrap() = game_selected.to_owned();
+
+            let source = Path::new(source).canonicalize()?;
+            let packfile_path = PathBuf::from(packfile);
+            let file_name = match packfile_path.file_name() {
+                Some(file_name) => file_name.to_string_lossy().to_string(),
+                None => return Err(ErrorKind::PackFileIsNotAFile.into()),
+            };
+
+            let destination = match destination {
+                Some(destination) => PathBuf::from(destination).join(&file_name),
+                None => get_game_selected_data_path().ok_or(ErrorKind::GamePathNotConfigured)?.join(&file_name),
+            };
+
+            // Load the entire PackFile to memory, as we may be overwriting it.
+            let mut packfile = if packfile_path.is_file() { PackFile::open_packfiles(&[packfile_path], false, false, false)? }
+            else { PackFile::new_with_name(&file_name, SUPPORTED_GAMES[&**game_selected].pfh_version[0]) };
+
+            if config.verbosity_level > 0 {
+                info!("Watching the folder {} and saving the PackFile to: {}", source.to_string_lossy(), destination.to_string_lossy());
+            }
+
+            // Before watching, make sure the PackFile has the current state of the folder.
+            sync(config, &mut packfile, &source, &[source.to_path_buf()], &destination)?;
+
+            let (sender, receiver) = channel();
+            let mut watcher = watcher(sender, DEBOUNCE_TIME).map_err(|error| ErrorKind::NoHTMLError(error.to_string()))?;
+            watcher.watch(&source, RecursiveMode::Recursive).map_err(|error| ErrorKind::NoHTMLError(error.to_string()))?;
+
+            loop {
+
+                // Wait for the first change, then get all the changes that happened meanwhile, so we only save once.
+                let mut changed_paths = vec![];
+                let event = receiver.recv().map_err(|error| ErrorKind::NoHTMLError(error.to_string()))?;
+                add_event_paths(&mut changed_paths, event, &source);
+                while let Ok(event) = receiver.try_recv() {
+                    add_event_paths(&mut changed_paths, event, &source);
+                }
+
+                // Saving may fail if the game is using the PackFile, so don't stop watching if that happens.
+                if !changed_paths.is_empty() {
+                    if let Err(error) = sync(config, &mut packfile, &source, &changed_paths, &destination) {
+                        warn!("The PackFile could not be updated: {}", error.to_terminal());
+                    }
+                }
+            }
+        }
+        None => Err(ErrorKind::NoHTMLError("No Game Selected provided.".to_owned()).into()),
+    }
+}
+
+/// This function updates the PackFile with the changed paths, and saves it if anything changed.
+fn sync(config: &Config, packfile: &mut PackFile, source: &Path, changed_paths: &[PathBuf], destination: &Path) -> Result<()> {
+    let (updated_paths, removed_paths) = packfile.update_from_folder(source, changed_paths)?;
+    if updated_paths.is_empty() && removed_paths.is_empty() {
+        return Ok(());
+    }
+
+    packfile.save(Some(destination.to_path_buf()))?;
+    if config.verbosity_level > 0 {
+        for path in &updated_paths {
+            info!("Updated: {}", path.join("/"));
+        }
+        for path in &removed_paths {
+            info!("Removed: {}", path.join("/"));
+        }
+        info!("PackFile saved.");
+    }
+    Ok(())
+}
+
+/// This function adds the paths affected by the provided event to the list of changed paths.
+fn add_event_paths(changed_paths: &mut Vec<PathBuf>, event: DebouncedEvent, source: &Path) {
+    match event {
+        DebouncedEvent::Create(path) |
+        DebouncedEvent::Write(path) |
+        DebouncedEvent::Remove(path) => changed_paths.push(path),
+        DebouncedEvent::Rename(old_path, new_path) => {
+            changed_paths.push(old_path);
+            changed_paths.push(new_path);
+        }
+
+        // If we lost track of the changes, check the entire folder.
+        DebouncedEvent::Rescan => changed_paths.push(source.to_path_buf()),
+        DebouncedEvent::Error(error, _) => warn!("Error while watching the folder: {}", error),
+
+        // Notices are followed by their real events, and chmods do not change the data.
+        DebouncedEvent::NoticeWrite(_) |
+        DebouncedEvent::NoticeRemove(_) |
+        DebouncedEvent::Chmod(_) => {}
+    }
+}
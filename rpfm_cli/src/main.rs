@@ -67,7 +67,14 @@ fn main() {
     let result = match matches.subcommand() {
         ("packfile", Some(matches)) => commands::command_packfile(&config, matches, packfile),
         ("table", Some(matches)) => commands::command_table(&config, matches, packfile),
-        ("schema", Some(matches)) => commands::command_schema(&config, matches),
+        ("image", Some(matches)) => commands::command_image(&config, matches, packfile),
+        ("esf", Some(matches)) => commands::command_esf(&config, matches, packfile),
+        ("diagnostics", Some(matches)) => commands::command_diagnostics(&config, matches, packfile),
+        ("conflicts", Some(matches)) => commands::command_conflicts(&config, matches, packfile),
+        ("schema", Some(matches)) => commands::command_schema(&config, matches, packfile),
+        ("references", Some(matches)) => commands::command_references(&config, matches, packfile),
+        ("translation", Some(matches)) => commands::command_translation(&config, matches, packfile),
+        ("watch", Some(matches)) => commands::command_watch(&config, matches, packfile),
         _ => { Ok(()) }
     };
 
@@ -206,6 +206,15 @@ pub enum ErrorKind {
     /// Error for when the PackFile size doesn't match what we expect. Contains both, the real size and the expected size.
     PackFileSizeIsNotWhatWeExpect(u64, u64),
 
+    /// Error for when a table in a project folder cannot be rebuilt as it was. Contains the path of the table.
+    ProjectEntryMismatch(String),
+
+    /// Error for when a file of a project folder is stored as a table, but it's not a table. Contains the path of the file.
+    ProjectEntryIsNotATable(String),
+
+    /// Error for when the path of a file of a project folder points outside of it, or it's not a valid path. Contains the path of the file.
+    ProjectEntryInvalidPath(String),
+
     //--------------------------------//
     // Schema Errors
     //--------------------------------//
@@ -250,6 +259,9 @@ pub enum ErrorKind {
     /// Error for when the compressed PackedFile is either incomplete (<9 bytes) or the decompression failed.
     PackedFileDataCouldNotBeDecompressed,
 
+    /// Error for when the compression of a PackedFile fails, or the PackedFile is too big (+4GB) to be compressed.
+    PackedFileDataCouldNotBeCompressed,
+
     /// Error for when we expect data to be in memory, but it isn't.
     PackedFileDataIsNotInMemory,
 
@@ -265,11 +277,14 @@ pub enum ErrorKind {
     /// Error for when we cannot open a PackedFile due to not being decodeable on the lib.
     PackedFileTypeUnknown,
 
+    /// Error for when we try to serialize to JSON/RON a PackedFile that doesn't support it.
+    DecodedPackedFileNotSerializable,
+
     /// Error for when we replace the binary data of a PackedFile with another data that's not decodeable in the same way as the old data.
     NewDataIsNotDecodeableTheSameWayAsOldDAta,
 
-    /// Error for when the checksum of a PackedFile fails.
-    PackedFileChecksumFailed,
+    /// Error for when the PackFile a PackedFile's data comes from has been modified on disk since it was opened.
+    PackedFileSourceChanged,
 
     //--------------------------------//
     // Table Errors
@@ -370,6 +385,9 @@ pub enum ErrorKind {
     /// Error for when an Image fails to decode. Contains the error message.
     ImageDecode(String),
 
+    /// Error for when an Image fails to encode. Contains the error message.
+    ImageEncode(String),
+
     //--------------------------------//
     // CA_VP8 Errors
     //--------------------------------//
@@ -377,6 +395,58 @@ pub enum ErrorKind {
     /// Error for when a CaVp8 PackedFile fails to decode. Contains the error message.
     CaVp8Decode(String),
 
+    //--------------------------------//
+    // ESF Errors
+    //--------------------------------//
+
+    /// Error for when an ESF PackedFile fails to decode. Contains the error message.
+    ESFDecode(String),
+
+    /// Error for when an ESF PackedFile fails to encode. Contains the error message.
+    ESFEncode(String),
+
+    /// Error for when an ESF PackedFile fails to be imported from XML. Contains the error message.
+    ESFImport(String),
+
+    /// Error for when we try to decode an ESF PackedFile with an unsupported signature.
+    ESFUnsupportedSignature,
+
+    //--------------------------------//
+    // Lua Errors
+    //--------------------------------//
+
+    /// Error for when a Lua script has a syntax error. Contains the line, column and message of the error.
+    LuaSyntaxError(usize, usize, String),
+
+    /// Error for when the game selected has no `ca_types` file.
+    CATypesNotSupportedForThisGame,
+
+    //--------------------------------//
+    // Key Rename Errors
+    //--------------------------------//
+
+    /// Error for when we try to rename a key to an empty key, or to itself.
+    KeyRenameInvalidKey,
+
+    /// Error for when the key to rename is not in any table of the PackFile. Contains the key and the table.
+    KeyRenameKeyNotFound(String, String),
+
+    /// Error for when the new key is already in a table of the PackFile. Contains the key and the table.
+    KeyRenameKeyAlreadyExists(String, String),
+
+    //--------------------------------//
+    // Translation Errors
+    //--------------------------------//
+
+    /// Error for when a PO or XLIFF translation file fails to be imported. Contains the error message.
+    TranslationImport(String),
+
+    /// Error for when a XLIFF translation file fails to be exported. Contains the error message.
+    TranslationExport(String),
+
+    /// Error for when the format of a translation file cannot be guessed from its extension.
+    TranslationUnsupportedFormat,
+
     //--------------------------------//
     // AnimPack Errors
     //--------------------------------//
@@ -507,13 +577,6 @@ pub enum ErrorKind {
     /// Error for when we try to get the assembly kit, but it's not yet installed.
     AssemblyKitNotFound,
 
-    //-----------------------------------------------------//
-    //                  7-Zip Errors
-    //-----------------------------------------------------//
-
-    /// Error for when 7-zip is not found in the specified path.
-    ZipFolderNotFound,
-
     //-----------------------------------------------------//
     //                  Common Errors
     //-----------------------------------------------------//
@@ -710,12 +773,14 @@ impl Display for ErrorKind {
             <ul>
             <li>The PackFile's type is <i>'Boot'</i>, <i>'Release'</i>, <i>'Patch'</i> or <i>'Music'</i> and you have <i>'Allow edition of CA PackFiles'</i> disabled in the settings.</li>
             <li>The PackFile's type is <i>'Other'</i>.</li>
-            <li>One of the greyed checkboxes under <i>'PackFile/Change PackFile Type'</i> is checked.</li>
             </ul>
-            <p>If you really want to save it, go to <i>'PackFile/Change PackFile Type'</i> and change his type to 'Mod' or 'Movie'. Note that if the cause it's the third on the list, there is no way to save the PackFile, yet.</p>
+            <p>If you really want to save it, go to <i>'PackFile/Change PackFile Type'</i> and change his type to 'Mod' or 'Movie'.</p>
             <p><b>NOTE</b>: If you created this PackFile using the <i>'Load All CA PackedFiles'</i> feature, NEVER try to save it unless you have 64GB of ram or more. Otherwise it may hang your entire computer to dead.</p>"),
             ErrorKind::PackFileIsNotAPackFile => write!(f, "<p>This file is not a valid PackFile.</p>"),
             ErrorKind::PackFileIsNotAFile => write!(f, "<p>This PackFile doesn't exists as a file in the disk.</p>"),
+            ErrorKind::ProjectEntryMismatch(path) => write!(f, "<p>The following table cannot be rebuilt from its file in the project folder without changes: {}.</p>", path),
+            ErrorKind::ProjectEntryIsNotATable(path) => write!(f, "<p>The following file is stored as a table in the project folder, but it's not a table: {}.</p>", path),
+            ErrorKind::ProjectEntryInvalidPath(path) => write!(f, "<p>The following file has a path that is not valid within a project folder: {}.</p><p>Paths cannot be empty, absolute, or contain empty, <i>.</i> or <i>..</i> components.</p>", path),
             ErrorKind::PackFileSizeIsNotWhatWeExpect(reported_size, expected_size) => write!(f, "<p>This PackFile's reported size is <i><b>{}</b></i> bytes, but we expected it to be <i><b>{}</b></i> bytes. This means that either the decoding logic in RPFM is broken for this PackFile, or this PackFile is corrupted.</p>", reported_size, expected_size),
             ErrorKind::NewDataIsNotDecodeableTheSameWayAsOldDAta => write!(f, "<p>The PackedFile you added is not the same type as the one you had before. So... the view showing it will get closed.</p>"),
 
@@ -738,12 +803,14 @@ impl Display for ErrorKind {
             ErrorKind::PackedFileDataCouldNotBeLoaded => write!(f, "<p>This PackedFile's data could not be loaded. This means RPFM can no longer read the PackFile from the disk.</p>"),
             ErrorKind::PackedFileSizeIsNotWhatWeExpect(reported_size, expected_size) => write!(f, "<p>This PackedFile's reported size is <i><b>{}</b></i> bytes, but we expected it to be <i><b>{}</b></i> bytes. This means that either the decoding logic in RPFM is broken for this PackedFile, or this PackedFile is corrupted.</p>", reported_size, expected_size),
             ErrorKind::PackedFileDataCouldNotBeDecompressed => write!(f, "<p>This is a compressed file and the decompresion failed for some reason. This means this PackedFile cannot be opened in RPFM.</p>"),
+            ErrorKind::PackedFileDataCouldNotBeCompressed => write!(f, "<p>This PackedFile could not be compressed. Compressed PackedFiles cannot be bigger than 4GB.</p>"),
             ErrorKind::PackedFileDataIsNotInMemory => write!(f, "<p>This PackedFile's data is not in memory. If you see this, report it, as it's a bug.</p>"),
             ErrorKind::PackedFileNotInFilter => write!(f, "<p>This PackedFile is not in the current TreeView filter. If you want to open it, remove the filter.</p>"),
             ErrorKind::PackedFileCouldNotBeImported(paths) => write!(f, "<p>The following failed to be imported:<ul>{}</ul></p>", paths.iter().map(|x| format!("<li>{}<li>", x)).collect::<String>()),
             ErrorKind::PackedFileSaveError(path) => write!(f, "<p>The following PackedFile failed to be saved: {}</p>", path.join("/")),
             ErrorKind::PackedFileTypeUnknown => write!(f, "<p>The PackedFile could not be opened.</p>"),
-            ErrorKind::PackedFileChecksumFailed => write!(f, "<p>The PackedFile checksum failed. If you see this, please report it with the actions you did in RPFM before this happened.</p>"),
+            ErrorKind::DecodedPackedFileNotSerializable => write!(f, "<p>This type of PackedFile cannot be exported to or imported from JSON/RON.</p>"),
+            ErrorKind::PackedFileSourceChanged => write!(f, "<p>The PackFile this PackedFile comes from has been modified by another program since it was opened, so its data cannot be read. Close and open it again, then try again.</p>"),
 
             //--------------------------------//
             // Table Errors
@@ -793,12 +860,41 @@ impl Display for ErrorKind {
             // Image Errors
             //--------------------------------//
             ErrorKind::ImageDecode(cause) => write!(f, "<p>Error while trying to decode the Image PackedFile:</p><p>{}</p>", cause),
+            ErrorKind::ImageEncode(cause) => write!(f, "<p>Error while trying to encode the Image PackedFile:</p><p>{}</p>", cause),
 
             //--------------------------------//
             // CA_VP8 Errors
             //--------------------------------//
             ErrorKind::CaVp8Decode(cause) => write!(f, "<p>Error while trying to decode the CaVp8 PackedFile:</p><p>{}</p>", cause),
 
+            //--------------------------------//
+            // ESF Errors
+            //--------------------------------//
+            ErrorKind::ESFDecode(cause) => write!(f, "<p>Error while trying to decode the ESF PackedFile:</p><p>{}</p>", cause),
+            ErrorKind::ESFEncode(cause) => write!(f, "<p>Error while trying to encode the ESF PackedFile:</p><p>{}</p>", cause),
+            ErrorKind::ESFImport(cause) => write!(f, "<p>Error while trying to import the ESF PackedFile:</p><p>{}</p>", cause),
+            ErrorKind::ESFUnsupportedSignature => write!(f, "<p>This file is not a supported ESF file.</p>"),
+
+            //--------------------------------//
+            // Lua Errors
+            //--------------------------------//
+            ErrorKind::LuaSyntaxError(line, column, message) => write!(f, "<p>Syntax error in line {}, column {}: {}.</p>", line, column, message),
+            ErrorKind::CATypesNotSupportedForThisGame => write!(f, "<p>There is no <i>ca_types</i> file for this game.</p>"),
+
+            //--------------------------------//
+            // Key Rename Errors
+            //--------------------------------//
+            ErrorKind::KeyRenameInvalidKey => write!(f, "<p>The new key cannot be empty or equal to the current one.</p>"),
+            ErrorKind::KeyRenameKeyNotFound(key, table) => write!(f, "<p>The key <i>{}</i> is not in any <i>{}</i> table of the PackFile.</p>", key, table),
+            ErrorKind::KeyRenameKeyAlreadyExists(key, table) => write!(f, "<p>The key <i>{}</i> is already in a <i>{}</i> table of the PackFile.</p>", key, table),
+
+            //--------------------------------//
+            // Translation Errors
+            //--------------------------------//
+            ErrorKind::TranslationImport(cause) => write!(f, "<p>Error while trying to import the translation file:</p><p>{}</p>", cause),
+            ErrorKind::TranslationExport(cause) => write!(f, "<p>Error while trying to export the translation file:</p><p>{}</p>", cause),
+            ErrorKind::TranslationUnsupportedFormat => write!(f, "<p>This translation file is not supported. Only PO (<i>.po</i>) and XLIFF (<i>.xlf</i>, <i>.xliff</i>) files are supported.</p>"),
+
             //--------------------------------//
             // AnimPack Errors
             //--------------------------------//
@@ -871,11 +967,6 @@ impl Display for ErrorKind {
             ErrorKind::AssemblyKitTableTableIgnored => write!(f, "<p>One of the Assembly Kit Tables you tried to decode has been blacklisted due to issues.</p>"),
             ErrorKind::AssemblyKitNotFound => write!(f, "<p>The Assembly Kit Folder could not be read. You may need to install the Assembly Kit for the current game selected.</p>"),
 
-            //-----------------------------------------------------//
-            //                  7-Zip Errors
-            //-----------------------------------------------------//
-            ErrorKind::ZipFolderNotFound => write!(f, "<p>7Zip path not found, or the 7Zip path you put in the settings is wrong.</p>"),
-
             //-----------------------------------------------------//
             //                  Common Errors
             //-----------------------------------------------------//
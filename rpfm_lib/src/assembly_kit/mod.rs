@@ -21,7 +21,6 @@ To differentiate between the different types of Assembly Kit, there are multiple
 use rayon::prelude::*;
 use serde_xml_rs::from_reader;
 
-use std::borrow::BorrowMut;
 use std::fs::{File, DirBuilder, read_dir};
 use std::io::{BufReader, Write};
 use std::path::{Path, PathBuf};
@@ -31,9 +30,9 @@ use rpfm_error::{Result, ErrorKind};
 use crate::assembly_kit::table_definition::RawDefinition;
 use crate::assembly_kit::table_data::RawTable;
 use crate::assembly_kit::localisable_fields::RawLocalisableFields;
-use crate::{GAME_SELECTED, SCHEMA, SUPPORTED_GAMES};
-use crate::common::*;
+use crate::SCHEMA;
 use crate::config::get_config_path;
+use crate::context::GameContext;
 use crate::dependencies::Dependencies;
 use crate::packfile::PackFile;
 use crate::packedfile::table::db::DB;
@@ -76,14 +75,35 @@ pub fn generate_pak_file(
     raw_db_path: &PathBuf,
     version: i16,
     dependencies: &Dependencies
+) -> Result<()> {
+    generate_pak_file_for_game(raw_db_path, version, dependencies, &GameContext::from_game_selected()?)
+}
+
+/// This function generates a PAK (Processed Assembly Kit) file from the raw tables found in the provided path,
+/// for the game and with the dependencies of the provided `GameContext`.
+///
+/// Otherwise, it works like `generate_pak_file`.
+pub fn generate_pak_file_with_context(
+    raw_db_path: &PathBuf,
+    version: i16,
+    context: &GameContext
+) -> Result<()> {
+    generate_pak_file_for_game(raw_db_path, version, context.get_ref_dependencies(), context)
+}
+
+/// This function generates a PAK (Processed Assembly Kit) file for the game of the provided `GameContext`, using the provided dependencies.
+fn generate_pak_file_for_game(
+    raw_db_path: &PathBuf,
+    version: i16,
+    dependencies: &Dependencies,
+    context: &GameContext
 ) -> Result<()> {
     let (raw_tables, _) = RawTable::read_all(raw_db_path, version, true, dependencies)?;
     let tables: Vec<DB> = raw_tables.par_iter().map(From::from).collect();
 
     // Save our new PAK File where it should be.
     let mut pak_path = get_config_path()?;
-    let game_selected = GAME_SELECTED.read().unwrap();
-    let pak_name = SUPPORTED_GAMES.get(&**game_selected).unwrap().pak_file.clone().unwrap();
+    let pak_name = context.get_ref_game_info().pak_file.clone().ok_or(ErrorKind::PAKFileNotSupportedForThisGame)?;
     pak_path.push("pak_files");
 
     DirBuilder::new().recursive(true).create(&pak_path)?;
@@ -104,68 +124,82 @@ pub fn generate_pak_file(
 /// - This decodes localisable fields as proper localisable fiels, separating them from the rest.
 /// - This only updates the current versions of the tables, not older ones.
 pub fn update_schema_from_raw_files(ass_kit_path: Option<PathBuf>, dependencies: &Dependencies) -> Result<()> {
-    let mut schema_writable = SCHEMA.write().unwrap();
-    let schema_referenced: &mut Option<Schema> = schema_writable.borrow_mut();
-    if let Some(ref mut schema) = schema_referenced {
-
-        // This has to do a different process depending on the `raw_db_version`.
-        let raw_db_version = SUPPORTED_GAMES[&**GAME_SELECTED.read().unwrap()].raw_db_version;
-        match raw_db_version {
-            2 | 1 => {
-                if let Some(packfile_db_path) = get_game_selected_db_pack_path() {
-                    let packfile_db = PackFile::open_packfiles(&packfile_db_path, true, false, false)?;
-
-                    let mut ass_kit_schemas_path =
-                        if raw_db_version == 1 {
-                            if let Some(path) = ass_kit_path { path }
-                            else { return Err(ErrorKind::SchemaNotFound.into()) }
-                        }
-                        else if let Some(path) = get_game_selected_assembly_kit_path() { path }
-                        else { return Err(ErrorKind::SchemaNotFound.into()) };
-
-                    ass_kit_schemas_path.push("raw_data");
-                    ass_kit_schemas_path.push("db");
-
-                    // This one is notably missing in Warhammer 2, so it's optional.
-                    let raw_localisable_fields: Option<RawLocalisableFields> =
-                        if let Ok(file_path) = get_raw_localisable_fields_path(&ass_kit_schemas_path, raw_db_version) {
-                            let file = BufReader::new(File::open(&file_path)?);
-                            from_reader(file).ok()
-                        } else { None };
-
-                    let (raw_definitions, _) = RawDefinition::read_all(&ass_kit_schemas_path, raw_db_version, false, dependencies)?;
-                    schema.get_ref_mut_versioned_file_db_all().par_iter_mut().for_each(|versioned_file| {
-                        if let VersionedFile::DB(table_name, definitions) = versioned_file {
-                            let name = &table_name[0..table_name.len() - 7];
-                            if let Some(raw_definition) = raw_definitions.iter().filter(|x| x.name.is_some()).find(|x| &(x.name.as_ref().unwrap())[0..x.name.as_ref().unwrap().len() - 4] == name) {
-                                let mut vanilla_tables = packfile_db.get_packed_files_by_path_start(&["db".to_owned(), table_name.to_owned()]);
-                                if !vanilla_tables.is_empty() {
-                                    let vanilla_table = &mut vanilla_tables[0];
-                                    if let Ok(vanilla_table_data) = vanilla_table.get_raw_data_and_keep_it() {
-                                        if let Ok((version, _, _, _, _)) = DB::read_header(&vanilla_table_data) {
-                                            if let Some(ref mut definition) = definitions.iter_mut().find(|x| x.get_version() == version) {
-                                                definition.update_from_raw_definition(&raw_definition);
-                                                if let Some(ref raw_localisable_fields) = raw_localisable_fields {
-                                                    definition.update_from_raw_localisable_fields(&raw_definition, &raw_localisable_fields.fields)
-                                                }
+    let context = GameContext::from_game_selected()?;
+    match *SCHEMA.write().unwrap() {
+        Some(ref mut schema) => update_schema_for_game(ass_kit_path, schema, dependencies, &context),
+        None => Err(ErrorKind::SchemaNotFound.into()),
+    }
+}
+
+/// This function updates the Schema of the provided `GameContext` with the information of the provided Assembly Kit.
+///
+/// Otherwise, it works like `update_schema_from_raw_files`.
+pub fn update_schema_from_raw_files_with_context(ass_kit_path: Option<PathBuf>, context: &mut GameContext) -> Result<()> {
+    let mut schema = context.get_ref_schema().cloned().ok_or(ErrorKind::SchemaNotFound)?;
+    update_schema_for_game(ass_kit_path, &mut schema, context.get_ref_dependencies(), context)?;
+    context.set_schema(Some(schema));
+    Ok(())
+}
+
+/// This function updates the provided Schema with the information of the provided Assembly Kit,
+/// for the game of the provided `GameContext`, and saves it to disk.
+fn update_schema_for_game(ass_kit_path: Option<PathBuf>, schema: &mut Schema, dependencies: &Dependencies, context: &GameContext) -> Result<()> {
+
+    // This has to do a different process depending on the `raw_db_version`.
+    let raw_db_version = context.get_ref_game_info().raw_db_version;
+    match raw_db_version {
+        2 | 1 => {
+            if let Some(packfile_db_path) = context.get_db_pack_paths() {
+                let packfile_db = PackFile::open_packfiles_with_context(&packfile_db_path, true, false, false, context)?;
+
+                let mut ass_kit_schemas_path =
+                    if raw_db_version == 1 {
+                        if let Some(path) = ass_kit_path { path }
+                        else { return Err(ErrorKind::SchemaNotFound.into()) }
+                    }
+                    else if let Some(path) = context.get_assembly_kit_path() { path }
+                    else { return Err(ErrorKind::SchemaNotFound.into()) };
+
+                ass_kit_schemas_path.push("raw_data");
+                ass_kit_schemas_path.push("db");
+
+                // This one is notably missing in Warhammer 2, so it's optional.
+                let raw_localisable_fields: Option<RawLocalisableFields> =
+                    if let Ok(file_path) = get_raw_localisable_fields_path(&ass_kit_schemas_path, raw_db_version) {
+                        let file = BufReader::new(File::open(&file_path)?);
+                        from_reader(file).ok()
+                    } else { None };
+
+                let (raw_definitions, _) = RawDefinition::read_all(&ass_kit_schemas_path, raw_db_version, false, dependencies)?;
+                schema.get_ref_mut_versioned_file_db_all().par_iter_mut().for_each(|versioned_file| {
+                    if let VersionedFile::DB(table_name, definitions) = versioned_file {
+                        let name = &table_name[0..table_name.len() - 7];
+                        if let Some(raw_definition) = raw_definitions.iter().filter(|x| x.name.is_some()).find(|x| &(x.name.as_ref().unwrap())[0..x.name.as_ref().unwrap().len() - 4] == name) {
+                            let mut vanilla_tables = packfile_db.get_packed_files_by_path_start(&["db".to_owned(), table_name.to_owned()]);
+                            if !vanilla_tables.is_empty() {
+                                let vanilla_table = &mut vanilla_tables[0];
+                                if let Ok(vanilla_table_data) = vanilla_table.get_raw_data_and_keep_it() {
+                                    if let Ok((version, _, _, _, _)) = DB::read_header(&vanilla_table_data) {
+                                        if let Some(ref mut definition) = definitions.iter_mut().find(|x| x.get_version() == version) {
+                                            definition.update_from_raw_definition(&raw_definition);
+                                            if let Some(ref raw_localisable_fields) = raw_localisable_fields {
+                                                definition.update_from_raw_localisable_fields(&raw_definition, &raw_localisable_fields.fields)
                                             }
                                         }
                                     }
                                 }
                             }
                         }
-                    });
-                    schema.save(&SUPPORTED_GAMES[&**GAME_SELECTED.read().unwrap()].schema)?;
+                    }
+                });
+                schema.save(&context.get_ref_game_info().schema)?;
 
-                    Ok(())
-                }
-                else { Err(ErrorKind::GamePathNotConfigured.into()) }
+                Ok(())
             }
-            _ => { Err(ErrorKind::AssemblyKitUnsupportedVersion(raw_db_version).into()) }
+            else { Err(ErrorKind::GamePathNotConfigured.into()) }
         }
+        _ => { Err(ErrorKind::AssemblyKitUnsupportedVersion(raw_db_version).into()) }
     }
-
-    else { Err(ErrorKind::SchemaNotFound.into()) }
 }
 
 //---------------------------------------------------------------------------//
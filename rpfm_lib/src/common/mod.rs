@@ -16,7 +16,7 @@ Basically, if you need a function, but it's kinda a generic function, it goes he
 
 use chrono::{Utc, DateTime};
 
-use rpfm_error::{Error, ErrorKind, Result};
+use rpfm_error::{ErrorKind, Result};
 
 use std::fs::{File, read_dir};
 use std::path::{Path, PathBuf};
@@ -24,6 +24,7 @@ use std::path::{Path, PathBuf};
 use crate::template;
 use crate::schema;
 use crate::config::get_config_path;
+use crate::context::GameContext;
 use crate::GAME_SELECTED;
 use crate::{SETTINGS, SUPPORTED_GAMES};
 
@@ -115,115 +116,48 @@ pub fn get_files_in_folder_from_newest_to_oldest(current_path: &Path) -> Result<
 /// This function gets the `/data` path of the game selected, straighoutta settings, if it's configured.
 #[allow(dead_code)]
 pub fn get_game_selected_data_path() -> Option<PathBuf> {
-    let game_selected: &str = &*GAME_SELECTED.read().unwrap();
-    if let Some(path) = SETTINGS.read().unwrap().paths.get(game_selected) {
-        if let Some(path) = path {
-            Some(path.join(PathBuf::from("data")))
-        }
-        else { None }
-    } else { None }
+    GameContext::from_game_selected().ok()?.get_data_path()
 }
 
 /// This function gets the `/assembly_kit` path of the game selected, if supported and it's configured.
 #[allow(dead_code)]
 pub fn get_game_selected_assembly_kit_path() -> Option<PathBuf> {
-    let game_selected: &str = &*GAME_SELECTED.read().unwrap();
-    if let Some(path) = SETTINGS.read().unwrap().paths.get(game_selected) {
-        if let Some(path) = path {
-            Some(path.join(PathBuf::from("assembly_kit")))
-        }
-        else { None }
-    } else { None }
+    GameContext::from_game_selected().ok()?.get_assembly_kit_path()
 }
 
 /// This function gets the `/data/xxx.pack` paths of the PackFile with db tables of the game selected, straighoutta settings, if it's configured.
 #[allow(dead_code)]
 pub fn get_game_selected_db_pack_path() -> Option<Vec<PathBuf>> {
-    let game_selected: &str = &*GAME_SELECTED.read().unwrap();
-    let base_path = SETTINGS.read().unwrap().paths[game_selected].clone()?;
-    let db_packs = &SUPPORTED_GAMES.get(game_selected)?.db_packs;
-    let mut db_paths = vec![];
-    for pack in db_packs {
-        let mut path = base_path.to_path_buf();
-        path.push("data");
-        path.push(pack);
-        db_paths.push(path);
-    }
-    Some(db_paths)
+    GameContext::from_game_selected().ok()?.get_db_pack_paths()
 }
 
 /// This function gets the `/data/xxx.pack` paths of the PackFile with the loc files of the game selected, straighoutta settings, if it's configured.
 #[allow(dead_code)]
 pub fn get_game_selected_loc_pack_path() -> Option<Vec<PathBuf>> {
-    let game_selected: &str = &*GAME_SELECTED.read().unwrap();
-    let base_path = SETTINGS.read().unwrap().paths[game_selected].clone()?;
-    let loc_packs = &SUPPORTED_GAMES.get(game_selected)?.loc_packs;
-    let mut loc_paths = vec![];
-    for pack in loc_packs {
-        let mut path = base_path.to_path_buf();
-        path.push("data");
-        path.push(pack);
-        loc_paths.push(path);
-    }
-    Some(loc_paths)
+    GameContext::from_game_selected().ok()?.get_loc_pack_paths()
 }
 
 /// This function gets a list of all the PackFiles in the `/data` folder of the game straighoutta settings, if it's configured.
 #[allow(dead_code)]
 pub fn get_game_selected_data_packfiles_paths() -> Option<Vec<PathBuf>> {
-    let mut paths = vec![];
-    let data_path = get_game_selected_data_path()?;
-
-    for path in get_files_from_subdir(&data_path).ok()?.iter() {
-        match path.extension() {
-            Some(extension) => if extension == "pack" { paths.push(path.to_path_buf()); }
-            None => continue,
-        }
-    }
-
-    paths.sort();
-    Some(paths)
+    GameContext::from_game_selected().ok()?.get_data_packfiles_paths()
 }
 
 /// This function gets a list of all the PackFiles in the `content` folder of the game straighoutta settings, if it's configured.
 #[allow(dead_code)]
 pub fn get_game_selected_content_packfiles_paths() -> Option<Vec<PathBuf>> {
-    let game_selected: &str = &*GAME_SELECTED.read().unwrap();
-    let mut path = SETTINGS.read().unwrap().paths[game_selected].clone()?;
-    let id = SUPPORTED_GAMES.get(game_selected)?.steam_id?.to_string();
-
-    path.pop();
-    path.pop();
-    path.push("workshop");
-    path.push("content");
-    path.push(id);
-
-    let mut paths = vec![];
-
-    for path in get_files_from_subdir(&path).ok()?.iter() {
-        match path.extension() {
-            Some(extension) => if extension == "pack" { paths.push(path.to_path_buf()); }
-            None => continue,
-        }
-    }
-
-    paths.sort();
-    Some(paths)
+    GameContext::from_game_selected().ok()?.get_content_packfiles_paths()
 }
 
 /// This function gets the `/rpfm_path/pak_files/xxx.pak` path of the Game Selected, if it has one.
 #[allow(dead_code)]
 pub fn get_game_selected_pak_file() -> Result<PathBuf> {
-    let game_selected: &str = &*GAME_SELECTED.read().unwrap();
-    if let Some(pak_file) = &SUPPORTED_GAMES.get(game_selected).ok_or_else(|| Error::from(ErrorKind::GameNotSupported) )?.pak_file {
-        let mut base_path = get_config_path()?;
-        base_path.push("pak_files");
-        base_path.push(pak_file);
+    GameContext::from_game_selected()?.get_pak_file()
+}
 
-        if base_path.is_file() { Ok(base_path) }
-        else { Err(ErrorKind::IOFileNotFound.into()) }
-    }
-    else { Err(ErrorKind::PAKFileNotSupportedForThisGame.into()) }
+/// This function gets the `/rpfm_path/lua_types/xxx.lua` path of the Game Selected, if it has one.
+pub fn get_game_selected_ca_types_file() -> Result<PathBuf> {
+    GameContext::from_game_selected()?.get_ca_types_file()
 }
 
 /// This function gets the `/templates/definitions` path of the game selected, if they exists.
@@ -280,8 +214,7 @@ pub fn get_template_definitions_path() -> Result<PathBuf> {
 /// This function returns the template assets path.
 #[allow(dead_code)]
 pub fn get_template_assets_path() -> Result<PathBuf> {
-    let game_selected: &str = &*GAME_SELECTED.read().unwrap();
-    Ok(get_config_path()?.join(template::TEMPLATE_FOLDER.to_owned() + "/" + game_selected + "/" + template::ASSETS_FOLDER))
+    GameContext::from_game_selected()?.get_template_assets_path()
 }
 
 /// This function returns the custom template definition path.
@@ -0,0 +1,128 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `GameContext` module, to make sure different games can be used at the same time.
+!*/
+
+use std::collections::BTreeMap;
+
+use crate::games::{KEY_EMPIRE, KEY_WARHAMMER_2};
+use crate::global_search::GlobalSearch;
+use crate::packedfile::DecodedPackedFile;
+use crate::packedfile::table::DecodedData;
+use crate::packedfile::table::db::DB;
+use crate::packfile::packedfile::{PackedFile, RawPackedFile};
+use crate::packfile::{PackFile, PFHVersion};
+use crate::schema::{Definition, Field, FieldType, VersionedFile};
+
+use super::*;
+
+/// This function builds a context for the provided game, with a schema containing only a `units_tables` definition.
+fn build_context(game_key: &str) -> (GameContext, Definition) {
+    let mut schema = Schema::default();
+    let mut definition = Definition::new(2);
+    *definition.get_ref_mut_fields() = vec![
+        Field::new("key".to_owned(), FieldType::StringU8, true, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("value".to_owned(), FieldType::I32, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+    ];
+    schema.add_versioned_file(&VersionedFile::DB("units_tables".to_owned(), vec![definition.clone()]));
+
+    let mut context = GameContext::new(game_key, &Settings::new()).unwrap();
+    context.set_schema(Some(schema));
+    (context, definition)
+}
+
+#[test]
+fn test_game_context_new() {
+    assert!(GameContext::new("not_a_game", &Settings::new()).is_err());
+
+    let context = GameContext::new(KEY_EMPIRE, &Settings::new()).unwrap();
+    assert_eq!(context.get_ref_game_key(), KEY_EMPIRE);
+    assert_eq!(context.get_ref_game_info().pfh_version[0], PFHVersion::PFH0);
+    assert!(context.get_ref_schema().is_none());
+    assert!(context.get_data_path().is_none());
+}
+
+#[test]
+fn test_save_and_decode_with_context() {
+    let (mut context_wh2, definition) = build_context(KEY_WARHAMMER_2);
+    let (context_empire, _) = build_context(KEY_EMPIRE);
+
+    let mut db = DB::new("units_tables", None, &definition);
+    db.set_table_data(&[vec![DecodedData::StringU8("swords".to_owned()), DecodedData::I32(5)]]).unwrap();
+    let path = vec!["db".to_owned(), "units_tables".to_owned(), "my_mod".to_owned()];
+
+    // Empire tables have no GUID, while Warhammer 2 tables have it, and both can be encoded in the same program.
+    let data_empire = db.save_with_context(&context_empire).unwrap();
+    let data_wh2 = db.save_with_context(&context_wh2).unwrap();
+    assert!(data_empire.starts_with(&[252, 253, 254, 255]));
+    assert!(data_wh2.starts_with(&[253, 254, 252, 255]));
+    assert_ne!(DB::read_header(&data_wh2).unwrap().2, "");
+
+    // The UUID is only kept if the settings of the context say so.
+    db.set_table_data(&[vec![DecodedData::StringU8("swords".to_owned()), DecodedData::I32(5)]]).unwrap();
+    let mut packed_file = PackedFile::new_from_raw(&RawPackedFile::read_from_vec(path.to_vec(), String::new(), 0, false, data_wh2.to_vec()));
+    packed_file.decode_with_context(&context_wh2).unwrap();
+    context_wh2.get_ref_mut_settings().settings_bool.insert("disable_uuid_regeneration_on_db_tables".to_owned(), true);
+    packed_file.encode_with_context(&context_wh2).unwrap();
+    assert_eq!(packed_file.get_raw_data().unwrap(), data_wh2);
+
+    // Each context decodes with its own schema, so a context without schema cannot decode tables.
+    let mut packed_file = PackedFile::new_from_raw(&RawPackedFile::read_from_vec(path.to_vec(), String::new(), 0, false, data_empire));
+    match packed_file.decode_return_ref_with_context(&context_empire).unwrap() {
+        DecodedPackedFile::DB(table) => assert_eq!(table.get_table_data(), db.get_table_data()),
+        _ => panic!("The table has not been decoded as a DB Table."),
+    }
+
+    let context_no_schema = GameContext::new(KEY_WARHAMMER_2, &Settings::new()).unwrap();
+    let mut packed_file = PackedFile::new_from_raw(&RawPackedFile::read_from_vec(path, String::new(), 0, false, data_wh2));
+    assert!(packed_file.decode_with_context(&context_no_schema).is_err());
+}
+
+#[test]
+fn test_migrate_and_search_with_context() {
+    let (mut context, definition) = build_context(KEY_WARHAMMER_2);
+
+    // The table in the mod uses an older version, with only the key.
+    let mut definition_old = Definition::new(1);
+    *definition_old.get_ref_mut_fields() = vec![Field::new("key".to_owned(), FieldType::StringU8, true, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new())];
+    let mut schema = context.get_ref_schema().unwrap().clone();
+    schema.add_versioned_file(&VersionedFile::DB("units_tables".to_owned(), vec![definition.clone(), definition_old.clone()]));
+    context.set_schema(Some(schema));
+
+    let vanilla = DB::new("units_tables", None, &definition);
+    context.get_ref_mut_dependencies().get_ref_mut_dependency_database().push(PackedFile::new_from_decoded(&DecodedPackedFile::DB(vanilla), &["db".to_owned(), "units_tables".to_owned(), "data__".to_owned()]));
+
+    let mut db = DB::new("units_tables", None, &definition_old);
+    db.set_table_data(&[vec![DecodedData::StringU8("swords".to_owned())]]).unwrap();
+    let path = vec!["db".to_owned(), "units_tables".to_owned(), "my_mod".to_owned()];
+    let data = db.save_with_context(&context).unwrap();
+
+    // The table is decoded and migrated with the schema and dependencies of the context, not the global ones.
+    let mut packed_file = PackedFile::new_from_raw(&RawPackedFile::read_from_vec(path.to_vec(), String::new(), 0, false, data.to_vec()));
+    packed_file.decode_with_context(&context).unwrap();
+    let migration = packed_file.get_ref_mut_decoded().migrate_table_with_context(&context).unwrap();
+    assert_eq!((migration.old_version, migration.new_version), (1, 2));
+    assert_eq!(migration.added_fields, vec!["value".to_owned()]);
+
+    // Same with the global search: the table is decoded with the schema of the context.
+    let mut pack_file = PackFile::new_with_name("my_mod.pack", PFHVersion::PFH5);
+    pack_file.add_packed_file(&PackedFile::new_from_raw(&RawPackedFile::read_from_vec(path.to_vec(), String::new(), 0, false, data)), false).unwrap();
+    let mut global_search = GlobalSearch { pattern: "swords".to_owned(), ..Default::default() };
+    global_search.search_with_context(&mut pack_file, &context);
+    assert_eq!(global_search.matches_db.len(), 1);
+    assert_eq!(global_search.matches_db[0].path, path);
+    assert_eq!(global_search.matches_db[0].matches.len(), 1);
+
+    let context_no_schema = GameContext::new(KEY_WARHAMMER_2, &Settings::new()).unwrap();
+    global_search.search_with_context(&mut pack_file, &context_no_schema);
+    assert!(global_search.matches_db.is_empty());
+}
@@ -0,0 +1,254 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with all the code related to the `GameContext`.
+
+A `GameContext` contains everything the lib needs to know about the game we're working with: its info, its schema,
+its dependencies and the settings used to find its files. It's meant to be passed explicitly to the functions that need it,
+so you can work with more than one game at the same time in the same program.
+
+The `GAME_SELECTED`, `SCHEMA` and `SETTINGS` globals are still supported, but they're only a compatibility layer
+over this: the functions that use them just build a `GameContext` from them, or use the same code this module uses.
+!*/
+
+use rayon::prelude::*;
+
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use rpfm_error::{Error, ErrorKind, Result};
+
+use crate::{GAME_SELECTED, SCHEMA, SETTINGS, SUPPORTED_GAMES};
+use crate::common::get_files_from_subdir;
+use crate::config::get_config_path;
+use crate::dependencies::Dependencies;
+use crate::games::GameInfo;
+use crate::packedfile::table::db::DB;
+use crate::packedfile::text::lua::CATypes;
+use crate::packfile::PackFile;
+use crate::schema::Schema;
+use crate::settings::Settings;
+use crate::template;
+
+#[cfg(test)]
+mod context_test;
+
+//---------------------------------------------------------------------------//
+//                              Enum & Structs
+//---------------------------------------------------------------------------//
+
+/// This struct contains all the data related to the game we're working with.
+#[derive(Debug, Clone)]
+pub struct GameContext {
+
+    /// The key of the game, as used in `SUPPORTED_GAMES`.
+    game_key: String,
+
+    /// The info of the game.
+    game_info: GameInfo,
+
+    /// The schema of the game, if it has been loaded.
+    schema: Option<Schema>,
+
+    /// The dependencies of the PackFile we're working with.
+    dependencies: Dependencies,
+
+    /// The settings used to find the files of the game.
+    settings: Settings,
+}
+
+//---------------------------------------------------------------------------//
+//                           Implementations
+//---------------------------------------------------------------------------//
+
+/// Implementation of `GameContext`.
+impl GameContext {
+
+    /// This function creates a new `GameContext` for the provided game, without schema or dependencies.
+    pub fn new(game_key: &str, settings: &Settings) -> Result<Self> {
+        let game_info = SUPPORTED_GAMES.get(game_key).ok_or_else(|| Error::from(ErrorKind::GameNotSupported))?.clone();
+        Ok(Self {
+            game_key: game_key.to_owned(),
+            game_info,
+            schema: None,
+            dependencies: Dependencies::default(),
+            settings: settings.clone(),
+        })
+    }
+
+    /// This function creates a new `GameContext` from the `GAME_SELECTED`, `SCHEMA` and `SETTINGS` globals.
+    ///
+    /// The schema is cloned, so try to not call this in loops.
+    pub fn from_globals() -> Result<Self> {
+        let mut context = Self::from_game_selected()?;
+        context.schema = SCHEMA.read().unwrap().clone();
+        Ok(context)
+    }
+
+    /// This function creates a new `GameContext` from the `GAME_SELECTED` and `SETTINGS` globals, without schema.
+    ///
+    /// Used by the functions that still work over the globals.
+    pub(crate) fn from_game_selected() -> Result<Self> {
+        Self::new(&GAME_SELECTED.read().unwrap(), &SETTINGS.read().unwrap())
+    }
+
+    /// This function returns the key of the game of the `GameContext`.
+    pub fn get_ref_game_key(&self) -> &str {
+        &self.game_key
+    }
+
+    /// This function returns the info of the game of the `GameContext`.
+    pub fn get_ref_game_info(&self) -> &GameInfo {
+        &self.game_info
+    }
+
+    /// This function returns the schema of the `GameContext`, if it has one.
+    pub fn get_ref_schema(&self) -> Option<&Schema> {
+        self.schema.as_ref()
+    }
+
+    /// This function sets the schema of the `GameContext`.
+    pub fn set_schema(&mut self, schema: Option<Schema>) {
+        self.schema = schema;
+    }
+
+    /// This function loads the schema of the game of the `GameContext` from disk.
+    pub fn load_schema(&mut self) -> Result<()> {
+        self.schema = Some(Schema::load(&self.game_info.schema)?);
+        Ok(())
+    }
+
+    /// This function returns the dependencies of the `GameContext`.
+    pub fn get_ref_dependencies(&self) -> &Dependencies {
+        &self.dependencies
+    }
+
+    /// This function returns a mutable reference to the dependencies of the `GameContext`.
+    pub fn get_ref_mut_dependencies(&mut self) -> &mut Dependencies {
+        &mut self.dependencies
+    }
+
+    /// This function sets the dependencies of the `GameContext`.
+    pub fn set_dependencies(&mut self, dependencies: Dependencies) {
+        self.dependencies = dependencies;
+    }
+
+    /// This function returns the settings of the `GameContext`.
+    pub fn get_ref_settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// This function returns a mutable reference to the settings of the `GameContext`.
+    pub fn get_ref_mut_settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    /// This function rebuilds the dependencies of the `GameContext` for a PackFile with the provided dependency list.
+    ///
+    /// If the `GameContext` has a schema, the DB and Loc PackedFiles of the dependencies are decoded with it.
+    pub fn rebuild_dependencies(&mut self, pack_files: &[String]) {
+        let mut dependencies = Dependencies::default();
+        let mut real_dep_db = PackFile::load_all_dependency_packfiles_with_context(pack_files, self);
+        if let Some(ref schema) = self.schema {
+            real_dep_db.par_iter_mut().for_each(|x| {
+                let _ = x.decode_no_locks(schema);
+            });
+        }
+
+        *dependencies.get_ref_mut_dependency_database() = real_dep_db;
+        *dependencies.get_ref_mut_fake_dependency_database() = self.get_pak_file().map(|x| DB::read_pak_file_from_path(&x)).unwrap_or_default();
+        *dependencies.get_ref_mut_packed_file_paths() = PackFile::load_all_dependency_packed_file_paths_with_context(pack_files, self);
+        *dependencies.get_ref_mut_ca_types() = self.get_ca_types_file().and_then(|x| Ok(CATypes::read(&read_to_string(x)?))).ok();
+        self.dependencies = dependencies;
+    }
+
+    /// This function gets the path of the game, if it's configured.
+    pub fn get_game_path(&self) -> Option<PathBuf> {
+        self.settings.paths.get(&self.game_key).cloned().flatten()
+    }
+
+    /// This function gets the `/data` path of the game, if it's configured.
+    pub fn get_data_path(&self) -> Option<PathBuf> {
+        Some(self.get_game_path()?.join("data"))
+    }
+
+    /// This function gets the `/assembly_kit` path of the game, if it's configured.
+    pub fn get_assembly_kit_path(&self) -> Option<PathBuf> {
+        Some(self.get_game_path()?.join("assembly_kit"))
+    }
+
+    /// This function gets the `/data/xxx.pack` paths of the PackFiles with db tables of the game, if it's configured.
+    pub fn get_db_pack_paths(&self) -> Option<Vec<PathBuf>> {
+        let data_path = self.get_data_path()?;
+        Some(self.game_info.db_packs.iter().map(|x| data_path.join(x)).collect())
+    }
+
+    /// This function gets the `/data/xxx.pack` paths of the PackFiles with the loc files of the game, if it's configured.
+    pub fn get_loc_pack_paths(&self) -> Option<Vec<PathBuf>> {
+        let data_path = self.get_data_path()?;
+        Some(self.game_info.loc_packs.iter().map(|x| data_path.join(x)).collect())
+    }
+
+    /// This function gets a list of all the PackFiles in the `/data` folder of the game, if it's configured.
+    pub fn get_data_packfiles_paths(&self) -> Option<Vec<PathBuf>> {
+        let data_path = self.get_data_path()?;
+        let mut paths = get_files_from_subdir(&data_path).ok()?.into_iter()
+            .filter(|x| x.extension().map(|x| x == "pack").unwrap_or(false))
+            .collect::<Vec<PathBuf>>();
+
+        paths.sort();
+        Some(paths)
+    }
+
+    /// This function gets a list of all the PackFiles in the `content` folder of the game, if it's configured.
+    pub fn get_content_packfiles_paths(&self) -> Option<Vec<PathBuf>> {
+        let mut path = self.get_game_path()?;
+        let id = self.game_info.steam_id?.to_string();
+
+        path.pop();
+        path.pop();
+        path.push("workshop");
+        path.push("content");
+        path.push(id);
+
+        let mut paths = get_files_from_subdir(&path).ok()?.into_iter()
+            .filter(|x| x.extension().map(|x| x == "pack").unwrap_or(false))
+            .collect::<Vec<PathBuf>>();
+
+        paths.sort();
+        Some(paths)
+    }
+
+    /// This function gets the `/rpfm_path/pak_files/xxx.pak` path of the game, if it has one.
+    pub fn get_pak_file(&self) -> Result<PathBuf> {
+        if let Some(pak_file) = &self.game_info.pak_file {
+            let path = get_config_path()?.join("pak_files").join(pak_file);
+            if path.is_file() { Ok(path) }
+            else { Err(ErrorKind::IOFileNotFound.into()) }
+        }
+        else { Err(ErrorKind::PAKFileNotSupportedForThisGame.into()) }
+    }
+
+    /// This function gets the `/rpfm_path/lua_types/xxx.lua` path of the game, if it has one.
+    pub fn get_ca_types_file(&self) -> Result<PathBuf> {
+        if let Some(ca_types_file) = &self.game_info.ca_types_file {
+            let path = get_config_path()?.join("lua_types").join(format!("{}.lua", ca_types_file));
+            if path.is_file() { Ok(path) }
+            else { Err(ErrorKind::IOFileNotFound.into()) }
+        }
+        else { Err(ErrorKind::CATypesNotSupportedForThisGame.into()) }
+    }
+
+    /// This function returns the template assets path of the game.
+    pub fn get_template_assets_path(&self) -> Result<PathBuf> {
+        Ok(get_config_path()?.join(template::TEMPLATE_FOLDER).join(&self.game_key).join(template::ASSETS_FOLDER))
+    }
+}
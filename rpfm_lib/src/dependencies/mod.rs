@@ -14,8 +14,11 @@ Module with all the code related to the `Dependencies`.
 This module contains the code needed to manage the dependencies of the currently open PackFile.
 !*/
 
+use std::collections::HashSet;
+
 use crate::DB;
 use crate::PackedFile;
+use crate::packedfile::text::lua::CATypes;
 
 //-------------------------------------------------------------------------------//
 //                              Enums & Structs
@@ -30,6 +33,12 @@ pub struct Dependencies {
 
     /// DB Files from the Pak File of the current game. Only for dependency checking, do not use it as base for new tables.
     fake_dependency_database: Vec<DB>,
+
+    /// Paths of all the PackedFiles in the vanilla PackFiles and the dependencies of the currently open PackFile, lowercased and joined with `/`.
+    packed_file_paths: HashSet<String>,
+
+    /// Definitions of the Lua API of the current game, if it has a `ca_types` file.
+    ca_types: Option<CATypes>,
 }
 
 //---------------------------------------------------------------p----------------//
@@ -54,4 +63,20 @@ impl Dependencies {
     pub fn get_ref_mut_fake_dependency_database(&mut self) -> &mut Vec<DB> {
         &mut self.fake_dependency_database
     }
+
+    pub fn get_ref_packed_file_paths(&self) -> &HashSet<String> {
+        &self.packed_file_paths
+    }
+
+    pub fn get_ref_mut_packed_file_paths(&mut self) -> &mut HashSet<String> {
+        &mut self.packed_file_paths
+    }
+
+    pub fn get_ref_ca_types(&self) -> &Option<CATypes> {
+        &self.ca_types
+    }
+
+    pub fn get_ref_mut_ca_types(&mut self) -> &mut Option<CATypes> {
+        &mut self.ca_types
+    }
 }
@@ -0,0 +1,235 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `Diagnostics` module, to make sure the exported results are correct.
+!*/
+
+use super::*;
+
+/// This function builds a `Diagnostics` with a few results of different types and levels.
+fn build_diagnostics() -> Diagnostics {
+    let mut table = TableDiagnostic::new(&["db".to_owned(), "units_tables".to_owned(), "my_mod".to_owned()]);
+    table.get_ref_mut_result().push(TableDiagnosticReport {
+        column_number: 2,
+        row_number: 4,
+        message: "Invalid reference \"x\" in column \"unit\".".to_owned(),
+        report_type: TableDiagnosticReportType::InvalidReference,
+        level: DiagnosticLevel::Error,
+    });
+    table.get_ref_mut_result().push(TableDiagnosticReport {
+        column_number: 1,
+        row_number: -1,
+        message: "No reference table found for column \"unit\".".to_owned(),
+        report_type: TableDiagnosticReportType::NoReferenceTableFound,
+        level: DiagnosticLevel::Info,
+    });
+
+    let mut dependency_manager = DependencyManagerDiagnostic::new();
+    dependency_manager.get_ref_mut_result().push(DependencyManagerDiagnosticReport {
+        column_number: 0,
+        row_number: 0,
+        message: "Invalid dependency PackFile name: my mod".to_owned(),
+        report_type: DependencyManagerDiagnosticReportType::InvalidDependencyPackFileName,
+        level: DiagnosticLevel::Warning,
+    });
+
+    let mut diagnostics = Diagnostics::default();
+    diagnostics.get_ref_mut_diagnostics().push(DiagnosticType::DB(table));
+    diagnostics.get_ref_mut_diagnostics().push(DiagnosticType::DependencyManager(dependency_manager));
+    diagnostics
+}
+
+#[test]
+fn test_diagnostics_entries() {
+    let entries = build_diagnostics().get_entries();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0], DiagnosticEntry {
+        path: "db/units_tables/my_mod".to_owned(),
+        diagnostic_type: "DB".to_owned(),
+        report_type: "InvalidReference".to_owned(),
+        level: DiagnosticLevel::Error,
+        message: "Invalid reference \"x\" in column \"unit\".".to_owned(),
+        row: Some(5),
+        column: Some(3),
+    });
+    assert_eq!((entries[1].row, entries[1].column), (None, None));
+    assert_eq!((entries[2].path.as_str(), entries[2].row), ("", Some(1)));
+
+    assert!(DiagnosticLevel::Error > DiagnosticLevel::Warning);
+    assert!(DiagnosticLevel::Warning > DiagnosticLevel::Info);
+    assert_eq!(entries.iter().filter(|x| x.level >= DiagnosticLevel::Warning).count(), 2);
+}
+
+#[test]
+fn test_diagnostics_sarif() {
+    let sarif = build_diagnostics().to_sarif("my_mod.pack").unwrap();
+    let sarif: serde_json::Value = serde_json::from_str(&sarif).unwrap();
+    assert_eq!(sarif["version"], "2.1.0");
+
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 3);
+
+    let result = &run["results"][0];
+    assert_eq!(result["ruleId"], "InvalidReference");
+    assert_eq!(result["level"], "error");
+    assert_eq!(result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "my_mod.pack/db/units_tables/my_mod");
+    assert_eq!(result["locations"][0]["physicalLocation"]["region"]["startLine"], 5);
+    assert_eq!(result["locations"][0]["physicalLocation"]["region"]["startColumn"], 3);
+
+    let result = &run["results"][2];
+    assert_eq!(result["level"], "warning");
+    assert_eq!(result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "my_mod.pack");
+}
+
+/// Test to make sure the constraints of the fields in the schema are checked.
+#[test]
+fn test_diagnostics_field_constraints() {
+    use std::collections::BTreeMap;
+    use crate::schema::{Definition, Field};
+
+    let mut enum_values = BTreeMap::new();
+    enum_values.insert(0, "land".to_owned());
+    enum_values.insert(1, "sea".to_owned());
+
+    // The data is saved without the bitwise and enum info, so we can use values not allowed by them.
+    let mut raw_definition = Definition::new(1);
+    *raw_definition.get_ref_mut_fields() = vec![
+        Field::new("key".to_owned(), FieldType::StringU8, true, None, 5, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("kind".to_owned(), FieldType::I32, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("flag".to_owned(), FieldType::StringU8, false, None, 0, true, Some("ui/flags".to_owned()), None, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("flags".to_owned(), FieldType::I32, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+    ];
+
+    let mut fields = raw_definition.get_ref_fields().to_vec();
+    fields[1] = Field::new("kind".to_owned(), FieldType::I32, false, None, 0, false, None, None, None, String::new(), 0, 0, enum_values);
+    fields[3].set_is_bitwise(2);
+
+    let mut raw_table = DB::new("units_tables", None, &raw_definition);
+    raw_table.set_table_data(&[
+        vec![DecodedData::StringU8("emp".to_owned()), DecodedData::I32(0), DecodedData::StringU8("emp.png".to_owned()), DecodedData::I32(1)],
+        vec![DecodedData::StringU8("too_long".to_owned()), DecodedData::I32(5), DecodedData::StringU8("missing.png".to_owned()), DecodedData::I32(6)],
+        vec![DecodedData::StringU8("dwf".to_owned()), DecodedData::I32(1), DecodedData::StringU8("DWF.png".to_owned()), DecodedData::I32(3)],
+    ]).unwrap();
+    let raw_data = raw_table.save().unwrap();
+    let table = DB::read_with_fields(&raw_data, "units_tables", &fields, false).unwrap();
+
+    let path = vec!["db".to_owned(), "units_tables".to_owned(), "my_mod".to_owned()];
+    let mut packed_file = PackedFile::new_from_decoded(&DecodedPackedFile::DB(table), &path);
+    packed_file.get_ref_mut_raw().set_data(&raw_data);
+
+    let mut pack_file = PackFile::new();
+    pack_file.add_packed_file(&packed_file, false).unwrap();
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::Unknown, &["ui".to_owned(), "flags".to_owned(), "emp.png".to_owned()]), false).unwrap();
+
+    let mut dependencies = Dependencies::default();
+    dependencies.get_ref_mut_packed_file_paths().insert("ui/flags/dwf.png".to_owned());
+
+    let mut diagnostics = Diagnostics::default();
+    diagnostics.check(&pack_file, &dependencies);
+    let entries = diagnostics.get_entries().into_iter().filter(|x| x.diagnostic_type == "DB").collect::<Vec<DiagnosticEntry>>();
+    let results = entries.iter().map(|x| (x.report_type.as_str(), x.row, x.column)).collect::<Vec<(&str, Option<u64>, Option<u32>)>>();
+    assert_eq!(results.len(), 4);
+    assert!(results.contains(&("InvalidBitwiseValue", Some(2), Some(4))));
+    assert!(results.contains(&("FieldMaxLengthExceeded", Some(2), Some(1))));
+    assert!(results.contains(&("InvalidEnumValue", Some(2), Some(2))));
+    assert!(results.contains(&("FileNotFound", Some(2), Some(3))));
+
+    // Without dependencies we cannot know if the files exist, so they're not checked.
+    diagnostics.check(&pack_file, &Dependencies::default());
+    assert!(diagnostics.get_entries().iter().all(|x| x.report_type != "FileNotFound"));
+}
+
+#[test]
+fn test_diagnostics_lua() {
+    use crate::schema::{Definition, Field};
+
+    let mut definition = Definition::new(1);
+    *definition.get_ref_mut_fields() = vec![
+        Field::new("key".to_owned(), FieldType::StringU8, true, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+    ];
+
+    let mut factions = DB::new("factions_tables", None, &definition);
+    factions.set_table_data(&[vec![DecodedData::StringU8("wh_main_emp_empire".to_owned())]]).unwrap();
+
+    let mut pack_file = PackFile::new();
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::DB(factions), &["db".to_owned(), "factions_tables".to_owned(), "my_mod".to_owned()]), false).unwrap();
+
+    let add_lua = |pack_file: &mut PackFile, name: &str, contents: &str| {
+        let mut text = Text::new();
+        text.set_text_type(TextType::Lua);
+        text.set_contents(contents);
+        let path = vec!["script".to_owned(), "campaign".to_owned(), "mod".to_owned(), name.to_owned()];
+        pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::Text(text), &path), false).unwrap();
+    };
+
+    add_lua(&mut pack_file, "broken.lua", "local function test()\n    out('a')\n");
+    add_lua(&mut pack_file, "my_mod.lua", r#"
+        local faction = cm:model():faction_for_key("wh_main_emp_empire")
+        cm:model():faction_for_key("wh_main_emp_empiree")
+        cm:model():get_faction("wh_main_emp_empire")
+        out()
+        out(unpack(args))
+        cm.model(cm)
+    "#);
+
+    let mut dependencies = Dependencies::default();
+    *dependencies.get_ref_mut_ca_types() = Some(CATypes::read(r#"
+        --# assume global class CM
+        --# assume global class CA_MODEL
+        --# assume global cm: CM
+        --# assume global out: function(text: string)
+        --# assume CM.model: method() --> CA_MODEL
+        --# assume CA_MODEL.faction_for_key: method(faction_key: string) --> CA_FACTION
+    "#));
+
+    // Unknown functions of known classes are only reported if enabled in the PackFile Settings.
+    let mut diagnostics = Diagnostics::default();
+    diagnostics.check(&pack_file, &dependencies);
+    let entries = diagnostics.get_entries().into_iter().filter(|x| x.diagnostic_type == "Lua").collect::<Vec<DiagnosticEntry>>();
+    let results = entries.iter().map(|x| (x.path.as_str(), x.report_type.as_str(), x.row, x.column)).collect::<Vec<(&str, &str, Option<u64>, Option<u32>)>>();
+    assert_eq!(results, vec![
+        ("script/campaign/mod/broken.lua", "SyntaxError", Some(3), Some(1)),
+        ("script/campaign/mod/my_mod.lua", "InvalidKey", Some(3), Some(20)),
+        ("script/campaign/mod/my_mod.lua", "InvalidArgumentCount", Some(5), Some(9)),
+    ]);
+
+    let mut settings = pack_file.get_settings().clone();
+    settings.settings_bool.insert("diagnostics_check_undefined_lua_functions".to_owned(), true);
+    pack_file.set_settings(&settings);
+    diagnostics.check(&pack_file, &dependencies);
+    let entries = diagnostics.get_entries().into_iter().filter(|x| x.diagnostic_type == "Lua").collect::<Vec<DiagnosticEntry>>();
+    let results = entries.iter().map(|x| (x.path.as_str(), x.report_type.as_str(), x.row, x.column)).collect::<Vec<(&str, &str, Option<u64>, Option<u32>)>>();
+    assert_eq!(results, vec![
+        ("script/campaign/mod/broken.lua", "SyntaxError", Some(3), Some(1)),
+        ("script/campaign/mod/my_mod.lua", "InvalidKey", Some(3), Some(20)),
+        ("script/campaign/mod/my_mod.lua", "UndefinedFunction", Some(4), Some(20)),
+        ("script/campaign/mod/my_mod.lua", "InvalidArgumentCount", Some(5), Some(9)),
+    ]);
+
+    // Without the ca_types file only syntax errors can be found.
+    diagnostics.check(&pack_file, &Dependencies::default());
+    assert!(diagnostics.get_entries().iter().filter(|x| x.diagnostic_type == "Lua").all(|x| x.report_type == "SyntaxError"));
+}
+
+/// Test to make sure only parameters named after a key type are checked for keys.
+#[test]
+fn test_diagnostics_lua_key_parameters() {
+    assert!(Diagnostics::is_key_parameter("faction", "faction"));
+    assert!(Diagnostics::is_key_parameter("faction_key", "faction"));
+    assert!(Diagnostics::is_key_parameter("target_faction_key", "faction"));
+    assert!(Diagnostics::is_key_parameter("factionKey", "faction"));
+    assert!(Diagnostics::is_key_parameter("unitID", "unit"));
+    assert!(Diagnostics::is_key_parameter("units", "unit"));
+    assert!(!Diagnostics::is_key_parameter("community", "unit"));
+    assert!(!Diagnostics::is_key_parameter("opportunity", "unit"));
+    assert!(!Diagnostics::is_key_parameter("unitkey", "unit"));
+    assert!(!Diagnostics::is_key_parameter("subfaction", "faction"));
+}
@@ -0,0 +1,85 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with all the code related to the Lua diagnostics.
+
+This module contains the structs used to report the problems found in the Lua scripts of a `PackFile`.
+!*/
+
+use std::{fmt, fmt::Display};
+
+use super::DiagnosticLevel;
+
+//-------------------------------------------------------------------------------//
+//                              Enums & Structs
+//-------------------------------------------------------------------------------//
+
+/// This struct contains the results of a diagnostics check over a single Lua script.
+#[derive(Debug, Clone)]
+pub struct LuaDiagnostic {
+    path: Vec<String>,
+    result: Vec<LuaDiagnosticReport>
+}
+
+/// This struct defines an individual diagnostic result.
+#[derive(Debug, Clone)]
+pub struct LuaDiagnosticReport {
+    pub line: u32,
+    pub column: u32,
+    pub message: String,
+    pub report_type: LuaDiagnosticReportType,
+    pub level: DiagnosticLevel,
+}
+
+#[derive(Debug, Clone)]
+pub enum LuaDiagnosticReportType {
+    SyntaxError,
+    UndefinedFunction,
+    InvalidArgumentCount,
+    InvalidKey,
+}
+
+//---------------------------------------------------------------p----------------//
+//                             Implementations
+//-------------------------------------------------------------------------------//
+
+/// Implementation of `LuaDiagnostic`.
+impl LuaDiagnostic {
+    pub fn new(path: &[String]) -> Self {
+        Self {
+            path: path.to_vec(),
+            result: vec![],
+        }
+    }
+
+    pub fn get_path(&self) -> &[String] {
+        &self.path
+    }
+
+    pub fn get_ref_result(&self) -> &[LuaDiagnosticReport] {
+        &self.result
+    }
+
+    pub fn get_ref_mut_result(&mut self) -> &mut Vec<LuaDiagnosticReport> {
+        &mut self.result
+    }
+}
+
+impl Display for LuaDiagnosticReportType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(match self {
+            Self::SyntaxError => "SyntaxError",
+            Self::UndefinedFunction => "UndefinedFunction",
+            Self::InvalidArgumentCount => "InvalidArgumentCount",
+            Self::InvalidKey => "InvalidKey",
+        }, f)
+    }
+}
@@ -16,25 +16,52 @@ This module contains the code needed to get a `Diagnostics` over an entire `Pack
 
 use rayon::prelude::*;
 use fancy_regex::Regex;
+use serde_derive::{Serialize, Deserialize};
+use serde_json::json;
 
+use std::collections::{BTreeMap, HashSet};
 use std::{fmt, fmt::Display};
 
+use rpfm_error::{ErrorKind, Result};
+
 use crate::DB;
+use crate::context::GameContext;
 use crate::dependencies::Dependencies;
 use crate::packfile::{PackFile, PathType};
 use crate::packedfile::{table::DecodedData, DecodedPackedFile, PackedFileType};
+use crate::packedfile::text::{Text, TextType, lua::{CATypes, LuaArgument, LuaPathSegment, LuaScript}};
 use crate::packfile::packedfile::PackedFileInfo;
 use crate::PackedFile;
 use crate::schema::FieldType;
 
 use self::dependency_manager::{DependencyManagerDiagnostic, DependencyManagerDiagnosticReport, DependencyManagerDiagnosticReportType};
+use self::lua::{LuaDiagnostic, LuaDiagnosticReport, LuaDiagnosticReportType};
 use self::packfile::PackFileDiagnostic;
 use self::table::{TableDiagnostic, TableDiagnosticReport, TableDiagnosticReportType};
 
 pub mod dependency_manager;
+pub mod lua;
 pub mod packfile;
 pub mod table;
 
+#[cfg(test)]
+mod diagnostics_test;
+
+/// Version of the SARIF format we use for the SARIF reports.
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Schema of the SARIF format we use for the SARIF reports.
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Keys checked in the string arguments of Lua functions, with the table and column they must be in.
+///
+/// An argument is checked if the name of its parameter contains the first value as a word, and its type is a string.
+const LUA_KEY_REFERENCES: [(&str, &str, &str); 3] = [
+    ("faction", "factions", "key"),
+    ("unit", "main_units", "unit"),
+    ("building", "building_levels", "level_name"),
+];
+
 //-------------------------------------------------------------------------------//
 //                              Enums & Structs
 //-------------------------------------------------------------------------------//
@@ -49,16 +76,47 @@ pub enum DiagnosticType {
     Loc(TableDiagnostic),
     PackFile(PackFileDiagnostic),
     DependencyManager(DependencyManagerDiagnostic),
+    Lua(LuaDiagnostic),
 }
 
 /// This enum defines the possible results for a result of a diagnostic check.
-#[derive(Debug, Clone)]
+///
+/// They're sorted by severity, so they can be compared to know if a result is over a threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum DiagnosticLevel {
     Info,
     Warning,
     Error,
 }
 
+/// This struct contains a single result of a diagnostic check, independently of the type of diagnostic it came from.
+///
+/// Used to export the results of a check.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticEntry {
+
+    /// Path of the PackedFile the result is about. Empty for results about the PackFile itself, or its Dependency Manager.
+    pub path: String,
+
+    /// Type of the diagnostic, like `DB` or `Loc`.
+    pub diagnostic_type: String,
+
+    /// Type of the result, like `InvalidReference`.
+    pub report_type: String,
+
+    /// Severity of the result.
+    pub level: DiagnosticLevel,
+
+    /// Human-readable explanation of the result.
+    pub message: String,
+
+    /// Row the result is about, starting at 1. None if the result is not about a specific row.
+    pub row: Option<u64>,
+
+    /// Column the result is about, starting at 1. None if the result is not about a specific column.
+    pub column: Option<u32>,
+}
+
 //---------------------------------------------------------------p----------------//
 //                             Implementations
 //-------------------------------------------------------------------------------//
@@ -77,6 +135,7 @@ impl DiagnosticType {
             Self::Loc(ref diag) => diag.get_path(),
             Self::PackFile(ref diag) => diag.get_path(),
             Self::DependencyManager(_) => &[],
+            Self::Lua(ref diag) => diag.get_path(),
         }
     }
 }
@@ -92,24 +151,38 @@ impl Diagnostics {
         &mut self.0
     }
 
+    /// This function performs a search over the parts of a `PackFile` you specify it, using the dependencies of the provided `GameContext`.
+    pub fn check_with_context(&mut self, pack_file: &PackFile, context: &GameContext) {
+        self.check(pack_file, context.get_ref_dependencies());
+    }
+
     /// This function performs a search over the parts of a `PackFile` you specify it, storing his results.
     pub fn check(&mut self, pack_file: &PackFile, dependencies: &Dependencies) {
         let real_dep_db = dependencies.get_ref_dependency_database();
         let fake_dep_db = dependencies.get_ref_fake_dependency_database();
+        let packed_file_paths = pack_file.get_ref_packed_files_all_paths().iter().map(|x| x.join("/").to_lowercase()).collect::<HashSet<String>>();
+
+        // Keys in Lua scripts can only be checked if we know the functions they're passed to.
+        let lua_keys = if dependencies.get_ref_ca_types().is_some() && !pack_file.get_ref_packed_files_by_types(&[PackedFileType::Text(TextType::Lua)], true).is_empty() {
+            Self::get_lua_keys(pack_file, real_dep_db, fake_dep_db)
+        } else { BTreeMap::new() };
+        let check_undefined_functions = pack_file.get_settings().settings_bool.get("diagnostics_check_undefined_lua_functions").cloned().unwrap_or(false);
+
         let files_to_ignore = pack_file.get_settings().settings_text.get("diagnostics_files_to_ignore").map(|files_to_ignore| {
             let files = files_to_ignore.split('\n').collect::<Vec<&str>>();
             files.iter().map(|x| x.split('/').map(|y| y.to_owned()).collect::<Vec<String>>()).collect::<Vec<Vec<String>>>()
         });
 
-        self.0 = pack_file.get_ref_packed_files_by_types(&[PackedFileType::DB, PackedFileType::Loc], false).par_iter().filter_map(|packed_file| {
+        self.0 = pack_file.get_ref_packed_files_by_types(&[PackedFileType::DB, PackedFileType::Loc, PackedFileType::Text(TextType::Lua)], false).par_iter().filter_map(|packed_file| {
             if let Some(ref files_to_ignore) = files_to_ignore {
                 if files_to_ignore.contains(&packed_file.get_path().to_vec()) {
                     return None;
                 }
             }
             match packed_file.get_packed_file_type_by_path() {
-                PackedFileType::DB => Self::check_db(pack_file, packed_file.get_ref_decoded(), packed_file.get_path(), &real_dep_db, &fake_dep_db),
+                PackedFileType::DB => Self::check_db(pack_file, packed_file, real_dep_db, fake_dep_db, &packed_file_paths, dependencies.get_ref_packed_file_paths()),
                 PackedFileType::Loc => Self::check_loc(packed_file.get_ref_decoded(), packed_file.get_path()),
+                PackedFileType::Text(TextType::Lua) => Self::check_lua(packed_file, dependencies.get_ref_ca_types(), &lua_keys, check_undefined_functions),
                 _ => None,
             }
         }).collect();
@@ -124,15 +197,19 @@ impl Diagnostics {
     }
 
     /// This function takes care of checking the db tables of your mod for errors.
+    ///
+    /// `packed_file_paths` and `dependency_packed_file_paths` are the paths of all the files in the `PackFile` and in its dependencies,
+    /// lowercased and joined with `/`. They're used to check that the files referenced in the table exist.
     fn check_db(
         pack_file: &PackFile,
-        packed_file: &DecodedPackedFile,
-        path: &[String],
+        packed_file: &PackedFile,
         real_dep_db: &[PackedFile],
         fake_dep_db: &[DB],
+        packed_file_paths: &HashSet<String>,
+        dependency_packed_file_paths: &HashSet<String>,
     ) ->Option<DiagnosticType> {
-        if let DecodedPackedFile::DB(table) = packed_file {
-            let mut diagnostic = TableDiagnostic::new(path);
+        if let DecodedPackedFile::DB(table) = packed_file.get_ref_decoded() {
+            let mut diagnostic = TableDiagnostic::new(packed_file.get_path());
             let dependency_data = DB::get_dependency_data(
                 &pack_file,
                 table.get_ref_definition(),
@@ -157,6 +234,24 @@ impl Diagnostics {
                 });
             }
 
+            // Check for bitwise values using bits not defined in the schema. These bits are lost when decoding, so we need the raw data for this.
+            // Keep in mind that, if the table has been edited but not saved, the raw data may be outdated.
+            if table.get_ref_definition().get_ref_fields().iter().any(|x| x.get_is_bitwise() > 1) {
+                if let Ok(values) = packed_file.get_raw_data().and_then(|data| DB::get_undefined_bitwise_values(&data, table.get_ref_definition())) {
+                    let fields_processed = table.get_ref_definition().get_fields_processed();
+                    for (row, field_name, value) in values {
+                        let column = fields_processed.iter().position(|x| x.get_name() == format!("{}_1", field_name)).unwrap_or(0);
+                        diagnostic.get_ref_mut_result().push(TableDiagnosticReport {
+                            column_number: column as u32,
+                            row_number: row as i64,
+                            message: format!("Bitwise value \"{}\" in column \"{}\" uses bits not defined in the schema.", value, field_name),
+                            report_type: TableDiagnosticReportType::InvalidBitwiseValue,
+                            level: DiagnosticLevel::Warning,
+                        });
+                    }
+                }
+            }
+
             for (row, cells) in table.get_ref_table_data().iter().enumerate() {
                 let mut row_is_empty = true;
                 let mut row_keys_are_empty = true;
@@ -195,6 +290,42 @@ impl Diagnostics {
                         }
                     }
 
+                    // Check for strings longer than the max length of their column. Enum columns are strings only after decoding, so we skip them.
+                    if field.get_max_length() > 0 && field.get_enum_values().is_empty() && matches!(field.get_ref_field_type(), FieldType::StringU8 | FieldType::StringU16 | FieldType::OptionalStringU8 | FieldType::OptionalStringU16) && cell_data.chars().count() > field.get_max_length() as usize {
+                        diagnostic.get_ref_mut_result().push(TableDiagnosticReport {
+                            column_number: column as u32,
+                            row_number: row as i64,
+                            message: format!("Value \"{}\" in column \"{}\" is longer than its max length ({}).", &cell_data, field.get_name(), field.get_max_length()),
+                            report_type: TableDiagnosticReportType::FieldMaxLengthExceeded,
+                            level: DiagnosticLevel::Error,
+                        });
+                    }
+
+                    // Check for enum values not in the schema. These are kept as numbers when decoding.
+                    if !field.get_enum_values().is_empty() && !field.get_enum_values().values().any(|x| x == &cell_data) {
+                        diagnostic.get_ref_mut_result().push(TableDiagnosticReport {
+                            column_number: column as u32,
+                            row_number: row as i64,
+                            message: format!("Invalid enum value \"{}\" in column \"{}\".", &cell_data, field.get_name()),
+                            report_type: TableDiagnosticReportType::InvalidEnumValue,
+                            level: DiagnosticLevel::Error,
+                        });
+                    }
+
+                    // Check for referenced files that don't exist. If we have no dependencies loaded, we skip this, as we cannot check vanilla files.
+                    if field.get_is_filename() && !cell_data.is_empty() && !dependency_packed_file_paths.is_empty() {
+                        let paths = get_filename_paths(&cell_data, field.get_filename_relative_path());
+                        if !paths.iter().any(|x| packed_file_paths.contains(x) || dependency_packed_file_paths.contains(x)) {
+                            diagnostic.get_ref_mut_result().push(TableDiagnosticReport {
+                                column_number: column as u32,
+                                row_number: row as i64,
+                                message: format!("File \"{}\" in column \"{}\" not found.", &cell_data, field.get_name()),
+                                report_type: TableDiagnosticReportType::FileNotFound,
+                                level: DiagnosticLevel::Warning,
+                            });
+                        }
+                    }
+
                     // Check for empty keys/rows.
                     if row_is_empty && (!cell_data.is_empty() && cell_data != "false") {
                         row_is_empty = false;
@@ -354,6 +485,159 @@ impl Diagnostics {
         } else { None }
     }
 
+    /// This function takes care of checking the Lua scripts of your mod for errors.
+    ///
+    /// Calls are only checked if we have the `ca_types` file of the game. Keys are only checked against tables with data.
+    ///
+    /// As the `ca_types` files are incomplete, calls to functions missing from known classes are only reported if `check_undefined_functions` is true.
+    fn check_lua(packed_file: &PackedFile, ca_types: &Option<CATypes>, lua_keys: &BTreeMap<&str, HashSet<String>>, check_undefined_functions: bool) -> Option<DiagnosticType> {
+        let text = match packed_file.get_ref_decoded() {
+            DecodedPackedFile::Text(text) => text.get_ref_contents().to_owned(),
+            _ => packed_file.get_raw_data().and_then(|data| Text::read(&data)).ok()?.get_ref_contents().to_owned(),
+        };
+
+        let mut diagnostic = LuaDiagnostic::new(packed_file.get_path());
+        match LuaScript::parse(&text) {
+            Ok(script) => {
+                for call in script.get_ref_calls() {
+                    let full_name = call.receiver.iter().filter_map(|x| if let LuaPathSegment::Field(name) = x { Some(&**name) } else { None })
+                        .chain(std::iter::once(&*call.name))
+                        .collect::<Vec<&str>>()
+                        .join(".");
+
+                    let function = match ca_types.as_ref().map(|x| x.get_called_function(call)) {
+                        Some(Ok(Some(function))) => function,
+                        Some(Err(class_name)) => {
+                            if !check_undefined_functions { continue; }
+                            diagnostic.get_ref_mut_result().push(LuaDiagnosticReport {
+                                line: call.line as u32,
+                                column: call.column as u32,
+                                message: format!("Function \"{}\" not found in class \"{}\".", call.name, class_name),
+                                report_type: LuaDiagnosticReportType::UndefinedFunction,
+                                level: DiagnosticLevel::Warning,
+                            });
+                            continue;
+                        }
+                        _ => continue,
+                    };
+
+                    // Calling a method with `.` means the object is passed as first argument. Calling a function with `:` is the opposite.
+                    let mut arguments = call.arguments.iter().collect::<Vec<&LuaArgument>>();
+                    let mut argument_count = arguments.len() as isize;
+                    if function.is_method && !call.is_method {
+                        argument_count -= 1;
+                        if !arguments.is_empty() {
+                            arguments.remove(0);
+                        }
+                    }
+                    else if !function.is_method && call.is_method {
+                        argument_count += 1;
+                    }
+
+                    // If the last argument can return multiple values, we cannot know how many arguments there really are.
+                    let min = function.get_min_arguments() as isize;
+                    let max = function.get_max_arguments().map(|x| x as isize);
+                    let is_valid_count = match arguments.last() {
+                        Some(LuaArgument::Multiple) => !matches!(max, Some(max) if argument_count - 1 > max),
+                        _ => argument_count >= min && !matches!(max, Some(max) if argument_count > max),
+                    };
+
+                    if !is_valid_count {
+                        let expected = match max {
+                            Some(max) if max == min => format!("{}", min),
+                            Some(max) => format!("{} to {}", min, max),
+                            None => format!("at least {}", min),
+                        };
+
+                        diagnostic.get_ref_mut_result().push(LuaDiagnosticReport {
+                            line: call.line as u32,
+                            column: call.column as u32,
+                            message: format!("Function \"{}\" called with {} arguments, but it expects {}.", full_name, argument_count.max(0), expected),
+                            report_type: LuaDiagnosticReportType::InvalidArgumentCount,
+                            level: DiagnosticLevel::Warning,
+                        });
+                    }
+
+                    // Check for string literals that should be keys of a table, but are not in it.
+                    for (index, argument) in arguments.iter().enumerate() {
+                        if let LuaArgument::String(value) = argument {
+                            if let Some(parameter) = function.get_parameter_for_argument(index) {
+                                if !parameter.parameter_type.contains("string") { continue; }
+                                for (key_type, table_name, _) in &LUA_KEY_REFERENCES {
+                                    if !Self::is_key_parameter(&parameter.name, key_type) { continue; }
+                                    if let Some(keys) = lua_keys.get(table_name) {
+                                        let values: Vec<&str> = if *key_type == "unit" { value.split(',').map(|x| x.trim()).collect() } else { vec![value] };
+                                        for value in values.iter().filter(|x| !x.is_empty() && !keys.contains(**x)) {
+                                            diagnostic.get_ref_mut_result().push(LuaDiagnosticReport {
+                                                line: call.line as u32,
+                                                column: call.column as u32,
+                                                message: format!("Invalid {} key \"{}\" in argument \"{}\" of function \"{}\".", key_type, value, parameter.name, full_name),
+                                                report_type: LuaDiagnosticReportType::InvalidKey,
+                                                level: DiagnosticLevel::Error,
+                                            });
+                                        }
+                                    }
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            Err(error) => {
+                let (line, column, message) = match error.kind() {
+                    ErrorKind::LuaSyntaxError(line, column, message) => (*line, *column, message.to_owned()),
+                    _ => (1, 1, error.to_string()),
+                };
+
+                diagnostic.get_ref_mut_result().push(LuaDiagnosticReport {
+                    line: line as u32,
+                    column: column as u32,
+                    message: format!("Syntax error: {}.", message),
+                    report_type: LuaDiagnosticReportType::SyntaxError,
+                    level: DiagnosticLevel::Error,
+                });
+            }
+        }
+
+        if !diagnostic.get_ref_result().is_empty() {
+            Some(DiagnosticType::Lua(diagnostic))
+        } else { None }
+    }
+
+    /// This function returns the keys checked in the Lua scripts, by table. Tables without data are not included.
+    fn get_lua_keys(pack_file: &PackFile, real_dep_db: &[PackedFile], fake_dep_db: &[DB]) -> BTreeMap<&'static str, HashSet<String>> {
+        LUA_KEY_REFERENCES.iter().filter_map(|(_, table_name, column_name)| {
+            let keys = DB::get_column_data(pack_file, table_name, column_name, real_dep_db, fake_dep_db).into_keys().collect::<HashSet<String>>();
+            if keys.is_empty() { None } else { Some((*table_name, keys)) }
+        }).collect()
+    }
+
+    /// This function returns if the name of a parameter refers to the provided key type.
+    ///
+    /// Names are split in words by `_` and by lowercase to uppercase changes, so `faction_key` and `factionKey` match `faction`,
+    /// but `community` doesn't match `unit`. Plurals also match, as they're used for lists of keys.
+    fn is_key_parameter(parameter_name: &str, key_type: &str) -> bool {
+        let mut words = vec![];
+        let mut word = String::new();
+        let mut previous_is_lowercase = false;
+        for character in parameter_name.chars() {
+            if character == '_' || (character.is_uppercase() && previous_is_lowercase) {
+                words.push(word.to_lowercase());
+                word.clear();
+            }
+
+            if character != '_' {
+                word.push(character);
+            }
+            previous_is_lowercase = character.is_lowercase();
+        }
+        words.push(word.to_lowercase());
+
+        words.iter().any(|word| word == key_type || word.strip_suffix('s') == Some(key_type))
+    }
+
     /// This function takes care of checking for PackFile-Related for errors.
     fn check_packfile() ->Option<DiagnosticType> {
         let diagnostic = PackFileDiagnostic::new();
@@ -415,6 +699,11 @@ impl Diagnostics {
         // If we got no schema, don't even decode.
         let real_dep_db = dependencies.get_ref_dependency_database();
         let fake_dep_db = dependencies.get_ref_fake_dependency_database();
+        let packed_file_paths = pack_file.get_ref_packed_files_all_paths().iter().map(|x| x.join("/").to_lowercase()).collect::<HashSet<String>>();
+        let lua_keys = if dependencies.get_ref_ca_types().is_some() && paths.iter().any(|x| PackedFileType::get_packed_file_type(x) == PackedFileType::Text(TextType::Lua)) {
+            Self::get_lua_keys(pack_file, real_dep_db, fake_dep_db)
+        } else { BTreeMap::new() };
+        let check_undefined_functions = pack_file.get_settings().settings_bool.get("diagnostics_check_undefined_lua_functions").cloned().unwrap_or(false);
 
         let files_to_ignore = pack_file.get_settings().settings_text.get("diagnostics_files_to_ignore").map(|files_to_ignore| {
             let files = files_to_ignore.split('\n').collect::<Vec<&str>>();
@@ -429,8 +718,9 @@ impl Diagnostics {
             }
 
             let diagnostic = match packed_file.get_packed_file_type_by_path() {
-                PackedFileType::DB => Self::check_db(pack_file, packed_file.get_ref_decoded(), packed_file.get_path(), &real_dep_db, &fake_dep_db),
+                PackedFileType::DB => Self::check_db(pack_file, packed_file, real_dep_db, fake_dep_db, &packed_file_paths, dependencies.get_ref_packed_file_paths()),
                 PackedFileType::Loc => Self::check_loc(packed_file.get_ref_decoded(), packed_file.get_path()),
+                PackedFileType::Text(TextType::Lua) => Self::check_lua(packed_file, dependencies.get_ref_ca_types(), &lua_keys, check_undefined_functions),
                 _ => None,
             };
 
@@ -449,6 +739,107 @@ impl Diagnostics {
         self.get_ref_mut_diagnostics().sort_by(|a, b| a.get_path().cmp(b.get_path()));
     }
 
+    /// This function returns all the results of the diagnostics as a flat list, in the same order they're stored.
+    pub fn get_entries(&self) -> Vec<DiagnosticEntry> {
+        let mut entries = vec![];
+        for diagnostic in &self.0 {
+            let path = diagnostic.get_path().join("/");
+            let diagnostic_type = diagnostic.to_string();
+            match diagnostic {
+                DiagnosticType::DB(ref diag) |
+                DiagnosticType::Loc(ref diag) => entries.extend(diag.get_ref_result().iter().map(|x| DiagnosticEntry {
+                    path: path.to_owned(),
+                    diagnostic_type: diagnostic_type.to_owned(),
+                    report_type: x.report_type.to_string(),
+                    level: x.level,
+                    message: x.message.to_owned(),
+                    row: if x.row_number >= 0 { Some(x.row_number as u64 + 1) } else { None },
+                    column: if x.row_number >= 0 { Some(x.column_number + 1) } else { None },
+                })),
+
+                DiagnosticType::PackFile(ref diag) => entries.extend(diag.get_ref_result().iter().map(|x| DiagnosticEntry {
+                    path: path.to_owned(),
+                    diagnostic_type: diagnostic_type.to_owned(),
+                    report_type: diagnostic_type.to_owned(),
+                    level: x.level,
+                    message: x.message.to_owned(),
+                    row: None,
+                    column: None,
+                })),
+
+                DiagnosticType::DependencyManager(ref diag) => entries.extend(diag.get_ref_result().iter().map(|x| DiagnosticEntry {
+                    path: path.to_owned(),
+                    diagnostic_type: diagnostic_type.to_owned(),
+                    report_type: x.report_type.to_string(),
+                    level: x.level,
+                    message: x.message.to_owned(),
+                    row: if x.row_number >= 0 { Some(x.row_number as u64 + 1) } else { None },
+                    column: None,
+                })),
+
+                DiagnosticType::Lua(ref diag) => entries.extend(diag.get_ref_result().iter().map(|x| DiagnosticEntry {
+                    path: path.to_owned(),
+                    diagnostic_type: diagnostic_type.to_owned(),
+                    report_type: x.report_type.to_string(),
+                    level: x.level,
+                    message: x.message.to_owned(),
+                    row: Some(x.line as u64),
+                    column: Some(x.column),
+                })),
+            }
+        }
+        entries
+    }
+
+    /// This function returns the results of the diagnostics as a SARIF log, so they can be consumed by CI tools.
+    ///
+    /// Results without a path are reported against the PackFile itself.
+    pub fn to_sarif(&self, pack_file_name: &str) -> Result<String> {
+        let entries = self.get_entries();
+        let mut rules = entries.iter().map(|x| x.report_type.to_owned()).collect::<Vec<String>>();
+        rules.sort();
+        rules.dedup();
+
+        let results = entries.iter().map(|entry| {
+            let uri = if entry.path.is_empty() { pack_file_name.to_owned() } else { format!("{}/{}", pack_file_name, entry.path) };
+            let mut physical_location = json!({ "artifactLocation": { "uri": uri } });
+            if let Some(row) = entry.row {
+                physical_location["region"] = json!({ "startLine": row });
+                if let Some(column) = entry.column {
+                    physical_location["region"]["startColumn"] = json!(column);
+                }
+            }
+
+            json!({
+                "ruleId": entry.report_type,
+                "level": match entry.level {
+                    DiagnosticLevel::Info => "note",
+                    DiagnosticLevel::Warning => "warning",
+                    DiagnosticLevel::Error => "error",
+                },
+                "message": { "text": entry.message },
+                "locations": [{ "physicalLocation": physical_location }],
+            })
+        }).collect::<Vec<serde_json::Value>>();
+
+        let sarif = json!({
+            "$schema": SARIF_SCHEMA,
+            "version": SARIF_VERSION,
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "RPFM",
+                        "informationUri": "https://github.com/Frodo45127/rpfm",
+                        "rules": rules.iter().map(|x| json!({ "id": x })).collect::<Vec<serde_json::Value>>(),
+                    }
+                },
+                "results": results,
+            }]
+        });
+
+        serde_json::to_string_pretty(&sarif).map_err(From::from)
+    }
+
     /// This function returns the PackedFileInfo for all the PackedFiles with the provided paths.
     pub fn get_update_paths_packed_file_info(&self, pack_file: &PackFile, paths: &[PathType]) -> Vec<PackedFileInfo> {
         let paths = paths.iter().filter_map(|x| if let PathType::File(path) = x { Some(&**path) } else { None }).collect();
@@ -464,6 +855,52 @@ impl Display for DiagnosticType {
             Self::Loc(_) => "Loc",
             Self::PackFile(_) => "Packfile",
             Self::DependencyManager(_) => "DependencyManager",
+            Self::Lua(_) => "Lua",
+        }, f)
+    }
+}
+
+impl Display for DiagnosticLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(match self {
+            Self::Info => "Info",
+            Self::Warning => "Warning",
+            Self::Error => "Error",
         }, f)
     }
 }
+
+/// Display implementation of `Diagnostics`, to get a human-readable report of the results.
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in self.get_entries() {
+            let path = if entry.path.is_empty() { entry.diagnostic_type.to_owned() } else { entry.path.to_owned() };
+            let location = match (entry.row, entry.column) {
+                (Some(row), Some(column)) => format!(":{}:{}", row, column),
+                (Some(row), None) => format!(":{}", row),
+                _ => String::new(),
+            };
+
+            writeln!(f, "{}{}: {} [{}]: {}", path, location, entry.level, entry.report_type, entry.message)?;
+        }
+
+        Ok(())
+    }
+}
+
+//-------------------------------------------------------------------------------//
+//                              Functions
+//-------------------------------------------------------------------------------//
+
+/// This function returns the paths, lowercased and joined with `/`, where the file referenced in a filename cell can be.
+///
+/// If the field has relative paths (separated by `;`), the file can be in any of them. Otherwise, the cell contains the full path.
+fn get_filename_paths(cell_data: &str, filename_relative_path: &Option<String>) -> Vec<String> {
+    let file_path = cell_data.replace('\\', "/").to_lowercase();
+    let relative_paths = filename_relative_path.as_ref()
+        .map(|x| x.split(';').map(|y| y.trim().replace('\\', "/").trim_matches('/').to_lowercase()).filter(|y| !y.is_empty()).collect::<Vec<String>>())
+        .unwrap_or_default();
+
+    if relative_paths.is_empty() { vec![file_path] }
+    else { relative_paths.iter().map(|x| format!("{}/{}", x, file_path)).collect() }
+}
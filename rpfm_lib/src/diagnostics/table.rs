@@ -52,6 +52,10 @@ pub enum TableDiagnosticReportType {
     NoReferenceTableNorColumnFoundNoPak,
     InvalidEscape,
     DuplicatedRow,
+    FieldMaxLengthExceeded,
+    InvalidEnumValue,
+    FileNotFound,
+    InvalidBitwiseValue,
 }
 
 //---------------------------------------------------------------p----------------//
@@ -94,6 +98,10 @@ impl Display for TableDiagnosticReportType {
             Self::NoReferenceTableNorColumnFoundNoPak => "NoReferenceTableNorColumnFoundNoPak",
             Self::InvalidEscape => "InvalidEscape",
             Self::DuplicatedRow => "DuplicatedRow",
+            Self::FieldMaxLengthExceeded => "FieldMaxLengthExceeded",
+            Self::InvalidEnumValue => "InvalidEnumValue",
+            Self::FileNotFound => "FileNotFound",
+            Self::InvalidBitwiseValue => "InvalidBitwiseValue",
         }, f)
     }
 }
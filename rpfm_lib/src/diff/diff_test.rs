@@ -0,0 +1,134 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `PackFileDiff` module, to make sure we report the right changes.
+!*/
+
+use std::collections::BTreeMap;
+
+use crate::packfile::PFHVersion;
+use crate::schema::{Field, FieldType};
+use super::*;
+
+/// This function builds a field for the test definitions.
+fn build_field(name: &str, field_type: FieldType, is_key: bool) -> Field {
+    Field::new(name.to_owned(), field_type, is_key, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new())
+}
+
+/// This function builds a PackedFile with the provided path and data.
+fn build_packed_file(path: &str, data: &[u8]) -> PackedFile {
+    let mut packed_file = PackedFile::new(path.split('/').map(|x| x.to_owned()).collect(), "test.pack".to_owned());
+    packed_file.set_raw_data(data);
+    packed_file
+}
+
+#[test]
+fn test_diff_table() {
+    let mut old_definition = Definition::new(1);
+    old_definition.get_ref_mut_fields().push(build_field("key", FieldType::StringU8, true));
+    old_definition.get_ref_mut_fields().push(build_field("value", FieldType::I32, false));
+    old_definition.get_ref_mut_fields().push(build_field("old_field", FieldType::Boolean, false));
+
+    let mut new_definition = Definition::new(2);
+    new_definition.get_ref_mut_fields().push(build_field("key", FieldType::StringU8, true));
+    new_definition.get_ref_mut_fields().push(build_field("new_field", FieldType::F32, false));
+    new_definition.get_ref_mut_fields().push(build_field("value", FieldType::I32, false));
+
+    let old_data = vec![
+        vec![DecodedData::StringU8("a".to_owned()), DecodedData::I32(1), DecodedData::Boolean(true)],
+        vec![DecodedData::StringU8("b".to_owned()), DecodedData::I32(2), DecodedData::Boolean(true)],
+        vec![DecodedData::StringU8("c".to_owned()), DecodedData::I32(3), DecodedData::Boolean(true)],
+    ];
+
+    let new_data = vec![
+        vec![DecodedData::StringU8("a".to_owned()), DecodedData::F32(0.0), DecodedData::I32(1)],
+        vec![DecodedData::StringU8("c".to_owned()), DecodedData::F32(0.0), DecodedData::I32(5)],
+        vec![DecodedData::StringU8("d".to_owned()), DecodedData::F32(0.0), DecodedData::I32(4)],
+    ];
+
+    let diff = TableDiff::new(&old_definition, &old_data, &new_definition, &new_data);
+    assert_eq!((diff.old_version, diff.new_version), (1, 2));
+    assert_eq!(diff.key_fields, vec!["key".to_owned()]);
+    assert_eq!(diff.added_fields, vec!["new_field".to_owned()]);
+    assert_eq!(diff.removed_fields, vec!["old_field".to_owned()]);
+    assert_eq!(diff.added_rows.len(), 1);
+    assert_eq!(diff.added_rows[0][0], "d");
+    assert_eq!(diff.removed_rows.len(), 1);
+    assert_eq!(diff.removed_rows[0][0], "b");
+    assert_eq!(diff.modified_rows, vec![RowDiff {
+        key: vec!["c".to_owned()],
+        cells: vec![CellDiff {
+            field: "value".to_owned(),
+            old_value: "3".to_owned(),
+            new_value: "5".to_owned(),
+        }],
+    }]);
+
+    // Same table should report no changes.
+    assert!(TableDiff::new(&old_definition, &old_data, &old_definition, &old_data).is_empty());
+
+    // Duplicated keys are matched in order, and the ones left are reported as removed.
+    let mut old_data_duplicated = old_data.to_vec();
+    old_data_duplicated.push(old_data[0].to_vec());
+    old_data_duplicated.push(old_data[0].to_vec());
+    let diff = TableDiff::new(&old_definition, &old_data_duplicated, &old_definition, &old_data);
+    assert!(diff.added_rows.is_empty());
+    assert!(diff.modified_rows.is_empty());
+    assert_eq!(diff.removed_rows.len(), 2);
+    assert!(diff.removed_rows.iter().all(|x| x[0] == "a"));
+}
+
+#[test]
+fn test_diff_lines() {
+    let old_text = "a\nb\nc\nd\ne";
+    let new_text = "a\nc\nd\nx\ne\nf";
+    assert_eq!(diff_lines(old_text, new_text), vec![
+        LineChange::Removed(2, "b".to_owned()),
+        LineChange::Added(4, "x".to_owned()),
+        LineChange::Added(6, "f".to_owned()),
+    ]);
+
+    assert!(diff_lines(old_text, old_text).is_empty());
+}
+
+#[test]
+fn test_diff_packfile() {
+    let mut old_pack_file = PackFile::new_with_name("old.pack", PFHVersion::PFH5);
+    old_pack_file.add_packed_file(&build_packed_file("text/removed.txt", b"removed"), true).unwrap();
+    old_pack_file.add_packed_file(&build_packed_file("text/modified.txt", b"one\ntwo\nthree"), true).unwrap();
+    old_pack_file.add_packed_file(&build_packed_file("text/same.txt", b"same"), true).unwrap();
+    old_pack_file.add_packed_file(&build_packed_file("data/binary.bin", &[0, 1, 2]), true).unwrap();
+
+    let mut new_pack_file = PackFile::new_with_name("new.pack", PFHVersion::PFH5);
+    new_pack_file.add_packed_file(&build_packed_file("text/added.txt", b"added"), true).unwrap();
+    new_pack_file.add_packed_file(&build_packed_file("text/modified.txt", b"one\n2\nthree"), true).unwrap();
+    new_pack_file.add_packed_file(&build_packed_file("text/same.txt", b"same"), true).unwrap();
+    new_pack_file.add_packed_file(&build_packed_file("data/binary.bin", &[0, 1, 2, 3]), true).unwrap();
+
+    let diff = PackFileDiff::new(&old_pack_file, &new_pack_file).unwrap();
+    assert_eq!(diff.added, vec!["text/added.txt".to_owned()]);
+    assert_eq!(diff.removed, vec!["text/removed.txt".to_owned()]);
+    assert_eq!(diff.modified, vec![
+        PackedFileDiff {
+            path: "data/binary.bin".to_owned(),
+            changes: PackedFileChanges::Binary(3, 4),
+        },
+        PackedFileDiff {
+            path: "text/modified.txt".to_owned(),
+            changes: PackedFileChanges::Text(vec![
+                LineChange::Removed(2, "two".to_owned()),
+                LineChange::Added(2, "2".to_owned()),
+            ]),
+        },
+    ]);
+
+    assert!(PackFileDiff::new(&old_pack_file, &old_pack_file).unwrap().is_empty());
+}
@@ -0,0 +1,421 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with all the code related to the `PackFileDiff`.
+
+This module contains the code needed to compare two `PackFile`s, reporting the PackedFiles added, removed and modified.
+For modified DB and Loc Tables, changes are reported at row and cell level, matching rows by the key fields of their definitions.
+For modified Text PackedFiles, changes are reported line by line.
+!*/
+
+use serde_derive::{Serialize, Deserialize};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use rpfm_error::Result;
+
+use crate::packedfile::{DecodedPackedFile, PackedFileType};
+use crate::packedfile::table::DecodedData;
+use crate::packfile::PackFile;
+use crate::packfile::packedfile::PackedFile;
+use crate::schema::Definition;
+
+#[cfg(test)]
+mod diff_test;
+
+/// Maximum amount of cells the line diff is allowed to use. Bigger diffs are reported as a full replacement of the changed lines.
+const MAX_LINE_DIFF_CELLS: usize = 16_000_000;
+
+//-------------------------------------------------------------------------------//
+//                              Enums & Structs
+//-------------------------------------------------------------------------------//
+
+/// This struct contains the differences between two `PackFile`s.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PackFileDiff {
+
+    /// Paths of the PackedFiles only in the new `PackFile`.
+    pub added: Vec<String>,
+
+    /// Paths of the PackedFiles only in the old `PackFile`.
+    pub removed: Vec<String>,
+
+    /// PackedFiles in both `PackFile`s, but with different data.
+    pub modified: Vec<PackedFileDiff>,
+}
+
+/// This struct contains the differences between two versions of the same PackedFile.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PackedFileDiff {
+
+    /// Path of the PackedFile.
+    pub path: String,
+
+    /// Changes done to the PackedFile.
+    pub changes: PackedFileChanges,
+}
+
+/// This enum contains the changes done to a PackedFile, depending on its type.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PackedFileChanges {
+
+    /// Changes on a DB or Loc Table.
+    Table(TableDiff),
+
+    /// Changes on a Text PackedFile.
+    Text(Vec<LineChange>),
+
+    /// Changes on PackedFiles we cannot decode, or we don't know how to compare. Contains the old and new sizes of the data.
+    Binary(u32, u32),
+}
+
+/// This struct contains the differences between two versions of the same table.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TableDiff {
+
+    /// Version of the old table.
+    pub old_version: i32,
+
+    /// Version of the new table.
+    pub new_version: i32,
+
+    /// Fields only in the new table.
+    pub added_fields: Vec<String>,
+
+    /// Fields only in the old table.
+    pub removed_fields: Vec<String>,
+
+    /// Fields used to match rows between both tables.
+    pub key_fields: Vec<String>,
+
+    /// Rows only in the new table, as strings.
+    pub added_rows: Vec<Vec<String>>,
+
+    /// Rows only in the old table, as strings.
+    pub removed_rows: Vec<Vec<String>>,
+
+    /// Rows in both tables, but with different values in some of their cells.
+    pub modified_rows: Vec<RowDiff>,
+}
+
+/// This struct contains the differences between two versions of the same row.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RowDiff {
+
+    /// Values of the key fields of the row.
+    pub key: Vec<String>,
+
+    /// Cells changed in the row.
+    pub cells: Vec<CellDiff>,
+}
+
+/// This struct contains the old and new values of a cell.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CellDiff {
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+/// This enum represents a line added or removed from a Text PackedFile.
+///
+/// Line numbers start at 1. Removed lines use the line number of the old file, and added lines the one of the new file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LineChange {
+    Added(usize, String),
+    Removed(usize, String),
+}
+
+//-------------------------------------------------------------------------------//
+//                             Implementations
+//-------------------------------------------------------------------------------//
+
+/// Implementation of `PackFileDiff`.
+impl PackFileDiff {
+
+    /// This function compares two `PackFile`s, returning the changes needed to go from the old one to the new one.
+    ///
+    /// DB and Loc Tables are decoded using the currently loaded Schema. If they cannot be decoded, they're compared as binary data.
+    pub fn new(old_pack_file: &PackFile, new_pack_file: &PackFile) -> Result<Self> {
+        let old_packed_files = old_pack_file.get_ref_packed_files_all().into_iter().map(|x| (x.get_path().join("/"), x)).collect::<BTreeMap<String, &PackedFile>>();
+        let new_packed_files = new_pack_file.get_ref_packed_files_all().into_iter().map(|x| (x.get_path().join("/"), x)).collect::<BTreeMap<String, &PackedFile>>();
+
+        let mut diff = Self {
+            added: new_packed_files.keys().filter(|x| !old_packed_files.contains_key(*x)).cloned().collect(),
+            removed: old_packed_files.keys().filter(|x| !new_packed_files.contains_key(*x)).cloned().collect(),
+            modified: vec![],
+        };
+
+        for (path, old_packed_file) in &old_packed_files {
+            if let Some(new_packed_file) = new_packed_files.get(path) {
+                let old_data = old_packed_file.get_raw_data()?;
+                let new_data = new_packed_file.get_raw_data()?;
+                if old_data != new_data {
+                    let changes = PackedFileChanges::new(old_packed_file, new_packed_file, old_data.len() as u32, new_data.len() as u32);
+                    diff.modified.push(PackedFileDiff {
+                        path: path.to_owned(),
+                        changes,
+                    });
+                }
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// This function returns if both `PackFile`s were equal.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Implementation of `PackedFileChanges`.
+impl PackedFileChanges {
+
+    /// This function compares two versions of the same PackedFile. If they cannot be decoded, they're compared as binary.
+    fn new(old_packed_file: &PackedFile, new_packed_file: &PackedFile, old_size: u32, new_size: u32) -> Self {
+        let packed_file_type = old_packed_file.get_packed_file_type_by_path();
+        if let PackedFileType::DB | PackedFileType::Loc | PackedFileType::Text(_) = packed_file_type {
+            let mut old_packed_file = old_packed_file.clone();
+            let mut new_packed_file = new_packed_file.clone();
+            if let (Ok(old_decoded), Ok(new_decoded)) = (DecodedPackedFile::decode(old_packed_file.get_ref_mut_raw()), DecodedPackedFile::decode(new_packed_file.get_ref_mut_raw())) {
+                match (old_decoded, new_decoded) {
+                    (DecodedPackedFile::DB(old), DecodedPackedFile::DB(new)) => return Self::Table(TableDiff::new(old.get_ref_definition(), old.get_ref_table_data(), new.get_ref_definition(), new.get_ref_table_data())),
+                    (DecodedPackedFile::Loc(old), DecodedPackedFile::Loc(new)) => return Self::Table(TableDiff::new(old.get_ref_definition(), old.get_ref_table_data(), new.get_ref_definition(), new.get_ref_table_data())),
+                    (DecodedPackedFile::Text(old), DecodedPackedFile::Text(new)) => return Self::Text(diff_lines(old.get_ref_contents(), new.get_ref_contents())),
+                    _ => {}
+                }
+            }
+        }
+
+        Self::Binary(old_size, new_size)
+    }
+}
+
+/// Implementation of `TableDiff`.
+impl TableDiff {
+
+    /// This function compares two versions of a table.
+    ///
+    /// Rows are matched using the key fields of the new definition. If the table has no key fields, whole rows are used as keys,
+    /// so rows can only be added or removed. Only fields present in both definitions are compared.
+    pub fn new(old_definition: &Definition, old_data: &[Vec<DecodedData>], new_definition: &Definition, new_data: &[Vec<DecodedData>]) -> Self {
+        let old_fields = old_definition.get_fields_processed();
+        let new_fields = new_definition.get_fields_processed();
+        let old_field_names = old_fields.iter().map(|x| x.get_name()).collect::<Vec<&str>>();
+        let new_field_names = new_fields.iter().map(|x| x.get_name()).collect::<Vec<&str>>();
+
+        // Common fields, as (old column, new column, name).
+        let common_fields = new_field_names.iter().enumerate()
+            .filter_map(|(new_column, name)| old_field_names.iter().position(|x| x == name).map(|old_column| (old_column, new_column, name.to_string())))
+            .collect::<Vec<(usize, usize, String)>>();
+
+        let key_fields = new_fields.iter().filter(|x| x.get_is_key() && old_field_names.contains(&x.get_name())).map(|x| x.get_name()).collect::<Vec<&str>>();
+        let old_key_columns = get_key_columns(&old_field_names, &key_fields);
+        let new_key_columns = get_key_columns(&new_field_names, &key_fields);
+
+        // Rows with the same key are matched in order. Duplicated keys left without match are reported as added/removed.
+        let mut old_rows_by_key: HashMap<Vec<String>, Vec<usize>> = HashMap::new();
+        for (index, row) in old_data.iter().enumerate() {
+            old_rows_by_key.entry(get_row_key(row, &old_key_columns)).or_default().push(index);
+        }
+        old_rows_by_key.values_mut().for_each(|x| x.reverse());
+
+        let mut diff = Self {
+            old_version: old_definition.get_version(),
+            new_version: new_definition.get_version(),
+            added_fields: new_field_names.iter().filter(|x| !old_field_names.contains(x)).map(|x| x.to_string()).collect(),
+            removed_fields: old_field_names.iter().filter(|x| !new_field_names.contains(x)).map(|x| x.to_string()).collect(),
+            key_fields: key_fields.iter().map(|x| x.to_string()).collect(),
+            ..Self::default()
+        };
+
+        let mut matched = BTreeSet::new();
+        for new_row in new_data {
+            let key = get_row_key(new_row, &new_key_columns);
+            match old_rows_by_key.get_mut(&key).and_then(|x| x.pop()) {
+                Some(old_index) => {
+                    matched.insert(old_index);
+                    let old_row = &old_data[old_index];
+                    let cells = common_fields.iter()
+                        .filter(|(old_column, new_column, _)| old_row[*old_column] != new_row[*new_column])
+                        .map(|(old_column, new_column, name)| CellDiff {
+                            field: name.to_owned(),
+                            old_value: old_row[*old_column].data_to_string(),
+                            new_value: new_row[*new_column].data_to_string(),
+                        })
+                        .collect::<Vec<CellDiff>>();
+
+                    if !cells.is_empty() {
+                        diff.modified_rows.push(RowDiff {
+                            key,
+                            cells,
+                        });
+                    }
+                }
+                None => diff.added_rows.push(new_row.iter().map(|x| x.data_to_string()).collect()),
+            }
+        }
+
+        diff.removed_rows = old_data.iter()
+            .enumerate()
+            .filter(|(index, _)| !matched.contains(index))
+            .map(|(_, x)| x.iter().map(|x| x.data_to_string()).collect())
+            .collect();
+
+        diff
+    }
+
+    /// This function returns if both tables were equal.
+    pub fn is_empty(&self) -> bool {
+        self.added_fields.is_empty() &&
+            self.removed_fields.is_empty() &&
+            self.added_rows.is_empty() &&
+            self.removed_rows.is_empty() &&
+            self.modified_rows.is_empty()
+    }
+}
+
+/// Display implementation of `PackFileDiff`, to get a human-readable report of the changes.
+impl fmt::Display for PackFileDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No differences found.");
+        }
+
+        for path in &self.added {
+            writeln!(f, "+ {}", path)?;
+        }
+
+        for path in &self.removed {
+            writeln!(f, "- {}", path)?;
+        }
+
+        for packed_file in &self.modified {
+            writeln!(f, "~ {}", packed_file.path)?;
+            match &packed_file.changes {
+                PackedFileChanges::Table(table) => {
+                    if table.old_version != table.new_version {
+                        writeln!(f, "    version: {} -> {}", table.old_version, table.new_version)?;
+                    }
+
+                    for field in &table.added_fields {
+                        writeln!(f, "    + field {}", field)?;
+                    }
+
+                    for field in &table.removed_fields {
+                        writeln!(f, "    - field {}", field)?;
+                    }
+
+                    for row in &table.added_rows {
+                        writeln!(f, "    + [{}]", row.join(", "))?;
+                    }
+
+                    for row in &table.removed_rows {
+                        writeln!(f, "    - [{}]", row.join(", "))?;
+                    }
+
+                    for row in &table.modified_rows {
+                        writeln!(f, "    ~ [{}]", row.key.join(", "))?;
+                        for cell in &row.cells {
+                            writeln!(f, "        {}: {} -> {}", cell.field, cell.old_value, cell.new_value)?;
+                        }
+                    }
+                }
+
+                PackedFileChanges::Text(lines) => {
+                    for line in lines {
+                        match line {
+                            LineChange::Added(number, text) => writeln!(f, "    +{}: {}", number, text)?,
+                            LineChange::Removed(number, text) => writeln!(f, "    -{}: {}", number, text)?,
+                        }
+                    }
+                }
+
+                PackedFileChanges::Binary(old_size, new_size) => writeln!(f, "    binary data changed ({} bytes -> {} bytes)", old_size, new_size)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//-------------------------------------------------------------------------------//
+//                             Helper functions
+//-------------------------------------------------------------------------------//
+
+/// This function returns the columns of the provided key fields. If there are no key fields, all columns are used.
+pub(crate) fn get_key_columns(field_names: &[&str], key_fields: &[&str]) -> Vec<usize> {
+    if key_fields.is_empty() {
+        (0..field_names.len()).collect()
+    } else {
+        key_fields.iter().filter_map(|x| field_names.iter().position(|y| y == x)).collect()
+    }
+}
+
+/// This function returns the key of a row, as strings.
+pub(crate) fn get_row_key(row: &[DecodedData], key_columns: &[usize]) -> Vec<String> {
+    key_columns.iter().filter_map(|x| row.get(*x)).map(|x| x.data_to_string()).collect()
+}
+
+/// This function returns the lines added and removed to go from the old text to the new one.
+pub fn diff_lines(old_text: &str, new_text: &str) -> Vec<LineChange> {
+    let old_lines = old_text.lines().collect::<Vec<&str>>();
+    let new_lines = new_text.lines().collect::<Vec<&str>>();
+
+    // Skip the common start and end, as most changes only touch a few lines.
+    let prefix = old_lines.iter().zip(new_lines.iter()).take_while(|(x, y)| x == y).count();
+    let suffix = old_lines[prefix..].iter().rev().zip(new_lines[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let old_middle = &old_lines[prefix..old_lines.len() - suffix];
+    let new_middle = &new_lines[prefix..new_lines.len() - suffix];
+
+    let mut changes = vec![];
+
+    // If the diff is too big, just report the changed block as replaced.
+    if old_middle.len().saturating_mul(new_middle.len()) > MAX_LINE_DIFF_CELLS {
+        changes.extend(old_middle.iter().enumerate().map(|(index, line)| LineChange::Removed(prefix + index + 1, line.to_string())));
+        changes.extend(new_middle.iter().enumerate().map(|(index, line)| LineChange::Added(prefix + index + 1, line.to_string())));
+        return changes;
+    }
+
+    // Longest Common Subsequence, computed from the end so we can walk it forward.
+    let width = new_middle.len() + 1;
+    let mut lcs = vec![0u32; (old_middle.len() + 1) * width];
+    for old_index in (0..old_middle.len()).rev() {
+        for new_index in (0..new_middle.len()).rev() {
+            lcs[old_index * width + new_index] = if old_middle[old_index] == new_middle[new_index] {
+                lcs[(old_index + 1) * width + new_index + 1] + 1
+            } else {
+                lcs[(old_index + 1) * width + new_index].max(lcs[old_index * width + new_index + 1])
+            };
+        }
+    }
+
+    let mut old_index = 0;
+    let mut new_index = 0;
+    while old_index < old_middle.len() || new_index < new_middle.len() {
+        if old_index < old_middle.len() && new_index < new_middle.len() && old_middle[old_index] == new_middle[new_index] {
+            old_index += 1;
+            new_index += 1;
+        }
+        else if new_index < new_middle.len() && (old_index == old_middle.len() || lcs[old_index * width + new_index + 1] > lcs[(old_index + 1) * width + new_index]) {
+            changes.push(LineChange::Added(prefix + new_index + 1, new_middle[new_index].to_string()));
+            new_index += 1;
+        }
+        else {
+            changes.push(LineChange::Removed(prefix + old_index + 1, old_middle[old_index].to_string()));
+            old_index += 1;
+        }
+    }
+
+    changes
+}
@@ -19,6 +19,7 @@ use rayon::prelude::*;
 
 use rpfm_error::{ErrorKind, Result};
 
+use crate::context::GameContext;
 use crate::packfile::{PackFile, PathType};
 use crate::packfile::packedfile::PackedFileInfo;
 use crate::packedfile::{DecodedPackedFile, PackedFileType};
@@ -125,6 +126,16 @@ impl GlobalSearch {
 
     /// This function performs a search over the parts of a `PackFile` you specify it, storing his results.
     pub fn search(&mut self, pack_file: &mut PackFile) {
+        self.search_with_schema(pack_file, SCHEMA.read().unwrap().as_ref());
+    }
+
+    /// This function performs a search over the parts of a `PackFile` you specify it, using the schema of the provided `GameContext`.
+    pub fn search_with_context(&mut self, pack_file: &mut PackFile, context: &GameContext) {
+        self.search_with_schema(pack_file, context.get_ref_schema());
+    }
+
+    /// This function performs a search over the parts of a `PackFile` you specify it, using the provided schema, if any.
+    fn search_with_schema(&mut self, pack_file: &mut PackFile, schema: Option<&Schema>) {
 
         // If we want to use regex and the pattern is invalid, don't search.
         let matching_mode = if self.use_regex {
@@ -141,7 +152,7 @@ impl GlobalSearch {
         self.matches_schema = vec![];
 
         // If we got no schema, don't even decode.
-        if let Some(ref schema) = *SCHEMA.read().unwrap() {
+        if let Some(schema) = schema {
             if self.search_on_dbs {
                 let mut packed_files = pack_file.get_ref_mut_packed_files_by_type(PackedFileType::DB, false);
                 self.matches_db = packed_files.par_iter_mut().filter_map(|packed_file| {
@@ -194,6 +205,18 @@ impl GlobalSearch {
     ///
     /// NOTE: The schema search is not updated on schema change. Remember that.
     pub fn update(&mut self, pack_file: &mut PackFile, updated_paths: &[PathType]) {
+        self.update_with_schema(pack_file, updated_paths, SCHEMA.read().unwrap().as_ref());
+    }
+
+    /// This function performs a limited search on the `PackedFiles` in the provided paths, using the schema of the provided `GameContext`.
+    ///
+    /// Otherwise, it works like `update`.
+    pub fn update_with_context(&mut self, pack_file: &mut PackFile, updated_paths: &[PathType], context: &GameContext) {
+        self.update_with_schema(pack_file, updated_paths, context.get_ref_schema());
+    }
+
+    /// This function performs a limited search on the `PackedFiles` in the provided paths, using the provided schema, if any.
+    fn update_with_schema(&mut self, pack_file: &mut PackFile, updated_paths: &[PathType], schema: Option<&Schema>) {
 
         // Don't do anything if we have no pattern to search.
         if &self.pattern == "" { return }
@@ -224,7 +247,7 @@ impl GlobalSearch {
         }
 
         // If we got no schema, don't even decode.
-        if let Some(ref schema) = *SCHEMA.read().unwrap() {
+        if let Some(schema) = schema {
             for path in &paths {
                 if let Some(packed_file) = pack_file.get_ref_mut_packed_file_by_path(&path) {
                     match packed_file.decode_return_ref_no_locks(&schema).unwrap_or_else(|_| &DecodedPackedFile::Unknown) {
@@ -276,6 +299,18 @@ impl GlobalSearch {
     ///
     /// NOTE: Schema matches are always ignored.
     pub fn replace_matches(&mut self, pack_file: &mut PackFile, matches: &[MatchHolder]) -> Vec<Vec<String>>{
+        self.replace_matches_with_schema(pack_file, matches, SCHEMA.read().unwrap().as_ref())
+    }
+
+    /// This function performs a replace operation over the provided matches, using the schema of the provided `GameContext`.
+    ///
+    /// Otherwise, it works like `replace_matches`.
+    pub fn replace_matches_with_context(&mut self, pack_file: &mut PackFile, matches: &[MatchHolder], context: &GameContext) -> Vec<Vec<String>>{
+        self.replace_matches_with_schema(pack_file, matches, context.get_ref_schema())
+    }
+
+    /// This function performs a replace operation over the provided matches, using the provided schema, if any.
+    fn replace_matches_with_schema(&mut self, pack_file: &mut PackFile, matches: &[MatchHolder], schema: Option<&Schema>) -> Vec<Vec<String>>{
         let mut errors = vec![];
 
         // If we want to use regex and the pattern is invalid, don't search.
@@ -285,8 +320,7 @@ impl GlobalSearch {
             }
             else { MatchingMode::Pattern }
         } else { MatchingMode::Pattern };
-        let schema = &*SCHEMA.read().unwrap();
-        if let Some(ref schema) = schema {
+        if let Some(schema) = schema {
             let mut changed_files = vec![];
             for match_file in matches {
                 match match_file {
@@ -343,13 +377,23 @@ impl GlobalSearch {
             }
 
             let changed_files = changed_files.iter().map(|x| PathType::File(x.to_vec())).collect::<Vec<PathType>>();
-            self.update(pack_file, &changed_files);
+            self.update_with_schema(pack_file, &changed_files, Some(schema));
         }
         errors
     }
 
     /// This function performs a replace operation over the entire match set, except schemas..
     pub fn replace_all(&mut self, pack_file: &mut PackFile) -> Vec<Vec<String>> {
+        self.replace_all_with_schema(pack_file, SCHEMA.read().unwrap().as_ref())
+    }
+
+    /// This function performs a replace operation over the entire match set, except schemas, using the schema of the provided `GameContext`.
+    pub fn replace_all_with_context(&mut self, pack_file: &mut PackFile, context: &GameContext) -> Vec<Vec<String>> {
+        self.replace_all_with_schema(pack_file, context.get_ref_schema())
+    }
+
+    /// This function performs a replace operation over the entire match set, except schemas, using the provided schema, if any.
+    fn replace_all_with_schema(&mut self, pack_file: &mut PackFile, schema: Option<&Schema>) -> Vec<Vec<String>> {
         let mut errors = vec![];
 
         // If we want to use regex and the pattern is invalid, don't search.
@@ -359,8 +403,7 @@ impl GlobalSearch {
             }
             else { MatchingMode::Pattern }
         } else { MatchingMode::Pattern };
-        let schema = &*SCHEMA.read().unwrap();
-        if let Some(ref schema) = schema {
+        if let Some(schema) = schema {
             let mut changed_files = vec![];
             for match_table in &self.matches_db {
                 if let Some(packed_file) = pack_file.get_ref_mut_packed_file_by_path(&match_table.path) {
@@ -411,7 +454,7 @@ impl GlobalSearch {
             }
 
             let changed_files = changed_files.iter().map(|x| PathType::File(x.to_vec())).collect::<Vec<PathType>>();
-            self.update(pack_file, &changed_files);
+            self.update_with_schema(pack_file, &changed_files, Some(schema));
         }
 
         errors
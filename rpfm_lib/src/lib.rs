@@ -34,17 +34,25 @@ pub mod assembly_kit;
 pub mod common;
 pub mod dependencies;
 pub mod diagnostics;
+pub mod diff;
 pub mod config;
+pub mod context;
 pub mod games;
 pub mod global_search;
+pub mod load_order;
 pub mod packedfile;
 pub mod packfile;
+pub mod references;
 pub mod schema;
 pub mod settings;
 pub mod template;
+pub mod translation;
 pub mod updater;
 
 // Statics, so we don't need to pass them everywhere to use them.
+//
+// `SETTINGS`, `GAME_SELECTED` and `SCHEMA` are kept for compatibility. If you need to work with more than one game at the same time,
+// use a `GameContext` and the `_with_context` variants of the functions instead.
 lazy_static! {
 
     /// List of supported games and their configuration. Their key is what we know as `folder_name`, used to identify the game and
@@ -0,0 +1,115 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `LoadOrder` module, to make sure we follow the game's load order rules.
+!*/
+
+use std::collections::BTreeMap;
+
+use crate::packedfile::table::DecodedData;
+use crate::packfile::PFHVersion;
+use crate::packfile::packedfile::PackedFile;
+use crate::schema::{Definition, Field, FieldType, VersionedFile};
+use super::*;
+
+/// This function builds a PackFile of the provided type with the provided PackedFiles.
+fn build_pack_file(name: &str, pfh_file_type: PFHFileType, packed_files: &[(&str, Vec<u8>)]) -> PackFile {
+    let mut pack_file = PackFile::new_with_name(name, PFHVersion::PFH5);
+    pack_file.set_pfh_file_type(pfh_file_type);
+    for (path, data) in packed_files {
+        let mut packed_file = PackedFile::new(path.split('/').map(|x| x.to_owned()).collect(), name.to_owned());
+        packed_file.set_raw_data(data);
+        pack_file.add_packed_file(&packed_file, true).unwrap();
+    }
+    pack_file
+}
+
+/// This function builds the definition of the test table, with a key and a value field.
+fn build_definition() -> Definition {
+    let mut definition = Definition::new(1);
+    definition.get_ref_mut_fields().push(Field::new("key".to_owned(), FieldType::StringU8, true, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()));
+    definition.get_ref_mut_fields().push(Field::new("value".to_owned(), FieldType::I32, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()));
+    definition
+}
+
+/// This function builds an encoded test table with the provided keys.
+fn build_table(keys: &[&str]) -> Vec<u8> {
+    let mut table = DB::new("units_tables", None, &build_definition());
+    let entries = keys.iter().map(|x| vec![DecodedData::StringU8(x.to_string()), DecodedData::I32(0)]).collect::<Vec<Vec<DecodedData>>>();
+    table.set_table_data(&entries).unwrap();
+    table.save().unwrap()
+}
+
+#[test]
+fn test_load_order_files() {
+    let pack_files = vec![
+        build_pack_file("b_mod.pack", PFHFileType::Mod, &[("script/a.lua", vec![0]), ("script/b.lua", vec![0])]),
+        build_pack_file("data.pack", PFHFileType::Release, &[("script/a.lua", vec![0]), ("script/c.lua", vec![0])]),
+        build_pack_file("data_1.pack", PFHFileType::Patch, &[("script/c.lua", vec![0])]),
+        build_pack_file("a_mod.pack", PFHFileType::Mod, &[("script/b.lua", vec![0])]),
+        build_pack_file("movie.pack", PFHFileType::Movie, &[("script/a.lua", vec![0])]),
+    ];
+
+    let load_order = LoadOrder::new_from_packfiles(pack_files, None);
+    assert_eq!(load_order.packs.iter().map(|x| &*x.name).collect::<Vec<&str>>(), vec!["movie.pack", "a_mod.pack", "b_mod.pack", "data_1.pack", "data.pack"]);
+
+    // Conflicts between CA PackFiles, like script/c.lua, are not reported.
+    assert_eq!(load_order.file_conflicts, vec![
+        FileConflict {
+            path: "script/a.lua".to_owned(),
+            winner: "movie.pack".to_owned(),
+            overwritten: vec!["b_mod.pack".to_owned(), "data.pack".to_owned()],
+        },
+        FileConflict {
+            path: "script/b.lua".to_owned(),
+            winner: "a_mod.pack".to_owned(),
+            overwritten: vec!["b_mod.pack".to_owned()],
+        },
+    ]);
+    assert!(load_order.row_conflicts.is_empty());
+}
+
+#[test]
+fn test_load_order_rows() {
+    let mut schema = Schema::default();
+    schema.add_versioned_file(&VersionedFile::DB("units_tables".to_owned(), vec![build_definition()]));
+
+    let pack_files = vec![
+        build_pack_file("data.pack", PFHFileType::Release, &[("db/units_tables/data__", build_table(&["unit_a", "unit_b", "unit_c"]))]),
+        build_pack_file("my_mod.pack", PFHFileType::Mod, &[
+            ("db/units_tables/!my_mod", build_table(&["unit_a", "unit_d"])),
+            ("db/units_tables/zz_my_mod", build_table(&["unit_b", "unit_d", "unit_d"])),
+        ]),
+        build_pack_file("other_mod.pack", PFHFileType::Mod, &[("db/units_tables/!my_mod", build_table(&["unit_c"]))]),
+
+        // Files in the db folder that are not in a table folder must be ignored.
+        build_pack_file("broken_mod.pack", PFHFileType::Mod, &[
+            ("db/units_tables", build_table(&["unit_a"])),
+            ("db/units_tables/extra/broken", build_table(&["unit_a"])),
+        ]),
+    ];
+
+    let mut load_order = LoadOrder::new_from_packfiles(pack_files, Some(&schema));
+    assert!(load_order.errors.is_empty());
+
+    // The "!my_mod" table of other_mod.pack is overwritten by the one in my_mod.pack, so its rows don't count.
+    assert_eq!(load_order.file_conflicts.len(), 1);
+    assert_eq!(load_order.file_conflicts[0].winner, "my_mod.pack");
+    assert_eq!(load_order.row_conflicts.iter().map(|x| (&*x.key[0], &*x.winner.path, x.overwritten.len())).collect::<Vec<(&str, &str, usize)>>(), vec![
+        ("unit_a", "db/units_tables/!my_mod", 1),
+        ("unit_d", "db/units_tables/!my_mod", 1),
+        ("unit_b", "db/units_tables/data__", 1),
+    ]);
+
+    load_order.filter("UNIT_D");
+    assert_eq!(load_order.row_conflicts.len(), 1);
+    assert!(load_order.file_conflicts.is_empty());
+}
@@ -0,0 +1,334 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with all the code related to the `LoadOrder` analysis.
+
+This module contains the code needed to find out which PackedFiles and which DB rows win when multiple PackFiles are loaded together.
+The rules followed are the same the game uses:
+- PackFiles are loaded by type (`Boot`, `Release`, `Patch`, `Mod` and `Movie`). If two PackFiles of different types have the same
+  PackedFile, the one of the type loaded last wins. If both are of the same type, the one whose name comes first alphabetically wins.
+- Rows of DB Tables of the same type are loaded together, even if their tables have different names. If two rows have the same key,
+  the one in the table whose name comes first alphabetically wins.
+
+Conflicts only between CA PackFiles (`Boot`, `Release` and `Patch`) are not reported.
+!*/
+
+use rayon::prelude::*;
+use serde_derive::{Serialize, Deserialize};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+
+use rpfm_error::{Error, ErrorKind, Result};
+
+use crate::common::{get_game_selected_content_packfiles_paths, get_game_selected_data_packfiles_paths};
+use crate::packedfile::PackedFileType;
+use crate::packedfile::table::db::DB;
+use crate::packfile::{PackFile, PFHFileType};
+use crate::schema::Schema;
+
+#[cfg(test)]
+mod load_order_test;
+
+//-------------------------------------------------------------------------------//
+//                              Enums & Structs
+//-------------------------------------------------------------------------------//
+
+/// This struct contains the result of analysing the load order of a list of PackFiles.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoadOrder {
+
+    /// PackFiles analysed, sorted from the one with the highest priority to the one with the lowest.
+    pub packs: Vec<LoadOrderPack>,
+
+    /// PackedFiles present in more than one PackFile.
+    pub file_conflicts: Vec<FileConflict>,
+
+    /// DB rows with the same key in more than one table.
+    pub row_conflicts: Vec<RowConflict>,
+
+    /// PackFiles and tables that couldn't be analysed, with the reason why.
+    pub errors: Vec<(String, String)>,
+}
+
+/// This struct represents a PackFile in the load order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoadOrderPack {
+
+    /// Name of the PackFile.
+    pub name: String,
+
+    /// Path of the PackFile on disk.
+    pub path: PathBuf,
+
+    /// Type of the PackFile.
+    pub pack_type: String,
+}
+
+/// This struct represents a PackedFile present in more than one PackFile.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FileConflict {
+
+    /// Path of the PackedFile.
+    pub path: String,
+
+    /// Name of the PackFile whose PackedFile is the one loaded.
+    pub winner: String,
+
+    /// Names of the PackFiles whose PackedFile gets overwritten, in load order.
+    pub overwritten: Vec<String>,
+}
+
+/// This struct represents a key present in more than one table of the same type.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RowConflict {
+
+    /// Name of the table, like `units_tables`.
+    pub table: String,
+
+    /// Values of the key fields of the row.
+    pub key: Vec<String>,
+
+    /// Table whose row is the one loaded.
+    pub winner: RowSource,
+
+    /// Tables whose row gets overwritten, in load order.
+    pub overwritten: Vec<RowSource>,
+}
+
+/// This struct represents the table a row comes from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RowSource {
+
+    /// Name of the PackFile of the table.
+    pub pack: String,
+
+    /// Path of the table within the PackFile.
+    pub path: String,
+}
+
+//-------------------------------------------------------------------------------//
+//                             Implementations
+//-------------------------------------------------------------------------------//
+
+/// Implementation of `LoadOrder`.
+impl LoadOrder {
+
+    /// This function analyses the load order of all the PackFiles in the `data` and `content` folders of the game selected,
+    /// plus the provided mod PackFiles.
+    ///
+    /// If a `Schema` is provided, DB rows are analysed too.
+    pub fn new_from_game_selected(mod_paths: &[PathBuf], schema: Option<&Schema>) -> Result<Self> {
+        let mut paths = get_game_selected_data_packfiles_paths().ok_or(ErrorKind::GamePathNotConfigured)?;
+        paths.extend(get_game_selected_content_packfiles_paths().unwrap_or_default());
+        paths.extend_from_slice(mod_paths);
+
+        let mut known_paths = BTreeSet::new();
+        paths.retain(|x| known_paths.insert(x.to_path_buf()));
+        Ok(Self::new(&paths, schema))
+    }
+
+    /// This function analyses the load order of the provided PackFiles.
+    ///
+    /// If a `Schema` is provided, DB rows are analysed too. PackFiles that cannot be opened are reported in the errors list.
+    pub fn new(paths: &[PathBuf], schema: Option<&Schema>) -> Self {
+        let mut pack_files = vec![];
+        let mut errors = vec![];
+        for path in paths {
+            match PackFile::read(path, true) {
+                Ok(pack_file) => match pack_file.get_pfh_file_type() {
+                    PFHFileType::Other(_) => errors.push((path.to_string_lossy().to_string(), Error::from(ErrorKind::PackFileTypeUknown).to_terminal())),
+                    _ => pack_files.push(pack_file),
+                }
+                Err(error) => errors.push((path.to_string_lossy().to_string(), error.to_terminal())),
+            }
+        }
+
+        let mut load_order = Self::new_from_packfiles(pack_files, schema);
+        errors.append(&mut load_order.errors);
+        load_order.errors = errors;
+        load_order
+    }
+
+    /// This function analyses the load order of the provided, already opened, PackFiles.
+    pub fn new_from_packfiles(mut pack_files: Vec<PackFile>, schema: Option<&Schema>) -> Self {
+        pack_files.sort_by(|x, y| get_type_priority(y.get_pfh_file_type()).cmp(&get_type_priority(x.get_pfh_file_type())).then_with(|| x.get_file_name().cmp(&y.get_file_name())));
+
+        let mut load_order = Self {
+            packs: pack_files.iter().map(|x| LoadOrderPack {
+                name: x.get_file_name(),
+                path: x.get_file_path().to_path_buf(),
+                pack_type: x.get_pfh_file_type().to_string(),
+            }).collect(),
+            ..Self::default()
+        };
+
+        // Get, for each path, the index of all the PackFiles that contain it, in load order. The first one is the one loaded.
+        let mut paths: BTreeMap<Vec<String>, Vec<usize>> = BTreeMap::new();
+        for (index, pack_file) in pack_files.iter().enumerate() {
+            for packed_file in pack_file.get_ref_packed_files_all() {
+                paths.entry(packed_file.get_path().to_vec()).or_default().push(index);
+            }
+        }
+
+        for (path, indexes) in &paths {
+            if indexes.len() > 1 && indexes.iter().any(|x| !is_ca_pack(&pack_files[*x])) {
+                load_order.file_conflicts.push(FileConflict {
+                    path: path.join("/"),
+                    winner: load_order.packs[indexes[0]].name.to_owned(),
+                    overwritten: indexes[1..].iter().map(|x| load_order.packs[*x].name.to_owned()).collect(),
+                });
+            }
+        }
+
+        if let Some(schema) = schema {
+            load_order.analyse_rows(&pack_files, &paths, schema);
+        }
+
+        load_order
+    }
+
+    /// This function finds the keys present in more than one table of the same type.
+    ///
+    /// Only the tables loaded (not overwritten) are checked, and only table types touched by non-CA PackFiles.
+    fn analyse_rows(&mut self, pack_files: &[PackFile], paths: &BTreeMap<Vec<String>, Vec<usize>>, schema: &Schema) {
+
+        // Tables, grouped by type, as (table name, pack index, path). BTreeMap keeps them sorted by table name, which is their load order.
+        // Files in the db folder that are not in a table folder (db/table_type/table_name) are not loaded as tables by the game, so skip them.
+        let mut tables: BTreeMap<String, Vec<(String, usize, Vec<String>)>> = BTreeMap::new();
+        for (path, indexes) in paths {
+            if path.len() == 3 && PackedFileType::get_packed_file_type(path) == PackedFileType::DB {
+                tables.entry(path[1].to_owned()).or_default().push((path[2].to_owned(), indexes[0], path.to_vec()));
+            }
+        }
+        tables.retain(|_, x| x.len() > 1 && x.iter().any(|(_, index, _)| !is_ca_pack(&pack_files[*index])));
+
+        let results = tables.par_iter().map(|(table_name, sources)| {
+            let mut errors = vec![];
+            let mut keys: HashMap<Vec<String>, Vec<usize>> = HashMap::new();
+            let mut key_order = vec![];
+            for (source_index, (_, pack_index, path)) in sources.iter().enumerate() {
+                let pack_file = &pack_files[*pack_index];
+                let table = pack_file.get_ref_packed_file_by_path(path)
+                    .ok_or_else(|| ErrorKind::PackedFileNotFound.into())
+                    .and_then(|x| x.get_raw_data())
+                    .and_then(|x| DB::read(&x, table_name, schema, false));
+
+                match table {
+                    Ok(table) => {
+                        let fields = table.get_ref_definition().get_fields_processed();
+                        let key_columns = fields.iter().enumerate().filter(|(_, x)| x.get_is_key()).map(|(x, _)| x).collect::<Vec<usize>>();
+                        if key_columns.is_empty() { continue; }
+
+                        for row in table.get_ref_table_data() {
+                            let key = key_columns.iter().map(|x| row[*x].data_to_string()).collect::<Vec<String>>();
+                            let row_sources = keys.entry(key.to_vec()).or_default();
+                            if row_sources.is_empty() {
+                                key_order.push(key);
+                            }
+
+                            // Duplicated keys within the same table are not a load order problem, so we ignore them.
+                            if !row_sources.contains(&source_index) {
+                                row_sources.push(source_index);
+                            }
+                        }
+                    }
+                    Err(error) => errors.push((format!("{}/{}", pack_file.get_file_name(), path.join("/")), error.to_terminal())),
+                }
+            }
+
+            let get_source = |index: usize| RowSource {
+                pack: pack_files[sources[index].1].get_file_name(),
+                path: sources[index].2.join("/"),
+            };
+
+            let conflicts = key_order.into_iter()
+                .filter_map(|key| {
+                    let row_sources = &keys[&key];
+                    if row_sources.len() > 1 && row_sources.iter().any(|x| !is_ca_pack(&pack_files[sources[*x].1])) {
+                        Some(RowConflict {
+                            table: table_name.to_owned(),
+                            key,
+                            winner: get_source(row_sources[0]),
+                            overwritten: row_sources[1..].iter().map(|x| get_source(*x)).collect(),
+                        })
+                    } else { None }
+                })
+                .collect::<Vec<RowConflict>>();
+
+            (conflicts, errors)
+        }).collect::<Vec<(Vec<RowConflict>, Vec<(String, String)>)>>();
+
+        for (mut conflicts, mut errors) in results {
+            self.row_conflicts.append(&mut conflicts);
+            self.errors.append(&mut errors);
+        }
+    }
+
+    /// This function removes all the conflicts whose path, table name, or key doesn't contain the provided text.
+    pub fn filter(&mut self, text: &str) {
+        let text = text.to_lowercase();
+        self.file_conflicts.retain(|x| x.path.to_lowercase().contains(&text));
+        self.row_conflicts.retain(|x| x.table.to_lowercase().contains(&text) || x.key.iter().any(|x| x.to_lowercase().contains(&text)));
+    }
+}
+
+/// Display implementation of `LoadOrder`, to get a human-readable report of the conflicts.
+impl fmt::Display for LoadOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Load order:")?;
+        for (index, pack) in self.packs.iter().enumerate() {
+            writeln!(f, "    {}. {} ({})", index + 1, pack.name, pack.pack_type)?;
+        }
+
+        writeln!(f, "\nFile conflicts: {}", self.file_conflicts.len())?;
+        for conflict in &self.file_conflicts {
+            writeln!(f, "    {}: {} wins over {}", conflict.path, conflict.winner, conflict.overwritten.join(", "))?;
+        }
+
+        writeln!(f, "\nRow conflicts: {}", self.row_conflicts.len())?;
+        for conflict in &self.row_conflicts {
+            let overwritten = conflict.overwritten.iter().map(|x| format!("{}/{}", x.pack, x.path)).collect::<Vec<String>>();
+            writeln!(f, "    {} [{}]: {}/{} wins over {}", conflict.table, conflict.key.join(", "), conflict.winner.pack, conflict.winner.path, overwritten.join(", "))?;
+        }
+
+        if !self.errors.is_empty() {
+            writeln!(f, "\nErrors: {}", self.errors.len())?;
+            for (path, error) in &self.errors {
+                writeln!(f, "    {}: {}", path, error)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//-------------------------------------------------------------------------------//
+//                             Helper functions
+//-------------------------------------------------------------------------------//
+
+/// This function returns the priority of a PackFile type. PackedFiles from PackFiles with higher priority overwrite the ones with lower priority.
+fn get_type_priority(pfh_file_type: PFHFileType) -> u32 {
+    match pfh_file_type {
+        PFHFileType::Boot => 1,
+        PFHFileType::Release => 2,
+        PFHFileType::Patch => 3,
+        PFHFileType::Mod => 4,
+        PFHFileType::Movie => 5,
+        PFHFileType::Other(_) => 0,
+    }
+}
+
+/// This function returns if the provided PackFile is a CA PackFile.
+fn is_ca_pack(pack_file: &PackFile) -> bool {
+    matches!(pack_file.get_pfh_file_type(), PFHFileType::Boot | PFHFileType::Release | PFHFileType::Patch)
+}
@@ -0,0 +1,286 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `ESF` module, to make sure we read and save ESF files without altering them.
+!*/
+
+use super::*;
+
+/// This function builds a record with one node of each type.
+///
+/// Values that cannot be optimized are always marked as not optimized, as that's how they're read back.
+fn build_record(name: &str, optimized: bool) -> RecordNode {
+    let mut record = RecordNode::new(name, 2);
+    let mut block = RecordBlockNode::new("FACTIONS", 20);
+    block.get_ref_mut_children().push(vec![NodeType::Ascii("wh_main_emp_empire".to_owned()), NodeType::Utf16("Karl Franz ".to_owned())]);
+    block.get_ref_mut_children().push(vec![NodeType::Ascii("wh_main_dwf_dwarfs".to_owned()), NodeType::Utf16("Thorgrim".to_owned())]);
+    block.get_ref_mut_children().push(vec![]);
+
+    let mut child = RecordNode::new("CAMPAIGN_ENV", 0);
+    child.get_ref_mut_children().push(NodeType::Coord2d(Coordinates2D { x: 1.5, y: -2.25 }));
+
+    *record.get_ref_mut_children() = vec![
+        NodeType::Bool(OptimizedNode { value: true, optimized }),
+        NodeType::Bool(OptimizedNode { value: false, optimized }),
+        NodeType::I8(-5),
+        NodeType::I16(-300),
+        NodeType::I32(OptimizedNode { value: 0, optimized }),
+        NodeType::I32(OptimizedNode { value: -100, optimized }),
+        NodeType::I32(OptimizedNode { value: 20_000, optimized }),
+        NodeType::I32(OptimizedNode { value: -4_000_000, optimized }),
+        NodeType::I32(OptimizedNode { value: i32::MIN, optimized: false }),
+        NodeType::I64(-1_234_567_890_123),
+        NodeType::U8(200),
+        NodeType::U16(60_000),
+        NodeType::U32(OptimizedNode { value: 0, optimized }),
+        NodeType::U32(OptimizedNode { value: 1, optimized }),
+        NodeType::U32(OptimizedNode { value: 255, optimized }),
+        NodeType::U32(OptimizedNode { value: 65_535, optimized }),
+        NodeType::U32(OptimizedNode { value: 0x00ab_cdef, optimized }),
+        NodeType::U32(OptimizedNode { value: u32::MAX, optimized: false }),
+        NodeType::U64(u64::MAX),
+        NodeType::F32(OptimizedNode { value: 0.0, optimized }),
+        NodeType::F32(OptimizedNode { value: 0.1, optimized: false }),
+        NodeType::F64(-123.456),
+        NodeType::Coord3d(Coordinates3D { x: 1.0, y: 2.0, z: -3.5 }),
+        NodeType::Utf16("Altdorf — Reikland".to_owned()),
+        NodeType::Ascii("wh_main_reikland_altdorf".to_owned()),
+        NodeType::Ascii(String::new()),
+        NodeType::Angle(16_384),
+        NodeType::BoolArray(vec![true, false, true]),
+        NodeType::I8Array(vec![-1, 0, 1]),
+        NodeType::I16Array(vec![-1000, 1000]),
+        NodeType::I32Array(OptimizedNode { value: vec![-1, 1, 100], optimized }),
+        NodeType::I32Array(OptimizedNode { value: vec![-1, 30_000], optimized }),
+        NodeType::I32Array(OptimizedNode { value: vec![-1, 8_000_000], optimized }),
+        NodeType::I32Array(OptimizedNode { value: vec![i32::MAX], optimized: false }),
+        NodeType::I64Array(vec![i64::MIN, i64::MAX]),
+        NodeType::U8Array((0..200).collect()),
+        NodeType::U16Array(vec![0, 65_535]),
+        NodeType::U32Array(OptimizedNode { value: vec![1, 2, 3], optimized }),
+        NodeType::U32Array(OptimizedNode { value: vec![1, 60_000], optimized }),
+        NodeType::U32Array(OptimizedNode { value: vec![1, 0x00ff_ffff], optimized }),
+        NodeType::U32Array(OptimizedNode { value: vec![u32::MAX], optimized: false }),
+        NodeType::U64Array(vec![]),
+        NodeType::F32Array(vec![0.5, -0.5]),
+        NodeType::F64Array(vec![1e100, -1e-100]),
+        NodeType::Coord2dArray(vec![Coordinates2D { x: 0.0, y: 1.0 }]),
+        NodeType::Coord3dArray(vec![Coordinates3D { x: 0.0, y: 1.0, z: 2.0 }, Coordinates3D::default()]),
+        NodeType::Utf16Array(vec!["Karl Franz ".to_owned(), "Thorgrim".to_owned()]),
+        NodeType::AsciiArray(vec!["wh_main_emp_empire".to_owned(), "wh_main_reikland_altdorf".to_owned()]),
+        NodeType::AngleArray(vec![0, 32_768]),
+        NodeType::Record(child),
+        NodeType::RecordBlock(block),
+    ];
+
+    record
+}
+
+/// This function checks that the provided ESF is saved, read and saved again without changes.
+fn check_round_trip(esf: &ESF) -> ESF {
+    let data = esf.save().unwrap();
+    let esf_decoded = ESF::read(&data).unwrap();
+    assert_eq!(esf_decoded.get_ref_root_node(), esf.get_ref_root_node());
+    assert_eq!(esf_decoded.save().unwrap(), data);
+    esf_decoded
+}
+
+/// Test to make sure all the signatures are read and saved without changes.
+#[test]
+fn test_esf_round_trip() {
+    for signature in &[ESFSignature::ABCD, ESFSignature::ABCE, ESFSignature::ABCF, ESFSignature::ABCA] {
+        let mut esf = ESF::new(*signature, build_record("CAMPAIGN_STARTPOS", *signature == ESFSignature::ABCA));
+        esf.set_creation_date(1_600_000_000);
+        let esf_decoded = check_round_trip(&esf);
+        assert_eq!(esf_decoded.get_signature(), *signature);
+        assert_eq!(esf_decoded.get_creation_date(), if *signature == ESFSignature::ABCD { 0 } else { 1_600_000_000 });
+    }
+
+    // Optimized nodes should be saved as normal nodes in files that don't support them.
+    let esf = ESF::new(ESFSignature::ABCF, build_record("CAMPAIGN_STARTPOS", true));
+    let esf_decoded = ESF::read(&esf.save().unwrap()).unwrap();
+    assert_eq!(esf_decoded.get_ref_root_node(), &NodeType::Record(build_record("CAMPAIGN_STARTPOS", false)));
+
+    // Optimized nodes should be smaller than the normal ones.
+    let esf_optimized = ESF::new(ESFSignature::ABCA, build_record("CAMPAIGN_STARTPOS", true));
+    let esf_not_optimized = ESF::new(ESFSignature::ABCA, build_record("CAMPAIGN_STARTPOS", false));
+    assert!(esf_optimized.save().unwrap().len() < esf_not_optimized.save().unwrap().len());
+}
+
+/// Test to make sure an ABCA file made by hand is read correctly and saved back without changes.
+#[test]
+fn test_esf_read_abca() {
+    let mut data = vec![];
+    data.encode_integer_u32(0xABCA);
+    data.encode_integer_u32(0);
+    data.encode_integer_u32(1_234);
+    data.encode_integer_u32(33);
+
+    // Root record: compact header with version 1 and name index 0, then its size as CAULEB128.
+    data.extend_from_slice(&[0x82, 0x00, 0x0e]);
+    data.push(U32_ONE);
+    data.extend_from_slice(&[I32_BYTE, 0xff]);
+    data.push(BOOL_TRUE);
+    data.extend_from_slice(&[U32_BYTE_ARRAY, 0x03, 0x01, 0x02, 0x03]);
+    data.push(ASCII);
+    data.encode_integer_u32(7);
+
+    // String tables.
+    data.encode_integer_u16(1);
+    data.encode_packedfile_string_u8("ROOT");
+    data.encode_integer_u32(0);
+    data.encode_integer_u32(1);
+    data.encode_packedfile_string_u8("abc");
+    data.encode_integer_u32(7);
+
+    let esf = ESF::read(&data).unwrap();
+    assert_eq!(esf.get_creation_date(), 1_234);
+
+    let mut root = RecordNode::new("ROOT", 1);
+    *root.get_ref_mut_children() = vec![
+        NodeType::U32(OptimizedNode { value: 1, optimized: true }),
+        NodeType::I32(OptimizedNode { value: -1, optimized: true }),
+        NodeType::Bool(OptimizedNode { value: true, optimized: true }),
+        NodeType::U32Array(OptimizedNode { value: vec![1, 2, 3], optimized: true }),
+        NodeType::Ascii("abc".to_owned()),
+    ];
+    assert_eq!(esf.get_ref_root_node(), &NodeType::Record(root));
+    assert_eq!(esf.save().unwrap(), data);
+}
+
+/// Test to make sure an ABCA file with the layout of a startpos is read correctly and saved back without changes.
+#[test]
+fn test_esf_read_file() {
+    let data = std::fs::read("../test_files/ESF_ABCA_test.esf").unwrap();
+    assert_eq!(&data[0..4], &[0xCA, 0xAB, 0x00, 0x00]);
+    assert!(ESF::is_esf(&data));
+
+    let esf = ESF::read(&data).unwrap();
+    assert_eq!(esf.get_signature(), ESFSignature::ABCA);
+    assert_eq!(esf.get_creation_date(), 1_600_000_000);
+
+    let mut factions = RecordBlockNode::new("FACTIONS", 0);
+    factions.get_ref_mut_children().push(vec![NodeType::Ascii("wh2_main_hef_eataine".to_owned()), NodeType::Bool(OptimizedNode { value: true, optimized: true })]);
+    factions.get_ref_mut_children().push(vec![NodeType::Ascii("wh2_main_lzd_hexoatl".to_owned()), NodeType::Bool(OptimizedNode { value: false, optimized: true })]);
+
+    let mut root = RecordNode::new("CAMPAIGN_STARTPOS", 1);
+    *root.get_ref_mut_children() = vec![
+        NodeType::Ascii("wh2_main_great_vortex".to_owned()),
+        NodeType::U32(OptimizedNode { value: 42, optimized: true }),
+        NodeType::RecordBlock(factions),
+        NodeType::Utf16("Eataine".to_owned()),
+    ];
+    assert_eq!(esf.get_ref_root_node(), &NodeType::Record(root));
+    assert_eq!(esf.save().unwrap(), data);
+
+    // The signature is a number, not text.
+    let mut data_text_signature = data.to_vec();
+    data_text_signature[0..4].copy_from_slice(b"ABCA");
+    assert!(!ESF::is_esf(&data_text_signature));
+}
+
+/// Test to make sure broken ESF files return an error instead of crashing.
+#[test]
+fn test_esf_read_broken() {
+
+    // Record block with more children than bytes in the file.
+    let mut data = vec![];
+    data.encode_integer_u32(0xABCE);
+    data.encode_integer_u32(0);
+    data.encode_integer_u32(0);
+    data.encode_integer_u32(28);
+    data.push(RECORD_BLOCK);
+    data.encode_integer_u16(0);
+    data.push(0);
+    data.encode_integer_u32(28);
+    data.encode_integer_u32(u32::MAX);
+    data.encode_integer_u16(1);
+    data.encode_packedfile_string_u8("ROOT");
+    assert!(ESF::read(&data).is_err());
+
+    // Records nested too deep.
+    let build_nested_records = |depth: usize| {
+        let mut record = RecordNode::new("ROOT", 0);
+        for _ in 0..depth {
+            let mut parent = RecordNode::new("ROOT", 0);
+            parent.get_ref_mut_children().push(NodeType::Record(record));
+            record = parent;
+        }
+        ESF::new(ESFSignature::ABCE, record)
+    };
+
+    check_round_trip(&build_nested_records(MAX_NODE_DEPTH));
+    assert!(ESF::read(&build_nested_records(MAX_NODE_DEPTH + 1).save().unwrap()).is_err());
+}
+
+/// Test to make sure CAULEB128 integers are read and written correctly.
+#[test]
+fn test_esf_cauleb128() {
+    for value in &[0, 1, 0x7f, 0x80, 0x3fff, 0x4000, 0x00ff_ffff, u32::MAX] {
+        let mut data = vec![];
+        encode_cauleb128(&mut data, *value);
+        let mut offset = 0;
+        assert_eq!(decode_cauleb128(&data, &mut offset).unwrap(), *value);
+        assert_eq!(offset, data.len());
+    }
+
+    let mut data = vec![];
+    encode_cauleb128(&mut data, 0x80);
+    assert_eq!(data, vec![0x81, 0x00]);
+}
+
+/// Test to make sure compressed data is decompressed and compressed back correctly.
+#[test]
+fn test_esf_compressed_data() {
+    let esf_inner = ESF::new(ESFSignature::ABCA, build_record("CAMPAIGN_SAVE_GAME", true));
+
+    let mut info = RecordNode::new(COMPRESSED_DATA_INFO_TAG, 0);
+    *info.get_ref_mut_children() = vec![NodeType::U32(OptimizedNode::default()), NodeType::U8Array(vec![])];
+    let mut compressed = RecordNode::new(COMPRESSED_DATA_TAG, 0);
+    *compressed.get_ref_mut_children() = vec![NodeType::U8Array(vec![]), NodeType::Record(info)];
+    assert!(compressed.is_compressed());
+    compressed.set_compressed_data(&esf_inner).unwrap();
+
+    let esf_inner_decoded = compressed.get_compressed_data().unwrap();
+    assert_eq!(esf_inner_decoded.get_ref_root_node(), esf_inner.get_ref_root_node());
+
+    let mut root = RecordNode::new("ROOT", 0);
+    root.get_ref_mut_children().push(NodeType::Record(compressed));
+    let esf = ESF::new(ESFSignature::ABCA, root);
+    let esf_decoded = check_round_trip(&esf);
+    if let NodeType::Record(root) = esf_decoded.get_ref_root_node() {
+        if let Some(NodeType::Record(compressed)) = root.get_ref_children().first() {
+            assert_eq!(compressed.get_compressed_data().unwrap().get_ref_root_node(), esf_inner.get_ref_root_node());
+        } else { panic!("Compressed record not found.") }
+    } else { panic!("Root record not found.") }
+
+    assert!(RecordNode::new("ROOT", 0).get_compressed_data().is_err());
+}
+
+/// Test to make sure ESF files are exported to JSON/XML and imported back without changes.
+#[test]
+fn test_esf_json_xml() {
+    for signature in &[ESFSignature::ABCE, ESFSignature::ABCA] {
+        let mut esf = ESF::new(*signature, build_record("CAMPAIGN_STARTPOS", true));
+        esf.set_creation_date(1_600_000_000);
+        let esf = ESF::read(&esf.save().unwrap()).unwrap();
+
+        let esf_json = ESF::from_json(&esf.to_json().unwrap()).unwrap();
+        assert_eq!(esf_json, esf);
+
+        let esf_xml = ESF::from_xml(&esf.to_xml().unwrap()).unwrap();
+        assert_eq!(esf_xml, esf);
+        assert_eq!(esf_xml.save().unwrap(), esf.save().unwrap());
+    }
+
+    assert!(ESF::from_xml("<esf signature=\"ABCA\" unknown_1=\"0\" creation_date=\"0\"><record_names/></esf>").is_err());
+    assert!(ESF::from_xml("<esf signature=\"ABCA\" unknown_1=\"0\" creation_date=\"0\"><u32>a</u32></esf>").is_err());
+}
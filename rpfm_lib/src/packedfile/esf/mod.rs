@@ -0,0 +1,1771 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with all the code to interact with ESF PackedFiles.
+
+ESF (Empire Serialization Format) is CA's binary format for storing trees of data. It's used by startpos files,
+save games and a few other files. Its structure is:
+- Header: signature, and (except in ABCD files) an unknown u32 and the creation date.
+- Offset of the string tables.
+- The root node, which is always a record containing the rest of the tree.
+- The string tables: the names of the records and, since ABCF, the UTF-16 and ASCII strings used by the nodes.
+
+Each node is a type byte followed by its data. There are three kinds of nodes:
+- Primitives: booleans, integers, floats, coordinates, strings and angles.
+- Arrays of primitives.
+- Records: named, versioned groups of nodes. Record blocks are records containing a list of groups of nodes.
+
+ABCA files also contain "optimized" versions of some primitives and arrays, and compact record headers. We keep track
+of which nodes were optimized, so they're saved back as they came.
+
+Some records contain LZMA-compressed data, which is itself an ESF file. We keep them compressed, so they're saved back
+as they came, but they can be decompressed and compressed through `RecordNode::get_compressed_data` and `RecordNode::set_compressed_data`.
+!*/
+
+use serde_derive::{Serialize, Deserialize};
+use xml::reader::{ParserConfig, XmlEvent as ReaderEvent};
+use xml::writer::{EmitterConfig, XmlEvent as WriterEvent};
+use xz2::read::{XzDecoder, XzEncoder};
+use xz2::stream::{LzmaOptions, Stream};
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::io::Read;
+use std::str::FromStr;
+
+use rpfm_error::{Error, ErrorKind, Result};
+
+use crate::common::{decoder::Decoder, encoder::Encoder};
+
+#[cfg(test)]
+mod esf_test;
+
+/// Extension used by ESF PackedFiles.
+pub const EXTENSION: &str = ".esf";
+
+/// Name of the record containing compressed data.
+pub const COMPRESSED_DATA_TAG: &str = "COMPRESSED_DATA";
+
+/// Name of the record containing the info needed to decompress the data of a `COMPRESSED_DATA` record.
+pub const COMPRESSED_DATA_INFO_TAG: &str = "COMPRESSED_DATA_INFO";
+
+/// Preset used by CA for the compressed data within ESF files.
+const LZMA_PRESET: u32 = 3;
+
+/// Size of the properties byte plus the dictionary size in a LZMA Alone header.
+const LZMA_ALONE_PROPERTIES_SIZE: usize = 5;
+
+/// Size of the header of a standard LZMA Alone file: properties byte, dictionary size (u32) and uncompressed size (u64).
+const LZMA_ALONE_HEADER_SIZE: usize = 13;
+
+// Primitive nodes.
+const BOOL: u8 = 0x01;
+const I8: u8 = 0x02;
+const I16: u8 = 0x03;
+const I32: u8 = 0x04;
+const I64: u8 = 0x05;
+const U8: u8 = 0x06;
+const U16: u8 = 0x07;
+const U32: u8 = 0x08;
+const U64: u8 = 0x09;
+const F32: u8 = 0x0a;
+const F64: u8 = 0x0b;
+const COORD_2D: u8 = 0x0c;
+const COORD_3D: u8 = 0x0d;
+const UTF16: u8 = 0x0e;
+const ASCII: u8 = 0x0f;
+const ANGLE: u8 = 0x10;
+
+// Optimized primitive nodes. ABCA only.
+const BOOL_TRUE: u8 = 0x12;
+const BOOL_FALSE: u8 = 0x13;
+const U32_ZERO: u8 = 0x14;
+const U32_ONE: u8 = 0x15;
+const U32_BYTE: u8 = 0x16;
+const U32_16BIT: u8 = 0x17;
+const U32_24BIT: u8 = 0x18;
+const I32_ZERO: u8 = 0x19;
+const I32_BYTE: u8 = 0x1a;
+const I32_16BIT: u8 = 0x1b;
+const I32_24BIT: u8 = 0x1c;
+const F32_ZERO: u8 = 0x1d;
+
+// Array nodes. Their type is the type of their primitive plus this.
+const ARRAY: u8 = 0x40;
+
+// Optimized array nodes. ABCA only.
+const U32_BYTE_ARRAY: u8 = 0x56;
+const U32_16BIT_ARRAY: u8 = 0x57;
+const U32_24BIT_ARRAY: u8 = 0x58;
+const I32_BYTE_ARRAY: u8 = 0x5a;
+const I32_16BIT_ARRAY: u8 = 0x5b;
+const I32_24BIT_ARRAY: u8 = 0x5c;
+
+// Record nodes.
+const RECORD: u8 = 0x80;
+const RECORD_BLOCK: u8 = 0x81;
+
+// Flags of the compact record headers. ABCA only.
+const RECORD_FLAG_BLOCK: u8 = 0x40;
+const RECORD_FLAG_LONG_INFO: u8 = 0x20;
+
+/// Max amount of nested records we decode, so broken files cannot overflow the stack.
+const MAX_NODE_DEPTH: usize = 128;
+
+//---------------------------------------------------------------------------//
+//                              Enum & Structs
+//---------------------------------------------------------------------------//
+
+/// This holds an entire ESF PackedFile decoded in memory.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct ESF {
+
+    /// Signature of the file. It determines how the nodes are encoded.
+    signature: ESFSignature,
+
+    /// Unknown value of the header. Not present in ABCD files.
+    unknown_1: u32,
+
+    /// Creation date of the file. Not present in ABCD files.
+    creation_date: u32,
+
+    /// Root node of the file. Should always be a record.
+    root_node: NodeType,
+
+    /// Names of the records, in the order they're in the file. New names are added at the end when saving.
+    record_names: Vec<String>,
+
+    /// UTF-16 strings of the file, by index. Only used since ABCF. New strings are added at the end when saving.
+    utf16_strings: BTreeMap<u32, String>,
+
+    /// ASCII strings of the file, by index. Only used since ABCF. New strings are added at the end when saving.
+    ascii_strings: BTreeMap<u32, String>,
+}
+
+/// This enum represents the different signatures (versions) of the ESF format.
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum ESFSignature {
+
+    /// Oldest version. No header data, strings inline.
+    ABCD,
+
+    /// Like ABCD, with the unknown value and creation date in the header.
+    ABCE,
+
+    /// Like ABCE, but the strings are stored in tables at the end of the file.
+    ABCF,
+
+    /// Like ABCF, with optimized nodes and compact record headers.
+    ABCA,
+}
+
+/// This enum represents a node of an ESF file.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum NodeType {
+    Bool(OptimizedNode<bool>),
+    I8(i8),
+    I16(i16),
+    I32(OptimizedNode<i32>),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(OptimizedNode<u32>),
+    U64(u64),
+    F32(OptimizedNode<f32>),
+    F64(f64),
+    Coord2d(Coordinates2D),
+    Coord3d(Coordinates3D),
+    Utf16(String),
+    Ascii(String),
+    Angle(u16),
+    BoolArray(Vec<bool>),
+    I8Array(Vec<i8>),
+    I16Array(Vec<i16>),
+    I32Array(OptimizedNode<Vec<i32>>),
+    I64Array(Vec<i64>),
+    U8Array(Vec<u8>),
+    U16Array(Vec<u16>),
+    U32Array(OptimizedNode<Vec<u32>>),
+    U64Array(Vec<u64>),
+    F32Array(Vec<f32>),
+    F64Array(Vec<f64>),
+    Coord2dArray(Vec<Coordinates2D>),
+    Coord3dArray(Vec<Coordinates3D>),
+    Utf16Array(Vec<String>),
+    AsciiArray(Vec<String>),
+    AngleArray(Vec<u16>),
+    Record(RecordNode),
+    RecordBlock(RecordBlockNode),
+}
+
+/// This struct represents a node that can be saved in an optimized way in ABCA files.
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OptimizedNode<T> {
+
+    /// Value of the node.
+    pub value: T,
+
+    /// If the node should be saved in its optimized form. Ignored in files other than ABCA.
+    pub optimized: bool,
+}
+
+/// This struct represents a 2D coordinate.
+#[derive(PartialEq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Coordinates2D {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// This struct represents a 3D coordinate.
+#[derive(PartialEq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Coordinates3D {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+/// This struct represents a record: a named and versioned group of nodes.
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RecordNode {
+
+    /// Name of the record.
+    name: String,
+
+    /// Version of the record.
+    version: u8,
+
+    /// Nodes within the record.
+    children: Vec<NodeType>,
+}
+
+/// This struct represents a record block: a named and versioned list of groups of nodes.
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RecordBlockNode {
+
+    /// Name of the record block.
+    name: String,
+
+    /// Version of the record block.
+    version: u8,
+
+    /// Groups of nodes within the record block.
+    children: Vec<Vec<NodeType>>,
+}
+
+/// This struct contains the data needed to decode the nodes of an ESF file.
+struct NodeReader<'a> {
+    data: &'a [u8],
+    signature: ESFSignature,
+    record_names: &'a [String],
+    utf16_strings: &'a BTreeMap<u32, String>,
+    ascii_strings: &'a BTreeMap<u32, String>,
+}
+
+/// This struct contains the data needed to encode the nodes of an ESF file.
+///
+/// The string tables start as the ones of the file, and get the new strings added at the end.
+struct NodeWriter {
+    signature: ESFSignature,
+    record_names: Vec<String>,
+    record_name_indexes: HashMap<String, u16>,
+    utf16_strings: BTreeMap<u32, String>,
+    utf16_string_indexes: HashMap<String, u32>,
+    ascii_strings: BTreeMap<u32, String>,
+    ascii_string_indexes: HashMap<String, u32>,
+}
+
+/// This struct represents a generic XML element, used to import ESF files from XML.
+struct XmlElement {
+    name: String,
+    attributes: HashMap<String, String>,
+    text: String,
+    children: Vec<XmlElement>,
+}
+
+//---------------------------------------------------------------------------//
+//                           Implementation of ESF
+//---------------------------------------------------------------------------//
+
+/// Implementation of `ESF`.
+impl ESF {
+
+    /// This function creates a new empty `ESF` with the provided signature and root node.
+    pub fn new(signature: ESFSignature, root_node: RecordNode) -> Self {
+        Self {
+            signature,
+            unknown_1: 0,
+            creation_date: 0,
+            root_node: NodeType::Record(root_node),
+            record_names: vec![],
+            utf16_strings: BTreeMap::new(),
+            ascii_strings: BTreeMap::new(),
+        }
+    }
+
+    /// This function returns if the provided data corresponds to an ESF file or not.
+    pub fn is_esf(data: &[u8]) -> bool {
+        ESFSignature::read(data).is_ok()
+    }
+
+    /// This function creates an `ESF` from a `&[u8]`.
+    pub fn read(packed_file_data: &[u8]) -> Result<Self> {
+        let signature = ESFSignature::read(packed_file_data)?;
+        let mut offset = 4;
+
+        let (unknown_1, creation_date) = if signature == ESFSignature::ABCD { (0, 0) } else {
+            let unknown_1 = packed_file_data.decode_packedfile_integer_u32(offset, &mut offset)?;
+            let creation_date = packed_file_data.decode_packedfile_integer_u32(offset, &mut offset)?;
+            (unknown_1, creation_date)
+        };
+
+        let string_tables_offset = packed_file_data.decode_packedfile_integer_u32(offset, &mut offset)? as usize;
+        let mut tables_offset = string_tables_offset;
+
+        let record_names_count = packed_file_data.decode_packedfile_integer_u16(tables_offset, &mut tables_offset)?;
+        let mut record_names = Vec::with_capacity(record_names_count as usize);
+        for _ in 0..record_names_count {
+            record_names.push(decode_string_ascii(packed_file_data, &mut tables_offset)?);
+        }
+
+        let mut utf16_strings = BTreeMap::new();
+        let mut ascii_strings = BTreeMap::new();
+        if signature.has_string_tables() {
+            let utf16_strings_count = packed_file_data.decode_packedfile_integer_u32(tables_offset, &mut tables_offset)?;
+            for _ in 0..utf16_strings_count {
+                let string = packed_file_data.decode_packedfile_string_u16(tables_offset, &mut tables_offset)?;
+                let index = packed_file_data.decode_packedfile_integer_u32(tables_offset, &mut tables_offset)?;
+                utf16_strings.insert(index, string);
+            }
+
+            let ascii_strings_count = packed_file_data.decode_packedfile_integer_u32(tables_offset, &mut tables_offset)?;
+            for _ in 0..ascii_strings_count {
+                let string = decode_string_ascii(packed_file_data, &mut tables_offset)?;
+                let index = packed_file_data.decode_packedfile_integer_u32(tables_offset, &mut tables_offset)?;
+                ascii_strings.insert(index, string);
+            }
+        }
+
+        if tables_offset != packed_file_data.len() {
+            return Err(ErrorKind::ESFDecode(format!("There are {} bytes left after the string tables.", packed_file_data.len() as isize - tables_offset as isize)).into());
+        }
+
+        let reader = NodeReader {
+            data: &packed_file_data[..string_tables_offset],
+            signature,
+            record_names: &record_names,
+            utf16_strings: &utf16_strings,
+            ascii_strings: &ascii_strings,
+        };
+
+        let root_node = reader.read_node(&mut offset, 0)?;
+        if offset != string_tables_offset {
+            return Err(ErrorKind::ESFDecode(format!("The root node ends at byte {}, but the string tables start at byte {}.", offset, string_tables_offset)).into());
+        }
+
+        Ok(Self {
+            signature,
+            unknown_1,
+            creation_date,
+            root_node,
+            record_names,
+            utf16_strings,
+            ascii_strings,
+        })
+    }
+
+    /// This function takes an `ESF` and encodes it to `Vec<u8>`.
+    pub fn save(&self) -> Result<Vec<u8>> {
+        let mut writer = NodeWriter::new(self);
+        let mut packed_file = vec![];
+        packed_file.encode_integer_u32(self.signature.get_magic_number());
+        if self.signature != ESFSignature::ABCD {
+            packed_file.encode_integer_u32(self.unknown_1);
+            packed_file.encode_integer_u32(self.creation_date);
+        }
+
+        // Placeholder for the string tables offset.
+        let string_tables_offset_position = packed_file.len();
+        packed_file.encode_integer_u32(0);
+        writer.write_node(&mut packed_file, &self.root_node)?;
+
+        let string_tables_offset = packed_file.len() as u32;
+        packed_file[string_tables_offset_position..string_tables_offset_position + 4].copy_from_slice(&string_tables_offset.to_le_bytes());
+
+        if writer.record_names.len() > u16::MAX as usize {
+            return Err(ErrorKind::ESFEncode("There are too many record names.".to_owned()).into());
+        }
+
+        packed_file.encode_integer_u16(writer.record_names.len() as u16);
+        for name in &writer.record_names {
+            encode_string_ascii(&mut packed_file, name);
+        }
+
+        if self.signature.has_string_tables() {
+            packed_file.encode_integer_u32(writer.utf16_strings.len() as u32);
+            for (index, string) in &writer.utf16_strings {
+                packed_file.encode_packedfile_string_u16(string);
+                packed_file.encode_integer_u32(*index);
+            }
+
+            packed_file.encode_integer_u32(writer.ascii_strings.len() as u32);
+            for (index, string) in &writer.ascii_strings {
+                encode_string_ascii(&mut packed_file, string);
+                packed_file.encode_integer_u32(*index);
+            }
+        }
+
+        Ok(packed_file)
+    }
+
+    /// This function returns the `ESF` as a JSON string.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(From::from)
+    }
+
+    /// This function creates an `ESF` from a JSON string, like the ones generated by `ESF::to_json`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(From::from)
+    }
+
+    /// This function returns the `ESF` as a XML string.
+    ///
+    /// Each node is an element named after its type. Records contain their nodes, and record blocks contain one `item` element per group of nodes.
+    pub fn to_xml(&self) -> Result<String> {
+        let mut xml = vec![];
+        {
+            let mut writer = EmitterConfig::new().perform_indent(true).create_writer(&mut xml);
+            let unknown_1 = self.unknown_1.to_string();
+            let creation_date = self.creation_date.to_string();
+            let signature = self.signature.to_string();
+            write_xml(&mut writer, WriterEvent::start_element("esf")
+                .attr("signature", &signature)
+                .attr("unknown_1", &unknown_1)
+                .attr("creation_date", &creation_date))?;
+
+            write_xml(&mut writer, WriterEvent::start_element("record_names"))?;
+            for name in &self.record_names {
+                write_xml_text_element(&mut writer, "name", name, &[])?;
+            }
+            write_xml(&mut writer, WriterEvent::end_element())?;
+
+            for (tag, strings) in &[("utf16_strings", &self.utf16_strings), ("ascii_strings", &self.ascii_strings)] {
+                write_xml(&mut writer, WriterEvent::start_element(*tag))?;
+                for (index, string) in *strings {
+                    write_xml_text_element(&mut writer, "string", string, &[("index", &*index.to_string())])?;
+                }
+                write_xml(&mut writer, WriterEvent::end_element())?;
+            }
+
+            self.root_node.write_xml(&mut writer)?;
+            write_xml(&mut writer, WriterEvent::end_element())?;
+        }
+
+        String::from_utf8(xml).map_err(|error| ErrorKind::ESFEncode(error.to_string()).into())
+    }
+
+    /// This function creates an `ESF` from a XML string, like the ones generated by `ESF::to_xml`.
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        let root = XmlElement::read(xml)?;
+        if root.name != "esf" {
+            return Err(ErrorKind::ESFImport(format!("Expected an \"esf\" element, found \"{}\".", root.name)).into());
+        }
+
+        let signature = ESFSignature::from_str(root.get_attribute("signature")?)?;
+        let unknown_1 = root.parse_attribute("unknown_1")?;
+        let creation_date = root.parse_attribute("creation_date")?;
+
+        let mut record_names = vec![];
+        let mut utf16_strings = BTreeMap::new();
+        let mut ascii_strings = BTreeMap::new();
+        let mut root_node = None;
+        for child in &root.children {
+            match &*child.name {
+                "record_names" => record_names = child.children.iter().map(|x| x.text.to_owned()).collect(),
+                "utf16_strings" => for string in &child.children { utf16_strings.insert(string.parse_attribute("index")?, string.text.to_owned()); },
+                "ascii_strings" => for string in &child.children { ascii_strings.insert(string.parse_attribute("index")?, string.text.to_owned()); },
+                _ => root_node = Some(NodeType::read_xml(child)?),
+            }
+        }
+
+        Ok(Self {
+            signature,
+            unknown_1,
+            creation_date,
+            root_node: root_node.ok_or_else(|| Error::from(ErrorKind::ESFImport("The root node is missing.".to_owned())))?,
+            record_names,
+            utf16_strings,
+            ascii_strings,
+        })
+    }
+
+    /// This function returns the signature of the file.
+    pub fn get_signature(&self) -> ESFSignature {
+        self.signature
+    }
+
+    /// This function sets the signature of the file. It'll be used when saving it.
+    pub fn set_signature(&mut self, signature: ESFSignature) {
+        self.signature = signature;
+    }
+
+    /// This function returns the unknown value of the header.
+    pub fn get_unknown_1(&self) -> u32 {
+        self.unknown_1
+    }
+
+    /// This function returns the creation date of the file.
+    pub fn get_creation_date(&self) -> u32 {
+        self.creation_date
+    }
+
+    /// This function sets the creation date of the file.
+    pub fn set_creation_date(&mut self, creation_date: u32) {
+        self.creation_date = creation_date;
+    }
+
+    /// This function returns a reference to the root node of the file.
+    pub fn get_ref_root_node(&self) -> &NodeType {
+        &self.root_node
+    }
+
+    /// This function returns a mutable reference to the root node of the file.
+    pub fn get_ref_mut_root_node(&mut self) -> &mut NodeType {
+        &mut self.root_node
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                       Implementation of ESFSignature
+//---------------------------------------------------------------------------//
+
+/// Implementation of `ESFSignature`.
+impl ESFSignature {
+
+    /// This function reads the signature from the first four bytes of the provided data.
+    ///
+    /// The signature is stored as a little-endian u32, so an ABCA file starts with `CA AB 00 00`.
+    fn read(data: &[u8]) -> Result<Self> {
+        match data.decode_integer_u32(0) {
+            Ok(0xABCD) => Ok(Self::ABCD),
+            Ok(0xABCE) => Ok(Self::ABCE),
+            Ok(0xABCF) => Ok(Self::ABCF),
+            Ok(0xABCA) => Ok(Self::ABCA),
+            _ => Err(ErrorKind::ESFUnsupportedSignature.into()),
+        }
+    }
+
+    /// This function returns the value used to store this signature in the file.
+    fn get_magic_number(self) -> u32 {
+        match self {
+            Self::ABCD => 0xABCD,
+            Self::ABCE => 0xABCE,
+            Self::ABCF => 0xABCF,
+            Self::ABCA => 0xABCA,
+        }
+    }
+
+    /// This function returns if files with this signature store their strings in tables.
+    fn has_string_tables(self) -> bool {
+        matches!(self, Self::ABCF | Self::ABCA)
+    }
+
+    /// This function returns if files with this signature support optimized nodes and compact record headers.
+    fn is_optimized(self) -> bool {
+        self == Self::ABCA
+    }
+}
+
+/// Display implementation of `ESFSignature`.
+impl Display for ESFSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        Display::fmt(match self {
+            Self::ABCD => "ABCD",
+            Self::ABCE => "ABCE",
+            Self::ABCF => "ABCF",
+            Self::ABCA => "ABCA",
+        }, f)
+    }
+}
+
+/// FromStr implementation of `ESFSignature`.
+impl FromStr for ESFSignature {
+    type Err = Error;
+
+    fn from_str(signature: &str) -> Result<Self> {
+        match signature {
+            "ABCD" => Ok(Self::ABCD),
+            "ABCE" => Ok(Self::ABCE),
+            "ABCF" => Ok(Self::ABCF),
+            "ABCA" => Ok(Self::ABCA),
+            _ => Err(ErrorKind::ESFUnsupportedSignature.into()),
+        }
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                         Implementation of NodeType
+//---------------------------------------------------------------------------//
+
+/// Implementation of `NodeType`.
+impl NodeType {
+
+    /// This function returns the name of the XML element used by the node.
+    fn get_xml_name(&self) -> &'static str {
+        match self {
+            Self::Bool(_) => "bool",
+            Self::I8(_) => "i8",
+            Self::I16(_) => "i16",
+            Self::I32(_) => "i32",
+            Self::I64(_) => "i64",
+            Self::U8(_) => "u8",
+            Self::U16(_) => "u16",
+            Self::U32(_) => "u32",
+            Self::U64(_) => "u64",
+            Self::F32(_) => "f32",
+            Self::F64(_) => "f64",
+            Self::Coord2d(_) => "coord2d",
+            Self::Coord3d(_) => "coord3d",
+            Self::Utf16(_) => "utf16",
+            Self::Ascii(_) => "ascii",
+            Self::Angle(_) => "angle",
+            Self::BoolArray(_) => "bool_array",
+            Self::I8Array(_) => "i8_array",
+            Self::I16Array(_) => "i16_array",
+            Self::I32Array(_) => "i32_array",
+            Self::I64Array(_) => "i64_array",
+            Self::U8Array(_) => "u8_array",
+            Self::U16Array(_) => "u16_array",
+            Self::U32Array(_) => "u32_array",
+            Self::U64Array(_) => "u64_array",
+            Self::F32Array(_) => "f32_array",
+            Self::F64Array(_) => "f64_array",
+            Self::Coord2dArray(_) => "coord2d_array",
+            Self::Coord3dArray(_) => "coord3d_array",
+            Self::Utf16Array(_) => "utf16_array",
+            Self::AsciiArray(_) => "ascii_array",
+            Self::AngleArray(_) => "angle_array",
+            Self::Record(_) => "record",
+            Self::RecordBlock(_) => "record_block",
+        }
+    }
+
+    /// This function writes the node as a XML element.
+    fn write_xml<W: std::io::Write>(&self, writer: &mut xml::EventWriter<W>) -> Result<()> {
+        let name = self.get_xml_name();
+        match self {
+            Self::Bool(node) => write_xml_optimized_element(writer, name, &node.value.to_string(), node.optimized),
+            Self::I8(value) => write_xml_text_element(writer, name, &value.to_string(), &[]),
+            Self::I16(value) => write_xml_text_element(writer, name, &value.to_string(), &[]),
+            Self::I32(node) => write_xml_optimized_element(writer, name, &node.value.to_string(), node.optimized),
+            Self::I64(value) => write_xml_text_element(writer, name, &value.to_string(), &[]),
+            Self::U8(value) => write_xml_text_element(writer, name, &value.to_string(), &[]),
+            Self::U16(value) => write_xml_text_element(writer, name, &value.to_string(), &[]),
+            Self::U32(node) => write_xml_optimized_element(writer, name, &node.value.to_string(), node.optimized),
+            Self::U64(value) => write_xml_text_element(writer, name, &value.to_string(), &[]),
+            Self::F32(node) => write_xml_optimized_element(writer, name, &node.value.to_string(), node.optimized),
+            Self::F64(value) => write_xml_text_element(writer, name, &value.to_string(), &[]),
+            Self::Coord2d(value) => value.write_xml(writer),
+            Self::Coord3d(value) => value.write_xml(writer),
+            Self::Utf16(value) => write_xml_text_element(writer, name, value, &[]),
+            Self::Ascii(value) => write_xml_text_element(writer, name, value, &[]),
+            Self::Angle(value) => write_xml_text_element(writer, name, &value.to_string(), &[]),
+            Self::BoolArray(values) => write_xml_text_element(writer, name, &join(values), &[]),
+            Self::I8Array(values) => write_xml_text_element(writer, name, &join(values), &[]),
+            Self::I16Array(values) => write_xml_text_element(writer, name, &join(values), &[]),
+            Self::I32Array(node) => write_xml_optimized_element(writer, name, &join(&node.value), node.optimized),
+            Self::I64Array(values) => write_xml_text_element(writer, name, &join(values), &[]),
+            Self::U8Array(values) => write_xml_text_element(writer, name, &join(values), &[]),
+            Self::U16Array(values) => write_xml_text_element(writer, name, &join(values), &[]),
+            Self::U32Array(node) => write_xml_optimized_element(writer, name, &join(&node.value), node.optimized),
+            Self::U64Array(values) => write_xml_text_element(writer, name, &join(values), &[]),
+            Self::F32Array(values) => write_xml_text_element(writer, name, &join(values), &[]),
+            Self::F64Array(values) => write_xml_text_element(writer, name, &join(values), &[]),
+            Self::AngleArray(values) => write_xml_text_element(writer, name, &join(values), &[]),
+            Self::Coord2dArray(values) => {
+                write_xml(writer, WriterEvent::start_element(name))?;
+                values.iter().try_for_each(|value| value.write_xml(writer))?;
+                write_xml(writer, WriterEvent::end_element())
+            }
+            Self::Coord3dArray(values) => {
+                write_xml(writer, WriterEvent::start_element(name))?;
+                values.iter().try_for_each(|value| value.write_xml(writer))?;
+                write_xml(writer, WriterEvent::end_element())
+            }
+            Self::Utf16Array(values) | Self::AsciiArray(values) => {
+                let item_name = if let Self::Utf16Array(_) = self { "utf16" } else { "ascii" };
+                write_xml(writer, WriterEvent::start_element(name))?;
+                values.iter().try_for_each(|value| write_xml_text_element(writer, item_name, value, &[]))?;
+                write_xml(writer, WriterEvent::end_element())
+            }
+            Self::Record(node) => {
+                let version = node.version.to_string();
+                write_xml(writer, WriterEvent::start_element(name).attr("name", &node.name).attr("version", &version))?;
+                node.children.iter().try_for_each(|child| child.write_xml(writer))?;
+                write_xml(writer, WriterEvent::end_element())
+            }
+            Self::RecordBlock(node) => {
+                let version = node.version.to_string();
+                write_xml(writer, WriterEvent::start_element(name).attr("name", &node.name).attr("version", &version))?;
+                for item in &node.children {
+                    write_xml(writer, WriterEvent::start_element("item"))?;
+                    item.iter().try_for_each(|child| child.write_xml(writer))?;
+                    write_xml(writer, WriterEvent::end_element())?;
+                }
+                write_xml(writer, WriterEvent::end_element())
+            }
+        }
+    }
+
+    /// This function creates a node from a XML element, like the ones written by `NodeType::write_xml`.
+    fn read_xml(element: &XmlElement) -> Result<Self> {
+        let optimized = matches!(element.attributes.get("optimized"), Some(x) if x == "true");
+        let text = &element.text;
+        Ok(match &*element.name {
+            "bool" => Self::Bool(OptimizedNode { value: parse_xml_value(text)?, optimized }),
+            "i8" => Self::I8(parse_xml_value(text)?),
+            "i16" => Self::I16(parse_xml_value(text)?),
+            "i32" => Self::I32(OptimizedNode { value: parse_xml_value(text)?, optimized }),
+            "i64" => Self::I64(parse_xml_value(text)?),
+            "u8" => Self::U8(parse_xml_value(text)?),
+            "u16" => Self::U16(parse_xml_value(text)?),
+            "u32" => Self::U32(OptimizedNode { value: parse_xml_value(text)?, optimized }),
+            "u64" => Self::U64(parse_xml_value(text)?),
+            "f32" => Self::F32(OptimizedNode { value: parse_xml_value(text)?, optimized }),
+            "f64" => Self::F64(parse_xml_value(text)?),
+            "coord2d" => Self::Coord2d(Coordinates2D::read_xml(element)?),
+            "coord3d" => Self::Coord3d(Coordinates3D::read_xml(element)?),
+            "utf16" => Self::Utf16(text.to_owned()),
+            "ascii" => Self::Ascii(text.to_owned()),
+            "angle" => Self::Angle(parse_xml_value(text)?),
+            "bool_array" => Self::BoolArray(parse_xml_values(text)?),
+            "i8_array" => Self::I8Array(parse_xml_values(text)?),
+            "i16_array" => Self::I16Array(parse_xml_values(text)?),
+            "i32_array" => Self::I32Array(OptimizedNode { value: parse_xml_values(text)?, optimized }),
+            "i64_array" => Self::I64Array(parse_xml_values(text)?),
+            "u8_array" => Self::U8Array(parse_xml_values(text)?),
+            "u16_array" => Self::U16Array(parse_xml_values(text)?),
+            "u32_array" => Self::U32Array(OptimizedNode { value: parse_xml_values(text)?, optimized }),
+            "u64_array" => Self::U64Array(parse_xml_values(text)?),
+            "f32_array" => Self::F32Array(parse_xml_values(text)?),
+            "f64_array" => Self::F64Array(parse_xml_values(text)?),
+            "angle_array" => Self::AngleArray(parse_xml_values(text)?),
+            "coord2d_array" => Self::Coord2dArray(element.children.iter().map(Coordinates2D::read_xml).collect::<Result<Vec<_>>>()?),
+            "coord3d_array" => Self::Coord3dArray(element.children.iter().map(Coordinates3D::read_xml).collect::<Result<Vec<_>>>()?),
+            "utf16_array" => Self::Utf16Array(element.children.iter().map(|x| x.text.to_owned()).collect()),
+            "ascii_array" => Self::AsciiArray(element.children.iter().map(|x| x.text.to_owned()).collect()),
+            "record" => Self::Record(RecordNode {
+                name: element.get_attribute("name")?.to_owned(),
+                version: element.parse_attribute("version")?,
+                children: element.children.iter().map(Self::read_xml).collect::<Result<Vec<_>>>()?,
+            }),
+            "record_block" => Self::RecordBlock(RecordBlockNode {
+                name: element.get_attribute("name")?.to_owned(),
+                version: element.parse_attribute("version")?,
+                children: element.children.iter().map(|item| item.children.iter().map(Self::read_xml).collect::<Result<Vec<_>>>()).collect::<Result<Vec<_>>>()?,
+            }),
+            name => return Err(ErrorKind::ESFImport(format!("Unknown node type: \"{}\".", name)).into()),
+        })
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                     Implementation of Coordinates
+//---------------------------------------------------------------------------//
+
+/// Implementation of `Coordinates2D`.
+impl Coordinates2D {
+
+    /// This function reads a `Coordinates2D` from the provided data.
+    fn read(data: &[u8], offset: &mut usize) -> Result<Self> {
+        Ok(Self {
+            x: data.decode_packedfile_float_f32(*offset, offset)?,
+            y: data.decode_packedfile_float_f32(*offset, offset)?,
+        })
+    }
+
+    /// This function writes a `Coordinates2D` to the provided data.
+    fn write(self, data: &mut Vec<u8>) {
+        data.encode_float_f32(self.x);
+        data.encode_float_f32(self.y);
+    }
+
+    /// This function writes a `Coordinates2D` as a XML element.
+    fn write_xml<W: std::io::Write>(&self, writer: &mut xml::EventWriter<W>) -> Result<()> {
+        let x = self.x.to_string();
+        let y = self.y.to_string();
+        write_xml(writer, WriterEvent::start_element("coord2d").attr("x", &x).attr("y", &y))?;
+        write_xml(writer, WriterEvent::end_element())
+    }
+
+    /// This function reads a `Coordinates2D` from a XML element.
+    fn read_xml(element: &XmlElement) -> Result<Self> {
+        Ok(Self {
+            x: element.parse_attribute("x")?,
+            y: element.parse_attribute("y")?,
+        })
+    }
+}
+
+/// Implementation of `Coordinates3D`.
+impl Coordinates3D {
+
+    /// This function reads a `Coordinates3D` from the provided data.
+    fn read(data: &[u8], offset: &mut usize) -> Result<Self> {
+        Ok(Self {
+            x: data.decode_packedfile_float_f32(*offset, offset)?,
+            y: data.decode_packedfile_float_f32(*offset, offset)?,
+            z: data.decode_packedfile_float_f32(*offset, offset)?,
+        })
+    }
+
+    /// This function writes a `Coordinates3D` to the provided data.
+    fn write(self, data: &mut Vec<u8>) {
+        data.encode_float_f32(self.x);
+        data.encode_float_f32(self.y);
+        data.encode_float_f32(self.z);
+    }
+
+    /// This function writes a `Coordinates3D` as a XML element.
+    fn write_xml<W: std::io::Write>(&self, writer: &mut xml::EventWriter<W>) -> Result<()> {
+        let x = self.x.to_string();
+        let y = self.y.to_string();
+        let z = self.z.to_string();
+        write_xml(writer, WriterEvent::start_element("coord3d").attr("x", &x).attr("y", &y).attr("z", &z))?;
+        write_xml(writer, WriterEvent::end_element())
+    }
+
+    /// This function reads a `Coordinates3D` from a XML element.
+    fn read_xml(element: &XmlElement) -> Result<Self> {
+        Ok(Self {
+            x: element.parse_attribute("x")?,
+            y: element.parse_attribute("y")?,
+            z: element.parse_attribute("z")?,
+        })
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                       Implementation of RecordNode
+//---------------------------------------------------------------------------//
+
+/// Implementation of `RecordNode`.
+impl RecordNode {
+
+    /// This function creates a new empty `RecordNode`.
+    pub fn new(name: &str, version: u8) -> Self {
+        Self {
+            name: name.to_owned(),
+            version,
+            children: vec![],
+        }
+    }
+
+    /// This function returns the name of the record.
+    pub fn get_ref_name(&self) -> &str {
+        &self.name
+    }
+
+    /// This function returns the version of the record.
+    pub fn get_version(&self) -> u8 {
+        self.version
+    }
+
+    /// This function returns a reference to the nodes within the record.
+    pub fn get_ref_children(&self) -> &[NodeType] {
+        &self.children
+    }
+
+    /// This function returns a mutable reference to the nodes within the record.
+    pub fn get_ref_mut_children(&mut self) -> &mut Vec<NodeType> {
+        &mut self.children
+    }
+
+    /// This function returns if the record contains compressed data.
+    pub fn is_compressed(&self) -> bool {
+        self.name == COMPRESSED_DATA_TAG
+    }
+
+    /// This function decompresses the data of a `COMPRESSED_DATA` record, returning the ESF file it contains.
+    pub fn get_compressed_data(&self) -> Result<ESF> {
+        let (data, info) = self.get_compressed_data_nodes()?;
+        let (size, properties) = match info.children.get(0..2) {
+            Some([NodeType::U32(size), NodeType::U8Array(properties)]) if properties.len() == LZMA_ALONE_PROPERTIES_SIZE => (size.value, properties),
+            _ => return Err(ErrorKind::ESFDecode("Invalid compressed data info.".to_owned()).into()),
+        };
+
+        let mut lzma_data = Vec::with_capacity(data.len() + LZMA_ALONE_HEADER_SIZE);
+        lzma_data.extend_from_slice(properties);
+        lzma_data.encode_integer_u64(u64::from(size));
+        lzma_data.extend_from_slice(data);
+
+        let stream = Stream::new_lzma_decoder(u64::MAX).map_err(|_| Error::from(ErrorKind::PackedFileDataCouldNotBeDecompressed))?;
+        let mut decoder = XzDecoder::new_stream(&*lzma_data, stream);
+        let mut decompressed_data = vec![];
+        decoder.read_to_end(&mut decompressed_data).map_err(|_| Error::from(ErrorKind::PackedFileDataCouldNotBeDecompressed))?;
+        ESF::read(&decompressed_data)
+    }
+
+    /// This function compresses the provided ESF file, replacing the data of a `COMPRESSED_DATA` record with it.
+    pub fn set_compressed_data(&mut self, esf: &ESF) -> Result<()> {
+        let data = esf.save()?;
+        if data.len() > u32::MAX as usize { return Err(ErrorKind::PackedFileDataCouldNotBeCompressed.into()) }
+
+        let options = LzmaOptions::new_preset(LZMA_PRESET).map_err(|_| Error::from(ErrorKind::PackedFileDataCouldNotBeCompressed))?;
+        let stream = Stream::new_lzma_encoder(&options).map_err(|_| Error::from(ErrorKind::PackedFileDataCouldNotBeCompressed))?;
+        let mut encoder = XzEncoder::new_stream(&*data, stream);
+        let mut compressed_data = vec![];
+        if encoder.read_to_end(&mut compressed_data).is_err() || compressed_data.len() < LZMA_ALONE_HEADER_SIZE {
+            return Err(ErrorKind::PackedFileDataCouldNotBeCompressed.into());
+        }
+
+        // Make sure the record is valid before touching it.
+        self.get_compressed_data_nodes()?;
+        let mut children = self.children.iter_mut();
+        if let Some(NodeType::U8Array(old_data)) = children.next() {
+            *old_data = compressed_data[LZMA_ALONE_HEADER_SIZE..].to_vec();
+        }
+
+        if let Some(NodeType::Record(info)) = children.next() {
+            let mut info_children = info.children.iter_mut();
+            if let Some(NodeType::U32(size)) = info_children.next() {
+                size.value = data.len() as u32;
+            }
+            if let Some(NodeType::U8Array(properties)) = info_children.next() {
+                *properties = compressed_data[..LZMA_ALONE_PROPERTIES_SIZE].to_vec();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// This function returns the compressed data and the info record of a `COMPRESSED_DATA` record.
+    fn get_compressed_data_nodes(&self) -> Result<(&[u8], &RecordNode)> {
+        if !self.is_compressed() {
+            return Err(ErrorKind::ESFDecode(format!("The record \"{}\" doesn't contain compressed data.", self.name)).into());
+        }
+
+        match self.children.get(0..2) {
+            Some([NodeType::U8Array(data), NodeType::Record(info)]) if info.name == COMPRESSED_DATA_INFO_TAG => Ok((data, info)),
+            _ => Err(ErrorKind::ESFDecode("Invalid compressed data record.".to_owned()).into()),
+        }
+    }
+}
+
+/// Implementation of `RecordBlockNode`.
+impl RecordBlockNode {
+
+    /// This function creates a new empty `RecordBlockNode`.
+    pub fn new(name: &str, version: u8) -> Self {
+        Self {
+            name: name.to_owned(),
+            version,
+            children: vec![],
+        }
+    }
+
+    /// This function returns the name of the record block.
+    pub fn get_ref_name(&self) -> &str {
+        &self.name
+    }
+
+    /// This function returns the version of the record block.
+    pub fn get_version(&self) -> u8 {
+        self.version
+    }
+
+    /// This function returns a reference to the groups of nodes within the record block.
+    pub fn get_ref_children(&self) -> &[Vec<NodeType>] {
+        &self.children
+    }
+
+    /// This function returns a mutable reference to the groups of nodes within the record block.
+    pub fn get_ref_mut_children(&mut self) -> &mut Vec<Vec<NodeType>> {
+        &mut self.children
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                       Implementation of NodeReader
+//---------------------------------------------------------------------------//
+
+/// Implementation of `NodeReader`.
+impl<'a> NodeReader<'a> {
+
+    /// This function reads the node at the provided offset and depth, moving the offset to the end of it.
+    fn read_node(&self, offset: &mut usize, depth: usize) -> Result<NodeType> {
+        let data = self.data;
+        let node_offset = *offset;
+        if depth > MAX_NODE_DEPTH {
+            return Err(ErrorKind::ESFDecode(format!("The node at byte {} is nested more than {} levels deep.", node_offset, MAX_NODE_DEPTH)).into());
+        }
+
+        let node_type = data.decode_packedfile_integer_u8(*offset, offset)?;
+        Ok(match node_type {
+            _ if self.signature.is_optimized() && node_type >= RECORD => self.read_record_optimized(node_type, offset, depth)?,
+            RECORD => {
+                let name = self.read_record_name(offset)?;
+                let version = data.decode_packedfile_integer_u8(*offset, offset)?;
+                let end = data.decode_packedfile_integer_u32(*offset, offset)? as usize;
+                NodeType::Record(RecordNode { name, version, children: self.read_nodes(offset, end, depth)? })
+            }
+            RECORD_BLOCK => {
+                let name = self.read_record_name(offset)?;
+                let version = data.decode_packedfile_integer_u8(*offset, offset)?;
+                let end = data.decode_packedfile_integer_u32(*offset, offset)? as usize;
+                let count = data.decode_packedfile_integer_u32(*offset, offset)?;
+
+                // Do not specify size here, because a broken count can end up triggering an OOM crash if we do.
+                let mut children = vec![];
+                for _ in 0..count {
+                    let item_end = data.decode_packedfile_integer_u32(*offset, offset)? as usize;
+                    children.push(self.read_nodes(offset, item_end, depth)?);
+                }
+
+                if *offset != end {
+                    return Err(ErrorKind::ESFDecode(format!("The record block at byte {} should end at byte {}, but it ends at byte {}.", node_offset, end, offset)).into());
+                }
+                NodeType::RecordBlock(RecordBlockNode { name, version, children })
+            }
+
+            _ => self.read_value_node(node_type, node_offset, offset)?,
+        })
+    }
+
+    /// This function reads the value of a node that is not a record, moving the offset to the end of it.
+    ///
+    /// It's kept separated from the records, so the stack used by each nested record doesn't grow with the amount of value types.
+    fn read_value_node(&self, node_type: u8, node_offset: usize, offset: &mut usize) -> Result<NodeType> {
+        let data = self.data;
+        let optimized = self.signature.is_optimized();
+
+        Ok(match node_type {
+            BOOL => NodeType::Bool(OptimizedNode { value: data.decode_packedfile_bool(*offset, offset)?, optimized: false }),
+            I8 => NodeType::I8(data.decode_packedfile_integer_i8(*offset, offset)?),
+            I16 => NodeType::I16(data.decode_packedfile_integer_i16(*offset, offset)?),
+            I32 => NodeType::I32(OptimizedNode { value: data.decode_packedfile_integer_i32(*offset, offset)?, optimized: false }),
+            I64 => NodeType::I64(data.decode_packedfile_integer_i64(*offset, offset)?),
+            U8 => NodeType::U8(data.decode_packedfile_integer_u8(*offset, offset)?),
+            U16 => NodeType::U16(data.decode_packedfile_integer_u16(*offset, offset)?),
+            U32 => NodeType::U32(OptimizedNode { value: data.decode_packedfile_integer_u32(*offset, offset)?, optimized: false }),
+            U64 => NodeType::U64(data.decode_packedfile_integer_u64(*offset, offset)?),
+            F32 => NodeType::F32(OptimizedNode { value: data.decode_packedfile_float_f32(*offset, offset)?, optimized: false }),
+            F64 => NodeType::F64(decode_float_f64(data, offset)?),
+            COORD_2D => NodeType::Coord2d(Coordinates2D::read(data, offset)?),
+            COORD_3D => NodeType::Coord3d(Coordinates3D::read(data, offset)?),
+            UTF16 => NodeType::Utf16(self.read_string_utf16(offset)?),
+            ASCII => NodeType::Ascii(self.read_string_ascii(offset)?),
+            ANGLE => NodeType::Angle(data.decode_packedfile_integer_u16(*offset, offset)?),
+
+            BOOL_TRUE if optimized => NodeType::Bool(OptimizedNode { value: true, optimized }),
+            BOOL_FALSE if optimized => NodeType::Bool(OptimizedNode { value: false, optimized }),
+            U32_ZERO if optimized => NodeType::U32(OptimizedNode { value: 0, optimized }),
+            U32_ONE if optimized => NodeType::U32(OptimizedNode { value: 1, optimized }),
+            U32_BYTE if optimized => NodeType::U32(OptimizedNode { value: u32::from(data.decode_packedfile_integer_u8(*offset, offset)?), optimized }),
+            U32_16BIT if optimized => NodeType::U32(OptimizedNode { value: u32::from(data.decode_packedfile_integer_u16(*offset, offset)?), optimized }),
+            U32_24BIT if optimized => NodeType::U32(OptimizedNode { value: decode_integer_u24(data, offset)?, optimized }),
+            I32_ZERO if optimized => NodeType::I32(OptimizedNode { value: 0, optimized }),
+            I32_BYTE if optimized => NodeType::I32(OptimizedNode { value: i32::from(data.decode_packedfile_integer_i8(*offset, offset)?), optimized }),
+            I32_16BIT if optimized => NodeType::I32(OptimizedNode { value: i32::from(data.decode_packedfile_integer_i16(*offset, offset)?), optimized }),
+            I32_24BIT if optimized => NodeType::I32(OptimizedNode { value: decode_integer_i24(data, offset)?, optimized }),
+            F32_ZERO if optimized => NodeType::F32(OptimizedNode { value: 0.0, optimized }),
+
+            _ if node_type > ARRAY && node_type <= ARRAY + ANGLE => {
+                let end = self.read_array_end(offset)?;
+                match node_type - ARRAY {
+                    BOOL => NodeType::BoolArray(self.read_array(offset, end, |offset| data.decode_packedfile_bool(*offset, offset))?),
+                    I8 => NodeType::I8Array(self.read_array(offset, end, |offset| data.decode_packedfile_integer_i8(*offset, offset))?),
+                    I16 => NodeType::I16Array(self.read_array(offset, end, |offset| data.decode_packedfile_integer_i16(*offset, offset))?),
+                    I32 => NodeType::I32Array(OptimizedNode { value: self.read_array(offset, end, |offset| data.decode_packedfile_integer_i32(*offset, offset))?, optimized: false }),
+                    I64 => NodeType::I64Array(self.read_array(offset, end, |offset| data.decode_packedfile_integer_i64(*offset, offset))?),
+                    U8 => NodeType::U8Array(self.read_array(offset, end, |offset| data.decode_packedfile_integer_u8(*offset, offset))?),
+                    U16 => NodeType::U16Array(self.read_array(offset, end, |offset| data.decode_packedfile_integer_u16(*offset, offset))?),
+                    U32 => NodeType::U32Array(OptimizedNode { value: self.read_array(offset, end, |offset| data.decode_packedfile_integer_u32(*offset, offset))?, optimized: false }),
+                    U64 => NodeType::U64Array(self.read_array(offset, end, |offset| data.decode_packedfile_integer_u64(*offset, offset))?),
+                    F32 => NodeType::F32Array(self.read_array(offset, end, |offset| data.decode_packedfile_float_f32(*offset, offset))?),
+                    F64 => NodeType::F64Array(self.read_array(offset, end, |offset| decode_float_f64(data, offset))?),
+                    COORD_2D => NodeType::Coord2dArray(self.read_array(offset, end, |offset| Coordinates2D::read(data, offset))?),
+                    COORD_3D => NodeType::Coord3dArray(self.read_array(offset, end, |offset| Coordinates3D::read(data, offset))?),
+                    UTF16 => NodeType::Utf16Array(self.read_array(offset, end, |offset| self.read_string_utf16(offset))?),
+                    ASCII => NodeType::AsciiArray(self.read_array(offset, end, |offset| self.read_string_ascii(offset))?),
+                    _ => NodeType::AngleArray(self.read_array(offset, end, |offset| data.decode_packedfile_integer_u16(*offset, offset))?),
+                }
+            }
+
+            U32_BYTE_ARRAY if optimized => {
+                let end = self.read_array_end(offset)?;
+                NodeType::U32Array(OptimizedNode { value: self.read_array(offset, end, |offset| data.decode_packedfile_integer_u8(*offset, offset).map(u32::from))?, optimized })
+            }
+            U32_16BIT_ARRAY if optimized => {
+                let end = self.read_array_end(offset)?;
+                NodeType::U32Array(OptimizedNode { value: self.read_array(offset, end, |offset| data.decode_packedfile_integer_u16(*offset, offset).map(u32::from))?, optimized })
+            }
+            U32_24BIT_ARRAY if optimized => {
+                let end = self.read_array_end(offset)?;
+                NodeType::U32Array(OptimizedNode { value: self.read_array(offset, end, |offset| decode_integer_u24(data, offset))?, optimized })
+            }
+            I32_BYTE_ARRAY if optimized => {
+                let end = self.read_array_end(offset)?;
+                NodeType::I32Array(OptimizedNode { value: self.read_array(offset, end, |offset| data.decode_packedfile_integer_i8(*offset, offset).map(i32::from))?, optimized })
+            }
+            I32_16BIT_ARRAY if optimized => {
+                let end = self.read_array_end(offset)?;
+                NodeType::I32Array(OptimizedNode { value: self.read_array(offset, end, |offset| data.decode_packedfile_integer_i16(*offset, offset).map(i32::from))?, optimized })
+            }
+            I32_24BIT_ARRAY if optimized => {
+                let end = self.read_array_end(offset)?;
+                NodeType::I32Array(OptimizedNode { value: self.read_array(offset, end, |offset| decode_integer_i24(data, offset))?, optimized })
+            }
+
+            _ => return Err(ErrorKind::ESFDecode(format!("Unknown node type 0x{:02X} at byte {}.", node_type, node_offset)).into()),
+        })
+    }
+
+    /// This function reads a record (or record block) with a compact header, used by ABCA files.
+    fn read_record_optimized(&self, node_type: u8, offset: &mut usize, depth: usize) -> Result<NodeType> {
+        let data = self.data;
+        let (name_index, version) = if node_type & RECORD_FLAG_LONG_INFO != 0 {
+            let name_index = data.decode_packedfile_integer_u16(*offset, offset)?;
+            let version = data.decode_packedfile_integer_u8(*offset, offset)?;
+            (name_index, version)
+        } else {
+            let name_index = (u16::from(node_type & 1) << 8) | u16::from(data.decode_packedfile_integer_u8(*offset, offset)?);
+            let version = (node_type & 0x1e) >> 1;
+            (name_index, version)
+        };
+
+        let name = self.get_record_name(name_index)?;
+        let size = decode_cauleb128(data, offset)? as usize;
+        let end = *offset + size;
+
+        if node_type & RECORD_FLAG_BLOCK != 0 {
+            let count = decode_cauleb128(data, offset)?;
+
+            // Do not specify size here, because a broken count can end up triggering an OOM crash if we do.
+            let mut children = vec![];
+            for _ in 0..count {
+                let item_size = decode_cauleb128(data, offset)? as usize;
+                let item_end = *offset + item_size;
+                children.push(self.read_nodes(offset, item_end, depth)?);
+            }
+
+            if *offset != end {
+                return Err(ErrorKind::ESFDecode(format!("The record block \"{}\" should end at byte {}, but it ends at byte {}.", name, end, offset)).into());
+            }
+            Ok(NodeType::RecordBlock(RecordBlockNode { name, version, children }))
+        } else {
+            Ok(NodeType::Record(RecordNode { name, version, children: self.read_nodes(offset, end, depth)? }))
+        }
+    }
+
+    /// This function reads the children of a record at the provided depth until the provided end offset.
+    fn read_nodes(&self, offset: &mut usize, end: usize, depth: usize) -> Result<Vec<NodeType>> {
+        let mut nodes = vec![];
+        while *offset < end {
+            nodes.push(self.read_node(offset, depth + 1)?);
+        }
+
+        if *offset != end {
+            return Err(ErrorKind::ESFDecode(format!("A group of nodes should end at byte {}, but it ends at byte {}.", end, offset)).into());
+        }
+        Ok(nodes)
+    }
+
+    /// This function reads the offset where the array starting at the provided offset ends.
+    fn read_array_end(&self, offset: &mut usize) -> Result<usize> {
+        if self.signature.is_optimized() {
+            let size = decode_cauleb128(self.data, offset)? as usize;
+            Ok(*offset + size)
+        } else {
+            Ok(self.data.decode_packedfile_integer_u32(*offset, offset)? as usize)
+        }
+    }
+
+    /// This function reads the items of an array until the provided end offset.
+    fn read_array<T, F: Fn(&mut usize) -> Result<T>>(&self, offset: &mut usize, end: usize, read_item: F) -> Result<Vec<T>> {
+        let mut items = vec![];
+        while *offset < end {
+            items.push(read_item(offset)?);
+        }
+
+        if *offset != end {
+            return Err(ErrorKind::ESFDecode(format!("An array should end at byte {}, but it ends at byte {}.", end, offset)).into());
+        }
+        Ok(items)
+    }
+
+    /// This function reads the name of a record from its index.
+    fn read_record_name(&self, offset: &mut usize) -> Result<String> {
+        let name_index = self.data.decode_packedfile_integer_u16(*offset, offset)?;
+        self.get_record_name(name_index)
+    }
+
+    /// This function returns the name of a record from its index.
+    fn get_record_name(&self, name_index: u16) -> Result<String> {
+        self.record_names.get(name_index as usize).cloned().ok_or_else(|| Error::from(ErrorKind::ESFDecode(format!("Record name with index {} not found.", name_index))))
+    }
+
+    /// This function reads an UTF-16 string, either inline or from the string tables, depending on the signature.
+    fn read_string_utf16(&self, offset: &mut usize) -> Result<String> {
+        if self.signature.has_string_tables() {
+            let index = self.data.decode_packedfile_integer_u32(*offset, offset)?;
+            self.utf16_strings.get(&index).cloned().ok_or_else(|| Error::from(ErrorKind::ESFDecode(format!("UTF-16 string with index {} not found.", index))))
+        } else {
+            self.data.decode_packedfile_string_u16(*offset, offset)
+        }
+    }
+
+    /// This function reads an ASCII string, either inline or from the string tables, depending on the signature.
+    fn read_string_ascii(&self, offset: &mut usize) -> Result<String> {
+        if self.signature.has_string_tables() {
+            let index = self.data.decode_packedfile_integer_u32(*offset, offset)?;
+            self.ascii_strings.get(&index).cloned().ok_or_else(|| Error::from(ErrorKind::ESFDecode(format!("ASCII string with index {} not found.", index))))
+        } else {
+            decode_string_ascii(self.data, offset)
+        }
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                       Implementation of NodeWriter
+//---------------------------------------------------------------------------//
+
+/// Implementation of `NodeWriter`.
+impl NodeWriter {
+
+    /// This function creates a new `NodeWriter` for the provided `ESF`.
+    fn new(esf: &ESF) -> Self {
+        let mut record_name_indexes = HashMap::new();
+        for (index, name) in esf.record_names.iter().enumerate() {
+            record_name_indexes.entry(name.to_owned()).or_insert(index as u16);
+        }
+
+        let mut utf16_string_indexes = HashMap::new();
+        for (index, string) in &esf.utf16_strings {
+            utf16_string_indexes.entry(string.to_owned()).or_insert(*index);
+        }
+
+        let mut ascii_string_indexes = HashMap::new();
+        for (index, string) in &esf.ascii_strings {
+            ascii_string_indexes.entry(string.to_owned()).or_insert(*index);
+        }
+
+        Self {
+            signature: esf.signature,
+            record_names: esf.record_names.to_vec(),
+            record_name_indexes,
+            utf16_strings: esf.utf16_strings.clone(),
+            utf16_string_indexes,
+            ascii_strings: esf.ascii_strings.clone(),
+            ascii_string_indexes,
+        }
+    }
+
+    /// This function writes a node at the end of the provided data.
+    ///
+    /// The data must start at the beginning of the file, as some offsets are absolute.
+    fn write_node(&mut self, data: &mut Vec<u8>, node: &NodeType) -> Result<()> {
+        let optimized = self.signature.is_optimized();
+        match node {
+            NodeType::Bool(node) if optimized && node.optimized => data.push(if node.value { BOOL_TRUE } else { BOOL_FALSE }),
+            NodeType::Bool(node) => {
+                data.push(BOOL);
+                data.encode_bool(node.value);
+            }
+            NodeType::I8(value) => {
+                data.push(I8);
+                data.encode_integer_i8(*value);
+            }
+            NodeType::I16(value) => {
+                data.push(I16);
+                data.encode_integer_i16(*value);
+            }
+            NodeType::I32(node) if optimized && node.optimized => match node.value {
+                0 => data.push(I32_ZERO),
+                value if is_i8(value) => {
+                    data.push(I32_BYTE);
+                    data.encode_integer_i8(value as i8);
+                }
+                value if is_i16(value) => {
+                    data.push(I32_16BIT);
+                    data.encode_integer_i16(value as i16);
+                }
+                value if is_i24(value) => {
+                    data.push(I32_24BIT);
+                    encode_integer_i24(data, value);
+                }
+                value => {
+                    data.push(I32);
+                    data.encode_integer_i32(value);
+                }
+            }
+            NodeType::I32(node) => {
+                data.push(I32);
+                data.encode_integer_i32(node.value);
+            }
+            NodeType::I64(value) => {
+                data.push(I64);
+                data.encode_integer_i64(*value);
+            }
+            NodeType::U8(value) => {
+                data.push(U8);
+                data.push(*value);
+            }
+            NodeType::U16(value) => {
+                data.push(U16);
+                data.encode_integer_u16(*value);
+            }
+            NodeType::U32(node) if optimized && node.optimized => match node.value {
+                0 => data.push(U32_ZERO),
+                1 => data.push(U32_ONE),
+                value if value <= u32::from(u8::MAX) => {
+                    data.push(U32_BYTE);
+                    data.push(value as u8);
+                }
+                value if value <= u32::from(u16::MAX) => {
+                    data.push(U32_16BIT);
+                    data.encode_integer_u16(value as u16);
+                }
+                value if value <= 0x00ff_ffff => {
+                    data.push(U32_24BIT);
+                    encode_integer_u24(data, value);
+                }
+                value => {
+                    data.push(U32);
+                    data.encode_integer_u32(value);
+                }
+            }
+            NodeType::U32(node) => {
+                data.push(U32);
+                data.encode_integer_u32(node.value);
+            }
+            NodeType::U64(value) => {
+                data.push(U64);
+                data.encode_integer_u64(*value);
+            }
+            NodeType::F32(node) if optimized && node.optimized && node.value.to_bits() == 0 => data.push(F32_ZERO),
+            NodeType::F32(node) => {
+                data.push(F32);
+                data.encode_float_f32(node.value);
+            }
+            NodeType::F64(value) => {
+                data.push(F64);
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+            NodeType::Coord2d(value) => {
+                data.push(COORD_2D);
+                value.write(data);
+            }
+            NodeType::Coord3d(value) => {
+                data.push(COORD_3D);
+                value.write(data);
+            }
+            NodeType::Utf16(value) => {
+                data.push(UTF16);
+                self.write_string_utf16(data, value);
+            }
+            NodeType::Ascii(value) => {
+                data.push(ASCII);
+                self.write_string_ascii(data, value);
+            }
+            NodeType::Angle(value) => {
+                data.push(ANGLE);
+                data.encode_integer_u16(*value);
+            }
+
+            NodeType::BoolArray(values) => self.write_array(data, ARRAY + BOOL, |data| values.iter().for_each(|x| data.encode_bool(*x))),
+            NodeType::I8Array(values) => self.write_array(data, ARRAY + I8, |data| values.iter().for_each(|x| data.encode_integer_i8(*x))),
+            NodeType::I16Array(values) => self.write_array(data, ARRAY + I16, |data| values.iter().for_each(|x| data.encode_integer_i16(*x))),
+            NodeType::I32Array(node) if optimized && node.optimized && node.value.iter().all(|x| is_i8(*x)) => {
+                self.write_array(data, I32_BYTE_ARRAY, |data| node.value.iter().for_each(|x| data.encode_integer_i8(*x as i8)))
+            }
+            NodeType::I32Array(node) if optimized && node.optimized && node.value.iter().all(|x| is_i16(*x)) => {
+                self.write_array(data, I32_16BIT_ARRAY, |data| node.value.iter().for_each(|x| data.encode_integer_i16(*x as i16)))
+            }
+            NodeType::I32Array(node) if optimized && node.optimized && node.value.iter().all(|x| is_i24(*x)) => {
+                self.write_array(data, I32_24BIT_ARRAY, |data| node.value.iter().for_each(|x| encode_integer_i24(data, *x)))
+            }
+            NodeType::I32Array(node) => self.write_array(data, ARRAY + I32, |data| node.value.iter().for_each(|x| data.encode_integer_i32(*x))),
+            NodeType::I64Array(values) => self.write_array(data, ARRAY + I64, |data| values.iter().for_each(|x| data.encode_integer_i64(*x))),
+            NodeType::U8Array(values) => self.write_array(data, ARRAY + U8, |data| data.extend_from_slice(values)),
+            NodeType::U16Array(values) => self.write_array(data, ARRAY + U16, |data| values.iter().for_each(|x| data.encode_integer_u16(*x))),
+            NodeType::U32Array(node) if optimized && node.optimized && node.value.iter().all(|x| *x <= u32::from(u8::MAX)) => {
+                self.write_array(data, U32_BYTE_ARRAY, |data| node.value.iter().for_each(|x| data.push(*x as u8)))
+            }
+            NodeType::U32Array(node) if optimized && node.optimized && node.value.iter().all(|x| *x <= u32::from(u16::MAX)) => {
+                self.write_array(data, U32_16BIT_ARRAY, |data| node.value.iter().for_each(|x| data.encode_integer_u16(*x as u16)))
+            }
+            NodeType::U32Array(node) if optimized && node.optimized && node.value.iter().all(|x| *x <= 0x00ff_ffff) => {
+                self.write_array(data, U32_24BIT_ARRAY, |data| node.value.iter().for_each(|x| encode_integer_u24(data, *x)))
+            }
+            NodeType::U32Array(node) => self.write_array(data, ARRAY + U32, |data| node.value.iter().for_each(|x| data.encode_integer_u32(*x))),
+            NodeType::U64Array(values) => self.write_array(data, ARRAY + U64, |data| values.iter().for_each(|x| data.encode_integer_u64(*x))),
+            NodeType::F32Array(values) => self.write_array(data, ARRAY + F32, |data| values.iter().for_each(|x| data.encode_float_f32(*x))),
+            NodeType::F64Array(values) => self.write_array(data, ARRAY + F64, |data| values.iter().for_each(|x| data.extend_from_slice(&x.to_le_bytes()))),
+            NodeType::Coord2dArray(values) => self.write_array(data, ARRAY + COORD_2D, |data| values.iter().for_each(|x| x.write(data))),
+            NodeType::Coord3dArray(values) => self.write_array(data, ARRAY + COORD_3D, |data| values.iter().for_each(|x| x.write(data))),
+            NodeType::AngleArray(values) => self.write_array(data, ARRAY + ANGLE, |data| values.iter().for_each(|x| data.encode_integer_u16(*x))),
+
+            // These two need the string tables, so we can't use write_array here.
+            NodeType::Utf16Array(values) | NodeType::AsciiArray(values) => {
+                let is_utf16 = matches!(node, NodeType::Utf16Array(_));
+                let mut array = vec![];
+                for value in values {
+                    if is_utf16 { self.write_string_utf16(&mut array, value); }
+                    else { self.write_string_ascii(&mut array, value); }
+                }
+                self.write_array(data, ARRAY + if is_utf16 { UTF16 } else { ASCII }, |data| data.extend_from_slice(&array));
+            }
+
+            NodeType::Record(node) => {
+                let name_index = self.get_record_name_index(&node.name)?;
+                if optimized {
+                    self.write_record_header_optimized(data, false, name_index, node.version);
+                    let mut children = vec![];
+                    for child in &node.children {
+                        self.write_node(&mut children, child)?;
+                    }
+                    encode_cauleb128(data, children.len() as u32);
+                    data.extend_from_slice(&children);
+                } else {
+                    data.push(RECORD);
+                    data.encode_integer_u16(name_index);
+                    data.push(node.version);
+                    let end_position = reserve_offset(data);
+                    for child in &node.children {
+                        self.write_node(data, child)?;
+                    }
+                    write_offset(data, end_position);
+                }
+            }
+
+            NodeType::RecordBlock(node) => {
+                let name_index = self.get_record_name_index(&node.name)?;
+                if optimized {
+                    self.write_record_header_optimized(data, true, name_index, node.version);
+                    let mut block = vec![];
+                    encode_cauleb128(&mut block, node.children.len() as u32);
+                    for item in &node.children {
+                        let mut children = vec![];
+                        for child in item {
+                            self.write_node(&mut children, child)?;
+                        }
+                        encode_cauleb128(&mut block, children.len() as u32);
+                        block.extend_from_slice(&children);
+                    }
+                    encode_cauleb128(data, block.len() as u32);
+                    data.extend_from_slice(&block);
+                } else {
+                    data.push(RECORD_BLOCK);
+                    data.encode_integer_u16(name_index);
+                    data.push(node.version);
+                    let end_position = reserve_offset(data);
+                    data.encode_integer_u32(node.children.len() as u32);
+                    for item in &node.children {
+                        let item_end_position = reserve_offset(data);
+                        for child in item {
+                            self.write_node(data, child)?;
+                        }
+                        write_offset(data, item_end_position);
+                    }
+                    write_offset(data, end_position);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// This function writes an array node, with the items written by the provided function.
+    ///
+    /// Arrays in ABCA files start with their size. In the rest of files, with the offset where they end.
+    fn write_array<F: FnOnce(&mut Vec<u8>)>(&self, data: &mut Vec<u8>, node_type: u8, write_items: F) {
+        data.push(node_type);
+        if self.signature.is_optimized() {
+            let mut items = vec![];
+            write_items(&mut items);
+            encode_cauleb128(data, items.len() as u32);
+            data.extend_from_slice(&items);
+        } else {
+            let end_position = reserve_offset(data);
+            write_items(data);
+            write_offset(data, end_position);
+        }
+    }
+
+    /// This function writes the compact header of a record, used in ABCA files. The size is not included.
+    fn write_record_header_optimized(&self, data: &mut Vec<u8>, is_block: bool, name_index: u16, version: u8) {
+        let mut node_type = RECORD;
+        if is_block { node_type |= RECORD_FLAG_BLOCK; }
+
+        if version < 16 && name_index < 512 {
+            node_type |= (version << 1) | (name_index >> 8) as u8;
+            data.push(node_type);
+            data.push(name_index as u8);
+        } else {
+            data.push(node_type | RECORD_FLAG_LONG_INFO);
+            data.encode_integer_u16(name_index);
+            data.push(version);
+        }
+    }
+
+    /// This function returns the index of a record name, adding it to the table if it's not there.
+    fn get_record_name_index(&mut self, name: &str) -> Result<u16> {
+        match self.record_name_indexes.get(name) {
+            Some(index) => Ok(*index),
+            None => {
+                if self.record_names.len() >= u16::MAX as usize {
+                    return Err(ErrorKind::ESFEncode("There are too many record names.".to_owned()).into());
+                }
+
+                let index = self.record_names.len() as u16;
+                self.record_names.push(name.to_owned());
+                self.record_name_indexes.insert(name.to_owned(), index);
+                Ok(index)
+            }
+        }
+    }
+
+    /// This function writes an UTF-16 string, either inline or as an index to the string tables, depending on the signature.
+    fn write_string_utf16(&mut self, data: &mut Vec<u8>, string: &str) {
+        if self.signature.has_string_tables() {
+            let index = get_string_index(&mut self.utf16_strings, &mut self.utf16_string_indexes, string);
+            data.encode_integer_u32(index);
+        } else {
+            data.encode_packedfile_string_u16(string);
+        }
+    }
+
+    /// This function writes an ASCII string, either inline or as an index to the string tables, depending on the signature.
+    fn write_string_ascii(&mut self, data: &mut Vec<u8>, string: &str) {
+        if self.signature.has_string_tables() {
+            let index = get_string_index(&mut self.ascii_strings, &mut self.ascii_string_indexes, string);
+            data.encode_integer_u32(index);
+        } else {
+            encode_string_ascii(data, string);
+        }
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                       Implementation of XmlElement
+//---------------------------------------------------------------------------//
+
+/// Implementation of `XmlElement`.
+impl XmlElement {
+
+    /// This function reads the root element of the provided XML, with all its children.
+    fn read(xml: &str) -> Result<Self> {
+        let config = ParserConfig::new()
+            .trim_whitespace(false)
+            .whitespace_to_characters(false)
+            .cdata_to_characters(true);
+
+        let mut stack: Vec<Self> = vec![];
+        for event in config.create_reader(xml.as_bytes()) {
+            match event.map_err(|error| ErrorKind::ESFImport(error.to_string()))? {
+                ReaderEvent::StartElement { name, attributes, .. } => stack.push(Self {
+                    name: name.local_name,
+                    attributes: attributes.into_iter().map(|x| (x.name.local_name, x.value)).collect(),
+                    text: String::new(),
+                    children: vec![],
+                }),
+
+                // Whitespace is only relevant within elements without children, like strings.
+                ReaderEvent::Characters(text) | ReaderEvent::Whitespace(text) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text);
+                    }
+                }
+
+                ReaderEvent::EndElement { .. } => {
+                    let element = stack.pop().ok_or_else(|| Error::from(ErrorKind::ESFImport("Unexpected end of element.".to_owned())))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Err(ErrorKind::ESFImport("Unexpected end of file.".to_owned()).into())
+    }
+
+    /// This function returns the value of the provided attribute.
+    fn get_attribute(&self, attribute: &str) -> Result<&str> {
+        self.attributes.get(attribute).map(|x| &**x).ok_or_else(|| Error::from(ErrorKind::ESFImport(format!("Attribute \"{}\" missing in element \"{}\".", attribute, self.name))))
+    }
+
+    /// This function returns the value of the provided attribute, parsed to the type we want.
+    fn parse_attribute<T: FromStr>(&self, attribute: &str) -> Result<T> {
+        parse_xml_value(self.get_attribute(attribute)?)
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                              Util functions
+//---------------------------------------------------------------------------//
+
+/// This function reads a CAULEB128 integer: a big-endian list of 7-bit groups, with the 8th bit set in all of them except the last one.
+fn decode_cauleb128(data: &[u8], offset: &mut usize) -> Result<u32> {
+    let mut value: u32 = 0;
+    for _ in 0..5 {
+        let byte = data.decode_packedfile_integer_u8(*offset, offset)?;
+        value = (value << 7) | u32::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(ErrorKind::ESFDecode(format!("Invalid CAULEB128 integer ending at byte {}.", offset)).into())
+}
+
+/// This function writes a CAULEB128 integer.
+fn encode_cauleb128(data: &mut Vec<u8>, mut value: u32) {
+    let mut bytes = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+
+    bytes.reverse();
+    data.extend_from_slice(&bytes);
+}
+
+/// This function reads a 24-bit unsigned integer.
+fn decode_integer_u24(data: &[u8], offset: &mut usize) -> Result<u32> {
+    let bytes = data.get_bytes_checked(*offset, 3)?;
+    *offset += 3;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+}
+
+/// This function writes a 24-bit unsigned integer.
+fn encode_integer_u24(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes()[..3]);
+}
+
+/// This function reads a 24-bit signed integer.
+fn decode_integer_i24(data: &[u8], offset: &mut usize) -> Result<i32> {
+    let value = decode_integer_u24(data, offset)?;
+    Ok(((value << 8) as i32) >> 8)
+}
+
+/// This function writes a 24-bit signed integer.
+fn encode_integer_i24(data: &mut Vec<u8>, value: i32) {
+    data.extend_from_slice(&value.to_le_bytes()[..3]);
+}
+
+/// This function returns if the provided value fits in a 8-bit signed integer.
+fn is_i8(value: i32) -> bool {
+    (i32::from(i8::MIN)..=i32::from(i8::MAX)).contains(&value)
+}
+
+/// This function returns if the provided value fits in a 16-bit signed integer.
+fn is_i16(value: i32) -> bool {
+    (i32::from(i16::MIN)..=i32::from(i16::MAX)).contains(&value)
+}
+
+/// This function returns if the provided value fits in a 24-bit signed integer.
+fn is_i24(value: i32) -> bool {
+    (-0x0080_0000..0x0080_0000).contains(&value)
+}
+
+/// This function reads a f64.
+fn decode_float_f64(data: &[u8], offset: &mut usize) -> Result<f64> {
+    let bytes = data.get_bytes_checked(*offset, 8)?;
+    *offset += 8;
+    Ok(f64::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]]))
+}
+
+/// This function reads an ASCII string, preceded by its length as an u16.
+///
+/// We read them as ISO-8859-1 so any byte is valid, and we save them back exactly as they came.
+fn decode_string_ascii(data: &[u8], offset: &mut usize) -> Result<String> {
+    let size = data.decode_packedfile_integer_u16(*offset, offset)? as usize;
+    let string = data.decode_string_u8_iso_8859_1(*offset, size)?;
+    *offset += size;
+    Ok(string)
+}
+
+/// This function writes an ASCII string, preceded by its length as an u16.
+fn encode_string_ascii(data: &mut Vec<u8>, string: &str) {
+    let mut bytes = vec![];
+    bytes.encode_string_u8_iso_8859_1(string);
+    data.encode_integer_u16(bytes.len() as u16);
+    data.extend_from_slice(&bytes);
+}
+
+/// This function reserves space for an absolute offset, returning the position to write it to later.
+fn reserve_offset(data: &mut Vec<u8>) -> usize {
+    let position = data.len();
+    data.encode_integer_u32(0);
+    position
+}
+
+/// This function writes the current end of the data as an absolute offset in the provided position.
+fn write_offset(data: &mut [u8], position: usize) {
+    let offset = data.len() as u32;
+    data[position..position + 4].copy_from_slice(&offset.to_le_bytes());
+}
+
+/// This function returns the index of a string in the provided string table, adding it to the table if it's not there.
+fn get_string_index(strings: &mut BTreeMap<u32, String>, indexes: &mut HashMap<String, u32>, string: &str) -> u32 {
+    match indexes.get(string) {
+        Some(index) => *index,
+        None => {
+            let index = strings.keys().next_back().map_or(0, |x| x + 1);
+            strings.insert(index, string.to_owned());
+            indexes.insert(string.to_owned(), index);
+            index
+        }
+    }
+}
+
+/// This function joins the provided values with spaces.
+fn join<T: ToString>(values: &[T]) -> String {
+    values.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+/// This function parses a value from a XML string.
+fn parse_xml_value<T: FromStr>(text: &str) -> Result<T> {
+    text.trim().parse::<T>().map_err(|_| Error::from(ErrorKind::ESFImport(format!("Invalid value: \"{}\".", text))))
+}
+
+/// This function parses a list of values separated by whitespace from a XML string.
+fn parse_xml_values<T: FromStr>(text: &str) -> Result<Vec<T>> {
+    text.split_whitespace().map(parse_xml_value).collect()
+}
+
+/// This function writes a XML event, mapping its error to one of ours.
+fn write_xml<'a, W: std::io::Write, E: Into<WriterEvent<'a>>>(writer: &mut xml::EventWriter<W>, event: E) -> Result<()> {
+    writer.write(event).map_err(|error| ErrorKind::ESFEncode(error.to_string()).into())
+}
+
+/// This function writes a XML element containing only text.
+fn write_xml_text_element<W: std::io::Write>(writer: &mut xml::EventWriter<W>, name: &str, text: &str, attributes: &[(&str, &str)]) -> Result<()> {
+    let mut start = WriterEvent::start_element(name);
+    for (attribute, value) in attributes {
+        start = start.attr(*attribute, value);
+    }
+
+    write_xml(writer, start)?;
+    if !text.is_empty() {
+        write_xml(writer, WriterEvent::characters(text))?;
+    }
+    write_xml(writer, WriterEvent::end_element())
+}
+
+/// This function writes a XML element containing the text of an `OptimizedNode`.
+fn write_xml_optimized_element<W: std::io::Write>(writer: &mut xml::EventWriter<W>, name: &str, text: &str, optimized: bool) -> Result<()> {
+    if optimized { write_xml_text_element(writer, name, text, &[("optimized", "true")]) }
+    else { write_xml_text_element(writer, name, text, &[]) }
+}
@@ -0,0 +1,800 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with all the code to decode/encode DDS Images.
+
+DDS Images are the format used by Total War games for textures. They're composed of:
+- A header, with the size, amount of mipmaps and format of the image. Optionally followed by a DX10 header.
+- The data of each face (6 for cubemaps, 1 for the rest), each one with all its mipmaps, from the biggest one to the smallest one.
+
+Here we support decoding DXT1/3/5, BC4/5/7 and uncompressed images to RGBA8, and encoding RGBA8 images to uncompressed DDS.
+!*/
+
+use serde_derive::{Serialize, Deserialize};
+
+use std::fmt;
+
+use rpfm_error::{ErrorKind, Result};
+
+use crate::common::{decoder::Decoder, encoder::Encoder};
+
+/// Signature of every DDS file.
+pub const SIGNATURE: &[u8; 4] = b"DDS ";
+
+/// Size of the DDS header, signature included.
+const HEADER_SIZE: usize = 128;
+
+/// Size of the DX10 extension of the header.
+const HEADER_DX10_SIZE: usize = 20;
+
+/// Flags used in the header.
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PITCH: u32 = 0x8;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+
+/// Flags used in the pixel format.
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x20000;
+
+/// Flags used in the caps.
+const DDSCAPS_COMPLEX: u32 = 0x8;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DDSCAPS_MIPMAP: u32 = 0x400000;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+
+/// Flag of the DX10 header used to mark cubemaps.
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+//---------------------------------------------------------------------------//
+//                              Enum & Structs
+//---------------------------------------------------------------------------//
+
+/// This enum represents the formats of DDS Images we know about.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DdsFormat {
+    Dxt1,
+    Dxt3,
+    Dxt5,
+    Bc4,
+    Bc5,
+    Bc7,
+
+    /// Uncompressed image. Contains the amount of bits per pixel.
+    Uncompressed(u32),
+}
+
+/// This struct contains the data of a DDS header we need to decode the image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DdsHeader {
+    pub width: u32,
+    pub height: u32,
+    pub mipmap_count: u32,
+    pub faces: u32,
+    pub is_cubemap: bool,
+    pub format: DdsFormat,
+
+    /// Masks of each channel, in RGBA order. Only used for uncompressed images.
+    masks: [u32; 4],
+
+    /// If the image is a luminance (greyscale) image. Only used for uncompressed images.
+    luminance: bool,
+
+    /// Offset where the image data starts.
+    data_offset: usize,
+}
+
+//---------------------------------------------------------------------------//
+//                              Implementations
+//---------------------------------------------------------------------------//
+
+/// Implementation of DdsFormat.
+impl DdsFormat {
+
+    /// This function returns the size in bytes of each 4x4 block of the format, or `None` if it's not a block-compressed format.
+    fn get_block_size(self) -> Option<usize> {
+        match self {
+            Self::Dxt1 | Self::Bc4 => Some(8),
+            Self::Dxt3 | Self::Dxt5 | Self::Bc5 | Self::Bc7 => Some(16),
+            Self::Uncompressed(_) => None,
+        }
+    }
+
+    /// This function returns the size in bytes of an image of this format with the provided size, or `None` if it doesn't fit in memory.
+    fn get_data_size(self, width: u32, height: u32) -> Option<usize> {
+        match self.get_block_size() {
+            Some(block_size) => get_block_count(width).checked_mul(get_block_count(height))?.checked_mul(block_size),
+            None => if let Self::Uncompressed(bits) = self { ((width as usize).checked_mul(bits as usize)?.checked_add(7)? >> 3).checked_mul(height as usize) } else { Some(0) }
+        }
+    }
+}
+
+/// Display implementation of `DdsFormat`.
+impl fmt::Display for DdsFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Dxt1 => write!(f, "DXT1"),
+            Self::Dxt3 => write!(f, "DXT3"),
+            Self::Dxt5 => write!(f, "DXT5"),
+            Self::Bc4 => write!(f, "BC4"),
+            Self::Bc5 => write!(f, "BC5"),
+            Self::Bc7 => write!(f, "BC7"),
+            Self::Uncompressed(bits) => write!(f, "Uncompressed ({} bits)", bits),
+        }
+    }
+}
+
+/// Implementation of DdsHeader.
+impl DdsHeader {
+
+    /// This function reads the header of a DDS Image, checking the image has enough data for all its mipmaps.
+    pub fn read(data: &[u8]) -> Result<Self> {
+        if data.len() < HEADER_SIZE || &data[0..4] != SIGNATURE {
+            return Err(ErrorKind::ImageDecode("This is not a valid DDS Image.".to_owned()).into());
+        }
+
+        let height = data.decode_integer_u32(12)?;
+        let width = data.decode_integer_u32(16)?;
+        let mipmap_count = data.decode_integer_u32(28)?.max(1);
+        let pixel_format_flags = data.decode_integer_u32(80)?;
+        let four_cc = data.get_bytes_checked(84, 4)?;
+        let bits_per_pixel = data.decode_integer_u32(88)?;
+        let mut masks = [
+            data.decode_integer_u32(92)?,
+            data.decode_integer_u32(96)?,
+            data.decode_integer_u32(100)?,
+            data.decode_integer_u32(104)?,
+        ];
+        let caps_2 = data.decode_integer_u32(112)?;
+
+        let mut data_offset = HEADER_SIZE;
+        let mut is_cubemap = caps_2 & DDSCAPS2_CUBEMAP != 0;
+        let mut faces = if is_cubemap { 6 } else { 1 };
+        let format = if pixel_format_flags & DDPF_FOURCC != 0 {
+            match four_cc {
+                b"DXT1" => DdsFormat::Dxt1,
+                b"DXT2" | b"DXT3" => DdsFormat::Dxt3,
+                b"DXT4" | b"DXT5" => DdsFormat::Dxt5,
+                b"ATI1" | b"BC4U" => DdsFormat::Bc4,
+                b"ATI2" | b"BC5U" => DdsFormat::Bc5,
+                b"DX10" => {
+                    let dxgi_format = data.decode_integer_u32(128)?;
+                    let misc_flags = data.decode_integer_u32(136)?;
+                    let array_size = data.decode_integer_u32(140)?.max(1);
+                    data_offset += HEADER_DX10_SIZE;
+                    is_cubemap = misc_flags & DDS_RESOURCE_MISC_TEXTURECUBE != 0;
+                    faces = array_size.checked_mul(if is_cubemap { 6 } else { 1 }).ok_or_else(|| ErrorKind::ImageDecode(format!("Invalid DDS array size: {}.", array_size)))?;
+
+                    match dxgi_format {
+                        71 | 72 => DdsFormat::Dxt1,
+                        74 | 75 => DdsFormat::Dxt3,
+                        77 | 78 => DdsFormat::Dxt5,
+                        80 => DdsFormat::Bc4,
+                        83 => DdsFormat::Bc5,
+                        98 | 99 => DdsFormat::Bc7,
+                        28 | 29 => {
+                            masks = [0xFF, 0xFF00, 0xFF_0000, 0xFF00_0000];
+                            DdsFormat::Uncompressed(32)
+                        }
+                        87 | 91 => {
+                            masks = [0xFF_0000, 0xFF00, 0xFF, 0xFF00_0000];
+                            DdsFormat::Uncompressed(32)
+                        }
+                        _ => return Err(ErrorKind::ImageDecode(format!("Unsupported DXGI format: {}.", dxgi_format)).into()),
+                    }
+                }
+                _ => return Err(ErrorKind::ImageDecode(format!("Unsupported DDS format: {}.", String::from_utf8_lossy(four_cc))).into()),
+            }
+        }
+        else if pixel_format_flags & (DDPF_RGB | DDPF_LUMINANCE | DDPF_ALPHAPIXELS) != 0 && [8, 16, 24, 32].contains(&bits_per_pixel) {
+            if pixel_format_flags & DDPF_ALPHAPIXELS == 0 { masks[3] = 0; }
+            DdsFormat::Uncompressed(bits_per_pixel)
+        }
+        else {
+            return Err(ErrorKind::ImageDecode("Unsupported DDS pixel format.".to_owned()).into());
+        };
+
+        if width == 0 || height == 0 {
+            return Err(ErrorKind::ImageDecode("The DDS Image has no size.".to_owned()).into());
+        }
+
+        // Each mipmap is half the size of the previous one, so there cannot be more mipmaps than bits in the biggest side of the image.
+        if mipmap_count > 32 - width.max(height).leading_zeros() {
+            return Err(ErrorKind::ImageDecode(format!("Invalid DDS mipmap count: {}.", mipmap_count)).into());
+        }
+
+        let header = Self {
+            width,
+            height,
+            mipmap_count,
+            faces,
+            is_cubemap,
+            format,
+            masks,
+            luminance: pixel_format_flags & DDPF_LUMINANCE != 0,
+            data_offset,
+        };
+
+        let expected_size = header.get_face_size()
+            .and_then(|face_size| face_size.checked_mul(faces as usize))
+            .and_then(|data_size| data_size.checked_add(header.data_offset))
+            .ok_or_else(|| ErrorKind::ImageDecode("The DDS Image is too big.".to_owned()))?;
+        if data.len() < expected_size {
+            return Err(ErrorKind::ImageDecode(format!("The DDS Image is incomplete. Expected {} bytes, found {}.", expected_size, data.len())).into());
+        }
+
+        Ok(header)
+    }
+
+    /// This function returns the size of the provided mipmap, or `None` if the image doesn't have that mipmap.
+    pub fn get_mipmap_size(&self, mipmap: u32) -> Option<(u32, u32)> {
+        if mipmap < self.mipmap_count {
+            Some((self.width.checked_shr(mipmap).unwrap_or(0).max(1), self.height.checked_shr(mipmap).unwrap_or(0).max(1)))
+        } else { None }
+    }
+
+    /// This function returns the size in bytes of the provided amount of mipmaps of a face of the image, or `None` if it doesn't fit in memory.
+    fn get_mipmaps_size(&self, mipmap_count: u32) -> Option<usize> {
+        (0..mipmap_count).try_fold(0usize, |size, mipmap| {
+            let (width, height) = self.get_mipmap_size(mipmap)?;
+            size.checked_add(self.format.get_data_size(width, height)?)
+        })
+    }
+
+    /// This function returns the size in bytes of each face of the image, with all its mipmaps, or `None` if it doesn't fit in memory.
+    fn get_face_size(&self) -> Option<usize> {
+        self.get_mipmaps_size(self.mipmap_count)
+    }
+
+    /// This function decodes the provided mipmap of the first face of the image to RGBA8, returning its size and pixels.
+    pub fn decode_rgba8(&self, data: &[u8], mipmap: u32) -> Result<(u32, u32, Vec<u8>)> {
+        let (width, height) = self.get_mipmap_size(mipmap).ok_or_else(|| ErrorKind::ImageDecode(format!("The DDS Image doesn't have a mipmap {}.", mipmap)))?;
+        let offset = self.get_mipmaps_size(mipmap).and_then(|size| size.checked_add(self.data_offset));
+        let size = self.format.get_data_size(width, height);
+        let (offset, size) = offset.zip(size).ok_or_else(|| ErrorKind::ImageDecode("The DDS Image is too big.".to_owned()))?;
+        let data = data.get_bytes_checked(offset, size)?;
+
+        let pixels = match self.format {
+            DdsFormat::Uncompressed(bits) => self.decode_uncompressed(data, width, height, bits),
+            format => decode_blocks(data, width, height, format),
+        };
+
+        Ok((width, height, pixels))
+    }
+
+    /// This function decodes an uncompressed image, using the channel masks of the header.
+    fn decode_uncompressed(&self, data: &[u8], width: u32, height: u32, bits: u32) -> Vec<u8> {
+        let bytes_per_pixel = bits as usize / 8;
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+        for pixel in data.chunks_exact(bytes_per_pixel) {
+            let mut value = 0u32;
+            for (index, byte) in pixel.iter().enumerate() {
+                value |= (*byte as u32) << (index * 8);
+            }
+
+            let red = extract_channel(value, self.masks[0]).unwrap_or(0);
+            if self.luminance {
+                pixels.extend_from_slice(&[red, red, red]);
+            } else {
+                pixels.push(red);
+                pixels.push(extract_channel(value, self.masks[1]).unwrap_or(0));
+                pixels.push(extract_channel(value, self.masks[2]).unwrap_or(0));
+            }
+            pixels.push(extract_channel(value, self.masks[3]).unwrap_or(255));
+        }
+
+        pixels
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                              Encoding functions
+//---------------------------------------------------------------------------//
+
+/// This function encodes a RGBA8 image into an uncompressed 32 bits DDS Image.
+///
+/// If `mipmaps` is true, all the mipmaps of the image are generated.
+pub fn encode_rgba8(width: u32, height: u32, pixels: &[u8], mipmaps: bool) -> Result<Vec<u8>> {
+    if width == 0 || height == 0 || pixels.len() != width as usize * height as usize * 4 {
+        return Err(ErrorKind::ImageEncode("The size of the image doesn't match the amount of pixels provided.".to_owned()).into());
+    }
+
+    let mipmap_count = if mipmaps { 32 - width.max(height).leading_zeros() } else { 1 };
+    let mut flags = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PITCH | DDSD_PIXELFORMAT;
+    let mut caps = DDSCAPS_TEXTURE;
+    if mipmap_count > 1 {
+        flags |= DDSD_MIPMAPCOUNT;
+        caps |= DDSCAPS_COMPLEX | DDSCAPS_MIPMAP;
+    }
+
+    let mut data = Vec::with_capacity(HEADER_SIZE + pixels.len() * 2);
+    data.extend_from_slice(SIGNATURE);
+    data.encode_integer_u32(124);
+    data.encode_integer_u32(flags);
+    data.encode_integer_u32(height);
+    data.encode_integer_u32(width);
+    data.encode_integer_u32(width * 4);
+    data.encode_integer_u32(0);
+    data.encode_integer_u32(mipmap_count);
+    data.extend_from_slice(&[0; 44]);
+
+    // Pixel format: BGRA, 32 bits.
+    data.encode_integer_u32(32);
+    data.encode_integer_u32(DDPF_RGB | DDPF_ALPHAPIXELS);
+    data.encode_integer_u32(0);
+    data.encode_integer_u32(32);
+    data.encode_integer_u32(0xFF_0000);
+    data.encode_integer_u32(0xFF00);
+    data.encode_integer_u32(0xFF);
+    data.encode_integer_u32(0xFF00_0000);
+
+    data.encode_integer_u32(caps);
+    data.extend_from_slice(&[0; 16]);
+
+    let mut mipmap = (width, height, pixels.to_vec());
+    for level in 0..mipmap_count {
+        if level > 0 {
+            mipmap = downscale_rgba8(mipmap.0, mipmap.1, &mipmap.2);
+        }
+
+        for pixel in mipmap.2.chunks_exact(4) {
+            data.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+        }
+    }
+
+    Ok(data)
+}
+
+/// This function generates the next mipmap of a RGBA8 image, halving its size with a box filter.
+fn downscale_rgba8(width: u32, height: u32, pixels: &[u8]) -> (u32, u32, Vec<u8>) {
+    let new_width = (width / 2).max(1);
+    let new_height = (height / 2).max(1);
+    let mut new_pixels = Vec::with_capacity(new_width as usize * new_height as usize * 4);
+    for y in 0..new_height {
+        for x in 0..new_width {
+            for channel in 0..4 {
+                let mut sum = 0u32;
+                let mut count = 0u32;
+                for (sample_x, sample_y) in &[(x * 2, y * 2), (x * 2 + 1, y * 2), (x * 2, y * 2 + 1), (x * 2 + 1, y * 2 + 1)] {
+                    if *sample_x < width && *sample_y < height {
+                        sum += pixels[((*sample_y * width + *sample_x) * 4 + channel) as usize] as u32;
+                        count += 1;
+                    }
+                }
+                new_pixels.push(((sum + count / 2) / count) as u8);
+            }
+        }
+    }
+
+    (new_width, new_height, new_pixels)
+}
+
+//---------------------------------------------------------------------------//
+//                              Decoding functions
+//---------------------------------------------------------------------------//
+
+/// This function extracts a channel from a pixel using its mask, expanding it to 8 bits. Returns `None` if the mask is empty.
+fn extract_channel(value: u32, mask: u32) -> Option<u8> {
+    if mask == 0 { return None; }
+    let bits = mask.count_ones();
+    let channel = (value & mask) >> mask.trailing_zeros();
+    let max = if bits >= 32 { u32::MAX } else { (1u32 << bits) - 1 };
+    Some((channel as u64 * 255 / max as u64) as u8)
+}
+
+/// This function returns the amount of 4x4 blocks needed to cover the provided amount of pixels.
+fn get_block_count(size: u32) -> usize {
+    ((size as usize + 3) >> 2).max(1)
+}
+
+/// This function decodes a block-compressed image to RGBA8.
+fn decode_blocks(data: &[u8], width: u32, height: u32, format: DdsFormat) -> Vec<u8> {
+    let block_size = format.get_block_size().unwrap_or(16);
+    let blocks_x = get_block_count(width);
+    let mut pixels = vec![0; width as usize * height as usize * 4];
+
+    for (index, block) in data.chunks_exact(block_size).enumerate() {
+        let block_pixels = match format {
+            DdsFormat::Dxt1 => decode_bc1_block(block, true),
+            DdsFormat::Dxt3 => decode_bc2_block(block),
+            DdsFormat::Dxt5 => decode_bc3_block(block),
+            DdsFormat::Bc4 => decode_bc4_block(block),
+            DdsFormat::Bc5 => decode_bc5_block(block),
+            DdsFormat::Bc7 => decode_bc7_block(block),
+            DdsFormat::Uncompressed(_) => unreachable!(),
+        };
+
+        // Copy the block to the image, ignoring the pixels out of it.
+        let block_x = (index % blocks_x) * 4;
+        let block_y = (index / blocks_x) * 4;
+        for y in 0..4 {
+            for x in 0..4 {
+                let pixel_x = block_x + x;
+                let pixel_y = block_y + y;
+                if pixel_x < width as usize && pixel_y < height as usize {
+                    let offset = (pixel_y * width as usize + pixel_x) * 4;
+                    pixels[offset..offset + 4].copy_from_slice(&block_pixels[y * 4 + x]);
+                }
+            }
+        }
+    }
+
+    pixels
+}
+
+/// This function expands a RGB565 colour to RGB8.
+fn decode_rgb565(color: u16) -> [u8; 3] {
+    let red = ((color >> 11) & 0x1F) as u32;
+    let green = ((color >> 5) & 0x3F) as u32;
+    let blue = (color & 0x1F) as u32;
+    [(red * 255 / 31) as u8, (green * 255 / 63) as u8, (blue * 255 / 31) as u8]
+}
+
+/// This function decodes a BC1 (DXT1) block. If `allow_alpha` is false, the block is always decoded in four-colour mode.
+fn decode_bc1_block(block: &[u8], allow_alpha: bool) -> [[u8; 4]; 16] {
+    let color_0 = u16::from_le_bytes([block[0], block[1]]);
+    let color_1 = u16::from_le_bytes([block[2], block[3]]);
+    let rgb_0 = decode_rgb565(color_0);
+    let rgb_1 = decode_rgb565(color_1);
+
+    let mut palette = [[0u8; 4]; 4];
+    palette[0] = [rgb_0[0], rgb_0[1], rgb_0[2], 255];
+    palette[1] = [rgb_1[0], rgb_1[1], rgb_1[2], 255];
+    if color_0 > color_1 || !allow_alpha {
+        for channel in 0..3 {
+            palette[2][channel] = ((2 * rgb_0[channel] as u32 + rgb_1[channel] as u32 + 1) / 3) as u8;
+            palette[3][channel] = ((rgb_0[channel] as u32 + 2 * rgb_1[channel] as u32 + 1) / 3) as u8;
+        }
+        palette[2][3] = 255;
+        palette[3][3] = 255;
+    } else {
+        for channel in 0..3 {
+            palette[2][channel] = ((rgb_0[channel] as u32 + rgb_1[channel] as u32) / 2) as u8;
+        }
+        palette[2][3] = 255;
+        palette[3] = [0, 0, 0, 0];
+    }
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let mut pixels = [[0u8; 4]; 16];
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = palette[((indices >> (index * 2)) & 0x3) as usize];
+    }
+    pixels
+}
+
+/// This function decodes a BC2 (DXT3) block.
+fn decode_bc2_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut pixels = decode_bc1_block(&block[8..16], false);
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        let alpha = (block[index / 2] >> ((index % 2) * 4)) & 0xF;
+        pixel[3] = alpha * 17;
+    }
+    pixels
+}
+
+/// This function decodes a BC3 (DXT5) block.
+fn decode_bc3_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut pixels = decode_bc1_block(&block[8..16], false);
+    let alpha = decode_alpha_block(&block[0..8]);
+    for (pixel, alpha) in pixels.iter_mut().zip(alpha.iter()) {
+        pixel[3] = *alpha;
+    }
+    pixels
+}
+
+/// This function decodes a BC4 block. As it only has one channel, we decode it as greyscale.
+fn decode_bc4_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let red = decode_alpha_block(block);
+    let mut pixels = [[0u8; 4]; 16];
+    for (pixel, red) in pixels.iter_mut().zip(red.iter()) {
+        *pixel = [*red, *red, *red, 255];
+    }
+    pixels
+}
+
+/// This function decodes a BC5 block. The blue channel is left empty.
+fn decode_bc5_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let red = decode_alpha_block(&block[0..8]);
+    let green = decode_alpha_block(&block[8..16]);
+    let mut pixels = [[0u8; 4]; 16];
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = [red[index], green[index], 0, 255];
+    }
+    pixels
+}
+
+/// This function decodes a single-channel block, like the alpha block of BC3 or the channels of BC4/5.
+fn decode_alpha_block(block: &[u8]) -> [u8; 16] {
+    let alpha_0 = block[0] as u32;
+    let alpha_1 = block[1] as u32;
+    let mut palette = [0u8; 8];
+    palette[0] = alpha_0 as u8;
+    palette[1] = alpha_1 as u8;
+    if alpha_0 > alpha_1 {
+        for index in 1..7 {
+            palette[index as usize + 1] = (((7 - index) * alpha_0 + index * alpha_1 + 3) / 7) as u8;
+        }
+    } else {
+        for index in 1..5 {
+            palette[index as usize + 1] = (((5 - index) * alpha_0 + index * alpha_1 + 2) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    let mut indices = 0u64;
+    for (index, byte) in block[2..8].iter().enumerate() {
+        indices |= (*byte as u64) << (index * 8);
+    }
+
+    let mut alpha = [0u8; 16];
+    for (index, value) in alpha.iter_mut().enumerate() {
+        *value = palette[((indices >> (index * 3)) & 0x7) as usize];
+    }
+    alpha
+}
+
+//---------------------------------------------------------------------------//
+//                              BC7 decoding
+//---------------------------------------------------------------------------//
+
+/// Partitions for BC7 blocks with two subsets. Each bit marks the subset of each pixel.
+pub(crate) const BC7_PARTITIONS_2: [u16; 64] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80, 0xC800, 0xFFEC, 0xFE80, 0xE800, 0xFFE8, 0xFF00, 0xFFF0, 0xF000,
+    0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE, 0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C,
+    0xAAAA, 0xF0F0, 0x5A5A, 0x33CC, 0x3C3C, 0x55AA, 0x9696, 0xA55A, 0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+    0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C, 0x9336, 0x9CC6, 0x817E, 0xE718, 0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+];
+
+/// Partitions for BC7 blocks with three subsets. Each two bits mark the subset of each pixel.
+pub(crate) const BC7_PARTITIONS_3: [u32; 64] = [
+    0xAA68_5050, 0x6A5A_5040, 0x5A5A_4200, 0x5450_A0A8, 0xA5A5_0000, 0xA0A0_5050, 0x5555_A0A0, 0x5A5A_5050,
+    0xAA55_0000, 0xAA55_5500, 0xAAAA_5500, 0x9090_9090, 0x9494_9494, 0xA4A4_A4A4, 0xA9A5_9450, 0x2A0A_4250,
+    0xA594_5040, 0x0A42_5054, 0xA5A5_A500, 0x55A0_A0A0, 0xA8A8_5454, 0x6A6A_4040, 0xA4A4_5000, 0x1A1A_0500,
+    0x0050_A4A4, 0xAAA5_9090, 0x1469_6914, 0x6969_1400, 0xA085_85A0, 0xAA82_1414, 0x50A4_A450, 0x6A5A_0200,
+    0xA9A5_8000, 0x5090_A0A8, 0xA8A0_9050, 0x2424_2424, 0x00AA_5500, 0x2492_4924, 0x2449_9224, 0x50A5_0A50,
+    0x500A_A550, 0xAAAA_4444, 0x6666_0000, 0xA5A0_A5A0, 0x50A0_50A0, 0x6928_6928, 0x44AA_AA44, 0x6666_6600,
+    0xAA44_4444, 0x54A8_54A8, 0x9580_9580, 0x9696_9600, 0xA854_54A8, 0x8095_9580, 0xAA14_1414, 0x9696_0000,
+    0xAAAA_1414, 0xA050_50A0, 0xA0A5_A5A0, 0x9600_0000, 0x4080_4080, 0xA9A8_A9A8, 0xAAAA_AA44, 0x2A4A_5254,
+];
+
+/// Anchor pixel of the second subset, for partitions with two subsets.
+pub(crate) const BC7_ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+    15,  2,  8,  2,  2,  8,  8, 15,  2,  8,  2,  2,  8,  8,  2,  2,
+    15, 15,  6,  8,  2,  8, 15, 15,  2,  8,  2,  2,  2, 15, 15,  6,
+     6,  2,  6,  8, 15, 15,  2,  2, 15, 15, 15, 15, 15,  2,  2, 15,
+];
+
+/// Anchor pixel of the second subset, for partitions with three subsets.
+pub(crate) const BC7_ANCHORS_3_2: [u8; 64] = [
+     3,  3, 15, 15,  8,  3, 15, 15,  8,  8,  6,  6,  6,  5,  3,  3,
+     3,  3,  8, 15,  3,  3,  6, 10,  5,  8,  8,  6,  8,  5, 15, 15,
+     8, 15,  3,  5,  6, 10,  8, 15, 15,  3, 15,  5, 15, 15, 15, 15,
+     3, 15,  5,  5,  5,  8,  5, 10,  5, 10,  8, 13, 15, 12,  3,  3,
+];
+
+/// Anchor pixel of the third subset, for partitions with three subsets.
+pub(crate) const BC7_ANCHORS_3_3: [u8; 64] = [
+    15,  8,  8,  3, 15, 15,  3,  8, 15, 15, 15, 15, 15, 15, 15,  8,
+    15,  8, 15,  3, 15,  8, 15,  8,  3, 15,  6, 10, 15, 15, 10,  8,
+    15,  3, 15, 10, 10,  8,  9, 10,  6, 15,  8, 15,  3,  6,  6,  8,
+    15,  3, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,  3, 15, 15,  8,
+];
+
+/// Interpolation weights for 2, 3 and 4 bits indices.
+const BC7_WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const BC7_WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const BC7_WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// This struct contains the properties of each BC7 mode.
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_p_bits: bool,
+    shared_p_bits: bool,
+    index_bits: u32,
+    secondary_index_bits: u32,
+}
+
+/// Properties of each one of the eight BC7 modes.
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, index_selection_bits: 0, color_bits: 4, alpha_bits: 0, endpoint_p_bits: true, shared_p_bits: false, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 6, alpha_bits: 0, endpoint_p_bits: false, shared_p_bits: true, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 0, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 0, endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 1, color_bits: 5, alpha_bits: 6, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 3 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 0, color_bits: 7, alpha_bits: 8, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 2 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 7, endpoint_p_bits: true, shared_p_bits: false, index_bits: 4, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 5, endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+];
+
+/// This struct allow us to read a BC7 block bit by bit, from the least significant bit.
+struct BitReader {
+    data: u128,
+    position: u32,
+}
+
+/// Implementation of BitReader.
+impl BitReader {
+    fn new(block: &[u8]) -> Self {
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&block[0..16]);
+        Self {
+            data: u128::from_le_bytes(bytes),
+            position: 0,
+        }
+    }
+
+    fn read(&mut self, bits: u32) -> u32 {
+        if bits == 0 { return 0; }
+        let value = ((self.data >> self.position) & ((1u128 << bits) - 1)) as u32;
+        self.position += bits;
+        value
+    }
+}
+
+/// This function returns the subset of the provided pixel, for the provided partition.
+fn get_bc7_subset(subsets: usize, partition: usize, pixel: usize) -> usize {
+    match subsets {
+        2 => ((BC7_PARTITIONS_2[partition] >> pixel) & 1) as usize,
+        3 => ((BC7_PARTITIONS_3[partition] >> (pixel * 2)) & 3) as usize,
+        _ => 0,
+    }
+}
+
+/// This function returns if the provided pixel is the anchor of its subset. Anchors have one less bit in their indices.
+fn is_bc7_anchor(subsets: usize, partition: usize, pixel: usize) -> bool {
+    if pixel == 0 { return true; }
+    match subsets {
+        2 => pixel == BC7_ANCHORS_2[partition] as usize,
+        3 => pixel == BC7_ANCHORS_3_2[partition] as usize || pixel == BC7_ANCHORS_3_3[partition] as usize,
+        _ => false,
+    }
+}
+
+/// This function expands a value of `bits` bits to 8 bits.
+fn expand_bc7_value(value: u32, bits: u32) -> u8 {
+    let value = value << (8 - bits);
+    (value | (value >> bits)) as u8
+}
+
+/// This function interpolates between two endpoints using the provided weight.
+fn interpolate_bc7(endpoint_0: u8, endpoint_1: u8, weight: u32) -> u8 {
+    (((64 - weight) * endpoint_0 as u32 + weight * endpoint_1 as u32 + 32) >> 6) as u8
+}
+
+/// This function returns the weight for the provided index.
+fn get_bc7_weight(bits: u32, index: u32) -> u32 {
+    match bits {
+        2 => BC7_WEIGHTS_2[index as usize],
+        3 => BC7_WEIGHTS_3[index as usize],
+        _ => BC7_WEIGHTS_4[index as usize],
+    }
+}
+
+/// This function decodes a BC7 block.
+fn decode_bc7_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut pixels = [[0u8; 4]; 16];
+    if block[0] == 0 { return pixels; }
+
+    let mode_index = block[0].trailing_zeros() as usize;
+    let mode = &BC7_MODES[mode_index];
+    let mut reader = BitReader::new(block);
+    reader.read(mode_index as u32 + 1);
+
+    let partition = reader.read(mode.partition_bits) as usize;
+    let rotation = reader.read(mode.rotation_bits);
+    let index_selection = reader.read(mode.index_selection_bits);
+
+    // Endpoints, first all the reds, then all the greens, blues and alphas.
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..3 {
+        for endpoint in endpoints.iter_mut().take(endpoint_count) {
+            endpoint[channel] = reader.read(mode.color_bits);
+        }
+    }
+
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        endpoint[3] = if mode.alpha_bits > 0 { reader.read(mode.alpha_bits) } else { 255 };
+    }
+
+    // Apply the P-bits, if any, and expand the endpoints to 8 bits.
+    let mut color_bits = mode.color_bits;
+    let mut alpha_bits = mode.alpha_bits;
+    if mode.endpoint_p_bits || mode.shared_p_bits {
+        let p_bits = if mode.endpoint_p_bits {
+            (0..endpoint_count).map(|_| reader.read(1)).collect::<Vec<u32>>()
+        } else {
+            let shared = (0..mode.subsets).map(|_| reader.read(1)).collect::<Vec<u32>>();
+            (0..endpoint_count).map(|x| shared[x / 2]).collect::<Vec<u32>>()
+        };
+
+        for (endpoint, p_bit) in endpoints.iter_mut().zip(p_bits.iter()) {
+            for (channel, value) in endpoint.iter_mut().enumerate() {
+                if channel < 3 || mode.alpha_bits > 0 {
+                    *value = (*value << 1) | p_bit;
+                }
+            }
+        }
+
+        color_bits += 1;
+        if alpha_bits > 0 { alpha_bits += 1; }
+    }
+
+    let mut endpoints_expanded = [[0u8; 4]; 6];
+    for (expanded, endpoint) in endpoints_expanded.iter_mut().zip(endpoints.iter()).take(endpoint_count) {
+        for channel in 0..3 {
+            expanded[channel] = expand_bc7_value(endpoint[channel], color_bits);
+        }
+        expanded[3] = if alpha_bits > 0 { expand_bc7_value(endpoint[3], alpha_bits) } else { 255 };
+    }
+
+    // Primary and secondary indices.
+    let mut indices = [0u32; 16];
+    for (pixel, index) in indices.iter_mut().enumerate() {
+        let bits = if is_bc7_anchor(mode.subsets, partition, pixel) { mode.index_bits - 1 } else { mode.index_bits };
+        *index = reader.read(bits);
+    }
+
+    let mut secondary_indices = [0u32; 16];
+    if mode.secondary_index_bits > 0 {
+        for (pixel, index) in secondary_indices.iter_mut().enumerate() {
+            let bits = if pixel == 0 { mode.secondary_index_bits - 1 } else { mode.secondary_index_bits };
+            *index = reader.read(bits);
+        }
+    }
+
+    for (pixel_index, pixel) in pixels.iter_mut().enumerate() {
+        let subset = get_bc7_subset(mode.subsets, partition, pixel_index);
+        let endpoint_0 = endpoints_expanded[subset * 2];
+        let endpoint_1 = endpoints_expanded[subset * 2 + 1];
+
+        if mode.secondary_index_bits > 0 {
+            let (color_index, color_index_bits, alpha_index, alpha_index_bits) = if index_selection == 0 {
+                (indices[pixel_index], mode.index_bits, secondary_indices[pixel_index], mode.secondary_index_bits)
+            } else {
+                (secondary_indices[pixel_index], mode.secondary_index_bits, indices[pixel_index], mode.index_bits)
+            };
+
+            let color_weight = get_bc7_weight(color_index_bits, color_index);
+            let alpha_weight = get_bc7_weight(alpha_index_bits, alpha_index);
+            for channel in 0..3 {
+                pixel[channel] = interpolate_bc7(endpoint_0[channel], endpoint_1[channel], color_weight);
+            }
+            pixel[3] = interpolate_bc7(endpoint_0[3], endpoint_1[3], alpha_weight);
+        } else {
+            let weight = get_bc7_weight(mode.index_bits, indices[pixel_index]);
+            for channel in 0..4 {
+                pixel[channel] = interpolate_bc7(endpoint_0[channel], endpoint_1[channel], weight);
+            }
+        }
+
+        match rotation {
+            1 => pixel.swap(0, 3),
+            2 => pixel.swap(1, 3),
+            3 => pixel.swap(2, 3),
+            _ => {}
+        }
+    }
+
+    pixels
+}
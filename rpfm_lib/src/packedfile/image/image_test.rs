@@ -0,0 +1,210 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `Image` module, to make sure we decode and encode the formats we support correctly.
+!*/
+
+use crate::common::encoder::Encoder;
+use super::*;
+use super::dds::*;
+
+/// This function builds a DDS Image with the provided FourCC and data, for a 4x4 image without mipmaps.
+fn build_dds_four_cc(four_cc: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut dds = vec![];
+    dds.extend_from_slice(b"DDS ");
+    dds.encode_integer_u32(124);
+    dds.encode_integer_u32(0x1007);
+    dds.encode_integer_u32(4);
+    dds.encode_integer_u32(4);
+    dds.encode_integer_u32(data.len() as u32);
+    dds.encode_integer_u32(0);
+    dds.encode_integer_u32(1);
+    dds.extend_from_slice(&[0; 44]);
+    dds.encode_integer_u32(32);
+    dds.encode_integer_u32(0x4);
+    dds.extend_from_slice(four_cc);
+    dds.extend_from_slice(&[0; 20]);
+    dds.encode_integer_u32(0x1000);
+    dds.extend_from_slice(&[0; 16]);
+    dds.extend_from_slice(data);
+    dds
+}
+
+/// This function builds a BC7 block from a list of (value, bits) pairs, written from the least significant bit.
+fn build_bc7_block(values: &[(u32, u32)]) -> Vec<u8> {
+    let mut block = 0u128;
+    let mut position = 0;
+    for (value, bits) in values {
+        block |= (*value as u128) << position;
+        position += bits;
+    }
+    assert_eq!(position, 128);
+    block.to_le_bytes().to_vec()
+}
+
+#[test]
+fn test_encode_dds_uncompressed() {
+    let pixels = (0..5 * 3 * 4).map(|x| (x * 3) as u8).collect::<Vec<u8>>();
+    let data = Image::encode_dds(5, 3, &pixels, true).unwrap();
+    let image = Image::read(&data).unwrap();
+
+    assert_eq!(image.get_format(), ImageFormat::Dds(DdsFormat::Uncompressed(32)));
+    assert_eq!(image.get_width(), 5);
+    assert_eq!(image.get_height(), 3);
+    assert_eq!(image.get_mipmap_count(), 3);
+    assert!(!image.is_cubemap());
+    assert_eq!(image.decode_rgba8(0).unwrap(), (5, 3, pixels));
+
+    let (width, height, pixels) = image.decode_rgba8(2).unwrap();
+    assert_eq!((width, height, pixels.len()), (1, 1, 4));
+    assert!(image.decode_rgba8(3).is_err());
+}
+
+#[test]
+fn test_encode_png() {
+    let pixels = (0..64 * 32 * 4).map(|x| (x % 251) as u8).collect::<Vec<u8>>();
+    let data = Image::encode_png(64, 32, &pixels).unwrap();
+    let image = Image::read(&data).unwrap();
+
+    assert_eq!(image.get_format(), ImageFormat::Png);
+    assert_eq!((image.get_width(), image.get_height(), image.get_mipmap_count()), (64, 32, 1));
+    assert_eq!(image.decode_rgba8(0).unwrap(), (64, 32, pixels));
+    assert!(image.decode_rgba8(1).is_err());
+
+    let thumbnail = Image::read(&image.to_png_thumbnail(16).unwrap()).unwrap();
+    assert_eq!((thumbnail.get_width(), thumbnail.get_height()), (16, 8));
+}
+
+#[test]
+fn test_decode_tga() {
+    let mut data = vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    data.encode_integer_u16(2);
+    data.encode_integer_u16(1);
+    data.extend_from_slice(&[32, 0x28]);
+    data.extend_from_slice(&[255, 0, 0, 255, 0, 255, 0, 128]);
+
+    let image = Image::read(&data).unwrap();
+    assert_eq!(image.get_format(), ImageFormat::Tga);
+    assert_eq!(image.decode_rgba8(0).unwrap(), (2, 1, vec![0, 0, 255, 255, 0, 255, 0, 128]));
+}
+
+#[test]
+fn test_decode_dxt1() {
+
+    // Red and blue endpoints. First row uses the four colours of the palette, the rest only red.
+    let block = [0x00, 0xF8, 0x1F, 0x00, 0b1110_0100, 0, 0, 0];
+    let image = Image::read(&build_dds_four_cc(b"DXT1", &block)).unwrap();
+    assert_eq!(image.get_format(), ImageFormat::Dds(DdsFormat::Dxt1));
+
+    let (_, _, pixels) = image.decode_rgba8(0).unwrap();
+    assert_eq!(&pixels[0..4], &[255, 0, 0, 255]);
+    assert_eq!(&pixels[4..8], &[0, 0, 255, 255]);
+    assert_eq!(&pixels[8..12], &[170, 0, 85, 255]);
+    assert_eq!(&pixels[12..16], &[85, 0, 170, 255]);
+    assert_eq!(&pixels[60..64], &[255, 0, 0, 255]);
+}
+
+#[test]
+fn test_decode_dxt5() {
+
+    // Alpha goes from 255 to 0 using the eight values palette. Colour is white.
+    let block = [255, 0, 0b1000_1000, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0];
+    let image = Image::read(&build_dds_four_cc(b"DXT5", &block)).unwrap();
+    let (_, _, pixels) = image.decode_rgba8(0).unwrap();
+    assert_eq!(&pixels[0..4], &[255, 255, 255, 255]);
+    assert_eq!(&pixels[4..8], &[255, 255, 255, 0]);
+    assert_eq!(&pixels[8..12], &[255, 255, 255, 219]);
+}
+
+#[test]
+fn test_decode_bc4_bc5() {
+    let block = [200, 100, 0, 0, 0, 0, 0, 0];
+    let image = Image::read(&build_dds_four_cc(b"ATI1", &block)).unwrap();
+    let (_, _, pixels) = image.decode_rgba8(0).unwrap();
+    assert_eq!(&pixels[0..4], &[200, 200, 200, 255]);
+
+    let block = [200, 100, 0, 0, 0, 0, 0, 0, 50, 10, 0, 0, 0, 0, 0, 0];
+    let image = Image::read(&build_dds_four_cc(b"ATI2", &block)).unwrap();
+    let (_, _, pixels) = image.decode_rgba8(0).unwrap();
+    assert_eq!(&pixels[0..4], &[200, 50, 0, 255]);
+}
+
+#[test]
+fn test_decode_bc7() {
+
+    // Mode 6: one subset, RGBA endpoints of 7 bits plus one P-bit each, and 4 bits indices.
+    let mut values = vec![(1 << 6, 7)];
+    values.extend_from_slice(&[(64, 7), (127, 7), (0, 7), (127, 7), (32, 7), (32, 7), (127, 7), (127, 7), (0, 1), (1, 1)]);
+    values.push((0, 3));
+    values.push((15, 4));
+    (2..16).for_each(|_| values.push((0, 4)));
+    let block = build_bc7_block(&values);
+
+    let mut data = build_dds_four_cc(b"DX10", &[]);
+    data.encode_integer_u32(98);
+    data.encode_integer_u32(3);
+    data.encode_integer_u32(0);
+    data.encode_integer_u32(1);
+    data.encode_integer_u32(0);
+    data.extend_from_slice(&block);
+
+    let image = Image::read(&data).unwrap();
+    assert_eq!(image.get_format(), ImageFormat::Dds(DdsFormat::Bc7));
+
+    let (_, _, pixels) = image.decode_rgba8(0).unwrap();
+    assert_eq!(&pixels[0..4], &[128, 0, 64, 254]);
+    assert_eq!(&pixels[4..8], &[255, 255, 65, 255]);
+}
+
+#[test]
+fn test_bc7_partition_tables() {
+    for partition in 0..64 {
+        assert_eq!(BC7_PARTITIONS_2[partition] & 1, 0);
+        assert_eq!((BC7_PARTITIONS_2[partition] >> BC7_ANCHORS_2[partition]) & 1, 1);
+
+        assert_eq!(BC7_PARTITIONS_3[partition] & 3, 0);
+        assert_eq!((BC7_PARTITIONS_3[partition] >> (BC7_ANCHORS_3_2[partition] * 2)) & 3, 1);
+        assert_eq!((BC7_PARTITIONS_3[partition] >> (BC7_ANCHORS_3_3[partition] * 2)) & 3, 2);
+    }
+}
+
+#[test]
+fn test_read_broken_dds() {
+    let mut data = build_dds_four_cc(b"DXT1", &[0; 8]);
+    data.truncate(data.len() - 1);
+
+    // Broken images can still be read, but not decoded.
+    let image = Image::read(&data).unwrap();
+    assert_eq!(image.get_format(), ImageFormat::Unknown);
+    assert!(image.decode_rgba8(0).is_err());
+    assert_eq!(image.get_data(), &*data);
+}
+
+#[test]
+fn test_read_dds_invalid_sizes() {
+
+    // A 4x4 image can have 3 mipmaps at most.
+    let mut data = build_dds_four_cc(b"DXT1", &[0; 8]);
+    data[28..32].copy_from_slice(&64u32.to_le_bytes());
+    assert!(DdsHeader::read(&data).is_err());
+
+    data[28..32].copy_from_slice(&4u32.to_le_bytes());
+    assert!(DdsHeader::read(&data).is_err());
+
+    // A cubemap array with so many faces their count doesn't fit in an u32.
+    let mut data = build_dds_four_cc(b"DX10", &[]);
+    data.encode_integer_u32(71);
+    data.encode_integer_u32(3);
+    data.encode_integer_u32(0x4);
+    data.encode_integer_u32(u32::MAX);
+    data.encode_integer_u32(0);
+    assert!(DdsHeader::read(&data).is_err());
+}
@@ -11,12 +11,29 @@
 /*!
 Module with all the code to interact with Image PackedFiles.
 
-Images... we really just get their that to memory. Nothing more.
+Images are kept in memory as they are, so we never lose data when saving them. On read we also get
+their format and size (and mipmaps, in case of DDS Images), and we can decode them to RGBA8 on demand.
+
+Supported formats are DDS (DXT1/3/5, BC4/5/7 and uncompressed), PNG, TGA and JPEG.
 !*/
 
+use image::{ColorType, ImageFormat as ImageCrateFormat, RgbaImage};
+use image::imageops::thumbnail;
+use image::io::Reader;
+use image::png::PngEncoder;
 use serde_derive::{Serialize, Deserialize};
 
-use rpfm_error::Result;
+use std::fmt;
+use std::io::Cursor;
+
+use rpfm_error::{ErrorKind, Result};
+
+use self::dds::{DdsFormat, DdsHeader};
+
+pub mod dds;
+
+#[cfg(test)]
+mod image_test;
 
 /// Extensions used by Image PackedFiles.
 pub const EXTENSIONS: [&str; 5] = [
@@ -27,6 +44,12 @@ pub const EXTENSIONS: [&str; 5] = [
     ".png",
 ];
 
+/// Signature of PNG Images.
+const SIGNATURE_PNG: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// Signature of JPEG Images.
+const SIGNATURE_JPEG: &[u8; 2] = b"\xFF\xD8";
+
 //---------------------------------------------------------------------------//
 //                              Enum & Structs
 //---------------------------------------------------------------------------//
@@ -37,6 +60,33 @@ pub struct Image {
 
     /// The raw_data of the image.
     data: Vec<u8>,
+
+    /// The format of the image.
+    format: ImageFormat,
+
+    /// Width of the image, in pixels.
+    width: u32,
+
+    /// Height of the image, in pixels.
+    height: u32,
+
+    /// Amount of mipmaps of the image, including the full-size one.
+    mipmap_count: u32,
+
+    /// If the image is a cubemap.
+    is_cubemap: bool,
+}
+
+/// This enum represents the formats of Image we can decode.
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum ImageFormat {
+    Dds(DdsFormat),
+    Png,
+    Tga,
+    Jpeg,
+
+    /// Image we don't know how to decode, or that failed to decode.
+    Unknown,
 }
 
 //---------------------------------------------------------------------------//
@@ -48,6 +98,11 @@ impl Default for Image {
     fn default() -> Self {
         Self {
             data: vec![],
+            format: ImageFormat::Unknown,
+            width: 0,
+            height: 0,
+            mipmap_count: 0,
+            is_cubemap: false,
         }
     }
 }
@@ -61,14 +116,155 @@ impl Image {
     }
 
     /// This function creates a `Image` from a `Vec<u8>`.
+    ///
+    /// Images we cannot decode are still read, but their format is set as `Unknown`. To know why they failed, use `decode_rgba8`.
     pub fn read(packed_file_data: &[u8]) -> Result<Self> {
-        Ok(Self {
+        let mut image = Self {
             data: packed_file_data.to_vec(),
-        })
+            ..Self::default()
+        };
+
+        if let Ok(header) = DdsHeader::read(packed_file_data) {
+            image.format = ImageFormat::Dds(header.format);
+            image.width = header.width;
+            image.height = header.height;
+            image.mipmap_count = header.mipmap_count;
+            image.is_cubemap = header.is_cubemap;
+        }
+
+        else if let Some(format) = Self::guess_non_dds_format(packed_file_data) {
+            if let Ok((width, height)) = Reader::with_format(Cursor::new(packed_file_data), format.into()).into_dimensions() {
+                image.format = format;
+                image.width = width;
+                image.height = height;
+                image.mipmap_count = 1;
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// This function returns the format of a non-DDS Image, based on its signature.
+    fn guess_non_dds_format(data: &[u8]) -> Option<ImageFormat> {
+        if data.starts_with(SIGNATURE_PNG) { Some(ImageFormat::Png) }
+        else if data.starts_with(SIGNATURE_JPEG) { Some(ImageFormat::Jpeg) }
+        else if data.starts_with(dds::SIGNATURE) || data.is_empty() { None }
+
+        // TGA doesn't have a signature, so we just try to decode whatever is left as TGA.
+        else { Some(ImageFormat::Tga) }
     }
 
     /// This function returns the data the provided `Image`.
     pub fn get_data(&self) -> &[u8] {
         &self.data
     }
+
+    /// This function returns the format of the `Image`.
+    pub fn get_format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// This function returns the width of the `Image`, in pixels.
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    /// This function returns the height of the `Image`, in pixels.
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// This function returns the amount of mipmaps of the `Image`, including the full-size one.
+    pub fn get_mipmap_count(&self) -> u32 {
+        self.mipmap_count
+    }
+
+    /// This function returns if the `Image` is a cubemap.
+    pub fn is_cubemap(&self) -> bool {
+        self.is_cubemap
+    }
+
+    /// This function decodes the provided mipmap of the `Image` to RGBA8, returning its width, height and pixels.
+    ///
+    /// Only DDS Images have mipmaps. For the rest, only the mipmap 0 is valid. For cubemaps, only the first face is decoded.
+    pub fn decode_rgba8(&self, mipmap: u32) -> Result<(u32, u32, Vec<u8>)> {
+        if self.data.starts_with(dds::SIGNATURE) {
+            let header = DdsHeader::read(&self.data)?;
+            header.decode_rgba8(&self.data, mipmap)
+        }
+
+        else if mipmap != 0 {
+            Err(ErrorKind::ImageDecode(format!("This Image doesn't have a mipmap {}.", mipmap)).into())
+        }
+
+        else {
+            let format = Self::guess_non_dds_format(&self.data).ok_or_else(|| ErrorKind::ImageDecode("Unknown Image format.".to_owned()))?;
+            let image = image::load_from_memory_with_format(&self.data, format.into()).map_err(|error| ErrorKind::ImageDecode(error.to_string()))?.to_rgba8();
+            Ok((image.width(), image.height(), image.into_raw()))
+        }
+    }
+
+    /// This function decodes the provided mipmap of the `Image` and encodes it as PNG.
+    pub fn to_png(&self, mipmap: u32) -> Result<Vec<u8>> {
+        let (width, height, pixels) = self.decode_rgba8(mipmap)?;
+        Self::encode_png(width, height, &pixels)
+    }
+
+    /// This function generates a PNG thumbnail of the `Image`, with its biggest side resized to the provided size.
+    ///
+    /// Images smaller than the thumbnail are not upscaled.
+    pub fn to_png_thumbnail(&self, size: u32) -> Result<Vec<u8>> {
+        let (width, height, pixels) = self.decode_rgba8(0)?;
+        if width <= size && height <= size {
+            return Self::encode_png(width, height, &pixels);
+        }
+
+        let (new_width, new_height) = if width >= height {
+            (size, ((height as u64 * size as u64) / width as u64).max(1) as u32)
+        } else {
+            (((width as u64 * size as u64) / height as u64).max(1) as u32, size)
+        };
+
+        let image = RgbaImage::from_raw(width, height, pixels).ok_or_else(|| ErrorKind::ImageDecode("The Image is incomplete.".to_owned()))?;
+        let image = thumbnail(&image, new_width, new_height);
+        Self::encode_png(new_width, new_height, image.as_raw())
+    }
+
+    /// This function encodes a RGBA8 image as PNG.
+    pub fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Result<Vec<u8>> {
+        let mut data = vec![];
+        PngEncoder::new(&mut data).encode(pixels, width, height, ColorType::Rgba8).map_err(|error| ErrorKind::ImageEncode(error.to_string()))?;
+        Ok(data)
+    }
+
+    /// This function encodes a RGBA8 image as an uncompressed DDS, optionally with all its mipmaps.
+    pub fn encode_dds(width: u32, height: u32, pixels: &[u8], mipmaps: bool) -> Result<Vec<u8>> {
+        dds::encode_rgba8(width, height, pixels, mipmaps)
+    }
+}
+
+/// Display implementation of `ImageFormat`.
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Dds(format) => write!(f, "DDS ({})", format),
+            Self::Png => write!(f, "PNG"),
+            Self::Tga => write!(f, "TGA"),
+            Self::Jpeg => write!(f, "JPEG"),
+            Self::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// Implementation to get the `image` crate's format from an `ImageFormat`. DDS and Unknown are mapped to PNG, as they're never decoded through it.
+impl From<ImageFormat> for ImageCrateFormat {
+    fn from(format: ImageFormat) -> Self {
+        match format {
+            ImageFormat::Tga => Self::Tga,
+            ImageFormat::Jpeg => Self::Jpeg,
+            ImageFormat::Dds(_) |
+            ImageFormat::Png |
+            ImageFormat::Unknown => Self::Png,
+        }
+    }
 }
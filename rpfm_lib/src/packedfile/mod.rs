@@ -18,17 +18,20 @@ For encoding/decoding/proper manipulation of the data in each type of `PackedFil
 !*/
 
 use rayon::prelude::*;
+use ron::ser::{to_string_pretty, PrettyConfig};
+use serde_derive::{Serialize, Deserialize};
 
 use std::{fmt, fmt::Display};
-use std::ops::Deref;
 
 use rpfm_error::{Error, ErrorKind, Result};
 
+use crate::context::GameContext;
 use crate::dependencies::Dependencies;
 use crate::packedfile::animpack::AnimPack;
 use crate::packedfile::ca_vp8::CaVp8;
+use crate::packedfile::esf::ESF;
 use crate::packedfile::image::Image;
-use crate::packedfile::table::{anim_fragment::AnimFragment, animtable::AnimTable, db::DB, loc::Loc, matched_combat::MatchedCombat};
+use crate::packedfile::table::{MergeConflict, anim_fragment::AnimFragment, animtable::AnimTable, db::DB, loc::Loc, matched_combat::MatchedCombat, migration::TableMigration, port::TablePort};
 use crate::packedfile::text::{Text, TextType};
 use crate::packedfile::rigidmodel::RigidModel;
 use crate::packfile::packedfile::{PackedFile, RawPackedFile};
@@ -37,6 +40,7 @@ use crate::SCHEMA;
 
 pub mod animpack;
 pub mod ca_vp8;
+pub mod esf;
 pub mod image;
 pub mod rigidmodel;
 pub mod table;
@@ -49,7 +53,7 @@ pub mod text;
 /// This enum represents a ***decoded `PackedFile`***,
 ///
 /// Keep in mind that, despite we having logic to recognize them, we can't decode many of them yet.
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum DecodedPackedFile {
     Anim,
     AnimFragment(AnimFragment),
@@ -63,7 +67,7 @@ pub enum DecodedPackedFile {
     Loc(Loc),
     MatchedCombat(MatchedCombat),
     RigidModel(RigidModel),
-    StarPos,
+    StarPos(ESF),
     Text(Text),
     Unknown,
 }
@@ -105,11 +109,20 @@ impl DecodedPackedFile {
 
     /// This function decodes a `RawPackedFile` into a `DecodedPackedFile`, returning it.
     pub fn decode(raw_packed_file: &mut RawPackedFile) -> Result<Self> {
+        Self::decode_with_schema(raw_packed_file, SCHEMA.read().unwrap().as_ref())
+    }
+
+    /// This function decodes a `RawPackedFile` into a `DecodedPackedFile` using the schema of the provided `GameContext`, returning it.
+    pub fn decode_with_context(raw_packed_file: &mut RawPackedFile, context: &GameContext) -> Result<Self> {
+        Self::decode_with_schema(raw_packed_file, context.get_ref_schema())
+    }
+
+    /// This function decodes a `RawPackedFile` into a `DecodedPackedFile` using the provided schema, if any, returning it.
+    fn decode_with_schema(raw_packed_file: &mut RawPackedFile, schema: Option<&Schema>) -> Result<Self> {
         match PackedFileType::get_packed_file_type(raw_packed_file.get_path()) {
 
             PackedFileType::AnimFragment => {
-                let schema = SCHEMA.read().unwrap();
-                match schema.deref() {
+                match schema {
                     Some(schema) => {
                         let data = raw_packed_file.get_data_and_keep_it()?;
                         let packed_file = AnimFragment::read(&data, &schema, false)?;
@@ -126,8 +139,7 @@ impl DecodedPackedFile {
             }
 
             PackedFileType::AnimTable => {
-                let schema = SCHEMA.read().unwrap();
-                match schema.deref() {
+                match schema {
                     Some(schema) => {
                         let data = raw_packed_file.get_data_and_keep_it()?;
                         let packed_file = AnimTable::read(&data, &schema, false)?;
@@ -144,8 +156,7 @@ impl DecodedPackedFile {
             }
 
             PackedFileType::DB => {
-                let schema = SCHEMA.read().unwrap();
-                match schema.deref() {
+                match schema {
                     Some(schema) => {
                         let data = raw_packed_file.get_data_and_keep_it()?;
                         let name = raw_packed_file.get_path().get(1).ok_or_else(|| Error::from(ErrorKind::DBTableIsNotADBTable))?;
@@ -163,8 +174,7 @@ impl DecodedPackedFile {
             }
 
             PackedFileType::Loc => {
-                let schema = SCHEMA.read().unwrap();
-                match schema.deref() {
+                match schema {
                     Some(schema) => {
                         let data = raw_packed_file.get_data_and_keep_it()?;
                         let packed_file = Loc::read(&data, &schema, false)?;
@@ -175,8 +185,7 @@ impl DecodedPackedFile {
             }
 
             PackedFileType::MatchedCombat => {
-                let schema = SCHEMA.read().unwrap();
-                match schema.deref() {
+                match schema {
                     Some(schema) => {
                         let data = raw_packed_file.get_data_and_keep_it()?;
                         let packed_file = MatchedCombat::read(&data, &schema, false)?;
@@ -186,6 +195,18 @@ impl DecodedPackedFile {
                 }
             }
 
+            PackedFileType::RigidModel => {
+                let data = raw_packed_file.get_data_and_keep_it()?;
+                let packed_file = RigidModel::read(&data)?;
+                Ok(DecodedPackedFile::RigidModel(packed_file))
+            }
+
+            PackedFileType::StarPos => {
+                let data = raw_packed_file.get_data_and_keep_it()?;
+                let packed_file = ESF::read(&data)?;
+                Ok(DecodedPackedFile::StarPos(packed_file))
+            }
+
             PackedFileType::Text(_) => {
                 let data = raw_packed_file.get_data_and_keep_it()?;
                 let mut packed_file = Text::read(&data)?;
@@ -201,48 +222,7 @@ impl DecodedPackedFile {
 
     /// This function decodes a `RawPackedFile` into a `DecodedPackedFile`, returning it.
     pub fn decode_no_locks(raw_packed_file: &mut RawPackedFile, schema: &Schema) -> Result<Self> {
-        match PackedFileType::get_packed_file_type(raw_packed_file.get_path()) {
-
-            PackedFileType::AnimFragment => {
-                let data = raw_packed_file.get_data_and_keep_it()?;
-                let packed_file = AnimFragment::read(&data, &schema, false)?;
-                Ok(DecodedPackedFile::AnimFragment(packed_file))
-            }
-
-            PackedFileType::AnimPack => Self::decode(raw_packed_file),
-
-            PackedFileType::AnimTable => {
-                let data = raw_packed_file.get_data_and_keep_it()?;
-                let packed_file = AnimTable::read(&data, &schema, false)?;
-                Ok(DecodedPackedFile::AnimTable(packed_file))
-            }
-
-            PackedFileType::CaVp8 => Self::decode(raw_packed_file),
-
-            PackedFileType::DB => {
-                let data = raw_packed_file.get_data_and_keep_it()?;
-                let name = raw_packed_file.get_path().get(1).ok_or_else(|| Error::from(ErrorKind::DBTableIsNotADBTable))?;
-                let packed_file = DB::read(&data, &name, &schema, false)?;
-                Ok(DecodedPackedFile::DB(packed_file))
-            }
-
-            PackedFileType::Image => Self::decode(raw_packed_file),
-
-            PackedFileType::Loc => {
-                let data = raw_packed_file.get_data_and_keep_it()?;
-                let packed_file = Loc::read(&data, &schema, false)?;
-                Ok(DecodedPackedFile::Loc(packed_file))
-            }
-
-            PackedFileType::MatchedCombat => {
-                let data = raw_packed_file.get_data_and_keep_it()?;
-                let packed_file = MatchedCombat::read(&data, &schema, false)?;
-                Ok(DecodedPackedFile::MatchedCombat(packed_file))
-            }
-
-            PackedFileType::Text(_) => Self::decode(raw_packed_file),
-            _=> Ok(DecodedPackedFile::Unknown)
-        }
+        Self::decode_with_schema(raw_packed_file, Some(schema))
     }
 
     /// This function encodes a `DecodedPackedFile` into a `Vec<u8>`, returning it.
@@ -257,15 +237,53 @@ impl DecodedPackedFile {
             DecodedPackedFile::DB(data) => Some(data.save()),
             DecodedPackedFile::Loc(data) => Some(data.save()),
             DecodedPackedFile::MatchedCombat(data) => Some(data.save()),
+            DecodedPackedFile::RigidModel(data) => Some(data.save()),
+            DecodedPackedFile::StarPos(data) => Some(data.save()),
             DecodedPackedFile::Text(data) => Some(data.save()),
             _=> None,
         }
     }
 
+    /// This function encodes a `DecodedPackedFile` into a `Vec<u8>` for the game of the provided `GameContext`, returning it.
+    ///
+    /// Otherwise, it works like `encode`.
+    pub fn encode_with_context(&self, context: &GameContext) -> Option<Result<Vec<u8>>> {
+        match self {
+            DecodedPackedFile::DB(data) => Some(data.save_with_context(context)),
+            _=> self.encode(),
+        }
+    }
+
     /// This function updates a DB Table to its latest valid version, being the latest valid version the one in the data.pack or equivalent of the game.
     ///
     /// It returns both, old and new versions, or an error.
     pub fn update_table(&mut self, dependencies: &Dependencies) -> Result<(i32, i32)> {
+        self.migrate_table(dependencies).map(|x| (x.old_version, x.new_version))
+    }
+
+    /// This function updates a DB Table to its latest valid version, using the schema and dependencies of the provided `GameContext`.
+    ///
+    /// Otherwise, it works like `update_table`.
+    pub fn update_table_with_context(&mut self, context: &GameContext) -> Result<(i32, i32)> {
+        self.migrate_table_with_context(context).map(|x| (x.old_version, x.new_version))
+    }
+
+    /// This function migrates a DB Table to its latest valid version, being the latest valid version the one in the data.pack or equivalent of the game.
+    ///
+    /// Fields are matched by name, using the history of the table in the schema to find renamed fields. It returns a report of the changes done, or an error.
+    pub fn migrate_table(&mut self, dependencies: &Dependencies) -> Result<TableMigration> {
+        self.migrate_table_with_schema(SCHEMA.read().unwrap().as_ref(), dependencies)
+    }
+
+    /// This function migrates a DB Table to its latest valid version, using the schema and dependencies of the provided `GameContext`.
+    ///
+    /// Otherwise, it works like `migrate_table`.
+    pub fn migrate_table_with_context(&mut self, context: &GameContext) -> Result<TableMigration> {
+        self.migrate_table_with_schema(context.get_ref_schema(), context.get_ref_dependencies())
+    }
+
+    /// This function migrates a DB Table to its latest valid version, using the provided schema, if any, to find renamed fields.
+    fn migrate_table_with_schema(&mut self, schema: Option<&Schema>, dependencies: &Dependencies) -> Result<TableMigration> {
         match self {
             DecodedPackedFile::DB(data) => {
                 let dep_db = dependencies.get_ref_dependency_database();
@@ -278,8 +296,11 @@ impl DecodedPackedFile {
                     let definition_new = vanilla_db.get_definition();
                     let definition_old = data.get_definition();
                     if definition_old != definition_new {
-                        data.set_definition(&definition_new);
-                        Ok((definition_old.get_version(), definition_new.get_version()))
+                        let history = match schema {
+                            Some(schema) => schema.get_ref_versioned_file_db(&data.name).map(|x| x.get_version_list().to_vec()).unwrap_or_default(),
+                            None => vec![],
+                        };
+                        Ok(data.migrate(&definition_new, &history))
                     }
                     else {
                         Err(ErrorKind::NoDefinitionUpdateAvailable.into())
@@ -290,6 +311,124 @@ impl DecodedPackedFile {
             _ => Err(ErrorKind::DBTableIsNotADBTable.into()),
         }
     }
+
+    /// This function ports a DB or Loc Table to the definition of the same table in the game of the provided `GameContext`.
+    ///
+    /// The definition used is the one of the game files of the target game or, if they're not loaded, the last one in its schema.
+    /// Fields are matched by name. References are not checked, as that needs all the tables of the `PackFile` already ported.
+    /// It returns a report of the changes done, or an error.
+    pub fn port_table(&mut self, target: &GameContext) -> Result<TablePort> {
+        let schema = target.get_ref_schema().ok_or_else(|| Error::from(ErrorKind::SchemaNotFound))?;
+        match self {
+            DecodedPackedFile::DB(data) => {
+                let definition = schema.get_ref_last_definition_db(&data.name, target.get_ref_dependencies())?;
+                Ok(data.port(definition))
+            }
+            DecodedPackedFile::Loc(data) => {
+                let definition = schema.get_ref_last_definition_loc()?;
+                Ok(data.port(definition))
+            }
+            _ => Err(ErrorKind::DBTableIsNotADBTable.into()),
+        }
+    }
+
+    /// This function performs a three-way merge of two modified versions of a DB or Loc Table, using the vanilla data as base.
+    ///
+    /// The base is built with the rows of the vanilla tables of the same type found in the dependencies whose keys are in both tables.
+    /// If a key is in more than one vanilla table, the row of the last one is used. If there are none, the merge is done against
+    /// an empty table. It returns the merged table and the conflicts found.
+    pub fn merge_tables(ours: &Self, theirs: &Self, dependencies: &Dependencies) -> Result<(Self, Vec<MergeConflict>)> {
+        let vanilla_tables = dependencies.get_ref_dependency_database().par_iter()
+            .filter_map(|x| x.get_decoded_from_memory().ok())
+            .collect::<Vec<&Self>>();
+
+        match (ours, theirs) {
+            (DecodedPackedFile::DB(ours), DecodedPackedFile::DB(theirs)) => {
+                let vanilla_tables = vanilla_tables.iter()
+                    .filter_map(|x| if let DecodedPackedFile::DB(y) = x { Some(y) } else { None })
+                    .filter(|x| x.name == ours.name)
+                    .collect::<Vec<&DB>>();
+
+                let definition = vanilla_tables.iter().map(|x| x.get_ref_definition()).max_by_key(|x| x.get_version()).unwrap_or_else(|| ours.get_ref_definition());
+                let mut base = DB::new(&ours.name, None, definition);
+                let mut entries = vec![];
+                for table in &vanilla_tables {
+                    let mut table = (*table).clone();
+                    table.set_definition(definition);
+                    entries.extend_from_slice(table.get_ref_table_data());
+                }
+                base.set_table_data(&entries)?;
+                base.dedup_merge_base();
+                base.retain_merge_base(ours, theirs);
+
+                let (merged, conflicts) = DB::merge(&base, ours, theirs)?;
+                Ok((DecodedPackedFile::DB(merged), conflicts))
+            }
+
+            (DecodedPackedFile::Loc(ours), DecodedPackedFile::Loc(theirs)) => {
+                let vanilla_tables = vanilla_tables.iter()
+                    .filter_map(|x| if let DecodedPackedFile::Loc(y) = x { Some(y) } else { None })
+                    .collect::<Vec<&Loc>>();
+
+                let definition = vanilla_tables.iter().map(|x| x.get_ref_definition()).max_by_key(|x| x.get_version()).unwrap_or_else(|| ours.get_ref_definition());
+                let mut base = Loc::new(definition);
+                let mut entries = vec![];
+                for table in &vanilla_tables {
+                    let mut table = (*table).clone();
+                    table.set_definition(definition);
+                    entries.extend_from_slice(table.get_ref_table_data());
+                }
+                base.set_table_data(&entries)?;
+                base.dedup_merge_base();
+                base.retain_merge_base(ours, theirs);
+
+                let (merged, conflicts) = Loc::merge(&base, ours, theirs);
+                Ok((DecodedPackedFile::Loc(merged), conflicts))
+            }
+            _ => Err(ErrorKind::InvalidFilesForMerging.into()),
+        }
+    }
+
+    /// This function returns if the `DecodedPackedFile` can be serialized to JSON/RON and back.
+    pub fn is_serializable(&self) -> bool {
+        matches!(self,
+            DecodedPackedFile::AnimFragment(_) |
+            DecodedPackedFile::AnimPack(_) |
+            DecodedPackedFile::AnimTable(_) |
+            DecodedPackedFile::CaVp8(_) |
+            DecodedPackedFile::DB(_) |
+            DecodedPackedFile::Loc(_) |
+            DecodedPackedFile::MatchedCombat(_)
+        )
+    }
+
+    /// This function returns the `DecodedPackedFile` as a JSON string.
+    ///
+    /// The type of the `DecodedPackedFile` and the type of each value of tables, including the ones in sequences, are kept.
+    pub fn to_json(&self) -> Result<String> {
+        if !self.is_serializable() { return Err(ErrorKind::DecodedPackedFileNotSerializable.into()) }
+        serde_json::to_string_pretty(self).map_err(From::from)
+    }
+
+    /// This function creates a `DecodedPackedFile` from a JSON string, like the ones generated by `DecodedPackedFile::to_json`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let decoded: Self = serde_json::from_str(json)?;
+        if decoded.is_serializable() { Ok(decoded) } else { Err(ErrorKind::DecodedPackedFileNotSerializable.into()) }
+    }
+
+    /// This function returns the `DecodedPackedFile` as a RON string.
+    ///
+    /// The type of the `DecodedPackedFile` and the type of each value of tables, including the ones in sequences, are kept.
+    pub fn to_ron(&self) -> Result<String> {
+        if !self.is_serializable() { return Err(ErrorKind::DecodedPackedFileNotSerializable.into()) }
+        to_string_pretty(self, PrettyConfig::default()).map_err(From::from)
+    }
+
+    /// This function creates a `DecodedPackedFile` from a RON string, like the ones generated by `DecodedPackedFile::to_ron`.
+    pub fn from_ron(ron: &str) -> Result<Self> {
+        let decoded: Self = ron::de::from_str(ron)?;
+        if decoded.is_serializable() { Ok(decoded) } else { Err(ErrorKind::DecodedPackedFileNotSerializable.into()) }
+    }
 }
 
 //----------------------------------------------------------------//
@@ -331,6 +470,7 @@ impl PackedFileType {
             else if packedfile_name.ends_with(animpack::EXTENSION) { Self::AnimPack }
             else if packedfile_name.ends_with(rigidmodel::EXTENSION) { Self::RigidModel }
             else if packedfile_name.ends_with(ca_vp8::EXTENSION) { Self::CaVp8 }
+            else if packedfile_name.ends_with(esf::EXTENSION) { Self::StarPos }
             else if packedfile_name.ends_with(table::anim_fragment::EXTENSION) { Self::AnimFragment }
             else if path == table::animtable::PATH { Self::AnimTable }
             else if path == table::matched_combat::PATH { Self::MatchedCombat }
@@ -369,6 +509,9 @@ impl PackedFileType {
                     else if packedfile_name.ends_with(animpack::EXTENSION) {
                         return Self::AnimPack
                     }
+                    else if packedfile_name.ends_with(esf::EXTENSION) && ESF::is_esf(&data) {
+                        return Self::StarPos
+                    }
                     else if packed_file.get_path() == table::animtable::PATH {
                         return Self::AnimTable
                     }
@@ -465,7 +608,7 @@ impl From<&DecodedPackedFile> for PackedFileType {
             DecodedPackedFile::Loc(_) => PackedFileType::Loc,
             DecodedPackedFile::MatchedCombat(_) => PackedFileType::MatchedCombat,
             DecodedPackedFile::RigidModel(_) => PackedFileType::RigidModel,
-            DecodedPackedFile::StarPos => PackedFileType::StarPos,
+            DecodedPackedFile::StarPos(_) => PackedFileType::StarPos,
             DecodedPackedFile::Text(text) => PackedFileType::Text(text.get_text_type()),
             DecodedPackedFile::Unknown => PackedFileType::Unknown,
         }
@@ -14,31 +14,75 @@ Module with all the code to interact with RigidModel PackedFiles.
 RigidModel PackedFiles are 3D models used by Total War games since Empire.
 This is basically a rewrite in Rust of the work done by Phazer on his tool.
 Because I want to avoid more C++ libs if posible.
+
+The structure of a RigidModel is:
+- Header: signature, version, amount of lods and skeleton.
+- Lod Headers: one per lod, with the sizes and offsets of their mesh groups.
+- Lods: one per lod, each one containing one or more mesh groups. Each mesh group contains:
+    - Mesh header: material type, sizes, offsets, bounding box and shader.
+    - Material: vertex format, textures, attachment points,...
+    - Vertices.
+    - Indices.
+
+Materials and vertex formats we don't understand yet are kept as raw data, so they're saved back as they came.
 !*/
 
+use half::f16;
 use serde_derive::{Serialize, Deserialize};
 
+use rpfm_error::{ErrorKind, Result};
+
+use crate::common::{decoder::Decoder, encoder::Encoder};
+
+#[cfg(test)]
+mod rigidmodel_test;
+
 /// This represents the value that every RigidModel PackedFile has in their 0-4 bytes. A.k.a it's signature or preamble.
-#[allow(dead_code)]
 const PACKED_FILE_TYPE: &str = "RMV2";
 
 /// Extension used by RigidModel PackedFiles.
 pub const EXTENSION: &str = ".rigid_model_v2";
 
+/// Size of the header of the RigidModel, lod headers not included.
+const HEADER_SIZE: usize = 140;
+
+/// Size of the skeleton id string, in the header.
+const SKELETON_ID_SIZE: usize = 128;
+
+/// Size of the header of each lod, for Attila or older.
+const LOD_HEADER_SIZE_V6: usize = 20;
+
+/// Size of the header of each lod, for Warhammer and newer.
+const LOD_HEADER_SIZE_V7: usize = 28;
+
+/// Size of the header of each mesh group.
+const MESH_HEADER_SIZE: usize = 80;
+
+/// Sizes of the fixed-size strings used in meshes and materials.
+const SHADER_NAME_SIZE: usize = 12;
+const SHADER_PARAMS_SIZE: usize = 20;
+const MATERIAL_NAME_SIZE: usize = 32;
+const MATERIAL_PATH_SIZE: usize = 256;
+const MATERIAL_FLAGS_SIZE: usize = 124;
+const ATTACHMENT_POINT_NAME_SIZE: usize = 32;
+
+/// Vertex formats we know how to decode.
+const VERTEX_FORMAT_DEFAULT: u16 = 0;
+const VERTEX_FORMAT_WEIGHTED: u16 = 3;
+const VERTEX_FORMAT_CINEMATIC: u16 = 4;
+
+/// Size of a vertex of each of the known formats.
+const VERTEX_SIZE_DEFAULT: usize = 32;
+const VERTEX_SIZE_WEIGHTED: usize = 28;
+const VERTEX_SIZE_CINEMATIC: usize = 32;
+
 //---------------------------------------------------------------------------//
 //                              Enum & Structs
 //---------------------------------------------------------------------------//
 
 /// This struct contains a RigidModel decoded in memory.
-#[derive(Clone, Debug,PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RigidModel {
-    pub header: Header,
-    //pub packed_file_data: RigidModelData,
-}
-
-/// This struct represents the header of a RigidModel.
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
-pub struct Header {
 
     /// The version of the RigidModel. The supported versions per game are:
     /// - `6`: Attila or older.
@@ -47,7 +91,199 @@ pub struct Header {
     version: u32,
 
     /// The skeleton used by this RigidModel.
-    skeleton_id: Vec<u8>,
+    skeleton_id: String,
+
+    /// The lods of the RigidModel, from the more detailed one to the less detailed one.
+    lods: Vec<Lod>,
+}
+
+/// This struct represents a Lod (Level Of Detail) of a RigidModel.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Lod {
+
+    /// Distance from the camera from which this lod is used.
+    visibility_distance: f32,
+
+    /// Number of this lod, as authored. Only in version 7 or newer.
+    authored_lod_number: u32,
+
+    /// Quality level of this lod. Only in version 7 or newer.
+    quality_level: u32,
+
+    /// The meshes of this lod.
+    mesh_groups: Vec<MeshGroup>,
+}
+
+/// This struct represents a mesh, with its material, vertices and indices.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MeshGroup {
+
+    /// Type of the material used by this mesh.
+    material_type: u16,
+
+    /// Render flags of the mesh.
+    render_flags: u16,
+
+    /// Bounding box of the mesh.
+    bounding_box: BoundingBox,
+
+    /// Name of the shader used by the mesh.
+    shader_name: String,
+
+    /// Params of the shader. We don't know what these are yet.
+    shader_params: Vec<u8>,
+
+    /// Material of the mesh.
+    material: Material,
+
+    /// Vertices of the mesh.
+    vertices: Vertices,
+
+    /// Indices of the mesh.
+    indices: Vec<u16>,
+
+    /// Data at the end of the mesh group, after the indices. Usually empty.
+    extra_data: Vec<u8>,
+}
+
+/// This struct represents the bounding box of a mesh.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    min: [f32; 3],
+    max: [f32; 3],
+}
+
+/// This enum represents the material of a mesh.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Material {
+
+    /// Material used by default, weighted, decal and dirtmap meshes.
+    Standard(Box<StandardMaterial>),
+
+    /// Material we don't know how to decode yet. Contains its raw data.
+    Unknown(Vec<u8>),
+}
+
+/// This struct represents the material used by most of the meshes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StandardMaterial {
+
+    /// Format of the vertices of the mesh using this material.
+    vertex_format: u16,
+
+    /// Name of the mesh.
+    name: String,
+
+    /// Directory of the textures of the mesh.
+    texture_directory: String,
+
+    /// Filters of the mesh.
+    filters: String,
+
+    /// Unknown data.
+    unknown: u16,
+
+    /// Pivot point of the mesh.
+    pivot_point: [f32; 3],
+
+    /// Transformation matrices of the mesh.
+    matrices: [[f32; 12]; 3],
+
+    /// Index of the matrix of the mesh.
+    matrix_index: i32,
+
+    /// Index of the parent matrix of the mesh.
+    parent_matrix_index: i32,
+
+    /// Amount of string params of the material.
+    string_params_count: u32,
+
+    /// Amount of float params of the material.
+    float_params_count: u32,
+
+    /// Amount of integer params of the material.
+    integer_params_count: u32,
+
+    /// Amount of vector params of the material.
+    vector_params_count: u32,
+
+    /// Flags of the material. We don't know what these do yet.
+    flags: Vec<u8>,
+
+    /// Attachment points of the mesh.
+    attachment_points: Vec<AttachmentPoint>,
+
+    /// Textures of the mesh.
+    textures: Vec<Texture>,
+
+    /// Raw data of the params of the material.
+    params_data: Vec<u8>,
+}
+
+/// This struct represents an attachment point of a mesh.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AttachmentPoint {
+    name: String,
+    matrix: [f32; 12],
+    bone_id: i32,
+}
+
+/// This struct represents a texture of a mesh.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Texture {
+
+    /// Type of the texture (diffuse, normal, mask,...).
+    texture_type: u32,
+
+    /// Path of the texture in the game's files.
+    path: String,
+}
+
+/// This enum represents the vertices of a mesh, in each one of the formats we know.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Vertices {
+    Default(Vec<DefaultVertex>),
+    Weighted(Vec<WeightedVertex>),
+    Cinematic(Vec<CinematicVertex>),
+
+    /// Vertices we don't know how to decode yet. Contains the amount of vertices and their raw data.
+    Unknown(u32, Vec<u8>),
+}
+
+/// This struct represents a vertex of a static mesh.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DefaultVertex {
+    position: [f32; 4],
+    uv: [f32; 2],
+    uv_2: [f32; 2],
+    normal: [u8; 4],
+    tangent: [u8; 4],
+    bitangent: [u8; 4],
+    color: [u8; 4],
+}
+
+/// This struct represents a vertex of a mesh with two bones per vertex.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WeightedVertex {
+    position: [f32; 4],
+    bone_indices: [u8; 2],
+    bone_weights: [u8; 2],
+    normal: [u8; 4],
+    uv: [f32; 2],
+    tangent: [u8; 4],
+    bitangent: [u8; 4],
+}
+
+/// This struct represents a vertex of a mesh with four bones per vertex.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CinematicVertex {
+    position: [f32; 4],
+    bone_indices: [u8; 4],
+    bone_weights: [u8; 4],
+    normal: [u8; 4],
+    uv: [f32; 2],
+    tangent: [u8; 4],
+    bitangent: [u8; 4],
 }
 
 //---------------------------------------------------------------------------//
@@ -57,96 +293,183 @@ pub struct Header {
 /// Implementation of RigidModel.
 impl RigidModel {
 
-    /// This function creates a new empty `Decal` RigidModel.
-    pub fn new_decal() -> Self {
+    /// This function creates a new empty RigidModel, for Warhammer.
+    pub fn new() -> Self {
         Self {
-            header: Header::default(),
+            version: 7,
+            skeleton_id: String::new(),
+            lods: vec![],
         }
     }
-}
-/*
-/// Struct "RigidModelHeader". For more info about this, check the comment at the start of "packedfile/
-/// rigidmodel/mod.rs".
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct RigidModelHeader {
-    pub packed_file_header_signature: String,
-    pub packed_file_header_model_type: u32,
-    pub packed_file_header_lods_count: u32,
-    pub packed_file_data_base_skeleton: (String, usize),
-}
 
-/// Struct "RigidModelData". For more info about this, check the comment at the start of "packedfile/
-/// rigidmodel/mod.rs".
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct RigidModelData {
-    pub packed_file_data_lods_header: Vec<RigidModelLodHeader>,
-    pub packed_file_data_lods_data: Vec<u8>,
-}
+    /// This function creates a `RigidModel` from a `&[u8]`.
+    ///
+    /// If we cannot encode back the data exactly as it came, this returns an error, so we never break a RigidModel on save.
+    pub fn read(packed_file_data: &[u8]) -> Result<Self> {
+        if packed_file_data.len() < HEADER_SIZE { return Err(ErrorKind::RigidModelNotSupportedFile.into()) }
 
-/// Struct "RigidModelLodHeader". For more info about this, check the comment at the start of "packedfile/
-/// rigidmodel/mod.rs".
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct RigidModelLodHeader {
-    pub groups_count: u32,
-    pub vertices_data_length: u32,
-    pub indices_data_length: u32,
-    pub start_offset: u32,
-    pub lod_zoom_factor: f32,
-    pub mysterious_data_1: Option<u32>,
-    pub mysterious_data_2: Option<u32>,
-}
+        // We check this, just in case we try to read some malformed file with a string in the first
+        // four bytes (which is not uncommon).
+        if packed_file_data.decode_string_u8(0, 4)? != PACKED_FILE_TYPE { return Err(ErrorKind::RigidModelNotSupportedFile.into()) }
 
-/// Implementation of "RigidModel"
-impl RigidModel {
+        let version = packed_file_data.decode_integer_u32(4)?;
+        let lod_header_size = Self::get_lod_header_size(version)?;
+        let lods_count = packed_file_data.decode_integer_u32(8)?;
+        let skeleton_id = packed_file_data.decode_string_u8_0padded(12, SKELETON_ID_SIZE)?.0;
 
-    /// This function reads the data from a Vec<u8> and decode it into a RigidModel. This CAN FAIL,
-    /// so we return Result<RigidModel, Error>.
-    pub fn read(packed_file_data: &[u8]) -> Result<RigidModel> {
-        let packed_file_header = RigidModelHeader::read(&packed_file_data[..140])?;
-        let packed_file_data = RigidModelData::read(&packed_file_data[140..], packed_file_header.packed_file_header_model_type, packed_file_header.packed_file_header_lods_count)?;
+        let mut lods = vec![];
+        let mut offset = HEADER_SIZE;
+        let mut expected_start_offset = HEADER_SIZE + lod_header_size * lods_count as usize;
+        for _ in 0..lods_count {
+            let lod = Lod::read(packed_file_data, &mut offset, version, expected_start_offset)?;
+            expected_start_offset += lod.get_size();
+            lods.push(lod);
+        }
 
-        Ok(RigidModel {
-            packed_file_header,
-            packed_file_data,
-        })
+        if expected_start_offset != packed_file_data.len() {
+            return Err(ErrorKind::RigidModelDecode(format!("There are {} bytes after the last lod that we don't know how to decode.", packed_file_data.len() as isize - expected_start_offset as isize)).into());
+        }
+
+        let rigid_model = Self {
+            version,
+            skeleton_id,
+            lods,
+        };
+
+        // Last check, to ensure we don't alter the file just by opening it.
+        if rigid_model.save()? != packed_file_data {
+            return Err(ErrorKind::RigidModelDecode("The RigidModel cannot be saved back exactly as it is. Its format is not yet fully supported.".to_owned()).into());
+        }
+
+        Ok(rigid_model)
+    }
+
+    /// This function takes a `RigidModel` and encodes it to `Vec<u8>`.
+    ///
+    /// Sizes and offsets are recalculated, so any change done to the model is reflected on them.
+    pub fn save(&self) -> Result<Vec<u8>> {
+        let lod_header_size = Self::get_lod_header_size(self.version)?;
+
+        let mut packed_file = vec![];
+        packed_file.encode_string_u8(PACKED_FILE_TYPE);
+        packed_file.encode_integer_u32(self.version);
+        packed_file.encode_integer_u32(self.lods.len() as u32);
+        packed_file.encode_string_u8_0padded(&(self.skeleton_id.to_owned(), SKELETON_ID_SIZE))?;
+
+        let mut lods_data = vec![];
+        let mut start_offset = HEADER_SIZE + lod_header_size * self.lods.len();
+        for lod in &self.lods {
+            let mut lod_data = vec![];
+            let mut vertices_size = 0;
+            let mut indices_size = 0;
+            for mesh_group in &lod.mesh_groups {
+                vertices_size += mesh_group.vertices.get_size();
+                indices_size += mesh_group.indices.len() * 2;
+                mesh_group.save(&mut lod_data)?;
+            }
+
+            packed_file.encode_integer_u32(lod.mesh_groups.len() as u32);
+            packed_file.encode_integer_u32(vertices_size as u32);
+            packed_file.encode_integer_u32(indices_size as u32);
+            packed_file.encode_integer_u32(start_offset as u32);
+            packed_file.encode_float_f32(lod.visibility_distance);
+
+            // These two doesn't exist in Attila's RigidModels.
+            if self.version >= 7 {
+                packed_file.encode_integer_u32(lod.authored_lod_number);
+                packed_file.encode_integer_u32(lod.quality_level);
+            }
+
+            start_offset += lod_data.len();
+            lods_data.append(&mut lod_data);
+        }
+
+        packed_file.append(&mut lods_data);
+        Ok(packed_file)
+    }
+
+    /// This function returns the size of each lod header for the provided version, or an error if we don't support that version.
+    fn get_lod_header_size(version: u32) -> Result<usize> {
+        match version {
+            6 => Ok(LOD_HEADER_SIZE_V6),
+            7 | 8 => Ok(LOD_HEADER_SIZE_V7),
+            _ => Err(ErrorKind::RigidModelNotSupportedType.into())
+        }
+    }
+
+    /// This function returns the version of the RigidModel.
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    /// This function returns the skeleton used by the RigidModel.
+    pub fn get_ref_skeleton_id(&self) -> &str {
+        &self.skeleton_id
+    }
+
+    /// This function sets the skeleton used by the RigidModel.
+    pub fn set_skeleton_id(&mut self, skeleton_id: &str) {
+        self.skeleton_id = skeleton_id.to_owned();
+    }
+
+    /// This function returns the lods of the RigidModel.
+    pub fn get_ref_lods(&self) -> &[Lod] {
+        &self.lods
     }
 
-    /// This function reads the data from a RigidModel and encode it into a Vec<u8>. This CAN FAIL,
-    /// so we return Result<Vec<u8>, Error>.
-    pub fn save(rigid_model_data: &RigidModel) -> Result<Vec<u8>> {
-        let mut packed_file_data_encoded = RigidModelData::save(&rigid_model_data.packed_file_data);
-        let mut packed_file_header_encoded = RigidModelHeader::save(&rigid_model_data.packed_file_header)?;
+    /// This function returns a mutable reference to the lods of the RigidModel.
+    pub fn get_ref_mut_lods(&mut self) -> &mut Vec<Lod> {
+        &mut self.lods
+    }
 
-        let mut packed_file_encoded = vec![];
-        packed_file_encoded.append(&mut packed_file_header_encoded);
-        packed_file_encoded.append(&mut packed_file_data_encoded);
+    /// This function returns the paths of all the textures used by the RigidModel, without duplicates.
+    pub fn get_texture_paths(&self) -> Vec<String> {
+        let mut paths = self.lods.iter()
+            .flat_map(|lod| lod.mesh_groups.iter())
+            .filter_map(|mesh_group| if let Material::Standard(ref material) = mesh_group.material { Some(material) } else { None })
+            .flat_map(|material| material.textures.iter().map(|texture| texture.path.to_owned()))
+            .collect::<Vec<String>>();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
 
-        Ok(packed_file_encoded)
+    /// This function replaces the provided texture path with the new one in every mesh of the RigidModel.
+    ///
+    /// It returns the amount of textures changed.
+    pub fn replace_texture_path(&mut self, old_path: &str, new_path: &str) -> usize {
+        let mut changes = 0;
+        for lod in &mut self.lods {
+            for mesh_group in &mut lod.mesh_groups {
+                if let Material::Standard(ref mut material) = mesh_group.material {
+                    for texture in &mut material.textures {
+                        if texture.path == old_path {
+                            texture.path = new_path.to_owned();
+                            changes += 1;
+                        }
+                    }
+                }
+            }
+        }
+        changes
     }
 
     /// This function is used to patch a RigidModel 3D model from Total War: Attila to work in Total War:
     /// Warhammer 1 and 2. The process to patch a RigidModel is simple:
     /// - We update the version of the RigidModel from 6(Attila) to 7(Warhammer 1&2).
     /// - We add 2 u32 to the Lods: a counter starting at 0, and a 0.
-    /// - We increase the start_offset of every Lod by (8*amount_of_lods).
+    /// - The start_offset of every Lod is recalculated on save.
     /// - We may need to increase the zoom_factor of the first Lod to 1000.0, because otherwise sometimes the models
     ///   disappear when you move the camera far from them.
-    /// It requires a mut ref to a decoded PackFile, and returns an String (Result<Success, Error>).
-    pub fn patch_rigid_model_attila_to_warhammer (&mut self) -> Result<String> {
-
-        // If the RigidModel is an Attila RigidModel, we continue. Otherwise, return Error.
-        match self.packed_file_header.packed_file_header_model_type {
+    pub fn patch_rigid_model_attila_to_warhammer(&mut self) -> Result<()> {
+        match self.version {
             6 => {
-                // We update his version.
-                self.packed_file_header.packed_file_header_model_type = 7;
-
-                // Next, we change the needed data for every Lod.
-                for (index, lod) in self.packed_file_data.packed_file_data_lods_header.iter_mut().enumerate() {
-                    lod.mysterious_data_1 = Some(index as u32);
-                    lod.mysterious_data_2 = Some(0);
-                    lod.start_offset += 8 * self.packed_file_header.packed_file_header_lods_count;
+                self.version = 7;
+                for (index, lod) in self.lods.iter_mut().enumerate() {
+                    lod.authored_lod_number = index as u32;
+                    lod.quality_level = 0;
                 }
-                Ok("RigidModel patched succesfully.".to_owned())
+                Ok(())
             },
             7 => Err(ErrorKind::RigidModelPatchToWarhammer("This is not an Attila's RigidModel, but a Warhammer one.".to_owned()).into()),
             _ => Err(ErrorKind::RigidModelPatchToWarhammer("I don't even know from what game is this RigidModel.".to_owned()).into()),
@@ -154,190 +477,750 @@ impl RigidModel {
     }
 }
 
-/// Implementation of "RigidModelHeader"
-impl RigidModelHeader {
+/// Implementation of Lod.
+impl Lod {
 
-    /// This function reads the data from a Vec<u8> and decode it into a RigidModelHeader. This CAN FAIL,
-    /// so we return Result<RigidModelHeader, Error>.
-    pub fn read(packed_file_data: &[u8]) -> Result<RigidModelHeader> {
+    /// This function creates a new empty `Lod`.
+    pub fn new(visibility_distance: f32, authored_lod_number: u32) -> Self {
+        Self {
+            visibility_distance,
+            authored_lod_number,
+            quality_level: 0,
+            mesh_groups: vec![],
+        }
+    }
 
-        let mut packed_file_header = RigidModelHeader {
-            packed_file_header_signature: String::new(),
-            packed_file_header_model_type: 0,
-            packed_file_header_lods_count: 0,
-            packed_file_data_base_skeleton: (String::new(), 0),
-        };
+    /// This function reads a `Lod` from the provided data. `offset` must point to its header, and it's moved to the next header.
+    fn read(data: &[u8], offset: &mut usize, version: u32, expected_start_offset: usize) -> Result<Self> {
+        let mesh_groups_count = data.decode_packedfile_integer_u32(*offset, offset)?;
+        let vertices_size = data.decode_packedfile_integer_u32(*offset, offset)?;
+        let indices_size = data.decode_packedfile_integer_u32(*offset, offset)?;
+        let start_offset = data.decode_packedfile_integer_u32(*offset, offset)? as usize;
+        let visibility_distance = data.decode_packedfile_float_f32(*offset, offset)?;
 
-        match packed_file_data.decode_string_u8(0, 4) {
-            Ok(data) => packed_file_header.packed_file_header_signature = data,
-            Err(error) => return Err(error)
-        }
+        let (authored_lod_number, quality_level) = if version >= 7 {
+            (data.decode_packedfile_integer_u32(*offset, offset)?, data.decode_packedfile_integer_u32(*offset, offset)?)
+        } else { (0, 0) };
 
-        // We check this, just in case we try to read some malformed file with a string in the first
-        // four bytes (which is not uncommon).
-        if packed_file_header.packed_file_header_signature != "RMV2" {
-            return Err(ErrorKind::RigidModelNotSupportedFile.into())
+        // We expect the lods to be one after another, without gaps. If not, we cannot guarantee we'll save them correctly.
+        if start_offset != expected_start_offset {
+            return Err(ErrorKind::RigidModelDecode(format!("Lod starts at {}, but we expected it to start at {}.", start_offset, expected_start_offset)).into());
         }
 
-        match packed_file_data.decode_integer_u32(4) {
-            Ok(data) => packed_file_header.packed_file_header_model_type = data,
-            Err(error) => return Err(error)
+        let mut mesh_groups = vec![];
+        let mut mesh_offset = start_offset;
+        for _ in 0..mesh_groups_count {
+            mesh_groups.push(MeshGroup::read(data, &mut mesh_offset)?);
         }
 
-        match packed_file_data.decode_integer_u32(8) {
-            Ok(data) => packed_file_header.packed_file_header_lods_count = data,
-            Err(error) => return Err(error)
-        }
+        let lod = Self {
+            visibility_distance,
+            authored_lod_number,
+            quality_level,
+            mesh_groups,
+        };
 
-        match packed_file_data.decode_string_u8_0padded(12, 128) {
-            Ok(data) => packed_file_header.packed_file_data_base_skeleton = data,
-            Err(error) => return Err(error)
+        if lod.get_vertices_size() != vertices_size as usize || lod.get_indices_size() != indices_size as usize {
+            return Err(ErrorKind::RigidModelDecode("The size of the vertices/indices of a lod doesn't match the ones of their meshes.".to_owned()).into());
         }
-        Ok(packed_file_header)
+
+        Ok(lod)
+    }
+
+    /// This function returns the size in bytes of all the mesh groups of the lod.
+    fn get_size(&self) -> usize {
+        self.mesh_groups.iter().map(|x| x.get_size()).sum()
+    }
+
+    /// This function returns the size in bytes of all the vertices of the lod.
+    fn get_vertices_size(&self) -> usize {
+        self.mesh_groups.iter().map(|x| x.vertices.get_size()).sum()
     }
 
-    /// This function reads the data from a RigidModelHeader and encode it into a Vec<u8>. This CAN FAIL,
-    /// so we return Result<Vec<u8>, Error>.
-    pub fn save(rigid_model_header: &RigidModelHeader) -> Result<Vec<u8>> {
-        let mut packed_file_data: Vec<u8> = vec![];
+    /// This function returns the size in bytes of all the indices of the lod.
+    fn get_indices_size(&self) -> usize {
+        self.mesh_groups.iter().map(|x| x.indices.len() * 2).sum()
+    }
 
-        packed_file_data.encode_string_u8(&rigid_model_header.packed_file_header_signature);
-        packed_file_data.encode_integer_u32(rigid_model_header.packed_file_header_model_type);
-        packed_file_data.encode_integer_u32(rigid_model_header.packed_file_header_lods_count);
-        packed_file_data.encode_string_u8_0padded(&rigid_model_header.packed_file_data_base_skeleton)?;
+    /// This function returns the visibility distance of the lod.
+    pub fn get_visibility_distance(&self) -> f32 {
+        self.visibility_distance
+    }
 
-        Ok(packed_file_data)
+    /// This function sets the visibility distance of the lod.
+    pub fn set_visibility_distance(&mut self, visibility_distance: f32) {
+        self.visibility_distance = visibility_distance;
+    }
+
+    /// This function returns the authored number of the lod.
+    pub fn get_authored_lod_number(&self) -> u32 {
+        self.authored_lod_number
+    }
+
+    /// This function returns the quality level of the lod.
+    pub fn get_quality_level(&self) -> u32 {
+        self.quality_level
+    }
+
+    /// This function returns the mesh groups of the lod.
+    pub fn get_ref_mesh_groups(&self) -> &[MeshGroup] {
+        &self.mesh_groups
+    }
+
+    /// This function returns a mutable reference to the mesh groups of the lod.
+    pub fn get_ref_mut_mesh_groups(&mut self) -> &mut Vec<MeshGroup> {
+        &mut self.mesh_groups
     }
 }
 
-/// Implementation of "RigidModelData"
-impl RigidModelData {
-
-    /// This function reads the data from a Vec<u8> and decode it into a RigidModelData. This CAN FAIL,
-    /// so we return Result<RigidModelData, Error>.
-    pub fn read(packed_file_data: &[u8], packed_file_header_model_type: u32, packed_file_header_lods_count: u32) -> Result<RigidModelData> {
-        let mut packed_file_data_lods_header: Vec<RigidModelLodHeader> = vec![];
-        let mut index: usize = 0;
-        let offset: usize = match packed_file_header_model_type {
-            6 => 20, // Attila
-            7 => 28, // Warhammer 1&2
-            _ => return Err(ErrorKind::RigidModelNotSupportedType.into())
+/// Implementation of MeshGroup.
+impl MeshGroup {
+
+    /// This function creates a new `MeshGroup` with the provided material, vertices and indices.
+    pub fn new(material_type: u16, shader_name: &str, material: Material, vertices: Vertices, indices: Vec<u16>) -> Self {
+        Self {
+            material_type,
+            render_flags: 0,
+            bounding_box: BoundingBox::default(),
+            shader_name: shader_name.to_owned(),
+            shader_params: vec![0; SHADER_PARAMS_SIZE],
+            material,
+            vertices,
+            indices,
+            extra_data: vec![],
+        }
+    }
+
+    /// This function reads a `MeshGroup` from the provided data. `offset` must point to its start, and it's moved to its end.
+    fn read(data: &[u8], offset: &mut usize) -> Result<Self> {
+        let start = *offset;
+        let material_type = data.decode_packedfile_integer_u16(*offset, offset)?;
+        let render_flags = data.decode_packedfile_integer_u16(*offset, offset)?;
+        let size = data.decode_packedfile_integer_u32(*offset, offset)? as usize;
+        let vertices_offset = data.decode_packedfile_integer_u32(*offset, offset)? as usize;
+        let vertices_count = data.decode_packedfile_integer_u32(*offset, offset)?;
+        let indices_offset = data.decode_packedfile_integer_u32(*offset, offset)? as usize;
+        let indices_count = data.decode_packedfile_integer_u32(*offset, offset)? as usize;
+
+        let bounding_box = BoundingBox {
+            min: [
+                data.decode_packedfile_float_f32(*offset, offset)?,
+                data.decode_packedfile_float_f32(*offset, offset)?,
+                data.decode_packedfile_float_f32(*offset, offset)?,
+            ],
+            max: [
+                data.decode_packedfile_float_f32(*offset, offset)?,
+                data.decode_packedfile_float_f32(*offset, offset)?,
+                data.decode_packedfile_float_f32(*offset, offset)?,
+            ],
         };
 
-        // We get the "headers" of every lod.
-        for _ in 0..packed_file_header_lods_count {
-            let lod_header = match RigidModelLodHeader::read(&packed_file_data[index..(index + offset)]) {
-                Ok(data) => data,
-                Err(error) => return Err(error)
-            };
-            packed_file_data_lods_header.push(lod_header);
-            index += offset;
+        let shader_name = data.decode_string_u8_0padded(*offset, SHADER_NAME_SIZE)?.0;
+        *offset += SHADER_NAME_SIZE;
+        let shader_params = data.get_bytes_checked(*offset, SHADER_PARAMS_SIZE)?.to_vec();
+        *offset += SHADER_PARAMS_SIZE;
+
+        // Sanity checks, so we don't try to read out of the mesh.
+        let indices_size = indices_count * 2;
+        if vertices_offset < MESH_HEADER_SIZE || indices_offset < vertices_offset || indices_offset + indices_size > size {
+            return Err(ErrorKind::RigidModelDecode(format!("Invalid offsets in the mesh at {}.", start)).into());
         }
 
-        let packed_file_data_lods_data = packed_file_data[index..].to_vec();
+        let material_data = data.get_bytes_checked(start + MESH_HEADER_SIZE, vertices_offset - MESH_HEADER_SIZE)?;
+        let material = Material::read(material_data);
+
+        let vertices_data = data.get_bytes_checked(start + vertices_offset, indices_offset - vertices_offset)?;
+        let vertices = Vertices::read(vertices_data, vertices_count, &material);
+
+        // The count comes from the file, so make sure all the indices are there before reserving memory for them.
+        data.get_bytes_checked(start + indices_offset, indices_size)?;
+        let mut indices = Vec::with_capacity(indices_count);
+        let mut index_offset = start + indices_offset;
+        for _ in 0..indices_count {
+            indices.push(data.decode_packedfile_integer_u16(index_offset, &mut index_offset)?);
+        }
 
-        Ok(RigidModelData {
-            packed_file_data_lods_header,
-            packed_file_data_lods_data,
+        let extra_data = data.get_bytes_checked(index_offset, size - indices_offset - indices_size)?.to_vec();
+
+        *offset = start + size;
+        Ok(Self {
+            material_type,
+            render_flags,
+            bounding_box,
+            shader_name,
+            shader_params,
+            material,
+            vertices,
+            indices,
+            extra_data,
         })
     }
 
-    /// This function reads the data from a RigidModelData and encode it into a Vec<u8>. This CAN FAIL,
-    /// so we return Result<Vec<u8>, Error>.
-    pub fn save(rigid_model_data: &RigidModelData) -> Vec<u8> {
-        let mut packed_file_data = vec![];
+    /// This function encodes the `MeshGroup` at the end of the provided buffer.
+    fn save(&self, data: &mut Vec<u8>) -> Result<()> {
+        let mut material_data = vec![];
+        self.material.save(&mut material_data)?;
 
-        // For each Lod, we save it, and add it to the "Encoded Data" vector. After that, we add to that
-        // vector the extra data, and return it.
-        for lod in &rigid_model_data.packed_file_data_lods_header {
-            packed_file_data.append(&mut RigidModelLodHeader::save(lod));
-        }
+        let mut vertices_data = vec![];
+        self.vertices.save(&mut vertices_data);
+
+        let vertices_offset = MESH_HEADER_SIZE + material_data.len();
+        let indices_offset = vertices_offset + vertices_data.len();
+
+        data.encode_integer_u16(self.material_type);
+        data.encode_integer_u16(self.render_flags);
+        data.encode_integer_u32(self.get_size() as u32);
+        data.encode_integer_u32(vertices_offset as u32);
+        data.encode_integer_u32(self.vertices.get_count());
+        data.encode_integer_u32(indices_offset as u32);
+        data.encode_integer_u32(self.indices.len() as u32);
 
-        packed_file_data.extend_from_slice(&rigid_model_data.packed_file_data_lods_data);
-        packed_file_data
+        self.bounding_box.min.iter().for_each(|x| data.encode_float_f32(*x));
+        self.bounding_box.max.iter().for_each(|x| data.encode_float_f32(*x));
+
+        data.encode_string_u8_0padded(&(self.shader_name.to_owned(), SHADER_NAME_SIZE))?;
+        data.extend_from_slice(&self.shader_params);
+
+        data.append(&mut material_data);
+        data.append(&mut vertices_data);
+        self.indices.iter().for_each(|x| data.encode_integer_u16(*x));
+        data.extend_from_slice(&self.extra_data);
+        Ok(())
+    }
+
+    /// This function returns the size in bytes of the `MeshGroup`, once encoded.
+    fn get_size(&self) -> usize {
+        MESH_HEADER_SIZE + self.material.get_size() + self.vertices.get_size() + self.indices.len() * 2 + self.extra_data.len()
+    }
+
+    /// This function returns the type of the material of the mesh.
+    pub fn get_material_type(&self) -> u16 {
+        self.material_type
+    }
+
+    /// This function returns the name of the shader of the mesh.
+    pub fn get_ref_shader_name(&self) -> &str {
+        &self.shader_name
+    }
+
+    /// This function returns the bounding box of the mesh.
+    pub fn get_ref_bounding_box(&self) -> &BoundingBox {
+        &self.bounding_box
+    }
+
+    /// This function returns the material of the mesh.
+    pub fn get_ref_material(&self) -> &Material {
+        &self.material
+    }
+
+    /// This function returns a mutable reference to the material of the mesh.
+    pub fn get_ref_mut_material(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    /// This function returns the vertices of the mesh.
+    pub fn get_ref_vertices(&self) -> &Vertices {
+        &self.vertices
+    }
+
+    /// This function returns a mutable reference to the vertices of the mesh.
+    pub fn get_ref_mut_vertices(&mut self) -> &mut Vertices {
+        &mut self.vertices
+    }
+
+    /// This function returns the indices of the mesh.
+    pub fn get_ref_indices(&self) -> &[u16] {
+        &self.indices
+    }
+
+    /// This function returns a mutable reference to the indices of the mesh.
+    pub fn get_ref_mut_indices(&mut self) -> &mut Vec<u16> {
+        &mut self.indices
     }
 }
 
-/// Implementation of "RigidModelLodHeader"
-impl RigidModelLodHeader {
-
-    /// This function reads the data from a Vec<u8> and decode it into a RigidModelLodHeader. This CAN FAIL,
-    /// so we return Result<RigidModelLodHeader, Error>.
-    pub fn read(packed_file_data: &[u8]) -> Result<RigidModelLodHeader> {
-        let mut header = RigidModelLodHeader {
-            groups_count: 0,
-            vertices_data_length: 0,
-            indices_data_length: 0,
-            start_offset: 0,
-            lod_zoom_factor: 0.0,
-            mysterious_data_1: None,
-            mysterious_data_2: None,
-        };
-        match packed_file_data.decode_integer_u32(0) {
-            Ok(data) => header.groups_count = data,
-            Err(error) => return Err(error)
+/// Implementation of BoundingBox.
+impl BoundingBox {
+
+    /// This function returns the minimum corner of the bounding box.
+    pub fn get_min(&self) -> [f32; 3] {
+        self.min
+    }
+
+    /// This function returns the maximum corner of the bounding box.
+    pub fn get_max(&self) -> [f32; 3] {
+        self.max
+    }
+}
+
+/// Implementation of Material.
+impl Material {
+
+    /// This function reads a `Material` from the provided data.
+    ///
+    /// If it's not a material we can decode and encode back exactly as it was, we keep it as raw data.
+    fn read(data: &[u8]) -> Self {
+        if let Ok(material) = StandardMaterial::read(data) {
+            let mut encoded_data = vec![];
+            if material.save(&mut encoded_data).is_ok() && encoded_data == data {
+                return Self::Standard(Box::new(material));
+            }
         }
-        match packed_file_data.decode_integer_u32(4) {
-            Ok(data) => header.vertices_data_length = data,
-            Err(error) => return Err(error)
+
+        Self::Unknown(data.to_vec())
+    }
+
+    /// This function encodes the `Material` at the end of the provided buffer.
+    fn save(&self, data: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::Standard(material) => material.save(data),
+            Self::Unknown(raw_data) => {
+                data.extend_from_slice(raw_data);
+                Ok(())
+            }
         }
-        match packed_file_data.decode_integer_u32(8) {
-            Ok(data) => header.indices_data_length = data,
-            Err(error) => return Err(error)
+    }
+
+    /// This function returns the size in bytes of the `Material`, once encoded.
+    fn get_size(&self) -> usize {
+        match self {
+            Self::Standard(material) => material.get_size(),
+            Self::Unknown(raw_data) => raw_data.len(),
         }
-        match packed_file_data.decode_integer_u32(12) {
-            Ok(data) => header.start_offset = data,
-            Err(error) => return Err(error)
+    }
+
+    /// This function returns the vertex format of the material, if we know it.
+    pub fn get_vertex_format(&self) -> Option<u16> {
+        match self {
+            Self::Standard(material) => Some(material.vertex_format),
+            Self::Unknown(_) => None,
         }
-        match packed_file_data.decode_float_f32(16) {
-            Ok(data) => header.lod_zoom_factor = data,
-            Err(error) => return Err(error)
+    }
+}
+
+/// Implementation of StandardMaterial.
+impl StandardMaterial {
+
+    /// This function reads a `StandardMaterial` from the provided data. The data must contain the material, and only the material.
+    fn read(data: &[u8]) -> Result<Self> {
+        let mut offset = 0;
+        let vertex_format = data.decode_packedfile_integer_u16(offset, &mut offset)?;
+        let name = data.decode_string_u8_0padded(offset, MATERIAL_NAME_SIZE)?.0;
+        offset += MATERIAL_NAME_SIZE;
+        let texture_directory = data.decode_string_u8_0padded(offset, MATERIAL_PATH_SIZE)?.0;
+        offset += MATERIAL_PATH_SIZE;
+        let filters = data.decode_string_u8_0padded(offset, MATERIAL_PATH_SIZE)?.0;
+        offset += MATERIAL_PATH_SIZE;
+        let unknown = data.decode_packedfile_integer_u16(offset, &mut offset)?;
+
+        let mut pivot_point = [0.0; 3];
+        for value in &mut pivot_point {
+            *value = data.decode_packedfile_float_f32(offset, &mut offset)?;
         }
 
-        // These two we only decode them if the RigidModel is v7 (Warhammer 1&2), as these doesn't exist
-        // in Attila's RigidModels.
-        if packed_file_data.len() == 28 {
-            match packed_file_data.decode_integer_u32(20) {
-                Ok(data) => header.mysterious_data_1 = Some(data),
-                Err(error) => return Err(error)
+        let mut matrices = [[0.0; 12]; 3];
+        for matrix in &mut matrices {
+            for value in matrix.iter_mut() {
+                *value = data.decode_packedfile_float_f32(offset, &mut offset)?;
             }
-            match packed_file_data.decode_integer_u32(24) {
-                Ok(data) => header.mysterious_data_2 = Some(data),
-                Err(error) => return Err(error)
+        }
+
+        let matrix_index = data.decode_packedfile_integer_i32(offset, &mut offset)?;
+        let parent_matrix_index = data.decode_packedfile_integer_i32(offset, &mut offset)?;
+        let attachment_points_count = data.decode_packedfile_integer_u32(offset, &mut offset)?;
+        let textures_count = data.decode_packedfile_integer_u32(offset, &mut offset)?;
+        let string_params_count = data.decode_packedfile_integer_u32(offset, &mut offset)?;
+        let float_params_count = data.decode_packedfile_integer_u32(offset, &mut offset)?;
+        let integer_params_count = data.decode_packedfile_integer_u32(offset, &mut offset)?;
+        let vector_params_count = data.decode_packedfile_integer_u32(offset, &mut offset)?;
+        let flags = data.get_bytes_checked(offset, MATERIAL_FLAGS_SIZE)?.to_vec();
+        offset += MATERIAL_FLAGS_SIZE;
+
+        let mut attachment_points = vec![];
+        for _ in 0..attachment_points_count {
+            let name = data.decode_string_u8_0padded(offset, ATTACHMENT_POINT_NAME_SIZE)?.0;
+            offset += ATTACHMENT_POINT_NAME_SIZE;
+
+            let mut matrix = [0.0; 12];
+            for value in &mut matrix {
+                *value = data.decode_packedfile_float_f32(offset, &mut offset)?;
             }
+
+            let bone_id = data.decode_packedfile_integer_i32(offset, &mut offset)?;
+            attachment_points.push(AttachmentPoint {
+                name,
+                matrix,
+                bone_id,
+            });
+        }
+
+        let mut textures = vec![];
+        for _ in 0..textures_count {
+            let texture_type = data.decode_packedfile_integer_u32(offset, &mut offset)?;
+            let path = data.decode_string_u8_0padded(offset, MATERIAL_PATH_SIZE)?.0;
+            offset += MATERIAL_PATH_SIZE;
+            textures.push(Texture {
+                texture_type,
+                path,
+            });
+        }
+
+        let params_data = data.get_bytes_checked(offset, data.len() - offset)?.to_vec();
+
+        Ok(Self {
+            vertex_format,
+            name,
+            texture_directory,
+            filters,
+            unknown,
+            pivot_point,
+            matrices,
+            matrix_index,
+            parent_matrix_index,
+            string_params_count,
+            float_params_count,
+            integer_params_count,
+            vector_params_count,
+            flags,
+            attachment_points,
+            textures,
+            params_data,
+        })
+    }
+
+    /// This function encodes the `StandardMaterial` at the end of the provided buffer.
+    fn save(&self, data: &mut Vec<u8>) -> Result<()> {
+        data.encode_integer_u16(self.vertex_format);
+        data.encode_string_u8_0padded(&(self.name.to_owned(), MATERIAL_NAME_SIZE))?;
+        data.encode_string_u8_0padded(&(self.texture_directory.to_owned(), MATERIAL_PATH_SIZE))?;
+        data.encode_string_u8_0padded(&(self.filters.to_owned(), MATERIAL_PATH_SIZE))?;
+        data.encode_integer_u16(self.unknown);
+
+        self.pivot_point.iter().for_each(|x| data.encode_float_f32(*x));
+        self.matrices.iter().flatten().for_each(|x| data.encode_float_f32(*x));
+
+        data.encode_integer_i32(self.matrix_index);
+        data.encode_integer_i32(self.parent_matrix_index);
+        data.encode_integer_u32(self.attachment_points.len() as u32);
+        data.encode_integer_u32(self.textures.len() as u32);
+        data.encode_integer_u32(self.string_params_count);
+        data.encode_integer_u32(self.float_params_count);
+        data.encode_integer_u32(self.integer_params_count);
+        data.encode_integer_u32(self.vector_params_count);
+        data.extend_from_slice(&self.flags);
+
+        for attachment_point in &self.attachment_points {
+            data.encode_string_u8_0padded(&(attachment_point.name.to_owned(), ATTACHMENT_POINT_NAME_SIZE))?;
+            attachment_point.matrix.iter().for_each(|x| data.encode_float_f32(*x));
+            data.encode_integer_i32(attachment_point.bone_id);
+        }
+
+        for texture in &self.textures {
+            data.encode_integer_u32(texture.texture_type);
+            data.encode_string_u8_0padded(&(texture.path.to_owned(), MATERIAL_PATH_SIZE))?;
+        }
+
+        data.extend_from_slice(&self.params_data);
+        Ok(())
+    }
+
+    /// This function returns the size in bytes of the `StandardMaterial`, once encoded.
+    fn get_size(&self) -> usize {
+        2 + MATERIAL_NAME_SIZE + MATERIAL_PATH_SIZE * 2 + 2 + 12 + 144 + 32 + self.flags.len() +
+            self.attachment_points.len() * (ATTACHMENT_POINT_NAME_SIZE + 52) +
+            self.textures.len() * (4 + MATERIAL_PATH_SIZE) +
+            self.params_data.len()
+    }
+
+    /// This function returns the vertex format of the material.
+    pub fn get_vertex_format(&self) -> u16 {
+        self.vertex_format
+    }
+
+    /// This function returns the name of the mesh using this material.
+    pub fn get_ref_name(&self) -> &str {
+        &self.name
+    }
+
+    /// This function sets the name of the mesh using this material.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_owned();
+    }
+
+    /// This function returns the texture directory of the material.
+    pub fn get_ref_texture_directory(&self) -> &str {
+        &self.texture_directory
+    }
+
+    /// This function sets the texture directory of the material.
+    pub fn set_texture_directory(&mut self, texture_directory: &str) {
+        self.texture_directory = texture_directory.to_owned();
+    }
+
+    /// This function returns the attachment points of the material.
+    pub fn get_ref_attachment_points(&self) -> &[AttachmentPoint] {
+        &self.attachment_points
+    }
+
+    /// This function returns the textures of the material.
+    pub fn get_ref_textures(&self) -> &[Texture] {
+        &self.textures
+    }
+
+    /// This function returns a mutable reference to the textures of the material.
+    pub fn get_ref_mut_textures(&mut self) -> &mut Vec<Texture> {
+        &mut self.textures
+    }
+}
+
+/// Implementation of AttachmentPoint.
+impl AttachmentPoint {
+
+    /// This function returns the name of the attachment point.
+    pub fn get_ref_name(&self) -> &str {
+        &self.name
+    }
+
+    /// This function returns the bone this attachment point is attached to.
+    pub fn get_bone_id(&self) -> i32 {
+        self.bone_id
+    }
+}
+
+/// Implementation of Texture.
+impl Texture {
+
+    /// This function creates a new `Texture`.
+    pub fn new(texture_type: u32, path: &str) -> Self {
+        Self {
+            texture_type,
+            path: path.to_owned(),
         }
+    }
 
-        Ok(header)
+    /// This function returns the type of the texture.
+    pub fn get_texture_type(&self) -> u32 {
+        self.texture_type
     }
 
-    /// This function reads the data from a RigidModelLodHeader and encode it into a Vec<u8>.
-    pub fn save(rigid_model_lod: &RigidModelLodHeader) -> Vec<u8> {
-        let mut packed_file_data: Vec<u8> = vec![];
+    /// This function returns the path of the texture.
+    pub fn get_ref_path(&self) -> &str {
+        &self.path
+    }
 
-        packed_file_data.encode_integer_u32(rigid_model_lod.groups_count);
-        packed_file_data.encode_integer_u32(rigid_model_lod.vertices_data_length);
-        packed_file_data.encode_integer_u32(rigid_model_lod.indices_data_length);
-        packed_file_data.encode_integer_u32(rigid_model_lod.start_offset);
-        packed_file_data.encode_float_f32(rigid_model_lod.lod_zoom_factor);
+    /// This function sets the path of the texture.
+    pub fn set_path(&mut self, path: &str) {
+        self.path = path.to_owned();
+    }
+}
 
-        let mysterious_data_1 = match rigid_model_lod.mysterious_data_1 {
-            Some(data) => Some(data),
-            None => None,
+/// Implementation of Vertices.
+impl Vertices {
+
+    /// This function reads the vertices of a mesh from the provided data, depending on the vertex format of its material.
+    ///
+    /// If we don't know the format, or we cannot encode the vertices back exactly as they were, we keep them as raw data.
+    fn read(data: &[u8], count: u32, material: &Material) -> Self {
+        let format = material.get_vertex_format();
+        let vertex_size = match format {
+            Some(VERTEX_FORMAT_DEFAULT) => VERTEX_SIZE_DEFAULT,
+            Some(VERTEX_FORMAT_WEIGHTED) => VERTEX_SIZE_WEIGHTED,
+            Some(VERTEX_FORMAT_CINEMATIC) => VERTEX_SIZE_CINEMATIC,
+            _ => return Self::Unknown(count, data.to_vec()),
         };
 
-        let mysterious_data_2 = match rigid_model_lod.mysterious_data_2 {
-            Some(data) => Some(data),
-            None => None,
+        if data.len() != vertex_size * count as usize {
+            return Self::Unknown(count, data.to_vec());
+        }
+
+        let vertices = match format {
+            Some(VERTEX_FORMAT_DEFAULT) => Self::Default(data.chunks_exact(vertex_size).map(DefaultVertex::read).collect()),
+            Some(VERTEX_FORMAT_WEIGHTED) => Self::Weighted(data.chunks_exact(vertex_size).map(WeightedVertex::read).collect()),
+            Some(VERTEX_FORMAT_CINEMATIC) => Self::Cinematic(data.chunks_exact(vertex_size).map(CinematicVertex::read).collect()),
+            _ => unreachable!(),
         };
 
-        // These two are only added if they are something (Warhammer1&2 RigidModels).
-        if mysterious_data_1 != None {
-            packed_file_data.encode_integer_u32(mysterious_data_1.unwrap());
+        let mut encoded_data = vec![];
+        vertices.save(&mut encoded_data);
+        if encoded_data == data { vertices } else { Self::Unknown(count, data.to_vec()) }
+    }
+
+    /// This function encodes the vertices at the end of the provided buffer.
+    fn save(&self, data: &mut Vec<u8>) {
+        match self {
+            Self::Default(vertices) => vertices.iter().for_each(|x| x.save(data)),
+            Self::Weighted(vertices) => vertices.iter().for_each(|x| x.save(data)),
+            Self::Cinematic(vertices) => vertices.iter().for_each(|x| x.save(data)),
+            Self::Unknown(_, raw_data) => data.extend_from_slice(raw_data),
+        }
+    }
+
+    /// This function returns the amount of vertices.
+    pub fn get_count(&self) -> u32 {
+        match self {
+            Self::Default(vertices) => vertices.len() as u32,
+            Self::Weighted(vertices) => vertices.len() as u32,
+            Self::Cinematic(vertices) => vertices.len() as u32,
+            Self::Unknown(count, _) => *count,
         }
-        if mysterious_data_2 != None {
-            packed_file_data.encode_integer_u32(mysterious_data_2.unwrap());
+    }
+
+    /// This function returns the size in bytes of the vertices, once encoded.
+    fn get_size(&self) -> usize {
+        match self {
+            Self::Default(vertices) => vertices.len() * VERTEX_SIZE_DEFAULT,
+            Self::Weighted(vertices) => vertices.len() * VERTEX_SIZE_WEIGHTED,
+            Self::Cinematic(vertices) => vertices.len() * VERTEX_SIZE_CINEMATIC,
+            Self::Unknown(_, raw_data) => raw_data.len(),
+        }
+    }
+}
+
+/// Implementation of DefaultVertex.
+impl DefaultVertex {
+
+    /// This function reads a `DefaultVertex` from the provided data. The data must be exactly one vertex.
+    fn read(data: &[u8]) -> Self {
+        Self {
+            position: read_f16_array(&data[0..8]),
+            uv: read_f16_array(&data[8..12]),
+            uv_2: read_f16_array(&data[12..16]),
+            normal: [data[16], data[17], data[18], data[19]],
+            tangent: [data[20], data[21], data[22], data[23]],
+            bitangent: [data[24], data[25], data[26], data[27]],
+            color: [data[28], data[29], data[30], data[31]],
         }
-        packed_file_data
     }
+
+    /// This function encodes the `DefaultVertex` at the end of the provided buffer.
+    fn save(&self, data: &mut Vec<u8>) {
+        save_f16_array(&self.position, data);
+        save_f16_array(&self.uv, data);
+        save_f16_array(&self.uv_2, data);
+        data.extend_from_slice(&self.normal);
+        data.extend_from_slice(&self.tangent);
+        data.extend_from_slice(&self.bitangent);
+        data.extend_from_slice(&self.color);
+    }
+
+    /// This function returns the position of the vertex.
+    pub fn get_position(&self) -> [f32; 4] {
+        self.position
+    }
+
+    /// This function returns the texture coordinates of the vertex.
+    pub fn get_uv(&self) -> [f32; 2] {
+        self.uv
+    }
+}
+
+/// Implementation of WeightedVertex.
+impl WeightedVertex {
+
+    /// This function reads a `WeightedVertex` from the provided data. The data must be exactly one vertex.
+    fn read(data: &[u8]) -> Self {
+        Self {
+            position: read_f16_array(&data[0..8]),
+            bone_indices: [data[8], data[9]],
+            bone_weights: [data[10], data[11]],
+            normal: [data[12], data[13], data[14], data[15]],
+            uv: read_f16_array(&data[16..20]),
+            tangent: [data[20], data[21], data[22], data[23]],
+            bitangent: [data[24], data[25], data[26], data[27]],
+        }
+    }
+
+    /// This function encodes the `WeightedVertex` at the end of the provided buffer.
+    fn save(&self, data: &mut Vec<u8>) {
+        save_f16_array(&self.position, data);
+        data.extend_from_slice(&self.bone_indices);
+        data.extend_from_slice(&self.bone_weights);
+        data.extend_from_slice(&self.normal);
+        save_f16_array(&self.uv, data);
+        data.extend_from_slice(&self.tangent);
+        data.extend_from_slice(&self.bitangent);
+    }
+
+    /// This function returns the position of the vertex.
+    pub fn get_position(&self) -> [f32; 4] {
+        self.position
+    }
+
+    /// This function returns the texture coordinates of the vertex.
+    pub fn get_uv(&self) -> [f32; 2] {
+        self.uv
+    }
+
+    /// This function returns the bones affecting the vertex.
+    pub fn get_bone_indices(&self) -> [u8; 2] {
+        self.bone_indices
+    }
+}
+
+/// Implementation of CinematicVertex.
+impl CinematicVertex {
+
+    /// This function reads a `CinematicVertex` from the provided data. The data must be exactly one vertex.
+    fn read(data: &[u8]) -> Self {
+        Self {
+            position: read_f16_array(&data[0..8]),
+            bone_indices: [data[8], data[9], data[10], data[11]],
+            bone_weights: [data[12], data[13], data[14], data[15]],
+            normal: [data[16], data[17], data[18], data[19]],
+            uv: read_f16_array(&data[20..24]),
+            tangent: [data[24], data[25], data[26], data[27]],
+            bitangent: [data[28], data[29], data[30], data[31]],
+        }
+    }
+
+    /// This function encodes the `CinematicVertex` at the end of the provided buffer.
+    fn save(&self, data: &mut Vec<u8>) {
+        save_f16_array(&self.position, data);
+        data.extend_from_slice(&self.bone_indices);
+        data.extend_from_slice(&self.bone_weights);
+        data.extend_from_slice(&self.normal);
+        save_f16_array(&self.uv, data);
+        data.extend_from_slice(&self.tangent);
+        data.extend_from_slice(&self.bitangent);
+    }
+
+    /// This function returns the position of the vertex.
+    pub fn get_position(&self) -> [f32; 4] {
+        self.position
+    }
+
+    /// This function returns the texture coordinates of the vertex.
+    pub fn get_uv(&self) -> [f32; 2] {
+        self.uv
+    }
+
+    /// This function returns the bones affecting the vertex.
+    pub fn get_bone_indices(&self) -> [u8; 4] {
+        self.bone_indices
+    }
+}
+
+/// Default implementation of RigidModel.
+impl Default for RigidModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                              Helper functions
+//---------------------------------------------------------------------------//
+
+/// This function reads an array of half-precision floats from the provided data, as `f32`.
+fn read_f16_array<T: Default + AsMut<[f32]>>(data: &[u8]) -> T {
+    let mut array = T::default();
+    for (value, bytes) in array.as_mut().iter_mut().zip(data.chunks_exact(2)) {
+        *value = f16::from_bits(u16::from_le_bytes([bytes[0], bytes[1]])).to_f32();
+    }
+    array
+}
+
+/// This function encodes an array of `f32` as half-precision floats at the end of the provided buffer.
+fn save_f16_array(array: &[f32], data: &mut Vec<u8>) {
+    array.iter().for_each(|x| data.encode_integer_u16(f16::from_f32(*x).to_bits()));
 }
-*/
@@ -0,0 +1,165 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `RigidModel` module, to make sure we read and save RigidModels without altering them.
+!*/
+
+use super::*;
+
+/// This function builds a small RigidModel with one lod per supported vertex format, and one mesh with an unknown material.
+fn build_rigid_model(version: u32) -> RigidModel {
+    let mut rigid_model = RigidModel::new();
+    rigid_model.version = version;
+    rigid_model.set_skeleton_id("humanoid01");
+
+    let material = |vertex_format| Material::Standard(Box::new(StandardMaterial {
+        vertex_format,
+        name: "body".to_owned(),
+        texture_directory: "variantmeshes/wh_variantmodels/hu1/emp/tex".to_owned(),
+        filters: String::new(),
+        unknown: 0,
+        pivot_point: [0.0, 1.0, 0.0],
+        matrices: [[1.0; 12]; 3],
+        matrix_index: -1,
+        parent_matrix_index: -1,
+        string_params_count: 0,
+        float_params_count: 1,
+        integer_params_count: 0,
+        vector_params_count: 0,
+        flags: vec![0; MATERIAL_FLAGS_SIZE],
+        attachment_points: vec![AttachmentPoint {
+            name: "root".to_owned(),
+            matrix: [0.5; 12],
+            bone_id: 0,
+        }],
+        textures: vec![
+            Texture::new(0, "variantmeshes/wh_variantmodels/hu1/emp/tex/body_base_colour.dds"),
+            Texture::new(1, "variantmeshes/wh_variantmodels/hu1/emp/tex/body_normal.dds"),
+        ],
+        params_data: vec![1, 2, 3, 4, 5, 6, 7, 8],
+    }));
+
+    let default_vertex = DefaultVertex {
+        position: [1.0, 0.5, -0.25, 1.0],
+        uv: [0.5, 0.5],
+        uv_2: [0.0, 1.0],
+        normal: [127, 127, 255, 0],
+        tangent: [255, 127, 127, 0],
+        bitangent: [127, 255, 127, 0],
+        color: [255, 255, 255, 255],
+    };
+
+    let weighted_vertex = WeightedVertex {
+        position: [1.0, 2.0, 3.0, 1.0],
+        bone_indices: [1, 2],
+        bone_weights: [200, 55],
+        normal: [127, 127, 255, 0],
+        uv: [0.25, 0.75],
+        tangent: [255, 127, 127, 0],
+        bitangent: [127, 255, 127, 0],
+    };
+
+    let cinematic_vertex = CinematicVertex {
+        position: [-1.0, -2.0, -3.0, 1.0],
+        bone_indices: [1, 2, 3, 4],
+        bone_weights: [100, 100, 50, 5],
+        normal: [127, 127, 255, 0],
+        uv: [0.125, 0.875],
+        tangent: [255, 127, 127, 0],
+        bitangent: [127, 255, 127, 0],
+    };
+
+    let mut lod_0 = Lod::new(100.0, 0);
+    lod_0.get_ref_mut_mesh_groups().push(MeshGroup::new(2, "default_dry", material(VERTEX_FORMAT_DEFAULT), Vertices::Default(vec![default_vertex; 3]), vec![0, 1, 2]));
+    lod_0.get_ref_mut_mesh_groups().push(MeshGroup::new(2, "weighted4", material(VERTEX_FORMAT_WEIGHTED), Vertices::Weighted(vec![weighted_vertex; 4]), vec![0, 1, 2, 2, 3, 0]));
+
+    let mut lod_1 = Lod::new(1000.0, 1);
+    lod_1.get_ref_mut_mesh_groups().push(MeshGroup::new(2, "cinematic", material(VERTEX_FORMAT_CINEMATIC), Vertices::Cinematic(vec![cinematic_vertex; 3]), vec![2, 1, 0]));
+    lod_1.get_ref_mut_mesh_groups().push(MeshGroup::new(17, "", Material::Unknown(vec![9; 40]), Vertices::Unknown(2, vec![7; 24]), vec![0, 1, 1]));
+
+    rigid_model.get_ref_mut_lods().push(lod_0);
+    rigid_model.get_ref_mut_lods().push(lod_1);
+    rigid_model
+}
+
+#[test]
+fn test_encode_rigidmodel() {
+    for version in &[6, 7, 8] {
+        let rigid_model = build_rigid_model(*version);
+        let data = rigid_model.save().unwrap();
+        let rigid_model_decoded = RigidModel::read(&data).unwrap();
+
+        // Version 6 doesn't have these, so they come back as 0.
+        if *version == 6 {
+            assert_eq!(rigid_model_decoded.get_ref_lods()[1].get_authored_lod_number(), 0);
+        } else {
+            assert_eq!(rigid_model_decoded, rigid_model);
+        }
+
+        assert_eq!(rigid_model_decoded.save().unwrap(), data);
+    }
+}
+
+#[test]
+fn test_edit_rigidmodel() {
+    let mut rigid_model = build_rigid_model(7);
+    rigid_model.set_skeleton_id("humanoid01d");
+    assert_eq!(rigid_model.replace_texture_path("variantmeshes/wh_variantmodels/hu1/emp/tex/body_normal.dds", "custom/body_normal.dds"), 3);
+    assert!(rigid_model.get_texture_paths().contains(&"custom/body_normal.dds".to_owned()));
+
+    // Sizes and offsets must be updated, so the edited model can be read back.
+    let data = rigid_model.save().unwrap();
+    assert_eq!(RigidModel::read(&data).unwrap(), rigid_model);
+}
+
+#[test]
+fn test_patch_rigidmodel_attila_to_warhammer() {
+    let mut rigid_model = RigidModel::read(&build_rigid_model(6).save().unwrap()).unwrap();
+    rigid_model.patch_rigid_model_attila_to_warhammer().unwrap();
+    assert_eq!(rigid_model.get_version(), 7);
+    assert_eq!(rigid_model.get_ref_lods()[1].get_authored_lod_number(), 1);
+    assert!(rigid_model.patch_rigid_model_attila_to_warhammer().is_err());
+
+    let data = rigid_model.save().unwrap();
+    assert_eq!(RigidModel::read(&data).unwrap(), rigid_model);
+}
+
+#[test]
+fn test_read_broken_rigidmodel() {
+    let mut data = build_rigid_model(7).save().unwrap();
+    assert!(RigidModel::read(&data[..100]).is_err());
+
+    data.push(0);
+    assert!(RigidModel::read(&data).is_err());
+
+    data[0..4].copy_from_slice(b"RMV3");
+    assert!(RigidModel::read(&data).is_err());
+}
+
+#[test]
+fn test_read_mesh_group_huge_indices_count() {
+
+    // A mesh claiming far more indices than the data has must fail instead of reserving memory for them.
+    let mut data = vec![];
+    data.encode_integer_u16(2);
+    data.encode_integer_u16(0);
+    data.encode_integer_u32(u32::MAX);
+    data.encode_integer_u32(MESH_HEADER_SIZE as u32);
+    data.encode_integer_u32(0);
+    data.encode_integer_u32(MESH_HEADER_SIZE as u32);
+    data.encode_integer_u32(u32::MAX / 4);
+    data.extend_from_slice(&[0; 24 + SHADER_NAME_SIZE + SHADER_PARAMS_SIZE]);
+    data.extend_from_slice(&[0; 16]);
+    assert_eq!(data.len(), MESH_HEADER_SIZE + 16);
+
+    let mut offset = 0;
+    assert!(MeshGroup::read(&data, &mut offset).is_err());
+}
@@ -19,24 +19,30 @@ use bincode::deserialize;
 use serde_derive::{Serialize, Deserialize};
 use uuid::Uuid;
 
+use std::cmp::Reverse;
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{BufReader, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use rpfm_error::{ErrorKind, Result};
 
 use crate::assembly_kit::table_data::RawTable;
 use crate::common::{decoder::Decoder, encoder::Encoder};
 use crate::common::get_game_selected_pak_file;
+use crate::context::GameContext;
+use crate::dependencies::Dependencies;
 use crate::GAME_SELECTED;
 use crate::games::*;
 use crate::packedfile::DecodedPackedFile;
 use crate::packfile::PackFile;
 use crate::packfile::packedfile::PackedFile;
 use crate::schema::*;
-use crate::SETTINGS;
 use super::DecodedData;
+use super::guesser::{GuessedDefinition, guess_definitions};
+use super::MergeConflict;
+use super::migration::TableMigration;
+use super::port::{TablePort, UnresolvedReference};
 use super::Table;
 
 /// If this sequence is found, the DB Table has a GUID after it.
@@ -147,6 +153,85 @@ impl DB {
         self.table.set_table_data(data)
     }
 
+    /// This function migrates this table to the provided definition, returning a report of the changes done.
+    ///
+    /// Unlike `set_definition`, this converts the data of fields whose type changed, and tries to find renamed fields using the history of the table.
+    pub fn migrate(&mut self, new_definition: &Definition, history: &[Definition]) -> TableMigration {
+        self.table.migrate(new_definition, history)
+    }
+
+    /// This function ports this table to the provided definition of the same table in another game, returning a report of the changes done.
+    ///
+    /// Fields are only matched by name. References are not checked here, as the tables they point to may not be ported yet.
+    /// Use `get_unresolved_references` once all the tables have been ported for that.
+    pub fn port(&mut self, new_definition: &Definition) -> TablePort {
+        TablePort {
+            migration: self.table.port(new_definition),
+            unresolved_references: vec![],
+        }
+    }
+
+    /// This function returns the non-empty cells of this table referencing values not found in the provided `PackFile` or `Dependencies`.
+    ///
+    /// Unlike the diagnostics, references to tables not found at all are also reported.
+    pub fn get_unresolved_references(&self, pack_file: &PackFile, dependencies: &Dependencies) -> Vec<UnresolvedReference> {
+        let fields = self.get_ref_definition().get_fields_processed();
+        let dependency_data = Self::get_dependency_data(
+            pack_file,
+            self.get_ref_definition(),
+            dependencies.get_ref_dependency_database(),
+            dependencies.get_ref_fake_dependency_database(),
+            &[],
+        );
+
+        let mut unresolved_references = vec![];
+        for (row, cells) in self.get_ref_table_data().iter().enumerate() {
+            for (column, ref_data) in &dependency_data {
+                let cell_data = cells[*column as usize].data_to_string();
+                if !cell_data.is_empty() && !ref_data.contains_key(&cell_data) {
+                    let field = &fields[*column as usize];
+                    if let Some((reference_table, reference_column)) = field.get_is_reference() {
+                        unresolved_references.push(UnresolvedReference {
+                            row: row as u64 + 1,
+                            field: field.get_name().to_owned(),
+                            value: cell_data,
+                            reference_table: reference_table.to_owned(),
+                            reference_column: reference_column.to_owned(),
+                        });
+                    }
+                }
+            }
+        }
+
+        unresolved_references
+    }
+
+    /// This function removes the duplicated keys of a merge base built from vanilla data, keeping the row of the last vanilla table.
+    pub fn dedup_merge_base(&mut self) {
+        self.table.dedup_merge_base();
+    }
+
+    /// This function removes from a merge base built from vanilla data the rows whose keys are not in both of the provided DB Tables.
+    pub fn retain_merge_base(&mut self, ours: &Self, theirs: &Self) {
+        self.table.retain_merge_base(&ours.table, &theirs.table);
+    }
+
+    /// This function performs a three-way merge of the provided DB Tables, returning the merged table and the conflicts found.
+    ///
+    /// The merged table keeps the UUID of `ours`. If the tables are not of the same type, this returns an error.
+    pub fn merge(base: &Self, ours: &Self, theirs: &Self) -> Result<(Self, Vec<MergeConflict>)> {
+        if base.name != ours.name || ours.name != theirs.name { return Err(ErrorKind::InvalidFilesForMerging.into()) }
+
+        let (table, conflicts) = Table::merge(&base.table, &ours.table, &theirs.table);
+        let merged = Self {
+            name: ours.name.to_owned(),
+            mysterious_byte: ours.mysterious_byte,
+            uuid: ours.uuid.to_owned(),
+            table,
+        };
+        Ok((merged, conflicts))
+    }
+
     /// This function creates a `DB` from a `Vec<u8>`.
     pub fn read(
         packed_file_data: &[u8],
@@ -210,21 +295,53 @@ impl DB {
         })
     }
 
+    /// This function tries to guess the `Definition` of an undecoded `DB` from its data, returning the candidates found, best first.
+    ///
+    /// The previous definitions of the table are used as a starting point, and to name the guessed fields.
+    pub fn guess_definitions(packed_file_data: &[u8], previous_definitions: &[Definition]) -> Result<Vec<GuessedDefinition>> {
+        let (version, _, _, entry_count, index) = Self::read_header(packed_file_data)?;
+        if entry_count == 0 { return Err(ErrorKind::TableEmptyWithNoDefinition.into()) }
+
+        let mut previous_definitions = previous_definitions.to_vec();
+        previous_definitions.sort_by_key(|x| Reverse(x.get_version()));
+        Ok(guess_definitions(packed_file_data, index, entry_count, version, &previous_definitions))
+    }
+
     /// This function takes a `DB` and encodes it to `Vec<u8>`.
     pub fn save(&self) -> Result<Vec<u8>> {
+        self.save_with_context(&GameContext::from_game_selected()?)
+    }
+
+    /// This function takes a `DB` and encodes it to `Vec<u8>`, for the game and with the settings of the provided `GameContext`.
+    pub fn save_with_context(&self, context: &GameContext) -> Result<Vec<u8>> {
+        let keep_uuid = context.get_ref_settings().settings_bool.get("disable_uuid_regeneration_on_db_tables").cloned().unwrap_or(false);
+        let uuid = self.get_uuid_for_saving(keep_uuid);
+        self.save_for_game(&uuid, context.get_ref_game_key())
+    }
+
+    /// This function takes a `DB` and encodes it to `Vec<u8>`, using the provided UUID instead of a new one.
+    pub fn save_with_uuid(&self, uuid: &str) -> Result<Vec<u8>> {
+        self.save_for_game(uuid, &GAME_SELECTED.read().unwrap())
+    }
+
+    /// This function returns the UUID to use when saving the `DB`: its current one if we have to keep it, or a new one otherwise.
+    fn get_uuid_for_saving(&self, keep_uuid: bool) -> String {
+        if keep_uuid && !self.uuid.is_empty() {
+            self.uuid.to_owned()
+        } else {
+            format!("{}", Uuid::new_v4())
+        }
+    }
+
+    /// This function takes a `DB` and encodes it to `Vec<u8>` for the provided game, using the provided UUID.
+    fn save_for_game(&self, uuid: &str, game_key: &str) -> Result<Vec<u8>> {
         let mut packed_file: Vec<u8> = vec![];
 
         // Napoleon and Empire do not have GUID, and adding it to their tables crash both games.
         // So for those two games, we ignore the GUID_MARKER and the GUID itself.
-        let game_selected = GAME_SELECTED.read().unwrap().to_owned();
-        if game_selected != KEY_EMPIRE && game_selected != KEY_NAPOLEON {
+        if game_key != KEY_EMPIRE && game_key != KEY_NAPOLEON {
             packed_file.extend_from_slice(GUID_MARKER);
-            if SETTINGS.read().unwrap().settings_bool["disable_uuid_regeneration_on_db_tables"] && !self.uuid.is_empty() {
-                packed_file.encode_packedfile_string_u16(&self.uuid);
-            }
-            else {
-                packed_file.encode_packedfile_string_u16(&format!("{}", Uuid::new_v4()));
-            }
+            packed_file.encode_packedfile_string_u16(uuid);
         }
         packed_file.extend_from_slice(VERSION_MARKER);
         packed_file.encode_integer_i32(self.table.definition.get_version());
@@ -274,23 +391,64 @@ impl DB {
         Ok((version, mysterious_byte, uuid, entry_count, index))
     }
 
+    /// This function returns the values of the bitwise fields of a `DB` using bits not defined in the provided `Definition`.
+    ///
+    /// These bits are lost when decoding the table, so this needs the raw data of the `DB`. The values are returned as `(row, field_name, value)`.
+    pub fn get_undefined_bitwise_values(packed_file_data: &[u8], definition: &Definition) -> Result<Vec<(usize, String, u64)>> {
+        let bitwise_fields = definition.get_ref_fields().iter().filter(|x| x.get_is_bitwise() > 1).collect::<Vec<&Field>>();
+        if bitwise_fields.is_empty() { return Ok(vec![]) }
+
+        // Decode the table with the bitwise fields as normal integers, so we get their full values.
+        let fields = definition.get_ref_fields().iter().map(|x| {
+            let mut field = x.clone();
+            field.set_is_bitwise(0);
+            field
+        }).collect::<Vec<Field>>();
+        let table = Self::read_with_fields(packed_file_data, "", &fields, false)?;
+        let fields_processed = table.get_ref_definition().get_fields_processed();
+
+        let mut values = vec![];
+        for field in bitwise_fields {
+            if let Some(column) = fields_processed.iter().position(|x| x.get_name() == field.get_name()) {
+                for (row, cells) in table.get_ref_table_data().iter().enumerate() {
+                    let value = match cells[column] {
+                        DecodedData::I16(value) => u64::from(value as u16),
+                        DecodedData::I32(value) => u64::from(value as u32),
+                        DecodedData::I64(value) => value as u64,
+                        _ => continue,
+                    };
+
+                    if value.checked_shr(field.get_is_bitwise() as u32).unwrap_or(0) != 0 {
+                        values.push((row, field.get_name().to_owned(), value));
+                    }
+                }
+            }
+        }
+
+        Ok(values)
+    }
+
     /// This function loads the PAK file of the game selected (if exists) into memory.
     ///
     /// This is useful to help resolving dependencies.
     pub fn read_pak_file() -> Vec<Self> {
+        match get_game_selected_pak_file() {
+            Ok(pak_file) => Self::read_pak_file_from_path(&pak_file),
+            Err(_) => vec![],
+        }
+    }
+
+    /// This function loads the provided PAK file into memory.
+    pub fn read_pak_file_from_path(pak_file: &Path) -> Vec<Self> {
 
         // Create the empty list.
         let mut db_files = vec![];
-
-        // Get all the paths we need.
-        if let Ok(pak_file) = get_game_selected_pak_file() {
-            if let Ok(pak_file) = File::open(pak_file) {
-                let mut pak_file = BufReader::new(pak_file);
-                let mut data = vec![];
-                if pak_file.read_to_end(&mut data).is_ok() {
-                    if let Ok(pak_file) = deserialize(&data) {
-                        db_files = pak_file;
-                    }
+        if let Ok(pak_file) = File::open(pak_file) {
+            let mut pak_file = BufReader::new(pak_file);
+            let mut data = vec![];
+            if pak_file.read_to_end(&mut data).is_ok() {
+                if let Ok(pak_file) = deserialize(&data) {
+                    db_files = pak_file;
                 }
             }
         }
@@ -539,6 +697,23 @@ impl DB {
         data
     }
 
+    /// This function returns all the values of a column of the provided table, from the PackFile and its dependencies.
+    ///
+    /// The table name must not have the `_tables` suffix.
+    pub fn get_column_data(
+        pack_file: &PackFile,
+        table_name: &str,
+        column_name: &str,
+        real_dep_db: &[PackedFile],
+        fake_dep_db: &[DB],
+    ) -> BTreeMap<String, String> {
+        let mut references = BTreeMap::new();
+        Self::get_dependency_data_from_real_dependencies(&mut references, (table_name, column_name, &[]), real_dep_db);
+        Self::get_dependency_data_from_fake_dependencies(&mut references, (table_name, column_name, &[]), fake_dep_db);
+        Self::get_dependency_data_from_packfile(&mut references, (table_name, column_name, &[]), pack_file, &[]);
+        references
+    }
+
     /// This function is used to check if a table is outdated or not.
     pub fn is_outdated(&self, dependencies: &[PackedFile]) -> bool {
         if let Some(vanilla_db) = dependencies.iter().find(|x| x.get_path().starts_with(&["db".to_owned(), self.get_table_name()])) {
@@ -555,15 +730,18 @@ impl DB {
     }
 
     /// This function imports a TSV file into a decoded table.
+    ///
+    /// TSV files from other versions of the table are migrated to the provided definition, using `history` to decode them.
     pub fn import_tsv(
         definition: &Definition,
+        history: &[Definition],
         path: &PathBuf,
         name: &str,
-    ) -> Result<Self> {
-        let table = Table::import_tsv(definition, path, name)?;
+    ) -> Result<(Self, Option<TableMigration>)> {
+        let (table, migration) = Table::import_tsv(definition, history, path, name)?;
         let mut db = DB::from(table);
         db.name = name.to_owned();
-        Ok(db)
+        Ok((db, migration))
     }
 
     /// This function exports the provided data to a TSV file.
@@ -576,17 +754,22 @@ impl DB {
     }
 
     /// This function imports a TSV file into a binary file on disk.
+    ///
+    /// TSV files from old versions of their table are migrated to the latest one. The reports of these migrations are returned.
     pub fn import_tsv_to_binary_file(
         schema: &Schema,
         source_paths: &[PathBuf],
-    ) -> Result<()> {
+    ) -> Result<Vec<(PathBuf, TableMigration)>> {
+        let mut migrations = vec![];
         for path in source_paths {
             let mut destination = path.clone();
             destination.set_extension("");
-            Table::import_tsv_to_binary_file(&schema, &path, &destination)?;
+            if let Some(migration) = Table::import_tsv_to_binary_file(schema, path, &destination)? {
+                migrations.push((path.to_path_buf(), migration));
+            }
         }
 
-        Ok(())
+        Ok(migrations)
     }
 
     /// This function exports to TSV a binary file on disk.
@@ -0,0 +1,468 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with the code to guess the `Definition` of tables we cannot decode.
+
+When a game update changes the structure of a table, its version gets bumped and we cannot decode it until someone
+makes a new `Definition` for it. The code here tries to find that `Definition` automatically, searching for sequences
+of `FieldType` that decode every row of the table consuming all its bytes.
+
+The search is done in two steps:
+- First, we try small edits (a field added, removed or with its type changed) over the previous versions of the table.
+- Then, we do a bruteforce search over the first row, validating every sequence of fields found against the full table.
+
+The candidates found are ranked by how plausible their data is, and by how many changes they have compared to the
+previous versions of the table. This is an heuristic: the best candidate is not always the right one.
+!*/
+
+use std::char::decode_utf16;
+
+use crate::common::decoder::Decoder;
+use crate::schema::{Definition, Field, FieldType};
+
+//---------------------------------------------------------------------------//
+//                              Constants
+//---------------------------------------------------------------------------//
+
+/// Max amount of candidates returned by the guesser.
+pub const MAX_CANDIDATES: usize = 10;
+
+/// Max amount of valid candidates the bruteforce search collects before stopping.
+const MAX_CANDIDATES_FOUND: usize = 50;
+
+/// Max amount of field sequences the bruteforce search tries before giving up.
+const MAX_STEPS: usize = 250_000;
+
+/// Max amount of fields a guessed `Definition` can have.
+const MAX_FIELDS: usize = 128;
+
+/// Penalty applied for each change between a candidate and the closest previous `Definition`.
+const PENALTY_PER_CHANGE: u32 = 10;
+
+/// Field types the guesser can use, in the order they're tried. Sequences are not guessed.
+const GUESSABLE_FIELD_TYPES: [FieldType; 9] = [
+    FieldType::StringU8,
+    FieldType::I32,
+    FieldType::F32,
+    FieldType::Boolean,
+    FieldType::OptionalStringU8,
+    FieldType::StringU16,
+    FieldType::OptionalStringU16,
+    FieldType::I16,
+    FieldType::I64,
+];
+
+//---------------------------------------------------------------------------//
+//                              Enum & Structs
+//---------------------------------------------------------------------------//
+
+/// This struct represents a `Definition` guessed from the data of a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuessedDefinition {
+
+    /// The guessed `Definition`. Fields matching the previous `Definition` keep their name and data.
+    definition: Definition,
+
+    /// How unlikely is this `Definition` to be the right one. Lower is better.
+    penalty: u32,
+}
+
+/// This struct contains the internal state of the search.
+struct Guesser<'a> {
+    data: &'a [u8],
+    start: usize,
+    entry_count: u32,
+    previous_definitions: &'a [Definition],
+    previous_field_types: Vec<Vec<FieldType>>,
+    candidates: Vec<(Vec<FieldType>, u32)>,
+    steps: usize,
+}
+
+/// This struct contains the info we collect about a column while decoding it.
+#[derive(Clone, Copy)]
+struct ColumnStats {
+
+    /// If any cell of the column has a non-default value.
+    has_data: bool,
+
+    /// If all the non-default cells of an integer column look like floats.
+    float_like: bool,
+}
+
+//---------------------------------------------------------------------------//
+//                              Implementations
+//---------------------------------------------------------------------------//
+
+/// Implementation of `GuessedDefinition`.
+impl GuessedDefinition {
+
+    /// This function returns a reference to the guessed `Definition`.
+    pub fn get_ref_definition(&self) -> &Definition {
+        &self.definition
+    }
+
+    /// This function returns the penalty of the guessed `Definition`. Lower is better.
+    pub fn get_penalty(&self) -> u32 {
+        self.penalty
+    }
+}
+
+/// Implementation of `Guesser`.
+impl<'a> Guesser<'a> {
+
+    /// This function tries all the definitions one change away from the previous definitions.
+    fn search_from_previous_definitions(&mut self) {
+        for field_types in self.previous_field_types.clone() {
+            self.try_candidate(&field_types);
+
+            for position in 0..=field_types.len() {
+                for field_type in &GUESSABLE_FIELD_TYPES {
+                    let mut candidate = field_types.to_vec();
+                    candidate.insert(position, field_type.clone());
+                    self.try_candidate(&candidate);
+                }
+
+                if position < field_types.len() {
+                    let mut candidate = field_types.to_vec();
+                    candidate.remove(position);
+                    self.try_candidate(&candidate);
+
+                    for field_type in GUESSABLE_FIELD_TYPES.iter().filter(|x| **x != field_types[position]) {
+                        let mut candidate = field_types.to_vec();
+                        candidate[position] = field_type.clone();
+                        self.try_candidate(&candidate);
+                    }
+                }
+            }
+        }
+    }
+
+    /// This function does a depth-first search of plausible fields over the first row, trying every sequence found as a candidate.
+    ///
+    /// `index` is where the next field of the first row starts, and `min_row_size` is the minimum size a row can have with the fields we have.
+    fn search_bruteforce(&mut self, field_types: &mut Vec<FieldType>, index: usize, min_row_size: usize) {
+        if self.steps >= MAX_STEPS || self.candidates.len() >= MAX_CANDIDATES_FOUND { return }
+        self.steps += 1;
+
+        if !field_types.is_empty() && self.could_be_complete(field_types, index) {
+            self.try_candidate(field_types);
+        }
+
+        if field_types.len() >= MAX_FIELDS { return }
+        for field_type in self.get_field_types_by_priority(field_types.len()) {
+
+            // If not even the smallest rows possible fit in the data, don't bother.
+            let min_row_size = min_row_size + get_min_size(&field_type);
+            if min_row_size * self.entry_count as usize > self.data.len() - self.start { continue }
+
+            let mut stats = ColumnStats::new();
+            if let Some(next_index) = read_field(self.data, index, &field_type, &mut stats) {
+                field_types.push(field_type);
+                self.search_bruteforce(field_types, next_index, min_row_size);
+                field_types.pop();
+            }
+        }
+    }
+
+    /// This function does some cheap checks to discard candidates before trying to decode the entire table with them.
+    fn could_be_complete(&self, field_types: &[FieldType], index: usize) -> bool {
+        let row_size = index - self.start;
+        if self.entry_count == 1 { index == self.data.len() }
+        else if field_types.iter().all(|x| get_min_size(x) == get_max_size(x)) { row_size * self.entry_count as usize == self.data.len() - self.start }
+        else { true }
+    }
+
+    /// This function tries to decode the entire table with the provided field types, storing them as a candidate if they work.
+    fn try_candidate(&mut self, field_types: &[FieldType]) {
+        if field_types.is_empty() || self.candidates.iter().any(|(x, _)| x == field_types) { return }
+
+        let mut index = self.start;
+        let mut stats = vec![ColumnStats::new(); field_types.len()];
+        for _ in 0..self.entry_count {
+            for (field_type, stats) in field_types.iter().zip(stats.iter_mut()) {
+                match read_field(self.data, index, field_type, stats) {
+                    Some(next_index) => index = next_index,
+                    None => return,
+                }
+            }
+        }
+
+        if index == self.data.len() {
+            let penalty = self.get_penalty(field_types, &stats);
+            self.candidates.push((field_types.to_vec(), penalty));
+        }
+    }
+
+    /// This function calculates how unlikely the provided field types are to be the right ones.
+    fn get_penalty(&self, field_types: &[FieldType], stats: &[ColumnStats]) -> u32 {
+        let mut penalty = field_types.iter().zip(stats).map(|(field_type, stats)| {
+            1 + match field_type {
+                FieldType::Boolean if !stats.has_data => 1,
+                FieldType::I32 if stats.has_data && stats.float_like => 2,
+                FieldType::OptionalStringU8 if stats.has_data => 1,
+                FieldType::OptionalStringU8 => 2,
+                FieldType::StringU16 => 1,
+                FieldType::OptionalStringU16 if stats.has_data => 2,
+                FieldType::OptionalStringU16 => 3,
+                FieldType::I16 | FieldType::I64 if stats.has_data => 3,
+                FieldType::I16 | FieldType::I64 => 4,
+                _ => 0,
+            }
+        }).sum::<u32>();
+
+        if let Some(changes) = self.previous_field_types.iter().map(|x| get_alignment(x, field_types).0).min() {
+            penalty += changes * PENALTY_PER_CHANGE;
+        }
+
+        penalty
+    }
+
+    /// This function returns the field types to try at the provided position, with the one the newest previous definition has there first.
+    fn get_field_types_by_priority(&self, position: usize) -> Vec<FieldType> {
+        let mut field_types = GUESSABLE_FIELD_TYPES.to_vec();
+        if let Some(field_type) = self.previous_field_types.first().and_then(|x| x.get(position)) {
+            if let Some(index) = field_types.iter().position(|x| x == field_type) {
+                let field_type = field_types.remove(index);
+                field_types.insert(0, field_type);
+            }
+        }
+        field_types
+    }
+
+    /// This function turns a list of field types into a `Definition`, reusing the fields of the closest previous definition where possible.
+    fn get_definition(&self, field_types: &[FieldType], version: i32) -> Definition {
+        let closest = self.previous_definitions.iter()
+            .zip(self.previous_field_types.iter())
+            .map(|(definition, previous_field_types)| (definition, get_alignment(previous_field_types, field_types)))
+            .min_by_key(|(_, (changes, _))| *changes);
+
+        let mut definition = Definition::new(version);
+        for (index, field_type) in field_types.iter().enumerate() {
+            let previous_field = closest.as_ref().and_then(|(previous_definition, (_, alignment))| {
+                alignment[index].map(|x| previous_definition.get_ref_fields()[x].clone())
+            });
+
+            let field = match previous_field {
+                Some(field) => field,
+                None => {
+                    let mut field = Field::default();
+                    field.set_name(&format!("unknown_{}", index + 1));
+                    field.set_field_type(field_type.clone());
+                    field
+                }
+            };
+            definition.get_ref_mut_fields().push(field);
+        }
+        definition
+    }
+}
+
+/// Implementation of `ColumnStats`.
+impl ColumnStats {
+
+    /// This function creates the stats of a column before decoding any of its cells.
+    fn new() -> Self {
+        Self {
+            has_data: false,
+            float_like: true,
+        }
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                              Functions
+//---------------------------------------------------------------------------//
+
+/// This function tries to guess the `Definition` of a table from its data, returning the candidates found, best first.
+///
+/// `index` must point to the first byte after the header of the table, and `previous_definitions` should be the definitions
+/// of the previous versions of the table, newest first. If none of the candidates work, the returned list is empty.
+pub fn guess_definitions(
+    data: &[u8],
+    index: usize,
+    entry_count: u32,
+    version: i32,
+    previous_definitions: &[Definition],
+) -> Vec<GuessedDefinition> {
+
+    // Without rows we have nothing to test the candidates against.
+    if entry_count == 0 || index >= data.len() { return vec![] }
+
+    let mut guesser = Guesser {
+        data,
+        start: index,
+        entry_count,
+        previous_definitions,
+        previous_field_types: previous_definitions.iter().map(|x| x.get_ref_fields().iter().map(|x| x.get_field_type()).collect()).collect(),
+        candidates: vec![],
+        steps: 0,
+    };
+
+    guesser.search_from_previous_definitions();
+    guesser.search_bruteforce(&mut vec![], index, 0);
+
+    // Stable sort, so on ties the candidates found first (the ones closer to the previous definitions) win.
+    let mut candidates = guesser.candidates.to_vec();
+    candidates.sort_by_key(|(_, penalty)| *penalty);
+    candidates.iter()
+        .take(MAX_CANDIDATES)
+        .map(|(field_types, penalty)| GuessedDefinition {
+            definition: guesser.get_definition(field_types, version),
+            penalty: *penalty,
+        })
+        .collect()
+}
+
+/// This function tries to read a plausible value of the provided type, returning the index where the next field starts if it succeeds.
+///
+/// Unlike the normal decoding functions, this rejects values that are technically valid but unlikely to be found in a table,
+/// like strings with control characters or floats so small they're probably integers.
+fn read_field(data: &[u8], index: usize, field_type: &FieldType, stats: &mut ColumnStats) -> Option<usize> {
+    match field_type {
+        FieldType::Boolean => {
+            let value = data.decode_bool(index).ok()?;
+            stats.has_data |= value;
+            Some(index + 1)
+        }
+        FieldType::F32 => {
+            let value = data.decode_float_f32(index).ok()?;
+            if value != 0.0 && !is_plausible_float(value) { return None }
+            stats.has_data |= value != 0.0;
+            Some(index + 4)
+        }
+        FieldType::I16 => {
+            stats.has_data |= data.decode_integer_i16(index).ok()? != 0;
+            Some(index + 2)
+        }
+        FieldType::I32 => {
+            let value = data.decode_integer_i32(index).ok()?;
+            if value != 0 {
+                stats.has_data = true;
+                stats.float_like &= is_plausible_float(f32::from_bits(value as u32));
+            }
+            Some(index + 4)
+        }
+        FieldType::I64 => {
+            stats.has_data |= data.decode_integer_i64(index).ok()? != 0;
+            Some(index + 8)
+        }
+        FieldType::StringU8 => read_string_u8(data, index, stats),
+        FieldType::StringU16 => read_string_u16(data, index, stats),
+        FieldType::OptionalStringU8 => if data.decode_bool(index).ok()? { read_string_u8(data, index + 1, stats) } else { Some(index + 1) },
+        FieldType::OptionalStringU16 => if data.decode_bool(index).ok()? { read_string_u16(data, index + 1, stats) } else { Some(index + 1) },
+        FieldType::SequenceU16(definition) => {
+            let entry_count = data.decode_integer_u16(index).ok()?;
+            read_sequence(data, index + 2, entry_count.into(), definition, stats)
+        }
+        FieldType::SequenceU32(definition) => {
+            let entry_count = data.decode_integer_u32(index).ok()?;
+            read_sequence(data, index + 4, entry_count, definition, stats)
+        }
+    }
+}
+
+/// This function tries to read a plausible UTF-8 String, returning the index where the next field starts if it succeeds.
+fn read_string_u8(data: &[u8], index: usize, stats: &mut ColumnStats) -> Option<usize> {
+    let size = data.decode_integer_u16(index).ok()? as usize;
+    let string = std::str::from_utf8(data.get_bytes_checked(index + 2, size).ok()?).ok()?;
+    if !string.chars().all(is_plausible_char) { return None }
+    stats.has_data |= size > 0;
+    Some(index + 2 + size)
+}
+
+/// This function tries to read a plausible UTF-16 String, returning the index where the next field starts if it succeeds.
+fn read_string_u16(data: &[u8], index: usize, stats: &mut ColumnStats) -> Option<usize> {
+    let size = data.decode_integer_u16(index).ok()? as usize * 2;
+    let bytes = data.get_bytes_checked(index + 2, size).ok()?;
+    let characters = bytes.chunks_exact(2).map(|x| u16::from_le_bytes([x[0], x[1]]));
+    if !decode_utf16(characters).all(|x| matches!(x, Ok(character) if is_plausible_char(character))) { return None }
+    stats.has_data |= size > 0;
+    Some(index + 2 + size)
+}
+
+/// This function tries to read all the entries of a sequence, returning the index where the next field starts if it succeeds.
+fn read_sequence(data: &[u8], mut index: usize, entry_count: u32, definition: &Definition, stats: &mut ColumnStats) -> Option<usize> {
+    stats.has_data |= entry_count > 0;
+    let mut sequence_stats = ColumnStats::new();
+    for _ in 0..entry_count {
+        for field in definition.get_ref_fields() {
+            index = read_field(data, index, field.get_ref_field_type(), &mut sequence_stats)?;
+        }
+    }
+    Some(index)
+}
+
+/// This function checks if a float is likely to be a real float, and not an integer or garbage data.
+fn is_plausible_float(value: f32) -> bool {
+    value.is_normal() && value.abs() >= 1e-6 && value.abs() <= 1e9
+}
+
+/// This function checks if a character is likely to be part of a String in a table.
+fn is_plausible_char(character: char) -> bool {
+    !character.is_control() || character == '\n' || character == '\r' || character == '\t'
+}
+
+/// This function returns the minimum amount of bytes a field of the provided type can take.
+fn get_min_size(field_type: &FieldType) -> usize {
+    match field_type {
+        FieldType::Boolean | FieldType::OptionalStringU8 | FieldType::OptionalStringU16 => 1,
+        FieldType::I16 | FieldType::StringU8 | FieldType::StringU16 | FieldType::SequenceU16(_) => 2,
+        FieldType::F32 | FieldType::I32 | FieldType::SequenceU32(_) => 4,
+        FieldType::I64 => 8,
+    }
+}
+
+/// This function returns the maximum amount of bytes a field of the provided type can take, or `usize::MAX` if it has no fixed size.
+fn get_max_size(field_type: &FieldType) -> usize {
+    match field_type {
+        FieldType::Boolean => 1,
+        FieldType::I16 => 2,
+        FieldType::F32 | FieldType::I32 => 4,
+        FieldType::I64 => 8,
+        _ => usize::MAX,
+    }
+}
+
+/// This function returns the amount of changes needed to turn the old field types into the new ones,
+/// and the position of the old field each new field matches to, if any.
+fn get_alignment(old: &[FieldType], new: &[FieldType]) -> (u32, Vec<Option<usize>>) {
+    let mut changes = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for (index, row) in changes.iter_mut().enumerate() { row[0] = index as u32; }
+    for (index, cell) in changes[0].iter_mut().enumerate() { *cell = index as u32; }
+
+    for (old_index, old_field_type) in old.iter().enumerate() {
+        for (new_index, new_field_type) in new.iter().enumerate() {
+            let replace_cost = if old_field_type == new_field_type { 0 } else { 1 };
+            changes[old_index + 1][new_index + 1] = (changes[old_index][new_index] + replace_cost)
+                .min(changes[old_index][new_index + 1] + 1)
+                .min(changes[old_index + 1][new_index] + 1);
+        }
+    }
+
+    // Walk the table back to find which fields were kept.
+    let mut alignment = vec![None; new.len()];
+    let (mut old_index, mut new_index) = (old.len(), new.len());
+    while old_index > 0 && new_index > 0 {
+        if old[old_index - 1] == new[new_index - 1] && changes[old_index][new_index] == changes[old_index - 1][new_index - 1] {
+            alignment[new_index - 1] = Some(old_index - 1);
+            old_index -= 1;
+            new_index -= 1;
+        }
+        else if changes[old_index][new_index] == changes[old_index - 1][new_index - 1] + 1 {
+            old_index -= 1;
+            new_index -= 1;
+        }
+        else if changes[old_index][new_index] == changes[old_index - 1][new_index] + 1 { old_index -= 1; }
+        else { new_index -= 1; }
+    }
+
+    (changes[old.len()][new.len()], alignment)
+}
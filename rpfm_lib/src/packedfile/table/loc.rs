@@ -15,12 +15,17 @@ Loc Tables are the files which contain all the localisation strings used by the
 They're just tables with a key, a text, and a boolean column.
 !*/
 
+use serde_derive::{Serialize, Deserialize};
+
 use std::path::PathBuf;
 
 use rpfm_error::{ErrorKind, Result};
 
 use crate::common::{decoder::Decoder, encoder::Encoder};
 use super::DecodedData;
+use super::MergeConflict;
+use super::migration::TableMigration;
+use super::port::TablePort;
 use super::Table;
 
 use crate::schema::*;
@@ -45,7 +50,7 @@ pub const EXTENSION: &str = ".loc";
 //---------------------------------------------------------------------------//
 
 /// This stores the data of a decoded Localisation PackedFile in memory.
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct Loc {
 
     /// The table's data, containing all the stuff needed to decode/encode it.
@@ -113,6 +118,32 @@ impl Loc {
         self.table.set_table_data(data)
     }
 
+    /// This function ports this table to the provided Loc definition of another game, returning a report of the changes done.
+    ///
+    /// Fields are only matched by name. Loc Tables have no references, so none are reported.
+    pub fn port(&mut self, new_definition: &Definition) -> TablePort {
+        TablePort {
+            migration: self.table.port(new_definition),
+            unresolved_references: vec![],
+        }
+    }
+
+    /// This function removes the duplicated keys of a merge base built from vanilla data, keeping the row of the last vanilla table.
+    pub fn dedup_merge_base(&mut self) {
+        self.table.dedup_merge_base();
+    }
+
+    /// This function removes from a merge base built from vanilla data the rows whose keys are not in both of the provided Loc Tables.
+    pub fn retain_merge_base(&mut self, ours: &Self, theirs: &Self) {
+        self.table.retain_merge_base(&ours.table, &theirs.table);
+    }
+
+    /// This function performs a three-way merge of the provided Loc Tables, returning the merged table and the conflicts found.
+    pub fn merge(base: &Self, ours: &Self, theirs: &Self) -> (Self, Vec<MergeConflict>) {
+        let (table, conflicts) = Table::merge(&base.table, &ours.table, &theirs.table);
+        (Self { table }, conflicts)
+    }
+
     /// This function creates a new `Loc` from a `Vec<u8>`.
     pub fn read(packed_file_data: &[u8], schema: &Schema, return_incomplete: bool) -> Result<Self> {
 
@@ -203,13 +234,16 @@ impl Loc {
     }
 
     /// This function imports a TSV file into a decoded table.
+    ///
+    /// TSV files from other versions of the table are migrated to the provided definition, using `history` to decode them.
     pub fn import_tsv(
         definition: &Definition,
+        history: &[Definition],
         path: &PathBuf,
         name: &str,
-    ) -> Result<Self> {
-        let table = Table::import_tsv(definition, path, name)?;
-        Ok(Loc::from(table))
+    ) -> Result<(Self, Option<TableMigration>)> {
+        let (table, migration) = Table::import_tsv(definition, history, path, name)?;
+        Ok((Loc::from(table), migration))
     }
 
     /// This function exports the provided data to a TSV file.
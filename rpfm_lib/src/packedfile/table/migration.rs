@@ -0,0 +1,270 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with the code to migrate tables between versions of their `Definition`.
+
+Fields are matched between versions by name. If a field is not found by name, we try to find if it has been renamed:
+- If the description of a new field mentions the name of an old field, we consider it a rename.
+- If, between two consecutive versions of the table, a field disappears and another one of the same type appears in
+  the same position, we consider it a rename. This is followed through all the versions in the history of the table.
+
+New fields get their default value, and fields whose type changed get their data converted. Everything that
+cannot be migrated cleanly is reported in a `TableMigration`.
+!*/
+
+use serde_derive::{Serialize, Deserialize};
+
+use std::fmt;
+
+use crate::schema::{Definition, Field};
+use super::{DecodedData, Table};
+
+//---------------------------------------------------------------------------//
+//                              Enum & Structs
+//---------------------------------------------------------------------------//
+
+/// This struct contains the report of a table migrated from one version of its `Definition` to another.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TableMigration {
+
+    /// Version of the table before the migration.
+    pub old_version: i32,
+
+    /// Version of the table after the migration.
+    pub new_version: i32,
+
+    /// Fields we think have been renamed, as `(old_name, new_name)`. Their data is kept.
+    pub renamed_fields: Vec<(String, String)>,
+
+    /// Fields only in the new version. They're filled with their default value.
+    pub added_fields: Vec<String>,
+
+    /// Fields only in the old version. Their data is lost.
+    pub removed_fields: Vec<String>,
+
+    /// Fields whose type changed, with the rows that failed to convert.
+    pub converted_fields: Vec<FieldConversion>,
+}
+
+/// This struct represents a field whose data had to be converted to a new type.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldConversion {
+
+    /// Name of the field, in the new version.
+    pub field: String,
+
+    /// Type of the field in the old version.
+    pub old_type: String,
+
+    /// Type of the field in the new version.
+    pub new_type: String,
+
+    /// Rows (starting at 1) whose data couldn't be converted, and got the default value instead.
+    pub failed_rows: Vec<u64>,
+}
+
+//---------------------------------------------------------------------------//
+//                              Implementations
+//---------------------------------------------------------------------------//
+
+/// Implementation of `Table`.
+impl Table {
+
+    /// This function migrates the table to the provided `Definition`, returning a report of the changes done.
+    ///
+    /// `history` should contain the definitions of the table in the schema, used to find renamed fields. It can be empty.
+    pub fn migrate(&mut self, new_definition: &Definition, history: &[Definition]) -> TableMigration {
+        let (mapping, renamed_fields) = get_field_mapping(&self.definition, new_definition, history);
+        self.migrate_with_mapping(new_definition, &mapping, renamed_fields)
+    }
+
+    /// This function migrates the table to the provided `Definition` using an already calculated field mapping, returning a report of the changes done.
+    ///
+    /// `mapping` must contain, for each processed field of the new definition, the index of the processed field of the current one it comes from.
+    pub(crate) fn migrate_with_mapping(&mut self, new_definition: &Definition, mapping: &[Option<usize>], renamed_fields: Vec<(String, String)>) -> TableMigration {
+        let old_fields = self.definition.get_fields_processed();
+        let new_fields = new_definition.get_fields_processed();
+
+        let mut migration = TableMigration {
+            old_version: self.definition.get_version(),
+            new_version: new_definition.get_version(),
+            renamed_fields,
+            ..TableMigration::default()
+        };
+
+        migration.added_fields = new_fields.iter().zip(mapping)
+            .filter(|(_, old_index)| old_index.is_none())
+            .map(|(field, _)| field.get_name().to_owned())
+            .collect();
+
+        migration.removed_fields = old_fields.iter().enumerate()
+            .filter(|(index, _)| !mapping.contains(&Some(*index)))
+            .map(|(_, field)| field.get_name().to_owned())
+            .collect();
+
+        let mut conversions = new_fields.iter().zip(mapping).map(|(new_field, old_index)| match old_index {
+            Some(old_index) if old_fields[*old_index].get_ref_field_type() != new_field.get_ref_field_type() => Some(FieldConversion {
+                field: new_field.get_name().to_owned(),
+                old_type: old_fields[*old_index].get_ref_field_type().to_string(),
+                new_type: new_field.get_ref_field_type().to_string(),
+                failed_rows: vec![],
+            }),
+            _ => None,
+        }).collect::<Vec<Option<FieldConversion>>>();
+
+        let default_row = new_fields.iter().map(get_default_value).collect::<Vec<DecodedData>>();
+        let mut new_entries = Vec::with_capacity(self.entries.len());
+        for (row_index, row) in self.entries.iter().enumerate() {
+            let mut new_row = Vec::with_capacity(new_fields.len());
+            for (new_index, new_field) in new_fields.iter().enumerate() {
+                let cell = match (mapping[new_index], &mut conversions[new_index]) {
+                    (Some(old_index), None) => row[old_index].clone(),
+                    (Some(old_index), Some(conversion)) => match row[old_index].convert_between_types(new_field.get_ref_field_type()) {
+                        Ok(cell) => cell,
+                        Err(_) => {
+                            conversion.failed_rows.push(row_index as u64 + 1);
+                            default_row[new_index].clone()
+                        }
+                    },
+                    (None, _) => default_row[new_index].clone(),
+                };
+                new_row.push(cell);
+            }
+            new_entries.push(new_row);
+        }
+
+        migration.converted_fields = conversions.into_iter().flatten().collect();
+        self.definition = new_definition.clone();
+        self.entries = new_entries;
+        migration
+    }
+}
+
+/// Implementation of `TableMigration`.
+impl TableMigration {
+
+    /// This function returns if the migration lost or changed any data, so it needs to be checked manually.
+    pub fn needs_review(&self) -> bool {
+        !self.renamed_fields.is_empty() || !self.removed_fields.is_empty() || !self.converted_fields.is_empty()
+    }
+}
+
+/// Display implementation of `TableMigration`.
+impl fmt::Display for TableMigration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Version {} => {}", self.old_version, self.new_version)?;
+        for (old_name, new_name) in &self.renamed_fields {
+            writeln!(f, "    Renamed: {} => {}", old_name, new_name)?;
+        }
+        for field in &self.added_fields {
+            writeln!(f, "    Added: {}", field)?;
+        }
+        for field in &self.removed_fields {
+            writeln!(f, "    Removed: {}", field)?;
+        }
+        for conversion in &self.converted_fields {
+            write!(f, "    Converted: {} ({} => {})", conversion.field, conversion.old_type, conversion.new_type)?;
+            if conversion.failed_rows.is_empty() { writeln!(f)?; }
+            else { writeln!(f, ", failed on rows: {}", conversion.failed_rows.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", "))?; }
+        }
+        Ok(())
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                              Functions
+//---------------------------------------------------------------------------//
+
+/// This function returns, for each processed field of the new definition, the index of the processed field of the old one it comes from,
+/// along with the renames found.
+fn get_field_mapping(old_definition: &Definition, new_definition: &Definition, history: &[Definition]) -> (Vec<Option<usize>>, Vec<(String, String)>) {
+    let old_fields = old_definition.get_fields_processed();
+    let new_fields = new_definition.get_fields_processed();
+
+    // First, match the fields by name.
+    let mut mapping = get_field_mapping_by_name(old_definition, new_definition);
+
+    // Then, check if any of the new fields mentions one of the missing old fields in its description.
+    let mut renamed_fields = vec![];
+    for (new_index, new_field) in new_fields.iter().enumerate() {
+        if mapping[new_index].is_some() { continue; }
+
+        let words = new_field.get_description()
+            .split(|x: char| !x.is_alphanumeric() && x != '_')
+            .map(|x| x.to_lowercase())
+            .collect::<Vec<String>>();
+
+        if let Some(old_index) = (0..old_fields.len()).find(|x| !mapping.contains(&Some(*x)) && words.contains(&old_fields[*x].get_name().to_lowercase())) {
+            mapping[new_index] = Some(old_index);
+            renamed_fields.push((old_fields[old_index].get_name().to_owned(), new_field.get_name().to_owned()));
+        }
+    }
+
+    // Then, follow the rest of the missing old fields through the history of the table, version by version.
+    let mut versions = vec![old_definition];
+    let mut intermediate_versions = history.iter()
+        .filter(|x| x.get_version() > old_definition.get_version() && x.get_version() < new_definition.get_version())
+        .collect::<Vec<&Definition>>();
+    intermediate_versions.sort_by_key(|x| x.get_version());
+    versions.append(&mut intermediate_versions);
+    versions.push(new_definition);
+
+    for (old_index, old_field) in old_fields.iter().enumerate() {
+        if mapping.contains(&Some(old_index)) { continue; }
+
+        let mut name = Some(old_field.get_name().to_owned());
+        for step in versions.windows(2) {
+            name = name.and_then(|name| get_name_in_next_version(&name, step[0], step[1]));
+        }
+
+        if let Some(name) = name {
+            if let Some(new_index) = new_fields.iter().position(|x| x.get_name() == name) {
+                if mapping[new_index].is_none() {
+                    mapping[new_index] = Some(old_index);
+                    renamed_fields.push((old_field.get_name().to_owned(), name));
+                }
+            }
+        }
+    }
+
+    (mapping, renamed_fields)
+}
+
+/// This function returns, for each processed field of the new definition, the index of the processed field of the old one with the same name.
+pub(crate) fn get_field_mapping_by_name(old_definition: &Definition, new_definition: &Definition) -> Vec<Option<usize>> {
+    let old_fields = old_definition.get_fields_processed();
+    new_definition.get_fields_processed().iter()
+        .map(|new_field| old_fields.iter().position(|old_field| old_field.get_name() == new_field.get_name()))
+        .collect()
+}
+
+/// This function returns the name a field has in the next version of a definition, or `None` if the field has been removed.
+///
+/// If there is no field with the same name in the next version, but there is a new field of the same type in the same position, we assume it has been renamed.
+fn get_name_in_next_version(name: &str, definition: &Definition, next_definition: &Definition) -> Option<String> {
+    let fields = definition.get_fields_processed();
+    let next_fields = next_definition.get_fields_processed();
+    if next_fields.iter().any(|x| x.get_name() == name) { return Some(name.to_owned()) }
+
+    let index = fields.iter().position(|x| x.get_name() == name)?;
+    let field = &fields[index];
+    let next_field = next_fields.get(index)?;
+    if next_field.get_ref_field_type() == field.get_ref_field_type() && !fields.iter().any(|x| x.get_name() == next_field.get_name()) {
+        Some(next_field.get_name().to_owned())
+    } else { None }
+}
+
+/// This function returns the default value of a field, taking into account the default value set in the schema, if any.
+fn get_default_value(field: &Field) -> DecodedData {
+    field.get_default_value().as_ref()
+        .and_then(|x| DecodedData::StringU8(x.to_owned()).convert_between_types(field.get_ref_field_type()).ok())
+        .unwrap_or_else(|| DecodedData::default(field.get_ref_field_type()))
+}
@@ -19,6 +19,7 @@ use csv::{QuoteStyle, ReaderBuilder, WriterBuilder};
 use serde_derive::{Serialize, Deserialize};
 
 use std::{fmt, fmt::Display};
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::PathBuf;
@@ -27,13 +28,20 @@ use rpfm_error::{Error, ErrorKind, Result};
 
 use crate::assembly_kit::table_data::RawTable;
 use crate::common::{decoder::Decoder, encoder::Encoder, parse_str_as_bool};
+use crate::packedfile::table::migration::TableMigration;
 use crate::schema::*;
 
 pub mod animtable;
 pub mod anim_fragment;
 pub mod db;
+pub mod guesser;
 pub mod loc;
 pub mod matched_combat;
+pub mod migration;
+pub mod port;
+
+#[cfg(test)]
+mod table_test;
 
 //---------------------------------------------------------------------------//
 //                              Enum & Structs
@@ -71,6 +79,28 @@ pub enum DecodedData {
     SequenceU32(Table)
 }
 
+/// This enum represents a conflict found while merging three versions of the same table.
+///
+/// Rows are identified by the values of their key fields, as strings.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MergeConflict {
+
+    /// Both sides changed the same cell to different values. The value from `ours` is the one kept in the merged table.
+    Cell {
+        key: Vec<String>,
+        field: String,
+        base: Option<String>,
+        ours: String,
+        theirs: String,
+    },
+
+    /// One side removed a row the other side modified. The modified row is the one kept in the merged table.
+    Row {
+        key: Vec<String>,
+        removed_in_ours: bool,
+    },
+}
+
 //----------------------------------------------------------------//
 // Implementations for `DecodedData`.
 //----------------------------------------------------------------//
@@ -390,6 +420,158 @@ impl Table {
         Ok(())
     }
 
+    /// This function performs a three-way merge of the provided tables, returning the merged table and the conflicts found.
+    ///
+    /// Rows are matched using the key fields of the definition. If there are no key fields, whole rows are used as keys.
+    /// Changes done only in one side are applied. Changes done in both sides are applied if they're the same, or reported as conflicts if not.
+    /// All tables are updated to the newest definition between them before merging.
+    pub fn merge(base: &Self, ours: &Self, theirs: &Self) -> (Self, Vec<MergeConflict>) {
+        let definition = [base, theirs, ours].iter().map(|x| &x.definition).max_by_key(|x| x.get_version()).unwrap().clone();
+        let mut tables = [base.clone(), ours.clone(), theirs.clone()];
+        for table in &mut tables {
+            if table.definition != definition {
+                table.set_definition(&definition);
+            }
+        }
+        let [base, ours, theirs] = tables;
+
+        let fields = definition.get_fields_processed();
+        let mut key_columns = fields.iter().enumerate().filter(|(_, x)| x.get_is_key()).map(|(x, _)| x).collect::<Vec<usize>>();
+        if key_columns.is_empty() {
+            key_columns = (0..fields.len()).collect();
+        }
+
+        let base_keys = Self::get_merge_keys(&base.entries, &key_columns);
+        let ours_keys = Self::get_merge_keys(&ours.entries, &key_columns);
+        let theirs_keys = Self::get_merge_keys(&theirs.entries, &key_columns);
+        let ours_rows = ours_keys.iter().enumerate().map(|(x, y)| (y, x)).collect::<HashMap<&(Vec<String>, usize), usize>>();
+        let theirs_rows = theirs_keys.iter().enumerate().map(|(x, y)| (y, x)).collect::<HashMap<&(Vec<String>, usize), usize>>();
+        let base_rows = base_keys.iter().enumerate().map(|(x, y)| (y, x)).collect::<HashMap<&(Vec<String>, usize), usize>>();
+
+        // Rows are kept in the order of the base table. New rows go after them, first the ones from ours, then the ones from theirs.
+        let mut keys = base_keys.iter().collect::<Vec<&(Vec<String>, usize)>>();
+        keys.extend(ours_keys.iter().filter(|x| !base_rows.contains_key(x)));
+        keys.extend(theirs_keys.iter().filter(|x| !base_rows.contains_key(x) && !ours_rows.contains_key(x)));
+
+        let mut merged = Self::new(&definition);
+        let mut conflicts = vec![];
+        for key in keys {
+            let base_row = base_rows.get(key).map(|x| &base.entries[*x]);
+            let ours_row = ours_rows.get(key).map(|x| &ours.entries[*x]);
+            let theirs_row = theirs_rows.get(key).map(|x| &theirs.entries[*x]);
+
+            match (base_row, ours_row, theirs_row) {
+                (base_row, Some(ours_row), Some(theirs_row)) => {
+                    let mut row = Vec::with_capacity(ours_row.len());
+                    for (column, field) in fields.iter().enumerate() {
+                        let base_cell = base_row.map(|x| &x[column]);
+                        let ours_cell = &ours_row[column];
+                        let theirs_cell = &theirs_row[column];
+
+                        if ours_cell == theirs_cell || base_cell == Some(theirs_cell) { row.push(ours_cell.clone()); }
+                        else if base_cell == Some(ours_cell) { row.push(theirs_cell.clone()); }
+                        else {
+                            conflicts.push(MergeConflict::Cell {
+                                key: key.0.to_vec(),
+                                field: field.get_name().to_owned(),
+                                base: base_cell.map(|x| x.data_to_string()),
+                                ours: ours_cell.data_to_string(),
+                                theirs: theirs_cell.data_to_string(),
+                            });
+                            row.push(ours_cell.clone());
+                        }
+                    }
+                    merged.entries.push(row);
+                }
+
+                // Rows removed in one side are only removed if the other side didn't touch them.
+                (Some(base_row), Some(row), None) |
+                (Some(base_row), None, Some(row)) => {
+                    if row != base_row {
+                        conflicts.push(MergeConflict::Row {
+                            key: key.0.to_vec(),
+                            removed_in_ours: ours_row.is_none(),
+                        });
+                        merged.entries.push(row.to_vec());
+                    }
+                }
+
+                (None, Some(row), None) |
+                (None, None, Some(row)) => merged.entries.push(row.to_vec()),
+                (_, None, None) => {},
+            }
+        }
+
+        (merged, conflicts)
+    }
+
+    /// This function removes the duplicated keys of a merge base built from vanilla data, keeping the row of the last vanilla table.
+    ///
+    /// The row is kept in the position of the first one with the same key. If there are no key fields, whole rows are used as keys.
+    pub fn dedup_merge_base(&mut self) {
+        let fields = self.definition.get_fields_processed();
+        let mut key_columns = fields.iter().enumerate().filter(|(_, x)| x.get_is_key()).map(|(x, _)| x).collect::<Vec<usize>>();
+        if key_columns.is_empty() {
+            key_columns = (0..fields.len()).collect();
+        }
+
+        let mut positions: HashMap<Vec<String>, usize> = HashMap::new();
+        let mut entries: Vec<Vec<DecodedData>> = vec![];
+        for row in self.entries.drain(..) {
+            let key = key_columns.iter().map(|x| row[*x].data_to_string()).collect::<Vec<String>>();
+            match positions.get(&key) {
+                Some(position) => entries[*position] = row,
+                None => {
+                    positions.insert(key, entries.len());
+                    entries.push(row);
+                }
+            }
+        }
+
+        self.entries = entries;
+    }
+
+    /// This function removes from a merge base built from vanilla data the rows whose keys are not in both of the provided tables.
+    ///
+    /// Mod tables only contain the rows they change, so a vanilla row missing in one of them means that side didn't touch it, not that it removed it.
+    /// Fields are matched by name, so the tables can use different definitions.
+    pub fn retain_merge_base(&mut self, ours: &Self, theirs: &Self) {
+        let fields = self.definition.get_fields_processed();
+        let mut key_fields = fields.iter().filter(|x| x.get_is_key()).map(|x| x.get_name()).collect::<Vec<&str>>();
+        if key_fields.is_empty() {
+            key_fields = fields.iter().map(|x| x.get_name()).collect();
+        }
+
+        let ours_keys = Self::get_merge_keys_by_name(ours, &key_fields).into_iter().collect::<HashSet<Vec<String>>>();
+        let theirs_keys = Self::get_merge_keys_by_name(theirs, &key_fields).into_iter().collect::<HashSet<Vec<String>>>();
+        let mut keep = Self::get_merge_keys_by_name(self, &key_fields).into_iter()
+            .map(|key| ours_keys.contains(&key) && theirs_keys.contains(&key));
+
+        self.entries.retain(|_| keep.next().unwrap_or(false));
+    }
+
+    /// This function returns the keys of the rows of a table, using the values of the provided fields.
+    ///
+    /// Fields missing in the definition of the table are keyed as empty values.
+    fn get_merge_keys_by_name(table: &Self, key_fields: &[&str]) -> Vec<Vec<String>> {
+        let fields = table.definition.get_fields_processed();
+        let key_columns = key_fields.iter().map(|x| fields.iter().position(|y| y.get_name() == *x)).collect::<Vec<Option<usize>>>();
+        table.entries.iter().map(|row| key_columns.iter().map(|x| x.map(|x| row[x].data_to_string()).unwrap_or_default()).collect()).collect()
+    }
+
+    /// This function returns the keys used to match the rows of a table while merging.
+    ///
+    /// Each key contains the values of the key columns, and the amount of previous rows with the same values, so duplicated keys are matched in order.
+    fn get_merge_keys(entries: &[Vec<DecodedData>], key_columns: &[usize]) -> Vec<(Vec<String>, usize)> {
+        let mut counts: HashMap<Vec<String>, usize> = HashMap::new();
+        entries.iter().map(|row| {
+            let key = key_columns.iter().map(|x| row[*x].data_to_string()).collect::<Vec<String>>();
+            let count = counts.entry(key.to_vec()).or_default();
+            *count += 1;
+            (key, *count - 1)
+        }).collect()
+    }
+
     /// This function decodes all the fields of a table from raw bytes.
     ///
     /// If return_incomplete == true, this function will return an error with the incompletely decoded table when it fails.
@@ -729,11 +911,15 @@ impl Table {
     //----------------------------------------------------------------//
 
     /// This function imports a TSV file into a decoded table.
+    ///
+    /// If the TSV file is from another version of the table, it's decoded with that version from `history`
+    /// and migrated to the provided definition, returning the report of the migration.
     fn import_tsv(
         definition: &Definition,
+        history: &[Definition],
         path: &PathBuf,
         name: &str,
-    ) -> Result<Self> {
+    ) -> Result<(Self, Option<TableMigration>)> {
 
         // We want the reader to have no quotes, tab as delimiter and custom headers, because otherwise
         // Excel, Libreoffice and all the programs that edit this kind of files break them on save.
@@ -746,16 +932,19 @@ impl Table {
 
         // If we succesfully load the TSV file into a reader, check the first two lines to ensure
         // it's a valid TSV for our specific table.
+        let mut tsv_definition = definition;
         let mut entries = vec![];
         for (row, record) in reader.records().enumerate() {
             if let Ok(record) = record {
 
                 // The first line should contain the "table_folder_name"/"Loc PackedFile/PackFile List", and the version (1 for Locs).
-                // If it doesn't match with the name we provided, return an error.
+                // If it doesn't match with the name we provided, return an error. If the version is not the one we want,
+                // we use its definition from the history of the table, if we have it.
                 if row == 0 {
                     if record.get(0).unwrap_or("error") != name { return Err(ErrorKind::ImportTSVWrongTypeTable.into()); }
-                    if record.get(1).unwrap_or("-1").parse::<i32>().map_err(|_| Error::from(ErrorKind::ImportTSVInvalidVersion))? != definition.get_version() {
-                        return Err(ErrorKind::ImportTSVWrongVersion.into());
+                    let version = record.get(1).unwrap_or("-1").parse::<i32>().map_err(|_| Error::from(ErrorKind::ImportTSVInvalidVersion))?;
+                    if version != definition.get_version() {
+                        tsv_definition = history.iter().find(|x| x.get_version() == version).ok_or(ErrorKind::ImportTSVWrongVersion)?;
                     }
                 }
 
@@ -763,10 +952,10 @@ impl Table {
                 else if row == 1 { continue }
 
                 // Then read the rest of the rows as a normal TSV.
-                else if record.len() == definition.get_fields_processed().len() {
+                else if record.len() == tsv_definition.get_fields_processed().len() {
                     let mut entry = vec![];
                     for (column, field) in record.iter().enumerate() {
-                        match definition.get_fields_processed()[column].get_ref_field_type() {
+                        match tsv_definition.get_fields_processed()[column].get_ref_field_type() {
                             FieldType::Boolean => {
                                 let value = field.to_lowercase();
                                 if value == "true" || value == "1" { entry.push(DecodedData::Boolean(true)); }
@@ -797,17 +986,22 @@ impl Table {
         }
 
         // If we reached this point without errors, we replace the old data with the new one and return success.
-        let mut table = Table::new(definition);
+        let mut table = Table::new(tsv_definition);
         table.entries = entries;
-        Ok(table)
+        if tsv_definition.get_version() != definition.get_version() {
+            let migration = table.migrate(definition, history);
+            Ok((table, Some(migration)))
+        } else { Ok((table, None)) }
     }
 
     /// This function imports a TSV file into a new Table File.
+    ///
+    /// If the TSV file is not from the latest version of the table, it's migrated to it, returning the report of the migration.
     fn import_tsv_to_binary_file(
         schema: &Schema,
         source_path: &PathBuf,
         destination_path: &PathBuf,
-    ) -> Result<()> {
+    ) -> Result<Option<TableMigration>> {
 
         // We want the reader to have no quotes, tab as delimiter and custom headers, because otherwise
         // Excel, Libreoffice and all the programs that edit this kind of files break them on save.
@@ -828,8 +1022,10 @@ impl Table {
         }
 
         // Get his definition depending on his first line's contents.
-        let definition = if table_type == loc::TSV_NAME_LOC { schema.get_ref_versioned_file_loc()?.get_version(table_version)?.clone() }
-        else { schema.get_ref_versioned_file_db(&table_type)?.get_version(table_version)?.clone() };
+        let versioned_file = if table_type == loc::TSV_NAME_LOC { schema.get_ref_versioned_file_loc()? }
+        else { schema.get_ref_versioned_file_db(&table_type)? };
+        let history = versioned_file.get_version_list();
+        let definition = versioned_file.get_version(table_version)?.clone();
 
         // Try to import the entries of the file.
         let mut entries = vec![];
@@ -872,15 +1068,23 @@ impl Table {
             else { return Err(ErrorKind::ImportTSVIncorrectRow(row, 0).into()); }
         }
 
+        // If the TSV is from an old version of the table, migrate it to the latest one.
+        let mut table = Table::new(&definition);
+        table.entries = entries;
+        let migration = match history.iter().max_by_key(|x| x.get_version()) {
+            Some(latest_definition) if latest_definition.get_version() != definition.get_version() => Some(table.migrate(latest_definition, history)),
+            _ => None,
+        };
+
         // If we reached this point without errors, we create the File in memory and add the entries to it.
         let data = if table_type == loc::TSV_NAME_LOC {
-            let mut file = loc::Loc::new(&definition);
-            file.set_table_data(&entries)?;
+            let mut file = loc::Loc::new(&table.definition);
+            file.set_table_data(&table.entries)?;
             file.save()
         }
         else {
-            let mut file = db::DB::new(&table_type, None, &definition);
-            file.set_table_data(&entries)?;
+            let mut file = db::DB::new(&table_type, None, &table.definition);
+            file.set_table_data(&table.entries)?;
             file.save()
         }?;
 
@@ -889,7 +1093,7 @@ impl Table {
         file.write_all(&data)?;
 
         // If all worked, return success.
-        Ok(())
+        Ok(migration)
     }
 
     /// This function exports the provided data to a TSV file.
@@ -1031,3 +1235,17 @@ impl From<&RawTable> for Table {
         }
     }
 }
+
+//----------------------------------------------------------------//
+// Implementations for `MergeConflict`.
+//----------------------------------------------------------------//
+
+/// Display implementation of `MergeConflict`.
+impl Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MergeConflict::Cell { key, field, base, ours, theirs } => write!(f, "Conflict in row {}, field {}: base \"{}\", ours \"{}\", theirs \"{}\".", key.join("|"), field, base.as_deref().unwrap_or_default(), ours, theirs),
+            MergeConflict::Row { key, removed_in_ours } => write!(f, "Conflict in row {}: removed in {}, but modified in {}.", key.join("|"), if *removed_in_ours { "ours" } else { "theirs" }, if *removed_in_ours { "theirs" } else { "ours" }),
+        }
+    }
+}
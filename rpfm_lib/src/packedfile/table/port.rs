@@ -0,0 +1,97 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with the code to port tables from the `Definition` of one game to the `Definition` of the same table in another game.
+
+Unlike migrations, the definitions of different games don't share a history, so fields are only matched by name.
+Fields only in the new game get their default value, and fields only in the old game are dropped.
+Once ported, the references of the table are checked against the dependencies of the new game,
+and the ones that don't resolve are reported in a `TablePort`.
+!*/
+
+use serde_derive::{Serialize, Deserialize};
+
+use std::fmt;
+
+use crate::schema::Definition;
+use super::Table;
+use super::migration::{get_field_mapping_by_name, TableMigration};
+
+//---------------------------------------------------------------------------//
+//                              Enum & Structs
+//---------------------------------------------------------------------------//
+
+/// This struct contains the report of a table ported from the `Definition` of one game to another.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TablePort {
+
+    /// Changes done to the fields of the table. It never contains renamed fields.
+    pub migration: TableMigration,
+
+    /// References of the ported table that don't resolve in the new game.
+    pub unresolved_references: Vec<UnresolvedReference>,
+}
+
+/// This struct represents a cell of a ported table referencing a value that doesn't exist in the new game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnresolvedReference {
+
+    /// Row (starting at 1) of the cell.
+    pub row: u64,
+
+    /// Name of the field of the cell.
+    pub field: String,
+
+    /// Value of the cell.
+    pub value: String,
+
+    /// Table referenced by the field, without the `_tables` suffix.
+    pub reference_table: String,
+
+    /// Column referenced by the field.
+    pub reference_column: String,
+}
+
+//---------------------------------------------------------------------------//
+//                              Implementations
+//---------------------------------------------------------------------------//
+
+/// Implementation of `Table`.
+impl Table {
+
+    /// This function ports the table to the provided `Definition` of another game, returning a report of the changes done.
+    ///
+    /// Fields are only matched by name. If the type of a field changed, its data is converted.
+    pub fn port(&mut self, new_definition: &Definition) -> TableMigration {
+        let mapping = get_field_mapping_by_name(&self.definition, new_definition);
+        self.migrate_with_mapping(new_definition, &mapping, vec![])
+    }
+}
+
+/// Implementation of `TablePort`.
+impl TablePort {
+
+    /// This function returns if the port lost or changed any data, or left references unresolved, so it needs to be checked manually.
+    pub fn needs_review(&self) -> bool {
+        self.migration.needs_review() || !self.unresolved_references.is_empty()
+    }
+}
+
+/// Display implementation of `TablePort`.
+impl fmt::Display for TablePort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.migration)?;
+        for reference in &self.unresolved_references {
+            writeln!(f, "    Unresolved reference: row {}, {} = \"{}\" ({}/{})", reference.row, reference.field, reference.value, reference.reference_table, reference.reference_column)?;
+        }
+        Ok(())
+    }
+}
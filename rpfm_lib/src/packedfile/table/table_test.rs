@@ -0,0 +1,422 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `Table` module, to make sure table merges, migrations and ports work as expected.
+!*/
+
+use std::collections::BTreeMap;
+
+use crate::context::GameContext;
+use crate::dependencies::Dependencies;
+use crate::games::{KEY_EMPIRE, KEY_WARHAMMER_2};
+use crate::packedfile::DecodedPackedFile;
+use crate::packfile::{PackFile, PFHVersion};
+use crate::packfile::packedfile::PackedFile;
+use crate::settings::Settings;
+use super::*;
+use super::db::DB;
+use super::loc::Loc;
+
+/// This function builds a definition with a string key field and the provided integer fields.
+fn build_definition(version: i32, fields: &[&str]) -> Definition {
+    let mut definition = Definition::new(version);
+    definition.get_ref_mut_fields().push(Field::new("key".to_owned(), FieldType::StringU8, true, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()));
+    for field in fields {
+        definition.get_ref_mut_fields().push(Field::new(field.to_string(), FieldType::I32, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()));
+    }
+    definition
+}
+
+/// This function builds a table from rows of a key and integer values.
+fn build_table(definition: &Definition, rows: &[(&str, &[i32])]) -> Table {
+    let mut table = Table::new(definition);
+    let entries = rows.iter().map(|(key, values)| {
+        let mut row = vec![DecodedData::StringU8(key.to_string())];
+        row.extend(values.iter().map(|x| DecodedData::I32(*x)));
+        row
+    }).collect::<Vec<Vec<DecodedData>>>();
+    table.set_table_data(&entries).unwrap();
+    table
+}
+
+#[test]
+fn test_merge_tables() {
+    let definition = build_definition(1, &["a", "b"]);
+    let base = build_table(&definition, &[("one", &[1, 1]), ("two", &[2, 2]), ("three", &[3, 3]), ("four", &[4, 4]), ("five", &[5, 5])]);
+    let ours = build_table(&definition, &[("one", &[10, 1]), ("two", &[2, 2]), ("three", &[30, 3]), ("five", &[5, 50]), ("ours", &[0, 0])]);
+    let theirs = build_table(&definition, &[("one", &[1, 10]), ("three", &[31, 3]), ("four", &[4, 4]), ("theirs", &[0, 0])]);
+
+    let (merged, conflicts) = Table::merge(&base, &ours, &theirs);
+    let expected = build_table(&definition, &[("one", &[10, 10]), ("three", &[30, 3]), ("five", &[5, 50]), ("ours", &[0, 0]), ("theirs", &[0, 0])]);
+    assert_eq!(merged, expected);
+    assert_eq!(conflicts, vec![
+        MergeConflict::Cell {
+            key: vec!["three".to_owned()],
+            field: "a".to_owned(),
+            base: Some("3".to_owned()),
+            ours: "30".to_owned(),
+            theirs: "31".to_owned(),
+        },
+        MergeConflict::Row {
+            key: vec!["five".to_owned()],
+            removed_in_ours: false,
+        },
+    ]);
+}
+
+#[test]
+fn test_merge_tables_different_versions() {
+    let old_definition = build_definition(1, &["a"]);
+    let new_definition = build_definition(2, &["a", "b"]);
+    let base = build_table(&old_definition, &[("one", &[1])]);
+    let ours = build_table(&old_definition, &[("one", &[2])]);
+    let theirs = build_table(&new_definition, &[("one", &[1, 5])]);
+
+    let (merged, conflicts) = Table::merge(&base, &ours, &theirs);
+    assert_eq!(merged, build_table(&new_definition, &[("one", &[2, 5])]));
+    assert!(conflicts.is_empty());
+}
+
+#[test]
+fn test_merge_tables_vanilla_base() {
+    let definition = build_definition(1, &["a", "b"]);
+    let mut base = build_table(&definition, &[("one", &[1, 1]), ("two", &[2, 2]), ("three", &[3, 3]), ("four", &[4, 4])]);
+    let ours = build_table(&definition, &[("one", &[10, 1]), ("two", &[20, 2]), ("ours", &[0, 0])]);
+    let theirs = build_table(&definition, &[("one", &[1, 10]), ("three", &[30, 3])]);
+
+    // Vanilla rows only changed by one side must not be treated as removed by the other one.
+    base.retain_merge_base(&ours, &theirs);
+    assert_eq!(base, build_table(&definition, &[("one", &[1, 1])]));
+
+    let (merged, conflicts) = Table::merge(&base, &ours, &theirs);
+    assert_eq!(merged, build_table(&definition, &[("one", &[10, 10]), ("two", &[20, 2]), ("ours", &[0, 0]), ("three", &[30, 3])]));
+    assert!(conflicts.is_empty());
+}
+
+#[test]
+fn test_merge_tables_with_dependencies() {
+    let definition = build_definition(1, &["a", "b"]);
+    let build_db = |rows: &[(&str, &[i32])]| {
+        let mut table = DB::new("units_tables", None, &definition);
+        table.set_table_data(build_table(&definition, rows).get_ref_table_data()).unwrap();
+        DecodedPackedFile::DB(table)
+    };
+
+    // The same key in two vanilla tables must only use the row of the last one as base.
+    let mut dependencies = Dependencies::default();
+    dependencies.get_ref_mut_dependency_database().push(PackedFile::new_from_decoded(&build_db(&[("one", &[1, 1]), ("two", &[2, 2])]), &["db".to_owned(), "units_tables".to_owned(), "data__".to_owned()]));
+    dependencies.get_ref_mut_dependency_database().push(PackedFile::new_from_decoded(&build_db(&[("one", &[5, 1])]), &["db".to_owned(), "units_tables".to_owned(), "data_1__".to_owned()]));
+
+    let ours = build_db(&[("one", &[5, 10]), ("ours", &[0, 0])]);
+    let theirs = build_db(&[("one", &[7, 1])]);
+    let (merged, conflicts) = DecodedPackedFile::merge_tables(&ours, &theirs, &dependencies).unwrap();
+    match merged {
+        DecodedPackedFile::DB(merged) => assert_eq!(merged.get_ref_table_data(), build_table(&definition, &[("one", &[7, 10]), ("ours", &[0, 0])]).get_ref_table_data()),
+        _ => panic!("The merged table is not a DB Table."),
+    }
+    assert!(conflicts.is_empty());
+
+    // Tables of different types cannot be merged.
+    assert!(DecodedPackedFile::merge_tables(&ours, &DecodedPackedFile::Loc(Loc::new(&definition)), &dependencies).is_err());
+}
+
+#[test]
+fn test_merge_db_different_tables() {
+    let definition = build_definition(1, &["a"]);
+    let base = DB::new("units_tables", None, &definition);
+    let ours = DB::new("units_tables", None, &definition);
+    let theirs = DB::new("land_units_tables", None, &definition);
+    assert!(DB::merge(&base, &ours, &theirs).is_err());
+
+    let (merged, conflicts) = DB::merge(&base, &ours, &ours).unwrap();
+    assert_eq!(merged.get_uuid(), ours.get_uuid());
+    assert!(conflicts.is_empty());
+}
+
+/// This function builds a field with the provided name and type.
+fn build_field(name: &str, field_type: FieldType) -> Field {
+    Field::new(name.to_owned(), field_type, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new())
+}
+
+/// This function builds an encoded DB Table with the provided definition and rows.
+fn build_db_data(definition: &Definition, rows: &[Vec<DecodedData>]) -> Vec<u8> {
+    let mut table = DB::new("units_tables", None, definition);
+    table.set_table_data(rows).unwrap();
+    table.save().unwrap()
+}
+
+#[test]
+fn test_guess_definition_from_previous_version() {
+    let mut old_definition = Definition::new(1);
+    old_definition.get_ref_mut_fields().push(build_field("key", FieldType::StringU8));
+    old_definition.get_ref_mut_fields().push(build_field("a", FieldType::I32));
+    old_definition.get_ref_mut_fields().push(build_field("b", FieldType::I32));
+    old_definition.get_ref_mut_fields().push(build_field("flag", FieldType::Boolean));
+
+    let mut new_definition = Definition::new(2);
+    *new_definition.get_ref_mut_fields() = old_definition.get_ref_fields().to_vec();
+    new_definition.get_ref_mut_fields().insert(2, build_field("c", FieldType::F32));
+
+    let data = build_db_data(&new_definition, &[
+        vec![DecodedData::StringU8("unit_a".to_owned()), DecodedData::I32(5), DecodedData::F32(1.5), DecodedData::I32(10), DecodedData::Boolean(true)],
+        vec![DecodedData::StringU8("unit_b".to_owned()), DecodedData::I32(300), DecodedData::F32(-2.25), DecodedData::I32(0), DecodedData::Boolean(false)],
+        vec![DecodedData::StringU8("unit_c".to_owned()), DecodedData::I32(-7), DecodedData::F32(100.0), DecodedData::I32(25), DecodedData::Boolean(true)],
+    ]);
+
+    // Without rows there is nothing to guess from.
+    assert!(DB::guess_definitions(&build_db_data(&new_definition, &[]), &[old_definition.clone()]).is_err());
+
+    let candidates = DB::guess_definitions(&data, &[old_definition]).unwrap();
+    let best = candidates[0].get_ref_definition();
+    assert_eq!(best.get_version(), 2);
+    assert_eq!(best.get_ref_fields().iter().map(|x| x.get_field_type()).collect::<Vec<FieldType>>(), vec![FieldType::StringU8, FieldType::I32, FieldType::F32, FieldType::I32, FieldType::Boolean]);
+    assert_eq!(best.get_ref_fields().iter().map(|x| x.get_name()).collect::<Vec<&str>>(), vec!["key", "a", "unknown_3", "b", "flag"]);
+    assert!(candidates.windows(2).all(|x| x[0].get_penalty() <= x[1].get_penalty()));
+}
+
+#[test]
+fn test_guess_definition_without_previous_version() {
+    let mut definition = Definition::new(3);
+    definition.get_ref_mut_fields().push(build_field("key", FieldType::StringU8));
+    definition.get_ref_mut_fields().push(build_field("value", FieldType::I32));
+    definition.get_ref_mut_fields().push(build_field("ratio", FieldType::F32));
+    definition.get_ref_mut_fields().push(build_field("flag", FieldType::Boolean));
+    definition.get_ref_mut_fields().push(build_field("text", FieldType::OptionalStringU8));
+
+    let data = build_db_data(&definition, &[
+        vec![DecodedData::StringU8("unit_a".to_owned()), DecodedData::I32(5), DecodedData::F32(0.5), DecodedData::Boolean(true), DecodedData::OptionalStringU8("hello".to_owned())],
+        vec![DecodedData::StringU8("unit_b".to_owned()), DecodedData::I32(300), DecodedData::F32(12.75), DecodedData::Boolean(false), DecodedData::OptionalStringU8(String::new())],
+        vec![DecodedData::StringU8("unit_c".to_owned()), DecodedData::I32(-2), DecodedData::F32(100.0), DecodedData::Boolean(true), DecodedData::OptionalStringU8("x".to_owned())],
+    ]);
+
+    let candidates = DB::guess_definitions(&data, &[]).unwrap();
+    let best = candidates[0].get_ref_definition();
+    assert_eq!(best.get_version(), 3);
+    assert_eq!(best.get_ref_fields().iter().map(|x| x.get_field_type()).collect::<Vec<FieldType>>(), vec![FieldType::StringU8, FieldType::I32, FieldType::F32, FieldType::Boolean, FieldType::OptionalStringU8]);
+    assert!(best.get_ref_fields().iter().all(|x| x.get_name().starts_with("unknown_")));
+}
+
+#[test]
+fn test_migrate_table() {
+    let mut old_definition = Definition::new(1);
+    old_definition.get_ref_mut_fields().push(build_field("key", FieldType::StringU8));
+    old_definition.get_ref_mut_fields().push(build_field("old_name", FieldType::I32));
+    old_definition.get_ref_mut_fields().push(build_field("value", FieldType::StringU8));
+    old_definition.get_ref_mut_fields().push(build_field("dropped", FieldType::Boolean));
+    old_definition.get_ref_mut_fields().push(build_field("legacy", FieldType::F32));
+
+    // Between versions 1 and 2 the field "old_name" got renamed, so we can only find it through the history.
+    let mut middle_definition = Definition::new(2);
+    *middle_definition.get_ref_mut_fields() = old_definition.get_ref_fields().to_vec();
+    middle_definition.get_ref_mut_fields()[1].set_name("new_name");
+    let history = vec![old_definition.clone(), middle_definition];
+
+    let mut new_definition = Definition::new(3);
+    new_definition.get_ref_mut_fields().push(build_field("key", FieldType::StringU8));
+    new_definition.get_ref_mut_fields().push(build_field("new_name", FieldType::I32));
+    new_definition.get_ref_mut_fields().push(build_field("value", FieldType::I32));
+    new_definition.get_ref_mut_fields().push(Field::new("modern".to_owned(), FieldType::F32, false, None, 0, false, None, None, None, "Previously legacy.".to_owned(), 0, 0, BTreeMap::new()));
+    new_definition.get_ref_mut_fields().push(Field::new("extra".to_owned(), FieldType::F32, false, Some("1.5".to_owned()), 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()));
+
+    let mut table = DB::new("units_tables", None, &old_definition);
+    table.set_table_data(&[
+        vec![DecodedData::StringU8("a".to_owned()), DecodedData::I32(1), DecodedData::StringU8("5".to_owned()), DecodedData::Boolean(true), DecodedData::F32(0.5)],
+        vec![DecodedData::StringU8("b".to_owned()), DecodedData::I32(2), DecodedData::StringU8("abc".to_owned()), DecodedData::Boolean(false), DecodedData::F32(2.0)],
+    ]).unwrap();
+
+    let migration = table.migrate(&new_definition, &history);
+    assert_eq!((migration.old_version, migration.new_version), (1, 3));
+    assert_eq!(migration.renamed_fields, vec![("legacy".to_owned(), "modern".to_owned()), ("old_name".to_owned(), "new_name".to_owned())]);
+    assert_eq!(migration.added_fields, vec!["extra".to_owned()]);
+    assert_eq!(migration.removed_fields, vec!["dropped".to_owned()]);
+    assert_eq!(migration.converted_fields.len(), 1);
+    assert_eq!(migration.converted_fields[0].field, "value");
+    assert_eq!(migration.converted_fields[0].failed_rows, vec![2]);
+    assert!(migration.needs_review());
+
+    let mut expected = DB::new("units_tables", None, &new_definition);
+    expected.set_table_data(&[
+        vec![DecodedData::StringU8("a".to_owned()), DecodedData::I32(1), DecodedData::I32(5), DecodedData::F32(0.5), DecodedData::F32(1.5)],
+        vec![DecodedData::StringU8("b".to_owned()), DecodedData::I32(2), DecodedData::I32(0), DecodedData::F32(2.0), DecodedData::F32(1.5)],
+    ]).unwrap();
+    assert_eq!(table.get_ref_table_data(), expected.get_ref_table_data());
+    assert_eq!(table.get_ref_definition(), &new_definition);
+}
+
+#[test]
+fn test_import_tsv_old_version() {
+    let old_definition = build_definition(1, &["value"]);
+    let new_definition = build_definition(2, &["value", "extra"]);
+    let history = vec![new_definition.clone(), old_definition];
+
+    let path = std::env::temp_dir().join("rpfm_test_import_tsv_old_version.tsv");
+    std::fs::write(&path, "units_tables\t1\nkey\tvalue\na\t1\nb\t2\n").unwrap();
+
+    let (table, migration) = DB::import_tsv(&new_definition, &history, &path, "units_tables").unwrap();
+    let migration = migration.unwrap();
+    assert_eq!((migration.old_version, migration.new_version), (1, 2));
+    assert_eq!(migration.added_fields, vec!["extra".to_owned()]);
+    assert!(!migration.needs_review());
+    assert_eq!(table.get_ref_definition(), &new_definition);
+    assert_eq!(table.get_ref_table_data(), &[
+        vec![DecodedData::StringU8("a".to_owned()), DecodedData::I32(1), DecodedData::I32(0)],
+        vec![DecodedData::StringU8("b".to_owned()), DecodedData::I32(2), DecodedData::I32(0)],
+    ]);
+
+    // TSV files of the current version must not be migrated, and unknown versions must fail.
+    std::fs::write(&path, "units_tables\t2\nkey\tvalue\textra\na\t1\t3\n").unwrap();
+    let (table, migration) = DB::import_tsv(&new_definition, &history, &path, "units_tables").unwrap();
+    assert!(migration.is_none());
+    assert_eq!(table.get_ref_table_data().len(), 1);
+
+    std::fs::write(&path, "units_tables\t3\nkey\tvalue\na\t1\n").unwrap();
+    assert!(DB::import_tsv(&new_definition, &history, &path, "units_tables").is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_decoded_packed_file_json_ron() {
+    use crate::packedfile::DecodedPackedFile;
+    use crate::packedfile::text::Text;
+
+    // A table with a nested sequence of values of different types.
+    let mut sequence_definition = Definition::new(0);
+    *sequence_definition.get_ref_mut_fields() = vec![build_field("name", FieldType::OptionalStringU8), build_field("weight", FieldType::F32)];
+    let mut definition = Definition::new(1);
+    *definition.get_ref_mut_fields() = vec![
+        build_field("key", FieldType::StringU16),
+        build_field("enabled", FieldType::Boolean),
+        build_field("count", FieldType::I16),
+        build_field("value", FieldType::I64),
+        build_field("parts", FieldType::SequenceU32(sequence_definition.clone())),
+    ];
+
+    let mut sequence = Table::new(&sequence_definition);
+    sequence.set_table_data(&[
+        vec![DecodedData::OptionalStringU8("blade".to_owned()), DecodedData::F32(0.25)],
+        vec![DecodedData::OptionalStringU8(String::new()), DecodedData::F32(-1.5)],
+    ]).unwrap();
+
+    let mut db = DB::new("swords_tables", None, &definition);
+    db.set_table_data(&[vec![
+        DecodedData::StringU16("sword\t\"1\"".to_owned()),
+        DecodedData::Boolean(true),
+        DecodedData::I16(-3),
+        DecodedData::I64(1 << 40),
+        DecodedData::SequenceU32(sequence),
+    ]]).unwrap();
+    let decoded = DecodedPackedFile::DB(db);
+
+    let json = decoded.to_json().unwrap();
+    assert!(json.contains("\"OptionalStringU8\": \"blade\""));
+    assert_eq!(DecodedPackedFile::from_json(&json).unwrap(), decoded);
+    assert_eq!(DecodedPackedFile::from_ron(&decoded.to_ron().unwrap()).unwrap(), decoded);
+
+    // Types that are not supported should fail in both directions.
+    let text = DecodedPackedFile::Text(Text::new());
+    assert!(text.to_json().is_err());
+    assert!(DecodedPackedFile::from_json(&serde_json::to_string(&text).unwrap()).is_err());
+    assert!(DecodedPackedFile::from_json("{\"DB\": {}}").is_err());
+}
+
+#[test]
+fn test_port_tables() {
+    let reference = Some(("land_units".to_owned(), "key".to_owned()));
+    let definition_source = build_definition(2, &["value"]);
+    let mut source = GameContext::new(KEY_WARHAMMER_2, &Settings::new()).unwrap();
+    let mut target = GameContext::new(KEY_EMPIRE, &Settings::new()).unwrap();
+
+    // In the source game, units have a flag the target game doesn't have, and the target game has a new field with a default value.
+    let mut definition_source_new = definition_source.clone();
+    definition_source_new.get_ref_mut_fields().push(Field::new("is_naval".to_owned(), FieldType::Boolean, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()));
+    definition_source_new.get_ref_mut_fields().push(Field::new("land_unit".to_owned(), FieldType::StringU8, false, None, 0, false, None, reference.clone(), None, String::new(), 0, 0, BTreeMap::new()));
+    let mut schema = Schema::default();
+    schema.add_versioned_file(&VersionedFile::DB("units_tables".to_owned(), vec![definition_source_new.clone()]));
+    schema.add_versioned_file(&VersionedFile::DB("other_tables".to_owned(), vec![definition_source.clone()]));
+    source.set_schema(Some(schema));
+
+    let mut definition_target = Definition::new(5);
+    *definition_target.get_ref_mut_fields() = vec![
+        Field::new("key".to_owned(), FieldType::StringU8, true, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("land_unit".to_owned(), FieldType::StringU8, false, None, 0, false, None, reference, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("value".to_owned(), FieldType::StringU8, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("cost".to_owned(), FieldType::I32, false, Some("100".to_owned()), 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+    ];
+    let mut definition_land_units = Definition::new(1);
+    *definition_land_units.get_ref_mut_fields() = vec![Field::new("key".to_owned(), FieldType::StringU8, true, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new())];
+    let mut schema = Schema::default();
+    schema.add_versioned_file(&VersionedFile::DB("units_tables".to_owned(), vec![definition_target.clone()]));
+    schema.add_versioned_file(&VersionedFile::DB("land_units_tables".to_owned(), vec![definition_land_units.clone()]));
+    target.set_schema(Some(schema));
+
+    // Only one of the land units exists in the target game.
+    let mut land_units = DB::new("land_units_tables", None, &definition_land_units);
+    land_units.set_table_data(&[vec![DecodedData::StringU8("lu_swords".to_owned())]]).unwrap();
+    target.get_ref_mut_dependencies().get_ref_mut_dependency_database().push(PackedFile::new_from_decoded(&DecodedPackedFile::DB(land_units), &["db".to_owned(), "land_units_tables".to_owned(), "data__".to_owned()]));
+
+    let mut units = DB::new("units_tables", None, &definition_source_new);
+    units.set_table_data(&[
+        vec![DecodedData::StringU8("swords".to_owned()), DecodedData::I32(5), DecodedData::Boolean(false), DecodedData::StringU8("lu_swords".to_owned())],
+        vec![DecodedData::StringU8("ships".to_owned()), DecodedData::I32(6), DecodedData::Boolean(true), DecodedData::StringU8("lu_ships".to_owned())],
+        vec![DecodedData::StringU8("none".to_owned()), DecodedData::I32(7), DecodedData::Boolean(false), DecodedData::StringU8(String::new())],
+    ]).unwrap();
+    let other = DB::new("other_tables", None, &definition_source);
+    let units_path = vec!["db".to_owned(), "units_tables".to_owned(), "my_mod".to_owned()];
+    let other_path = vec!["db".to_owned(), "other_tables".to_owned(), "my_mod".to_owned()];
+
+    let mut pack_file = PackFile::new_with_name("my_mod.pack", PFHVersion::PFH5);
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::DB(units), &units_path), false).unwrap();
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::DB(other), &other_path), false).unwrap();
+
+    // Tables not in the target game fail to port. They're reported, but only removed from the PackFile if we ask for it.
+    let mut pack_file_keep = pack_file.clone();
+    let ports = pack_file_keep.port_tables(&source, &target, false);
+    assert_eq!(ports.len(), 2);
+    assert_eq!(ports[0].0, other_path);
+    assert!(ports[0].1.is_err());
+    match pack_file_keep.get_ref_packed_file_by_path(&other_path).unwrap().get_decoded_from_memory().unwrap() {
+        DecodedPackedFile::DB(table) => assert_eq!(table.get_ref_definition(), &definition_source),
+        _ => panic!("The table has not been decoded as a DB Table."),
+    }
+
+    let ports = pack_file.port_tables(&source, &target, true);
+    assert_eq!(ports.len(), 2);
+    assert_eq!(pack_file.get_pfh_version(), PFHVersion::PFH0);
+    assert_eq!(ports[0].0, other_path);
+    assert!(ports[0].1.is_err());
+    assert!(pack_file.get_ref_packed_file_by_path(&other_path).is_none());
+
+    assert_eq!(ports[1].0, units_path);
+    let port = ports[1].1.as_ref().unwrap();
+    assert_eq!((port.migration.old_version, port.migration.new_version), (2, 5));
+    assert!(port.migration.renamed_fields.is_empty());
+    assert_eq!(port.migration.added_fields, vec!["cost".to_owned()]);
+    assert_eq!(port.migration.removed_fields, vec!["is_naval".to_owned()]);
+    assert_eq!(port.migration.converted_fields.len(), 1);
+    assert_eq!(port.unresolved_references.len(), 1);
+    assert_eq!(port.unresolved_references[0].row, 2);
+    assert_eq!(port.unresolved_references[0].field, "land_unit");
+    assert_eq!(port.unresolved_references[0].value, "lu_ships");
+    assert!(port.needs_review());
+
+    match pack_file.get_ref_packed_file_by_path(&units_path).unwrap().get_decoded_from_memory().unwrap() {
+        DecodedPackedFile::DB(table) => {
+            assert_eq!(table.get_ref_definition(), &definition_target);
+            assert_eq!(table.get_ref_table_data()[0], vec![
+                DecodedData::StringU8("swords".to_owned()),
+                DecodedData::StringU8("lu_swords".to_owned()),
+                DecodedData::StringU8("5".to_owned()),
+                DecodedData::I32(100),
+            ]);
+        }
+        _ => panic!("The table has not been decoded as a DB Table."),
+    }
+}
@@ -0,0 +1,136 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `Lua` module, to make sure we parse scripts and `ca_types` files correctly.
+!*/
+
+use super::*;
+
+const CA_TYPES: &str = r#"
+--# assume global class CM
+--# assume global class CA_MODEL
+--# assume global class CA_FACTION
+
+--# type global CA_CQI = number
+
+--# assume global cm: CM
+--# assume global out: function(text: string)
+--# assume global find_uicomponent: function(
+--# parent: CA_UIC,
+--# ...: string
+--# ) --> CA_UIC?
+
+--# assume CM.model: method() --> CA_MODEL
+--# assume CM.add_unit_to_faction: method(faction_key: string, unit_key: string, delay: number?)
+--# assume CA_MODEL.world: CA_WORLD
+--# assume CA_MODEL.faction_for_key: method(faction_key: string) --> CA_FACTION
+--# assume CA_FACTION.name: method() --> string
+"#;
+
+/// Test to make sure the calls of a script are found, with the path to their object.
+#[test]
+fn test_lua_parse_calls() {
+    let script = r#"
+        -- Comment with a call: fake_call()
+        --[==[ Long comment
+        with another_fake_call() ]==]
+        local function on_turn_start(context)
+            local faction = cm:model():faction_for_key("wh_main_emp_empire")
+            if faction:name() == 'x' .. [[y]] then
+                out("Turn " .. tostring(#context + 1.5e3))
+            end
+            for i, v in ipairs({ a = 1, [2] = 0x1F; "b" }) do end
+            return faction
+        end
+
+        utils.log "done"
+        data[1].func(...)
+    "#;
+
+    let script = LuaScript::parse(script).unwrap();
+    let calls = script.get_ref_calls();
+    let names = calls.iter().map(|x| x.name.to_owned()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["model", "faction_for_key", "name", "tostring", "out", "ipairs", "log"]);
+
+    let faction_for_key = &calls[1];
+    assert_eq!(faction_for_key.receiver, vec![LuaPathSegment::Field("cm".to_owned()), LuaPathSegment::Field("model".to_owned()), LuaPathSegment::Call]);
+    assert!(faction_for_key.is_method);
+    assert_eq!(faction_for_key.arguments, vec![LuaArgument::String("wh_main_emp_empire".to_owned())]);
+    assert_eq!((faction_for_key.line, faction_for_key.column), (6, 40));
+
+    assert_eq!(calls[4].arguments, vec![LuaArgument::Other]);
+    assert_eq!(calls[6].receiver, vec![LuaPathSegment::Field("utils".to_owned())]);
+    assert_eq!(calls[6].arguments, vec![LuaArgument::String("done".to_owned())]);
+}
+
+/// Test to make sure syntax errors are reported in the right position.
+#[test]
+fn test_lua_syntax_errors() {
+    let get_error = |script: &str| match LuaScript::parse(script) {
+        Err(error) => match error.kind() {
+            ErrorKind::LuaSyntaxError(line, column, message) => (*line, *column, message.to_owned()),
+            _ => panic!("Unexpected error."),
+        }
+        Ok(_) => panic!("No error found."),
+    };
+
+    assert_eq!(get_error("function test()\n    print('a')\n"), (3, 1, "'end' expected (to close 'function' at line 1) near '<eof>'".to_owned()));
+    assert_eq!(get_error("local a = 'unfinished\n"), (1, 22, "unfinished string".to_owned()));
+    assert_eq!(get_error("local a = 1 +\n"), (2, 1, "unexpected symbol near '<eof>'".to_owned()));
+    assert_eq!(get_error("a.b"), (1, 4, "syntax error near '<eof>'".to_owned()));
+    assert_eq!(get_error("local a = 3x"), (1, 13, "malformed number near '3x'".to_owned()));
+    assert_eq!(get_error("return 1\nlocal a = 2"), (2, 1, "'<eof>' expected near 'local'".to_owned()));
+    assert!(LuaScript::parse("local a = b\n(c)()").is_err());
+    assert!(LuaScript::parse("local a = b;(c)()").is_ok());
+
+    // Nesting is limited, so broken scripts cannot overflow the stack.
+    let nested = |levels: usize| format!("local a = {}1{}", "(".repeat(levels), ")".repeat(levels));
+    assert!(LuaScript::parse(&nested(100)).is_ok());
+    assert_eq!(get_error(&nested(100_000)), (1, 210, "chunk has too many syntax levels".to_owned()));
+    assert_eq!(get_error(&"do ".repeat(100_000)).2, "chunk has too many syntax levels");
+}
+
+/// Test to make sure the `ca_types` files are read correctly, and calls are resolved against them.
+#[test]
+fn test_lua_ca_types() {
+    let ca_types = CATypes::read(CA_TYPES);
+    assert_eq!(ca_types.get_ref_globals().get("cm"), Some(&"CM".to_owned()));
+    assert!(ca_types.get_ref_classes().contains_key("CA_FACTION"));
+
+    let out = ca_types.get_ref_functions().get("out").unwrap();
+    assert!(!out.is_method);
+    assert_eq!((out.get_min_arguments(), out.get_max_arguments()), (1, Some(1)));
+
+    let find_uicomponent = ca_types.get_ref_functions().get("find_uicomponent").unwrap();
+    assert_eq!((find_uicomponent.get_min_arguments(), find_uicomponent.get_max_arguments()), (1, None));
+    assert_eq!(find_uicomponent.return_type, Some("CA_UIC".to_owned()));
+
+    let add_unit = match ca_types.get_ref_classes().get("CM").unwrap().get("add_unit_to_faction").unwrap() {
+        CAMember::Function(function) => function,
+        CAMember::Field(_) => panic!("Expected function."),
+    };
+    assert!(add_unit.is_method);
+    assert_eq!((add_unit.get_min_arguments(), add_unit.get_max_arguments()), (2, Some(3)));
+    assert_eq!(add_unit.get_parameter_for_argument(1).unwrap().name, "unit_key");
+
+    let script = LuaScript::parse(r#"
+        cm:model():faction_for_key("x"):name()
+        cm:model():get_faction("x")
+        cm.model.world()
+        unknown:call()
+    "#).unwrap();
+
+    let calls = script.get_ref_calls();
+    assert!(matches!(ca_types.get_called_function(&calls[2]), Ok(Some(function)) if function.return_type == Some("string".to_owned())));
+    assert_eq!(ca_types.get_called_function(&calls[4]), Err("CA_MODEL".to_owned()));
+    assert_eq!(ca_types.get_called_function(&calls[5]), Ok(None));
+    assert_eq!(ca_types.get_called_function(&calls[6]), Ok(None));
+}
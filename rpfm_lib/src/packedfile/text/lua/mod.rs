@@ -0,0 +1,1135 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with the code to analyse Lua scripts.
+
+This contains a parser for Lua 5.1 scripts (the version used by the games), used to find syntax errors
+and to get the list of function calls of a script, and a parser for the `ca_types` files, which contain
+the definitions of the functions and objects of CA's Lua API.
+
+The parser doesn't build a full syntax tree. It only keeps what we need to check the calls against the `ca_types` file.
+!*/
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+
+use rpfm_error::{ErrorKind, Result};
+
+use crate::common::get_game_selected_ca_types_file;
+
+#[cfg(test)]
+mod lua_test;
+
+/// Reserved words of Lua 5.1.
+const KEYWORDS: [&str; 21] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Symbols of Lua 5.1, longest first so they're matched correctly.
+const SYMBOLS: [&str; 26] = [
+    "...", "..", "==", "~=", "<=", ">=",
+    "+", "-", "*", "/", "%", "^", "#", "<", ">", "=", "(", ")", "{", "}", "[", "]", ";", ":", ",", ".",
+];
+
+/// Priority of the unary operators.
+const UNARY_PRIORITY: u8 = 8;
+
+/// Max amount of nested blocks and expressions, like the `LUAI_MAXCCALLS` limit of Lua 5.1.
+const MAX_SYNTAX_LEVELS: usize = 200;
+
+//---------------------------------------------------------------------------//
+//                              Enum & Structs
+//---------------------------------------------------------------------------//
+
+/// This struct contains the results of parsing a Lua script.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LuaScript {
+
+    /// Function calls done in the script, in order of appearance.
+    calls: Vec<LuaCall>,
+}
+
+/// This struct represents a call to a named function, like `find_uicomponent(...)` or `cm:get_faction(...)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LuaCall {
+
+    /// Path to the object the function belongs to, like `[cm]` for `cm:get_faction(...)`. Empty for global functions.
+    pub receiver: Vec<LuaPathSegment>,
+
+    /// Name of the function called.
+    pub name: String,
+
+    /// If the function was called as a method, with `:`.
+    pub is_method: bool,
+
+    /// Arguments passed to the function.
+    pub arguments: Vec<LuaArgument>,
+
+    /// Line of the name of the function, starting at 1.
+    pub line: usize,
+
+    /// Column of the name of the function, starting at 1.
+    pub column: usize,
+}
+
+/// This enum represents a step in the path to the object a function belongs to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LuaPathSegment {
+
+    /// A variable or a field of the previous step, like `cm` or `model`.
+    Field(String),
+
+    /// A call to the previous step.
+    Call,
+}
+
+/// This enum represents an argument passed to a function. We only care about string literals.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LuaArgument {
+
+    /// A string literal.
+    String(String),
+
+    /// An expression that can return multiple values, like a function call or `...`.
+    Multiple,
+
+    /// Any other expression.
+    Other,
+}
+
+/// This struct contains the definitions of CA's Lua API, read from a `ca_types` file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CATypes {
+
+    /// Classes, with their members. Members without `CAFunction` are fields, with their type.
+    classes: BTreeMap<String, BTreeMap<String, CAMember>>,
+
+    /// Global variables, with their type.
+    globals: BTreeMap<String, String>,
+
+    /// Global functions.
+    functions: BTreeMap<String, CAFunction>,
+}
+
+/// This enum represents a member of a class of CA's Lua API.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CAMember {
+
+    /// A field, with its type.
+    Field(String),
+
+    /// A function.
+    Function(CAFunction),
+}
+
+/// This struct represents a function of CA's Lua API.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CAFunction {
+
+    /// If the function is a method, receiving the object as first argument.
+    pub is_method: bool,
+
+    /// Parameters of the function, without the object for methods.
+    pub parameters: Vec<CAParameter>,
+
+    /// Type returned by the function, if any.
+    pub return_type: Option<String>,
+}
+
+/// This struct represents a parameter of a function of CA's Lua API.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CAParameter {
+
+    /// Name of the parameter. Empty if the definition doesn't name it.
+    pub name: String,
+
+    /// Type of the parameter, as it's written in the definition.
+    pub parameter_type: String,
+
+    /// If the parameter can be omitted.
+    pub is_optional: bool,
+
+    /// If the parameter accepts any amount of arguments.
+    pub is_variadic: bool,
+}
+
+/// This enum represents the types of tokens of a Lua script.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TokenType {
+    Name,
+    Keyword,
+    Number,
+    String,
+    Symbol,
+    Eof,
+}
+
+/// This struct represents a token of a Lua script.
+#[derive(Clone, Debug)]
+struct Token {
+    token_type: TokenType,
+
+    /// Text of the token. For strings, this is their value, without quotes and with the escape sequences processed.
+    text: String,
+    line: usize,
+    column: usize,
+}
+
+/// This struct contains the state of the tokenizer.
+struct Lexer {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+}
+
+/// This struct contains the state of the parser.
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    calls: Vec<LuaCall>,
+    level: usize,
+}
+
+//---------------------------------------------------------------------------//
+//                           Implementation of LuaScript
+//---------------------------------------------------------------------------//
+
+/// Implementation of `LuaScript`.
+impl LuaScript {
+
+    /// This function parses the provided Lua script.
+    ///
+    /// If the script has a syntax error, this returns a `LuaSyntaxError` with the position of the error.
+    pub fn parse(code: &str) -> Result<Self> {
+        let tokens = Lexer::new(code).tokenize()?;
+        let mut parser = Parser {
+            tokens,
+            index: 0,
+            calls: vec![],
+            level: 0,
+        };
+
+        parser.block()?;
+        if parser.peek().token_type != TokenType::Eof {
+            return Err(parser.error("'<eof>' expected"));
+        }
+
+        Ok(Self {
+            calls: parser.calls,
+        })
+    }
+
+    /// This function returns a reference to the calls of the script.
+    pub fn get_ref_calls(&self) -> &[LuaCall] {
+        &self.calls
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                           Implementation of CATypes
+//---------------------------------------------------------------------------//
+
+/// Implementation of `CATypes`.
+impl CATypes {
+
+    /// This function reads the `ca_types` file of the game selected.
+    pub fn read_game_selected() -> Result<Self> {
+        let path = get_game_selected_ca_types_file()?;
+        let mut file = BufReader::new(File::open(&path)?);
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+        Ok(Self::read(&data))
+    }
+
+    /// This function parses the provided `ca_types` file.
+    ///
+    /// Only the `--# assume` declarations are read. Declarations can span multiple lines, as long as their parenthesis are not closed.
+    /// Declarations we cannot understand are ignored.
+    pub fn read(data: &str) -> Self {
+        let mut ca_types = Self::default();
+        let mut declaration = String::new();
+        for line in data.lines() {
+            let line = line.trim();
+            if !line.starts_with("--#") { continue; }
+            let line = line[3..].trim();
+
+            if declaration.is_empty() && !line.starts_with("assume ") { continue; }
+            declaration.push(' ');
+            declaration.push_str(line);
+
+            if get_depth(&declaration) <= 0 {
+                ca_types.read_declaration(declaration.trim());
+                declaration.clear();
+            }
+        }
+
+        ca_types
+    }
+
+    /// This function reads a single `assume` declaration into the `CATypes`.
+    fn read_declaration(&mut self, declaration: &str) {
+        let declaration = declaration.trim_start_matches("assume").trim();
+        if let Some(class) = declaration.strip_prefix("global class ") {
+            self.classes.entry(class.trim().to_owned()).or_default();
+        }
+
+        else if let Some(global) = declaration.strip_prefix("global ") {
+            if let Some((name, value)) = split_declaration(global) {
+                match parse_function(value) {
+                    Some(function) => { self.functions.insert(name, function); }
+                    None => { self.globals.insert(name, value.to_owned()); }
+                }
+            }
+        }
+
+        else if let Some((name, value)) = split_declaration(declaration) {
+            if let Some(dot) = name.find('.') {
+                let class = name[..dot].to_owned();
+                let member_name = name[dot + 1..].to_owned();
+                let member = match parse_function(value) {
+                    Some(function) => CAMember::Function(function),
+                    None => CAMember::Field(value.to_owned()),
+                };
+
+                self.classes.entry(class).or_default().insert(member_name, member);
+            }
+        }
+    }
+
+    /// This function returns a reference to the classes of the API, with their members.
+    pub fn get_ref_classes(&self) -> &BTreeMap<String, BTreeMap<String, CAMember>> {
+        &self.classes
+    }
+
+    /// This function returns a reference to the global variables of the API, with their types.
+    pub fn get_ref_globals(&self) -> &BTreeMap<String, String> {
+        &self.globals
+    }
+
+    /// This function returns a reference to the global functions of the API.
+    pub fn get_ref_functions(&self) -> &BTreeMap<String, CAFunction> {
+        &self.functions
+    }
+
+    /// This function returns the function called in the provided `LuaCall`, if it's part of the API.
+    ///
+    /// If the function is called on an object of a known class, but the class doesn't have the function, this returns `Err` with the name of the class.
+    /// Calls we cannot resolve return `Ok(None)`.
+    pub fn get_called_function(&self, call: &LuaCall) -> std::result::Result<Option<&CAFunction>, String> {
+        if call.receiver.is_empty() {
+            return Ok(self.functions.get(&call.name));
+        }
+
+        // Follow the path to the object, keeping track of its type.
+        let mut current_type: Option<&str> = None;
+        let mut current_function: Option<&CAFunction> = None;
+        for (index, segment) in call.receiver.iter().enumerate() {
+            match segment {
+                LuaPathSegment::Field(name) => {
+                    if index == 0 {
+                        current_function = self.functions.get(name);
+                        current_type = self.globals.get(name).map(|x| &**x);
+                    }
+                    else {
+                        match current_type.and_then(|x| self.classes.get(x)).and_then(|x| x.get(name)) {
+                            Some(CAMember::Field(field_type)) => {
+                                current_type = Some(field_type);
+                                current_function = None;
+                            }
+                            Some(CAMember::Function(function)) => {
+                                current_type = None;
+                                current_function = Some(function);
+                            }
+                            None => return Ok(None),
+                        }
+                    }
+                }
+                LuaPathSegment::Call => {
+                    current_type = current_function.and_then(|x| x.return_type.as_deref());
+                    current_function = None;
+                }
+            }
+
+            if current_type.is_none() && current_function.is_none() {
+                return Ok(None);
+            }
+        }
+
+        match current_type.and_then(|x| self.classes.get_key_value(x)) {
+            Some((class_name, class)) => match class.get(&call.name) {
+                Some(CAMember::Function(function)) => Ok(Some(function)),
+                Some(CAMember::Field(_)) => Ok(None),
+                None => Err(class_name.to_owned()),
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                           Implementation of CAFunction
+//---------------------------------------------------------------------------//
+
+/// Implementation of `CAFunction`.
+impl CAFunction {
+
+    /// This function returns the minimum amount of arguments this function needs.
+    pub fn get_min_arguments(&self) -> usize {
+        self.parameters.iter().filter(|x| !x.is_optional && !x.is_variadic).count()
+    }
+
+    /// This function returns the maximum amount of arguments this function accepts, or `None` if it has no limit.
+    pub fn get_max_arguments(&self) -> Option<usize> {
+        if self.parameters.iter().any(|x| x.is_variadic) { None }
+        else { Some(self.parameters.len()) }
+    }
+
+    /// This function returns the parameter that receives the argument in the provided position, if any.
+    pub fn get_parameter_for_argument(&self, index: usize) -> Option<&CAParameter> {
+        match self.parameters.get(index) {
+            Some(parameter) => Some(parameter),
+            None => self.parameters.last().filter(|x| x.is_variadic),
+        }
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                           Implementation of Lexer
+//---------------------------------------------------------------------------//
+
+/// Implementation of `Lexer`.
+impl Lexer {
+
+    /// This function creates a new `Lexer` for the provided code.
+    fn new(code: &str) -> Self {
+        Self {
+            chars: code.chars().collect(),
+            index: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// This function returns the char in the provided offset from the current position, if any.
+    fn peek_char(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.index + offset).copied()
+    }
+
+    /// This function advances one char, keeping track of the line and column.
+    fn next_char(&mut self) -> Option<char> {
+        let character = self.peek_char(0)?;
+        self.index += 1;
+        if character == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(character)
+    }
+
+    /// This function returns a syntax error in the current position.
+    fn error(&self, message: &str) -> rpfm_error::Error {
+        ErrorKind::LuaSyntaxError(self.line, self.column, message.to_owned()).into()
+    }
+
+    /// This function splits the code into tokens, ignoring whitespaces and comments.
+    fn tokenize(mut self) -> Result<Vec<Token>> {
+        let mut tokens = vec![];
+        loop {
+            self.skip_whitespaces_and_comments()?;
+            let (line, column) = (self.line, self.column);
+            let (token_type, text) = match self.peek_char(0) {
+                None => (TokenType::Eof, "<eof>".to_owned()),
+                Some(character) if character.is_alphabetic() || character == '_' => {
+                    let mut text = String::new();
+                    while let Some(character) = self.peek_char(0).filter(|x| x.is_alphanumeric() || *x == '_') {
+                        text.push(character);
+                        self.next_char();
+                    }
+
+                    if KEYWORDS.contains(&&*text) { (TokenType::Keyword, text) }
+                    else { (TokenType::Name, text) }
+                }
+                Some(character) if character.is_ascii_digit() || (character == '.' && matches!(self.peek_char(1), Some(x) if x.is_ascii_digit())) => (TokenType::Number, self.read_number()?),
+                Some(character) if character == '"' || character == '\'' => (TokenType::String, self.read_string(character)?),
+                Some('[') if self.get_long_bracket_level().is_some() => {
+                    let level = self.get_long_bracket_level().unwrap();
+                    (TokenType::String, self.read_long_string(level, "string")?)
+                }
+                Some(_) => match SYMBOLS.iter().find(|x| x.chars().enumerate().all(|(index, character)| self.peek_char(index) == Some(character))) {
+                    Some(symbol) => {
+                        symbol.chars().for_each(|_| { self.next_char(); });
+                        (TokenType::Symbol, (*symbol).to_owned())
+                    }
+                    None => return Err(self.error(&format!("unexpected symbol near '{}'", self.peek_char(0).unwrap()))),
+                }
+            };
+
+            let is_eof = token_type == TokenType::Eof;
+            tokens.push(Token { token_type, text, line, column });
+            if is_eof { break Ok(tokens) }
+        }
+    }
+
+    /// This function skips whitespaces and comments, until the next token.
+    fn skip_whitespaces_and_comments(&mut self) -> Result<()> {
+        loop {
+            match self.peek_char(0) {
+                Some(character) if character.is_whitespace() => { self.next_char(); }
+                Some('-') if self.peek_char(1) == Some('-') => {
+                    self.next_char();
+                    self.next_char();
+                    match self.get_long_bracket_level() {
+                        Some(level) => { self.read_long_string(level, "comment")?; }
+                        None => while let Some(character) = self.peek_char(0) {
+                            if character == '\n' { break; }
+                            self.next_char();
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// This function returns the level of the long bracket (`[[`, `[=[`,...) in the current position, if there is one.
+    fn get_long_bracket_level(&self) -> Option<usize> {
+        if self.peek_char(0) != Some('[') { return None }
+        let mut level = 0;
+        while self.peek_char(level + 1) == Some('=') { level += 1; }
+        if self.peek_char(level + 1) == Some('[') { Some(level) } else { None }
+    }
+
+    /// This function reads a long string or comment, returning its contents.
+    fn read_long_string(&mut self, level: usize, kind: &str) -> Result<String> {
+        (0..level + 2).for_each(|_| { self.next_char(); });
+
+        // The first newline of a long string is ignored.
+        if self.peek_char(0) == Some('\r') { self.next_char(); }
+        if self.peek_char(0) == Some('\n') { self.next_char(); }
+
+        let mut text = String::new();
+        loop {
+            match self.next_char() {
+                Some(']') if (0..level).all(|x| self.peek_char(x) == Some('=')) && self.peek_char(level) == Some(']') => {
+                    (0..level + 1).for_each(|_| { self.next_char(); });
+                    break Ok(text);
+                }
+                Some(character) => text.push(character),
+                None => break Err(self.error(&format!("unfinished long {} near '<eof>'", kind))),
+            }
+        }
+    }
+
+    /// This function reads a string between quotes, processing its escape sequences.
+    fn read_string(&mut self, quote: char) -> Result<String> {
+        self.next_char();
+        let mut text = String::new();
+        loop {
+            if matches!(self.peek_char(0), Some('\n') | None) {
+                break Err(self.error("unfinished string"));
+            }
+
+            match self.next_char() {
+                Some(character) if character == quote => break Ok(text),
+                Some('\\') => match self.next_char() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('a') => text.push('\u{7}'),
+                    Some('b') => text.push('\u{8}'),
+                    Some('f') => text.push('\u{c}'),
+                    Some('v') => text.push('\u{b}'),
+                    Some(character) if character.is_ascii_digit() => {
+                        let mut value = character.to_digit(10).unwrap();
+                        for _ in 0..2 {
+                            match self.peek_char(0).and_then(|x| x.to_digit(10)) {
+                                Some(digit) => {
+                                    value = value * 10 + digit;
+                                    self.next_char();
+                                }
+                                None => break,
+                            }
+                        }
+
+                        if value > 255 { break Err(self.error("escape sequence too large")) }
+                        text.push(value as u8 as char);
+                    }
+                    Some(character) => text.push(character),
+                    None => break Err(self.error("unfinished string")),
+                },
+                Some(character) => text.push(character),
+                None => break Err(self.error("unfinished string")),
+            }
+        }
+    }
+
+    /// This function reads a number, checking it's valid.
+    fn read_number(&mut self) -> Result<String> {
+        let mut text = String::new();
+        while let Some(character) = self.peek_char(0) {
+            let is_exponent_sign = (character == '+' || character == '-') && text.ends_with(['e', 'E']) && !text.starts_with("0x") && !text.starts_with("0X");
+            if character.is_alphanumeric() || character == '_' || character == '.' || is_exponent_sign {
+                text.push(character);
+                self.next_char();
+            } else { break; }
+        }
+
+        let is_valid = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+            Some(hex) => !hex.is_empty() && hex.chars().all(|x| x.is_ascii_hexdigit()),
+            None => text.parse::<f64>().is_ok(),
+        };
+
+        if is_valid { Ok(text) }
+        else { Err(self.error(&format!("malformed number near '{}'", text))) }
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                           Implementation of Parser
+//---------------------------------------------------------------------------//
+
+/// Implementation of `Parser`.
+impl Parser {
+
+    /// This function returns the current token.
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index]
+    }
+
+    /// This function returns if the current token is the provided keyword or symbol.
+    fn check(&self, text: &str) -> bool {
+        let token = self.peek();
+        (token.token_type == TokenType::Keyword || token.token_type == TokenType::Symbol) && token.text == text
+    }
+
+    /// This function advances to the next token, returning the current one.
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.index].clone();
+        if token.token_type != TokenType::Eof {
+            self.index += 1;
+        }
+        token
+    }
+
+    /// This function returns a syntax error on the current token.
+    fn error(&self, message: &str) -> rpfm_error::Error {
+        let token = self.peek();
+        ErrorKind::LuaSyntaxError(token.line, token.column, format!("{} near '{}'", message, token.text)).into()
+    }
+
+    /// This function enters a nested block or expression, returning an error if there are too many nested levels.
+    fn enter_level(&mut self) -> Result<()> {
+        self.level += 1;
+        if self.level > MAX_SYNTAX_LEVELS {
+            let token = self.peek();
+            return Err(ErrorKind::LuaSyntaxError(token.line, token.column, "chunk has too many syntax levels".to_owned()).into());
+        }
+        Ok(())
+    }
+
+    /// This function leaves a nested block or expression.
+    fn leave_level(&mut self) {
+        self.level -= 1;
+    }
+
+    /// This function advances if the current token is the provided keyword or symbol, returning if it did it.
+    fn test_next(&mut self, text: &str) -> bool {
+        if self.check(text) {
+            self.next();
+            true
+        } else { false }
+    }
+
+    /// This function expects the provided keyword or symbol, returning an error if it's not there.
+    fn expect(&mut self, text: &str) -> Result<()> {
+        if self.test_next(text) { Ok(()) }
+        else { Err(self.error(&format!("'{}' expected", text))) }
+    }
+
+    /// This function expects the provided keyword or symbol closing a block opened in the provided line.
+    fn expect_match(&mut self, text: &str, opened_by: &str, line: usize) -> Result<()> {
+        if self.test_next(text) { Ok(()) }
+        else if line == self.peek().line { Err(self.error(&format!("'{}' expected", text))) }
+        else { Err(self.error(&format!("'{}' expected (to close '{}' at line {})", text, opened_by, line))) }
+    }
+
+    /// This function expects a name, returning it.
+    fn expect_name(&mut self) -> Result<Token> {
+        if self.peek().token_type == TokenType::Name { Ok(self.next()) }
+        else { Err(self.error("<name> expected")) }
+    }
+
+    /// This function returns if the current token ends a block.
+    fn is_block_end(&self) -> bool {
+        self.peek().token_type == TokenType::Eof || ["else", "elseif", "end", "until"].iter().any(|x| self.check(x))
+    }
+
+    /// This function parses a block of statements.
+    fn block(&mut self) -> Result<()> {
+        self.enter_level()?;
+        while !self.is_block_end() {
+            if self.check("return") {
+                self.next();
+                if !self.is_block_end() && !self.check(";") {
+                    self.expression_list()?;
+                }
+                self.test_next(";");
+
+                // Return must be the last statement of a block, so whatever comes after it must close the block.
+                break;
+            }
+
+            self.statement()?;
+        }
+        self.leave_level();
+        Ok(())
+    }
+
+    /// This function parses a single statement.
+    fn statement(&mut self) -> Result<()> {
+        let line = self.peek().line;
+        if self.test_next(";") || self.test_next("break") { return Ok(()) }
+
+        if self.test_next("if") {
+            self.expression()?;
+            self.expect("then")?;
+            self.block()?;
+            while self.test_next("elseif") {
+                self.expression()?;
+                self.expect("then")?;
+                self.block()?;
+            }
+            if self.test_next("else") {
+                self.block()?;
+            }
+            self.expect_match("end", "if", line)
+        }
+
+        else if self.test_next("while") {
+            self.expression()?;
+            self.expect("do")?;
+            self.block()?;
+            self.expect_match("end", "while", line)
+        }
+
+        else if self.test_next("do") {
+            self.block()?;
+            self.expect_match("end", "do", line)
+        }
+
+        else if self.test_next("for") {
+            self.expect_name()?;
+            if self.test_next("=") {
+                self.expression()?;
+                self.expect(",")?;
+                self.expression()?;
+                if self.test_next(",") {
+                    self.expression()?;
+                }
+            } else {
+                while self.test_next(",") {
+                    self.expect_name()?;
+                }
+                self.expect("in")?;
+                self.expression_list()?;
+            }
+            self.expect("do")?;
+            self.block()?;
+            self.expect_match("end", "for", line)
+        }
+
+        else if self.test_next("repeat") {
+            self.block()?;
+            self.expect_match("until", "repeat", line)?;
+            self.expression().map(|_| ())
+        }
+
+        else if self.test_next("function") {
+            self.expect_name()?;
+            while self.test_next(".") {
+                self.expect_name()?;
+            }
+            if self.test_next(":") {
+                self.expect_name()?;
+            }
+            self.function_body(line)
+        }
+
+        else if self.test_next("local") {
+            if self.test_next("function") {
+                self.expect_name()?;
+                self.function_body(line)
+            } else {
+                self.expect_name()?;
+                while self.test_next(",") {
+                    self.expect_name()?;
+                }
+                if self.test_next("=") {
+                    self.expression_list()?;
+                }
+                Ok(())
+            }
+        }
+
+        // Only calls and assignments are left.
+        else {
+            let (_, is_call) = self.suffixed_expression()?;
+            if self.check("=") || self.check(",") {
+                if is_call { return Err(self.error("syntax error")) }
+                while self.test_next(",") {
+                    let (_, is_call) = self.suffixed_expression()?;
+                    if is_call { return Err(self.error("syntax error")) }
+                }
+                self.expect("=")?;
+                self.expression_list().map(|_| ())
+            }
+            else if is_call { Ok(()) }
+            else { Err(self.error("syntax error")) }
+        }
+    }
+
+    /// This function parses the parameters and body of a function.
+    fn function_body(&mut self, line: usize) -> Result<()> {
+        self.expect("(")?;
+        if !self.check(")") {
+            loop {
+                if self.test_next("...") { break; }
+                self.expect_name()?;
+                if !self.test_next(",") { break; }
+            }
+        }
+        self.expect(")")?;
+        self.block()?;
+        self.expect_match("end", "function", line)
+    }
+
+    /// This function parses a list of expressions separated by commas.
+    fn expression_list(&mut self) -> Result<Vec<LuaArgument>> {
+        let mut expressions = vec![self.expression()?];
+        while self.test_next(",") {
+            expressions.push(self.expression()?);
+        }
+        Ok(expressions)
+    }
+
+    /// This function parses an expression.
+    fn expression(&mut self) -> Result<LuaArgument> {
+        self.sub_expression(0)
+    }
+
+    /// This function parses an expression, until it finds a binary operator with a priority lower than the provided limit.
+    fn sub_expression(&mut self, limit: u8) -> Result<LuaArgument> {
+        self.enter_level()?;
+        let mut expression = if self.check("not") || self.check("-") || self.check("#") {
+            self.next();
+            self.sub_expression(UNARY_PRIORITY)?;
+            LuaArgument::Other
+        } else {
+            self.simple_expression()?
+        };
+
+        while let Some((left, right)) = self.get_binary_priority() {
+            if left <= limit { break; }
+            self.next();
+            self.sub_expression(right)?;
+            expression = LuaArgument::Other;
+        }
+
+        self.leave_level();
+        Ok(expression)
+    }
+
+    /// This function returns the left and right priorities of the current token, if it's a binary operator.
+    fn get_binary_priority(&self) -> Option<(u8, u8)> {
+        let token = self.peek();
+        if token.token_type != TokenType::Keyword && token.token_type != TokenType::Symbol { return None }
+        match &*token.text {
+            "or" => Some((1, 1)),
+            "and" => Some((2, 2)),
+            "<" | ">" | "<=" | ">=" | "~=" | "==" => Some((3, 3)),
+            ".." => Some((5, 4)),
+            "+" | "-" => Some((6, 6)),
+            "*" | "/" | "%" => Some((7, 7)),
+            "^" => Some((10, 9)),
+            _ => None,
+        }
+    }
+
+    /// This function parses a simple expression, like a literal, a table or a function.
+    fn simple_expression(&mut self) -> Result<LuaArgument> {
+        let token = self.peek().clone();
+        match token.token_type {
+            TokenType::Number => {
+                self.next();
+                Ok(LuaArgument::Other)
+            }
+            TokenType::String => {
+                self.next();
+                Ok(LuaArgument::String(token.text))
+            }
+            TokenType::Keyword if token.text == "nil" || token.text == "true" || token.text == "false" => {
+                self.next();
+                Ok(LuaArgument::Other)
+            }
+            TokenType::Keyword if token.text == "function" => {
+                self.next();
+                self.function_body(token.line)?;
+                Ok(LuaArgument::Other)
+            }
+            TokenType::Symbol if token.text == "..." => {
+                self.next();
+                Ok(LuaArgument::Multiple)
+            }
+            TokenType::Symbol if token.text == "{" => {
+                self.table_constructor()?;
+                Ok(LuaArgument::Other)
+            }
+            _ => {
+                let (expression, _) = self.suffixed_expression()?;
+                Ok(expression)
+            }
+        }
+    }
+
+    /// This function parses a table constructor.
+    fn table_constructor(&mut self) -> Result<()> {
+        let line = self.peek().line;
+        self.expect("{")?;
+        while !self.check("}") {
+            if self.test_next("[") {
+                self.expression()?;
+                self.expect("]")?;
+                self.expect("=")?;
+            }
+            else if self.peek().token_type == TokenType::Name && matches!(self.tokens.get(self.index + 1), Some(x) if x.token_type == TokenType::Symbol && x.text == "=") {
+                self.next();
+                self.next();
+            }
+            self.expression()?;
+
+            if !self.test_next(",") && !self.test_next(";") { break; }
+        }
+        self.expect_match("}", "{", line)
+    }
+
+    /// This function parses a variable or call expression, like `a.b[c]:d(e)`, recording the calls on it.
+    ///
+    /// It returns the kind of value of the expression, and if the expression is a call.
+    fn suffixed_expression(&mut self) -> Result<(LuaArgument, bool)> {
+
+        // The path is only kept while it's made of names and calls, as we cannot follow anything else.
+        let mut path = if self.peek().token_type == TokenType::Name {
+            let token = self.next();
+            Some(vec![(LuaPathSegment::Field(token.text), token.line, token.column)])
+        }
+        else if self.check("(") {
+            let line = self.next().line;
+            self.expression()?;
+            self.expect_match(")", "(", line)?;
+            None
+        }
+        else {
+            return Err(self.error("unexpected symbol"));
+        };
+
+        let mut is_call = false;
+        loop {
+            if self.test_next(".") {
+                let token = self.expect_name()?;
+                if let Some(ref mut path) = path {
+                    path.push((LuaPathSegment::Field(token.text), token.line, token.column));
+                }
+                is_call = false;
+            }
+
+            else if self.test_next("[") {
+                self.expression()?;
+                self.expect("]")?;
+                path = None;
+                is_call = false;
+            }
+
+            else if self.test_next(":") {
+                let token = self.expect_name()?;
+                let arguments = self.call_arguments()?;
+                if let Some(ref mut path) = path {
+                    self.calls.push(LuaCall {
+                        receiver: path.iter().map(|(segment, _, _)| segment.clone()).collect(),
+                        name: token.text.to_owned(),
+                        is_method: true,
+                        arguments,
+                        line: token.line,
+                        column: token.column,
+                    });
+                    path.push((LuaPathSegment::Field(token.text), token.line, token.column));
+                    path.push((LuaPathSegment::Call, token.line, token.column));
+                }
+                is_call = true;
+            }
+
+            else if self.check("(") || self.check("{") || self.peek().token_type == TokenType::String {
+
+                // A call on a different line than the function is ambiguous in Lua 5.1.
+                if self.check("(") && self.index > 0 && self.tokens[self.index - 1].line != self.peek().line {
+                    return Err(self.error("ambiguous syntax (function call x new statement)"));
+                }
+
+                let arguments = self.call_arguments()?;
+                if let Some(ref mut path) = path {
+                    if let Some((LuaPathSegment::Field(name), line, column)) = path.last().cloned() {
+                        self.calls.push(LuaCall {
+                            receiver: path[..path.len() - 1].iter().map(|(segment, _, _)| segment.clone()).collect(),
+                            name,
+                            is_method: false,
+                            arguments,
+                            line,
+                            column,
+                        });
+                    }
+                    path.push((LuaPathSegment::Call, line_of(path), 0));
+                }
+                is_call = true;
+            }
+
+            else {
+                break;
+            }
+        }
+
+        let expression = if is_call { LuaArgument::Multiple } else { LuaArgument::Other };
+        Ok((expression, is_call))
+    }
+
+    /// This function parses the arguments of a call.
+    fn call_arguments(&mut self) -> Result<Vec<LuaArgument>> {
+        let token = self.peek().clone();
+        if token.token_type == TokenType::String {
+            self.next();
+            Ok(vec![LuaArgument::String(token.text)])
+        }
+        else if self.check("{") {
+            self.table_constructor()?;
+            Ok(vec![LuaArgument::Other])
+        }
+        else {
+            self.expect("(")?;
+            let arguments = if self.check(")") { vec![] } else { self.expression_list()? };
+            self.expect_match(")", "(", token.line)?;
+            Ok(arguments)
+        }
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                              Functions
+//---------------------------------------------------------------------------//
+
+/// This function returns the line of the last segment of a path.
+fn line_of(path: &[(LuaPathSegment, usize, usize)]) -> usize {
+    path.last().map_or(0, |(_, line, _)| *line)
+}
+
+/// This function returns the depth of the parenthesis, braces and angle brackets of the provided declaration.
+///
+/// The `>` of return arrows (`-->`) is not counted.
+fn get_depth(declaration: &str) -> i32 {
+    let mut depth = 0;
+    let mut previous = ' ';
+    for character in declaration.chars() {
+        match character {
+            '(' | '{' | '<' => depth += 1,
+            ')' | '}' => depth -= 1,
+            '>' if previous != '-' => depth -= 1,
+            _ => {}
+        }
+        previous = character;
+    }
+    depth
+}
+
+/// This function splits a declaration like `name: value` into its name and value.
+fn split_declaration(declaration: &str) -> Option<(String, &str)> {
+    let colon = declaration.find(':')?;
+    let name = declaration[..colon].trim();
+    if name.is_empty() || name.contains(char::is_whitespace) { return None }
+    Some((name.to_owned(), declaration[colon + 1..].trim()))
+}
+
+/// This function parses the signature of a function, like `method(name: string, value: number?) --> boolean`.
+fn parse_function(value: &str) -> Option<CAFunction> {
+    let (is_method, signature) = if let Some(signature) = value.strip_prefix("method") { (true, signature) }
+    else if let Some(signature) = value.strip_prefix("function") { (false, signature) }
+    else { return None };
+
+    let signature = signature.trim_start();
+    if !signature.starts_with('(') { return None }
+
+    // Find the parenthesis closing the parameters, and split them by the commas outside other parenthesis.
+    let mut depth = 0;
+    let mut parameters = vec![];
+    let mut current = String::new();
+    let mut end = None;
+    let mut previous = ' ';
+    for (index, character) in signature.char_indices().skip(1) {
+        match character {
+            '(' | '{' | '<' => depth += 1,
+            ')' | '}' if depth == 0 => {
+                end = Some(index);
+                break;
+            }
+            ')' | '}' => depth -= 1,
+            '>' if previous != '-' => depth -= 1,
+            ',' if depth == 0 => {
+                parameters.push(current.trim().to_owned());
+                current.clear();
+                previous = character;
+                continue;
+            }
+            _ => {}
+        }
+        current.push(character);
+        previous = character;
+    }
+
+    let end = end?;
+    if !current.trim().is_empty() || !parameters.is_empty() {
+        parameters.push(current.trim().to_owned());
+    }
+
+    let parameters = parameters.iter().map(|parameter| {
+        let (name, parameter_type) = match split_declaration(parameter) {
+            Some((name, parameter_type)) if !name.contains('(') => (name, parameter_type.to_owned()),
+            _ => (String::new(), parameter.to_owned()),
+        };
+
+        CAParameter {
+            is_optional: parameter_type.ends_with('?'),
+            is_variadic: name == "..." || parameter_type.contains("..."),
+            name,
+            parameter_type,
+        }
+    }).collect();
+
+    let return_type = signature[end + 1..].trim().strip_prefix("-->")
+        .map(|x| x.trim().trim_end_matches('?').to_owned())
+        .filter(|x| !x.is_empty());
+
+    Some(CAFunction {
+        is_method,
+        parameters,
+        return_type,
+    })
+}
@@ -10,27 +10,31 @@
 
 // Here should go all the functions related to the compresion/decompression of PackedFiles.
 
-use xz2::read::XzDecoder;
-use xz2::stream::Stream;
+use xz2::read::{XzDecoder, XzEncoder};
+use xz2::stream::{LzmaOptions, Stream};
 
-use std::env::temp_dir;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::{BufReader, Read, SeekFrom};
-
-use std::process::Command;
+use std::io::Read;
 use std::u64;
 
 use rpfm_error::{Error, ErrorKind, Result};
 use crate::common::encoder::Encoder;
-use crate::common::decoder::Decoder;
-use crate::SETTINGS;
-use crate::settings::ZIP_PATH;
+
+/// Preset used by CA for their compressed PackedFiles. It's the same as 7-Zip's LZMA Level 3.
+const LZMA_PRESET: u32 = 3;
+
+/// Size of the header of a standard LZMA Alone file: properties byte, dictionary size (u32) and uncompressed size (u64).
+const LZMA_ALONE_HEADER_SIZE: usize = 13;
+
+/// Size of the properties byte plus the dictionary size in a LZMA Alone header.
+const LZMA_ALONE_PROPERTIES_SIZE: usize = 5;
+
+/// Size of the header of a LZMA Alone file, as CA writes it: uncompressed size (u32), properties byte and dictionary size (u32).
+const CA_LZMA_HEADER_SIZE: usize = 9;
 
 /// This function decompress the data of a PackedFile, returning the decompressed data.
 pub fn decompress_data(data: &[u8]) -> Result<Vec<u8>> {
     if !data.is_empty() {
-        if data.len() >= 9 {
+        if data.len() >= CA_LZMA_HEADER_SIZE {
 
             // CA Tweaks their headers to remove 4 bytes per PackedFile, while losing +4GB File Compression Support.
             // We need to fix their headers so the normal LZMA lib can read them.
@@ -58,56 +62,28 @@ pub fn decompress_data(data: &[u8]) -> Result<Vec<u8>> {
 
 /// This function compress the data of a PackedFile, returning the compressed data.
 ///
-/// Now, some explanation: CA uses Non-Streamed LZMA1 (or LZMA Alone) compressed files.
-/// Xz, the `standard` linux lib to deal with LZMA files has a fucking exception for
-/// Non-Streamed LZMA1 files. So we can decode from it, but not encode to it.
-/// So we do it the hard way: write the uncompressed file to disk, call 7z, compress it
-/// to 7z LZMA1 Level 3 format, read the compressed file, and remove the 7z part.
-/// Sadly, this means we have to ship 7z with RPFM. But hey, we're not the ones doing a
-/// fucking exception to a known format because we don't want to support the original format.
+/// Now, some explanation: CA uses Non-Streamed LZMA1 (or LZMA Alone) compressed files, with a tweaked header.
+/// The standard LZMA Alone header is 13 bytes long: 1 byte for the properties, 4 for the dictionary size and
+/// 8 for the uncompressed size. CA's one is 9 bytes long: 4 for the uncompressed size (as u32, hence their
+/// lack of +4GB support), then the properties byte and the dictionary size.
+///
+/// So we compress the data as a normal LZMA Alone Level 3 file, then we fix the header so it's CA's one.
+/// This is the exact opposite of what `decompress_data` does.
 pub fn compress_data(data: &[u8]) -> Result<Vec<u8>> {
-
-    match SETTINGS.read().unwrap().paths.get(ZIP_PATH) {
-        Some(zip_path) => {
-            match zip_path {
-                Some(zip_path) => {
-
-                    // Prepare both paths, uncompressed and compressed.
-                    let mut uncompressed_path = temp_dir();
-                    let mut compressed_path = temp_dir();
-                    uncompressed_path.push("frodo_best_waifu");
-                    compressed_path.push("frodo_bestest_waifu.7z");
-
-                    // Get the data into the uncompressed file, and launch 7z.
-                    File::create(&uncompressed_path)?.write_all(data)?;
-                    Command::new(zip_path).arg("a").arg("-m0=lzma").arg("-mx=3").arg(&compressed_path).arg(&uncompressed_path).output()?;
-
-                    // Get the compressed LZMA data (and only that data) from the compressed file. To get it, we know:
-                    // - The header of a 7z file is 32 bytes.
-                    // - The bytes 12-16 are the offset of the footer from the end of the header.
-                    // - We have just one file, so the offset is the exact lenght of that file.
-                    // - Then we read the offset from the end of the header. And done.
-                    let mut reader = BufReader::new(File::open(&compressed_path)?);
-                    let mut footer_offset = vec![0; 4];
-                    reader.seek(SeekFrom::Start(12))?;
-                    reader.read_exact(&mut footer_offset)?;
-                    let compressed_data_lenght = footer_offset.decode_integer_u32(0)?;
-
-                    let mut compressed_data = vec![0; compressed_data_lenght as usize];
-                    reader.seek(SeekFrom::Start(32))?;
-                    reader.read_exact(&mut compressed_data)?;
-
-                    let mut fixed_data = vec![];
-                    fixed_data.encode_integer_i32(data.len() as i32);
-                    fixed_data.extend_from_slice(&[0x5D, 0x00, 0x00, 0x40, 0x00]);
-                    fixed_data.append(&mut compressed_data);
-
-                    Ok(fixed_data)
-                }
-                None => Err(ErrorKind::ZipFolderNotFound.into())
-            }
-        }
-
-        None => Err(ErrorKind::ZipFolderNotFound.into())
+    if data.len() > u32::MAX as usize { return Err(ErrorKind::PackedFileDataCouldNotBeCompressed.into()) }
+
+    let options = LzmaOptions::new_preset(LZMA_PRESET).map_err(|_| Error::from(ErrorKind::PackedFileDataCouldNotBeCompressed))?;
+    let stream = Stream::new_lzma_encoder(&options).map_err(|_| Error::from(ErrorKind::PackedFileDataCouldNotBeCompressed))?;
+    let mut encoder = XzEncoder::new_stream(data, stream);
+    let mut compressed_data = vec![];
+    if encoder.read_to_end(&mut compressed_data).is_err() || compressed_data.len() < LZMA_ALONE_HEADER_SIZE {
+        return Err(ErrorKind::PackedFileDataCouldNotBeCompressed.into());
     }
+
+    // The encoder doesn't write the uncompressed size in the header (it writes u64::MAX instead), so we have to write it ourselves.
+    let mut fixed_data = Vec::with_capacity(compressed_data.len() - LZMA_ALONE_HEADER_SIZE + CA_LZMA_HEADER_SIZE);
+    fixed_data.encode_integer_u32(data.len() as u32);
+    fixed_data.extend_from_slice(&compressed_data[..LZMA_ALONE_PROPERTIES_SIZE]);
+    fixed_data.extend_from_slice(&compressed_data[LZMA_ALONE_HEADER_SIZE..]);
+    Ok(fixed_data)
 }
@@ -0,0 +1,73 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `compression` module, to make sure what we compress can be read by what reads CA's files.
+!*/
+
+use std::path::PathBuf;
+
+use crate::common::decoder::Decoder;
+use crate::packedfile::PackedFileType;
+use super::PackFile;
+use super::compression::{compress_data, decompress_data};
+
+#[test]
+fn test_compress_header() {
+    let data = b"Frodo best waifu. Frodo best waifu. Frodo best waifu.".to_vec();
+    let compressed = compress_data(&data).unwrap();
+
+    // CA's header: uncompressed size, then the LZMA properties and dictionary size of a Level 3 compression.
+    assert_eq!(compressed.decode_integer_u32(0).unwrap(), data.len() as u32);
+    assert_eq!(&compressed[4..9], &[0x5D, 0x00, 0x00, 0x40, 0x00]);
+    assert_eq!(decompress_data(&compressed).unwrap(), data);
+}
+
+#[test]
+fn test_compress_empty() {
+    let compressed = compress_data(&[]).unwrap();
+    assert_eq!(compressed.decode_integer_u32(0).unwrap(), 0);
+    assert_eq!(decompress_data(&compressed).unwrap(), Vec::<u8>::new());
+}
+
+#[test]
+fn test_compress_pfh5() {
+    let pack_file = PackFile::read(&PathBuf::from("../test_files/PFH5_test.pack"), false).unwrap();
+    for packed_file in pack_file.get_ref_packed_files_all() {
+        let data = packed_file.get_raw_data().unwrap();
+        let compressed = compress_data(&data).unwrap();
+        assert_eq!(decompress_data(&compressed).unwrap(), data);
+
+        // Compressing the same data twice must give us the same result, or we'll break the checksums.
+        assert_eq!(compress_data(&data).unwrap(), compressed);
+    }
+}
+
+#[test]
+fn test_encode_pfh5_compressed() {
+
+    // Tables are never compressed, so we only check that the rest of the PackedFiles come back compressed and with the same data.
+    let pack_file_base = PackFile::read(&PathBuf::from("../test_files/PFH5_test.pack"), false).unwrap();
+    let mut pack_file_new = pack_file_base.clone();
+    pack_file_new.toggle_compression(true);
+    let path = std::env::temp_dir().join("rpfm_PFH5_test_compressed.pack");
+    pack_file_new.save(Some(path.to_path_buf())).unwrap();
+
+    let pack_file_new = PackFile::read(&path, true).unwrap();
+    for packed_file_base in pack_file_base.get_ref_packed_files_all() {
+        let packed_file_new = pack_file_new.get_ref_packed_file_by_path(packed_file_base.get_path()).unwrap();
+        match packed_file_base.get_packed_file_type_by_path() {
+            PackedFileType::DB | PackedFileType::Loc => assert!(!packed_file_new.get_ref_raw().get_compression_state()),
+            _ => assert!(packed_file_new.get_ref_raw().get_compression_state()),
+        }
+
+        assert_eq!(packed_file_base.get_raw_data().unwrap(), packed_file_new.get_raw_data().unwrap());
+    }
+}
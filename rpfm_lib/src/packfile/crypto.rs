@@ -48,29 +48,59 @@ pub fn decrypt_index_item_filename(ciphertext: &[u8], decrypted_size: u8, offset
     path
 }
 
+/// This function encrypts the size of a PackedFile. Requires:
+/// - 'plaintext': the size of the PackedFile.
+/// - 'packed_files_after_this_one': the amount of items after this one in the Index.
+pub fn encrypt_index_item_file_length(plaintext: u32, packed_files_after_this_one: u32) -> u32 {
+    !packed_files_after_this_one ^ plaintext ^ INDEX_U32_KEY
+}
+
+/// This function encrypts the path of a PackedFile, including his 00 terminator. Requires:
+/// - 'plaintext': the path of the PackedFile, with his folders separated by `\`.
+/// - 'decrypted_size': the decrypted size of the PackedFile.
+pub fn encrypt_index_item_filename(plaintext: &str, decrypted_size: u8) -> Vec<u8> {
+    plaintext.as_bytes().iter()
+        .chain(&[0])
+        .enumerate()
+        .map(|(index, character)| character ^ !decrypted_size ^ INDEX_STRING_KEY[index % INDEX_STRING_KEY.len()])
+        .collect()
+}
+
 // Function to decrypt a PackedFile's data. Just needs the data to decrypt.
 pub fn decrypt_packed_file(ciphertext: &[u8]) -> Vec<u8> {
+    apply_data_key(ciphertext)
+}
+
+// Function to encrypt a PackedFile's data. Just needs the data to encrypt.
+//
+// The encrypted data has the same size as the unencrypted one. If it needs to be aligned to 8 bytes, that's up to the PackFile.
+pub fn encrypt_packed_file(plaintext: &[u8]) -> Vec<u8> {
+    apply_data_key(plaintext)
+}
+
+// Function to apply the data key to a PackedFile's data. As it's a XOR, this both encrypts and decrypts the data.
+fn apply_data_key(data: &[u8]) -> Vec<u8> {
 
     // First, make sure the file ends in a multiple of 8. If not, extend it with zeros.
     // We need it because the decoding is done in packs of 8 bytes.
-    let mut ciphertext = Vec::from(ciphertext);
-    let size = ciphertext.len();
+    let mut data = Vec::from(data);
+    let size = data.len();
     let padding = 8 - (size % 8);
-    if padding < 8 { ciphertext.resize(size + padding, 0) };
+    if padding < 8 { data.resize(size + padding, 0) };
 
-    // Then decrypt the file in packs of 8. It's faster than in packs of 4.
-    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    // Then process the file in packs of 8. It's faster than in packs of 4.
+    let mut output = Vec::with_capacity(data.len());
     let mut edi: u32 = 0;
-    for _ in 0..ciphertext.len()/8 {
+    for _ in 0..data.len()/8 {
 
         let mut prod = (DATA_KEY * Wrapping(u64::from(!edi))).0;
         let esi = edi as usize;
-        prod ^= (&ciphertext[esi..esi + 8]).read_u64::<LittleEndian>().unwrap();
-        plaintext.write_u64::<LittleEndian>(prod).unwrap();
+        prod ^= (&data[esi..esi + 8]).read_u64::<LittleEndian>().unwrap();
+        output.write_u64::<LittleEndian>(prod).unwrap();
         edi += 8
     }
 
     // Remove the extra bytes we added in the first step.
-    plaintext.truncate(size);
-    plaintext
+    output.truncate(size);
+    output
 }
@@ -0,0 +1,50 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `crypto` module, to make sure what we encrypt can be decrypted back.
+!*/
+
+use super::crypto::*;
+
+#[test]
+fn test_crypto_index_item_file_length() {
+    for (size, packed_files_after_this_one) in &[(0, 0), (1, 5), (123_456, 2), (u32::MAX, 1000)] {
+        let encrypted = encrypt_index_item_file_length(*size, *packed_files_after_this_one);
+        assert_ne!(encrypted, *size);
+        assert_eq!(decrypt_index_item_file_length(encrypted, *packed_files_after_this_one), *size);
+    }
+}
+
+#[test]
+fn test_crypto_index_item_filename() {
+    let path = "db\\units_tables\\a_very_long_name_for_a_table_that_goes_over_the_length_of_the_key_used_by_the_index";
+    let size = 1234;
+    let mut encrypted = encrypt_index_item_filename(path, size as u8);
+    assert_eq!(encrypted.len(), path.len() + 1);
+    assert_ne!(&encrypted[..path.len()], path.as_bytes());
+
+    // The offset should end right after the terminator, even if there is more data after it.
+    encrypted.extend_from_slice(&[1, 2, 3]);
+    let mut offset = 0;
+    assert_eq!(decrypt_index_item_filename(&encrypted, size as u8, &mut offset), path);
+    assert_eq!(offset, path.len() + 1);
+}
+
+#[test]
+fn test_crypto_packed_file() {
+    for size in &[0, 1, 7, 8, 9, 64, 1001] {
+        let data = (0..*size).map(|x| (x % 251) as u8).collect::<Vec<u8>>();
+        let encrypted = encrypt_packed_file(&data);
+        assert_eq!(encrypted.len(), data.len());
+        if *size > 0 { assert_ne!(encrypted, data); }
+        assert_eq!(decrypt_packed_file(&encrypted), data);
+    }
+}
@@ -22,39 +22,54 @@ so you don't have to worry about that.
 use bitflags::bitflags;
 use csv::ReaderBuilder;
 use itertools::{Itertools, Either};
+use memmap2::Mmap;
 use serde_derive::{Serialize, Deserialize};
 use serde_json::{from_slice, to_string_pretty};
 use rayon::prelude::*;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::{fmt, fmt::Display};
 use std::fs::{DirBuilder, File};
 use std::io::{prelude::*, BufReader, BufWriter, SeekFrom, Read, Write};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 use rpfm_error::{Error, ErrorKind, Result};
 
-use crate::GAME_SELECTED;
 use crate::SCHEMA;
 use crate::SETTINGS;
-use crate::SUPPORTED_GAMES;
 use crate::common::{*, decoder::Decoder, encoder::Encoder};
+use crate::context::GameContext;
 use crate::dependencies::Dependencies;
 use crate::packfile::compression::*;
 use crate::packfile::crypto::*;
 use crate::packfile::packedfile::*;
 use crate::packedfile::{DecodedPackedFile, PackedFileType};
+use crate::packedfile::table::{DecodedData, MergeConflict, Table};
 use crate::packedfile::table::db::DB;
 use crate::packedfile::table::loc::{Loc, TSV_NAME_LOC};
+use crate::packedfile::table::migration::TableMigration;
+use crate::packedfile::table::port::TablePort;
+use crate::references::{KeyRename, MissingLocEntry, ReferenceIndex};
+use crate::schema::{Definition, Schema};
 
 mod compression;
 mod crypto;
 pub mod packedfile;
+pub mod project;
+
+#[cfg(test)]
+mod compression_test;
+
+#[cfg(test)]
+mod crypto_test;
 
 #[cfg(test)]
 mod packfile_test;
 
+#[cfg(test)]
+mod project_test;
+
 /// These consts are used for dealing with Time-related operations.
 const WINDOWS_TICK: i64 = 10_000_000;
 const SEC_TO_UNIX_EPOCH: i64 = 11_644_473_600;
@@ -90,12 +105,14 @@ const FILE_TYPE_RELEASE: u32 = 1;
 const FILE_TYPE_PATCH: u32 = 2;
 const FILE_TYPE_MOD: u32 = 3;
 const FILE_TYPE_MOVIE: u32 = 4;
+
+/// Size in bytes of the extra data at the end of PFH5 PackFiles with extended header (only ARENA).
+const EXTENDED_HEADER_TRAILING_DATA_SIZE: usize = 256;
 bitflags! {
 
     /// This represents the bitmasks a PackFile can have applied to his type.
     ///
-    /// Keep in mind that this lib supports decoding PackFiles with any of these flags enabled,
-    /// but it only supports enconding for the `HAS_INDEX_WITH_TIMESTAMPS` flag.
+    /// Keep in mind that this lib supports decoding and encoding PackFiles with any of these flags enabled.
     pub struct PFHFlags: u32 {
 
         /// Used to specify that the header of the PackFile is extended by 20 bytes. Used in Arena.
@@ -148,6 +165,34 @@ pub struct PackFile {
 
     /// Settings stored in the PackFile itself, to be able to share them between instalations.
     settings: PackFileSettings,
+
+    /// The extra data of the header, only used if the `HAS_EXTENDED_HEADER` flag is enabled.
+    extended_header: PFHExtendedHeader,
+}
+
+/// This struct contains the data of the extended header of a PackFile, and the extra data at the end of PFH5 PackFiles with it.
+///
+/// Only seen in Arena. We don't know what these values mean, so we just keep them as they are to be able to save the PackFile back.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PFHExtendedHeader {
+
+    /// First unknown value of the extended header.
+    pub unknown_1: u32,
+
+    /// Second unknown value of the extended header.
+    pub unknown_2: u32,
+
+    /// Third unknown value of the extended header.
+    pub unknown_3: u32,
+
+    /// Fourth unknown value of the extended header.
+    pub unknown_4: u32,
+
+    /// Fifth unknown value of the extended header.
+    pub unknown_5: u32,
+
+    /// The 256 bytes at the end of PFH5 PackFiles with extended header. Ignored in other PackFiles.
+    pub trailing_data: Vec<u8>,
 }
 
 /// This struct is a reduced version of the `PackFile` one, used to pass just the needed data to an UI.
@@ -479,6 +524,7 @@ impl PackFile {
 
             notes: None,
             settings: PackFileSettings::default(),
+            extended_header: PFHExtendedHeader::default(),
         }
     }
 
@@ -496,6 +542,7 @@ impl PackFile {
 
             notes: None,
             settings: PackFileSettings::default(),
+            extended_header: PFHExtendedHeader::default(),
         }
     }
 
@@ -696,6 +743,80 @@ impl PackFile {
         self.add_packed_files(&packed_files_to_add.iter().map(|x|x).collect::<Vec<&PackedFile>>(), overwrite)
     }
 
+    /// This function updates the `PackedFiles` of the `PackFile` with the changes in the provided paths from a source folder.
+    ///
+    /// The source folder is mapped to the root of the `PackFile`. Changed files (or files in changed folders) are added, overwriting
+    /// the current ones, and paths that no longer exist on disk are removed. Changed folders are synced entirely, so the `PackedFiles`
+    /// in them without a source file are removed too. Paths outside the source folder, and hidden or temporary files are ignored.
+    /// It returns the paths of the updated and removed `PackedFiles`.
+    pub fn update_from_folder(
+        &mut self,
+        folder: &Path,
+        changed_paths: &[PathBuf],
+    ) -> Result<(Vec<Vec<String>>, Vec<Vec<String>>)> {
+        let mut paths_to_add = vec![];
+        let mut removed_paths = vec![];
+        for path in changed_paths {
+            let packed_file_path = match path.strip_prefix(folder) {
+                Ok(relative_path) => relative_path.components().map(|x| x.as_os_str().to_string_lossy().to_string()).collect::<Vec<String>>(),
+                Err(_) => continue,
+            };
+
+            if Self::is_ignored_source_path(&packed_file_path) {
+                continue;
+            }
+
+            if path.is_file() {
+                paths_to_add.push((path.to_path_buf(), packed_file_path));
+            }
+            else if path.is_dir() {
+                let mut source_paths = HashSet::new();
+                for file_path in get_files_from_subdir(path)? {
+                    if let Ok(relative_path) = file_path.strip_prefix(folder) {
+                        let packed_file_path = relative_path.components().map(|x| x.as_os_str().to_string_lossy().to_string()).collect::<Vec<String>>();
+                        if !Self::is_ignored_source_path(&packed_file_path) {
+                            source_paths.insert(packed_file_path.to_vec());
+                            paths_to_add.push((file_path.to_path_buf(), packed_file_path));
+                        }
+                    }
+                }
+
+                // The files removed from the folder while we were not looking are only noticed here.
+                let mut paths = self.get_ref_packed_files_all_paths().into_iter()
+                    .filter(|x| x.starts_with(&packed_file_path) && x.len() > packed_file_path.len())
+                    .filter(|x| !source_paths.contains(*x) && !Self::is_ignored_source_path(x))
+                    .map(|x| x.to_vec())
+                    .collect::<Vec<Vec<String>>>();
+
+                if !paths.is_empty() {
+                    let paths_set = paths.iter().map(|x| &**x).collect::<HashSet<&[String]>>();
+                    self.packed_files.retain(|x| !paths_set.contains(&x.get_path()));
+                    removed_paths.append(&mut paths);
+                }
+            }
+            else if !packed_file_path.is_empty() {
+                let mut paths = self.get_packed_files_paths_by_path_start(&packed_file_path);
+                if self.packedfile_exists(&packed_file_path) {
+                    paths.push(packed_file_path.to_vec());
+                }
+
+                self.remove_packed_file_by_path(&packed_file_path);
+                self.remove_packed_files_by_path_start(&packed_file_path);
+                removed_paths.append(&mut paths);
+            }
+        }
+
+        paths_to_add.sort();
+        paths_to_add.dedup();
+        let updated_paths = self.add_from_files(&paths_to_add, true)?;
+        Ok((updated_paths, removed_paths))
+    }
+
+    /// This function returns if the provided path of a source folder belongs to a hidden or temporary file, like the swap files of editors.
+    fn is_ignored_source_path(path: &[String]) -> bool {
+        path.iter().any(|name| name.starts_with('.') || name.ends_with('~') || name.ends_with(".swp") || name.ends_with(".tmp"))
+    }
+
     /// This function is used to add a `PackedFile` from one `PackFile` into another.
     ///
     /// It's a ***Copy from another PackFile*** kind of function. It returns the PathTypes
@@ -817,7 +938,6 @@ impl PackFile {
     /// This function returns if the `PackFile` is editable or not.
     ///
     /// By *if is editable or not* I mean *If you can save it or not*. The conditions under which a PackFile is not editable are:
-    /// - All PackFiles with extended header or encrypted parts are not editable.
     /// - All PackFiles of type `Mod` or `Movie` are editable.
     /// - If you say CA PackFiles are not editable:
     ///   - All PackFiles of type `Boot`, `Release` or `Patch` are not editable.
@@ -827,11 +947,6 @@ impl PackFile {
 
         // If it's this very specific type, don't save under any circunstance.
         if let PFHFileType::Other(_) = self.pfh_file_type { false }
-
-        // If ANY of these bitmask is detected in the PackFile, disable all saving.
-        else if self.bitmask.contains(PFHFlags::HAS_ENCRYPTED_DATA) ||
-            self.bitmask.contains(PFHFlags::HAS_ENCRYPTED_INDEX) ||
-            self.bitmask.contains(PFHFlags::HAS_EXTENDED_HEADER) { false }
         else {
             self.pfh_file_type == PFHFileType::Mod ||
             self.pfh_file_type == PFHFileType::Movie ||
@@ -1297,6 +1412,16 @@ impl PackFile {
         self.bitmask = bitmask;
     }
 
+    /// This function returns a reference to the `PFHExtendedHeader` of the provided `PackFile`.
+    pub fn get_ref_extended_header(&self) -> &PFHExtendedHeader {
+        &self.extended_header
+    }
+
+    /// This function sets the `PFHExtendedHeader` of the provided `PackFile`.
+    pub fn set_extended_header(&mut self, extended_header: &PFHExtendedHeader) {
+        self.extended_header = extended_header.clone();
+    }
+
     /// This function remove all `PackedFiles` from a `PackFile`.
     pub fn remove_all_packedfiles(&mut self) {
         self.packed_files = vec![];
@@ -1491,10 +1616,31 @@ impl PackFile {
         name: &str,
         delete_source_paths: bool,
     ) -> Result<Vec<String>> {
+        self.merge_tables_with_schema(paths, name, delete_source_paths, SCHEMA.read().unwrap().as_ref())
+    }
 
-        // Get the schema, as we'll need it unlocked to decode all the files fast.
-        let schema = SCHEMA.read().unwrap();
-        let schema = if let Some(ref schema) = *schema { schema } else { return Err(ErrorKind::SchemaNotFound.into()) };
+    /// This function merges (if possible) the provided DB and LOC tables into one with the provided name, using the schema of the provided `GameContext`.
+    ///
+    /// NOTE: The merged table will be created in the folder of the first provided file.
+    pub fn merge_tables_with_context(
+        &mut self,
+        paths: &[Vec<String>],
+        name: &str,
+        delete_source_paths: bool,
+        context: &GameContext,
+    ) -> Result<Vec<String>> {
+        self.merge_tables_with_schema(paths, name, delete_source_paths, context.get_ref_schema())
+    }
+
+    /// This function merges (if possible) the provided DB and LOC tables into one with the provided name, using the provided schema.
+    fn merge_tables_with_schema(
+        &mut self,
+        paths: &[Vec<String>],
+        name: &str,
+        delete_source_paths: bool,
+        schema: Option<&Schema>,
+    ) -> Result<Vec<String>> {
+        let schema = schema.ok_or(ErrorKind::SchemaNotFound)?;
 
         let mut db_files = vec![];
         let mut loc_files = vec![];
@@ -1502,7 +1648,7 @@ impl PackFile {
         // Decode the files and put them in their respective list.
         for path in paths {
             if let Some(packed_file) = self.get_ref_mut_packed_file_by_path(path) {
-                match packed_file.decode_return_ref_no_locks(&schema)? {
+                match packed_file.decode_return_ref_no_locks(schema)? {
                     DecodedPackedFile::DB(table) => db_files.push(table.clone()),
                     DecodedPackedFile::Loc(table) => loc_files.push(table.clone()),
                     _ => return Err(ErrorKind::InvalidFilesForMerging.into())
@@ -1558,6 +1704,36 @@ impl PackFile {
         self.add_packed_file(&packed_file, true)
     }
 
+    /// This function merges the DB and Loc Tables of the provided `PackFile` into the ones with the same path in this `PackFile`.
+    ///
+    /// Each pair of tables is merged using the vanilla tables in the provided `Dependencies` as base, keeping our values on conflicts.
+    /// Tables only in the other `PackFile` are added as they are. It returns the path and the conflicts, or the error, of every table
+    /// of the other `PackFile`. Tables that fail to merge are left untouched.
+    pub fn merge_tables_from_packfile(&mut self, other: &Self, dependencies: &Dependencies) -> Vec<(Vec<String>, Result<Vec<MergeConflict>>)> {
+        let mut merges = vec![];
+        for other_packed_file in other.get_ref_packed_files_by_types(&[PackedFileType::DB, PackedFileType::Loc], false) {
+            let path = other_packed_file.get_path().to_vec();
+            let result = match self.get_ref_mut_packed_file_by_path(&path) {
+                Some(packed_file) => {
+                    let mut other_packed_file = other_packed_file.clone();
+                    match (packed_file.decode_return_ref(), other_packed_file.decode_return_ref()) {
+                        (Ok(ours), Ok(theirs)) => DecodedPackedFile::merge_tables(ours, theirs, dependencies)
+                            .map(|(merged, conflicts)| {
+                                packed_file.set_decoded(&merged);
+                                conflicts
+                            }),
+                        (Err(error), _) | (_, Err(error)) => Err(error),
+                    }
+                }
+                None => self.add_packed_file(other_packed_file, false).map(|_| vec![]),
+            };
+
+            merges.push((path, result));
+        }
+
+        merges
+    }
+
     /// This function is used to optimize a `PackFile` by removing extra useless data from it.
     ///
     /// Currently, this function removes:
@@ -1567,6 +1743,22 @@ impl PackFile {
     /// - Empty Loc tables (except if the table has the same name as his vanilla counterpart and certain setting is enabled).
     /// - XML files in map folders.
     pub fn optimize(&mut self, dependencies: &Dependencies) -> Vec<Vec<String>> {
+        let optimize_not_renamed_packedfiles = SETTINGS.read().unwrap().settings_bool["optimize_not_renamed_packedfiles"];
+        self.optimize_with_schema(dependencies, SCHEMA.read().unwrap().as_ref(), optimize_not_renamed_packedfiles)
+    }
+
+    /// This function is used to optimize a `PackFile` by removing extra useless data from it, using the schema, dependencies and settings of the provided `GameContext`.
+    ///
+    /// It removes the same data as `optimize`.
+    pub fn optimize_with_context(&mut self, context: &GameContext) -> Vec<Vec<String>> {
+        let optimize_not_renamed_packedfiles = context.get_ref_settings().settings_bool.get("optimize_not_renamed_packedfiles").cloned().unwrap_or(false);
+        self.optimize_with_schema(context.get_ref_dependencies(), context.get_ref_schema(), optimize_not_renamed_packedfiles)
+    }
+
+    /// This function is used to optimize a `PackFile` by removing extra useless data from it, using the provided schema.
+    ///
+    /// Tables cannot be decoded without a schema, so if there is none, only the XML files in map folders are removed.
+    fn optimize_with_schema(&mut self, dependencies: &Dependencies, schema: Option<&Schema>, optimize_not_renamed_packedfiles: bool) -> Vec<Vec<String>> {
 
         // List of PackedFiles to delete.
         let mut files_to_delete: Vec<Vec<String>> = vec![];
@@ -1594,30 +1786,24 @@ impl PackFile {
 
             // Unless we specifically wanted to, ignore the same-name-as-vanilla files,
             // as those are probably intended to overwrite vanilla files, not to be optimized.
-            if database_path_list.contains(&path) && !SETTINGS.read().unwrap().settings_bool["optimize_not_renamed_packedfiles"] { continue; }
+            if database_path_list.contains(&path) && !optimize_not_renamed_packedfiles { continue; }
 
             // If it's a DB table, try to optimize it.
             if path.len() == 3 && path[0] == "db" && !game_dbs.is_empty() {
 
                 // Try to decode our table.
-                match packed_file.decode_return_ref_mut() {
-                    Ok(data) => if let DecodedPackedFile::DB(db) = data {
-                        let is_empty = db.optimize_table(&game_dbs);
-                        if is_empty { files_to_delete.push(path.to_vec()); }
-                    },
-                    Err(_) => continue,
-                };
+                if let Some(Ok(DecodedPackedFile::DB(db))) = schema.map(|schema| packed_file.decode_return_ref_mut_no_locks(schema)) {
+                    let is_empty = db.optimize_table(&game_dbs);
+                    if is_empty { files_to_delete.push(path.to_vec()); }
+                }
             }
 
             // If it's a Loc table, try to optimize it.
             else if path.last().unwrap().ends_with(".loc") && !game_locs.is_empty() {
-                match packed_file.decode_return_ref_mut() {
-                    Ok(data) => if let DecodedPackedFile::Loc(loc) = data {
-                        let is_empty = loc.optimize_table(&game_locs);
-                        if is_empty { files_to_delete.push(path.to_vec()); }
-                    },
-                    Err(_) => continue,
-                };
+                if let Some(Ok(DecodedPackedFile::Loc(loc))) = schema.map(|schema| packed_file.decode_return_ref_mut_no_locks(schema)) {
+                    let is_empty = loc.optimize_table(&game_locs);
+                    if is_empty { files_to_delete.push(path.to_vec()); }
+                }
             }
 
             // If it's an xml in a map folder, remove it.
@@ -1633,6 +1819,195 @@ impl PackFile {
         files_to_delete
     }
 
+    /// This function migrates all the outdated DB Tables of the `PackFile` to the version used by the game, in one pass.
+    ///
+    /// It returns the path and the migration report of every table updated. Tables that cannot be decoded or updated are ignored.
+    pub fn update_tables(&mut self, dependencies: &Dependencies) -> Vec<(Vec<String>, TableMigration)> {
+        let dependency_database = dependencies.get_ref_dependency_database();
+        let mut migrations = vec![];
+        for packed_file in self.get_ref_mut_packed_files_by_type(PackedFileType::DB, false) {
+            let path = packed_file.get_path().to_vec();
+            if let Ok(data) = packed_file.decode_return_ref_mut() {
+                let is_outdated = if let DecodedPackedFile::DB(db) = data { db.is_outdated(dependency_database) } else { false };
+                if is_outdated {
+                    if let Ok(migration) = data.migrate_table(dependencies) {
+                        migrations.push((path, migration));
+                    }
+                }
+            }
+        }
+
+        migrations
+    }
+
+    /// This function ports all the DB and Loc Tables of the `PackFile` from the game of `source` to the game of `target`, in one pass.
+    ///
+    /// Tables are decoded with the schema of `source` and ported to the definitions of the same tables in `target`. The `PackFile`
+    /// is changed to the `PFHVersion` of `target`, so it can be saved for it. It returns the path and the port report, or the error,
+    /// of every table. Tables that fail to decode or port are left untouched, in the layout of `source`, unless `remove_failed` is true,
+    /// in which case they're removed from the `PackFile`. The references of the ported tables are checked once all of them are ported.
+    pub fn port_tables(&mut self, source: &GameContext, target: &GameContext, remove_failed: bool) -> Vec<(Vec<String>, Result<TablePort>)> {
+        let mut ports = vec![];
+        let mut decoded_tables = vec![];
+        for packed_file in self.get_ref_mut_packed_files_by_types(&[PackedFileType::DB, PackedFileType::Loc], false) {
+            let path = packed_file.get_path().to_vec();
+            match packed_file.decode_return_ref_with_context(source) {
+                Ok(data) => decoded_tables.push((path, data.clone())),
+                Err(error) => ports.push((path, Err(error))),
+            }
+        }
+
+        let mut ported_tables = vec![];
+        for (path, mut data) in decoded_tables {
+            match data.port_table(target) {
+                Ok(port) => {
+                    if let Some(packed_file) = self.get_ref_mut_packed_file_by_path(&path) {
+                        packed_file.set_decoded(&data);
+                    }
+                    ported_tables.push((path, port));
+                }
+                Err(error) => ports.push((path, Err(error))),
+            }
+        }
+
+        if remove_failed {
+            for (path, _) in &ports {
+                self.remove_packed_file_by_path(path);
+            }
+        }
+
+        // Only check the references once every ported table is in the layout of the target game.
+        for (path, mut port) in ported_tables {
+            if let Some(DecodedPackedFile::DB(table)) = self.get_ref_packed_file_by_path(&path).map(|x| x.get_ref_decoded()) {
+                port.unresolved_references = table.get_unresolved_references(self, target.get_ref_dependencies());
+            }
+            ports.push((path, Ok(port)));
+        }
+
+        self.set_pfh_version(target.get_ref_game_info().pfh_version[0]);
+        ports.sort_by(|(x, _), (y, _)| x.cmp(y));
+        ports
+    }
+
+    /// This function renames a key of a DB Table of the `PackFile`, along with everything in the `PackFile` referencing it.
+    ///
+    /// This includes the columns referencing the table in other DB Tables, the Loc entries of the key and, if `rename_in_scripts`
+    /// is true, the string literals equal to the key in Lua scripts. It returns the list of changes, which are not applied if `dry_run` is true.
+    pub fn rename_key(
+        &mut self,
+        schema: &Schema,
+        table_name: &str,
+        old_key: &str,
+        new_key: &str,
+        rename_in_scripts: bool,
+        dry_run: bool
+    ) -> Result<Vec<KeyRename>> {
+        self.get_ref_mut_packed_files_by_types(&[PackedFileType::DB, PackedFileType::Loc], false).par_iter_mut().for_each(|x| {
+            let _ = x.decode_no_locks(schema);
+        });
+
+        let renames = ReferenceIndex::new(schema).get_key_renames(self, table_name, old_key, new_key, rename_in_scripts)?;
+        if dry_run {
+            return Ok(renames);
+        }
+
+        let mut renames_by_path: BTreeMap<&str, Vec<&KeyRename>> = BTreeMap::new();
+        for rename in &renames {
+            renames_by_path.entry(&rename.path).or_default().push(rename);
+        }
+
+        // Edits are computed over copies of the files and only applied once all of them succeeded, so a failure doesn't leave the PackFile half-renamed.
+        let mut edited_files = vec![];
+        for (path, renames) in &renames_by_path {
+            let path = path.split('/').map(|x| x.to_owned()).collect::<Vec<String>>();
+            if let Some(packed_file) = self.get_ref_mut_packed_file_by_path(&path) {
+                let decoded = match packed_file.decode_return_ref_mut_no_locks(schema)? {
+                    DecodedPackedFile::DB(table) => {
+                        let mut table = table.clone();
+                        let data = Self::get_renamed_table_data(table.get_ref_definition(), table.get_table_data(), renames)?;
+                        table.set_table_data(&data)?;
+                        DecodedPackedFile::DB(table)
+                    }
+
+                    DecodedPackedFile::Loc(table) => {
+                        let mut table = table.clone();
+                        let data = Self::get_renamed_table_data(table.get_ref_definition(), table.get_table_data(), renames)?;
+                        table.set_table_data(&data)?;
+                        DecodedPackedFile::Loc(table)
+                    }
+
+                    DecodedPackedFile::Text(text) => {
+                        let mut text = text.clone();
+                        let contents = ReferenceIndex::rename_in_lua_strings(text.get_ref_contents(), old_key, new_key);
+                        text.set_contents(&contents);
+                        DecodedPackedFile::Text(text)
+                    }
+
+                    _ => continue,
+                };
+                edited_files.push((path, decoded));
+            }
+        }
+
+        for (path, decoded) in &edited_files {
+            if let Some(packed_file) = self.get_ref_mut_packed_file_by_path(path) {
+                packed_file.set_decoded(decoded);
+            }
+        }
+
+        Ok(renames)
+    }
+
+    /// This function returns the provided table data with the provided renames applied to it.
+    fn get_renamed_table_data(definition: &Definition, mut data: Vec<Vec<DecodedData>>, renames: &[&KeyRename]) -> Result<Vec<Vec<DecodedData>>> {
+        let fields = definition.get_fields_processed();
+        for rename in renames {
+            let (row, column) = (rename.row as usize - 1, rename.column as usize - 1);
+            let field = fields.get(column).ok_or(ErrorKind::Generic)?;
+            let cell = data.get_mut(row).and_then(|x| x.get_mut(column)).ok_or(ErrorKind::Generic)?;
+            *cell = DecodedData::StringU8(rename.new_value.to_owned()).convert_between_types(field.get_ref_field_type())?;
+        }
+        Ok(data)
+    }
+
+    /// This function adds placeholder Loc entries for the localised fields of the DB rows of the `PackFile` without one.
+    ///
+    /// The entries are appended to the Loc PackedFile in `path`, which is created if it doesn't exist. Entries already in the
+    /// dependencies of the `PackFile` are not added. If `dry_run` is true, the entries are only returned.
+    pub fn add_loc_stubs(&mut self, schema: &Schema, dependencies: &Dependencies, path: &[String], dry_run: bool) -> Result<Vec<MissingLocEntry>> {
+        self.get_ref_mut_packed_files_by_types(&[PackedFileType::DB, PackedFileType::Loc], false).par_iter_mut().for_each(|x| {
+            let _ = x.decode_no_locks(schema);
+        });
+
+        let entries = ReferenceIndex::new(schema).get_missing_loc_entries(self, dependencies);
+        if dry_run || entries.is_empty() {
+            return Ok(entries);
+        }
+
+        if self.get_ref_packed_file_by_path(path).is_none() {
+            let loc = Loc::new(schema.get_ref_last_definition_loc()?);
+            self.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::Loc(loc), path), false)?;
+        }
+
+        let packed_file = self.get_ref_mut_packed_file_by_path(path).ok_or(ErrorKind::PackedFileNotFound)?;
+        match packed_file.decode_return_ref_mut_no_locks(schema)? {
+            DecodedPackedFile::Loc(table) => {
+                let fields = table.get_ref_definition().get_fields_processed();
+                let mut data = table.get_table_data();
+                for entry in &entries {
+                    let mut row = Table::get_new_row(table.get_ref_definition());
+                    row[0] = DecodedData::StringU8(entry.key.to_owned()).convert_between_types(fields[0].get_ref_field_type())?;
+                    row[1] = DecodedData::StringU8(entry.text.to_owned()).convert_between_types(fields[1].get_ref_field_type())?;
+                    data.push(row);
+                }
+                table.set_table_data(&data)?;
+            }
+            _ => return Err(ErrorKind::LocPackedFileIsNotALocPackedFile.into()),
+        }
+
+        Ok(entries)
+    }
+
     /// This function is used to patch Warhammer Siege map packs so their AI actually works.
     ///
     /// This also removes the useles xml files left by Terry in the `PackFile`.
@@ -1730,23 +2105,50 @@ impl PackFile {
 
 
     /// This function is used to Mass-Import TSV files into a PackFile.
+    ///
+    /// TSV files from old versions of their table are migrated to the latest one. The reports of these migrations are returned
+    /// along with the paths of the removed and added PackedFiles.
     pub fn mass_import_tsv(
         &mut self,
         tsv_paths: &[PathBuf],
         name: Option<String>,
         overwrite: bool
-    ) -> Result<(Vec<Vec<String>>, Vec<Vec<String>>)> {
+    ) -> Result<(Vec<Vec<String>>, Vec<Vec<String>>, Vec<(Vec<String>, TableMigration)>)> {
+        self.mass_import_tsv_with_schema(tsv_paths, name, overwrite, SCHEMA.read().unwrap().as_ref())
+    }
+
+    /// This function is used to Mass-Import TSV files into a PackFile, using the schema of the provided `GameContext`.
+    pub fn mass_import_tsv_with_context(
+        &mut self,
+        tsv_paths: &[PathBuf],
+        name: Option<String>,
+        overwrite: bool,
+        context: &GameContext,
+    ) -> Result<(Vec<Vec<String>>, Vec<Vec<String>>, Vec<(Vec<String>, TableMigration)>)> {
+        self.mass_import_tsv_with_schema(tsv_paths, name, overwrite, context.get_ref_schema())
+    }
+
+    /// This function is used to Mass-Import TSV files into a PackFile, using the provided schema.
+    fn mass_import_tsv_with_schema(
+        &mut self,
+        tsv_paths: &[PathBuf],
+        name: Option<String>,
+        overwrite: bool,
+        schema: Option<&Schema>,
+    ) -> Result<(Vec<Vec<String>>, Vec<Vec<String>>, Vec<(Vec<String>, TableMigration)>)> {
 
         // Create the following lists:
         // - PackedFiles to add.
         // - PackedFiles to remove.
         // - Paths with errors.
+        // - Migrations of the tables from old versions.
         let mut packed_files: Vec<PackedFile> = vec![];
         let mut packed_files_to_remove = vec![];
         let mut error_files = vec![];
+        let mut migrations = vec![];
 
         // If there is not a schema, don't do anything.
-        if let Some(ref schema) = *SCHEMA.read().unwrap() {
+        if let Some(schema) = schema {
             for path in tsv_paths {
 
                 // The first row has the PackedFile Type (or name, in case of tables) and version.
@@ -1762,22 +2164,21 @@ impl PackFile {
                     let tsv_info = line.split('\t').collect::<Vec<&str>>();
                     if tsv_info.len() == 2 {
 
-                        // Get the type and the version of the table.
+                        // Get the type of the table, and check the version is valid.
                         let table_type = tsv_info[0];
-                        let table_version = match tsv_info[1].parse::<i32>() {
-                            Ok(version) => version,
-                            Err(_) => {
-                                error_files.push(path.to_string_lossy().to_string());
-                                continue
-                            }
-                        };
+                        if tsv_info[1].parse::<i32>().is_err() {
+                            error_files.push(path.to_string_lossy().to_string());
+                            continue
+                        }
 
-                        // Get the definition, depending on the table type and version.
+                        // Get the latest definition, depending on the table type. The version of the TSV is used to decode it.
                         // If the name is not specific for a type of file, we trat it as a DB Table.
                         match table_type {
                             TSV_NAME_LOC => {
-                                let definition = schema.get_ref_versioned_file_loc()?.get_version(table_version)?;
-                                if let Ok(table) = Loc::import_tsv(&definition, &path, &table_type) {
+                                let history = schema.get_ref_versioned_file_loc()?.get_version_list();
+                                let definition = history.iter().max_by_key(|x| x.get_version()).ok_or(ErrorKind::SchemaDefinitionNotFound)?;
+
+                                if let Ok((table, migration)) = Loc::import_tsv(definition, history, path, table_type) {
 
                                     // Depending on the name received, call it one thing or another.
                                     let name = match name {
@@ -1799,6 +2200,10 @@ impl PackFile {
                                     // If that path already exist in the PackFile, add it to the "remove" list.
                                     if self.packedfile_exists(&path) { packed_files_to_remove.push(path.to_vec()) }
 
+                                    if let Some(migration) = migration {
+                                        migrations.push((path.to_vec(), migration));
+                                    }
+
                                     // Create and add the new PackedFile to the list of PackedFiles to add.
                                     let mut packed_file = PackedFile::new(path, self.get_file_name());
                                     packed_file.set_decoded(&DecodedPackedFile::Loc(table));
@@ -1807,8 +2212,10 @@ impl PackFile {
                                 else { error_files.push(path.to_string_lossy().to_string()); }
                             }
                             _ => {
-                                let definition = schema.get_ref_versioned_file_db(&table_type)?.get_version(table_version)?;
-                                if let Ok(table) = DB::import_tsv(&definition, &path, &table_type) {
+                                let history = schema.get_ref_versioned_file_db(table_type)?.get_version_list();
+                                let definition = history.iter().max_by_key(|x| x.get_version()).ok_or(ErrorKind::SchemaDefinitionNotFound)?;
+
+                                if let Ok((table, migration)) = DB::import_tsv(definition, history, path, table_type) {
 
                                     // Depending on the name received, call it one thing or another.
                                     let name = match name {
@@ -1830,6 +2237,10 @@ impl PackFile {
                                     // If that path already exists in the PackFile, add it to the "remove" list.
                                     if self.packedfile_exists(&path) { packed_files_to_remove.push(path.to_vec()) }
 
+                                    if let Some(migration) = migration {
+                                        migrations.push((path.to_vec(), migration));
+                                    }
+
                                     // Create and add the new PackedFile to the list of PackedFiles to add.
                                     let mut packed_file = PackedFile::new(path, self.get_file_name());
                                     packed_file.set_decoded(&DecodedPackedFile::DB(table));
@@ -1861,7 +2272,7 @@ impl PackFile {
             // We add all the files to the PackFile, and return success.
             let packed_files_to_add = packed_files.iter().collect::<Vec<&PackedFile>>();
             self.add_packed_files(&packed_files_to_add, true)?;
-            Ok((packed_files_to_remove, tree_path))
+            Ok((packed_files_to_remove, tree_path, migrations))
         }
         else {
             Err(ErrorKind::SchemaNotFound.into())
@@ -1872,6 +2283,18 @@ impl PackFile {
     ///
     /// NOTE: this will OVERWRITE any existing file that has a name conflict with the TSV files provided.
     pub fn mass_export_tsv(&mut self, path_types: &[PathType], export_path: &PathBuf) -> Result<String> {
+        self.mass_export_tsv_with_schema(path_types, export_path, SCHEMA.read().unwrap().as_ref())
+    }
+
+    /// This function is used to Mass-Export TSV files from a PackFile, using the schema of the provided `GameContext`.
+    ///
+    /// NOTE: this will OVERWRITE any existing file that has a name conflict with the TSV files provided.
+    pub fn mass_export_tsv_with_context(&mut self, path_types: &[PathType], export_path: &PathBuf, context: &GameContext) -> Result<String> {
+        self.mass_export_tsv_with_schema(path_types, export_path, context.get_ref_schema())
+    }
+
+    /// This function is used to Mass-Export TSV files from a PackFile, using the provided schema.
+    fn mass_export_tsv_with_schema(&mut self, path_types: &[PathType], export_path: &PathBuf, schema: Option<&Schema>) -> Result<String> {
 
         // Lists of PackedFiles that couldn't be exported for one thing or another and exported PackedFile names,
         // so we make sure we don't overwrite those with the following ones.
@@ -1879,8 +2302,8 @@ impl PackFile {
         let mut exported_files = vec![];
 
         // We need the schema to export. If there is no schema, return an error.
-        match *SCHEMA.read().unwrap() {
-            Some(ref schema) => {
+        match schema {
+            Some(schema) => {
 
                 // Keep the PathTypes added so we can return them to the UI easely.
                 let path_types = PathType::dedup(path_types);
@@ -1956,37 +2379,24 @@ impl PackFile {
     }
 
     /// This function loads to memory the vanilla (made by CA) dependencies of a `PackFile`.
-    fn load_vanilla_dependency_packfiles(packed_files: &mut Vec<PackedFile>) {
+    fn load_vanilla_dependency_packfiles(packed_files: &mut Vec<PackedFile>, context: &GameContext) {
 
         // Get all the paths we need.
-        let main_db_pack_paths = get_game_selected_db_pack_path();
-        let main_loc_pack_paths = get_game_selected_loc_pack_path();
+        let main_db_pack_paths = context.get_db_pack_paths();
+        let main_loc_pack_paths = context.get_loc_pack_paths();
+        let pfh_version = context.get_ref_game_info().pfh_version[0];
 
         // Get all the DB Tables from the main DB `PackFiles`, if it's configured.
         if let Some(paths) = main_db_pack_paths {
-            if let Ok(pack_file) = PackFile::open_packfiles(&paths, true, false, false) {
-                for packed_file in pack_file.get_ref_packed_files_by_path_start(&["db".to_owned()]) {
-
-                    // Clone the PackedFile, and add it to the list.
-                    let mut packed_file = packed_file.clone();
-                    if packed_file.get_ref_mut_raw().load_data().is_ok() {
-                        packed_files.push(packed_file);
-                    }
-                }
+            if let Ok(pack_file) = PackFile::open_packfiles_for_game(&paths, true, true, false, false, context.get_ref_game_key(), pfh_version) {
+                Self::load_dependency_packed_files(packed_files, &pack_file.get_ref_packed_files_by_path_start(&["db".to_owned()]));
             }
         }
 
         // Get all the Loc PackedFiles from the main Loc `PackFiles`, if it's configured.
         if let Some(paths) = main_loc_pack_paths {
-             if let Ok(pack_file) = PackFile::open_packfiles(&paths, true, false, false) {
-                for packed_file in pack_file.get_ref_packed_files_by_path_end(&[".loc".to_owned()]) {
-
-                    // Clone the PackedFile, and add it to the list.
-                    let mut packed_file = packed_file.clone();
-                    if packed_file.get_ref_mut_raw().load_data().is_ok() {
-                        packed_files.push(packed_file);
-                    }
-                }
+            if let Ok(pack_file) = PackFile::open_packfiles_for_game(&paths, true, true, false, false, context.get_ref_game_key(), pfh_version) {
+                Self::load_dependency_packed_files(packed_files, &pack_file.get_ref_packed_files_by_path_end(&[".loc".to_owned()]));
             }
         }
     }
@@ -2000,63 +2410,29 @@ impl PackFile {
         contents_paths: &Option<Vec<PathBuf>>,
     ) {
 
-        // First we load the content `PackFiles`.
-        if let Some(ref paths) = contents_paths {
+        // First we load the content `PackFiles`, then the data ones.
+        for paths in contents_paths.iter().chain(data_paths.iter()) {
             if let Some(path) = paths.iter().find(|x| x.file_name().unwrap().to_string_lossy() == packfile_name) {
-                if let Ok(pack_file) = PackFile::open_packfiles(&[path.to_path_buf()], true, false, false) {
+                if let Ok(pack_file) = PackFile::read_mapped(path) {
 
                     // Add the current `PackFile` to the done list, so we don't get into cyclic dependencies.
                     already_loaded_dependencies.push(packfile_name.to_owned());
                     pack_file.get_packfiles_list().iter().for_each(|x| Self::load_single_dependency_packfile(packed_files, x, already_loaded_dependencies, data_paths, contents_paths));
-                    for packed_file in pack_file.get_ref_packed_files_by_path_start(&["db".to_owned()]) {
-
-                        // Clone the PackedFile, and add it to the list.
-                        let mut packed_file = packed_file.clone();
-                        if packed_file.get_ref_mut_raw().load_data().is_ok() {
-                            packed_files.push(packed_file);
-                        }
-                    }
-
-                    for packed_file in pack_file.get_ref_packed_files_by_path_end(&["loc".to_owned()]) {
-
-                        // Clone the PackedFile, and add it to the list.
-                        let mut packed_file = packed_file.clone();
-                        if packed_file.get_ref_mut_raw().load_data().is_ok() {
-                            packed_files.push(packed_file);
-                        }
-                    }
+                    Self::load_dependency_packed_files(packed_files, &pack_file.get_ref_packed_files_by_path_start(&["db".to_owned()]));
+                    Self::load_dependency_packed_files(packed_files, &pack_file.get_ref_packed_files_by_path_end(&["loc".to_owned()]));
                 }
             }
         }
+    }
 
-        // Then we load the data `PackFiles`.
-        if let Some(ref paths) = data_paths {
-            if let Some(path) = paths.iter().find(|x| x.file_name().unwrap().to_string_lossy() == packfile_name) {
-                if let Ok(pack_file) = PackFile::open_packfiles(&[path.to_path_buf()], true, false, false) {
-
-                    // Add the current `PackFile` to the done list, so we don't get into cyclic dependencies.
-                    already_loaded_dependencies.push(packfile_name.to_owned());
-                    pack_file.get_packfiles_list().iter().for_each(|x| Self::load_single_dependency_packfile(packed_files, x, already_loaded_dependencies, data_paths, contents_paths));
-                    for packed_file in pack_file.get_ref_packed_files_by_path_start(&["db".to_owned()]) {
-
-                        // Clone the PackedFile, and add it to the list.
-                        let mut packed_file = packed_file.clone();
-                        if packed_file.get_ref_mut_raw().load_data().is_ok() {
-                            packed_files.push(packed_file);
-                        }
-                    }
-
-                    for packed_file in pack_file.get_ref_packed_files_by_path_end(&["loc".to_owned()]) {
-
-                        // Clone the PackedFile, and add it to the list.
-                        let mut packed_file = packed_file.clone();
-                        if packed_file.get_ref_mut_raw().load_data().is_ok() {
-                            packed_files.push(packed_file);
-                        }
-                    }
-                }
-            }
-        }
+    /// This function clones the provided `PackedFiles` of a dependency `PackFile`, loads them to memory in parallel, and adds them to the list.
+    ///
+    /// `PackedFiles` that fail to load are ignored.
+    fn load_dependency_packed_files(packed_files: &mut Vec<PackedFile>, packed_files_to_load: &[&PackedFile]) {
+        packed_files.par_extend(packed_files_to_load.par_iter().filter_map(|packed_file| {
+            let mut packed_file = (*packed_file).clone();
+            if packed_file.get_ref_mut_raw().load_data().is_ok() { Some(packed_file) } else { None }
+        }));
     }
 
     /// This function loads to memory the custom (made by modders) dependencies of a `PackFile`.
@@ -2066,10 +2442,11 @@ impl PackFile {
     fn load_custom_dependency_packfiles(
         packed_files: &mut Vec<PackedFile>,
         pack_file_names: &[String],
+        context: &GameContext,
     ) {
 
-        let data_packs_paths = get_game_selected_data_packfiles_paths();
-        let content_packs_paths = get_game_selected_content_packfiles_paths();
+        let data_packs_paths = context.get_data_packfiles_paths();
+        let content_packs_paths = context.get_content_packfiles_paths();
         let mut loaded_packfiles = vec![];
 
         pack_file_names.iter().for_each(|x| Self::load_single_dependency_packfile(packed_files, x, &mut loaded_packfiles, &data_packs_paths, &content_packs_paths));
@@ -2080,30 +2457,91 @@ impl PackFile {
     /// This function loads to memory all DB and Loc `PackedFiles` from vanilla `PackFiles` and
     /// from any `PackFile` the provided `PackFile` has as a dependency.
     pub fn load_all_dependency_packfiles(dependencies: &[String]) -> Vec<PackedFile> {
+        match GameContext::from_game_selected() {
+            Ok(context) => Self::load_all_dependency_packfiles_with_context(dependencies, &context),
+            Err(_) => vec![],
+        }
+    }
+
+    /// This function loads to memory the dependencies of a `PackFile`, using the game of the provided `GameContext`.
+    ///
+    /// Otherwise, it works like `load_all_dependency_packfiles`.
+    pub fn load_all_dependency_packfiles_with_context(dependencies: &[String], context: &GameContext) -> Vec<PackedFile> {
 
         // Create the empty list.
         let mut packed_files = vec![];
 
-        Self::load_vanilla_dependency_packfiles(&mut packed_files);
-        Self::load_custom_dependency_packfiles(&mut packed_files, dependencies);
+        Self::load_vanilla_dependency_packfiles(&mut packed_files, context);
+        Self::load_custom_dependency_packfiles(&mut packed_files, dependencies, context);
 
         packed_files
     }
 
+    /// This function returns the paths of all the `PackedFiles` in the vanilla `PackFiles` and in any `PackFile`
+    /// the provided `PackFile` has as a dependency, lowercased and joined with `/`.
+    ///
+    /// Only the indexes of the `PackFiles` are read, so this doesn't load any data to memory.
+    pub fn load_all_dependency_packed_file_paths(dependencies: &[String]) -> HashSet<String> {
+        match GameContext::from_game_selected() {
+            Ok(context) => Self::load_all_dependency_packed_file_paths_with_context(dependencies, &context),
+            Err(_) => HashSet::new(),
+        }
+    }
+
+    /// This function returns the paths of all the `PackedFiles` in the dependencies of a `PackFile`, using the game of the provided `GameContext`.
+    ///
+    /// Otherwise, it works like `load_all_dependency_packed_file_paths`.
+    pub fn load_all_dependency_packed_file_paths_with_context(dependencies: &[String], context: &GameContext) -> HashSet<String> {
+        let mut paths = HashSet::new();
+        if let Ok(pack_file) = Self::open_all_ca_packfiles_with_context(context) {
+            paths.extend(pack_file.get_ref_packed_files_all_paths().iter().map(|x| x.join("/").to_lowercase()));
+        }
+
+        // Custom dependencies are searched in the content folder first, then in the data folder, following their own dependencies.
+        let data_packs_paths = context.get_data_packfiles_paths();
+        let content_packs_paths = context.get_content_packfiles_paths();
+        let mut loaded_packfiles = vec![];
+        let mut packfiles_to_load = dependencies.to_vec();
+        while let Some(packfile_name) = packfiles_to_load.pop() {
+            if loaded_packfiles.contains(&packfile_name) { continue; }
+            loaded_packfiles.push(packfile_name.to_owned());
+
+            let path = content_packs_paths.iter().chain(data_packs_paths.iter()).flatten().find(|x| x.file_name().unwrap().to_string_lossy() == packfile_name);
+            if let Some(path) = path {
+                if let Ok(pack_file) = PackFile::open_packfiles_with_context(&[path.to_path_buf()], true, false, false, context) {
+                    paths.extend(pack_file.get_ref_packed_files_all_paths().iter().map(|x| x.join("/").to_lowercase()));
+                    packfiles_to_load.extend(pack_file.get_packfiles_list().iter().cloned());
+                }
+            }
+        }
+
+        paths
+    }
+
     /// This function allows you to open all CA PackFiles as one for the currently selected Game.
     ///
     /// This function tries to get the list of CA PackFile of the currently selected game from the manifest.txt on /data,
     /// then it tries to open them all as one. Simple and effective.
     pub fn open_all_ca_packfiles() -> Result<Self> {
-        let data_path = get_game_selected_data_path().ok_or_else(|| ErrorKind::GameSelectedPathNotCorrectlyConfigured)?;
-        let manifest = Manifest::read_from_game_selected()?;
+        Self::open_all_ca_packfiles_with_context(&GameContext::from_game_selected()?)
+    }
+
+    /// This function allows you to open all CA PackFiles as one for the game of the provided `GameContext`.
+    ///
+    /// Otherwise, it works like `open_all_ca_packfiles`.
+    ///
+    /// NOTE: Unlike the dependencies, these PackFiles are lazy-loaded from disk instead of mapped to memory, as the returned `PackFile`
+    /// is meant to be kept open, and a map kept open crashes the program if the game updates the PackFile while we have it.
+    pub fn open_all_ca_packfiles_with_context(context: &GameContext) -> Result<Self> {
+        let data_path = context.get_data_path().ok_or(ErrorKind::GameSelectedPathNotCorrectlyConfigured)?;
+        let manifest = Manifest::read_from_data_path(&data_path)?;
         let pack_file_names = manifest.0.iter().filter_map(|x| if x.relative_path.ends_with(".pack") { Some(x.relative_path.to_owned()) } else { None }).collect::<Vec<String>>();
         let pack_file_paths = pack_file_names.iter().map(|x| {
             let mut pack_file_path = data_path.to_path_buf();
             pack_file_path.push(x);
             pack_file_path
         }).collect::<Vec<PathBuf>>();
-        Self::open_packfiles(&pack_file_paths, true, true, true)
+        Self::open_packfiles_with_context(&pack_file_paths, true, true, true, context)
     }
 
     /// This function allows you to open one or more `PackFiles`.
@@ -2120,11 +2558,41 @@ impl PackFile {
         ignore_mods: bool,
         lock_packfile: bool
     ) -> Result<Self> {
+        Self::open_packfiles_with_context(packs_paths, use_lazy_loading, ignore_mods, lock_packfile, &GameContext::from_game_selected()?)
+    }
+
+    /// This function allows you to open one or more `PackFiles`, merging them following the rules of the game of the provided `GameContext`.
+    ///
+    /// Otherwise, it works like `open_packfiles`.
+    pub fn open_packfiles_with_context(
+        packs_paths: &[PathBuf],
+        use_lazy_loading: bool,
+        ignore_mods: bool,
+        lock_packfile: bool,
+        context: &GameContext,
+    ) -> Result<Self> {
+        let pfh_version = context.get_ref_game_info().pfh_version[0];
+        Self::open_packfiles_for_game(packs_paths, use_lazy_loading, false, ignore_mods, lock_packfile, context.get_ref_game_key(), pfh_version)
+    }
+
+    /// This function opens one or more `PackFiles`, using the provided game key and `PFHVersion` for the merged `PackFile`.
+    ///
+    /// If `use_mmap` is true, the `PackFiles` are mapped to memory instead of loaded. Like with `read_mapped`, only use it to copy
+    /// the PackedFiles you need to memory, then drop the `PackFile` right away.
+    fn open_packfiles_for_game(
+        packs_paths: &[PathBuf],
+        use_lazy_loading: bool,
+        use_mmap: bool,
+        ignore_mods: bool,
+        lock_packfile: bool,
+        game_key: &str,
+        pfh_version: PFHVersion,
+    ) -> Result<Self> {
 
         // If we just have one `PackFile`, just read it. No fancy logic needed. If you're an asshole and tried to break this
         // by passing it no paths, enjoy the error.
         if packs_paths.is_empty() { return Err(ErrorKind::PackFileNoPathProvided.into()) }
-        if packs_paths.len() == 1 { Self::read(&packs_paths[0], use_lazy_loading) }
+        if packs_paths.len() == 1 { Self::read_packfile(&packs_paths[0], use_lazy_loading, use_mmap) }
 
         // Otherwise, read all of them into a *fake* `PackFile` and take care of the duplicated files like the game will do.
         else {
@@ -2133,8 +2601,7 @@ impl PackFile {
             let mut packs_paths = packs_paths.iter().filter(|x| x.is_file()).collect::<Vec<&PathBuf>>();
             packs_paths.sort_by_key(|x| x.file_name().unwrap().to_string_lossy().to_string());
 
-            let pfh_version = SUPPORTED_GAMES.get(&**GAME_SELECTED.read().unwrap()).unwrap().pfh_version[0];
-            let pfh_name = if ignore_mods { GAME_SELECTED.read().unwrap().to_owned() } else { String::from("merged_mod.pack")};
+            let pfh_name = if ignore_mods { game_key.to_owned() } else { String::from("merged_mod.pack")};
             let mut pack_file = Self::new_with_name(&pfh_name, pfh_version);
 
             // Read all the `PackFiles`, one by one, and separate their files by `PFHFileType`.
@@ -2144,7 +2611,7 @@ impl PackFile {
             let mut mod_files = vec![];
             let mut movie_files = vec![];
             for path in packs_paths {
-                match Self::read(&path, use_lazy_loading) {
+                match Self::read_packfile(path, use_lazy_loading, use_mmap) {
                     Ok(pack) => match pack.get_pfh_file_type() {
                         PFHFileType::Boot => boot_files.append(&mut pack.get_packed_files_all()),
                         PFHFileType::Release => release_files.append(&mut pack.get_packed_files_all()),
@@ -2199,6 +2666,23 @@ impl PackFile {
         file_path: &PathBuf,
         use_lazy_loading: bool
     ) -> Result<Self> {
+        Self::read_packfile(file_path, use_lazy_loading, false)
+    }
+
+    /// This function reads the content of a PackFile into a `PackFile` struct, mapping the PackFile to memory instead of loading its data.
+    ///
+    /// The map is kept until all its PackedFiles are loaded to memory or dropped, and the program crashes if another program truncates
+    /// the PackFile while it's mapped. So only use it to copy the PackedFiles you need to memory, then drop the `PackFile` right away.
+    fn read_mapped(file_path: &PathBuf) -> Result<Self> {
+        Self::read_packfile(file_path, true, true)
+    }
+
+    /// This function reads the content of a PackFile into a `PackFile` struct, mapping it to memory if `use_mmap` is true.
+    fn read_packfile(
+        file_path: &PathBuf,
+        use_lazy_loading: bool,
+        use_mmap: bool,
+    ) -> Result<Self> {
 
         // Check if what we received is even a `PackFile`.
         if !file_path.file_name().unwrap().to_string_lossy().to_string().ends_with(".pack") { return Err(ErrorKind::OpenPackFileInvalidExtension.into()) }
@@ -2256,6 +2740,15 @@ impl PackFile {
             PFHVersion::PFH0 => 0
         };
 
+        // If the PackFile has an extended header, get its data. Only PFH5 and PFH4 PackFiles can have it.
+        if pack_file_decoded.bitmask.contains(PFHFlags::HAS_EXTENDED_HEADER) && (pack_file_decoded.pfh_version == PFHVersion::PFH5 || pack_file_decoded.pfh_version == PFHVersion::PFH4) {
+            pack_file_decoded.extended_header.unknown_1 = buffer.decode_integer_u32(28)?;
+            pack_file_decoded.extended_header.unknown_2 = buffer.decode_integer_u32(32)?;
+            pack_file_decoded.extended_header.unknown_3 = buffer.decode_integer_u32(36)?;
+            pack_file_decoded.extended_header.unknown_4 = buffer.decode_integer_u32(40)?;
+            pack_file_decoded.extended_header.unknown_5 = buffer.decode_integer_u32(44)?;
+        }
+
         // Ensure the PackFile has all the data needed for the index. If the PackFile's data is encrypted
         // and the PackFile is PFH5, due to how the encryption works, the data should start in a multiple of 8.
         let mut data_position = u64::from(buffer.len() as u32 + pack_file_index_size + packed_file_index_size);
@@ -2308,9 +2801,14 @@ impl PackFile {
             PFHVersion::PFH0 => 4
         };
 
-        // Prepare the needed stuff to read the PackedFiles.
+        // Prepare the needed stuff to read the PackedFiles. If we're going to map the PackFile, we map the same file we opened.
+        // This is unsafe because, if another program truncates the PackFile while it's mapped, touching the lost part of the map
+        // crashes the program. The `FileStamp` checks can't prevent that, as the data is used after the check. What keeps this
+        // sound enough is that mapped PackFiles only live while their dependency PackedFiles are copied to memory.
         let mut index_position: usize = 0;
-        let pack_file = Arc::new(Mutex::new(pack_file));
+        let pack_file = Arc::new(pack_file.into_inner());
+        let file_stamp = FileStamp::new(&pack_file)?;
+        let mmap = if use_mmap { Some(Arc::new(unsafe { Mmap::map(&pack_file)? })) } else { None };
         for packed_files_to_decode in (0..packed_file_count).rev() {
 
             // Get his size. If it's encrypted, decrypt it first.
@@ -2338,9 +2836,9 @@ impl PackFile {
                 }
             } else { 0 };
 
-            // Update his offset, and get his compression data if it has it.
+            // Update his offset, and get his compression data if it has it. Arena PackFiles use the PFH4 format, so they don't have it.
             index_position += packed_file_index_path_offset;
-            let is_compressed = if let PFHVersion::PFH5 = pack_file_decoded.pfh_version {
+            let is_compressed = if pack_file_decoded.pfh_version == PFHVersion::PFH5 && !pack_file_decoded.bitmask.contains(PFHFlags::HAS_EXTENDED_HEADER) {
                 if let Ok(true) = packed_file_index.decode_bool(index_position - 1) { true }
                 else { false }
             } else { false };
@@ -2359,13 +2857,25 @@ impl PackFile {
                 timestamp,
                 is_compressed,
                 if pack_file_decoded.bitmask.contains(PFHFlags::HAS_ENCRYPTED_DATA) { Some(pack_file_decoded.pfh_version) } else { None },
-                PackedFileData::OnDisk(RawOnDisk::new(
-                    pack_file.clone(),
-                    data_position,
-                    size,
-                    is_compressed,
-                    if pack_file_decoded.bitmask.contains(PFHFlags::HAS_ENCRYPTED_DATA) { Some(pack_file_decoded.pfh_version) } else { None },
-                ))
+                match mmap {
+                    Some(ref mmap) => PackedFileData::OnMmap(RawOnMmap::new(
+                        pack_file.clone(),
+                        mmap.clone(),
+                        data_position,
+                        size,
+                        is_compressed,
+                        if pack_file_decoded.bitmask.contains(PFHFlags::HAS_ENCRYPTED_DATA) { Some(pack_file_decoded.pfh_version) } else { None },
+                        file_stamp,
+                    )),
+                    None => PackedFileData::OnDisk(RawOnDisk::new(
+                        pack_file.clone(),
+                        data_position,
+                        size,
+                        is_compressed,
+                        if pack_file_decoded.bitmask.contains(PFHFlags::HAS_ENCRYPTED_DATA) { Some(pack_file_decoded.pfh_version) } else { None },
+                        file_stamp,
+                    )),
+                }
             );
 
             let mut packed_file = PackedFile::new_from_raw(&raw_data);
@@ -2406,7 +2916,13 @@ impl PackFile {
         // If at this point we have not reached the end of the PackFile, there is something wrong with it.
         // NOTE: Arena PackFiles have extra data at the end. If we detect one of those PackFiles, take that into account.
         if pack_file_decoded.pfh_version == PFHVersion::PFH5 && pack_file_decoded.bitmask.contains(PFHFlags::HAS_EXTENDED_HEADER) {
-            if data_position + 256 != pack_file_len { return Err(ErrorKind::PackFileSizeIsNotWhatWeExpect(pack_file_len, data_position).into()) }
+            if data_position + EXTENDED_HEADER_TRAILING_DATA_SIZE as u64 != pack_file_len { return Err(ErrorKind::PackFileSizeIsNotWhatWeExpect(pack_file_len, data_position).into()) }
+
+            let mut trailing_data = vec![0; EXTENDED_HEADER_TRAILING_DATA_SIZE];
+            let mut pack_file = &*pack_file;
+            pack_file.seek(SeekFrom::Start(data_position))?;
+            pack_file.read_exact(&mut trailing_data)?;
+            pack_file_decoded.extended_header.trailing_data = trailing_data;
         }
         else if data_position != pack_file_len { return Err(ErrorKind::PackFileSizeIsNotWhatWeExpect(pack_file_len, data_position).into()) }
 
@@ -2422,9 +2938,34 @@ impl PackFile {
     /// If no path is passed, the `PackFile` will be saved in his current path.
     /// If a path is passed as `new_path` the `PackFile` will be saved in that path.
     pub fn save(&mut self, new_path: Option<PathBuf>) -> Result<()> {
+        self.timestamp = get_current_time();
+        self.save_keeping_timestamp(new_path)
+    }
+
+    /// This function tries to save a `PackFile` to a file in the filesystem, without updating its timestamp.
+    ///
+    /// This is useful to get reproducible PackFiles. Otherwise, it works like `save`.
+    pub fn save_keeping_timestamp(&mut self, new_path: Option<PathBuf>) -> Result<()> {
+        self.save_keeping_timestamp_with_context(new_path, &GameContext::from_game_selected()?)
+    }
+
+    /// This function tries to save a `PackFile` to a file in the filesystem, for the game and with the settings of the provided `GameContext`.
+    ///
+    /// Otherwise, it works like `save`.
+    pub fn save_with_context(&mut self, new_path: Option<PathBuf>, context: &GameContext) -> Result<()> {
+        self.timestamp = get_current_time();
+        self.save_keeping_timestamp_with_context(new_path, context)
+    }
+
+    /// This function tries to save a `PackFile` to a file in the filesystem, for the game and with the settings of the provided `GameContext`,
+    /// without updating its timestamp.
+    ///
+    /// Otherwise, it works like `save`.
+    pub fn save_keeping_timestamp_with_context(&mut self, new_path: Option<PathBuf>, context: &GameContext) -> Result<()> {
 
         // If any of the problematic masks in the header is set or is one of CA's, return an error.
-        if !self.is_editable(*SETTINGS.read().unwrap().settings_bool.get("allow_editing_of_ca_packfiles").unwrap()) { return Err(ErrorKind::PackFileIsNonEditable.into()) }
+        let is_editing_of_ca_packfiles_allowed = context.get_ref_settings().settings_bool.get("allow_editing_of_ca_packfiles").cloned().unwrap_or(false);
+        if !self.is_editable(is_editing_of_ca_packfiles_allowed) { return Err(ErrorKind::PackFileIsNonEditable.into()) }
 
         // If we receive a new path, update it. Otherwise, ensure the file actually exists on disk.
         if let Some(path) = new_path { self.set_file_path(&path)?; }
@@ -2456,15 +2997,23 @@ impl PackFile {
         for packed_file in &mut self.packed_files {
 
             // If we decoded it, re-encode it. Otherwise, just load it.
-            packed_file.encode()?;
+            packed_file.encode_with_context(context)?;
 
-            // Remember: first compress (only PFH5), then encrypt.
+            // Remember: first compress (only PFH5), then encrypt. So, if we need to change the compression of encrypted data, we need to decrypt it first.
             let (path, data, is_compressed, is_encrypted, should_be_compressed, should_be_encrypted) = packed_file.get_ref_mut_raw().get_data_and_info_from_memory()?;
 
             // If, in any moment, we enabled/disabled the PackFile compression, compress/decompress the PackedFile. EXCEPT FOR TABLES. NEVER COMPRESS TABLES.
+            // Arena PackFiles use the PFH4 index format, so they cannot have compressed PackedFiles either.
             match PackedFileType::get_packed_file_type(path) {
                 PackedFileType::DB | PackedFileType::Loc => *should_be_compressed = false,
-                _ => {}
+                _ => if self.bitmask.contains(PFHFlags::HAS_EXTENDED_HEADER) { *should_be_compressed = false; }
+            }
+
+            // Encryption is set for the entire PackFile, so all the PackedFiles have to follow what the bitmask says.
+            *should_be_encrypted = if self.bitmask.contains(PFHFlags::HAS_ENCRYPTED_DATA) { Some(self.pfh_version) } else { None };
+            if is_encrypted.is_some() && (*should_be_compressed != *is_compressed || should_be_encrypted.is_none()) {
+                *data = decrypt_packed_file(data);
+                *is_encrypted = None;
             }
 
             if *should_be_compressed && !*is_compressed {
@@ -2476,14 +3025,17 @@ impl PackFile {
                 *is_compressed = false;
             }
 
-            // Encryption is not yet supported. Unencrypt everything.
-            if is_encrypted.is_some() {
-                *data = decrypt_packed_file(&data);
-                *is_encrypted = None;
-                *should_be_encrypted = None;
+            if should_be_encrypted.is_some() && is_encrypted.is_none() {
+                *data = encrypt_packed_file(data);
+                *is_encrypted = *should_be_encrypted;
             }
         }
 
+        // Encrypted data in PFH5 PackFiles with extended header (only ARENA) has to be aligned to 8 bytes.
+        let is_data_aligned = self.bitmask.contains(PFHFlags::HAS_ENCRYPTED_DATA) &&
+            self.bitmask.contains(PFHFlags::HAS_EXTENDED_HEADER) &&
+            self.pfh_version == PFHVersion::PFH5;
+
         // First we encode the indexes and the data (just in case we compressed it).
         let mut pack_file_index = vec![];
         let mut packed_file_index = vec![];
@@ -2493,18 +3045,29 @@ impl PackFile {
             pack_file_index.push(0);
         }
 
-        for packed_file in &self.packed_files {
-            packed_file_index.encode_integer_u32(packed_file.get_ref_raw().get_size());
+        let is_index_encrypted = self.bitmask.contains(PFHFlags::HAS_ENCRYPTED_INDEX);
+        for (index, packed_file) in self.packed_files.iter().enumerate() {
+            let packed_files_after_this_one = (self.packed_files.len() - index - 1) as u32;
+            let size = packed_file.get_ref_raw().get_size();
+            if is_index_encrypted { packed_file_index.encode_integer_u32(encrypt_index_item_file_length(size, packed_files_after_this_one)); }
+            else { packed_file_index.encode_integer_u32(size); }
+
+            // Timestamps are only encrypted in PFH5 and PFH4 PackFiles.
+            let timestamp = packed_file.get_ref_raw().get_timestamp() as u32;
+            let timestamp = if is_index_encrypted { encrypt_index_item_file_length(timestamp, packed_files_after_this_one) } else { timestamp };
 
             // Depending on the version of the PackFile and his bitmask, the PackedFile index has one format or another.
-            // In PFH5 case, we don't support saving encrypted PackFiles for Arena. So we'll default to Warhammer 2 format.
+            // In PFH5 case, PackFiles with extended header (only ARENA) use the PFH4 format.
             match self.pfh_version {
+                PFHVersion::PFH5 if self.bitmask.contains(PFHFlags::HAS_EXTENDED_HEADER) => {
+                    if self.bitmask.contains(PFHFlags::HAS_INDEX_WITH_TIMESTAMPS) { packed_file_index.encode_integer_u32(timestamp); }
+                }
                 PFHVersion::PFH5 => {
-                    if self.bitmask.contains(PFHFlags::HAS_INDEX_WITH_TIMESTAMPS) { packed_file_index.encode_integer_u32(packed_file.get_ref_raw().get_timestamp() as u32); }
+                    if self.bitmask.contains(PFHFlags::HAS_INDEX_WITH_TIMESTAMPS) { packed_file_index.encode_integer_u32(timestamp); }
                     if packed_file.get_ref_raw().get_should_be_compressed() { packed_file_index.push(1); } else { packed_file_index.push(0); }
                 }
                 PFHVersion::PFH4 => {
-                    if self.bitmask.contains(PFHFlags::HAS_INDEX_WITH_TIMESTAMPS) { packed_file_index.encode_integer_u32(packed_file.get_ref_raw().get_timestamp() as u32); }
+                    if self.bitmask.contains(PFHFlags::HAS_INDEX_WITH_TIMESTAMPS) { packed_file_index.encode_integer_u32(timestamp); }
                 }
                 PFHVersion::PFH3 | PFHVersion::PFH2 => {
                     if self.bitmask.contains(PFHFlags::HAS_INDEX_WITH_TIMESTAMPS) { packed_file_index.encode_integer_i64(packed_file.get_ref_raw().get_timestamp()); }
@@ -2514,8 +3077,12 @@ impl PackFile {
                 PFHVersion::PFH0 => {}
             }
 
-            packed_file_index.append(&mut packed_file.get_path().join("\\").as_bytes().to_vec());
-            packed_file_index.push(0);
+            if is_index_encrypted {
+                packed_file_index.append(&mut encrypt_index_item_filename(&packed_file.get_path().join("\\"), size as u8));
+            } else {
+                packed_file_index.append(&mut packed_file.get_path().join("\\").as_bytes().to_vec());
+                packed_file_index.push(0);
+            }
         }
 
         // Create the file to save to, and save the header and the indexes.
@@ -2530,21 +3097,41 @@ impl PackFile {
         header.encode_integer_u32(self.packed_files.len() as u32);
         header.encode_integer_u32(packed_file_index.len() as u32);
 
-        // Update the creation time, then save it. PFH0 files don't have timestamp in the headers.
-        self.timestamp = get_current_time();
+        // Save the creation time. PFH0 files don't have timestamp in the headers.
         match self.pfh_version {
             PFHVersion::PFH5 | PFHVersion::PFH4 => header.encode_integer_u32(self.timestamp as u32),
             PFHVersion::PFH3 | PFHVersion::PFH2 => header.encode_integer_i64((self.timestamp + SEC_TO_UNIX_EPOCH) * WINDOWS_TICK),
             PFHVersion::PFH0 => {}
         };
 
+        // The extended header only exists in PFH5 and PFH4 PackFiles.
+        let has_extended_header = self.bitmask.contains(PFHFlags::HAS_EXTENDED_HEADER) && (self.pfh_version == PFHVersion::PFH5 || self.pfh_version == PFHVersion::PFH4);
+        if has_extended_header {
+            header.encode_integer_u32(self.extended_header.unknown_1);
+            header.encode_integer_u32(self.extended_header.unknown_2);
+            header.encode_integer_u32(self.extended_header.unknown_3);
+            header.encode_integer_u32(self.extended_header.unknown_4);
+            header.encode_integer_u32(self.extended_header.unknown_5);
+        }
+
         // Write the indexes and the data of the PackedFiles. No need to keep the data, as it has been preloaded before.
         file.write_all(&header)?;
         file.write_all(&pack_file_index)?;
         file.write_all(&packed_file_index)?;
+
+        let index_end = header.len() + pack_file_index.len() + packed_file_index.len();
+        if is_data_aligned && index_end % 8 > 0 { file.write_all(&vec![0; 8 - index_end % 8])?; }
         for packed_file in &self.packed_files {
             let data = packed_file.get_ref_raw().get_raw_data()?;
             file.write_all(&data)?;
+            if is_data_aligned && data.len() % 8 > 0 { file.write_all(&vec![0; 8 - data.len() % 8])?; }
+        }
+
+        // PFH5 PackFiles with extended header (only ARENA) have extra data at the end. If we don't have it, fill it with zeros.
+        if has_extended_header && self.pfh_version == PFHVersion::PFH5 {
+            let mut trailing_data = self.extended_header.trailing_data.to_vec();
+            trailing_data.resize(EXTENDED_HEADER_TRAILING_DATA_SIZE, 0);
+            file.write_all(&trailing_data)?;
         }
 
         // Remove again the reserved PackedFiles.
@@ -2585,10 +3172,9 @@ impl From<&PackFile> for PackFileInfo {
 /// Implementation of `Manifest`.
 impl Manifest {
 
-    /// This function returns a parsed version of the `manifest.txt` of the Game Selected, if exists and is parseable.
-    pub fn read_from_game_selected() -> Result<Self> {
-        let mut manifest_path = get_game_selected_data_path().ok_or_else(|| ErrorKind::GameSelectedPathNotCorrectlyConfigured)?;
-        manifest_path.push("manifest.txt");
+    /// This function returns a parsed version of the `manifest.txt` in the provided `/data` folder, if exists and is parseable.
+    pub fn read_from_data_path(data_path: &Path) -> Result<Self> {
+        let manifest_path = data_path.join("manifest.txt");
 
         let mut reader = ReaderBuilder::new()
             .delimiter(b'\t')
@@ -2634,10 +3220,11 @@ impl Default for PackFileSettings {
     fn default() -> Self {
         let mut settings_text = BTreeMap::new();
         let settings_string = BTreeMap::new();
-        let settings_bool = BTreeMap::new();
+        let mut settings_bool = BTreeMap::new();
         let settings_number = BTreeMap::new();
 
         settings_text.insert("diagnostics_files_to_ignore".to_owned(), "".to_owned());
+        settings_bool.insert("diagnostics_check_undefined_lua_functions".to_owned(), false);
 
         Self {
             settings_text,
@@ -17,15 +17,17 @@ meaning the code that takes care of loading/writing their data from/to disk.
 You'll rarely have to touch anything here.
 !*/
 
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::io::prelude::*;
-use std::io::{BufReader, Read, SeekFrom};
+use memmap2::Mmap;
+
+use std::borrow::Cow;
+use std::io;
 use std::fs::File;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use rpfm_error::Error;
 
+use crate::context::GameContext;
 use crate::packedfile::animpack::AnimPacked;
 use crate::packfile::*;
 use crate::packfile::compression::decompress_data;
@@ -77,21 +79,51 @@ pub enum PackedFileData {
 
     /// The data is not loaded to memory and the variant holds the info needed to get the data loaded to memory on demand.
     OnDisk(RawOnDisk),
+
+    /// The data is not loaded to memory, but the PackFile containing it is mapped to memory, so it can be read without copying it first.
+    ///
+    /// Only used while loading the dependencies of a PackFile. Never keep PackedFiles with this data around.
+    OnMmap(RawOnMmap),
 }
 
 /// This struct contains the stuff needed to read the data of a particular PackedFile from disk.
 #[derive(Clone, Debug)]
 pub struct RawOnDisk {
 
-    /// Reader over the PackFile containing the PackedFile.
-    reader: Arc<Mutex<BufReader<File>>>,
+    /// The PackFile containing the PackedFile. It's read at the position of the PackedFile, so it can be read from multiple threads without locks.
+    file: Arc<File>,
     start: u64,
     size: u32,
     is_compressed: bool,
     is_encrypted: Option<PFHVersion>,
 
-    /// Hash of the PackedFile's data, to ensure we don't grab the wrong data.
-    hash: Arc<Mutex<u64>>,
+    /// Size and modification date of the PackFile when it was opened, to ensure we don't grab the wrong data.
+    file_stamp: FileStamp,
+}
+
+/// This struct contains the stuff needed to read the data of a particular PackedFile from a PackFile mapped to memory.
+#[derive(Clone, Debug)]
+pub struct RawOnMmap {
+
+    /// The PackFile containing the PackedFile, and its map in memory. The map must be dropped as soon as the data we need is copied out of it.
+    file: Arc<File>,
+    mmap: Arc<Mmap>,
+    start: u64,
+    size: u32,
+    is_compressed: bool,
+    is_encrypted: Option<PFHVersion>,
+
+    /// Size and modification date of the PackFile when it was opened, to ensure we don't grab the wrong data.
+    file_stamp: FileStamp,
+}
+
+/// This struct contains the size and modification date of a file, to detect if it has been changed by another program.
+///
+/// It's way cheaper than hashing the data we read from it every time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FileStamp {
+    size: u64,
+    modified: Option<SystemTime>,
 }
 
 /// This struct represents the detailed info about the `PackedFile` we can provide to whoever request it.
@@ -182,15 +214,33 @@ impl PackedFile {
         path: Vec<String>,
         dependencies: &Dependencies,
     ) -> Result<Self> {
+        Self::new_from_type_and_path_with_schema(packed_file_type, path, SCHEMA.read().unwrap().as_ref(), dependencies)
+    }
+
+    /// This function creates a new empty `PackedFile` of the provided type and path, using the schema and dependencies of the provided `GameContext`.
+    pub fn new_from_type_and_path_with_context(
+        packed_file_type: PackedFileType,
+        path: Vec<String>,
+        context: &GameContext,
+    ) -> Result<Self> {
+        Self::new_from_type_and_path_with_schema(packed_file_type, path, context.get_ref_schema(), context.get_ref_dependencies())
+    }
+
+    /// This function creates a new empty `PackedFile` of the provided type and path, using the provided schema, if any.
+    fn new_from_type_and_path_with_schema(
+        packed_file_type: PackedFileType,
+        path: Vec<String>,
+        schema: Option<&Schema>,
+        dependencies: &Dependencies,
+    ) -> Result<Self> {
 
         // Depending on their type, we do different things to prepare the PackedFile and get his data.
-        let schema = SCHEMA.read().unwrap();
         let data = match packed_file_type {
 
             // For locs, we just create them with their last definition.
             PackedFileType::Loc => {
-                let definition = match *schema {
-                    Some(ref schema) => schema.get_ref_last_definition_loc()?,
+                let definition = match schema {
+                    Some(schema) => schema.get_ref_last_definition_loc()?,
                     None => return Err(ErrorKind::SchemaNotFound.into())
                 };
                 DecodedPackedFile::Loc(Loc::new(&definition))
@@ -199,8 +249,8 @@ impl PackedFile {
             // For dbs, we create them with their last definition, if we found one, and their table name.
             PackedFileType::DB => {
                 let table_name = path.get(1).ok_or_else(|| Error::from(ErrorKind::DBTableIsNotADBTable))?;
-                let table_definition = match *schema {
-                    Some(ref schema) => schema.get_ref_last_definition_db(table_name, dependencies)?,
+                let table_definition = match schema {
+                    Some(schema) => schema.get_ref_last_definition_db(table_name, dependencies)?,
                     None => return Err(ErrorKind::SchemaNotFound.into())
                 };
                 DecodedPackedFile::DB(DB::new(&table_name, None, &table_definition))
@@ -286,6 +336,22 @@ impl PackedFile {
         Ok(())
     }
 
+    /// This function tries to decode a `RawPackedFile` into a `DecodedPackedFile` using the schema of the provided `GameContext`,
+    /// storing the results in the `Packedfile`.
+    pub fn decode_with_context(&mut self, context: &GameContext) -> Result<()> {
+        if self.decoded == DecodedPackedFile::Unknown {
+            self.decoded = DecodedPackedFile::decode_with_context(&mut self.raw, context)?;
+        }
+        Ok(())
+    }
+
+    /// This function tries to decode a `RawPackedFile` into a `DecodedPackedFile` using the schema of the provided `GameContext`,
+    /// storing the results in the `Packedfile`, and returning a reference to it.
+    pub fn decode_return_ref_with_context(&mut self, context: &GameContext) -> Result<&DecodedPackedFile> {
+        self.decode_with_context(context)?;
+        Ok(&self.decoded)
+    }
+
     /// This function tries to decode a `RawPackedFile` into a `DecodedPackedFile`, storing the results in the `Packedfile`,
     /// and returning a reference to it.
     ///
@@ -352,6 +418,18 @@ impl PackedFile {
         Ok(())
     }
 
+    /// This function tries to encode a `DecodedPackedFile` into a `RawPackedFile` for the game of the provided `GameContext`,
+    /// storing the results in the `Packedfile`.
+    ///
+    /// If the PackedFile is not decoded or has no saving support (encode returns None), it does nothing.
+    pub fn encode_with_context(&mut self, context: &GameContext) -> Result<()> {
+        match self.decoded.encode_with_context(context) {
+            Some(data) => self.raw.set_data(&data?),
+            None => self.raw.load_data()?,
+        }
+        Ok(())
+    }
+
     /// This function tries to encode a `DecodedPackedFile` into a `RawPackedFile`, storing the results in the `Packedfile`.
     /// Then, it removes the decoded data from the cache.
     ///
@@ -490,9 +568,11 @@ impl RawPackedFile {
 
     /// This function loads the data of a `RawPackedFile` to memory, if it isn't loaded already.
     pub fn load_data(&mut self) -> Result<()> {
-        let data_on_memory = if let PackedFileData::OnDisk(ref raw_on_disk) = self.data {
-            PackedFileData::OnMemory(raw_on_disk.read()?, raw_on_disk.get_compression_state(), raw_on_disk.get_encryption())
-        } else { return Ok(()) };
+        let data_on_memory = match self.data {
+            PackedFileData::OnDisk(ref raw_on_disk) => PackedFileData::OnMemory(raw_on_disk.read()?, raw_on_disk.get_compression_state(), raw_on_disk.get_encryption()),
+            PackedFileData::OnMmap(ref raw_on_mmap) => PackedFileData::OnMemory(raw_on_mmap.read()?, raw_on_mmap.get_compression_state(), raw_on_mmap.get_encryption()),
+            PackedFileData::OnMemory(..) => return Ok(()),
+        };
 
         self.data = data_on_memory;
         Ok(())
@@ -509,6 +589,20 @@ impl RawPackedFile {
             PackedFileData::OnDisk(ref raw_on_disk) => {
                 raw_on_disk.read()
             }
+            PackedFileData::OnMmap(ref raw_on_mmap) => {
+                raw_on_mmap.read()
+            }
+        }
+    }
+
+    /// This function returns the RAW data of the `RawPackedFile` without loading it to memory, and without copying it if possible.
+    ///
+    /// The data is only copied if it needs to be read from disk. Like `get_raw_data`, this data is not decompressed/decrypted.
+    pub fn get_ref_raw_data(&self) -> Result<Cow<'_, [u8]>> {
+        match self.data {
+            PackedFileData::OnMemory(ref data, _, _) => Ok(Cow::from(data.as_slice())),
+            PackedFileData::OnDisk(ref raw_on_disk) => raw_on_disk.read().map(Cow::from),
+            PackedFileData::OnMmap(ref raw_on_mmap) => raw_on_mmap.get_ref_data().map(Cow::from),
         }
     }
 
@@ -529,6 +623,7 @@ impl RawPackedFile {
                 if raw_on_disk.get_compression_state() { decompress_data(&data) }
                 else { Ok(data) }
             }
+            PackedFileData::OnMmap(ref raw_on_mmap) => raw_on_mmap.read_and_unpack(),
         }
     }
 
@@ -550,6 +645,7 @@ impl RawPackedFile {
                 if raw_on_disk.get_compression_state() { decompress_data(&data)? }
                 else { data }
             }
+            PackedFileData::OnMmap(ref raw_on_mmap) => raw_on_mmap.read_and_unpack()?,
         };
 
         self.data = PackedFileData::OnMemory(data.to_vec(), false, None);
@@ -575,6 +671,7 @@ impl RawPackedFile {
                 if raw_on_disk.get_compression_state() { decompress_data(&data)? }
                 else { data }
             }
+            PackedFileData::OnMmap(ref raw_on_mmap) => raw_on_mmap.read_and_unpack()?,
         };
 
         self.data = PackedFileData::OnMemory(data, false, None);
@@ -589,7 +686,7 @@ impl RawPackedFile {
             PackedFileData::OnMemory(ref mut data, ref mut is_compressed, ref mut is_encrypted) => {
                 Ok((&self.path, data, is_compressed, is_encrypted, &mut self.should_be_compressed, &mut self.should_be_encrypted))
             },
-            PackedFileData::OnDisk(_) => {
+            PackedFileData::OnDisk(_) | PackedFileData::OnMmap(_) => {
                 Err(ErrorKind::PackedFileDataIsNotInMemory.into())
             }
         }
@@ -605,6 +702,7 @@ impl RawPackedFile {
         match self.data {
             PackedFileData::OnMemory(ref data, _, _) => data.len() as u32,
             PackedFileData::OnDisk(ref raw_on_disk) => raw_on_disk.get_size(),
+            PackedFileData::OnMmap(ref raw_on_mmap) => raw_on_mmap.get_size(),
         }
     }
 
@@ -613,6 +711,7 @@ impl RawPackedFile {
         match self.data {
             PackedFileData::OnMemory(_, state, _) => state,
             PackedFileData::OnDisk(ref raw_on_disk) => raw_on_disk.get_compression_state(),
+            PackedFileData::OnMmap(ref raw_on_mmap) => raw_on_mmap.get_compression_state(),
         }
     }
 
@@ -641,6 +740,7 @@ impl RawPackedFile {
         match self.data {
             PackedFileData::OnMemory(_, _, state) => state.is_some(),
             PackedFileData::OnDisk(ref raw_on_disk) => raw_on_disk.get_encryption_state(),
+            PackedFileData::OnMmap(ref raw_on_mmap) => raw_on_mmap.get_encryption_state(),
         }
     }
 
@@ -688,45 +788,110 @@ impl RawOnDisk {
 
     /// This function creates a new RawOnDisk.
     pub fn new(
-        reader: Arc<Mutex<BufReader<File>>>,
+        file: Arc<File>,
         start: u64,
         size: u32,
         is_compressed: bool,
         is_encrypted: Option<PFHVersion>,
+        file_stamp: FileStamp,
     ) -> Self {
         Self {
-            reader,
+            file,
             start,
             size,
             is_compressed,
             is_encrypted,
-            hash: Arc::new(Mutex::new(0)),
+            file_stamp,
         }
     }
 
     /// This function tries to read and return the raw data of the PackedFile.
     pub fn read(&self) -> Result<Vec<u8>> {
         let mut data = vec![0; self.size as usize];
-        let mut file = self.reader.lock().unwrap();
-        file.seek(SeekFrom::Start(self.start))?;
-        file.read_exact(&mut data)?;
 
-        let mut current_hash = self.hash.lock().unwrap();
-        let mut hasher = DefaultHasher::new();
-        data.hash(&mut hasher);
-        let new_hash = hasher.finish();
+        // Check the PackFile has not been changed since we opened it, to ensure we're not fucking up the PackFile.
+        if FileStamp::new(&self.file)? != self.file_stamp {
+            return Err(ErrorKind::PackedFileSourceChanged.into());
+        }
+
+        read_exact_at(&self.file, &mut data, self.start)?;
+        Ok(data)
+    }
+
+    /// This function returns the size of the PackedFile.
+    pub fn get_size(&self) -> u32 {
+        self.size
+    }
+
+    /// This function returns if the PackedFile is compressed or not.
+    pub fn get_compression_state(&self) -> bool {
+        self.is_compressed
+    }
+
+    /// This function returns if the PackedFile is encrypted or not.
+    pub fn get_encryption_state(&self) -> bool {
+        self.is_encrypted.is_some()
+    }
+
+    /// This function returns the encryption info of the PackedFile.
+    pub fn get_encryption(&self) -> Option<PFHVersion> {
+        self.is_encrypted
+    }
+}
+
+/// Implementation of RawOnMmap.
+impl RawOnMmap {
+
+    /// This function creates a new RawOnMmap.
+    pub fn new(
+        file: Arc<File>,
+        mmap: Arc<Mmap>,
+        start: u64,
+        size: u32,
+        is_compressed: bool,
+        is_encrypted: Option<PFHVersion>,
+        file_stamp: FileStamp,
+    ) -> Self {
+        Self {
+            file,
+            mmap,
+            start,
+            size,
+            is_compressed,
+            is_encrypted,
+            file_stamp,
+        }
+    }
 
-        // If we have no hash, it's the first read. Hash it.
-        if *current_hash == 0 {
-            *current_hash = new_hash;
+    /// This function returns a reference to the raw data of the PackedFile, without copying it.
+    ///
+    /// If the PackFile has been changed since it was mapped, this returns an error instead of touching the map. That doesn't make
+    /// the returned slice safe to keep: if another program truncates the PackFile after the check, touching the slice crashes the
+    /// program. Only use it to copy or decode the data right away, while the PackFile is being loaded.
+    pub fn get_ref_data(&self) -> Result<&[u8]> {
+        if FileStamp::new(&self.file)? != self.file_stamp {
+            return Err(ErrorKind::PackedFileSourceChanged.into());
         }
 
+        let start = self.start as usize;
+        let end = start + self.size as usize;
+        self.mmap.get(start..end).ok_or_else(|| ErrorKind::PackedFileDataCouldNotBeLoaded.into())
+    }
+
+    /// This function tries to read and return the raw data of the PackedFile.
+    pub fn read(&self) -> Result<Vec<u8>> {
+        self.get_ref_data().map(|data| data.to_vec())
+    }
 
-        // Otherwise, check its hash to ensure we're not fucking up the PackFile.
-        else if *current_hash != new_hash {
-            return Err(ErrorKind::PackedFileChecksumFailed.into());
+    /// This function returns the data of the PackedFile decrypted and decompressed, only copying it once.
+    pub fn read_and_unpack(&self) -> Result<Vec<u8>> {
+        let data = self.get_ref_data()?;
+        match (self.get_encryption_state(), self.get_compression_state()) {
+            (true, true) => decompress_data(&decrypt_packed_file(data)),
+            (true, false) => Ok(decrypt_packed_file(data)),
+            (false, true) => decompress_data(data),
+            (false, false) => Ok(data.to_vec()),
         }
-        Ok(data)
     }
 
     /// This function returns the size of the PackedFile.
@@ -750,6 +915,19 @@ impl RawOnDisk {
     }
 }
 
+/// Implementation of `FileStamp`.
+impl FileStamp {
+
+    /// This function gets the current `FileStamp` of the provided file.
+    pub fn new(file: &File) -> Result<Self> {
+        let metadata = file.metadata()?;
+        Ok(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
 /// Implementation of `PartialEq` for `PackedFileData`.
 impl PartialEq for PackedFileData {
     fn eq(&self, other: &PackedFileData) -> bool {
@@ -791,3 +969,54 @@ impl From<&AnimPacked> for PackedFile {
         packed_file
     }
 }
+
+//---------------------------------------------------------------------------//
+//                              Util functions
+//---------------------------------------------------------------------------//
+
+/// This function fills the provided buffer with the data of the file starting at `offset`, without moving a shared cursor.
+#[cfg(unix)]
+fn read_exact_at(file: &File, buffer: &mut [u8], offset: u64) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+    file.read_exact_at(buffer, offset)
+}
+
+/// This function fills the provided buffer with the data of the file starting at `offset`, without moving a shared cursor.
+///
+/// On Windows `seek_read` moves the cursor of the file, but we never read from it through the cursor once the PackFile is open.
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buffer: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buffer.is_empty() {
+        match file.seek_read(buffer, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(bytes_read) => {
+                buffer = &mut buffer[bytes_read..];
+                offset += bytes_read as u64;
+            }
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(())
+}
+
+/// This function fills the provided buffer with the data of the file starting at `offset`.
+///
+/// Other platforms have no positional reads, so we seek and read through the cursor of the file, one read at a time.
+#[cfg(not(any(unix, windows)))]
+fn read_exact_at(file: &File, buffer: &mut [u8], offset: u64) -> io::Result<()> {
+    use lazy_static::lazy_static;
+    use std::io::{Read, Seek, SeekFrom};
+    use std::sync::Mutex;
+
+    // The cursor is shared by every handle of the file, so this lock is what keeps parallel reads from moving it under us.
+    lazy_static! {
+        static ref CURSOR_LOCK: Mutex<()> = Mutex::new(());
+    }
+
+    let _lock = CURSOR_LOCK.lock().unwrap();
+    let mut file = file;
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buffer)
+}
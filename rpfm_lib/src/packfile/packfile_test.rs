@@ -12,9 +12,13 @@
 Module containing test for the `PackFile` module, just to make sure we don't break it... again...
 !*/
 
+use rayon::prelude::*;
+
+use std::fs::{create_dir_all, remove_dir_all, OpenOptions};
+use std::io::Write;
 use std::path::PathBuf;
 
-use super::PackFile;
+use super::{PackFile, PFHExtendedHeader, PFHFlags, PFHVersion};
 
 #[test]
 fn test_decode_pfh5() {
@@ -121,3 +125,163 @@ fn test_encode_pfh0() {
 
 	assert_eq!(pack_file_base, pack_file_new);
 }
+
+#[test]
+fn test_update_from_folder() {
+	let folder = std::env::temp_dir().join("rpfm_update_from_folder");
+	let _ = std::fs::remove_dir_all(&folder);
+	std::fs::create_dir_all(folder.join("script").join("campaign")).unwrap();
+	std::fs::write(folder.join("script").join("campaign").join("mod.lua"), b"print(1)").unwrap();
+	std::fs::write(folder.join("readme.txt"), b"readme").unwrap();
+
+	// The first sync adds the entire folder.
+	let mut pack_file = PackFile::new();
+	let (updated, removed) = pack_file.update_from_folder(&folder, &[folder.to_path_buf()]).unwrap();
+	assert_eq!(updated.len(), 2);
+	assert!(removed.is_empty());
+	assert!(pack_file.packedfile_exists(&["script".to_owned(), "campaign".to_owned(), "mod.lua".to_owned()]));
+
+	// Then only the changed paths are updated or removed.
+	std::fs::write(folder.join("readme.txt"), b"new readme").unwrap();
+	std::fs::remove_dir_all(folder.join("script")).unwrap();
+	let (updated, removed) = pack_file.update_from_folder(&folder, &[folder.join("readme.txt"), folder.join("script"), PathBuf::from("/not/in/folder")]).unwrap();
+	assert_eq!(updated, vec![vec!["readme.txt".to_owned()]]);
+	assert_eq!(removed, vec![vec!["script".to_owned(), "campaign".to_owned(), "mod.lua".to_owned()]]);
+	assert_eq!(pack_file.get_ref_packed_file_by_path(&["readme.txt".to_owned()]).unwrap().get_raw_data().unwrap(), b"new readme".to_vec());
+	assert_eq!(pack_file.get_packed_files_all_paths().len(), 1);
+
+	// Full syncs remove the files deleted while not watching, and ignore hidden and temporary files.
+	std::fs::remove_file(folder.join("readme.txt")).unwrap();
+	std::fs::write(folder.join("notes.txt"), b"notes").unwrap();
+	std::fs::write(folder.join(".notes.txt.swp"), b"swap").unwrap();
+	std::fs::write(folder.join("notes.txt~"), b"backup").unwrap();
+	std::fs::write(folder.join(".#notes.txt"), b"lock").unwrap();
+	let (updated, removed) = pack_file.update_from_folder(&folder, &[folder.to_path_buf(), folder.join("notes.txt~")]).unwrap();
+	assert_eq!(updated, vec![vec!["notes.txt".to_owned()]]);
+	assert_eq!(removed, vec![vec!["readme.txt".to_owned()]]);
+	assert_eq!(pack_file.get_packed_files_all_paths(), vec![vec!["notes.txt".to_owned()]]);
+}
+
+#[test]
+fn test_encode_encrypted() {
+	let mut pack_file_base = PackFile::read(&PathBuf::from("../test_files/PFH5_test.pack"), false).unwrap();
+	let path = std::env::temp_dir().join("rpfm_encrypted_test.pack");
+
+	// Encrypt both, index and data, and make sure we get back the same PackedFiles, and that the paths are not in the file in plain text.
+	let mut pack_file_new = pack_file_base.clone();
+	pack_file_new.set_bitmask(PFHFlags::HAS_ENCRYPTED_INDEX | PFHFlags::HAS_ENCRYPTED_DATA | PFHFlags::HAS_INDEX_WITH_TIMESTAMPS);
+	pack_file_new.save(Some(path.to_path_buf())).unwrap();
+
+	let data = std::fs::read(&path).unwrap();
+	let packed_file_path = pack_file_base.get_packed_files_all_paths()[0].join("\\");
+	assert!(!data.windows(packed_file_path.len()).any(|x| x == packed_file_path.as_bytes()));
+
+	let mut pack_file_encrypted = PackFile::read(&path, false).unwrap();
+	assert_eq!(pack_file_encrypted.get_bitmask(), PFHFlags::HAS_ENCRYPTED_INDEX | PFHFlags::HAS_ENCRYPTED_DATA | PFHFlags::HAS_INDEX_WITH_TIMESTAMPS);
+	for packed_file in pack_file_base.get_ref_mut_packed_files_all() {
+		let packed_file_encrypted = pack_file_encrypted.get_ref_mut_packed_file_by_path(packed_file.get_path()).unwrap();
+		assert_eq!(packed_file_encrypted.get_raw_data_and_keep_it().unwrap(), packed_file.get_raw_data_and_keep_it().unwrap());
+	}
+
+	// Then remove the encryption, and make sure we get a normal PackFile back.
+	pack_file_encrypted.set_bitmask(PFHFlags::empty());
+	pack_file_encrypted.save(None).unwrap();
+
+	let data = std::fs::read(&path).unwrap();
+	assert!(data.windows(packed_file_path.len()).any(|x| x == packed_file_path.as_bytes()));
+
+	let mut pack_file_decrypted = PackFile::read(&path, false).unwrap();
+	assert_eq!(pack_file_decrypted.get_bitmask(), PFHFlags::empty());
+	for packed_file in pack_file_base.get_ref_mut_packed_files_all() {
+		let packed_file_decrypted = pack_file_decrypted.get_ref_mut_packed_file_by_path(packed_file.get_path()).unwrap();
+		assert!(!packed_file_decrypted.get_ref_raw().get_encryption_state());
+		assert_eq!(packed_file_decrypted.get_raw_data_and_keep_it().unwrap(), packed_file.get_raw_data_and_keep_it().unwrap());
+	}
+}
+
+/// This function saves the provided PackFile with an extended header, and checks it's read and saved back byte by byte.
+fn round_trip_extended_header(mut pack_file: PackFile, bitmask: PFHFlags, name: &str) -> Vec<u8> {
+	let extended_header = PFHExtendedHeader {
+		unknown_1: 1,
+		unknown_2: 0x0102_0304,
+		unknown_3: 3,
+		unknown_4: 0xFFFF_FFFF,
+		unknown_5: 5,
+		trailing_data: (0..=255).collect(),
+	};
+
+	let path = std::env::temp_dir().join(format!("rpfm_extended_header_{}_test.pack", name));
+	let path_new = std::env::temp_dir().join(format!("rpfm_extended_header_{}_test_new.pack", name));
+	pack_file.set_bitmask(bitmask);
+	pack_file.set_extended_header(&extended_header);
+	assert!(pack_file.is_editable(false));
+	pack_file.save_keeping_timestamp(Some(path.to_path_buf())).unwrap();
+
+	let mut pack_file_extended = PackFile::read(&path, false).unwrap();
+	assert_eq!(pack_file_extended.get_bitmask(), bitmask);
+	assert_eq!(pack_file_extended.get_ref_extended_header().unknown_2, 0x0102_0304);
+	assert_eq!(pack_file_extended.get_ref_extended_header().unknown_4, 0xFFFF_FFFF);
+	pack_file_extended.save_keeping_timestamp(Some(path_new.to_path_buf())).unwrap();
+
+	let data = std::fs::read(&path).unwrap();
+	assert_eq!(data, std::fs::read(&path_new).unwrap());
+	assert_eq!(data[28..48], [1, 0, 0, 0, 4, 3, 2, 1, 3, 0, 0, 0, 255, 255, 255, 255, 5, 0, 0, 0]);
+	data
+}
+
+#[test]
+fn test_encode_extended_header_pfh5() {
+	let pack_file_base = PackFile::read(&PathBuf::from("../test_files/PFH5_test.pack"), false).unwrap();
+	let bitmask = PFHFlags::HAS_EXTENDED_HEADER | PFHFlags::HAS_ENCRYPTED_INDEX | PFHFlags::HAS_ENCRYPTED_DATA | PFHFlags::HAS_INDEX_WITH_TIMESTAMPS;
+	let data = round_trip_extended_header(pack_file_base.clone(), bitmask, "pfh5");
+
+	// PFH5 PackFiles with extended header end with the trailing data.
+	assert_eq!(data[data.len() - 256..], (0..=255).collect::<Vec<u8>>()[..]);
+
+	let mut pack_file_extended = PackFile::read(&std::env::temp_dir().join("rpfm_extended_header_pfh5_test.pack"), false).unwrap();
+	assert_eq!(pack_file_extended.get_ref_extended_header().trailing_data, (0..=255).collect::<Vec<u8>>());
+	for packed_file in pack_file_base.clone().get_ref_mut_packed_files_all() {
+		let packed_file_extended = pack_file_extended.get_ref_mut_packed_file_by_path(packed_file.get_path()).unwrap();
+		assert!(!packed_file_extended.get_ref_raw().get_compression_state());
+		assert_eq!(packed_file_extended.get_raw_data_and_keep_it().unwrap(), packed_file.get_raw_data_and_keep_it().unwrap());
+	}
+}
+
+#[test]
+fn test_encode_extended_header_pfh4() {
+	let pack_file_base = PackFile::read(&PathBuf::from("../test_files/PFH4_test.pack"), false).unwrap();
+	assert_eq!(pack_file_base.get_pfh_version(), PFHVersion::PFH4);
+	round_trip_extended_header(pack_file_base, PFHFlags::HAS_EXTENDED_HEADER | PFHFlags::HAS_INDEX_WITH_TIMESTAMPS, "pfh4");
+}
+
+#[test]
+fn test_read_mapped() {
+
+	// This test changes the PackFiles on disk, so use a folder no other test run can be using.
+	let folder = std::env::temp_dir().join(format!("rpfm_read_mapped_{}", std::process::id()));
+	create_dir_all(&folder).unwrap();
+	for is_compressed in &[false, true] {
+		let path = folder.join(format!("rpfm_mapped_{}_test.pack", is_compressed));
+		let mut pack_file_base = PackFile::read(&PathBuf::from("../test_files/PFH5_test.pack"), false).unwrap();
+		pack_file_base.toggle_compression(*is_compressed);
+		pack_file_base.save(Some(path.to_path_buf())).unwrap();
+
+		// Mapped and lazy-loaded PackedFiles must be readable from multiple threads, and give the same data as loaded ones.
+		let mut pack_file_loaded = PackFile::read(&path, false).unwrap();
+		let pack_file_mapped = PackFile::read_mapped(&path).unwrap();
+		let pack_file_lazy = PackFile::read(&path, true).unwrap();
+		let data_mapped = pack_file_mapped.get_ref_packed_files_all().par_iter().map(|x| x.get_ref_raw().get_data().unwrap()).collect::<Vec<Vec<u8>>>();
+		let data_lazy = pack_file_lazy.get_ref_packed_files_all().par_iter().map(|x| x.get_ref_raw().get_data().unwrap()).collect::<Vec<Vec<u8>>>();
+		assert_eq!(data_mapped.len(), pack_file_loaded.get_ref_packed_files_all().len());
+		assert_eq!(data_mapped, data_lazy);
+		for (packed_file, data) in pack_file_loaded.get_ref_mut_packed_files_all().iter_mut().zip(&data_mapped) {
+			assert_eq!(&packed_file.get_ref_mut_raw().get_data_and_keep_it().unwrap(), data);
+		}
+
+		// If the PackFile changes on disk, reading from it must fail instead of returning the wrong data.
+		OpenOptions::new().append(true).open(&path).unwrap().write_all(&[0; 8]).unwrap();
+		assert!(pack_file_mapped.get_ref_packed_files_all()[0].get_ref_raw().get_ref_raw_data().is_err());
+		assert!(pack_file_lazy.get_ref_packed_files_all()[0].get_ref_raw().get_raw_data().is_err());
+	}
+	remove_dir_all(&folder).unwrap();
+}
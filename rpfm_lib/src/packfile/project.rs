@@ -0,0 +1,347 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with the code to unpack a PackFile into a project folder, and pack it back.
+
+A project folder is a git-friendly version of a PackFile: tables are stored as TSV files (or JSON files,
+if they cannot survive the trip through a TSV), the rest of the PackedFiles are stored as they are,
+and everything needed to rebuild the exact same PackFile (header, flags, timestamps, notes, settings,...)
+goes into a manifest at the root of the folder.
+!*/
+
+use serde_derive::{Serialize, Deserialize};
+use serde_json::{from_slice, to_string_pretty};
+
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, read, read_to_string, remove_file, File};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use rpfm_error::{ErrorKind, Result};
+
+use crate::packedfile::PackedFileType;
+use crate::packedfile::table::db::DB;
+use crate::packedfile::table::loc::{Loc, TSV_NAME_LOC};
+use crate::packfile::packedfile::{PackedFile, RawPackedFile};
+use crate::schema::Schema;
+
+use super::{PackFile, PackFileSettings, PFHExtendedHeader, PFHFileType, PFHFlags, PFHVersion};
+
+/// Name of the manifest file of a project folder.
+pub const PROJECT_MANIFEST_NAME: &str = "rpfm_project.json";
+
+//---------------------------------------------------------------------------//
+//                              Enum & Structs
+//---------------------------------------------------------------------------//
+
+/// This struct represents the manifest of a project folder.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectManifest {
+
+    /// Name of the PackFile's file.
+    pub file_name: String,
+
+    /// Version of the PackFile, as it's written in his header.
+    pub pfh_version: String,
+
+    /// Numeric value of the type of the PackFile.
+    pub pfh_file_type: u32,
+
+    /// Bitmasks applied to the PackFile.
+    pub bitmask: u32,
+
+    /// Timestamp of the PackFile.
+    pub timestamp: i64,
+
+    /// Dependency List of the PackFile.
+    pub pack_files: Vec<String>,
+
+    /// Notes of the PackFile.
+    pub notes: Option<String>,
+
+    /// PackFile-specific settings.
+    pub settings: PackFileSettings,
+
+    /// Data of the extended header of the PackFile. Only used if the PackFile has the `HAS_EXTENDED_HEADER` flag.
+    #[serde(default)]
+    pub extended_header: PFHExtendedHeader,
+
+    /// PackedFiles of the PackFile, by their path inside the PackFile, joined with `/`.
+    pub packed_files: BTreeMap<String, ProjectEntry>,
+}
+
+/// This struct represents the info needed to rebuild a PackedFile from its file in a project folder.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectEntry {
+
+    /// Path of the file, relative to the project folder and joined with `/`.
+    pub file: String,
+
+    /// Format the PackedFile was stored as.
+    pub format: ProjectEntryFormat,
+
+    /// Timestamp of the PackedFile.
+    pub timestamp: i64,
+
+    /// If the PackedFile should be compressed when saving.
+    pub is_compressed: bool,
+
+    /// UUID of the table, for DB Tables stored as TSV.
+    pub uuid: Option<String>,
+
+    /// The mysterious byte of the table, for DB Tables stored as TSV.
+    pub mysterious_byte: Option<bool>,
+}
+
+/// This enum represents the formats PackedFiles can be stored as in a project folder.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProjectEntryFormat {
+
+    /// The data of the PackedFile, as it is.
+    Raw,
+
+    /// A DB or Loc Table, exported to TSV.
+    TSV,
+
+    /// A DB or Loc Table, serialized to JSON. Used for tables that lose data when exported to TSV.
+    JSON,
+}
+
+//---------------------------------------------------------------------------//
+//                       Implementation of PackFile
+//---------------------------------------------------------------------------//
+
+/// Implementation of the project-related functions of `PackFile`.
+impl PackFile {
+
+    /// This function unpacks the `PackFile` into a project folder, returning the paths of the tables that had to be stored as raw data.
+    ///
+    /// Tables are only stored as TSV/JSON if they can be rebuilt from them byte by byte. If the folder contains a previous unpack,
+    /// the files of said unpack are removed before unpacking. If any of the files of the previous unpack is outside the folder,
+    /// nothing is removed and an error is returned.
+    pub fn unpack_to_folder(&mut self, schema: &Schema, folder: &Path) -> Result<Vec<Vec<String>>> {
+        let manifest_path = folder.join(PROJECT_MANIFEST_NAME);
+        if manifest_path.is_file() {
+            let old_manifest: ProjectManifest = from_slice(&read(&manifest_path)?)?;
+            let old_paths = old_manifest.packed_files.values().map(|entry| get_project_path(folder, &entry.file)).collect::<Result<Vec<PathBuf>>>()?;
+            for path in &old_paths {
+                let _ = remove_file(path);
+            }
+        }
+
+        let mut manifest = ProjectManifest {
+            file_name: self.get_file_name(),
+            pfh_version: self.pfh_version.get_value().to_owned(),
+            pfh_file_type: self.pfh_file_type.get_value(),
+            bitmask: self.bitmask.bits,
+            timestamp: self.timestamp,
+            pack_files: self.pack_files.to_vec(),
+            notes: self.notes.clone(),
+            settings: self.settings.clone(),
+            extended_header: self.extended_header.clone(),
+            packed_files: BTreeMap::new(),
+        };
+
+        let paths = self.get_packed_files_all_paths();
+        let mut raw_tables = vec![];
+        for packed_file in &mut self.packed_files {
+            packed_file.encode()?;
+            let path = packed_file.get_path().to_vec();
+            let raw = packed_file.get_ref_raw();
+            let data = raw.get_data()?;
+
+            let mut entry = ProjectEntry {
+                file: path.join("/"),
+                format: ProjectEntryFormat::Raw,
+                timestamp: raw.get_timestamp(),
+                is_compressed: raw.get_should_be_compressed(),
+                uuid: None,
+                mysterious_byte: None,
+            };
+
+            // Tables go first as TSV, then as JSON. We only keep them if we can get the same data back from them.
+            let packed_file_type = PackedFileType::get_packed_file_type(&path);
+            if packed_file_type == PackedFileType::DB || packed_file_type == PackedFileType::Loc {
+                for format in &[ProjectEntryFormat::TSV, ProjectEntryFormat::JSON] {
+                    let file = format!("{}.{}", path.join("/"), if *format == ProjectEntryFormat::TSV { "tsv" } else { "json" });
+
+                    // Do not overwrite files that exist in the PackFile.
+                    if paths.iter().any(|x| x.join("/") == file) { continue; }
+                    let file_path = get_file_path(folder, &file)?;
+                    match unpack_table(schema, &path, &data, *format, &file_path) {
+                        Ok((uuid, mysterious_byte)) => {
+                            entry.file = file;
+                            entry.format = *format;
+                            if *format == ProjectEntryFormat::TSV {
+                                entry.uuid = uuid;
+                                entry.mysterious_byte = mysterious_byte;
+                            }
+                            break;
+                        }
+                        Err(_) => { let _ = remove_file(&file_path); },
+                    }
+                }
+
+                if entry.format == ProjectEntryFormat::Raw {
+                    raw_tables.push(path.to_vec());
+                }
+            }
+
+            if entry.format == ProjectEntryFormat::Raw {
+                File::create(get_file_path(folder, &entry.file)?)?.write_all(&data)?;
+            }
+
+            manifest.packed_files.insert(path.join("/"), entry);
+        }
+
+        File::create(&manifest_path)?.write_all(to_string_pretty(&manifest)?.as_bytes())?;
+        Ok(raw_tables)
+    }
+
+    /// This function rebuilds a `PackFile` from a project folder.
+    ///
+    /// The PackFile is not saved to disk. To get a PackFile equal to the unpacked one, save it with `save_keeping_timestamp`.
+    pub fn pack_from_folder(schema: &Schema, folder: &Path) -> Result<Self> {
+        let manifest: ProjectManifest = from_slice(&read(folder.join(PROJECT_MANIFEST_NAME))?)?;
+
+        let mut pack_file = Self::new_with_name(&manifest.file_name, PFHVersion::get_version(&manifest.pfh_version)?);
+        pack_file.set_pfh_file_type(PFHFileType::get_type(manifest.pfh_file_type));
+        pack_file.set_bitmask(PFHFlags::from_bits_truncate(manifest.bitmask));
+        pack_file.set_timestamp(manifest.timestamp);
+        pack_file.set_packfiles_list(&manifest.pack_files);
+        pack_file.set_notes(&manifest.notes);
+        pack_file.set_settings(&manifest.settings);
+        pack_file.set_extended_header(&manifest.extended_header);
+
+        let mut packed_files = vec![];
+        for (path, entry) in &manifest.packed_files {
+            let path = path.split('/').map(|x| x.to_owned()).collect::<Vec<String>>();
+            let file_path = get_file_path(folder, &entry.file)?;
+            let data = match entry.format {
+                ProjectEntryFormat::Raw => read(&file_path)?,
+                ProjectEntryFormat::TSV => pack_table_tsv(schema, &path, entry, &file_path)?,
+                ProjectEntryFormat::JSON => match PackedFileType::get_packed_file_type(&path) {
+                    PackedFileType::DB => {
+                        let db = from_slice::<DB>(&read(&file_path)?)?;
+                        db.save_with_uuid(&db.uuid)?
+                    }
+                    PackedFileType::Loc => from_slice::<Loc>(&read(&file_path)?)?.save()?,
+                    _ => return Err(ErrorKind::ProjectEntryIsNotATable(entry.file.to_owned()).into()),
+                },
+            };
+
+            let mut raw_packed_file = RawPackedFile::read_from_vec(path, manifest.file_name.to_owned(), entry.timestamp, false, data);
+            raw_packed_file.set_should_be_compressed(entry.is_compressed);
+            packed_files.push(PackedFile::new_from_raw(&raw_packed_file));
+        }
+
+        pack_file.add_packed_files(&packed_files.iter().collect::<Vec<&PackedFile>>(), true)?;
+        Ok(pack_file)
+    }
+}
+
+//---------------------------------------------------------------------------//
+//                              Util functions
+//---------------------------------------------------------------------------//
+
+/// This function returns the full path of a file in the project folder, creating its parent folders if needed.
+fn get_file_path(folder: &Path, file: &str) -> Result<PathBuf> {
+    let path = get_project_path(folder, file)?;
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    Ok(path)
+}
+
+/// This function returns the full path of a file in the project folder.
+///
+/// Each part of the path must be a normal name, so paths from a manifest cannot point outside the project folder.
+fn get_project_path(folder: &Path, file: &str) -> Result<PathBuf> {
+    let mut path = folder.to_path_buf();
+    for part in file.split('/') {
+        let mut components = Path::new(part).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), None) if name == part => path.push(part),
+            _ => return Err(ErrorKind::ProjectEntryInvalidPath(file.to_owned()).into()),
+        }
+    }
+    Ok(path)
+}
+
+/// This function writes a table into a file of the provided format, and checks that the table can be rebuilt from it.
+///
+/// It returns the UUID and the mysterious byte of the table, if it's a DB Table.
+fn unpack_table(schema: &Schema, path: &[String], data: &[u8], format: ProjectEntryFormat, file_path: &PathBuf) -> Result<(Option<String>, Option<bool>)> {
+    match PackedFileType::get_packed_file_type(path) {
+        PackedFileType::DB => {
+            let table_name = path.get(1).ok_or(ErrorKind::DBTableIsNotADBTable)?;
+            let db = DB::read(data, table_name, schema, false)?;
+            let new_data = if format == ProjectEntryFormat::TSV {
+                db.export_tsv(file_path, table_name)?;
+                let (mut new_db, _) = DB::import_tsv(db.get_ref_definition(), &[], file_path, table_name)?;
+                new_db.mysterious_byte = db.mysterious_byte;
+                new_db.save_with_uuid(&db.uuid)?
+            } else {
+                File::create(file_path)?.write_all(to_string_pretty(&db)?.as_bytes())?;
+                let new_db = from_slice::<DB>(&read(file_path)?)?;
+                new_db.save_with_uuid(&new_db.uuid)?
+            };
+
+            if new_data == data { Ok((Some(db.uuid), Some(db.mysterious_byte))) }
+            else { Err(ErrorKind::ProjectEntryMismatch(path.join("/")).into()) }
+        }
+
+        PackedFileType::Loc => {
+            let loc = Loc::read(data, schema, false)?;
+            let new_data = if format == ProjectEntryFormat::TSV {
+                loc.export_tsv(file_path, TSV_NAME_LOC)?;
+                Loc::import_tsv(loc.get_ref_definition(), &[], file_path, TSV_NAME_LOC)?.0.save()?
+            } else {
+                File::create(file_path)?.write_all(to_string_pretty(&loc)?.as_bytes())?;
+                from_slice::<Loc>(&read(file_path)?)?.save()?
+            };
+
+            if new_data == data { Ok((None, None)) }
+            else { Err(ErrorKind::ProjectEntryMismatch(path.join("/")).into()) }
+        }
+
+        _ => Err(ErrorKind::ProjectEntryIsNotATable(path.join("/")).into()),
+    }
+}
+
+/// This function rebuilds the data of a table from its TSV file in a project folder.
+///
+/// The version of the table is taken from the TSV file, so it's never migrated.
+fn pack_table_tsv(schema: &Schema, path: &[String], entry: &ProjectEntry, file_path: &PathBuf) -> Result<Vec<u8>> {
+    let tsv = read_to_string(file_path)?;
+    let version = tsv.lines().next()
+        .and_then(|line| line.split('\t').nth(1))
+        .and_then(|version| version.parse::<i32>().ok())
+        .ok_or(ErrorKind::ImportTSVInvalidVersion)?;
+
+    match PackedFileType::get_packed_file_type(path) {
+        PackedFileType::DB => {
+            let table_name = path.get(1).ok_or(ErrorKind::DBTableIsNotADBTable)?;
+            let definition = schema.get_ref_versioned_file_db(table_name)?.get_version(version)?;
+            let (mut db, _) = DB::import_tsv(definition, &[], file_path, table_name)?;
+            db.mysterious_byte = entry.mysterious_byte.unwrap_or(true);
+            match entry.uuid {
+                Some(ref uuid) => db.save_with_uuid(uuid),
+                None => db.save(),
+            }
+        }
+        PackedFileType::Loc => {
+            let definition = schema.get_ref_versioned_file_loc()?.get_version(version)?;
+            Loc::import_tsv(definition, &[], file_path, TSV_NAME_LOC)?.0.save()
+        }
+        _ => Err(ErrorKind::ProjectEntryIsNotATable(entry.file.to_owned()).into()),
+    }
+}
@@ -0,0 +1,136 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `Project` module, to make sure unpacking and packing a PackFile gives us the same PackFile.
+!*/
+
+use std::collections::BTreeMap;
+use std::env::temp_dir;
+use std::fs::{read, remove_dir_all};
+use std::path::PathBuf;
+
+use crate::packedfile::DecodedPackedFile;
+use crate::packedfile::table::DecodedData;
+use crate::packedfile::table::db::DB;
+use crate::packedfile::table::loc::Loc;
+use crate::packfile::packedfile::{PackedFile, RawPackedFile};
+use crate::schema::{Definition, Field, FieldType, Schema, VersionedFile};
+
+use super::*;
+use super::project::*;
+
+/// This function unpacks the provided PackFile into a folder, packs it back, and checks both PackFiles are equal byte by byte.
+fn round_trip(pack_file: &mut PackFile, schema: &Schema, name: &str) -> ProjectManifest {
+    let folder = temp_dir().join(format!("rpfm_{}_project", name));
+    let _ = remove_dir_all(&folder);
+
+    let base_path = temp_dir().join(format!("rpfm_{}_base.pack", name));
+    pack_file.save_keeping_timestamp(Some(base_path.to_path_buf())).unwrap();
+    let mut pack_file = PackFile::read(&base_path, false).unwrap();
+
+    // Unpacking twice should give us the same project.
+    pack_file.unpack_to_folder(schema, &folder).unwrap();
+    let manifest = read(folder.join(PROJECT_MANIFEST_NAME)).unwrap();
+    pack_file.unpack_to_folder(schema, &folder).unwrap();
+    assert_eq!(read(folder.join(PROJECT_MANIFEST_NAME)).unwrap(), manifest);
+
+    let new_path = temp_dir().join(format!("rpfm_{}_new.pack", name));
+    let mut new_pack_file = PackFile::pack_from_folder(schema, &folder).unwrap();
+    new_pack_file.save_keeping_timestamp(Some(new_path.to_path_buf())).unwrap();
+    assert_eq!(read(&base_path).unwrap(), read(&new_path).unwrap());
+
+    serde_json::from_slice(&manifest).unwrap()
+}
+
+#[test]
+fn test_project_pfh5() {
+    let mut pack_file = PackFile::read(&PathBuf::from("../test_files/PFH5_test.pack"), false).unwrap();
+    pack_file.set_notes(&Some("Notes\nin multiple lines.".to_owned()));
+    pack_file.set_packfiles_list(&["my_dependency.pack".to_owned()]);
+
+    let manifest = round_trip(&mut pack_file, &Schema::default(), "pfh5");
+    assert_eq!(manifest.notes, Some("Notes\nin multiple lines.".to_owned()));
+    assert_eq!(manifest.pack_files, vec!["my_dependency.pack".to_owned()]);
+    assert!(manifest.packed_files.values().all(|x| x.format == ProjectEntryFormat::Raw));
+}
+
+#[test]
+fn test_project_tables() {
+    let mut schema = Schema::default();
+    let mut definition = Definition::new(2);
+    *definition.get_ref_mut_fields() = vec![
+        Field::new("key".to_owned(), FieldType::StringU8, true, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("value".to_owned(), FieldType::I32, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+    ];
+    schema.add_versioned_file(&VersionedFile::DB("units_tables".to_owned(), vec![definition.clone()]));
+
+    let mut definition_loc = Definition::new(1);
+    *definition_loc.get_ref_mut_fields() = vec![
+        Field::new("key".to_owned(), FieldType::StringU16, true, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("text".to_owned(), FieldType::StringU16, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("tooltip".to_owned(), FieldType::Boolean, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+    ];
+    schema.add_versioned_file(&VersionedFile::Loc(vec![definition_loc.clone()]));
+
+    let mut db = DB::new("units_tables", None, &definition);
+    db.set_table_data(&[vec![DecodedData::StringU8("swords".to_owned()), DecodedData::I32(5)]]).unwrap();
+
+    let mut loc = Loc::new(&definition_loc);
+    loc.set_table_data(&[vec![DecodedData::StringU16("units_name_swords".to_owned()), DecodedData::StringU16("Swords\tand shields".to_owned()), DecodedData::Boolean(true)]]).unwrap();
+
+    let mut pack_file = PackFile::new_with_name("my_mod.pack", PFHVersion::PFH5);
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::DB(db), &["db".to_owned(), "units_tables".to_owned(), "my_mod".to_owned()]), false).unwrap();
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::Loc(loc), &["text".to_owned(), "my_mod.loc".to_owned()]), false).unwrap();
+    pack_file.add_packed_file(&PackedFile::new_from_raw(&RawPackedFile::read_from_vec(vec!["script".to_owned(), "my_mod.lua".to_owned()], String::new(), 0, false, b"print(\"hi\")".to_vec())), false).unwrap();
+
+    // The TSV path of the Loc is taken by another file, so the Loc should end up as JSON.
+    pack_file.add_packed_file(&PackedFile::new_from_raw(&RawPackedFile::read_from_vec(vec!["text".to_owned(), "my_mod.loc.tsv".to_owned()], String::new(), 0, false, b"not a table".to_vec())), false).unwrap();
+
+    let manifest = round_trip(&mut pack_file, &schema, "tables");
+    assert_eq!(manifest.packed_files["db/units_tables/my_mod"].format, ProjectEntryFormat::TSV);
+    assert_eq!(manifest.packed_files["db/units_tables/my_mod"].file, "db/units_tables/my_mod.tsv");
+    assert_eq!(manifest.packed_files["text/my_mod.loc"].format, ProjectEntryFormat::JSON);
+    assert_eq!(manifest.packed_files["text/my_mod.loc"].file, "text/my_mod.loc.json");
+    assert_eq!(manifest.packed_files["text/my_mod.loc.tsv"].format, ProjectEntryFormat::Raw);
+    assert_eq!(manifest.packed_files["script/my_mod.lua"].format, ProjectEntryFormat::Raw);
+}
+
+#[test]
+fn test_project_invalid_paths() {
+    let folder = temp_dir().join("rpfm_invalid_paths_project");
+    let outside_path = temp_dir().join("rpfm_invalid_paths_outside.txt");
+    let _ = remove_dir_all(&folder);
+    std::fs::write(&outside_path, b"outside").unwrap();
+
+    let mut pack_file = PackFile::new_with_name("my_mod.pack", PFHVersion::PFH5);
+    pack_file.add_packed_file(&PackedFile::new_from_raw(&RawPackedFile::read_from_vec(vec!["script".to_owned(), "my_mod.lua".to_owned()], String::new(), 0, false, b"print(\"hi\")".to_vec())), false).unwrap();
+    pack_file.unpack_to_folder(&Schema::default(), &folder).unwrap();
+
+    // Manifests with entries pointing outside the folder should fail, without touching the files outside.
+    let manifest_path = folder.join(PROJECT_MANIFEST_NAME);
+    let manifest_valid: ProjectManifest = serde_json::from_slice(&read(&manifest_path).unwrap()).unwrap();
+    for file in &["../rpfm_invalid_paths_outside.txt", &outside_path.to_string_lossy(), "script//my_mod.lua", "script/./my_mod.lua", ""] {
+        let mut manifest = manifest_valid.clone();
+        manifest.packed_files.get_mut("script/my_mod.lua").unwrap().file = (*file).to_owned();
+        std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest).unwrap()).unwrap();
+
+        assert!(PackFile::pack_from_folder(&Schema::default(), &folder).is_err());
+        assert!(pack_file.unpack_to_folder(&Schema::default(), &folder).is_err());
+        assert!(outside_path.is_file());
+    }
+
+    // Same with PackedFiles whose path would end up outside the folder.
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest_valid).unwrap()).unwrap();
+    let mut pack_file = PackFile::new_with_name("my_mod.pack", PFHVersion::PFH5);
+    pack_file.add_packed_file(&PackedFile::new_from_raw(&RawPackedFile::read_from_vec(vec!["..".to_owned(), "rpfm_invalid_paths_outside.txt".to_owned()], String::new(), 0, false, b"inside".to_vec())), false).unwrap();
+    assert!(pack_file.unpack_to_folder(&Schema::default(), &folder).is_err());
+    assert_eq!(read(&outside_path).unwrap(), b"outside");
+}
@@ -0,0 +1,489 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with all the code related to the `ReferenceIndex`.
+
+This module contains the code needed to build a graph of the references between DB Tables from a `Schema`,
+and to find all the usages of a key across the DB Tables, Locs and scripts of a `PackFile` and its dependencies.
+!*/
+
+use rayon::prelude::*;
+use serde_derive::{Serialize, Deserialize};
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::{fmt, fmt::Display};
+
+use rpfm_error::{ErrorKind, Result};
+
+use crate::DB;
+use crate::dependencies::Dependencies;
+use crate::packfile::PackFile;
+use crate::packfile::packedfile::PackedFile;
+use crate::packedfile::{DecodedPackedFile, PackedFileType};
+use crate::packedfile::table::loc::Loc;
+use crate::packedfile::text::{Text, TextType};
+use crate::schema::{Schema, VersionedFile};
+
+#[cfg(test)]
+mod references_test;
+
+//-------------------------------------------------------------------------------//
+//                              Enums & Structs
+//-------------------------------------------------------------------------------//
+
+/// This struct contains the reference graph of all the DB Tables in a `Schema`.
+///
+/// All table names here are without the `_tables` suffix.
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceIndex {
+
+    /// Columns referencing each table, by the name of the referenced table.
+    references: BTreeMap<String, BTreeSet<ColumnReference>>,
+
+    /// Localised fields of each table, by table name.
+    localised_fields: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// This struct represents a column of a table referencing another table.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ColumnReference {
+    pub table_name: String,
+    pub column_name: String,
+    pub referenced_column_name: String,
+}
+
+/// This struct represents a place where a key is used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyUsage {
+
+    /// Path of the `PackedFile` where the key is used.
+    pub path: String,
+
+    /// If the `PackedFile` is from the dependencies of the `PackFile`, instead of from the `PackFile` itself.
+    pub is_dependency: bool,
+    pub usage_type: KeyUsageType,
+
+    /// Row of the table or line of the text where the key is used, starting at 1.
+    pub row: u64,
+
+    /// Column of the table or the text where the key is used, starting at 1.
+    pub column: u32,
+
+    /// Column name for tables, the full key for Locs, and the line for texts.
+    pub context: String,
+}
+
+/// This struct represents a change needed to rename a key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRename {
+
+    /// Path of the `PackedFile` to change.
+    pub path: String,
+    pub usage_type: KeyUsageType,
+
+    /// Row of the table or line of the text to change, starting at 1.
+    pub row: u64,
+
+    /// Column of the table or the text to change, starting at 1.
+    pub column: u32,
+
+    /// Value before the rename. For texts, this is the full line.
+    pub old_value: String,
+
+    /// Value after the rename. For texts, this is the full line.
+    pub new_value: String,
+}
+
+/// This struct represents a Loc entry missing for a localised field of a DB row.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissingLocEntry {
+
+    /// Path of the DB Table with the row.
+    pub path: String,
+
+    /// Row of the table missing the Loc entry, starting at 1.
+    pub row: u64,
+
+    /// Key the Loc entry should have, in the `table_field_key` format.
+    pub key: String,
+
+    /// Placeholder text for the Loc entry. This is the key of the row.
+    pub text: String,
+}
+
+/// This enum represents the different ways a key can be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyUsageType {
+
+    /// The key is in one of the key columns of its own table.
+    Key,
+
+    /// The key is in a column referencing its table.
+    Reference,
+
+    /// The key is part of the key of a Loc entry.
+    Loc,
+
+    /// The key is in a script or another text file.
+    Text,
+}
+
+//-------------------------------------------------------------------------------//
+//                             Implementations
+//-------------------------------------------------------------------------------//
+
+/// Implementation of `ReferenceIndex`.
+impl ReferenceIndex {
+
+    /// This function builds the reference graph from all the versions of all the DB Tables of the provided `Schema`.
+    pub fn new(schema: &Schema) -> Self {
+        let mut index = Self::default();
+        for versioned_file in schema.get_ref_versioned_file_db_all() {
+            if let VersionedFile::DB(table_name, definitions) = versioned_file {
+                let table_name = table_name.strip_suffix("_tables").unwrap_or(table_name);
+                for definition in definitions {
+                    for field in definition.get_fields_processed() {
+                        if let Some((ref_table, ref_column)) = field.get_is_reference() {
+                            if !ref_table.is_empty() && !ref_column.is_empty() {
+                                index.references.entry(ref_table.to_owned()).or_default().insert(ColumnReference {
+                                    table_name: table_name.to_owned(),
+                                    column_name: field.get_name().to_owned(),
+                                    referenced_column_name: ref_column.to_owned(),
+                                });
+                            }
+                        }
+                    }
+
+                    if !definition.get_localised_fields().is_empty() {
+                        index.localised_fields.entry(table_name.to_owned()).or_default().extend(definition.get_localised_fields().iter().map(|x| x.get_name().to_owned()));
+                    }
+                }
+            }
+        }
+
+        index
+    }
+
+    /// This function returns all the columns referencing the provided table, with or without the `_tables` suffix.
+    pub fn get_references_to(&self, table_name: &str) -> Vec<&ColumnReference> {
+        let table_name = table_name.strip_suffix("_tables").unwrap_or(table_name);
+        self.references.get(table_name).map(|x| x.iter().collect()).unwrap_or_default()
+    }
+
+    /// This function returns all the columns of the provided table referencing other tables.
+    pub fn get_references_from(&self, table_name: &str) -> Vec<(&str, &ColumnReference)> {
+        let table_name = table_name.strip_suffix("_tables").unwrap_or(table_name);
+        self.references.iter()
+            .flat_map(|(ref_table, references)| references.iter().map(move |x| (&**ref_table, x)))
+            .filter(|(_, x)| x.table_name == table_name)
+            .collect()
+    }
+
+    /// This function returns the localised fields of the provided table, with or without the `_tables` suffix.
+    pub fn get_localised_fields(&self, table_name: &str) -> Vec<&str> {
+        let table_name = table_name.strip_suffix("_tables").unwrap_or(table_name);
+        self.localised_fields.get(table_name).map(|x| x.iter().map(|x| &**x).collect()).unwrap_or_default()
+    }
+
+    /// This function returns the Loc keys the provided key of the provided table may have, in the `table_field_key` format.
+    pub fn get_loc_keys(&self, table_name: &str, key: &str) -> Vec<String> {
+        let table_name = table_name.strip_suffix("_tables").unwrap_or(table_name);
+        self.get_localised_fields(table_name).iter().map(|field| format!("{}_{}_{}", table_name, field, key)).collect()
+    }
+
+    /// This function returns the Loc entries missing for the localised fields of the rows of all the DB Tables in the `PackFile`.
+    ///
+    /// An entry is missing if its key is not in the `PackFile` nor in its dependencies. The key of a row is the data of
+    /// all its key columns, joined in order. Each missing key is only reported once.
+    ///
+    /// NOTE: Only DB and Loc PackedFiles that are already decoded are checked.
+    pub fn get_missing_loc_entries(&self, pack_file: &PackFile, dependencies: &Dependencies) -> Vec<MissingLocEntry> {
+        let loc_keys = pack_file.get_ref_packed_files_by_type(PackedFileType::Loc, false).iter()
+            .map(|packed_file| packed_file.get_ref_decoded())
+            .chain(dependencies.get_ref_dependency_database().iter().filter_map(|packed_file| packed_file.get_decoded_from_memory().ok()))
+            .filter_map(|decoded| if let DecodedPackedFile::Loc(table) = decoded { Some(table) } else { None })
+            .flat_map(|table| table.get_ref_table_data().iter().filter_map(|row| row.first().map(|key| key.data_to_string())))
+            .collect::<HashSet<String>>();
+
+        let mut keys_missing = HashSet::new();
+        let mut entries = vec![];
+        for packed_file in pack_file.get_ref_packed_files_by_type(PackedFileType::DB, false) {
+            if let DecodedPackedFile::DB(table) = packed_file.get_ref_decoded() {
+                let table_name = table.get_table_name_without_tables();
+                let key_columns = table.get_ref_definition().get_fields_processed().iter()
+                    .enumerate()
+                    .filter(|(_, field)| field.get_is_key())
+                    .map(|(column, _)| column)
+                    .collect::<Vec<usize>>();
+
+                if key_columns.is_empty() || self.get_localised_fields(&table_name).is_empty() {
+                    continue;
+                }
+
+                let path = packed_file.get_path().join("/");
+                for (row, data) in table.get_ref_table_data().iter().enumerate() {
+                    let key = key_columns.iter().map(|column| data[*column].data_to_string()).collect::<String>();
+                    if key.is_empty() {
+                        continue;
+                    }
+
+                    for loc_key in self.get_loc_keys(&table_name, &key) {
+                        if !loc_keys.contains(&loc_key) && keys_missing.insert(loc_key.to_owned()) {
+                            entries.push(MissingLocEntry {
+                                path: path.to_owned(),
+                                row: row as u64 + 1,
+                                key: loc_key,
+                                text: key.to_owned(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        entries
+    }
+
+    /// This function returns all the usages of the provided key of the provided table, in the `PackFile` and its dependencies.
+    ///
+    /// This includes the rows of the table itself, any row referencing it, Loc entries of the key and occurrences of the key
+    /// in Lua and XML files. The table name can be passed with or without the `_tables` suffix.
+    ///
+    /// NOTE: Only DB and Loc PackedFiles that are already decoded are checked.
+    pub fn find_usages(&self, pack_file: &PackFile, dependencies: &Dependencies, table_name: &str, key: &str) -> Vec<KeyUsage> {
+        if key.is_empty() {
+            return vec![];
+        }
+
+        let table_name = table_name.strip_suffix("_tables").unwrap_or(table_name);
+        let loc_keys = self.get_loc_keys(table_name, key);
+
+        let mut usages = pack_file.get_ref_packed_files_by_types(&[PackedFileType::DB, PackedFileType::Loc], false).par_iter()
+            .flat_map(|packed_file| self.find_usages_in_decoded(packed_file.get_path(), packed_file.get_ref_decoded(), false, table_name, key, &loc_keys))
+            .collect::<Vec<KeyUsage>>();
+
+        usages.par_extend(dependencies.get_ref_dependency_database().par_iter()
+            .filter_map(|packed_file| packed_file.get_decoded_from_memory().ok().map(|decoded| (packed_file.get_path(), decoded)))
+            .flat_map(|(path, decoded)| self.find_usages_in_decoded(path, decoded, true, table_name, key, &loc_keys)));
+
+        usages.par_extend(dependencies.get_ref_fake_dependency_database().par_iter()
+            .flat_map(|table| {
+                let path = vec!["db".to_owned(), table.get_ref_table_name().to_owned()];
+                self.find_usages_in_db(&path, table, true, table_name, key)
+            }));
+
+        usages.par_extend(pack_file.get_ref_packed_files_by_types(&[PackedFileType::Text(TextType::Lua), PackedFileType::Text(TextType::Xml)], true).par_iter()
+            .flat_map(|packed_file| Self::find_usages_in_text(packed_file, key)));
+
+        usages.sort_by(|a, b| (a.is_dependency, &a.path, a.row, a.column).cmp(&(b.is_dependency, &b.path, b.row, b.column)));
+        usages
+    }
+
+    /// This function returns all the changes needed to rename a key of the provided table in the `PackFile`.
+    ///
+    /// Dependencies are not touched, so only the usages inside the `PackFile` are renamed. The key must be in the table, and the new key must not.
+    /// If `rename_in_scripts` is true, string literals in Lua scripts equal to the key are renamed too.
+    pub fn get_key_renames(&self, pack_file: &PackFile, table_name: &str, old_key: &str, new_key: &str, rename_in_scripts: bool) -> Result<Vec<KeyRename>> {
+        let table_name = table_name.strip_suffix("_tables").unwrap_or(table_name);
+        if new_key.is_empty() || old_key == new_key {
+            return Err(ErrorKind::KeyRenameInvalidKey.into());
+        }
+
+        let dependencies = Dependencies::default();
+        let usages = self.find_usages(pack_file, &dependencies, table_name, old_key);
+        if !usages.iter().any(|x| x.usage_type == KeyUsageType::Key) {
+            return Err(ErrorKind::KeyRenameKeyNotFound(old_key.to_owned(), table_name.to_owned()).into());
+        }
+
+        if self.find_usages(pack_file, &dependencies, table_name, new_key).iter().any(|x| x.usage_type == KeyUsageType::Key) {
+            return Err(ErrorKind::KeyRenameKeyAlreadyExists(new_key.to_owned(), table_name.to_owned()).into());
+        }
+
+        let mut renames = usages.into_iter().filter_map(|usage| {
+            let (old_value, new_value) = match usage.usage_type {
+                KeyUsageType::Key | KeyUsageType::Reference => (old_key.to_owned(), new_key.to_owned()),
+                KeyUsageType::Loc => {
+                    let new_value = format!("{}{}", &usage.context[..usage.context.len() - old_key.len()], new_key);
+                    (usage.context, new_value)
+                }
+
+                // Only string literals in scripts are renamed, and only if we're asked to.
+                KeyUsageType::Text => return None,
+            };
+
+            Some(KeyRename {
+                path: usage.path,
+                usage_type: usage.usage_type,
+                row: usage.row,
+                column: usage.column,
+                old_value,
+                new_value,
+            })
+        }).collect::<Vec<KeyRename>>();
+
+        if rename_in_scripts {
+            for packed_file in pack_file.get_ref_packed_files_by_type(PackedFileType::Text(TextType::Lua), true) {
+                if let Some(text) = Self::get_text_contents(packed_file) {
+                    let path = packed_file.get_path().join("/");
+                    for (row, line) in text.lines().enumerate() {
+                        let new_line = Self::rename_in_lua_strings(line, old_key, new_key);
+                        if new_line != line {
+                            let index = ['"', '\''].iter().filter_map(|quote| line.find(&format!("{}{}{}", quote, old_key, quote))).min().unwrap_or(0);
+
+                            // The column points to the key, not to its opening quote.
+                            let column = line[..index].chars().count() + 1;
+                            renames.push(KeyRename {
+                                path: path.to_owned(),
+                                usage_type: KeyUsageType::Text,
+                                row: row as u64 + 1,
+                                column: column as u32 + 1,
+                                old_value: line.trim().to_owned(),
+                                new_value: new_line.trim().to_owned(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(renames)
+    }
+
+    /// This function renames all the string literals in the provided Lua code that are equal to the provided key.
+    pub fn rename_in_lua_strings(text: &str, old_key: &str, new_key: &str) -> String {
+        ['"', '\''].iter().fold(text.to_owned(), |text, quote| text.replace(&format!("{}{}{}", quote, old_key, quote), &format!("{}{}{}", quote, new_key, quote)))
+    }
+
+    /// This function returns the usages of a key in a decoded DB or Loc Table.
+    fn find_usages_in_decoded(&self, path: &[String], decoded: &DecodedPackedFile, is_dependency: bool, table_name: &str, key: &str, loc_keys: &[String]) -> Vec<KeyUsage> {
+        match decoded {
+            DecodedPackedFile::DB(table) => self.find_usages_in_db(path, table, is_dependency, table_name, key),
+            DecodedPackedFile::Loc(table) => Self::find_usages_in_loc(path, table, is_dependency, table_name, key, loc_keys),
+            _ => vec![],
+        }
+    }
+
+    /// This function returns the usages of a key in a DB Table.
+    fn find_usages_in_db(&self, path: &[String], table: &DB, is_dependency: bool, table_name: &str, key: &str) -> Vec<KeyUsage> {
+        let current_table_name = table.get_table_name_without_tables();
+        let references = self.get_references_to(table_name);
+        let columns = table.get_ref_definition().get_fields_processed().iter().enumerate().filter_map(|(column, field)| {
+            if current_table_name == table_name && (field.get_is_key() || references.iter().any(|x| x.referenced_column_name == field.get_name())) {
+                Some((column, field.get_name().to_owned(), KeyUsageType::Key))
+            }
+            else if references.iter().any(|x| x.table_name == current_table_name && x.column_name == field.get_name()) {
+                Some((column, field.get_name().to_owned(), KeyUsageType::Reference))
+            }
+            else { None }
+        }).collect::<Vec<(usize, String, KeyUsageType)>>();
+
+        if columns.is_empty() {
+            return vec![];
+        }
+
+        let path = path.join("/");
+        let mut usages = vec![];
+        for (row, data) in table.get_ref_table_data().iter().enumerate() {
+            for (column, column_name, usage_type) in &columns {
+                if data[*column].data_to_string() == key {
+                    usages.push(KeyUsage {
+                        path: path.to_owned(),
+                        is_dependency,
+                        usage_type: *usage_type,
+                        row: row as u64 + 1,
+                        column: *column as u32 + 1,
+                        context: column_name.to_owned(),
+                    });
+                }
+            }
+        }
+
+        usages
+    }
+
+    /// This function returns the usages of a key in a Loc Table.
+    ///
+    /// If we don't know the localised fields of the table, we match any Loc key starting with the table name and ending with the key.
+    fn find_usages_in_loc(path: &[String], table: &Loc, is_dependency: bool, table_name: &str, key: &str, loc_keys: &[String]) -> Vec<KeyUsage> {
+        let prefix = format!("{}_", table_name);
+        let suffix = format!("_{}", key);
+        let path = path.join("/");
+
+        table.get_ref_table_data().iter().enumerate().filter_map(|(row, data)| {
+            let loc_key = data.first()?.data_to_string();
+            let is_match = if loc_keys.is_empty() {
+                loc_key.len() > prefix.len() + suffix.len() && loc_key.starts_with(&prefix) && loc_key.ends_with(&suffix)
+            } else { loc_keys.contains(&loc_key) };
+
+            if is_match {
+                Some(KeyUsage {
+                    path: path.to_owned(),
+                    is_dependency,
+                    usage_type: KeyUsageType::Loc,
+                    row: row as u64 + 1,
+                    column: 1,
+                    context: loc_key,
+                })
+            } else { None }
+        }).collect()
+    }
+
+    /// This function returns the occurrences of a key in a Text PackedFile.
+    ///
+    /// Only full words count, so a key that's part of a longer key is not reported.
+    fn find_usages_in_text(packed_file: &PackedFile, key: &str) -> Vec<KeyUsage> {
+        let text = match Self::get_text_contents(packed_file) {
+            Some(text) => text,
+            None => return vec![],
+        };
+
+        let is_word_char = |character: char| character.is_alphanumeric() || character == '_';
+        let path = packed_file.get_path().join("/");
+        let mut usages = vec![];
+        for (row, line) in text.lines().enumerate() {
+            for (index, _) in line.match_indices(key) {
+                let is_start = !matches!(line[..index].chars().last(), Some(character) if is_word_char(character));
+                let is_end = !matches!(line[index + key.len()..].chars().next(), Some(character) if is_word_char(character));
+                if is_start && is_end {
+                    usages.push(KeyUsage {
+                        path: path.to_owned(),
+                        is_dependency: false,
+                        usage_type: KeyUsageType::Text,
+                        row: row as u64 + 1,
+                        column: line[..index].chars().count() as u32 + 1,
+                        context: line.trim().to_owned(),
+                    });
+                }
+            }
+        }
+
+        usages
+    }
+
+    /// This function returns the contents of a Text PackedFile, decoding them if needed.
+    fn get_text_contents(packed_file: &PackedFile) -> Option<String> {
+        match packed_file.get_ref_decoded() {
+            DecodedPackedFile::Text(text) => Some(text.get_ref_contents().to_owned()),
+            _ => packed_file.get_raw_data().and_then(|data| Text::read(&data)).ok().map(|text| text.get_ref_contents().to_owned()),
+        }
+    }
+}
+
+impl Display for KeyUsageType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(match self {
+            Self::Key => "Key",
+            Self::Reference => "Reference",
+            Self::Loc => "Loc",
+            Self::Text => "Text",
+        }, f)
+    }
+}
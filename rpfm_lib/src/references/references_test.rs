@@ -0,0 +1,244 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `References` module, to make sure we find all the usages of a key.
+!*/
+
+use std::collections::BTreeMap;
+
+use crate::packedfile::table::DecodedData;
+use crate::schema::{Definition, Field, FieldType};
+
+use super::*;
+
+/// This function builds a field with the provided name, key status and reference.
+fn field(name: &str, is_key: bool, is_reference: Option<(&str, &str)>) -> Field {
+    let is_reference = is_reference.map(|(table, column)| (table.to_owned(), column.to_owned()));
+    Field::new(name.to_owned(), FieldType::StringU8, is_key, None, 0, false, None, is_reference, None, String::new(), 0, 0, BTreeMap::new())
+}
+
+/// This function builds a `Schema` with a few tables referencing each other.
+fn build_schema() -> Schema {
+    let mut schema = Schema::default();
+
+    let mut definition = Definition::new(1);
+    *definition.get_ref_mut_fields() = vec![field("key", true, None)];
+    *definition.get_ref_mut_localised_fields() = vec![field("onscreen_name", false, None)];
+    schema.add_versioned_file(&VersionedFile::DB("land_units_tables".to_owned(), vec![definition]));
+
+    let mut definition = Definition::new(1);
+    *definition.get_ref_mut_fields() = vec![field("unit", true, None), field("land_unit", false, Some(("land_units", "key")))];
+    schema.add_versioned_file(&VersionedFile::DB("main_units_tables".to_owned(), vec![definition]));
+
+    let mut definition_old = Definition::new(1);
+    *definition_old.get_ref_mut_fields() = vec![field("unit", true, Some(("main_units", "unit")))];
+    let mut definition = Definition::new(2);
+    *definition.get_ref_mut_fields() = vec![field("unit", true, Some(("main_units", "unit"))), field("faction", true, None)];
+    schema.add_versioned_file(&VersionedFile::DB("units_to_factions_tables".to_owned(), vec![definition, definition_old]));
+
+    schema
+}
+
+/// This function builds a decoded DB PackedFile from the provided schema.
+fn build_db(schema: &Schema, table_name: &str, file_name: &str, data: &[&[&str]]) -> PackedFile {
+    let definition = schema.get_ref_versioned_file_db(table_name).unwrap().get_version_list()[0].clone();
+    let mut table = DB::new(table_name, None, &definition);
+    table.set_table_data(&data.iter().map(|row| row.iter().map(|x| DecodedData::StringU8((*x).to_owned())).collect()).collect::<Vec<Vec<DecodedData>>>()).unwrap();
+    PackedFile::new_from_decoded(&DecodedPackedFile::DB(table), &["db".to_owned(), table_name.to_owned(), file_name.to_owned()])
+}
+
+#[test]
+fn test_references_index() {
+    let index = ReferenceIndex::new(&build_schema());
+
+    let references = index.get_references_to("main_units_tables");
+    assert_eq!(references, vec![&ColumnReference {
+        table_name: "units_to_factions".to_owned(),
+        column_name: "unit".to_owned(),
+        referenced_column_name: "unit".to_owned(),
+    }]);
+
+    let references = index.get_references_from("main_units");
+    assert_eq!(references.len(), 1);
+    assert_eq!(references[0].0, "land_units");
+
+    assert_eq!(index.get_localised_fields("land_units"), vec!["onscreen_name"]);
+    assert_eq!(index.get_loc_keys("land_units_tables", "lu_swords"), vec!["land_units_onscreen_name_lu_swords".to_owned()]);
+    assert!(index.get_references_to("factions").is_empty());
+}
+
+#[test]
+fn test_references_find_usages() {
+    let schema = build_schema();
+    let index = ReferenceIndex::new(&schema);
+
+    let mut pack_file = PackFile::new();
+    pack_file.add_packed_file(&build_db(&schema, "main_units_tables", "my_mod", &[&["wh_swords", "lu_swords"], &["wh_spears", "lu_spears"]]), false).unwrap();
+    pack_file.add_packed_file(&build_db(&schema, "units_to_factions_tables", "my_mod", &[&["wh_spears", "wh_swords"], &["wh_swords", "emp"]]), false).unwrap();
+
+    let mut text = Text::new();
+    text.set_text_type(TextType::Lua);
+    text.set_contents("cm:add_unit_to_faction(\"emp\", \"wh_swords\")\n-- wh_swords_2, my_wh_swords, wh_swords.");
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::Text(text), &["script".to_owned(), "my_mod.lua".to_owned()]), false).unwrap();
+
+    let mut loc_definition = Definition::new(1);
+    *loc_definition.get_ref_mut_fields() = vec![field("key", true, None), field("text", false, None)];
+    let mut loc = Loc::new(&loc_definition);
+    loc.set_table_data(&[
+        vec![DecodedData::StringU8("land_units_onscreen_name_lu_swords".to_owned()), DecodedData::StringU8("Swords".to_owned())],
+        vec![DecodedData::StringU8("land_units_onscreen_name_lu_swords_2".to_owned()), DecodedData::StringU8("Swords 2".to_owned())],
+    ]).unwrap();
+
+    let mut dependencies = Dependencies::default();
+    dependencies.get_ref_mut_dependency_database().push(build_db(&schema, "main_units_tables", "data__", &[&["wh_swords", "lu_swords"]]));
+    dependencies.get_ref_mut_dependency_database().push(PackedFile::new_from_decoded(&DecodedPackedFile::Loc(loc), &["text".to_owned(), "db".to_owned(), "data__.loc".to_owned()]));
+
+    let definition = schema.get_ref_versioned_file_db("units_to_factions_tables").unwrap().get_version_list()[1].clone();
+    let mut fake_table = DB::new("units_to_factions_tables", None, &definition);
+    fake_table.set_table_data(&[vec![DecodedData::StringU8("wh_swords".to_owned())]]).unwrap();
+    dependencies.get_ref_mut_fake_dependency_database().push(fake_table);
+
+    let usages = index.find_usages(&pack_file, &dependencies, "main_units", "wh_swords");
+    let results = usages.iter().map(|x| (x.path.as_str(), x.is_dependency, x.usage_type, x.row, x.column)).collect::<Vec<(&str, bool, KeyUsageType, u64, u32)>>();
+    assert_eq!(results, vec![
+        ("db/main_units_tables/my_mod", false, KeyUsageType::Key, 1, 1),
+        ("db/units_to_factions_tables/my_mod", false, KeyUsageType::Reference, 2, 1),
+        ("script/my_mod.lua", false, KeyUsageType::Text, 1, 32),
+        ("script/my_mod.lua", false, KeyUsageType::Text, 2, 31),
+        ("db/main_units_tables/data__", true, KeyUsageType::Key, 1, 1),
+        ("db/units_to_factions_tables", true, KeyUsageType::Reference, 1, 1),
+    ]);
+    assert_eq!(usages[1].context, "unit");
+
+    let usages = index.find_usages(&pack_file, &dependencies, "land_units_tables", "lu_swords");
+    let results = usages.iter().map(|x| (x.path.as_str(), x.usage_type, x.row, x.context.as_str())).collect::<Vec<(&str, KeyUsageType, u64, &str)>>();
+    assert_eq!(results, vec![
+        ("db/main_units_tables/my_mod", KeyUsageType::Reference, 1, "land_unit"),
+        ("db/main_units_tables/data__", KeyUsageType::Reference, 1, "land_unit"),
+        ("text/db/data__.loc", KeyUsageType::Loc, 1, "land_units_onscreen_name_lu_swords"),
+    ]);
+
+    assert!(index.find_usages(&pack_file, &dependencies, "main_units", "").is_empty());
+}
+
+#[test]
+fn test_references_rename_key() {
+    let schema = build_schema();
+
+    let mut pack_file = PackFile::new();
+    pack_file.add_packed_file(&build_db(&schema, "land_units_tables", "my_mod", &[&["lu_swords"], &["lu_spears"]]), false).unwrap();
+    pack_file.add_packed_file(&build_db(&schema, "main_units_tables", "my_mod", &[&["wh_swords", "lu_swords"], &["wh_spears", "lu_spears"]]), false).unwrap();
+
+    let mut text = Text::new();
+    text.set_text_type(TextType::Lua);
+    text.set_contents("local unit = \"lu_swords\"\nlocal other = 'lu_swords_2' -- lu_swords\n");
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::Text(text), &["script".to_owned(), "my_mod.lua".to_owned()]), false).unwrap();
+
+    let mut loc_definition = Definition::new(1);
+    *loc_definition.get_ref_mut_fields() = vec![field("key", true, None), field("text", false, None)];
+    let mut loc = Loc::new(&loc_definition);
+    loc.set_table_data(&[vec![DecodedData::StringU8("land_units_onscreen_name_lu_swords".to_owned()), DecodedData::StringU8("Swords".to_owned())]]).unwrap();
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::Loc(loc), &["text".to_owned(), "db".to_owned(), "my_mod.loc".to_owned()]), false).unwrap();
+
+    assert!(pack_file.rename_key(&schema, "land_units", "lu_swords", "lu_swords", false, true).is_err());
+    assert!(pack_file.rename_key(&schema, "land_units", "lu_axes", "lu_great_axes", false, true).is_err());
+    assert!(pack_file.rename_key(&schema, "land_units", "lu_swords", "lu_spears", false, true).is_err());
+
+    // Dry runs must report the changes without applying them.
+    let renames = pack_file.rename_key(&schema, "land_units_tables", "lu_swords", "lu_sabres", true, true).unwrap();
+    let results = renames.iter().map(|x| (x.path.as_str(), x.usage_type, x.row, x.column, x.new_value.as_str())).collect::<Vec<(&str, KeyUsageType, u64, u32, &str)>>();
+    assert_eq!(results, vec![
+        ("db/land_units_tables/my_mod", KeyUsageType::Key, 1, 1, "lu_sabres"),
+        ("db/main_units_tables/my_mod", KeyUsageType::Reference, 1, 2, "lu_sabres"),
+        ("text/db/my_mod.loc", KeyUsageType::Loc, 1, 1, "land_units_onscreen_name_lu_sabres"),
+        ("script/my_mod.lua", KeyUsageType::Text, 1, 15, "local unit = \"lu_sabres\""),
+    ]);
+
+    let index = ReferenceIndex::new(&schema);
+    assert_eq!(index.find_usages(&pack_file, &Dependencies::default(), "land_units", "lu_sabres"), vec![]);
+
+    let renames_applied = pack_file.rename_key(&schema, "land_units", "lu_swords", "lu_sabres", true, false).unwrap();
+    assert_eq!(renames, renames_applied);
+
+    let usages = index.find_usages(&pack_file, &Dependencies::default(), "land_units", "lu_sabres");
+    assert_eq!(usages.len(), 4);
+    assert!(index.find_usages(&pack_file, &Dependencies::default(), "land_units", "lu_swords").iter().all(|x| x.usage_type == KeyUsageType::Text));
+
+    let script = pack_file.get_ref_packed_file_by_path(&["script".to_owned(), "my_mod.lua".to_owned()]).unwrap();
+    if let DecodedPackedFile::Text(text) = script.get_ref_decoded() {
+        assert_eq!(text.get_ref_contents(), "local unit = \"lu_sabres\"\nlocal other = 'lu_swords_2' -- lu_swords\n");
+    } else { panic!("Script not decoded."); }
+}
+
+#[test]
+fn test_references_rename_key_failed() {
+    let mut schema = build_schema();
+    let mut definition = Definition::new(1);
+    let mut id = field("land_unit_id", true, Some(("land_units", "key")));
+    id.set_field_type(FieldType::I32);
+    *definition.get_ref_mut_fields() = vec![id];
+    schema.add_versioned_file(&VersionedFile::DB("unit_ids_tables".to_owned(), vec![definition.to_owned()]));
+
+    let mut pack_file = PackFile::new();
+    pack_file.add_packed_file(&build_db(&schema, "land_units_tables", "my_mod", &[&["10"]]), false).unwrap();
+    let mut table = DB::new("unit_ids_tables", None, &definition);
+    table.set_table_data(&[vec![DecodedData::I32(10)]]).unwrap();
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::DB(table), &["db".to_owned(), "unit_ids_tables".to_owned(), "my_mod".to_owned()]), false).unwrap();
+
+    // The new key is not valid for the integer reference, so nothing should be renamed.
+    assert!(pack_file.rename_key(&schema, "land_units", "10", "lu_ten", false, false).is_err());
+    let index = ReferenceIndex::new(&schema);
+    assert_eq!(index.find_usages(&pack_file, &Dependencies::default(), "land_units", "10").len(), 2);
+    assert!(index.find_usages(&pack_file, &Dependencies::default(), "land_units", "lu_ten").is_empty());
+}
+
+#[test]
+fn test_references_loc_stubs() {
+    let mut schema = build_schema();
+    let mut loc_definition = Definition::new(1);
+    *loc_definition.get_ref_mut_fields() = vec![field("key", true, None), field("text", false, None)];
+    schema.add_versioned_file(&VersionedFile::Loc(vec![loc_definition.clone()]));
+
+    let mut pack_file = PackFile::new();
+    pack_file.add_packed_file(&build_db(&schema, "land_units_tables", "my_mod", &[&["lu_swords"], &["lu_spears"], &["lu_axes"], &[""]]), false).unwrap();
+    pack_file.add_packed_file(&build_db(&schema, "land_units_tables", "my_mod_2", &[&["lu_axes"]]), false).unwrap();
+    pack_file.add_packed_file(&build_db(&schema, "main_units_tables", "my_mod", &[&["wh_swords", "lu_swords"]]), false).unwrap();
+
+    let mut loc = Loc::new(&loc_definition);
+    loc.set_table_data(&[vec![DecodedData::StringU8("land_units_onscreen_name_lu_swords".to_owned()), DecodedData::StringU8("Swords".to_owned())]]).unwrap();
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::Loc(loc), &["text".to_owned(), "db".to_owned(), "my_mod.loc".to_owned()]), false).unwrap();
+
+    let mut loc = Loc::new(&loc_definition);
+    loc.set_table_data(&[vec![DecodedData::StringU8("land_units_onscreen_name_lu_spears".to_owned()), DecodedData::StringU8("Spears".to_owned())]]).unwrap();
+    let mut dependencies = Dependencies::default();
+    dependencies.get_ref_mut_dependency_database().push(PackedFile::new_from_decoded(&DecodedPackedFile::Loc(loc), &["text".to_owned(), "db".to_owned(), "data__.loc".to_owned()]));
+
+    let index = ReferenceIndex::new(&schema);
+    assert_eq!(index.get_missing_loc_entries(&pack_file, &dependencies), vec![MissingLocEntry {
+        path: "db/land_units_tables/my_mod".to_owned(),
+        row: 3,
+        key: "land_units_onscreen_name_lu_axes".to_owned(),
+        text: "lu_axes".to_owned(),
+    }]);
+
+    // Dry runs must not add anything.
+    let path = vec!["text".to_owned(), "db".to_owned(), "my_mod_stubs.loc".to_owned()];
+    assert_eq!(pack_file.add_loc_stubs(&schema, &dependencies, &path, true).unwrap().len(), 1);
+    assert!(pack_file.get_ref_packed_file_by_path(&path).is_none());
+
+    assert_eq!(pack_file.add_loc_stubs(&schema, &dependencies, &path, false).unwrap().len(), 1);
+    if let DecodedPackedFile::Loc(table) = pack_file.get_ref_packed_file_by_path(&path).unwrap().get_ref_decoded() {
+        assert_eq!(table.get_ref_table_data(), &[vec![DecodedData::StringU8("land_units_onscreen_name_lu_axes".to_owned()), DecodedData::StringU8("lu_axes".to_owned())]]);
+    } else { panic!("Loc not decoded."); }
+
+    assert!(index.get_missing_loc_entries(&pack_file, &dependencies).is_empty());
+    assert!(pack_file.add_loc_stubs(&schema, &dependencies, &["db".to_owned(), "land_units_tables".to_owned(), "my_mod".to_owned()], false).unwrap().is_empty());
+}
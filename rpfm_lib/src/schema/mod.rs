@@ -831,6 +831,11 @@ impl Definition {
         &self.localised_fields
     }
 
+    /// This function returns a mutable reference to the localised fields of the provided definition
+    pub fn get_ref_mut_localised_fields(&mut self) -> &mut Vec<Field> {
+        &mut self.localised_fields
+    }
+
     /// This function returns the list of fields a table contains, after it has been expanded/changed due to the attributes of each field.
     pub fn get_fields_processed(&self) -> Vec<Field> {
         self.get_ref_fields().iter()
@@ -1160,6 +1165,11 @@ impl Field {
         self.is_bitwise
     }
 
+    /// Setter for the `is_bitwise` field.
+    pub fn set_is_bitwise(&mut self, is_bitwise: i32) {
+        self.is_bitwise = is_bitwise;
+    }
+
     /// Getter for the `enum_values` field.
     pub fn get_enum_values(&self) -> &BTreeMap<i32, String> {
         &self.enum_values
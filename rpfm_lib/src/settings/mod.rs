@@ -35,9 +35,6 @@ use crate::updater::STABLE;
 /// Name of the settings file.
 const SETTINGS_FILE: &str = "settings.ron";
 
-/// Key of the 7Zip path in the settings";
-pub const ZIP_PATH: &str = "7zip_path";
-
 /// Key of the MyMod path in the settings";
 pub const MYMOD_BASE_PATH: &str = "mymods_base_path";
 
@@ -60,7 +57,6 @@ impl Settings {
         let mut settings_string = BTreeMap::new();
         let mut settings_bool = BTreeMap::new();
         paths.insert(MYMOD_BASE_PATH.to_owned(), None);
-        paths.insert(ZIP_PATH.to_owned(), None);
         for (folder_name, _) in SUPPORTED_GAMES.iter() {
             paths.insert((*folder_name).to_string(), None);
         }
@@ -21,10 +21,12 @@ use serde_derive::{Serialize, Deserialize};
 
 use std::fs::{DirBuilder, File};
 use std::io::{BufReader, Write};
+use std::path::Path;
 
 use rpfm_error::{ErrorKind, Result};
 
 use crate::common::*;
+use crate::context::GameContext;
 use crate::dependencies::Dependencies;
 use crate::packfile::{PackFile, packedfile::PackedFile};
 use crate::packedfile::DecodedPackedFile;
@@ -33,7 +35,7 @@ use crate::packedfile::table::loc::Loc;
 use crate::packedfile::table::Table;
 use crate::packedfile::table::DecodedData;
 use crate::SCHEMA;
-use crate::schema::{APIResponseSchema, FieldType};
+use crate::schema::{APIResponseSchema, FieldType, Schema};
 
 pub const TEMPLATE_FOLDER: &str = "templates";
 pub const DEFINITIONS_FOLDER: &str = "definitions";
@@ -95,6 +97,18 @@ impl Template {
 
     /// This function applyes a `Template` into the currently open PackFile, if there is one open.
     pub fn apply_template(&mut self, params: &[String], pack_file: &mut PackFile, dependencies: &Dependencies) -> Result<Vec<Vec<String>>> {
+        let assets_folder = GameContext::from_game_selected()?.get_template_assets_path()?;
+        self.apply(params, pack_file, SCHEMA.read().unwrap().as_ref(), dependencies, &assets_folder)
+    }
+
+    /// This function applyes a `Template` into the provided PackFile, using the schema, dependencies and template assets of the provided `GameContext`.
+    pub fn apply_template_with_context(&mut self, params: &[String], pack_file: &mut PackFile, context: &GameContext) -> Result<Vec<Vec<String>>> {
+        let assets_folder = context.get_template_assets_path()?;
+        self.apply(params, pack_file, context.get_ref_schema(), context.get_ref_dependencies(), &assets_folder)
+    }
+
+    /// This function applyes a `Template` into the provided PackFile, using the provided schema, dependencies and template assets folder.
+    fn apply(&mut self, params: &[String], pack_file: &mut PackFile, schema: Option<&Schema>, dependencies: &Dependencies, assets_folder: &Path) -> Result<Vec<Vec<String>>> {
 
         // If there is no PackFile open, stop.
         if pack_file.get_file_name().is_empty() {
@@ -137,7 +151,7 @@ impl Template {
 
 
         // Then, just process each section. In case of collision, we try to append the new data at the end of the file.
-        match &*SCHEMA.read().unwrap() {
+        match schema {
             Some(schema) => {
                 let mut paths = vec![];
                 let mut packed_files = vec![];
@@ -241,7 +255,6 @@ impl Template {
                 }
 
                 // And finally, the custom assets.
-                let assets_folder = get_template_assets_path()?;
                 for asset in &self.assets {
                     let path = assets_folder.join(&asset.file_name);
                     let packed_file_path = asset.packed_file_path.split('/').map(|x| x.to_owned()).collect::<Vec<String>>();
@@ -0,0 +1,484 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module with all the code related to the `Translation`.
+
+This module contains the code needed to export the Loc entries of a `PackFile` to gettext PO and XLIFF files,
+import them back by key, and report which keys are missing or out of date in a translation.
+!*/
+
+use serde_derive::{Serialize, Deserialize};
+use xml::reader::{ParserConfig, XmlEvent as ReaderEvent};
+use xml::writer::{EmitterConfig, XmlEvent as WriterEvent};
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use rpfm_error::{ErrorKind, Result};
+
+use crate::common::get_game_selected_loc_pack_path;
+use crate::packedfile::{DecodedPackedFile, PackedFileType};
+use crate::packedfile::table::DecodedData;
+use crate::packfile::PackFile;
+use crate::schema::Schema;
+
+#[cfg(test)]
+mod translation_test;
+
+/// Language of the source strings of the translations.
+const SOURCE_LANGUAGE: &str = "en";
+
+/// Version of the XLIFF format we use.
+const XLIFF_VERSION: &str = "1.2";
+
+/// Namespace of the XLIFF format we use.
+const XLIFF_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:1.2";
+
+//-------------------------------------------------------------------------------//
+//                              Enums & Structs
+//-------------------------------------------------------------------------------//
+
+/// This struct contains the translation of all the Loc entries of a `PackFile` to a language.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Translation {
+
+    /// Language the entries are translated to.
+    language: String,
+
+    /// Translated entries, in the order they're in the `PackFile`.
+    entries: Vec<TranslationEntry>,
+}
+
+/// This struct represents the translation of a single Loc entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranslationEntry {
+    pub key: String,
+
+    /// Text in the source language. This is the vanilla english text if the key exists in vanilla, or the text in the `PackFile` if not.
+    pub source: String,
+
+    /// Translated text. Empty if the entry is not translated yet.
+    pub target: String,
+}
+
+/// This enum represents the file formats a `Translation` can be exported to and imported from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TranslationFormat {
+    PO,
+    XLIFF,
+}
+
+/// This struct contains the keys missing or out of date in a `Translation`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranslationReport {
+    pub language: String,
+
+    /// Keys in the `PackFile` that are not translated.
+    pub missing: Vec<String>,
+
+    /// Keys whose source text changed since they were translated.
+    pub outdated: Vec<String>,
+}
+
+//-------------------------------------------------------------------------------//
+//                             Implementations
+//-------------------------------------------------------------------------------//
+
+/// Implementation of `Translation`.
+impl Translation {
+
+    /// This function creates a `Translation` with all the Loc entries of the provided `PackFile`.
+    ///
+    /// The source of each entry is taken from `source_texts` if the key is in there, or from the `PackFile` otherwise.
+    /// The target of each entry is taken from the `existing` translation if it has the key translated, and it's left empty otherwise.
+    /// The texts of the `PackFile` are never used as targets, as mods can change the source texts too.
+    ///
+    /// NOTE: Only Loc PackedFiles that are already decoded are used.
+    pub fn new(pack_file: &PackFile, source_texts: &BTreeMap<String, String>, language: &str, existing: Option<&Self>) -> Self {
+        let targets = existing.map(|existing| existing.entries.iter()
+            .filter(|x| !x.target.is_empty())
+            .map(|x| (&*x.key, &*x.target))
+            .collect::<BTreeMap<&str, &str>>()
+        ).unwrap_or_default();
+
+        let mut keys = HashSet::new();
+        let mut entries = vec![];
+        for packed_file in pack_file.get_ref_packed_files_by_type(PackedFileType::Loc, false) {
+            if let DecodedPackedFile::Loc(table) = packed_file.get_ref_decoded() {
+                for row in table.get_ref_table_data() {
+                    if let (Some(key), Some(text)) = (row.first(), row.get(1)) {
+                        let key = key.data_to_string();
+                        if key.is_empty() || !keys.insert(key.to_owned()) {
+                            continue;
+                        }
+
+                        let source = source_texts.get(&key).cloned().unwrap_or_else(|| text.data_to_string());
+                        let target = targets.get(&*key).map(|x| (*x).to_owned()).unwrap_or_default();
+                        entries.push(TranslationEntry { key, source, target });
+                    }
+                }
+            }
+        }
+
+        Self {
+            language: language.to_owned(),
+            entries,
+        }
+    }
+
+    /// This function returns the texts of all the vanilla english Loc entries of the game selected, by key.
+    pub fn get_source_texts(schema: &Schema) -> Result<BTreeMap<String, String>> {
+        let paths = get_game_selected_loc_pack_path().ok_or(ErrorKind::GameSelectedPathNotCorrectlyConfigured)?;
+        let mut source_texts = BTreeMap::new();
+
+        // The english packs are the base one and its patches, and the later ones overwrite the earlier ones.
+        for path in &paths {
+            let is_english = match path.file_stem() {
+                Some(stem) => stem.to_string_lossy().split('_').any(|x| x == SOURCE_LANGUAGE),
+                None => false,
+            };
+
+            if !is_english || !path.is_file() {
+                continue;
+            }
+
+            let mut pack_file = PackFile::open_packfiles(&[path.to_path_buf()], true, false, false)?;
+            for packed_file in pack_file.get_ref_mut_packed_files_by_type(PackedFileType::Loc, false) {
+                if let Ok(DecodedPackedFile::Loc(table)) = packed_file.decode_return_ref_no_locks(schema) {
+                    for row in table.get_ref_table_data() {
+                        if let (Some(key), Some(text)) = (row.first(), row.get(1)) {
+                            source_texts.insert(key.data_to_string(), text.data_to_string());
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(source_texts)
+    }
+
+    /// This function returns the language of the `Translation`.
+    pub fn get_language(&self) -> &str {
+        &self.language
+    }
+
+    /// This function returns a reference to the entries of the `Translation`.
+    pub fn get_ref_entries(&self) -> &[TranslationEntry] {
+        &self.entries
+    }
+
+    /// This function returns a mutable reference to the entries of the `Translation`.
+    pub fn get_ref_mut_entries(&mut self) -> &mut Vec<TranslationEntry> {
+        &mut self.entries
+    }
+
+    /// This function reads a `Translation` in the provided format.
+    pub fn read(data: &str, format: TranslationFormat) -> Result<Self> {
+        match format {
+            TranslationFormat::PO => Self::read_po(data),
+            TranslationFormat::XLIFF => Self::read_xliff(data),
+        }
+    }
+
+    /// This function writes the `Translation` in the provided format.
+    pub fn save(&self, format: TranslationFormat) -> Result<String> {
+        match format {
+            TranslationFormat::PO => Ok(self.save_po()),
+            TranslationFormat::XLIFF => self.save_xliff(),
+        }
+    }
+
+    /// This function sets the texts of the Loc entries of the `PackFile` to the translated ones, matching them by key.
+    ///
+    /// Untranslated entries are ignored. It returns the keys of the translated entries not found in the `PackFile`.
+    pub fn import(&self, pack_file: &mut PackFile, schema: &Schema) -> Result<Vec<String>> {
+        let mut translations = self.entries.iter()
+            .filter(|x| !x.target.is_empty())
+            .map(|x| (&*x.key, &*x.target))
+            .collect::<BTreeMap<&str, &str>>();
+
+        let mut keys_found = HashSet::new();
+        for packed_file in pack_file.get_ref_mut_packed_files_by_type(PackedFileType::Loc, false) {
+            if let Ok(DecodedPackedFile::Loc(table)) = packed_file.decode_return_ref_mut_no_locks(schema) {
+                let field_type = match table.get_ref_definition().get_fields_processed().get(1) {
+                    Some(field) => field.get_ref_field_type().clone(),
+                    None => continue,
+                };
+
+                let mut data = table.get_table_data();
+                let mut is_changed = false;
+                for row in &mut data {
+                    let key = match row.first() {
+                        Some(key) => key.data_to_string(),
+                        None => continue,
+                    };
+
+                    if let Some(target) = translations.get(&*key) {
+                        let text = DecodedData::StringU8((*target).to_owned()).convert_between_types(&field_type)?;
+                        if row[1] != text {
+                            row[1] = text;
+                            is_changed = true;
+                        }
+                        keys_found.insert(key);
+                    }
+                }
+
+                if is_changed {
+                    table.set_table_data(&data)?;
+                }
+            }
+        }
+
+        translations.retain(|key, _| !keys_found.contains(*key));
+        Ok(translations.keys().map(|x| (*x).to_owned()).collect())
+    }
+
+    /// This function returns the keys missing or out of date in this `Translation`, compared to the provided up-to-date one.
+    ///
+    /// The up-to-date `Translation` is expected to be generated from the current `PackFile`, using `Translation::new`.
+    pub fn get_report(&self, current: &Self) -> TranslationReport {
+        let entries = self.entries.iter().map(|x| (&*x.key, x)).collect::<BTreeMap<&str, &TranslationEntry>>();
+        let mut report = TranslationReport {
+            language: self.language.to_owned(),
+            ..Default::default()
+        };
+
+        for current_entry in &current.entries {
+            match entries.get(&*current_entry.key) {
+                Some(entry) if !entry.target.is_empty() => {
+                    if entry.source != current_entry.source {
+                        report.outdated.push(current_entry.key.to_owned());
+                    }
+                }
+                _ => report.missing.push(current_entry.key.to_owned()),
+            }
+        }
+
+        report
+    }
+
+    /// This function reads a `Translation` from a gettext PO file.
+    ///
+    /// Each entry must have its key as `msgctxt`. Entries without it, except the header, are ignored.
+    fn read_po(data: &str) -> Result<Self> {
+        let mut translation = Self::default();
+        let mut key: Option<String> = None;
+        let mut source = String::new();
+        let mut target = String::new();
+        let mut current_field = None;
+        let mut is_header_read = false;
+
+        for (index, line) in data.lines().chain(std::iter::once("")).enumerate() {
+            let line = line.trim();
+
+            // Empty lines and comments end the current entry.
+            if line.is_empty() || line.starts_with('#') {
+                if current_field.is_some() {
+                    match key.take() {
+                        Some(key) => translation.entries.push(TranslationEntry { key, source, target }),
+                        None => if !is_header_read && source.is_empty() {
+                            translation.language = target.lines()
+                                .find_map(|x| x.strip_prefix("Language:"))
+                                .map(|x| x.trim().to_owned())
+                                .unwrap_or_default();
+                            is_header_read = true;
+                        }
+                    }
+
+                    source = String::new();
+                    target = String::new();
+                    current_field = None;
+                }
+                continue;
+            }
+
+            let (field, value) = if line.starts_with('"') { (current_field, line) }
+            else {
+                let (field, value) = line.split_at(line.find(' ').unwrap_or(line.len()));
+                (Some(field.to_owned()), value.trim())
+            };
+
+            let value = Self::unescape_po_string(value).ok_or_else(|| ErrorKind::TranslationImport(format!("Invalid string in line {}.", index + 1)))?;
+            match field.as_deref() {
+                Some("msgctxt") => key.get_or_insert_with(String::new).push_str(&value),
+                Some("msgid") => source.push_str(&value),
+                Some("msgstr") | Some("msgstr[0]") => target.push_str(&value),
+                Some("msgid_plural") | Some("msgstr[1]") => {},
+                _ => return Err(ErrorKind::TranslationImport(format!("Unexpected keyword in line {}.", index + 1)).into()),
+            }
+
+            current_field = field;
+        }
+
+        Ok(translation)
+    }
+
+    /// This function writes the `Translation` as a gettext PO file.
+    fn save_po(&self) -> String {
+        let mut po = String::new();
+        po.push_str("msgid \"\"\nmsgstr \"\"\n");
+        po.push_str(&format!("\"Language: {}\\n\"\n", self.language));
+        po.push_str("\"MIME-Version: 1.0\\n\"\n");
+        po.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+        po.push_str("\"Content-Transfer-Encoding: 8bit\\n\"\n");
+
+        for entry in &self.entries {
+            po.push_str(&format!("\nmsgctxt \"{}\"\n", Self::escape_po_string(&entry.key)));
+            po.push_str(&format!("msgid \"{}\"\n", Self::escape_po_string(&entry.source)));
+            po.push_str(&format!("msgstr \"{}\"\n", Self::escape_po_string(&entry.target)));
+        }
+
+        po
+    }
+
+    /// This function reads a `Translation` from a XLIFF 1.2 file.
+    ///
+    /// Each `trans-unit` must have its key as `id`.
+    fn read_xliff(data: &str) -> Result<Self> {
+        let config = ParserConfig::new()
+            .trim_whitespace(false)
+            .whitespace_to_characters(true)
+            .cdata_to_characters(true);
+
+        let mut translation = Self::default();
+        let mut entry: Option<TranslationEntry> = None;
+        let mut current_element = String::new();
+
+        for event in config.create_reader(data.as_bytes()) {
+            match event.map_err(|error| ErrorKind::TranslationImport(error.to_string()))? {
+                ReaderEvent::StartElement { name, attributes, .. } => {
+                    match &*name.local_name {
+                        "file" => if let Some(language) = attributes.iter().find(|x| x.name.local_name == "target-language") {
+                            translation.language = language.value.to_owned();
+                        }
+                        "trans-unit" => match attributes.iter().find(|x| x.name.local_name == "id") {
+                            Some(id) => entry = Some(TranslationEntry { key: id.value.to_owned(), source: String::new(), target: String::new() }),
+                            None => return Err(ErrorKind::TranslationImport("Translation unit without id found.".to_owned()).into()),
+                        }
+                        _ => {}
+                    }
+                    current_element = name.local_name;
+                }
+
+                ReaderEvent::Characters(text) => if let Some(ref mut entry) = entry {
+                    match &*current_element {
+                        "source" => entry.source.push_str(&text),
+                        "target" => entry.target.push_str(&text),
+                        _ => {}
+                    }
+                }
+
+                ReaderEvent::EndElement { name } => {
+                    if name.local_name == "trans-unit" {
+                        if let Some(entry) = entry.take() {
+                            translation.entries.push(entry);
+                        }
+                    }
+                    current_element = String::new();
+                }
+                _ => {}
+            }
+        }
+
+        Ok(translation)
+    }
+
+    /// This function writes the `Translation` as a XLIFF 1.2 file.
+    fn save_xliff(&self) -> Result<String> {
+        let mut xliff = vec![];
+        {
+            let mut writer = EmitterConfig::new().perform_indent(true).create_writer(&mut xliff);
+            write_xml(&mut writer, WriterEvent::start_element("xliff").attr("version", XLIFF_VERSION).default_ns(XLIFF_NAMESPACE))?;
+            write_xml(&mut writer, WriterEvent::start_element("file")
+                .attr("original", "loc")
+                .attr("datatype", "plaintext")
+                .attr("source-language", SOURCE_LANGUAGE)
+                .attr("target-language", &self.language))?;
+            write_xml(&mut writer, WriterEvent::start_element("body"))?;
+
+            for entry in &self.entries {
+                write_xml(&mut writer, WriterEvent::start_element("trans-unit").attr("id", &entry.key))?;
+                write_xml_text_element(&mut writer, "source", &entry.source)?;
+                if !entry.target.is_empty() {
+                    write_xml_text_element(&mut writer, "target", &entry.target)?;
+                }
+                write_xml(&mut writer, WriterEvent::end_element())?;
+            }
+
+            write_xml(&mut writer, WriterEvent::end_element())?;
+            write_xml(&mut writer, WriterEvent::end_element())?;
+            write_xml(&mut writer, WriterEvent::end_element())?;
+        }
+
+        String::from_utf8(xliff).map_err(|error| ErrorKind::TranslationExport(error.to_string()).into())
+    }
+
+    /// This function escapes a string so it can be written between quotes in a PO file.
+    fn escape_po_string(string: &str) -> String {
+        let mut escaped = String::with_capacity(string.len());
+        for character in string.chars() {
+            match character {
+                '\\' => escaped.push_str("\\\\"),
+                '"' => escaped.push_str("\\\""),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                _ => escaped.push(character),
+            }
+        }
+        escaped
+    }
+
+    /// This function unescapes a quoted string from a PO file. If the string is not properly quoted, it returns `None`.
+    fn unescape_po_string(string: &str) -> Option<String> {
+        let string = string.strip_prefix('"')?.strip_suffix('"')?;
+        let mut unescaped = String::with_capacity(string.len());
+        let mut characters = string.chars();
+        while let Some(character) = characters.next() {
+            if character == '\\' {
+                match characters.next()? {
+                    'n' => unescaped.push('\n'),
+                    'r' => unescaped.push('\r'),
+                    't' => unescaped.push('\t'),
+                    character => unescaped.push(character),
+                }
+            } else { unescaped.push(character); }
+        }
+        Some(unescaped)
+    }
+}
+
+/// Implementation of `TranslationFormat`.
+impl TranslationFormat {
+
+    /// This function returns the format of a translation file, based on its extension.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().map(|x| x.to_string_lossy().to_lowercase()).as_deref() {
+            Some("po") | Some("pot") => Ok(Self::PO),
+            Some("xlf") | Some("xliff") => Ok(Self::XLIFF),
+            _ => Err(ErrorKind::TranslationUnsupportedFormat.into()),
+        }
+    }
+}
+
+/// This function writes a XML event, mapping the errors to our own.
+fn write_xml<'a, W: std::io::Write, E: Into<WriterEvent<'a>>>(writer: &mut xml::EventWriter<W>, event: E) -> Result<()> {
+    writer.write(event).map_err(|error| ErrorKind::TranslationExport(error.to_string()).into())
+}
+
+/// This function writes a XML element containing only text.
+fn write_xml_text_element<W: std::io::Write>(writer: &mut xml::EventWriter<W>, name: &str, text: &str) -> Result<()> {
+    write_xml(writer, WriterEvent::start_element(name))?;
+    if !text.is_empty() {
+        write_xml(writer, WriterEvent::characters(text))?;
+    }
+    write_xml(writer, WriterEvent::end_element())
+}
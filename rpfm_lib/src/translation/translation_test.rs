@@ -0,0 +1,163 @@
+//---------------------------------------------------------------------------//
+// Copyright (c) 2017-2020 Ismael Gutiérrez González. All rights reserved.
+//
+// This file is part of the Rusted PackFile Manager (RPFM) project,
+// which can be found here: https://github.com/Frodo45127/rpfm.
+//
+// This file is licensed under the MIT license, which can be found here:
+// https://github.com/Frodo45127/rpfm/blob/master/LICENSE.
+//---------------------------------------------------------------------------//
+
+/*!
+Module containing test for the `Translation` module, to make sure we export and import translations without losing data.
+!*/
+
+use crate::packedfile::table::loc::Loc;
+use crate::packfile::packedfile::PackedFile;
+use crate::schema::{Definition, Field, FieldType, VersionedFile};
+
+use super::*;
+
+/// This function builds a `Schema` with only the Loc definition.
+fn build_schema() -> Schema {
+    let mut schema = Schema::default();
+    let mut definition = Definition::new(1);
+    *definition.get_ref_mut_fields() = vec![
+        Field::new("key".to_owned(), FieldType::StringU16, true, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("text".to_owned(), FieldType::StringU16, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+        Field::new("tooltip".to_owned(), FieldType::Boolean, false, None, 0, false, None, None, None, String::new(), 0, 0, BTreeMap::new()),
+    ];
+    schema.add_versioned_file(&VersionedFile::Loc(vec![definition]));
+    schema
+}
+
+/// This function builds a `PackFile` with a decoded Loc PackedFile with the provided entries.
+fn build_pack_file(schema: &Schema, entries: &[(&str, &str)]) -> PackFile {
+    let definition = schema.get_ref_last_definition_loc().unwrap();
+    let mut loc = Loc::new(definition);
+    loc.set_table_data(&entries.iter().map(|(key, text)| vec![
+        DecodedData::StringU16((*key).to_owned()),
+        DecodedData::StringU16((*text).to_owned()),
+        DecodedData::Boolean(true),
+    ]).collect::<Vec<Vec<DecodedData>>>()).unwrap();
+
+    let mut pack_file = PackFile::new();
+    pack_file.add_packed_file(&PackedFile::new_from_decoded(&DecodedPackedFile::Loc(loc), &["text".to_owned(), "db".to_owned(), "my_mod.loc".to_owned()]), false).unwrap();
+    pack_file
+}
+
+/// This function builds a `Translation` with a few entries that need escaping.
+fn build_translation() -> Translation {
+    Translation {
+        language: "es".to_owned(),
+        entries: vec![
+            TranslationEntry { key: "units_name_swords".to_owned(), source: "Swords".to_owned(), target: "Espadas".to_owned() },
+            TranslationEntry { key: "units_description_swords".to_owned(), source: "They have \"swords\".\nAnd\tC:\\shields".to_owned(), target: String::new() },
+            TranslationEntry { key: "units_name_<spears>".to_owned(), source: "Spears & co".to_owned(), target: "Lanzas & cía".to_owned() },
+        ],
+    }
+}
+
+#[test]
+fn test_translation_po() {
+    let translation = build_translation();
+    let po = translation.save(TranslationFormat::PO).unwrap();
+    assert!(po.contains("msgctxt \"units_description_swords\"\nmsgid \"They have \\\"swords\\\".\\nAnd\\tC:\\\\shields\"\nmsgstr \"\"\n"));
+    assert_eq!(Translation::read(&po, TranslationFormat::PO).unwrap(), translation);
+
+    // Multi-line strings and comments, as generated by most PO editors.
+    let po = "# Translation of my mod.\nmsgid \"\"\nmsgstr \"\"\n\"Language: es\\n\"\n\n#. Comment\nmsgctxt \"units_name_swords\"\nmsgid \"\"\n\"Swo\"\n\"rds\"\nmsgstr \"Espadas\"\n";
+    let translation = Translation::read(po, TranslationFormat::PO).unwrap();
+    assert_eq!(translation.get_language(), "es");
+    assert_eq!(translation.get_ref_entries(), &[TranslationEntry { key: "units_name_swords".to_owned(), source: "Swords".to_owned(), target: "Espadas".to_owned() }]);
+
+    assert!(Translation::read("msgctxt \"units_name_swords\nmsgid \"Swords\"", TranslationFormat::PO).is_err());
+    assert!(Translation::read("msgfoo \"units_name_swords\"", TranslationFormat::PO).is_err());
+}
+
+#[test]
+fn test_translation_xliff() {
+    let translation = build_translation();
+    let xliff = translation.save(TranslationFormat::XLIFF).unwrap();
+    assert!(xliff.contains("target-language=\"es\""));
+    assert_eq!(Translation::read(&xliff, TranslationFormat::XLIFF).unwrap(), translation);
+
+    assert!(Translation::read("<xliff><file><body><trans-unit>", TranslationFormat::XLIFF).is_err());
+    assert_eq!(TranslationFormat::from_path(Path::new("my_mod.xlf")).unwrap(), TranslationFormat::XLIFF);
+    assert_eq!(TranslationFormat::from_path(Path::new("my_mod.PO")).unwrap(), TranslationFormat::PO);
+    assert!(TranslationFormat::from_path(Path::new("my_mod.tsv")).is_err());
+}
+
+#[test]
+fn test_translation_import() {
+    let schema = build_schema();
+    let mut pack_file = build_pack_file(&schema, &[("units_name_swords", "Swords"), ("units_name_spears", "Long Spears"), ("units_name_axes", "Axes"), ("units_name_swords", "Swords")]);
+
+    // The mod overrides the vanilla text of the spears. That's still english, so it must not be exported as translated.
+    let mut source_texts = BTreeMap::new();
+    source_texts.insert("units_name_spears".to_owned(), "Spears".to_owned());
+
+    let mut translation = Translation::new(&pack_file, &source_texts, "es", None);
+    assert_eq!(translation.get_ref_entries().iter().map(|x| (x.key.as_str(), x.source.as_str(), x.target.as_str())).collect::<Vec<(&str, &str, &str)>>(), vec![
+        ("units_name_swords", "Swords", ""),
+        ("units_name_spears", "Spears", ""),
+        ("units_name_axes", "Axes", ""),
+    ]);
+
+    // Targets are only taken from an existing translation.
+    let existing = Translation {
+        language: "es".to_owned(),
+        entries: vec![
+            TranslationEntry { key: "units_name_spears".to_owned(), source: "Spears".to_owned(), target: "Lanzas".to_owned() },
+            TranslationEntry { key: "units_name_axes".to_owned(), source: "Axes".to_owned(), target: String::new() },
+        ],
+    };
+
+    let translation_updated = Translation::new(&pack_file, &source_texts, "es", Some(&existing));
+    assert_eq!(translation_updated.get_ref_entries().iter().map(|x| (x.key.as_str(), x.target.as_str())).collect::<Vec<(&str, &str)>>(), vec![
+        ("units_name_swords", ""),
+        ("units_name_spears", "Lanzas"),
+        ("units_name_axes", ""),
+    ]);
+
+    // Importing an untouched export must not change the PackFile.
+    assert!(translation.import(&mut pack_file, &schema).unwrap().is_empty());
+    let packed_file = pack_file.get_ref_packed_file_by_path(&["text".to_owned(), "db".to_owned(), "my_mod.loc".to_owned()]).unwrap();
+    if let DecodedPackedFile::Loc(loc) = packed_file.get_ref_decoded() {
+        let texts = loc.get_ref_table_data().iter().map(|x| x[1].data_to_string()).collect::<Vec<String>>();
+        assert_eq!(texts, vec!["Swords", "Long Spears", "Axes", "Swords"]);
+    } else { panic!("Loc not decoded."); }
+
+    translation.get_ref_mut_entries()[0].target = "Espadas".to_owned();
+    translation.get_ref_mut_entries()[1].target = "Lanzas largas".to_owned();
+    translation.get_ref_mut_entries().push(TranslationEntry { key: "units_name_bows".to_owned(), source: "Bows".to_owned(), target: "Arcos".to_owned() });
+    assert_eq!(translation.import(&mut pack_file, &schema).unwrap(), vec!["units_name_bows".to_owned()]);
+
+    let packed_file = pack_file.get_ref_packed_file_by_path(&["text".to_owned(), "db".to_owned(), "my_mod.loc".to_owned()]).unwrap();
+    if let DecodedPackedFile::Loc(loc) = packed_file.get_ref_decoded() {
+        let texts = loc.get_ref_table_data().iter().map(|x| x[1].data_to_string()).collect::<Vec<String>>();
+        assert_eq!(texts, vec!["Espadas", "Lanzas largas", "Axes", "Espadas"]);
+        assert_eq!(loc.get_ref_table_data()[0][1], DecodedData::StringU16("Espadas".to_owned()));
+    } else { panic!("Loc not decoded."); }
+}
+
+#[test]
+fn test_translation_report() {
+    let translation = build_translation();
+    let current = Translation {
+        language: String::new(),
+        entries: vec![
+            TranslationEntry { key: "units_name_swords".to_owned(), source: "Great Swords".to_owned(), target: String::new() },
+            TranslationEntry { key: "units_description_swords".to_owned(), source: "They have swords.".to_owned(), target: String::new() },
+            TranslationEntry { key: "units_name_<spears>".to_owned(), source: "Spears & co".to_owned(), target: String::new() },
+            TranslationEntry { key: "units_name_axes".to_owned(), source: "Axes".to_owned(), target: String::new() },
+        ],
+    };
+
+    let report = translation.get_report(&current);
+    assert_eq!(report, TranslationReport {
+        language: "es".to_owned(),
+        missing: vec!["units_description_swords".to_owned(), "units_name_axes".to_owned()],
+        outdated: vec!["units_name_swords".to_owned()],
+    });
+}
@@ -37,8 +37,9 @@ use rpfm_lib::packedfile::*;
 use rpfm_lib::packedfile::animpack::AnimPack;
 use rpfm_lib::packedfile::table::db::DB;
 use rpfm_lib::packedfile::table::loc::{Loc, TSV_NAME_LOC};
-use rpfm_lib::packedfile::text::{Text, TextType};
+use rpfm_lib::packedfile::text::{Text, TextType, lua::CATypes};
 use rpfm_lib::packfile::{PackFile, PackFileInfo, packedfile::PackedFile, PathType, PFHFlags, RESERVED_NAME_NOTES};
+use rpfm_lib::references::ReferenceIndex;
 use rpfm_lib::schema::*;
 use rpfm_lib::SCHEMA;
 use rpfm_lib::SETTINGS;
@@ -237,9 +238,11 @@ pub fn background_loop() {
                 // the bastart triggers a memory leak in the next step.
                 dependencies.get_ref_mut_dependency_database().clear();
                 dependencies.get_ref_mut_fake_dependency_database().clear();
+                dependencies.get_ref_mut_packed_file_paths().clear();
 
                 *dependencies.get_ref_mut_dependency_database() = vec![];
                 *dependencies.get_ref_mut_fake_dependency_database() = vec![];
+                *dependencies.get_ref_mut_ca_types() = CATypes::read_game_selected().ok();
 
                 // Only preload dependencies if we have a schema.
                 if let Some(ref schema) = *SCHEMA.read().unwrap() {
@@ -251,6 +254,7 @@ pub fn background_loop() {
                     // Update the dependencies.
                     *dependencies.get_ref_mut_dependency_database() = real_dep_db;
                     *dependencies.get_ref_mut_fake_dependency_database() = DB::read_pak_file();
+                    *dependencies.get_ref_mut_packed_file_paths() = PackFile::load_all_dependency_packed_file_paths(&pack_file_decoded.get_packfiles_list());
                 }
 
                 // If there is a PackFile open, change his id to match the one of the new `Game Selected`.
@@ -505,7 +509,7 @@ pub fn background_loop() {
             // In case we want to Mass-Import TSV Files...
             Command::MassImportTSV(paths, name) => {
                 match pack_file_decoded.mass_import_tsv(&paths, name, true) {
-                    Ok(result) => CENTRAL_COMMAND.send_message_rust(Response::VecVecStringVecVecString(result)),
+                    Ok((paths_removed, paths_added, migrations)) => CENTRAL_COMMAND.send_message_rust(Response::VecVecStringVecVecStringVecVecStringTableMigration((paths_removed, paths_added, migrations))),
                     Err(error) => CENTRAL_COMMAND.send_message_rust(Response::Error(error)),
                 }
             }
@@ -662,13 +666,19 @@ pub fn background_loop() {
             Command::ImportTSV((internal_path, external_path)) => {
                 match pack_file_decoded.get_ref_mut_packed_file_by_path(&internal_path) {
                     Some(packed_file) => match packed_file.get_decoded() {
-                        DecodedPackedFile::DB(data) => match DB::import_tsv(&data.get_definition(), &external_path, &internal_path[1]) {
-                            Ok(data) => CENTRAL_COMMAND.send_message_rust(Response::TableType(TableType::DB(data))),
-                            Err(error) =>  CENTRAL_COMMAND.send_message_rust(Response::Error(error)),
+                        DecodedPackedFile::DB(data) => {
+                            let history = SCHEMA.read().unwrap().as_ref().and_then(|schema| schema.get_ref_versioned_file_db(&internal_path[1]).ok()).map(|x| x.get_version_list().to_vec()).unwrap_or_default();
+                            match DB::import_tsv(&data.get_definition(), &history, &external_path, &internal_path[1]) {
+                                Ok((data, migration)) => CENTRAL_COMMAND.send_message_rust(Response::TableTypeOptionTableMigration((TableType::DB(data), migration))),
+                                Err(error) =>  CENTRAL_COMMAND.send_message_rust(Response::Error(error)),
+                            }
                         },
-                        DecodedPackedFile::Loc(data) => match Loc::import_tsv(&data.get_definition(), &external_path, &TSV_NAME_LOC) {
-                            Ok(data) => CENTRAL_COMMAND.send_message_rust(Response::TableType(TableType::Loc(data))),
-                            Err(error) =>  CENTRAL_COMMAND.send_message_rust(Response::Error(error)),
+                        DecodedPackedFile::Loc(data) => {
+                            let history = SCHEMA.read().unwrap().as_ref().and_then(|schema| schema.get_ref_versioned_file_loc().ok()).map(|x| x.get_version_list().to_vec()).unwrap_or_default();
+                            match Loc::import_tsv(&data.get_definition(), &history, &external_path, &TSV_NAME_LOC) {
+                                Ok((data, migration)) => CENTRAL_COMMAND.send_message_rust(Response::TableTypeOptionTableMigration((TableType::Loc(data), migration))),
+                                Err(error) =>  CENTRAL_COMMAND.send_message_rust(Response::Error(error)),
+                            }
                         },
                         /*
                         DecodedPackedFile::DependencyPackFileList(data) => match data.export_tsv(&[external_path]) {
@@ -784,11 +794,12 @@ pub fn background_loop() {
                                 match packed_file.decode_return_ref_mut() {
                                     Ok(data) => {
                                         if let DecodedPackedFile::DB(ref mut data) = data {
-                                            match DB::import_tsv(&data.get_definition(), &external_path, &path[1]) {
-                                                Ok(new_data) => {
+                                            let history = SCHEMA.read().unwrap().as_ref().and_then(|schema| schema.get_ref_versioned_file_db(&path[1]).ok()).map(|x| x.get_version_list().to_vec()).unwrap_or_default();
+                                            match DB::import_tsv(&data.get_definition(), &history, &external_path, &path[1]) {
+                                                Ok((new_data, migration)) => {
                                                     *data = new_data;
                                                     match packed_file.encode_and_clean_cache() {
-                                                        Ok(_) => CENTRAL_COMMAND.send_message_save_packedfile(Response::Success),
+                                                        Ok(_) => CENTRAL_COMMAND.send_message_save_packedfile(Response::OptionTableMigration(migration)),
                                                         Err(error) => CENTRAL_COMMAND.send_message_save_packedfile(Response::Error(error)),
                                                     }
                                                 }
@@ -796,11 +807,12 @@ pub fn background_loop() {
                                             }
                                         }
                                         else if let DecodedPackedFile::Loc(ref mut data) = data {
-                                            match Loc::import_tsv(&data.get_definition(), &external_path, &TSV_NAME_LOC) {
-                                                Ok(new_data) => {
+                                            let history = SCHEMA.read().unwrap().as_ref().and_then(|schema| schema.get_ref_versioned_file_loc().ok()).map(|x| x.get_version_list().to_vec()).unwrap_or_default();
+                                            match Loc::import_tsv(&data.get_definition(), &history, &external_path, &TSV_NAME_LOC) {
+                                                Ok((new_data, migration)) => {
                                                     *data = new_data;
                                                     match packed_file.encode_and_clean_cache() {
-                                                        Ok(_) => CENTRAL_COMMAND.send_message_save_packedfile(Response::Success),
+                                                        Ok(_) => CENTRAL_COMMAND.send_message_save_packedfile(Response::OptionTableMigration(migration)),
                                                         Err(error) => CENTRAL_COMMAND.send_message_save_packedfile(Response::Error(error)),
                                                     }
                                                 }
@@ -952,6 +964,14 @@ pub fn background_loop() {
                 pack_file_decoded.set_settings(&settings);
             }
 
+            // In case we want to find where a key is used...
+            Command::FindKeyUsages((table_name, key)) => {
+                if let Some(ref schema) = *SCHEMA.read().unwrap() {
+                    let usages = ReferenceIndex::new(schema).find_usages(&pack_file_decoded, &dependencies, &table_name, &key);
+                    CENTRAL_COMMAND.send_message_rust(Response::VecKeyUsage(usages));
+                } else { CENTRAL_COMMAND.send_message_rust(Response::Error(ErrorKind::SchemaNotFound.into())); }
+            }
+
             // These two belong to the network thread, not to this one!!!!
             Command::CheckUpdates | Command::CheckSchemaUpdates | Command::CheckTemplateUpdates => panic!("{}{:?}", THREADS_COMMUNICATION_ERROR, response),
         }
@@ -28,11 +28,12 @@ use rpfm_lib::global_search::MatchHolder;
 use rpfm_lib::packedfile::ca_vp8::{CaVp8, SupportedFormats};
 use rpfm_lib::packedfile::DecodedPackedFile;
 use rpfm_lib::packedfile::image::Image;
-use rpfm_lib::packedfile::table::{anim_fragment::AnimFragment, animtable::AnimTable, db::DB, loc::Loc, matched_combat::MatchedCombat};
+use rpfm_lib::packedfile::table::{anim_fragment::AnimFragment, animtable::AnimTable, db::DB, loc::Loc, matched_combat::MatchedCombat, migration::TableMigration};
 use rpfm_lib::packedfile::text::Text;
 use rpfm_lib::packedfile::rigidmodel::RigidModel;
 use rpfm_lib::packfile::{PackFileInfo, PackFileSettings, PathType, PFHFileType};
 use rpfm_lib::packfile::packedfile::{PackedFile, PackedFileInfo};
+use rpfm_lib::references::KeyUsage;
 use rpfm_lib::schema::{APIResponseSchema, Definition, Schema};
 use rpfm_lib::settings::*;
 use rpfm_lib::template::Template;
@@ -305,6 +306,9 @@ pub enum Command {
 
     /// This command is used to set the settings of the currently open PackFile.
     SetPackFileSettings(PackFileSettings),
+
+    /// This command is used to find all the usages of a key of a table in the open PackFile and its dependencies.
+    FindKeyUsages((String, String)),
 }
 
 /// This enum defines the responses (messages) you can send to the to the UI thread as result of a command.
@@ -400,8 +404,8 @@ pub enum Response {
     /// Response to return `Unknown`.
     Unknown,
 
-    /// Response to return `(Vec<Vec<String>>, Vec<Vec<String>>)`.
-    VecVecStringVecVecString((Vec<Vec<String>>, Vec<Vec<String>>)),
+    /// Response to return `(Vec<Vec<String>>, Vec<Vec<String>>, Vec<(Vec<String>, TableMigration)>)`.
+    VecVecStringVecVecStringVecVecStringTableMigration((Vec<Vec<String>>, Vec<Vec<String>>, Vec<(Vec<String>, TableMigration)>)),
 
     /// Response to return `Vec<String>`.
     VecString(Vec<String>),
@@ -415,11 +419,17 @@ pub enum Response {
     /// Response to return `Option<PackedFile>`.
     OptionPackedFile(Option<PackedFile>),
 
-    /// Response to return `TableType`.
-    TableType(TableType),
+    /// Response to return `(TableType, Option<TableMigration>)`.
+    TableTypeOptionTableMigration((TableType, Option<TableMigration>)),
+
+    /// Response to return `Option<TableMigration>`.
+    OptionTableMigration(Option<TableMigration>),
 
     /// Response to return `PackFileSettings`.
     PackFileSettings(PackFileSettings),
+
+    /// Response to return `Vec<KeyUsage>`.
+    VecKeyUsage(Vec<KeyUsage>),
 }
 
 #[derive(Debug)]
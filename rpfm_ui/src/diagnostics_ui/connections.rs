@@ -42,5 +42,10 @@ pub unsafe fn set_connections(ui: &DiagnosticsUI, slots: &DiagnosticsUISlots) {
     ui.checkbox_no_reference_table_nor_column_found_no_pak.toggled().connect(&slots.toggle_filters);
     ui.checkbox_invalid_escape.toggled().connect(&slots.toggle_filters);
     ui.checkbox_duplicated_row.toggled().connect(&slots.toggle_filters);
+    ui.checkbox_field_max_length_exceeded.toggled().connect(&slots.toggle_filters);
+    ui.checkbox_invalid_enum_value.toggled().connect(&slots.toggle_filters);
+    ui.checkbox_file_not_found.toggled().connect(&slots.toggle_filters);
+    ui.checkbox_invalid_bitwise_value.toggled().connect(&slots.toggle_filters);
+    ui.checkbox_lua.toggled().connect(&slots.toggle_filters);
     ui.checkbox_invalid_dependency_packfile.toggled().connect(&slots.toggle_filters);
 }
@@ -49,7 +49,7 @@ use std::rc::Rc;
 
 use rpfm_error::ErrorKind;
 
-use rpfm_lib::diagnostics::{*, table::*, dependency_manager::*};
+use rpfm_lib::diagnostics::{*, table::*, dependency_manager::*, lua::*};
 use rpfm_lib::packfile::PathType;
 use rpfm_lib::SETTINGS;
 
@@ -108,6 +108,11 @@ pub struct DiagnosticsUI {
     checkbox_no_reference_table_nor_column_found_no_pak: QBox<QCheckBox>,
     checkbox_invalid_escape: QBox<QCheckBox>,
     checkbox_duplicated_row: QBox<QCheckBox>,
+    checkbox_field_max_length_exceeded: QBox<QCheckBox>,
+    checkbox_invalid_enum_value: QBox<QCheckBox>,
+    checkbox_file_not_found: QBox<QCheckBox>,
+    checkbox_invalid_bitwise_value: QBox<QCheckBox>,
+    checkbox_lua: QBox<QCheckBox>,
     checkbox_invalid_dependency_packfile: QBox<QCheckBox>,
 }
 
@@ -233,6 +238,11 @@ impl DiagnosticsUI {
         let label_no_reference_table_nor_column_found_no_pak = QLabel::from_q_string_q_widget(&qtr("label_no_reference_table_nor_column_found_no_pak"), &sidebar_scroll_area);
         let label_invalid_escape = QLabel::from_q_string_q_widget(&qtr("label_invalid_escape"), &sidebar_scroll_area);
         let label_duplicated_row = QLabel::from_q_string_q_widget(&qtr("label_duplicated_row"), &sidebar_scroll_area);
+        let label_field_max_length_exceeded = QLabel::from_q_string_q_widget(&qtr("label_field_max_length_exceeded"), &sidebar_scroll_area);
+        let label_invalid_enum_value = QLabel::from_q_string_q_widget(&qtr("label_invalid_enum_value"), &sidebar_scroll_area);
+        let label_file_not_found = QLabel::from_q_string_q_widget(&qtr("label_file_not_found"), &sidebar_scroll_area);
+        let label_invalid_bitwise_value = QLabel::from_q_string_q_widget(&qtr("label_invalid_bitwise_value"), &sidebar_scroll_area);
+        let label_lua = QLabel::from_q_string_q_widget(&qtr("label_lua"), &sidebar_scroll_area);
         let label_invalid_dependency_packfile = QLabel::from_q_string_q_widget(&qtr("label_invalid_dependency_packfile"), &sidebar_scroll_area);
 
         let checkbox_all = QCheckBox::from_q_widget(&sidebar_scroll_area);
@@ -247,6 +257,11 @@ impl DiagnosticsUI {
         let checkbox_no_reference_table_nor_column_found_no_pak = QCheckBox::from_q_widget(&sidebar_scroll_area);
         let checkbox_invalid_escape = QCheckBox::from_q_widget(&sidebar_scroll_area);
         let checkbox_duplicated_row = QCheckBox::from_q_widget(&sidebar_scroll_area);
+        let checkbox_field_max_length_exceeded = QCheckBox::from_q_widget(&sidebar_scroll_area);
+        let checkbox_invalid_enum_value = QCheckBox::from_q_widget(&sidebar_scroll_area);
+        let checkbox_file_not_found = QCheckBox::from_q_widget(&sidebar_scroll_area);
+        let checkbox_invalid_bitwise_value = QCheckBox::from_q_widget(&sidebar_scroll_area);
+        let checkbox_lua = QCheckBox::from_q_widget(&sidebar_scroll_area);
         let checkbox_invalid_dependency_packfile = QCheckBox::from_q_widget(&sidebar_scroll_area);
 
         checkbox_all.set_checked(true);
@@ -261,6 +276,11 @@ impl DiagnosticsUI {
         checkbox_no_reference_table_nor_column_found_no_pak.set_checked(true);
         checkbox_invalid_escape.set_checked(true);
         checkbox_duplicated_row.set_checked(true);
+        checkbox_field_max_length_exceeded.set_checked(true);
+        checkbox_invalid_enum_value.set_checked(true);
+        checkbox_file_not_found.set_checked(true);
+        checkbox_invalid_bitwise_value.set_checked(true);
+        checkbox_lua.set_checked(true);
         checkbox_invalid_dependency_packfile.set_checked(true);
 
         sidebar_grid.set_alignment_q_widget_q_flags_alignment_flag(&checkbox_all, QFlags::from(AlignmentFlag::AlignHCenter));
@@ -275,6 +295,11 @@ impl DiagnosticsUI {
         sidebar_grid.set_alignment_q_widget_q_flags_alignment_flag(&checkbox_no_reference_table_nor_column_found_no_pak, QFlags::from(AlignmentFlag::AlignHCenter));
         sidebar_grid.set_alignment_q_widget_q_flags_alignment_flag(&checkbox_invalid_escape, QFlags::from(AlignmentFlag::AlignHCenter));
         sidebar_grid.set_alignment_q_widget_q_flags_alignment_flag(&checkbox_duplicated_row, QFlags::from(AlignmentFlag::AlignHCenter));
+        sidebar_grid.set_alignment_q_widget_q_flags_alignment_flag(&checkbox_field_max_length_exceeded, QFlags::from(AlignmentFlag::AlignHCenter));
+        sidebar_grid.set_alignment_q_widget_q_flags_alignment_flag(&checkbox_invalid_enum_value, QFlags::from(AlignmentFlag::AlignHCenter));
+        sidebar_grid.set_alignment_q_widget_q_flags_alignment_flag(&checkbox_file_not_found, QFlags::from(AlignmentFlag::AlignHCenter));
+        sidebar_grid.set_alignment_q_widget_q_flags_alignment_flag(&checkbox_invalid_bitwise_value, QFlags::from(AlignmentFlag::AlignHCenter));
+        sidebar_grid.set_alignment_q_widget_q_flags_alignment_flag(&checkbox_lua, QFlags::from(AlignmentFlag::AlignHCenter));
         sidebar_grid.set_alignment_q_widget_q_flags_alignment_flag(&checkbox_invalid_dependency_packfile, QFlags::from(AlignmentFlag::AlignHCenter));
 
         sidebar_grid.add_widget_5a(&label_all, 1, 0, 1, 1);
@@ -289,7 +314,12 @@ impl DiagnosticsUI {
         sidebar_grid.add_widget_5a(&label_no_reference_table_nor_column_found_no_pak, 10, 0, 1, 1);
         sidebar_grid.add_widget_5a(&label_invalid_escape, 11, 0, 1, 1);
         sidebar_grid.add_widget_5a(&label_duplicated_row, 12, 0, 1, 1);
-        sidebar_grid.add_widget_5a(&label_invalid_dependency_packfile, 13, 0, 1, 1);
+        sidebar_grid.add_widget_5a(&label_field_max_length_exceeded, 13, 0, 1, 1);
+        sidebar_grid.add_widget_5a(&label_invalid_enum_value, 14, 0, 1, 1);
+        sidebar_grid.add_widget_5a(&label_file_not_found, 15, 0, 1, 1);
+        sidebar_grid.add_widget_5a(&label_invalid_bitwise_value, 16, 0, 1, 1);
+        sidebar_grid.add_widget_5a(&label_lua, 17, 0, 1, 1);
+        sidebar_grid.add_widget_5a(&label_invalid_dependency_packfile, 18, 0, 1, 1);
 
         sidebar_grid.add_widget_5a(&checkbox_all, 1, 1, 1, 1);
         sidebar_grid.add_widget_5a(&checkbox_outdated_table, 2, 1, 1, 1);
@@ -303,7 +333,12 @@ impl DiagnosticsUI {
         sidebar_grid.add_widget_5a(&checkbox_no_reference_table_nor_column_found_no_pak, 10, 1, 1, 1);
         sidebar_grid.add_widget_5a(&checkbox_invalid_escape, 11, 1, 1, 1);
         sidebar_grid.add_widget_5a(&checkbox_duplicated_row, 12, 1, 1, 1);
-        sidebar_grid.add_widget_5a(&checkbox_invalid_dependency_packfile, 13, 1, 1, 1);
+        sidebar_grid.add_widget_5a(&checkbox_field_max_length_exceeded, 13, 1, 1, 1);
+        sidebar_grid.add_widget_5a(&checkbox_invalid_enum_value, 14, 1, 1, 1);
+        sidebar_grid.add_widget_5a(&checkbox_file_not_found, 15, 1, 1, 1);
+        sidebar_grid.add_widget_5a(&checkbox_invalid_bitwise_value, 16, 1, 1, 1);
+        sidebar_grid.add_widget_5a(&checkbox_lua, 17, 1, 1, 1);
+        sidebar_grid.add_widget_5a(&checkbox_invalid_dependency_packfile, 18, 1, 1, 1);
 
         // Add all the stuff to the main grid and hide the search widget.
         diagnostics_dock_layout.add_widget_5a(&sidebar_scroll_area, 0, 1, 2, 1);
@@ -342,6 +377,11 @@ impl DiagnosticsUI {
             checkbox_no_reference_table_nor_column_found_no_pak,
             checkbox_invalid_escape,
             checkbox_duplicated_row,
+            checkbox_field_max_length_exceeded,
+            checkbox_invalid_enum_value,
+            checkbox_file_not_found,
+            checkbox_invalid_bitwise_value,
+            checkbox_lua,
             checkbox_invalid_dependency_packfile
         }
     }
@@ -528,6 +568,55 @@ impl DiagnosticsUI {
                             diagnostics_ui.diagnostics_table_model.append_row_q_list_of_q_standard_item(qlist_boi.as_ref());
                         }
                     }
+
+                    DiagnosticType::Lua(ref diagnostic) => {
+                        for result in diagnostic.get_ref_result() {
+                            let qlist_boi = QListOfQStandardItem::new();
+
+                            // Create an empty row.
+                            let level = QStandardItem::new();
+                            let diag_type = QStandardItem::new();
+                            let column = QStandardItem::new();
+                            let row = QStandardItem::new();
+                            let path = QStandardItem::new();
+                            let message = QStandardItem::new();
+                            let report_type = QStandardItem::new();
+                            let (result_type, color) = match result.level {
+                                DiagnosticLevel::Info => ("Info".to_owned(), get_color_info()),
+                                DiagnosticLevel::Warning => ("Warning".to_owned(), get_color_warning()),
+                                DiagnosticLevel::Error => ("Error".to_owned(), get_color_error()),
+                            };
+
+                            level.set_background(&QBrush::from_q_color(&QColor::from_q_string(&QString::from_std_str(color))));
+                            level.set_text(&QString::from_std_str(result_type));
+                            diag_type.set_text(&QString::from_std_str(&format!("{}", diagnostic_type)));
+                            column.set_data_2a(&QVariant::from_uint(result.column), 2);
+                            row.set_data_2a(&QVariant::from_uint(result.line), 2);
+                            path.set_text(&QString::from_std_str(&diagnostic.get_path().join("/")));
+                            message.set_text(&QString::from_std_str(&result.message));
+                            report_type.set_text(&QString::from_std_str(&format!("{}", result.report_type)));
+
+                            level.set_editable(false);
+                            diag_type.set_editable(false);
+                            column.set_editable(false);
+                            row.set_editable(false);
+                            path.set_editable(false);
+                            message.set_editable(false);
+                            report_type.set_editable(false);
+
+                            // Add an empty row to the list.
+                            qlist_boi.append_q_standard_item(&level.into_ptr().as_mut_raw_ptr());
+                            qlist_boi.append_q_standard_item(&diag_type.into_ptr().as_mut_raw_ptr());
+                            qlist_boi.append_q_standard_item(&column.into_ptr().as_mut_raw_ptr());
+                            qlist_boi.append_q_standard_item(&row.into_ptr().as_mut_raw_ptr());
+                            qlist_boi.append_q_standard_item(&path.into_ptr().as_mut_raw_ptr());
+                            qlist_boi.append_q_standard_item(&message.into_ptr().as_mut_raw_ptr());
+                            qlist_boi.append_q_standard_item(&report_type.into_ptr().as_mut_raw_ptr());
+
+                            // Append the new row.
+                            diagnostics_ui.diagnostics_table_model.append_row_q_list_of_q_standard_item(qlist_boi.as_ref());
+                        }
+                    }
                 }
 
                 // After that, check if the table is open, and paint the results into it.
@@ -819,6 +908,24 @@ impl DiagnosticsUI {
         if diagnostics_ui.checkbox_duplicated_row.is_checked() {
             diagnostic_type_pattern.push_str(&format!("{}|", TableDiagnosticReportType::DuplicatedRow));
         }
+        if diagnostics_ui.checkbox_field_max_length_exceeded.is_checked() {
+            diagnostic_type_pattern.push_str(&format!("{}|", TableDiagnosticReportType::FieldMaxLengthExceeded));
+        }
+        if diagnostics_ui.checkbox_invalid_enum_value.is_checked() {
+            diagnostic_type_pattern.push_str(&format!("{}|", TableDiagnosticReportType::InvalidEnumValue));
+        }
+        if diagnostics_ui.checkbox_file_not_found.is_checked() {
+            diagnostic_type_pattern.push_str(&format!("{}|", TableDiagnosticReportType::FileNotFound));
+        }
+        if diagnostics_ui.checkbox_invalid_bitwise_value.is_checked() {
+            diagnostic_type_pattern.push_str(&format!("{}|", TableDiagnosticReportType::InvalidBitwiseValue));
+        }
+        if diagnostics_ui.checkbox_lua.is_checked() {
+            diagnostic_type_pattern.push_str(&format!("{}|", LuaDiagnosticReportType::SyntaxError));
+            diagnostic_type_pattern.push_str(&format!("{}|", LuaDiagnosticReportType::UndefinedFunction));
+            diagnostic_type_pattern.push_str(&format!("{}|", LuaDiagnosticReportType::InvalidArgumentCount));
+            diagnostic_type_pattern.push_str(&format!("{}|", LuaDiagnosticReportType::InvalidKey));
+        }
 
         if diagnostics_ui.checkbox_invalid_dependency_packfile.is_checked() {
             diagnostic_type_pattern.push_str(&format!("{}|", DependencyManagerDiagnosticReportType::InvalidDependencyPackFileName));
@@ -851,6 +958,10 @@ impl DiagnosticsUI {
                     .filter(|y| if let DiagnosticLevel::Info = y.level { true } else { false })
                     .count(),
                  DiagnosticType::DependencyManager(ref diag) => diag.get_ref_result()
+                    .iter()
+                    .filter(|y| if let DiagnosticLevel::Info = y.level { true } else { false })
+                    .count(),
+                DiagnosticType::Lua(ref diag) => diag.get_ref_result()
                     .iter()
                     .filter(|y| if let DiagnosticLevel::Info = y.level { true } else { false })
                     .count()
@@ -868,6 +979,10 @@ impl DiagnosticsUI {
                     .filter(|y| if let DiagnosticLevel::Warning = y.level { true } else { false })
                     .count(),
                 DiagnosticType::DependencyManager(ref diag) => diag.get_ref_result()
+                    .iter()
+                    .filter(|y| if let DiagnosticLevel::Warning = y.level { true } else { false })
+                    .count(),
+                DiagnosticType::Lua(ref diag) => diag.get_ref_result()
                     .iter()
                     .filter(|y| if let DiagnosticLevel::Warning = y.level { true } else { false })
                     .count()
@@ -886,6 +1001,10 @@ impl DiagnosticsUI {
                     .filter(|y| if let DiagnosticLevel::Error = y.level { true } else { false })
                     .count(),
                 DiagnosticType::DependencyManager(ref diag) => diag.get_ref_result()
+                    .iter()
+                    .filter(|y| if let DiagnosticLevel::Error = y.level { true } else { false })
+                    .count(),
+                DiagnosticType::Lua(ref diag) => diag.get_ref_result()
                     .iter()
                     .filter(|y| if let DiagnosticLevel::Error = y.level { true } else { false })
                     .count()
@@ -85,6 +85,11 @@ impl DiagnosticsUISlots {
                 diagnostics_ui.checkbox_no_reference_table_nor_column_found_no_pak.toggle();
                 diagnostics_ui.checkbox_invalid_escape.toggle();
                 diagnostics_ui.checkbox_duplicated_row.toggle();
+                diagnostics_ui.checkbox_field_max_length_exceeded.toggle();
+                diagnostics_ui.checkbox_invalid_enum_value.toggle();
+                diagnostics_ui.checkbox_file_not_found.toggle();
+                diagnostics_ui.checkbox_invalid_bitwise_value.toggle();
+                diagnostics_ui.checkbox_lua.toggle();
                 diagnostics_ui.checkbox_invalid_dependency_packfile.toggle();
             }
         ));
@@ -60,6 +60,7 @@ pub unsafe fn set_connections(ui: &PackedFileDecoderView, slots: &PackedFileDeco
     ui.get_mut_ptr_table_view_old_versions_context_menu_delete().triggered().connect(&slots.table_view_old_versions_context_menu_delete);
 
     ui.get_mut_ptr_import_from_assembly_kit_button().released().connect(&slots.import_from_assembly_kit);
+    ui.get_mut_ptr_guess_definition_button().released().connect(&slots.guess_definition);
     ui.get_mut_ptr_test_definition_button().released().connect(&slots.test_definition);
     ui.get_mut_ptr_clear_definition_button().released().connect(&slots.remove_all_fields);
     ui.get_mut_ptr_save_button().released().connect(&slots.save_definition);
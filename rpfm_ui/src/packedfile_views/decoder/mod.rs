@@ -154,6 +154,7 @@ pub struct PackedFileDecoderView {
     table_view_old_versions_context_menu_delete: QPtr<QAction>,
 
     import_from_assembly_kit_button: QBox<QPushButton>,
+    guess_definition_button: QBox<QPushButton>,
     test_definition_button: QBox<QPushButton>,
     clear_definition_button: QBox<QPushButton>,
     save_button: QBox<QPushButton>,
@@ -396,15 +397,20 @@ impl PackedFileDecoderView {
 
         // Create the bottom Buttons.
         let import_from_assembly_kit_button = QPushButton::from_q_string_q_widget(&QString::from_std_str("Import from Assembly Kit"), &button_box);
+        let guess_definition_button = QPushButton::from_q_string_q_widget(&QString::from_std_str("Guess Definition"), &button_box);
         let test_definition_button = QPushButton::from_q_string_q_widget(&QString::from_std_str("Test Definition"), &button_box);
         let clear_definition_button = QPushButton::from_q_string_q_widget(&QString::from_std_str("Remove all fields"), &button_box);
         let save_button = QPushButton::from_q_string_q_widget(&QString::from_std_str("Finish it!"), &button_box);
 
         // Add them to the Dialog.
         button_box_layout.add_widget_5a(&import_from_assembly_kit_button, 0, 0, 1, 1);
-        button_box_layout.add_widget_5a(&test_definition_button, 0, 1, 1, 1);
-        button_box_layout.add_widget_5a(&clear_definition_button, 0, 2, 1, 1);
-        button_box_layout.add_widget_5a(&save_button, 0, 3, 1, 1);
+        button_box_layout.add_widget_5a(&guess_definition_button, 0, 1, 1, 1);
+        button_box_layout.add_widget_5a(&test_definition_button, 0, 2, 1, 1);
+        button_box_layout.add_widget_5a(&clear_definition_button, 0, 3, 1, 1);
+        button_box_layout.add_widget_5a(&save_button, 0, 4, 1, 1);
+
+        // The guesser only works with DB Tables.
+        guess_definition_button.set_enabled(packed_file_type == PackedFileType::DB);
 
         layout.add_widget_5a(&button_box, 4, 1, 1, 2);
 
@@ -465,6 +471,7 @@ impl PackedFileDecoderView {
             table_view_old_versions_context_menu_delete,
 
             import_from_assembly_kit_button,
+            guess_definition_button,
             test_definition_button,
             clear_definition_button,
             save_button,
@@ -753,6 +760,10 @@ impl PackedFileDecoderView {
         &self.import_from_assembly_kit_button
     }
 
+    fn get_mut_ptr_guess_definition_button(&self) -> &QBox<QPushButton> {
+        &self.guess_definition_button
+    }
+
     fn get_mut_ptr_test_definition_button(&self) -> &QBox<QPushButton> {
         &self.test_definition_button
     }
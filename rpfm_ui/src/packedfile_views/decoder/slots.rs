@@ -94,6 +94,7 @@ pub struct PackedFileDecoderViewSlots {
     pub table_view_old_versions_context_menu_delete: QBox<SlotOfBool>,
 
     pub import_from_assembly_kit: QBox<SlotNoArgs>,
+    pub guess_definition: QBox<SlotNoArgs>,
     pub test_definition: QBox<SlotNoArgs>,
     pub remove_all_fields: QBox<SlotNoArgs>,
     pub save_definition: QBox<SlotNoArgs>,
@@ -532,6 +533,36 @@ impl PackedFileDecoderViewSlots {
             }
         ));
 
+        // Slot for the "Guess Definition" button.
+        let guess_definition = SlotNoArgs::new(&view.table_view, clone!(
+            mut mutable_data,
+            mut view => move || {
+                let previous_definitions = match *SCHEMA.read().unwrap() {
+                    Some(ref schema) => schema.get_ref_versioned_file_db(&view.packed_file_path[1]).map(|x| x.get_version_list().to_vec()).unwrap_or_default(),
+                    None => vec![],
+                };
+
+                match DB::guess_definitions(&view.packed_file_data, &previous_definitions) {
+                    Ok(candidates) => {
+                        if let Some(candidate) = candidates.get(0) {
+
+                            // If it worked, update the decoder view with the best candidate.
+                            view.table_model.clear();
+                            *mutable_data.index.lock().unwrap() = get_header_size(view.packed_file_type, &view.packed_file_data).unwrap();
+                            let _ = view.update_view(candidate.get_ref_definition().get_ref_fields(), true, &mut mutable_data.index.lock().unwrap());
+                        }
+
+                        else {
+                            show_dialog(&view.table_view, "No valid definitions found.", false)
+                        }
+                    }
+
+                    // If it failed, tell us why.
+                    Err(error) => show_dialog(&view.table_view, error, false),
+                }
+            }
+        ));
+
         // Slot for the "Test Definition" button.
         let test_definition = SlotNoArgs::new(&view.table_view, clone!(
             app_ui,
@@ -686,6 +717,7 @@ impl PackedFileDecoderViewSlots {
             table_view_old_versions_context_menu_delete,
 
             import_from_assembly_kit,
+            guess_definition,
             test_definition,
             remove_all_fields,
             save_definition,
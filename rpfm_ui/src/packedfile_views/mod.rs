@@ -39,7 +39,7 @@ use crate::pack_tree::*;
 use crate::packfile_contents_ui::PackFileContentsUI;
 use crate::views::table::utils::get_table_from_view;
 use crate::utils::create_grid_layout;
-use crate::utils::show_dialog;
+use crate::utils::{show_dialog, show_table_migrations_dialog};
 use crate::UI_STATE;
 use crate::views::table::TableType;
 
@@ -323,6 +323,9 @@ impl PackedFileView {
                 let response = CENTRAL_COMMAND.recv_message_save_packedfile_try();
                 match response {
                     Response::Success => {},
+                    Response::OptionTableMigration(migration) => if let Some(migration) = migration {
+                        show_table_migrations_dialog(&pack_file_contents_ui.packfile_contents_tree_view, &[(self.get_path(), migration)]);
+                    },
                     Response::Error(error) => show_dialog(&pack_file_contents_ui.packfile_contents_tree_view, error, false),
                     _ => panic!("{}{:?}", THREADS_COMMUNICATION_ERROR, response),
                 }
@@ -47,7 +47,7 @@ use crate::packfile_contents_ui::PackFileContentsUI;
 use crate::packedfile_views::packfile::PackFileExtraView;
 use crate::packedfile_views::PackedFileView;
 use crate::QString;
-use crate::utils::{show_dialog, check_regex};
+use crate::utils::{show_dialog, show_table_migrations_dialog, check_regex};
 use crate::UI_STATE;
 use crate::ui_state::op_mode::OperationalMode;
 
@@ -1187,11 +1187,11 @@ impl PackFileContentsSlots {
                         match response {
 
                             // If it's success....
-                            Response::VecVecStringVecVecString(paths) => {
+                            Response::VecVecStringVecVecStringVecVecStringTableMigration((paths_removed, paths_added, migrations)) => {
 
                                 // Get the list of paths to add, removing those we "replaced".
-                                let mut paths_to_add = paths.1.to_vec();
-                                paths_to_add.retain(|x| !paths.0.contains(&x));
+                                let mut paths_to_add = paths_added.to_vec();
+                                paths_to_add.retain(|x| !paths_removed.contains(&x));
                                 let paths_to_add2 = paths_to_add.iter().map(|x| TreePathType::File(x.to_vec())).collect::<Vec<TreePathType>>();
 
                                 // Update the TreeView.
@@ -1202,6 +1202,9 @@ impl PackFileContentsSlots {
                                 // Update the global search stuff, if needed.
                                 GlobalSearchUI::search_on_path(&pack_file_contents_ui, &global_search_ui, paths_to_add.iter().map(|x| PathType::File(x.to_vec())).collect::<Vec<PathType>>());
                                 DiagnosticsUI::check_on_path(&app_ui, &pack_file_contents_ui, &diagnostics_ui, paths_to_add.iter().map(|x| PathType::File(x.to_vec())).collect::<Vec<PathType>>());
+
+                                // Warn about the tables imported from older versions that lost or changed data.
+                                show_table_migrations_dialog(&app_ui.main_window, &migrations);
                             }
 
                             Response::Error(error) => show_dialog(&app_ui.main_window, error, false),
@@ -25,7 +25,7 @@ use qt_core::SlotNoArgs;
 use std::collections::BTreeMap;
 use std::rc::Rc;
 
-use rpfm_lib::settings::{Settings, MYMOD_BASE_PATH, ZIP_PATH};
+use rpfm_lib::settings::{Settings, MYMOD_BASE_PATH};
 
 use crate::CENTRAL_COMMAND;
 use crate::communications::{Command, Response, THREADS_COMMUNICATION_ERROR};
@@ -45,7 +45,6 @@ use crate::utils::show_dialog;
 pub struct SettingsUISlots {
     pub restore_default: QBox<SlotNoArgs>,
     pub select_mymod_path: QBox<SlotNoArgs>,
-    pub select_zip_path: QBox<SlotNoArgs>,
     pub select_game_paths: BTreeMap<String, QBox<SlotNoArgs>>,
     pub shortcuts: QBox<SlotNoArgs>,
     pub text_editor: QBox<SlotNoArgs>,
@@ -77,12 +76,6 @@ impl SettingsUISlots {
             }
         ));
 
-        // What happens when we hit the "..." button for 7Zip.
-        let select_zip_path = SlotNoArgs::new(&ui.dialog, clone!(
-            ui => move || {
-            ui.update_entry_path(ZIP_PATH);
-        }));
-
         // What happens when we hit any of the "..." buttons for the games.
         let mut select_game_paths = BTreeMap::new();
         for key in ui.paths_games_line_edits.keys() {
@@ -129,7 +122,6 @@ impl SettingsUISlots {
 		Self {
             restore_default,
             select_mymod_path,
-            select_zip_path,
             select_game_paths,
             shortcuts,
             text_editor,
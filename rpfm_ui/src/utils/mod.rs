@@ -38,9 +38,11 @@ use std::convert::AsRef;
 use std::fmt::Display;
 use std::sync::atomic::{AtomicPtr, Ordering};
 
+use rpfm_lib::packedfile::table::migration::TableMigration;
+
 use crate::ASSETS_PATH;
 use crate::ffi::{new_text_editor_safe, set_text_safe};
-use crate::locale::qtr;
+use crate::locale::{qtr, tr};
 use crate::ORANGE;
 use crate::SLIGHTLY_DARKER_GREY;
 use crate::MEDIUM_DARKER_GREY;
@@ -110,6 +112,18 @@ pub unsafe fn show_dialog<T: Display>(parent: impl cpp_core::CastInto<Ptr<QWidge
     ).exec();
 }
 
+/// This function creates a modal dialog with the reports of the tables migrated from older versions, if any of them needs to be reviewed.
+///
+/// It requires:
+/// - parent: a pointer to the widget that'll be the parent of the dialog.
+/// - migrations: the migrated tables, with the path of their PackedFile.
+pub unsafe fn show_table_migrations_dialog(parent: impl cpp_core::CastInto<Ptr<QWidget>>, migrations: &[(Vec<String>, TableMigration)]) {
+    if migrations.iter().any(|(_, migration)| migration.needs_review()) {
+        let reports = migrations.iter().map(|(path, migration)| format!("{}: {}", path.join("/"), migration)).collect::<String>();
+        show_dialog(parent, format!("<p>{}</p><pre>{}</pre>", tr("table_migrations_need_review"), reports), true);
+    }
+}
+
 /// This function creates a non-modal dialog, for debugging purpouses.
 ///
 /// It requires:
@@ -181,6 +181,7 @@ pub struct TableView {
     context_menu_resize_columns: QPtr<QAction>,
     context_menu_sidebar: QPtr<QAction>,
     context_menu_search: QPtr<QAction>,
+    context_menu_find_usages: QPtr<QAction>,
     smart_delete: QBox<QAction>,
 
     sidebar_scroll_area: QBox<QScrollArea>,
@@ -343,6 +344,7 @@ impl TableView {
 
         let context_menu_search = context_menu.add_action_q_string(&qtr("context_menu_search"));
         let context_menu_sidebar = context_menu.add_action_q_string(&qtr("context_menu_sidebar"));
+        let context_menu_find_usages = context_menu.add_action_q_string(&qtr("context_menu_find_usages"));
 
         let context_menu_undo = context_menu.add_action_q_string(&qtr("context_menu_undo"));
         let context_menu_redo = context_menu.add_action_q_string(&qtr("context_menu_redo"));
@@ -509,6 +511,7 @@ impl TableView {
             context_menu_resize_columns,
             context_menu_sidebar,
             context_menu_search,
+            context_menu_find_usages,
             smart_delete,
 
             search_search_line_edit,
@@ -798,6 +801,11 @@ impl TableView {
         &self.context_menu_search
     }
 
+    /// This function returns a pointer to the find usages action.
+    pub fn get_mut_ptr_context_menu_find_usages(&self) -> &QPtr<QAction> {
+        &self.context_menu_find_usages
+    }
+
     /// This function returns a vector with the entire hide/show checkbox list.
     pub fn get_hide_show_checkboxes(&self) -> &[QBox<QCheckBox>] {
         &self.sidebar_hide_checkboxes
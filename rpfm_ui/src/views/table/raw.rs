@@ -40,6 +40,7 @@ use cpp_core::Ref;
 use std::collections::BTreeMap;
 use std::sync::atomic::Ordering;
 
+use crate::locale::{tr, tre};
 use crate::utils::{atomic_from_ptr, create_grid_layout, log_to_status_bar};
 use crate::pack_tree::*;
 use super::*;
@@ -65,6 +66,7 @@ impl TableView {
             self.context_menu_copy_as_lua_table.set_enabled(true);
             self.context_menu_delete_rows.set_enabled(true);
             self.context_menu_rewrite_selection.set_enabled(true);
+            self.context_menu_find_usages.set_enabled(self.table_name.is_some());
         }
 
         // Otherwise, disable them.
@@ -75,6 +77,7 @@ impl TableView {
             self.context_menu_copy.set_enabled(false);
             self.context_menu_copy_as_lua_table.set_enabled(false);
             self.context_menu_delete_rows.set_enabled(false);
+            self.context_menu_find_usages.set_enabled(false);
         }
 
         if !self.undo_lock.load(Ordering::SeqCst) {
@@ -310,6 +313,46 @@ impl TableView {
         }
     }
 
+    /// This function shows all the usages of the key in the current cell, across the open PackFile and its dependencies.
+    ///
+    /// If the cell is in a column referencing another table, the usages are searched for the referenced table instead.
+    pub unsafe fn find_usages(&self) {
+        let model_index = self.table_filter.map_to_source(&self.table_view_primary.current_index());
+        if !model_index.is_valid() { return; }
+
+        let table_name = match self.get_ref_table_definition().get_fields_processed().get(model_index.column() as usize).and_then(|x| x.get_is_reference().clone()) {
+            Some((ref_table, _)) => ref_table,
+            None => match self.table_name {
+                Some(ref table_name) => table_name.to_owned(),
+                None => return,
+            }
+        };
+
+        let key = self.table_model.item_from_index(&model_index).text().to_std_string();
+        CENTRAL_COMMAND.send_message_qt(Command::FindKeyUsages((table_name.to_owned(), key.to_owned())));
+        let response = CENTRAL_COMMAND.recv_message_qt_try();
+        match response {
+            Response::VecKeyUsage(usages) => {
+                let mut message = tre("find_usages_title", &[&key, &table_name]);
+                if usages.is_empty() {
+                    message.push_str(&tr("find_usages_none"));
+                }
+                else {
+                    message.push_str("<ul>");
+                    for usage in &usages {
+                        let source = if usage.is_dependency { tr("find_usages_dependency") } else { String::new() };
+                        message.push_str(&format!("<li><b>{}</b> {}{}, {}:{} <i>{}</i></li>", usage.usage_type, usage.path, source, usage.row, usage.column, usage.context));
+                    }
+                    message.push_str("</ul>");
+                }
+
+                show_dialog(&self.table_view_primary, message, true);
+            }
+            Response::Error(error) => show_dialog(&self.table_view_primary, error, false),
+            _ => panic!("{}{:?}", THREADS_COMMUNICATION_ERROR, response),
+        }
+    }
+
     /// This function copies the selected cells into the clipboard as a TSV file, so you can paste them in other programs.
     pub unsafe fn copy_selection(&self) {
 
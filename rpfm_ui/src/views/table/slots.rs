@@ -43,7 +43,7 @@ use crate::global_search_ui::GlobalSearchUI;
 use crate::packfile_contents_ui::PackFileContentsUI;
 use crate::packedfile_views::utils::set_modified;
 use crate::pack_tree::*;
-use crate::utils::{check_regex, show_dialog};
+use crate::utils::{check_regex, show_dialog, show_table_migrations_dialog};
 use crate::UI_STATE;
 
 use super::utils::*;
@@ -81,6 +81,7 @@ pub struct TableViewSlots {
     pub resize_columns: QBox<SlotNoArgs>,
     pub sidebar: QBox<SlotOfBool>,
     pub search: QBox<SlotOfBool>,
+    pub find_usages: QBox<SlotNoArgs>,
     pub hide_show_columns: Vec<QBox<SlotOfInt>>,
     pub hide_show_columns_all: QBox<SlotOfInt>,
     pub freeze_columns: Vec<QBox<SlotOfInt>>,
@@ -402,7 +403,7 @@ impl TableViewSlots {
                         CENTRAL_COMMAND.send_message_qt(Command::ImportTSV((packed_file_path.read().unwrap().to_vec(), path)));
                         let response = CENTRAL_COMMAND.recv_message_qt_try();
                         match response {
-                            Response::TableType(data) => {
+                            Response::TableTypeOptionTableMigration((data, migration)) => {
                                 let old_data = view.get_copy_of_table();
 
                                 view.undo_lock.store(true, Ordering::SeqCst);
@@ -435,6 +436,10 @@ impl TableViewSlots {
                                 view.history_redo.write().unwrap().clear();
                                 update_undo_model(&view.get_mut_ptr_table_model(), &view.get_mut_ptr_undo_model());
                                 set_modified(true, &packed_file_path.read().unwrap(), &app_ui, &pack_file_contents_ui);
+
+                                if let Some(migration) = migration {
+                                    show_table_migrations_dialog(&view.table_view_primary, &[(packed_file_path.read().unwrap().to_vec(), migration)]);
+                                }
                             },
                             Response::Error(error) => return show_dialog(&view.table_view_primary, error, false),
                             _ => panic!("{}{:?}", THREADS_COMMUNICATION_ERROR, response),
@@ -530,6 +535,12 @@ impl TableViewSlots {
             }
         }));
 
+        // When we want to find where the key of the current cell is used...
+        let find_usages = SlotNoArgs::new(&view.table_view_primary, clone!(
+            view => move || {
+            view.find_usages();
+        }));
+
         let mut hide_show_columns = vec![];
         let mut freeze_columns = vec![];
 
@@ -676,6 +687,7 @@ impl TableViewSlots {
             resize_columns,
             sidebar,
             search,
+            find_usages,
             hide_show_columns,
             hide_show_columns_all,
             freeze_columns,